@@ -0,0 +1,233 @@
+// Achievements in storage. Each one is a pure check over a run or the upgrades; the App runs
+// them whenever the run state or the levels change and toasts whatever newly passed. Unlocks
+// sit under md_achievements with the time they happened, and an unlocked entry is never checked
+// (or toasted) again. Runs that don't go in the history (scenarios, debug replays) can't unlock
+// the run ones.
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::{RunState, UpgradeState, UPGRADE_DEFS};
+use crate::persistence::{self, Storage};
+use crate::run_history;
+
+pub const ACHIEVEMENTS_KEY: &str = "md_achievements";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AchievementId {
+    FirstBlood,
+    Survivor,
+    Excavator,
+    Garrison,
+    Centurion,
+    Mastery,
+}
+
+impl AchievementId {
+    // Key in storage; kept apart from the variant name so a rename doesn't drop unlocks
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::FirstBlood => "first_blood",
+            Self::Survivor => "survivor",
+            Self::Excavator => "excavator",
+            Self::Garrison => "garrison",
+            Self::Centurion => "centurion",
+            Self::Mastery => "mastery",
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum Check {
+    Run(fn(&RunState) -> bool),
+    Upgrades(fn(&UpgradeState) -> bool),
+}
+
+pub struct AchievementDef {
+    pub id: AchievementId,
+    pub name: &'static str,
+    pub description: &'static str,
+    pub check: Check,
+}
+
+pub static ACHIEVEMENT_DEFS: &[AchievementDef] = &[
+    AchievementDef {
+        id: AchievementId::FirstBlood,
+        name: "First Blood",
+        description: "Defeat an enemy",
+        check: Check::Run(|rs| rs.stats.enemies_killed >= 1),
+    },
+    AchievementDef {
+        id: AchievementId::Survivor,
+        name: "Survivor",
+        description: "Survive 10 minutes in one run",
+        check: Check::Run(|rs| rs.stats.time_survived_secs >= 600),
+    },
+    AchievementDef {
+        id: AchievementId::Excavator,
+        name: "Excavator",
+        description: "Mine 500 blocks in one run",
+        check: Check::Run(|rs| rs.stats.blocks_mined >= 500),
+    },
+    AchievementDef {
+        id: AchievementId::Garrison,
+        name: "Garrison",
+        description: "Own 20 towers at once",
+        check: Check::Run(|rs| rs.towers.len() >= 20),
+    },
+    AchievementDef {
+        id: AchievementId::Centurion,
+        name: "Centurion",
+        description: "Defeat 100 enemies in one run",
+        check: Check::Run(|rs| rs.stats.enemies_killed >= 100),
+    },
+    AchievementDef {
+        id: AchievementId::Mastery,
+        name: "Mastery",
+        description: "Raise any upgrade to its maximum level",
+        check: Check::Upgrades(|ups| {
+            UPGRADE_DEFS
+                .iter()
+                .any(|d| d.max_level > 0 && ups.level(d.id) >= d.max_level)
+        }),
+    },
+];
+
+pub fn def(id: AchievementId) -> &'static AchievementDef {
+    ACHIEVEMENT_DEFS.iter().find(|d| d.id == id).unwrap()
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AchievementState {
+    // Achievement key -> unlock time (ms since the epoch)
+    pub unlocked: BTreeMap<String, f64>,
+}
+
+impl AchievementState {
+    pub fn unlocked_at(&self, id: AchievementId) -> Option<f64> {
+        self.unlocked.get(id.key()).copied()
+    }
+}
+
+// Unlocks every achievement `rs` and `ups` now pass that wasn't unlocked before, stamped
+// `now_ms`, and returns them in ACHIEVEMENT_DEFS order for the toasts
+pub fn evaluate(
+    state: &mut AchievementState,
+    rs: &RunState,
+    ups: &UpgradeState,
+    now_ms: f64,
+) -> Vec<AchievementId> {
+    let run_counts = run_history::counts(rs);
+    let mut fresh = Vec::new();
+    for d in ACHIEVEMENT_DEFS {
+        if state.unlocked.contains_key(d.id.key()) {
+            continue;
+        }
+        let passed = match d.check {
+            Check::Run(f) => run_counts && f(rs),
+            Check::Upgrades(f) => f(ups),
+        };
+        if passed {
+            state.unlocked.insert(d.id.key().into(), now_ms);
+            fresh.push(d.id);
+        }
+    }
+    fresh
+}
+
+pub fn load(store: &dyn Storage) -> AchievementState {
+    store
+        .get(ACHIEVEMENTS_KEY)
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+pub fn save(store: &mut dyn Storage, state: &AchievementState) {
+    if let Ok(raw) = serde_json::to_string(state) {
+        persistence::write(store, ACHIEVEMENTS_KEY, &raw);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{GridSize, ScenarioId, ScenarioRun, UpgradeId};
+    use crate::persistence::MemoryStorage;
+
+    fn run() -> RunState {
+        let gs = GridSize {
+            width: 12,
+            height: 12,
+        };
+        let mut rs = RunState::new_with_upgrades_seeded(gs, &UpgradeState::default(), 3);
+        rs.started = true;
+        rs
+    }
+
+    fn max_level(id: UpgradeId) -> u8 {
+        UPGRADE_DEFS.iter().find(|d| d.id == id).unwrap().max_level
+    }
+
+    #[test]
+    fn unlocks_once_and_never_toasts_again() {
+        let ups = UpgradeState::default();
+        let mut state = AchievementState::default();
+        let mut rs = run();
+        assert!(evaluate(&mut state, &rs, &ups, 1.0).is_empty());
+
+        rs.stats.enemies_killed = 120;
+        rs.stats.time_survived_secs = 601;
+        let fresh = evaluate(&mut state, &rs, &ups, 2.0);
+        let want = [
+            AchievementId::FirstBlood,
+            AchievementId::Survivor,
+            AchievementId::Centurion,
+        ];
+        assert_eq!(fresh, want);
+        assert_eq!(state.unlocked_at(AchievementId::Survivor), Some(2.0));
+        // Still true on the next pass, but already unlocked: no toast, first time kept
+        assert!(evaluate(&mut state, &rs, &ups, 3.0).is_empty());
+        assert_eq!(state.unlocked_at(AchievementId::Survivor), Some(2.0));
+        assert_eq!(state.unlocked_at(AchievementId::Excavator), None);
+    }
+
+    #[test]
+    fn upgrades_unlock_on_any_run_and_scenarios_dont_count() {
+        let mut ups = UpgradeState::default();
+        let mut state = AchievementState::default();
+        let mut rs = run();
+        rs.stats.blocks_mined = 500;
+        rs.scenario = Some(ScenarioRun {
+            id: ScenarioId::GoldRush,
+            outcome: None,
+        });
+        let max = max_level(UpgradeId::FireRate);
+        ups.levels.insert(UpgradeId::FireRate.key().into(), max - 1);
+        assert!(evaluate(&mut state, &rs, &ups, 1.0).is_empty());
+
+        ups.levels.insert(UpgradeId::FireRate.key().into(), max);
+        let fresh = evaluate(&mut state, &rs, &ups, 2.0);
+        assert_eq!(fresh, [AchievementId::Mastery]);
+        // The same stats on a run that counts
+        rs.scenario = None;
+        let fresh = evaluate(&mut state, &rs, &ups, 3.0);
+        assert_eq!(fresh, [AchievementId::Excavator]);
+    }
+
+    #[test]
+    fn state_round_trips_through_storage() {
+        let mut store = MemoryStorage::default();
+        assert_eq!(load(&store), AchievementState::default());
+        let mut state = AchievementState::default();
+        let mut rs = run();
+        rs.stats.enemies_killed = 1;
+        evaluate(&mut state, &rs, &UpgradeState::default(), 1.7e12);
+        save(&mut store, &state);
+        assert_eq!(load(&store), state);
+        // Keys a later build dropped are kept rather than failing the load
+        let raw = r#"{"unlocked":{"retired":5.0,"garrison":9.0}}"#;
+        store.set(ACHIEVEMENTS_KEY, raw);
+        assert_eq!(load(&store).unlocked_at(AchievementId::Garrison), Some(9.0));
+    }
+}
@@ -0,0 +1,246 @@
+// Short synthesized cues and a procedural music layer through one shared AudioContext.
+// Browsers won't start a context outside a user gesture, so it is only built by unlock() on the
+// first click or key; cues and music before that are dropped. Effects and music each go through
+// their own gain bus so their volumes (and mute) are independent.
+use std::cell::RefCell;
+use wasm_bindgen::JsValue;
+use web_sys::{AudioContext, GainNode, OscillatorType};
+
+use crate::state::Cue;
+
+// Music gain while a cue it ducks under is playing, as a share of the music volume
+const DUCK_LEVEL: f32 = 0.25;
+// Time constant of gain moves, so volume changes and ducking never click
+const GAIN_SMOOTHING_SECS: f64 = 0.08;
+// A minor arpeggio, walked up and down; the octave above joins past half intensity
+const ARPEGGIO_HZ: [f32; 6] = [220.0, 261.63, 329.63, 440.0, 329.63, 261.63];
+
+struct Mixer {
+    ctx: AudioContext,
+    sfx: GainNode,
+    music: GainNode,
+    // Music volume before ducking
+    music_gain: f32,
+    // Next arpeggio step, and when the next note is due on the context clock
+    step: usize,
+    next_note_at: f64,
+    // Context time each cue last played, by Cue index, for Cue::min_gap_secs
+    cue_played_at: [f64; 7],
+}
+
+thread_local! {
+    static MIXER: RefCell<Option<Mixer>> = const { RefCell::new(None) };
+    // Gains to apply when the mixer is first built: (music, effects)
+    static GAINS: RefCell<(f32, f32)> = const { RefCell::new((0.5, 0.8)) };
+}
+
+fn bus(ctx: &AudioContext, gain: f32) -> Result<GainNode, JsValue> {
+    let g = ctx.create_gain()?;
+    g.gain().set_value(gain);
+    g.connect_with_audio_node(&ctx.destination())?;
+    Ok(g)
+}
+
+fn with_mixer(f: impl FnOnce(&mut Mixer) -> Result<(), JsValue>) {
+    MIXER.with(|m| {
+        if let Some(mixer) = m.borrow_mut().as_mut() {
+            let _ = f(mixer);
+        }
+    });
+}
+
+// Builds the context on the first user gesture, and resumes it on later ones in case the
+// browser suspended it meanwhile. Call from gesture handlers only.
+pub fn unlock() {
+    MIXER.with(|m| {
+        let mut m = m.borrow_mut();
+        if m.is_none() {
+            let (music_gain, sfx_gain) = GAINS.with(|g| *g.borrow());
+            *m = AudioContext::new().ok().and_then(|ctx| {
+                Some(Mixer {
+                    sfx: bus(&ctx, sfx_gain).ok()?,
+                    music: bus(&ctx, music_gain).ok()?,
+                    ctx,
+                    music_gain,
+                    step: 0,
+                    next_note_at: 0.0,
+                    cue_played_at: [f64::NEG_INFINITY; 7],
+                })
+            });
+        }
+        if let Some(mixer) = m.as_ref() {
+            let _ = mixer.ctx.resume();
+        }
+    });
+}
+
+// Eases a gain toward `to`, starting now
+fn glide(mixer: &Mixer, node: &GainNode, to: f32) -> Result<(), JsValue> {
+    let now = mixer.ctx.current_time();
+    node.gain().cancel_scheduled_values(now)?;
+    node.gain()
+        .set_target_at_time(to, now, GAIN_SMOOTHING_SECS)?;
+    Ok(())
+}
+
+// Music and effect volumes, 0..=1; takes effect on the next cue if nothing has played yet
+pub fn set_gains(music: f32, sfx: f32) {
+    GAINS.with(|g| *g.borrow_mut() = (music, sfx));
+    MIXER.with(|m| {
+        if let Some(mixer) = m.borrow_mut().as_mut() {
+            mixer.music_gain = music;
+            let _ = glide(mixer, &mixer.music, music);
+            let _ = glide(mixer, &mixer.sfx, sfx);
+        }
+    });
+}
+
+// Drops the music under a cue for `secs`, then brings it back
+fn duck_music(mixer: &Mixer, secs: f64) -> Result<(), JsValue> {
+    let now = mixer.ctx.current_time();
+    let param = mixer.music.gain();
+    param.cancel_scheduled_values(now)?;
+    param.set_target_at_time(mixer.music_gain * DUCK_LEVEL, now, GAIN_SMOOTHING_SECS)?;
+    param.set_target_at_time(mixer.music_gain, now + secs, GAIN_SMOOTHING_SECS * 4.0)?;
+    Ok(())
+}
+
+// One enveloped oscillator note into `out`, `delay` seconds from now
+fn note(
+    ctx: &AudioContext,
+    out: &GainNode,
+    delay: f64,
+    kind: OscillatorType,
+    freq: f32,
+    peak: f32,
+    length: f64,
+) -> Result<(), JsValue> {
+    let t = ctx.current_time() + delay;
+    let osc = ctx.create_oscillator()?;
+    osc.set_type(kind);
+    osc.frequency().set_value(freq);
+    let gain = ctx.create_gain()?;
+    gain.gain().set_value_at_time(0.0001, t)?;
+    gain.gain()
+        .exponential_ramp_to_value_at_time(peak, t + 0.02)?;
+    gain.gain()
+        .exponential_ramp_to_value_at_time(0.0001, t + length)?;
+    osc.connect_with_audio_node(&gain)?;
+    gain.connect_with_audio_node(out)?;
+    osc.start_with_when(t)?;
+    osc.stop_with_when(t + length + 0.02)
+}
+
+// One sine thump `delay` seconds from now, with a fast attack and a short decay
+fn thump(mixer: &Mixer, delay: f64, freq: f32, peak: f32) -> Result<(), JsValue> {
+    note(
+        &mixer.ctx,
+        &mixer.sfx,
+        delay,
+        OscillatorType::Sine,
+        freq,
+        peak,
+        0.18,
+    )
+}
+
+// "Lub-dub": two low thumps, the second softer; the music ducks under it
+pub fn play_heartbeat() {
+    with_mixer(|mixer| {
+        duck_music(mixer, 0.5)?;
+        thump(mixer, 0.0, 55.0, 0.5)?;
+        thump(mixer, 0.24, 48.0, 0.3)
+    });
+}
+
+// Three falling thumps when the run ends; the music ducks under it
+pub fn play_game_over_sting() {
+    with_mixer(|mixer| {
+        duck_music(mixer, 1.6)?;
+        thump(mixer, 0.0, 98.0, 0.6)?;
+        thump(mixer, 0.3, 82.0, 0.5)?;
+        thump(mixer, 0.6, 55.0, 0.6)
+    });
+}
+
+// One of the run_view sound cues (see state::sound_cues), skipped if the same cue played less
+// than its minimum gap ago
+pub fn play_cue(cue: Cue) {
+    if cue == Cue::GameOver {
+        play_game_over_sting();
+        return;
+    }
+    with_mixer(|mixer| {
+        let now = mixer.ctx.current_time();
+        let last = &mut mixer.cue_played_at[cue as usize];
+        if now - *last < cue.min_gap_secs() {
+            return Ok(());
+        }
+        *last = now;
+        let (ctx, out) = (&mixer.ctx, &mixer.sfx);
+        match cue {
+            // A short high blip
+            Cue::Shot => note(ctx, out, 0.0, OscillatorType::Square, 880.0, 0.04, 0.05),
+            // A rising pair
+            Cue::Kill => {
+                note(ctx, out, 0.0, OscillatorType::Triangle, 523.25, 0.15, 0.08)?;
+                note(ctx, out, 0.06, OscillatorType::Triangle, 783.99, 0.15, 0.1)
+            }
+            // A low knock, twice
+            Cue::Mined => {
+                note(ctx, out, 0.0, OscillatorType::Square, 140.0, 0.12, 0.07)?;
+                note(ctx, out, 0.05, OscillatorType::Square, 110.0, 0.1, 0.09)
+            }
+            // A bright chime
+            Cue::Gold => {
+                note(ctx, out, 0.0, OscillatorType::Sine, 1318.5, 0.1, 0.12)?;
+                note(ctx, out, 0.04, OscillatorType::Sine, 1760.0, 0.08, 0.16)
+            }
+            // A falling buzz; the music ducks under it
+            Cue::LifeLost => {
+                duck_music(mixer, 0.4)?;
+                note(ctx, out, 0.0, OscillatorType::Sawtooth, 196.0, 0.2, 0.12)?;
+                note(ctx, out, 0.1, OscillatorType::Sawtooth, 147.0, 0.2, 0.2)
+            }
+            // A rising triad
+            Cue::ClutchSave => {
+                note(ctx, out, 0.0, OscillatorType::Sine, 523.25, 0.12, 0.1)?;
+                note(ctx, out, 0.07, OscillatorType::Sine, 659.25, 0.12, 0.1)?;
+                note(ctx, out, 0.14, OscillatorType::Sine, 783.99, 0.14, 0.18)
+            }
+            Cue::GameOver => Ok(()),
+        }
+    });
+}
+
+// Schedules the next second of arpeggio at music level 0..=1 (see state::music). Notes pick up
+// where the last call left off, so once-a-second calls make one continuous line.
+pub fn play_music_second(level: f64, notes_per_sec: f64) {
+    with_mixer(|mixer| {
+        let now = mixer.ctx.current_time();
+        let gap = 1.0 / notes_per_sec.max(0.5);
+        // After a pause or before the first call, start just ahead of now
+        if mixer.next_note_at < now {
+            mixer.next_note_at = now + 0.05;
+        }
+        let peak = 0.05 + 0.1 * level as f32;
+        while mixer.next_note_at < now + 1.0 {
+            let mut freq = ARPEGGIO_HZ[mixer.step % ARPEGGIO_HZ.len()];
+            if level > 0.5 && mixer.step % 4 == 3 {
+                freq *= 2.0;
+            }
+            note(
+                &mixer.ctx,
+                &mixer.music,
+                mixer.next_note_at - now,
+                OscillatorType::Triangle,
+                freq,
+                peak,
+                gap * 0.9,
+            )?;
+            mixer.step += 1;
+            mixer.next_note_at += gap;
+        }
+        Ok(())
+    });
+}
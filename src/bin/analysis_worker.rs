@@ -0,0 +1,51 @@
+// Map analysis web worker (see src/worker/). Built by Trunk as its own wasm module; it shares
+// the model with the app but none of the UI, so most of the shared modules go unused here.
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+use wasm_bindgen::JsValue;
+use web_sys::{DedicatedWorkerGlobalScope, MessageEvent};
+
+#[allow(dead_code)]
+#[path = "../map_gen.rs"]
+mod map_gen;
+#[allow(dead_code)]
+#[path = "../model.rs"]
+mod model;
+#[allow(dead_code)]
+#[path = "../path.rs"]
+mod path;
+#[allow(dead_code, unused_imports)]
+#[path = "../state/mod.rs"]
+mod state;
+#[allow(dead_code)]
+#[path = "../util.rs"]
+mod util;
+
+#[path = "../worker/host.rs"]
+mod host;
+#[path = "../worker/protocol.rs"]
+mod protocol;
+
+use host::AnalysisHost;
+use protocol::{from_json, to_json, FromWorker, ToWorker};
+
+fn main() {
+    let scope: DedicatedWorkerGlobalScope = js_sys::global().unchecked_into();
+    let reply = scope.clone();
+    let mut host = AnalysisHost::default();
+    let onmessage = Closure::wrap(Box::new(move |e: MessageEvent| {
+        let Some(msg) = e
+            .data()
+            .as_string()
+            .and_then(|raw| from_json::<ToWorker>(&raw))
+        else {
+            return;
+        };
+        if let Some(out) = host.handle(msg) {
+            let _ = reply.post_message(&JsValue::from_str(&to_json(&out)));
+        }
+    }) as Box<dyn FnMut(MessageEvent)>);
+    scope.set_onmessage(Some(onmessage.as_ref().unchecked_ref()));
+    onmessage.forget();
+    let _ = scope.post_message(&JsValue::from_str(&to_json(&FromWorker::Ready)));
+}
@@ -0,0 +1,51 @@
+use wasm_bindgen::{closure::Closure, JsCast};
+use yew::prelude::*;
+
+use crate::achievements::{self, AchievementId};
+
+// How long a toast stays up before the next one (if any) takes its place
+const TOAST_MS: i32 = 4000;
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct AchievementToastProps {
+    pub id: AchievementId,
+    // Gets the toast's own id, so a late timer can only ever dismiss itself
+    pub on_done: Callback<AchievementId>,
+}
+
+// Slides in from the top right when an achievement unlocks; the App keys it by id so each
+// unlock gets its own slide and timer
+#[function_component(AchievementToast)]
+pub fn achievement_toast(props: &AchievementToastProps) -> Html {
+    {
+        let on_done = props.on_done.clone();
+        use_effect_with(props.id, move |&id| {
+            let win = web_sys::window();
+            let handle = win.as_ref().and_then(|win| {
+                let cb = Closure::once_into_js(move || on_done.emit(id));
+                win.set_timeout_with_callback_and_timeout_and_arguments_0(
+                    cb.unchecked_ref(),
+                    TOAST_MS,
+                )
+                .ok()
+            });
+            // Unmounted early (queue cleared, progress reset): the timer must not fire later
+            move || {
+                if let (Some(win), Some(handle)) = (win, handle) {
+                    win.clear_timeout_with_handle(handle);
+                }
+            }
+        });
+    }
+    let def = achievements::def(props.id);
+    html! {
+        <div class="achievement-toast" style="position:fixed; top:16px; right:16px; background:#161b22; border:1px solid #d29922; border-radius:10px; padding:10px 14px; z-index:70; display:flex; gap:10px; align-items:center; max-width:300px;">
+            <span style="font-size:22px;">{"🏆"}</span>
+            <div style="display:flex; flex-direction:column; gap:2px;">
+                <span style="font-size:11px; color:#d29922; text-transform:uppercase; letter-spacing:0.05em;">{"Achievement unlocked"}</span>
+                <span style="font-weight:600;">{ def.name }</span>
+                <span style="font-size:12px; color:#8b949e;">{ def.description }</span>
+            </div>
+        </div>
+    }
+}
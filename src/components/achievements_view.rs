@@ -0,0 +1,54 @@
+use crate::achievements::{AchievementState, ACHIEVEMENT_DEFS};
+use wasm_bindgen::JsValue;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct AchievementsViewProps {
+    pub state: AchievementState,
+    pub to_run: Callback<()>,
+}
+
+// "Fri Oct 16 2026", in the player's time zone
+fn unlock_date(ms: f64) -> String {
+    String::from(js_sys::Date::new(&JsValue::from_f64(ms)).to_date_string())
+}
+
+// Every achievement, unlocked ones first with the day they were earned
+#[function_component]
+pub fn AchievementsView(props: &AchievementsViewProps) -> Html {
+    let back_cb = {
+        let cb = props.to_run.clone();
+        Callback::from(move |_| cb.emit(()))
+    };
+    let mut rows: Vec<_> = ACHIEVEMENT_DEFS
+        .iter()
+        .map(|d| (d, props.state.unlocked_at(d.id)))
+        .collect();
+    rows.sort_by_key(|(_, at)| at.is_none());
+    let unlocked = rows.iter().filter(|(_, at)| at.is_some()).count();
+
+    html! {<div style="position:absolute; inset:0; overflow:auto; background:#0e1116; color:#e6edf3; display:flex; flex-direction:column; align-items:center; padding:24px 12px;">
+        <div style="width:100%; max-width:720px; display:flex; justify-content:space-between; align-items:center; margin-bottom:12px;">
+            <h2 style="margin:0;">{"Achievements"}</h2>
+            <div style="display:flex; gap:12px; align-items:center;">
+                <span style="color:#8b949e; font-size:14px;">{ format!("{unlocked}/{}", ACHIEVEMENT_DEFS.len()) }</span>
+                <button onclick={back_cb}>{"Back"}</button>
+            </div>
+        </div>
+        <div style="width:100%; max-width:720px; display:flex; flex-direction:column; gap:8px;">
+            { for rows.iter().map(|(d, at)| {
+                let (border, opacity) = if at.is_some() { ("#d29922", "1") } else { ("#30363d", "0.55") };
+                html! {<div style={format!("display:flex; gap:12px; align-items:center; background:#161b22; border:1px solid {border}; border-radius:8px; padding:10px 14px; opacity:{opacity};")}>
+                    <span style="font-size:22px;">{ if at.is_some() { "🏆" } else { "🔒" } }</span>
+                    <div style="display:flex; flex-direction:column; gap:2px; flex:1;">
+                        <span style="font-weight:600;">{ d.name }</span>
+                        <span style="font-size:13px; color:#8b949e;">{ d.description }</span>
+                    </div>
+                    <span style="font-size:12px; color:#8b949e;">
+                        { at.map(unlock_date).unwrap_or_else(|| "Locked".into()) }
+                    </span>
+                </div>}
+            }) }
+        </div>
+    </div>}
+}
@@ -1,11 +1,52 @@
-use super::{run_view::RunView, upgrades_view::UpgradesView};
-use crate::model::{GridSize, RunAction, RunState, UpgradeId, UpgradeState};
+use super::{
+    achievement_toast::AchievementToast, achievements_view::AchievementsView,
+    history_view::HistoryView, run_view::RunView, upgrades_view::UpgradesView,
+};
+use crate::achievements::{self, AchievementId, AchievementState};
+use crate::model::{
+    self, edit_upgrade_queue, process_upgrade_queue, run_is_live, GridSize, QueueEdit,
+    ResearchTrickle, RunAction, RunState, UpgradeId, UpgradeState,
+};
+use crate::persistence::{self, Storage};
+use crate::prestige;
+use crate::run_history::{self, RunRecord};
+use crate::save_transfer::{self, SaveData};
+use crate::state::{levels_hash, NextRun};
+use std::rc::Rc;
+use wasm_bindgen::{closure::Closure, JsCast};
 use yew::prelude::*;
 
 #[derive(PartialEq, Clone)]
 enum View {
     Run,
     Upgrades,
+    History,
+    Achievements,
+}
+
+// Unlocks waiting to be toasted, oldest first. A reducer, so an unlock arriving while a toast
+// is up and that toast's timer both apply to the latest queue.
+#[derive(Default, PartialEq)]
+struct ToastQueue(Vec<AchievementId>);
+
+enum ToastAction {
+    Push(Vec<AchievementId>),
+    Done(AchievementId),
+    Clear,
+}
+
+impl Reducible for ToastQueue {
+    type Action = ToastAction;
+
+    fn reduce(self: Rc<Self>, action: ToastAction) -> Rc<Self> {
+        let mut queue = self.0.clone();
+        match action {
+            ToastAction::Push(ids) => queue.extend(ids),
+            ToastAction::Done(id) => queue.retain(|q| *q != id),
+            ToastAction::Clear => queue.clear(),
+        }
+        Rc::new(Self(queue))
+    }
 }
 
 // Provide upgrade context (so future components can read/purchase upgrades without prop drilling)
@@ -15,6 +56,46 @@ pub struct UpgradeContext {
     pub purchase: Callback<UpgradeId>,
 }
 
+// Buys `levels` in order for `spent` research in one pass of dispatches
+fn buy_levels(
+    run_state: &UseReducerHandle<RunState>,
+    upgrade_state: &UseStateHandle<UpgradeState>,
+    levels: &[UpgradeId],
+    spent: u64,
+) {
+    // The reducer refuses a purchase it can't pay for, so keep the levels in step with it
+    if levels.is_empty() || run_state.currencies.research < spent {
+        return;
+    }
+    let mut ups = (**upgrade_state).clone();
+    for &id in levels {
+        ups.purchase(id);
+    }
+    run_state.dispatch(RunAction::PurchaseUpgrades {
+        cost: spent,
+        ups: ups.clone(),
+        // If play area size changed, fully reset run to apply new grid dimensions
+        reset: levels.contains(&UpgradeId::PlayAreaSize),
+    });
+    upgrade_state.set(ups);
+}
+
+// Adds a run to md_run_history; true when it beats every stored run's time
+fn record_run(rs: &RunState) -> bool {
+    let Some(mut store) = persistence::LocalStorage::open() else {
+        return false;
+    };
+    run_history::record(&mut store, RunRecord::from_run(rs, js_sys::Date::now()))
+}
+
+// Runs `f` on a later task, so whatever the current one rendered gets painted first
+fn defer(f: impl FnOnce() + 'static) {
+    if let Some(win) = web_sys::window() {
+        let cb = Closure::once_into_js(f);
+        let _ = win.set_timeout_with_callback_and_timeout_and_arguments_0(cb.unchecked_ref(), 0);
+    }
+}
+
 #[function_component(App)]
 pub fn app() -> Html {
     let view = use_state(|| View::Run);
@@ -29,28 +110,74 @@ pub fn app() -> Html {
         ..Default::default()
     });
     let hard_reset_counter = use_state(|| 0u64);
+    // Lives here so the session cap holds across visits to the upgrades screen
+    let trickle = use_mut_ref(ResearchTrickle::default);
+    // Queued upgrade levels, bought as research arrives (see process_upgrade_queue)
+    let upgrade_queue = use_state(|| {
+        web_sys::window()
+            .and_then(|w| w.local_storage().ok().flatten())
+            .and_then(|s| s.get_item("md_upgrade_queue").ok().flatten())
+            .and_then(|raw| serde_json::from_str::<Vec<UpgradeId>>(&raw).ok())
+            .unwrap_or_default()
+    });
+    // Autosaved run read at startup, resumed once the first upgrade pass has run
+    let pending_resume = use_mut_ref(|| None::<RunState>);
+    // Next run, generated while the game-over screen is up (see state::next_run)
+    let next_run = use_mut_ref(NextRun::default);
+    // A restart found nothing prepared and is generating on the spot
+    let preparing = use_state(|| false);
+    // Run that just set a new best time, for the game-over banner
+    let new_best_run = use_state(|| None::<u64>);
+    let achievement_state = use_state(|| {
+        persistence::LocalStorage::open()
+            .map(|s| achievements::load(&s))
+            .unwrap_or_default()
+    });
+    let toasts = use_reducer(ToastQueue::default);
 
-    // Load persisted upgrade & research
+    // Load persisted upgrade, research & the autosaved run
     {
         let run_state = run_state.clone();
         let upgrade_state = upgrade_state.clone();
+        let trickle = trickle.clone();
+        let pending_resume = pending_resume.clone();
         use_effect_with((), move |_| {
+            let mut actions = Vec::new();
             if let Some(win) = web_sys::window() {
                 if let Ok(Some(store)) = win.local_storage() {
+                    let meta = persistence::LocalStorage::open()
+                        .map(|s| prestige::load(&s))
+                        .unwrap_or_default();
                     if let Ok(Some(raw)) = store.get_item("md_upgrade_state") {
-                        if let Ok(us) = serde_json::from_str::<UpgradeState>(&raw) {
+                        if let Ok(mut us) = serde_json::from_str::<UpgradeState>(&raw) {
+                            us.meta = meta;
                             upgrade_state.set(us.clone());
                             // Immediately reset run with proper grid size for play area level
-                            run_state.dispatch(RunAction::ResetRunWithUpgrades { ups: us.clone() });
+                            actions.push(RunAction::ResetRunWithUpgrades { ups: us.clone() });
                         }
                     }
                     if let Ok(Some(rp)) = store.get_item("md_research") {
                         if let Ok(v) = rp.parse::<u64>() {
-                            run_state.dispatch(RunAction::SetResearch { amount: v });
+                            actions.push(RunAction::SetResearch { amount: v });
+                        }
+                    }
+                    if let Ok(Some(raw)) = store.get_item("md_research_carry")
+                        && let Ok(v) = raw.parse::<f64>()
+                    {
+                        trickle.borrow_mut().carry = v.clamp(0.0, 1.0);
+                    }
+                    if let Ok(Some(raw)) = store.get_item("md_run_state") {
+                        // A save from an older build that no longer fits is dropped for a fresh run
+                        match RunState::from_save(&raw) {
+                            Some(rs) => *pending_resume.borrow_mut() = Some(rs),
+                            None => {
+                                let _ = store.remove_item("md_run_state");
+                            }
                         }
                     }
                 }
             }
+            run_state.dispatch(RunAction::Batch(actions));
             || ()
         });
     }
@@ -58,19 +185,27 @@ pub fn app() -> Html {
     {
         let upgrade_state = upgrade_state.clone();
         let run_state = run_state.clone();
-        use_effect_with((*upgrade_state).levels.clone(), move |_| {
+        let pending_resume = pending_resume.clone();
+        let key = (upgrade_state.levels.clone(), upgrade_state.meta);
+        use_effect_with(key, move |_| {
             // persist
-            if let Some(win) = web_sys::window() {
-                if let Ok(Some(store)) = win.local_storage() {
-                    if let Ok(s) = serde_json::to_string(&*upgrade_state) {
-                        let _ = store.set_item("md_upgrade_state", &s);
-                    }
-                }
+            if let (Some(mut store), Ok(s)) = (
+                persistence::LocalStorage::open(),
+                serde_json::to_string(&*upgrade_state),
+            ) {
+                persistence::write(&mut store, "md_upgrade_state", &s);
             }
             // apply to current run (non-destructive)
-            run_state.dispatch(RunAction::ApplyUpgrades {
+            let mut actions = vec![RunAction::ApplyUpgrades {
                 ups: (*upgrade_state).clone(),
-            });
+            }];
+            // The mount-time pass uses default upgrades and would clamp a resumed run's life
+            if let Some(saved) = pending_resume.borrow_mut().take() {
+                actions.push(RunAction::LoadSaved {
+                    state: Box::new(saved),
+                });
+            }
+            run_state.dispatch(RunAction::Batch(actions));
             || ()
         });
     }
@@ -78,15 +213,145 @@ pub fn app() -> Html {
     {
         let run_state = run_state.clone();
         use_effect_with(run_state.currencies.research, move |_| {
-            if let Some(win) = web_sys::window() {
-                if let Ok(Some(store)) = win.local_storage() {
-                    let _ =
-                        store.set_item("md_research", &run_state.currencies.research.to_string());
-                }
+            // Research earned replaying an imported debug run isn't real
+            if let Some(mut store) =
+                persistence::LocalStorage::open().filter(|_| !run_state.debug_allowed)
+            {
+                persistence::write(
+                    &mut store,
+                    "md_research",
+                    &run_state.currencies.research.to_string(),
+                );
+            }
+            || ()
+        });
+    }
+
+    // Persist the upgrade queue
+    {
+        let queue = (*upgrade_queue).clone();
+        use_effect_with(queue, move |queue| {
+            if let (Some(mut store), Ok(s)) = (
+                persistence::LocalStorage::open(),
+                serde_json::to_string(queue),
+            ) {
+                persistence::write(&mut store, "md_upgrade_queue", &s);
             }
             || ()
         });
     }
+    // Buy whatever the queue can afford whenever research, levels or the queue change
+    {
+        let run_state = run_state.clone();
+        let upgrade_state = upgrade_state.clone();
+        let upgrade_queue = upgrade_queue.clone();
+        use_effect_with(
+            (
+                run_state.currencies.research,
+                upgrade_state.levels.clone(),
+                (*upgrade_queue).clone(),
+                run_is_live(&run_state),
+            ),
+            move |(research, _, queue, live)| {
+                let out = process_upgrade_queue(queue, &upgrade_state, *research, *live);
+                buy_levels(&run_state, &upgrade_state, &out.bought, out.spent);
+                if out.remaining != *queue {
+                    upgrade_queue.set(out.remaining);
+                }
+                || ()
+            },
+        );
+    }
+
+    // Autosave the run once a sim second; drop the save when the run ends or is replaced
+    {
+        let run_state = run_state.clone();
+        let saved_run = use_mut_ref(|| None::<u64>);
+        use_effect_with(
+            (
+                run_state.run_id,
+                run_state.stats.time_survived_secs,
+                run_state.game_over,
+                run_state.started,
+            ),
+            move |&(run_id, _, game_over, started)| {
+                if let Some(mut store) = persistence::LocalStorage::open() {
+                    let replaced = saved_run.borrow().is_some_and(|id| id != run_id);
+                    if game_over || (!started && replaced) {
+                        store.remove("md_run_state");
+                        *saved_run.borrow_mut() = None;
+                    } else if started && !run_state.debug_allowed {
+                        persistence::write(&mut store, "md_run_state", &run_state.to_save());
+                        *saved_run.borrow_mut() = Some(run_id);
+                    }
+                }
+                || ()
+            },
+        );
+    }
+
+    // Record each finished run in the history
+    {
+        let run_state = run_state.clone();
+        let new_best_run = new_best_run.clone();
+        use_effect_with(
+            (run_state.game_over, run_state.run_id),
+            move |&(game_over, run_id)| {
+                if game_over && run_history::counts(&run_state) && record_run(&run_state) {
+                    new_best_run.set(Some(run_id));
+                }
+                || ()
+            },
+        );
+    }
+
+    // Check achievements on every run state change and every purchase; only new unlocks toast
+    {
+        let run_state = run_state.clone();
+        let upgrade_state = upgrade_state.clone();
+        let achievement_state = achievement_state.clone();
+        let toasts = toasts.clone();
+        use_effect_with(
+            (run_state.version, levels_hash(&upgrade_state)),
+            move |_| {
+                let mut state = (*achievement_state).clone();
+                let now = js_sys::Date::now();
+                let fresh = achievements::evaluate(&mut state, &run_state, &upgrade_state, now);
+                if !fresh.is_empty() {
+                    if let Some(mut store) = persistence::LocalStorage::open() {
+                        achievements::save(&mut store, &state);
+                    }
+                    toasts.dispatch(ToastAction::Push(fresh));
+                    achievement_state.set(state);
+                }
+                || ()
+            },
+        );
+    }
+
+    // Prepare the next run at game over, and again whenever upgrades change before the restart
+    {
+        let upgrade_state = upgrade_state.clone();
+        let next_run = next_run.clone();
+        use_effect_with(
+            (run_state.game_over, levels_hash(&upgrade_state)),
+            move |&(game_over, _)| {
+                if game_over {
+                    let ups = (*upgrade_state).clone();
+                    let key = next_run.borrow_mut().key(&ups);
+                    if next_run.borrow().wants(&key) {
+                        defer(move || {
+                            if next_run.borrow().wants(&key) {
+                                let state = key.generate(&ups);
+                                next_run.borrow_mut().store(key, state);
+                            }
+                        });
+                    }
+                }
+                || ()
+            },
+        );
+    }
 
     let to_run = {
         let view = view.clone();
@@ -96,30 +361,37 @@ pub fn app() -> Html {
         let view = view.clone();
         Callback::from(move |_| view.set(View::Upgrades))
     };
+    let to_history = {
+        let view = view.clone();
+        Callback::from(move |_| view.set(View::History))
+    };
+    let to_achievements = {
+        let view = view.clone();
+        Callback::from(move |_| view.set(View::Achievements))
+    };
+    let toast_done = {
+        let toasts = toasts.clone();
+        Callback::from(move |id| toasts.dispatch(ToastAction::Done(id)))
+    };
 
     // Purchase upgrade handler
     let purchase = {
         let run_state = run_state.clone();
         let upgrade_state = upgrade_state.clone();
+        // A one-entry queue: bought now if unlocked and affordable, else nothing happens
         Callback::from(move |id: UpgradeId| {
-            let mut ups = (*upgrade_state).clone();
-            if !ups.can_purchase(id) {
-                return;
-            }
-            if let Some(cost) = ups.next_cost(id) {
-                if run_state.currencies.research < cost {
-                    return;
-                }
-                ups.purchase(id);
-                run_state.dispatch(RunAction::SpendResearch { amount: cost });
-                // If play area size changed, fully reset run to apply new grid dimensions
-                if id == UpgradeId::PlayAreaSize {
-                    run_state.dispatch(RunAction::ResetRunWithUpgrades { ups: ups.clone() });
-                } else {
-                    run_state.dispatch(RunAction::ApplyUpgrades { ups: ups.clone() });
-                }
-                upgrade_state.set(ups);
-            }
+            let out =
+                process_upgrade_queue(&[id], &upgrade_state, run_state.currencies.research, false);
+            buy_levels(&run_state, &upgrade_state, &out.bought, out.spent);
+        })
+    };
+    let edit_queue = {
+        let upgrade_state = upgrade_state.clone();
+        let upgrade_queue = upgrade_queue.clone();
+        Callback::from(move |edit: QueueEdit| {
+            let mut queue = (*upgrade_queue).clone();
+            edit_upgrade_queue(&mut queue, &upgrade_state, edit);
+            upgrade_queue.set(queue);
         })
     };
 
@@ -132,55 +404,183 @@ pub fn app() -> Html {
         let run_state = run_state.clone();
         let upgrade_state = upgrade_state.clone();
         let hard_reset_counter = hard_reset_counter.clone();
+        let trickle = trickle.clone();
+        let upgrade_queue = upgrade_queue.clone();
+        let next_run = next_run.clone();
+        let achievement_state = achievement_state.clone();
+        let toasts = toasts.clone();
         Callback::from(move |_| {
-            if let Some(win) = web_sys::window() {
-                if let Ok(Some(store)) = win.local_storage() {
-                    let _ = store.remove_item("md_upgrade_state");
-                    let _ = store.remove_item("md_research");
-                    let _ = store.remove_item("md_intro_seen");
-                    let _ = store.remove_item("md_setting_show_path");
-                    let _ = store.remove_item("md_setting_show_damage_numbers");
-                    let _ = store.remove_item("md_setting_show_secondary_stats");
-                    let _ = store.remove_item("md_records");
-                }
+            if let Some(mut store) = persistence::LocalStorage::open() {
+                persistence::clear_all(&mut store);
             }
+            achievement_state.set(AchievementState::default());
+            toasts.dispatch(ToastAction::Clear);
             let default_ups = UpgradeState {
                 tower_refund_rate_percent: 100,
                 ..Default::default()
             };
             upgrade_state.set(default_ups.clone());
-            run_state.dispatch(RunAction::ResetRunWithUpgrades {
-                ups: default_ups.clone(),
-            });
-            run_state.dispatch(RunAction::SetResearch { amount: 0 });
+            run_state.dispatch(RunAction::Batch(vec![
+                RunAction::ResetRunWithUpgrades {
+                    ups: default_ups.clone(),
+                },
+                RunAction::SetResearch { amount: 0 },
+            ]));
+            *trickle.borrow_mut() = ResearchTrickle::default();
+            upgrade_queue.set(Vec::new());
+            *next_run.borrow_mut() = NextRun::default();
             hard_reset_counter.set(*hard_reset_counter + 1);
         })
     };
 
+    // Trades every level and all research for prestige points; the run in progress, if any,
+    // goes in the history first as it would on a restart
+    let prestige_cb = {
+        let run_state = run_state.clone();
+        let upgrade_state = upgrade_state.clone();
+        let upgrade_queue = upgrade_queue.clone();
+        let next_run = next_run.clone();
+        Callback::from(move |_| {
+            let Some(ups) = model::prestige(&upgrade_state, run_state.currencies.research) else {
+                return;
+            };
+            if !run_state.game_over && run_history::counts(&run_state) {
+                record_run(&run_state);
+            }
+            if let Some(mut store) = persistence::LocalStorage::open() {
+                prestige::commit(&mut store, &ups);
+            }
+            upgrade_state.set(ups.clone());
+            run_state.dispatch(RunAction::Batch(vec![
+                RunAction::ResetRunWithUpgrades { ups },
+                RunAction::SetResearch { amount: 0 },
+            ]));
+            upgrade_queue.set(Vec::new());
+            *next_run.borrow_mut() = NextRun::default();
+        })
+    };
+
+    // Takes over a save from another browser: progress through the handles, history and
+    // settings through storage, then a fresh run and a remount so they're read back in
+    let import_save_cb = {
+        let run_state = run_state.clone();
+        let upgrade_state = upgrade_state.clone();
+        let hard_reset_counter = hard_reset_counter.clone();
+        let upgrade_queue = upgrade_queue.clone();
+        let next_run = next_run.clone();
+        let new_best_run = new_best_run.clone();
+        Callback::from(move |data: SaveData| {
+            if let Some(mut store) = persistence::LocalStorage::open() {
+                save_transfer::apply_stored(&mut store, &data);
+            }
+            upgrade_state.set(data.upgrades.clone());
+            run_state.dispatch(RunAction::Batch(vec![
+                RunAction::ResetRunWithUpgrades {
+                    ups: data.upgrades.clone(),
+                },
+                RunAction::SetResearch {
+                    amount: data.research,
+                },
+            ]));
+            upgrade_queue.set(Vec::new());
+            *next_run.borrow_mut() = NextRun::default();
+            new_best_run.set(None);
+            hard_reset_counter.set(*hard_reset_counter + 1);
+        })
+    };
+
+    let restart_run = {
+        let run_state = run_state.clone();
+        let upgrade_state = upgrade_state.clone();
+        let next_run = next_run.clone();
+        let preparing = preparing.clone();
+        Callback::from(move |_| {
+            let ups = (*upgrade_state).clone();
+            // Abandoned runs go in the history too; finished ones already did at game over.
+            // Either way the new run comes from the upgrade-sized seeded map in the current
+            // route mode, in one reduce.
+            if !run_state.game_over && run_history::counts(&run_state) {
+                record_run(&run_state);
+            }
+            let key = next_run.borrow_mut().key(&ups);
+            let prepared = next_run.borrow_mut().take(&key);
+            match prepared {
+                Some(state) => run_state.dispatch(RunAction::StartPregenerated {
+                    state: Box::new(state),
+                }),
+                None => {
+                    // Show the indicator for a frame, then generate the same map synchronously
+                    preparing.set(true);
+                    let run_state = run_state.clone();
+                    let preparing = preparing.clone();
+                    defer(move || {
+                        run_state.dispatch(RunAction::ResetRunWithSeed {
+                            ups,
+                            seed: key.seed,
+                        });
+                        preparing.set(false);
+                    });
+                }
+            }
+        })
+    };
+
+    // A chosen map: picked from the candidates, or a seed typed into Settings
+    let restart_run_with_seed = {
+        let run_state = run_state.clone();
+        let upgrade_state = upgrade_state.clone();
+        Callback::from(move |seed: u64| {
+            if !run_state.game_over && run_history::counts(&run_state) {
+                record_run(&run_state);
+            }
+            run_state.dispatch(RunAction::ResetRunWithSeed {
+                ups: (*upgrade_state).clone(),
+                seed,
+            });
+        })
+    };
+
     let content = match *view {
         View::Run => html! { <RunView
             key={*hard_reset_counter}
             run_state={run_state.clone()}
             upgrade_state={(*upgrade_state).clone()}
+            upgrade_queue={(*upgrade_queue).clone()}
             to_upgrades={to_upgrades.clone()}
-            restart_run={{
-                let run_state = run_state.clone();
-                let upgrade_state = upgrade_state.clone();
-                Callback::from(move |_| {
-                    // Fresh run keeping current grid size; upgrades re-applied after reset.
-                    run_state.dispatch(RunAction::ResetRun);
-                    run_state.dispatch(RunAction::ApplyUpgrades { ups: (*upgrade_state).clone() });
-                })
-            }}
+            to_history={to_history.clone()}
+            new_best={*new_best_run == Some(run_state.run_id)}
+            restart_run={restart_run.clone()}
+            restart_run_with_seed={restart_run_with_seed.clone()}
             hard_reset={hard_reset_cb.clone()}
+            import_save={import_save_cb.clone()}
         /> },
         View::Upgrades => html! { <UpgradesView
             run_state={run_state.clone()}
             upgrade_state={upgrade_state.clone()}
             to_run={to_run.clone()}
             purchase={purchase.clone()}
+            queue={(*upgrade_queue).clone()}
+            edit_queue={edit_queue.clone()}
+            trickle={trickle.clone()}
+            prestige={prestige_cb.clone()}
+        /> },
+        View::History => html! { <HistoryView
+            to_run={to_run.clone()}
+            to_achievements={to_achievements}
+        /> },
+        View::Achievements => html! { <AchievementsView
+            state={(*achievement_state).clone()}
+            to_run={to_run.clone()}
         /> },
     };
 
-    html! { <ContextProvider<UpgradeContext> context={upgrade_ctx}>{ content }</ContextProvider<UpgradeContext>> }
+    html! { <ContextProvider<UpgradeContext> context={upgrade_ctx}>
+        { content }
+        if let Some(&id) = toasts.0.first() {
+            <AchievementToast key={id.key()} {id} on_done={toast_done} />
+        }
+        if *preparing {
+            <div style="position:fixed; bottom:12px; left:50%; transform:translateX(-50%); background:rgba(22,27,34,0.9); border:1px solid #30363d; border-radius:6px; padding:4px 10px; font-size:12px; color:#8b949e; z-index:60;">{"Preparing next run…"}</div>
+        }
+    </ContextProvider<UpgradeContext>> }
 }
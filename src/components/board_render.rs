@@ -0,0 +1,1112 @@
+// The world layers of the board canvas: tiles, overlays, enemies, towers, scarecrows,
+// projectiles and floating numbers. Everything here is a function of the run state, the camera
+// and the layer toggles, so the same picture comes out for the same inputs; the cursor-driven
+// passes (mining progress, path, ghosts, hover) stay in run_view's draw closure on top.
+// The terrain (grid, tiles, fog) comes from a cached offscreen layer; see state::render_cache.
+use std::collections::BTreeMap;
+
+use wasm_bindgen::JsCast;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
+
+use crate::model::{self, GridSize, RunState, TowerTally};
+use crate::state::enemy_motion::EnemyMotion;
+use crate::state::prospector::prospect_tiles;
+use crate::state::theme::Palette;
+use crate::state::{Camera, PaletteId, Prospect, RenderCache, StaticLayer};
+
+// Labels need roughly 128px tiles to be legible
+const TILE_LABEL_MIN_ZOOM: f64 = 4.0;
+// Prospector coin and "?" glyphs below this are dots or nothing
+const PROSPECT_GLYPH_MIN_ZOOM: f64 = 1.5;
+// Enemy health bars below this would be a smear over every dot
+const HEALTH_BAR_MIN_ZOOM: f64 = 0.75;
+// How long a heal pulse ring takes to swell and fade
+const HEAL_RING_SECS: f64 = 0.8;
+
+// Tile range (x0, y0, x1, y1), end-exclusive, the camera shows on a w x h canvas
+fn visible_tiles(cam: &Camera, gs: GridSize, w: f64, h: f64) -> Option<(u32, u32, u32, u32)> {
+    let scale_px = cam.zoom * 32.0;
+    let x0 = ((-cam.offset_x / scale_px).floor().max(0.0) as u32).min(gs.width);
+    let y0 = ((-cam.offset_y / scale_px).floor().max(0.0) as u32).min(gs.height);
+    let x1 = (((w - cam.offset_x) / scale_px).ceil().max(0.0) as u32).min(gs.width);
+    let y1 = (((h - cam.offset_y) / scale_px).ceil().max(0.0) as u32).min(gs.height);
+    (x0 < x1 && y0 < y1).then_some((x0, y0, x1, y1))
+}
+
+// Screen-space text pass over the visible tiles: font set once, fills grouped by color. With
+// `history`, tiles that held towers show their lifetime damage and kills instead, and with
+// `all` off those are the only labels.
+fn draw_tile_labels(
+    ctx: &CanvasRenderingContext2d,
+    rs: &RunState,
+    cam: &Camera,
+    (w, h): (f64, f64),
+    history: Option<&BTreeMap<u32, TowerTally>>,
+    all: bool,
+) {
+    let gs = rs.grid_size;
+    let scale_px = cam.zoom * 32.0;
+    let Some((x0, y0, x1, y1)) = visible_tiles(cam, gs, w, h) else {
+        return;
+    };
+    let mut loop_dist = vec![None; rs.tiles.len()];
+    for (i, p) in rs.path_loop.iter().enumerate() {
+        if let Some(slot) = loop_dist.get_mut((p.y * gs.width + p.x) as usize) {
+            *slot = rs.loop_cum_lengths.get(i).copied();
+        }
+    }
+    // Buckets: tower, gold, rock/wall, path, placement history
+    const COLORS: [&str; 5] = ["#ffd700", "#d29922", "#8b949e", "#58a6ff", "#f0883e"];
+    let mut buckets: [Vec<(f64, f64, String)>; 5] = Default::default();
+    for y in y0..y1 {
+        for x in x0..x1 {
+            let idx = (y * gs.width + x) as usize;
+            let sx = cam.offset_x + (x as f64 + 0.5) * scale_px;
+            let sy = cam.offset_y + (y as f64 + 0.5) * scale_px;
+            if let Some(t) = history.and_then(|hist| hist.get(&(idx as u32))) {
+                buckets[4].push((sx, sy, format!("{} / {}k", t.damage, t.kills)));
+                continue;
+            }
+            if !all {
+                continue;
+            }
+            let tile = &rs.tiles[idx];
+            let tower = rs.tower_at(x, y);
+            let Some(text) = model::tile_label(
+                tile,
+                tower,
+                loop_dist[idx],
+                rs.mining_gold_mul,
+                rs.prestige_damage_mult,
+            ) else {
+                continue;
+            };
+            let bucket = match (&tower, &tile.kind) {
+                (Some(_), _) => 0,
+                (None, model::TileKind::Rock { has_gold: true, .. }) => 1,
+                (None, model::TileKind::Empty) => 3,
+                _ => 2,
+            };
+            buckets[bucket].push((sx, sy, text));
+        }
+    }
+    ctx.set_transform(1.0, 0.0, 0.0, 1.0, 0.0, 0.0).ok();
+    ctx.set_font("11px monospace");
+    ctx.set_text_align("center");
+    ctx.set_text_baseline("middle");
+    for (color, labels) in COLORS.iter().zip(buckets.iter()) {
+        if labels.is_empty() {
+            continue;
+        }
+        ctx.set_fill_style_str(color);
+        for (sx, sy, text) in labels {
+            ctx.fill_text(text, *sx, *sy).ok();
+        }
+    }
+    ctx.set_text_align("start");
+    ctx.set_text_baseline("alphabetic");
+}
+
+// Settings-driven layers of render_grid
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BoardLayers {
+    pub show_damage_numbers: bool,
+    pub show_health_bars: bool,
+    pub show_tile_labels: bool,
+    pub prospector: bool,
+    pub reduced_effects: bool,
+    pub heatmap: bool,
+    pub tower_history: bool,
+    pub palette: PaletteId,
+}
+
+// Background, grid lines, tiles and fog for tiles x0..x1, y0..y1, in world units on a
+// context already scaled to scale_px per tile. Tiles outside interact_mask are dimmed.
+fn draw_terrain(
+    ctx: &CanvasRenderingContext2d,
+    rs: &RunState,
+    interact_mask: &[bool],
+    pal: &Palette,
+    scale_px: f64,
+    (x0, y0, x1, y1): (u32, u32, u32, u32),
+) {
+    let gs = rs.grid_size;
+    let (fx0, fy0, fx1, fy1) = (x0 as f64, y0 as f64, x1 as f64, y1 as f64);
+    ctx.set_fill_style_str("#161b22");
+    ctx.fill_rect(fx0, fy0, fx1 - fx0, fy1 - fy0);
+    ctx.set_stroke_style_str("#2f3641");
+    let line_w = (1.0f64 / scale_px).max(0.001f64);
+    ctx.set_line_width(line_w);
+    for x in x0..=x1 {
+        ctx.begin_path();
+        ctx.move_to(x as f64, fy0);
+        ctx.line_to(x as f64, fy1);
+        ctx.stroke();
+    }
+    for y in y0..=y1 {
+        ctx.begin_path();
+        ctx.move_to(fx0, y as f64);
+        ctx.line_to(fx1, y as f64);
+        ctx.stroke();
+    }
+    let margin = 0.1;
+    for y in y0..y1 {
+        for x in x0..x1 {
+            let idx = (y * gs.width + x) as usize;
+            match rs.tiles[idx].kind {
+                model::TileKind::Rock { has_gold, boost } => {
+                    let rx = x as f64 + margin;
+                    let ry = y as f64 + margin;
+                    let rw = 1.0 - 2.0 * margin;
+                    let rh = rw;
+                    let fill = match boost {
+                        Some(b) => pal.boost(b),
+                        None => {
+                            if has_gold {
+                                pal.gold_rock
+                            } else if rs.tiles[idx].magma {
+                                pal.magma_rock
+                            } else {
+                                pal.rock
+                            }
+                        }
+                    };
+                    ctx.set_fill_style_str(fill);
+                    ctx.fill_rect(rx, ry, rw, rh);
+                    if has_gold && boost.is_some() {
+                        ctx.set_fill_style_str("#d4af37");
+                        ctx.fill_rect(rx + rw * 0.35, ry + rh * 0.35, rw * 0.3, rh * 0.3);
+                    }
+                    ctx.set_stroke_style_str("#3a4455");
+                    ctx.set_line_width((1.0f64 / scale_px).max(0.001f64));
+                    ctx.stroke_rect(rx, ry, rw, rh);
+                }
+                model::TileKind::Wall => {
+                    let rx = x as f64 + margin;
+                    let ry = y as f64 + margin;
+                    let rw = 1.0 - 2.0 * margin;
+                    let rh = rw;
+                    ctx.set_fill_style_str(pal.wall);
+                    ctx.fill_rect(rx, ry, rw, rh);
+                    ctx.set_stroke_style_str("#555e6b");
+                    ctx.set_line_width((1.0f64 / scale_px).max(0.001f64));
+                    ctx.stroke_rect(rx, ry, rw, rh);
+                }
+                model::TileKind::Start => {
+                    let rx = x as f64;
+                    let ry = y as f64;
+                    ctx.set_fill_style_str(pal.path);
+                    ctx.fill_rect(rx, ry, 1.0, 1.0);
+                    let cx = rx + 0.5;
+                    let cy = ry + 0.5;
+                    ctx.begin_path();
+                    ctx.set_fill_style_str(pal.start);
+                    ctx.arc(cx, cy, 0.30, 0.0, std::f64::consts::PI * 2.0).ok();
+                    ctx.fill();
+                    ctx.set_stroke_style_str("#1f6feb");
+                    ctx.set_line_width((1.2f64 / scale_px).max(0.001f64));
+                    ctx.stroke();
+                }
+                model::TileKind::Direction { dir, role } => {
+                    let rx = x as f64;
+                    let ry = y as f64;
+                    ctx.set_fill_style_str(pal.path);
+                    ctx.fill_rect(rx, ry, 1.0, 1.0);
+                    let color = match role {
+                        model::DirRole::Entrance => pal.entrance,
+                        model::DirRole::Exit => pal.exit,
+                    };
+                    ctx.set_fill_style_str(color);
+                    ctx.begin_path();
+                    match dir {
+                        model::ArrowDir::Right => {
+                            ctx.move_to(rx + 0.25, ry + 0.20);
+                            ctx.line_to(rx + 0.25, ry + 0.80);
+                            ctx.line_to(rx + 0.80, ry + 0.50);
+                        }
+                        model::ArrowDir::Left => {
+                            ctx.move_to(rx + 0.75, ry + 0.20);
+                            ctx.line_to(rx + 0.75, ry + 0.80);
+                            ctx.line_to(rx + 0.20, ry + 0.50);
+                        }
+                        model::ArrowDir::Up => {
+                            ctx.move_to(rx + 0.20, ry + 0.75);
+                            ctx.line_to(rx + 0.80, ry + 0.75);
+                            ctx.line_to(rx + 0.50, ry + 0.20);
+                        }
+                        model::ArrowDir::Down => {
+                            ctx.move_to(rx + 0.20, ry + 0.25);
+                            ctx.line_to(rx + 0.80, ry + 0.25);
+                            ctx.line_to(rx + 0.50, ry + 0.80);
+                        }
+                    }
+                    ctx.close_path();
+                    ctx.fill();
+                }
+                model::TileKind::Indestructible => {
+                    let rx = x as f64 + margin;
+                    let ry = y as f64 + margin;
+                    let rw = 1.0 - 2.0 * margin;
+                    let rh = rw;
+                    ctx.set_fill_style_str(pal.indestructible);
+                    ctx.fill_rect(rx, ry, rw, rh);
+                    ctx.set_stroke_style_str("#596273");
+                    ctx.set_line_width((1.0f64 / scale_px).max(0.001f64));
+                    ctx.stroke_rect(rx, ry, rw, rh);
+                }
+                model::TileKind::End => {
+                    let rx = x as f64;
+                    let ry = y as f64;
+                    ctx.set_fill_style_str(pal.path);
+                    ctx.fill_rect(rx, ry, 1.0, 1.0);
+                    ctx.set_stroke_style_str(pal.exit);
+                    ctx.set_line_width((2.0f64 / scale_px).max(0.001f64));
+                    for r in [0.32, 0.14] {
+                        ctx.begin_path();
+                        ctx.arc(rx + 0.5, ry + 0.5, r, 0.0, std::f64::consts::PI * 2.0)
+                            .ok();
+                        ctx.stroke();
+                    }
+                }
+                model::TileKind::Empty => {
+                    let rx = x as f64;
+                    let ry = y as f64;
+                    if rs.tiles[idx].magma {
+                        ctx.set_fill_style_str(pal.lava_floor);
+                        ctx.fill_rect(rx, ry, 1.0, 1.0);
+                        ctx.set_fill_style_str(pal.lava);
+                        ctx.fill_rect(rx + 0.25, ry + 0.25, 0.5, 0.5);
+                    } else {
+                        ctx.set_fill_style_str(pal.path);
+                        ctx.fill_rect(rx, ry, 1.0, 1.0);
+                    }
+                }
+            }
+            if !interact_mask[idx] {
+                ctx.set_fill_style_str("rgba(0,0,0,0.35)");
+                ctx.fill_rect(x as f64, y as f64, 1.0, 1.0);
+            }
+        }
+    }
+}
+
+// The offscreen canvas behind the terrain, and the cache deciding when to redraw it
+#[derive(Default)]
+pub struct TerrainLayer {
+    pub cache: RenderCache,
+    canvas: Option<HtmlCanvasElement>,
+}
+
+impl TerrainLayer {
+    pub fn invalidate(&mut self) {
+        self.cache.invalidate();
+    }
+
+    // compute_interactable_mask of the state last drawn
+    pub fn mask(&self) -> &[bool] {
+        self.cache.mask()
+    }
+
+    // Draws the whole grid into the offscreen canvas at tile_px per tile
+    fn redraw(&mut self, rs: &RunState, pal: &Palette, tile_px: u32) -> Option<()> {
+        if self.canvas.is_none() {
+            self.canvas = web_sys::window()?
+                .document()?
+                .create_element("canvas")
+                .ok()?
+                .dyn_into::<HtmlCanvasElement>()
+                .ok();
+        }
+        let canvas = self.canvas.as_ref()?;
+        let gs = rs.grid_size;
+        // Resizing clears the canvas and resets its state
+        canvas.set_width(gs.width * tile_px);
+        canvas.set_height(gs.height * tile_px);
+        let ctx = canvas
+            .get_context("2d")
+            .ok()??
+            .dyn_into::<CanvasRenderingContext2d>()
+            .ok()?;
+        let px = tile_px as f64;
+        ctx.set_transform(px, 0.0, 0.0, px, 0.0, 0.0).ok();
+        let all = (0, 0, gs.width, gs.height);
+        draw_terrain(&ctx, rs, self.cache.mask(), pal, px, all);
+        Some(())
+    }
+}
+
+// Clears the canvas and draws the board at the camera, leaving the world transform set.
+// The terrain is blitted from `terrain`, redrawn first if the board or zoom level changed;
+// afterwards terrain.mask() is the interactable mask for this state.
+pub fn render_grid(
+    ctx: &CanvasRenderingContext2d,
+    rs: &RunState,
+    cam: &Camera,
+    (w, h): (f64, f64),
+    terrain: &mut TerrainLayer,
+    motion: &EnemyMotion,
+    layers: BoardLayers,
+) {
+    let scale_px = cam.zoom * 32.0;
+    let pal = layers.palette.palette();
+    ctx.set_transform(1.0, 0.0, 0.0, 1.0, 0.0, 0.0).ok();
+    ctx.set_fill_style_str("#0e1116");
+    ctx.fill_rect(0.0, 0.0, w, h);
+    ctx.set_transform(scale_px, 0.0, 0.0, scale_px, cam.offset_x, cam.offset_y)
+        .ok();
+    let gs = rs.grid_size;
+    let blitted = match terrain.cache.update(rs, cam.zoom) {
+        StaticLayer::Redraw(px) => terrain.redraw(rs, pal, px).is_some(),
+        StaticLayer::Cached(_) => true,
+        StaticLayer::Direct => false,
+    };
+    match terrain.canvas.as_ref().filter(|_| blitted) {
+        Some(layer) => {
+            ctx.draw_image_with_html_canvas_element_and_dw_and_dh(
+                layer,
+                0.0,
+                0.0,
+                gs.width as f64,
+                gs.height as f64,
+            )
+            .ok();
+        }
+        None => {
+            if let Some(range) = visible_tiles(cam, gs, w, h) {
+                draw_terrain(ctx, rs, terrain.mask(), pal, scale_px, range);
+            }
+        }
+    }
+    let interact_mask = terrain.mask();
+    // Prospector: coins on frontier gold, "?" one step behind it. Glyphs need
+    // a legible tile size; zoomed out or with reduced effects, coins become dots
+    if layers.prospector {
+        let prospects = prospect_tiles(&rs.tiles, gs, interact_mask);
+        let glyphs = !layers.reduced_effects && cam.zoom >= PROSPECT_GLYPH_MIN_ZOOM;
+        ctx.set_text_align("center");
+        ctx.set_text_baseline("middle");
+        ctx.set_font("bold 0.4px sans-serif");
+        for (idx, p) in prospects.iter().enumerate() {
+            let cx = (idx as u32 % gs.width) as f64 + 0.5;
+            let cy = (idx as u32 / gs.width) as f64 + 0.5;
+            match (p, glyphs) {
+                (Prospect::Coin, true) => {
+                    ctx.begin_path();
+                    ctx.set_fill_style_str("#d4af37");
+                    ctx.arc(cx, cy, 0.2, 0.0, std::f64::consts::PI * 2.0).ok();
+                    ctx.fill();
+                    ctx.set_fill_style_str("#4d3b1f");
+                    ctx.fill_text("$", cx, cy).ok();
+                }
+                (Prospect::Coin, false) => {
+                    ctx.begin_path();
+                    ctx.set_fill_style_str("#d4af37");
+                    ctx.arc(cx, cy, 0.1, 0.0, std::f64::consts::PI * 2.0).ok();
+                    ctx.fill();
+                }
+                (Prospect::Hint, true) => {
+                    ctx.set_fill_style_str("rgba(212,175,55,0.75)");
+                    ctx.fill_text("?", cx, cy).ok();
+                }
+                _ => {}
+            }
+        }
+        ctx.set_text_baseline("alphabetic");
+    }
+    // Freshly laid Mason rock, fading out
+    for fr in &rs.fresh_rock {
+        let alpha = (fr.ttl / model::FRESH_ROCK_TTL).clamp(0.0, 1.0) * 0.55;
+        ctx.set_fill_style_str(&format!("rgba(196,140,72,{:.3})", alpha));
+        ctx.fill_rect(fr.x as f64, fr.y as f64, 1.0, 1.0);
+    }
+    // Targeting exclusion zones: faint hatching
+    if rs.no_target.count() > 0 {
+        ctx.set_stroke_style_str("rgba(248,81,73,0.35)");
+        ctx.set_line_width((1.5f64 / scale_px).max(0.001f64));
+        ctx.begin_path();
+        for y in 0..rs.grid_size.height {
+            for x in 0..rs.grid_size.width {
+                if !rs.no_target.get(x, y) {
+                    continue;
+                }
+                let (fx, fy) = (x as f64, y as f64);
+                for k in [0.25, 0.5, 0.75, 1.0] {
+                    ctx.move_to(fx, fy + k);
+                    ctx.line_to(fx + k, fy);
+                }
+                for k in [0.25, 0.5, 0.75] {
+                    ctx.move_to(fx + k, fy + 1.0);
+                    ctx.line_to(fx + 1.0, fy + k);
+                }
+            }
+        }
+        ctx.stroke();
+    }
+    // Damage heatmap: red wash, log-scaled against the hottest cell
+    if layers.heatmap {
+        let max = rs.heat.max();
+        let cols = rs.heat.width().max(1);
+        let step = model::HEAT_CELL_TILES;
+        for (i, v) in rs.heat.cells().iter().enumerate() {
+            let a = model::heat_intensity(*v, max);
+            if a <= 0.0 {
+                continue;
+            }
+            let (x0, y0) = (i as u32 % cols * step, i as u32 / cols * step);
+            let w = step.min(rs.grid_size.width.saturating_sub(x0));
+            let h = step.min(rs.grid_size.height.saturating_sub(y0));
+            ctx.set_fill_style_str(&format!("rgba(248,81,73,{:.3})", a * 0.45));
+            ctx.fill_rect(x0 as f64, y0 as f64, w as f64, h as f64);
+        }
+    }
+    // Placement history: orange wash over tiles that ever held a tower, by lifetime damage
+    let history = layers.tower_history.then(|| model::placement_history(rs));
+    if let Some(hist) = &history {
+        let max = hist.values().map(|t| t.damage).max().unwrap_or(0) as f32;
+        for (&idx, t) in hist {
+            let a = model::heat_intensity(t.damage as f32, max);
+            if a <= 0.0 {
+                continue;
+            }
+            ctx.set_fill_style_str(&format!("rgba(240,136,62,{:.3})", 0.15 + a * 0.5));
+            ctx.fill_rect((idx % gs.width) as f64, (idx / gs.width) as f64, 1.0, 1.0);
+        }
+    }
+    ctx.set_line_width((1.0f64 / scale_px).max(0.001f64));
+    for e in &rs.enemies {
+        // Drawn between sim steps, see state::enemy_motion
+        let (ex, ey) = motion.position(e);
+        // New arrivals grow in at the Entrance
+        let grown = if layers.reduced_effects {
+            1.0
+        } else {
+            1.0 - (e.spawn_anim_ttl / model::SPAWN_ANIM_SECS).clamp(0.0, 1.0)
+        };
+        let radius = 0.28 * e.radius_scale * grown;
+
+        // Calculate enemy color based on debuffs
+        let (r, g, b) = pal.enemy_rgb(e.kind);
+        let (mut base_r, mut base_g, mut base_b) = (r as f64, g as f64, b as f64);
+
+        // Apply debuff color tints by blending
+        let mut has_slow = false;
+        let mut has_poison = false;
+        let mut has_burn = false;
+        let mut has_freeze = false;
+        let mut slow_strength = 0.0;
+        let mut poison_strength = 0.0;
+        let mut burn_strength = 0.0;
+        let mut freeze_strength = 0.0;
+
+        for debuff in &e.debuffs {
+            match debuff.kind {
+                model::DebuffKind::Slow => {
+                    has_slow = true;
+                    // Fade over duration, but stay visibly blue until it ends
+                    slow_strength = (debuff.remaining / 4.0).clamp(0.5, 1.0);
+                }
+                model::DebuffKind::Poison => {
+                    has_poison = true;
+                    poison_strength = (debuff.remaining / 4.0).min(1.0);
+                }
+                model::DebuffKind::Burn => {
+                    has_burn = true;
+                    burn_strength = (debuff.remaining / 4.0).min(1.0);
+                }
+                // A thawed freeze lingers as immunity; only a running one tints
+                model::DebuffKind::Freeze if debuff.remaining > 0.0 => {
+                    has_freeze = true;
+                    freeze_strength = (debuff.remaining / 4.0).min(1.0);
+                }
+                model::DebuffKind::Freeze => {}
+            }
+        }
+
+        // Blend colors based on debuffs
+        if has_burn {
+            // Burn: Bright orange/yellow (fire) - most visually distinct
+            base_r = 255.0 * (1.0 - burn_strength) + 255.0 * burn_strength;
+            base_g = 80.0 * (1.0 - burn_strength) + 140.0 * burn_strength;
+            base_b = 50.0 * (1.0 - burn_strength) + 0.0 * burn_strength;
+        } else if has_freeze {
+            base_r = 255.0 * (1.0 - freeze_strength) + 136.0 * freeze_strength;
+            base_g = 80.0 * (1.0 - freeze_strength) + 204.0 * freeze_strength;
+            base_b = 50.0 * (1.0 - freeze_strength) + 255.0 * freeze_strength;
+        } else if has_slow && has_poison {
+            // Both: Mix blue and green = cyan/teal
+            let blend = (slow_strength + poison_strength) / 2.0;
+            base_r = 255.0 * (1.0 - blend) + 0.0 * blend;
+            base_g = 80.0 * (1.0 - blend) + 200.0 * blend;
+            base_b = 50.0 * (1.0 - blend) + 180.0 * blend;
+        } else if has_slow {
+            // Slow: Blue/cyan tint
+            base_r = 255.0 * (1.0 - slow_strength) + 50.0 * slow_strength;
+            base_g = 80.0 * (1.0 - slow_strength) + 150.0 * slow_strength;
+            base_b = 50.0 * (1.0 - slow_strength) + 255.0 * slow_strength;
+        } else if has_poison {
+            // Poison: Green/yellow-green tint
+            base_r = 255.0 * (1.0 - poison_strength) + 100.0 * poison_strength;
+            base_g = 80.0 * (1.0 - poison_strength) + 220.0 * poison_strength;
+            base_b = 50.0 * (1.0 - poison_strength) + 50.0 * poison_strength;
+        }
+
+        let enemy_color = format!(
+            "#{:02x}{:02x}{:02x}",
+            base_r as u8, base_g as u8, base_b as u8
+        );
+
+        // Calculate HP percentage
+        let hp_percent = if e.max_hp > 0 {
+            (e.hp as f64 / e.max_hp as f64).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+
+        // Heading notch poking out of the rim the way it walks; the body covers its base
+        if e.dir_dx != 0.0 || e.dir_dy != 0.0 {
+            let heading = e.dir_dy.atan2(e.dir_dx);
+            let (c, sn) = (heading.cos(), heading.sin());
+            let (tip, base, half) = (radius * 1.4, radius * 0.6, radius * 0.5);
+            ctx.begin_path();
+            ctx.move_to(ex + c * tip, ey + sn * tip);
+            ctx.line_to(ex + c * base - sn * half, ey + sn * base + c * half);
+            ctx.line_to(ex + c * base + sn * half, ey + sn * base - c * half);
+            ctx.close_path();
+            ctx.set_fill_style_str(&enemy_color);
+            ctx.fill();
+        }
+
+        // Draw background circle (dark, shows missing HP)
+        ctx.begin_path();
+        ctx.set_fill_style_str("#1a2332"); // Dark blue-gray
+        ctx.arc(ex, ey, radius, 0.0, std::f64::consts::PI * 2.0)
+            .ok();
+        ctx.fill();
+
+        // Draw HP circle on top (scales with HP percentage)
+        // Color now indicates debuff status!
+        let hp_radius = radius * hp_percent.sqrt(); // sqrt makes it area-based
+        ctx.begin_path();
+        ctx.set_fill_style_str(&enemy_color);
+        ctx.arc(ex, ey, hp_radius, 0.0, std::f64::consts::PI * 2.0)
+            .ok();
+        ctx.fill();
+
+        // Outline (always full size); green and thicker while poison is ticking
+        ctx.begin_path();
+        if has_poison {
+            ctx.set_stroke_style_str("#64dc37");
+            ctx.set_line_width(0.07);
+        } else {
+            ctx.set_stroke_style_str("#a80032");
+            ctx.set_line_width(0.04);
+        }
+        ctx.arc(ex, ey, radius, 0.0, std::f64::consts::PI * 2.0)
+            .ok();
+        ctx.stroke();
+
+        // White flash over the body just after a projectile lands
+        if e.hit_flash_ttl > 0.0 && !layers.reduced_effects {
+            let alpha = (e.hit_flash_ttl / model::HIT_FLASH_SECS).clamp(0.0, 1.0) * 0.8;
+            ctx.begin_path();
+            ctx.set_fill_style_str(&format!("rgba(255,255,255,{:.3})", alpha));
+            ctx.arc(ex, ey, radius, 0.0, std::f64::consts::PI * 2.0)
+                .ok();
+            ctx.fill();
+        }
+
+        // Frozen solid: an icy white glaze with a pale rim
+        if has_freeze {
+            ctx.begin_path();
+            ctx.set_fill_style_str("rgba(230,245,255,0.55)");
+            ctx.set_stroke_style_str("#e6f5ff");
+            ctx.set_line_width(0.06);
+            ctx.arc(ex, ey, radius, 0.0, std::f64::consts::PI * 2.0)
+                .ok();
+            ctx.fill();
+            ctx.stroke();
+        }
+
+        // Health bar above wounded enemies, once they are big enough to carry one
+        if layers.show_health_bars && e.hp < e.max_hp && cam.zoom >= HEALTH_BAR_MIN_ZOOM {
+            let (bw, by) = (radius * 2.2, ey - radius - 0.14);
+            ctx.set_fill_style_str("#1a2332");
+            ctx.fill_rect(ex - bw / 2.0, by, bw, 0.07);
+            ctx.set_fill_style_str(if hp_percent > 0.3 {
+                "#3fb950"
+            } else {
+                "#f85149"
+            });
+            ctx.fill_rect(ex - bw / 2.0, by, bw * hp_percent, 0.07);
+        }
+    }
+    for tw in &rs.towers {
+        let cx = tw.x as f64 + 0.5;
+        let cy = tw.y as f64 + 0.5;
+
+        // Draw boost ring if tower has a boost
+        if let Some(boost) = tw.boost {
+            ctx.begin_path();
+            let boost_color = match boost {
+                model::BoostKind::Range => "#58a6ff",    // Blue
+                model::BoostKind::Damage => "#64dc37",   // Green (Poison)
+                model::BoostKind::FireRate => "#f85149", // Red
+                model::BoostKind::Slow => "#3296ff",     // Bright Blue (Cold)
+                model::BoostKind::Fire => "#ff8c00",     // Orange (Fire/Burn)
+                model::BoostKind::Healing => "#7ee787",  // Mint
+            };
+            ctx.set_stroke_style_str(boost_color);
+            ctx.set_line_width(0.08);
+            ctx.arc(cx, cy, 0.40, 0.0, std::f64::consts::PI * 2.0).ok();
+            ctx.stroke();
+        }
+
+        // Heal pulse: a mint ring swelling out to the aura (or a tile) and fading
+        if tw.boost == Some(model::BoostKind::Healing)
+            && let Some(at) = rs.last_heal_pulse_at
+        {
+            let t = (rs.sim_time - at) / HEAL_RING_SECS;
+            if (0.0..1.0).contains(&t) {
+                let reach = rs.healing_aura_radius.max(1.0);
+                let r = 0.4 + (reach - 0.4) * t;
+                let alpha = 0.8 * (1.0 - t);
+                ctx.begin_path();
+                ctx.set_stroke_style_str(&format!("rgba(126,231,135,{alpha:.2})"));
+                ctx.set_line_width(0.06);
+                ctx.arc(cx, cy, r, 0.0, std::f64::consts::PI * 2.0).ok();
+                ctx.stroke();
+            }
+        }
+
+        ctx.begin_path();
+        ctx.set_fill_style_str(&pal.tower_color(&tw.kind));
+        ctx.arc(cx, cy, 0.30, 0.0, std::f64::consts::PI * 2.0).ok();
+        ctx.fill();
+        ctx.set_stroke_style_str("#111821");
+        ctx.set_line_width(0.04);
+        ctx.stroke();
+
+        // Target mode letter at the tower's upper right
+        ctx.set_font("bold 0.26px sans-serif");
+        ctx.set_text_align("center");
+        ctx.set_fill_style_str("rgba(230,237,243,0.85)");
+        ctx.fill_text(tw.target_mode.letter(), cx + 0.36, cy - 0.22)
+            .ok();
+
+        // Veterancy chevrons under the tower
+        let rank = tw.rank();
+        if rank > 0 {
+            ctx.begin_path();
+            ctx.set_stroke_style_str("#ffd700");
+            ctx.set_line_width(0.05);
+            let w = 0.14;
+            let left = cx - (rank as f64 * w) * 0.5;
+            for i in 0..rank {
+                let x0 = left + i as f64 * w;
+                ctx.move_to(x0 + 0.02, cy + 0.44);
+                ctx.line_to(x0 + w * 0.5, cy + 0.37);
+                ctx.line_to(x0 + w - 0.02, cy + 0.44);
+            }
+            ctx.stroke();
+        }
+
+        // Siege durability above the tower, once it has taken wear
+        if rs.siege && tw.hp < tw.max_hp {
+            let frac = (tw.hp / tw.max_hp.max(1.0)).clamp(0.0, 1.0);
+            ctx.set_fill_style_str("#1a2332");
+            ctx.fill_rect(cx - 0.35, cy - 0.48, 0.7, 0.07);
+            ctx.set_fill_style_str(if frac > 0.3 { "#3fb950" } else { "#f85149" });
+            ctx.fill_rect(cx - 0.35, cy - 0.48, 0.7 * frac, 0.07);
+        }
+    }
+    // Scarecrows: a post and crossbar, with a durability bar beneath
+    for st in &rs.structures {
+        let cx = st.x as f64 + 0.5;
+        let cy = st.y as f64 + 0.5;
+        ctx.begin_path();
+        ctx.set_stroke_style_str("#c8a165");
+        ctx.set_line_width(0.07);
+        ctx.move_to(cx, cy - 0.32);
+        ctx.line_to(cx, cy + 0.28);
+        ctx.move_to(cx - 0.26, cy - 0.12);
+        ctx.line_to(cx + 0.26, cy - 0.12);
+        ctx.stroke();
+        ctx.begin_path();
+        ctx.set_fill_style_str("#e3b341");
+        ctx.arc(cx, cy - 0.3, 0.1, 0.0, std::f64::consts::PI * 2.0)
+            .ok();
+        ctx.fill();
+        let frac = st.hp as f64 / st.max_hp.max(1) as f64;
+        ctx.set_fill_style_str("#1a2332");
+        ctx.fill_rect(cx - 0.35, cy + 0.36, 0.7, 0.08);
+        ctx.set_fill_style_str(if frac > 0.3 { "#3fb950" } else { "#f85149" });
+        ctx.fill_rect(cx - 0.35, cy + 0.36, 0.7 * frac, 0.08);
+    }
+    if !rs.projectiles.is_empty() {
+        ctx.set_fill_style_str("#fffb");
+        for p in &rs.projectiles {
+            ctx.begin_path();
+            ctx.arc(p.x, p.y, 0.08, 0.0, std::f64::consts::PI * 2.0)
+                .ok();
+            ctx.fill();
+        }
+    }
+    for se in rs
+        .splash_explosions
+        .iter()
+        .filter(|_| !layers.reduced_effects)
+    {
+        let life_ratio = (se.ttl / 0.25).clamp(0.0, 1.0);
+        let alpha = life_ratio * 0.6;
+        let current_radius = se.radius * (1.0 - life_ratio * 0.3);
+        ctx.begin_path();
+        ctx.arc(se.x, se.y, current_radius, 0.0, std::f64::consts::PI * 2.0)
+            .ok();
+        ctx.set_fill_style_str(&format!("rgba(255,160,50,{:.3})", alpha));
+        ctx.fill();
+        ctx.set_stroke_style_str(&format!("rgba(255,100,30,{:.3})", alpha));
+        ctx.set_line_width(0.06);
+        ctx.stroke();
+    }
+    // Pickups: a twinkling dot inside a ring that drains as the drop nears expiry
+    for (i, pk) in rs.pickups.iter().enumerate() {
+        let twinkle = if layers.reduced_effects {
+            1.0
+        } else {
+            0.75 + 0.25 * (rs.sim_time * 6.0 + i as f64 * 1.7).sin()
+        };
+        let rgb = if pk.gold > 0 {
+            "212,175,55"
+        } else {
+            "163,113,247"
+        };
+        ctx.begin_path();
+        ctx.arc(pk.x, pk.y, 0.1 * twinkle, 0.0, std::f64::consts::PI * 2.0)
+            .ok();
+        ctx.set_fill_style_str(&format!("rgba({rgb},{:.3})", twinkle));
+        ctx.fill();
+        let left = (pk.ttl / model::PICKUP_TTL_SECS).clamp(0.0, 1.0);
+        let start = -std::f64::consts::FRAC_PI_2;
+        ctx.begin_path();
+        ctx.arc(
+            pk.x,
+            pk.y,
+            0.2,
+            start,
+            start + left * std::f64::consts::PI * 2.0,
+        )
+        .ok();
+        ctx.set_stroke_style_str(&format!("rgba({rgb},{:.3})", 0.3 + 0.5 * left));
+        ctx.set_line_width(0.04);
+        ctx.stroke();
+    }
+    // Tile labels at high zoom; hidden while the camera is being dragged
+    if (layers.show_tile_labels || history.is_some())
+        && cam.zoom >= TILE_LABEL_MIN_ZOOM
+        && !cam.panning
+    {
+        draw_tile_labels(
+            ctx,
+            rs,
+            cam,
+            (w, h),
+            history.as_ref(),
+            layers.show_tile_labels,
+        );
+        ctx.set_transform(scale_px, 0.0, 0.0, scale_px, cam.offset_x, cam.offset_y)
+            .ok();
+    }
+    // Damage numbers (floating text)
+    if layers.show_damage_numbers && !rs.damage_numbers.is_empty() {
+        let base_font_size = (0.2 / scale_px).max(0.5);
+        ctx.set_text_align("center");
+        for dn in &rs.damage_numbers {
+            let life_ratio = (dn.ttl / 0.8_f64).clamp(0.0, 1.0);
+            let rise = (0.8_f64 - dn.ttl).max(0.0_f64) * 0.30_f64;
+            let alpha = life_ratio;
+            if dn.is_clutch {
+                ctx.set_font(&format!("bold {}px sans-serif", base_font_size * 1.6));
+                ctx.set_fill_style_str(&format!("rgba(255,123,209,{:.3})", alpha));
+                ctx.fill_text(
+                    &format!("+{} \u{2665} CLUTCH", dn.amount),
+                    dn.x,
+                    dn.y - rise,
+                )
+                .ok();
+            } else if dn.is_heal {
+                ctx.set_font(&format!("bold {}px sans-serif", base_font_size * 1.2));
+                ctx.set_fill_style_str(&format!("rgba(46,160,67,{:.3})", alpha));
+                ctx.fill_text(&format!("+{}", dn.amount), dn.x, dn.y - rise)
+                    .ok();
+            } else if dn.is_gold {
+                if dn.is_crit {
+                    ctx.set_font(&format!("bold {}px sans-serif", base_font_size * 1.5));
+                    ctx.set_fill_style_str(&format!("rgba(255,215,0,{:.3})", alpha));
+                } else {
+                    ctx.set_font(&format!("{}px sans-serif", base_font_size));
+                    ctx.set_fill_style_str(&format!("rgba(210,153,34,{:.3})", alpha));
+                }
+                ctx.fill_text(&format!("+{}", dn.amount), dn.x, dn.y - rise)
+                    .ok();
+            } else {
+                if dn.is_crit {
+                    ctx.set_font(&format!("bold {}px sans-serif", base_font_size * 1.4));
+                } else {
+                    ctx.set_font(&format!("{}px sans-serif", base_font_size));
+                }
+                ctx.set_fill_style_str(&format!("rgba(255,50,50,{:.3})", alpha));
+                ctx.fill_text(&dn.amount.to_string(), dn.x, dn.y - rise)
+                    .ok();
+            }
+        }
+        ctx.set_text_align("start");
+    }
+    // Reward text, rising and fading the same way
+    if layers.show_damage_numbers && !rs.floating_texts.is_empty() {
+        let base_font_size = (0.2 / scale_px).max(0.5);
+        ctx.set_text_align("center");
+        for ft in &rs.floating_texts {
+            let age = model::FLOATING_TEXT_SECS - ft.ttl;
+            ctx.set_global_alpha((ft.ttl / model::FLOATING_TEXT_SECS).clamp(0.0, 1.0));
+            if ft.is_crit {
+                ctx.set_font(&format!("bold {}px sans-serif", base_font_size * 1.5));
+            } else {
+                ctx.set_font(&format!("{}px sans-serif", base_font_size));
+            }
+            ctx.set_fill_style_str(&ft.color);
+            ctx.fill_text(&ft.text, ft.x, ft.y - age.max(0.0) * 0.30)
+                .ok();
+        }
+        ctx.set_global_alpha(1.0);
+        ctx.set_text_align("start");
+    }
+}
+
+// Golden images for render_grid: a handcrafted board is drawn at a fixed camera into a
+// detached canvas and the pixels are hashed against fixtures/golden/render_grid.txt, one
+// "<scene>/<palette> <hash>" line per layer set and palette. Text rasterizes differently across
+// browsers and platforms, so the hashes are those of headless Chrome on Linux, as CI runs it.
+//
+// To regenerate after an intended rendering change: run
+// `wasm-pack test --headless --chrome -- golden_tests` and copy the lines the failures print
+// into the file. A changed render fails until its line is replaced, and a scene or palette
+// without a line fails too, so neither can go unchecked.
+#[cfg(all(test, target_arch = "wasm32"))]
+mod golden_tests {
+    use super::*;
+    use crate::model::{
+        ArrowDir, BoostKind, DamageNumber, DirRole, Enemy, EnemyKind, EntityId, GridSize,
+        Projectile, Tile, TileKind, Tower, TowerKind, UpgradeState,
+    };
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_test::{wasm_bindgen_test, wasm_bindgen_test_configure};
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    const SIZE: u32 = 12;
+    const GOLDEN: &str = include_str!("../../fixtures/golden/render_grid.txt");
+
+    // One of each tile kind along the top rows, a tower on a boost rock's neighbour, and an
+    // enemy, a shot and a damage number caught mid-flight
+    fn scene() -> RunState {
+        let gs = GridSize {
+            width: SIZE,
+            height: SIZE,
+        };
+        let mut rs = RunState::new_with_upgrades_seeded(gs, &UpgradeState::default(), 1);
+        let kinds = [
+            TileKind::Empty,
+            TileKind::Rock {
+                has_gold: false,
+                boost: None,
+            },
+            TileKind::Rock {
+                has_gold: true,
+                boost: None,
+            },
+            TileKind::Rock {
+                has_gold: true,
+                boost: Some(BoostKind::Fire),
+            },
+            TileKind::Wall,
+            TileKind::Start,
+            TileKind::Direction {
+                dir: ArrowDir::Right,
+                role: DirRole::Entrance,
+            },
+            TileKind::Direction {
+                dir: ArrowDir::Down,
+                role: DirRole::Exit,
+            },
+            TileKind::Indestructible,
+            TileKind::End,
+        ];
+        for (i, tile) in rs.tiles.iter_mut().enumerate() {
+            let kind = kinds.get(i).cloned().unwrap_or(if i % 5 == 0 {
+                TileKind::Wall
+            } else {
+                TileKind::Empty
+            });
+            *tile = Tile {
+                hardness: model::default_hardness(&kind),
+                kind,
+                magma: false,
+            };
+        }
+        rs.towers = vec![Tower::new(
+            4,
+            5,
+            TowerKind::Basic,
+            3.5,
+            20,
+            Some(BoostKind::Range),
+        )];
+        rs.enemies = vec![Enemy {
+            id: EntityId::default(),
+            x: 6.5,
+            y: 5.5,
+            speed_tps: 0.0,
+            hp: 30,
+            max_hp: 60,
+            spawned_at: 0,
+            path_index: 0,
+            dir_dx: 1.0,
+            dir_dy: 0.0,
+            radius_scale: 1.0,
+            loop_dist: 0.0,
+            debuffs: Vec::new(),
+            kind: EnemyKind::Normal,
+            hit_flash_ttl: 0.0,
+            spawn_anim_ttl: 0.0,
+        }];
+        rs.projectiles = vec![Projectile {
+            id: EntityId::default(),
+            x: 5.6,
+            y: 5.5,
+            vx: 8.0,
+            vy: 0.0,
+            remaining: 0.9,
+            damage: 20,
+            splash_radius: 0.0,
+            apply_debuff: None,
+            source: None,
+            age: 0.1,
+            bounces_remaining: 0,
+            slow: None,
+        }];
+        rs.damage_numbers = vec![DamageNumber {
+            x: 6.5,
+            y: 5.0,
+            amount: 20,
+            ttl: 0.5,
+            is_crit: false,
+            is_gold: false,
+            is_heal: false,
+            is_clutch: false,
+        }];
+        rs
+    }
+
+    // FNV-1a over the RGBA bytes
+    fn fnv1a(bytes: &[u8]) -> u64 {
+        bytes.iter().fold(0xcbf2_9ce4_8422_2325, |h, b| {
+            (h ^ u64::from(*b)).wrapping_mul(0x0100_0000_01b3)
+        })
+    }
+
+    fn render_hash(rs: &RunState, layers: BoardLayers) -> u64 {
+        let px = SIZE * 32;
+        let canvas = web_sys::window()
+            .and_then(|w| w.document())
+            .and_then(|d| d.create_element("canvas").ok())
+            .and_then(|e| e.dyn_into::<web_sys::HtmlCanvasElement>().ok())
+            .expect("canvas");
+        canvas.set_width(px);
+        canvas.set_height(px);
+        let ctx = canvas
+            .get_context("2d")
+            .ok()
+            .flatten()
+            .and_then(|c| c.dyn_into::<CanvasRenderingContext2d>().ok())
+            .expect("2d context");
+        let cam = Camera {
+            zoom: 1.0,
+            ..Camera::default()
+        };
+        let size = (px as f64, px as f64);
+        let mut terrain = TerrainLayer::default();
+        let motion = EnemyMotion::new(16.0);
+        render_grid(&ctx, rs, &cam, size, &mut terrain, &motion, layers);
+        let image = ctx
+            .get_image_data(0.0, 0.0, size.0, size.1)
+            .expect("image data");
+        fnv1a(&image.data())
+    }
+
+    fn palette_key(palette: PaletteId) -> &'static str {
+        match palette {
+            PaletteId::Default => "default",
+            PaletteId::HighContrast => "high_contrast",
+        }
+    }
+
+    // Every palette of the scene against its recorded hash
+    fn check(scene_name: &str, layers: BoardLayers) {
+        let mut unrecorded = Vec::new();
+        for palette in PaletteId::ALL {
+            let name = format!("{scene_name}/{}", palette_key(palette));
+            let hash = render_hash(&scene(), BoardLayers { palette, ..layers });
+            let recorded = GOLDEN.lines().find_map(|l| {
+                let (scene, hex) = l.split_once(' ')?;
+                (scene == name).then(|| u64::from_str_radix(hex.trim(), 16).ok())?
+            });
+            match recorded {
+                Some(want) => assert_eq!(
+                    hash, want,
+                    "{name} no longer matches its golden image; if the change is intended, \
+                     replace its line in fixtures/golden/render_grid.txt with:\n{name} {hash:016x}"
+                ),
+                None => unrecorded.push(format!("{name} {hash:016x}")),
+            }
+        }
+        assert!(
+            unrecorded.is_empty(),
+            "no golden hash recorded; add to fixtures/golden/render_grid.txt:\n{}",
+            unrecorded.join("\n")
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn default_layers_match_golden() {
+        check(
+            "default",
+            BoardLayers {
+                show_damage_numbers: true,
+                ..BoardLayers::default()
+            },
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn prospector_with_reduced_effects_matches_golden() {
+        check(
+            "prospector_reduced",
+            BoardLayers {
+                prospector: true,
+                reduced_effects: true,
+                ..BoardLayers::default()
+            },
+        );
+    }
+
+    // The same inputs have to give the same pixels, or no golden could ever hold
+    #[wasm_bindgen_test]
+    fn rendering_is_deterministic() {
+        let layers = BoardLayers {
+            show_damage_numbers: true,
+            prospector: true,
+            ..BoardLayers::default()
+        };
+        assert_eq!(render_hash(&scene(), layers), render_hash(&scene(), layers));
+    }
+}
@@ -1,3 +1,4 @@
+use web_sys::HtmlInputElement;
 use yew::prelude::*;
 
 #[derive(Properties, PartialEq, Clone)]
@@ -9,6 +10,11 @@ pub struct CameraControlsProps {
     pub on_pan_up: Callback<()>,
     pub on_pan_down: Callback<()>,
     pub on_center: Callback<()>,
+    // Bookmark slot names, None for an empty slot
+    pub bookmarks: Vec<Option<String>>,
+    pub on_save_bookmark: Callback<usize>,
+    pub on_recall_bookmark: Callback<usize>,
+    pub on_rename_bookmark: Callback<(usize, String)>,
 }
 
 #[function_component(CameraControls)]
@@ -41,6 +47,93 @@ pub fn camera_controls(props: &CameraControlsProps) -> Html {
         let cb = props.on_center.clone();
         Callback::from(move |_| cb.emit(()))
     };
+    // Slot whose name is being edited; the field takes focus as soon as it appears
+    let editing = use_state(|| None::<usize>);
+    let edit_ref = use_node_ref();
+    {
+        let edit_ref = edit_ref.clone();
+        use_effect_with(*editing, move |_| {
+            if let Some(input) = edit_ref.cast::<HtmlInputElement>() {
+                let _ = input.focus();
+                input.select();
+            }
+            || ()
+        });
+    }
+    let slots = props
+        .bookmarks
+        .iter()
+        .enumerate()
+        .map(|(i, label)| {
+            let key = i + 1;
+            if *editing == Some(i) {
+                let commit = {
+                    let editing = editing.clone();
+                    let cb = props.on_rename_bookmark.clone();
+                    move |input: HtmlInputElement| {
+                        cb.emit((i, input.value()));
+                        editing.set(None);
+                    }
+                };
+                let value = label.clone().unwrap_or_default();
+                let onkeydown = {
+                    let commit = commit.clone();
+                    let original = value.clone();
+                    Callback::from(move |e: KeyboardEvent| {
+                        let input: HtmlInputElement = e.target_unchecked_into();
+                        match e.key().as_str() {
+                            "Enter" => commit(input),
+                            // Put the old name back so the blur that follows is a no-op
+                            "Escape" => {
+                                input.set_value(&original);
+                                commit(input);
+                            }
+                            _ => {}
+                        }
+                    })
+                };
+                let onblur = Callback::from(move |e: FocusEvent| commit(e.target_unchecked_into()));
+                return html! {
+                    <input ref={edit_ref.clone()} type="text" maxlength="16" {value} {onkeydown} {onblur}
+                        style="width:72px; font-size:11px;" />
+                };
+            }
+            let onclick = {
+                let save = props.on_save_bookmark.clone();
+                let recall = props.on_recall_bookmark.clone();
+                let filled = label.is_some();
+                Callback::from(move |e: MouseEvent| {
+                    if filled && !e.shift_key() {
+                        recall.emit(i);
+                    } else {
+                        save.emit(i);
+                    }
+                })
+            };
+            let ondblclick = {
+                let editing = editing.clone();
+                let filled = label.is_some();
+                Callback::from(move |_: MouseEvent| {
+                    if filled {
+                        editing.set(Some(i));
+                    }
+                })
+            };
+            let (text, title, style) = match label {
+                Some(name) => (
+                    name.clone(),
+                    format!("Go to {name} (Alt+{key}). Double-click to rename, Shift+click or Ctrl+{key} to save over it"),
+                    "font-size:11px;",
+                ),
+                None => (
+                    key.to_string(),
+                    format!("Save this view (Ctrl+{key})"),
+                    "font-size:11px; opacity:0.55;",
+                ),
+            };
+            html! { <button {onclick} {ondblclick} {title} {style}>{ text }</button> }
+        })
+        .collect::<Vec<Html>>();
     html! {<div style="position:absolute; left:12px; bottom:12px; background:rgba(22,27,34,0.9); border:1px solid #30363d; border-radius:8px; padding:8px; display:flex; gap:6px; align-items:center;">
         <button onclick={zo}> {"-"} </button>
         <button onclick={zi}> {"+"} </button>
@@ -51,5 +144,8 @@ pub fn camera_controls(props: &CameraControlsProps) -> Html {
         <button onclick={pl}> {"→"} </button>
         <span style="width:8px;"></span>
         <button onclick={cc}> {"Center"} </button>
+        <span style="width:8px;"></span>
+        <span style="font-size:11px; color:#8b949e;" title="Camera bookmarks">{"📍"}</span>
+        { for slots }
     </div>}
 }
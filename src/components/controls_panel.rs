@@ -1,3 +1,5 @@
+use crate::model::{TowerKind, SIM_SPEEDS};
+use crate::state::PaletteId;
 use yew::prelude::*;
 
 #[derive(Properties, PartialEq, Clone)]
@@ -5,6 +7,30 @@ pub struct ControlsPanelProps {
     pub to_upgrades: Callback<()>,
     pub on_show_help: Callback<()>,
     pub on_open_settings: Callback<()>,
+    pub on_scenarios: Callback<()>,
+    #[prop_or_default]
+    pub on_history: Callback<()>,
+    pub show_upgrades: bool,
+    pub show_scenarios: bool,
+    pub tower_kind: TowerKind,
+    // Price per kind, in TowerKind::ALL order
+    pub tower_costs: [u64; 3],
+    pub on_select_tower: Callback<TowerKind>,
+    // Hidden while towers are still locked
+    pub show_towers: bool,
+    // Key label for the undo prompt while the latest wall can still be taken back
+    #[prop_or_default]
+    pub undo_wall_key: Option<String>,
+    #[prop_or_default]
+    pub on_undo_wall: Callback<()>,
+    // Game speed buttons (+/- step through them); Step advances a paused run by one frame
+    pub sim_speed: f64,
+    pub on_set_speed: Callback<f64>,
+    pub can_step: bool,
+    pub on_step: Callback<()>,
+    // Tower swatches match the board's palette
+    #[prop_or_default]
+    pub palette: PaletteId,
 }
 
 #[function_component]
@@ -21,9 +47,66 @@ pub fn ControlsPanel(props: &ControlsPanelProps) -> Html {
         let cb = props.on_open_settings.clone();
         Callback::from(move |_| cb.emit(()))
     };
+    let scenarios_cb = {
+        let cb = props.on_scenarios.clone();
+        Callback::from(move |_| cb.emit(()))
+    };
+    let history_cb = {
+        let cb = props.on_history.clone();
+        Callback::from(move |_| cb.emit(()))
+    };
+    let undo_wall_cb = {
+        let cb = props.on_undo_wall.clone();
+        Callback::from(move |_| cb.emit(()))
+    };
+    let step_cb = {
+        let cb = props.on_step.clone();
+        Callback::from(move |_| cb.emit(()))
+    };
+    let pal = props.palette.palette();
     html! {<div style="position:absolute; top:12px; right:12px; background:rgba(22,27,34,0.9); border:1px solid #30363d; border-radius:8px; padding:10px 12px; min-width:170px; display:flex; flex-direction:column; gap:6px;">
+        if props.show_towers {
+            <div style="display:flex; gap:4px;" title="1/2/3 pick a tower, Q cycles">
+                { for TowerKind::ALL.iter().zip(props.tower_costs).enumerate().map(|(i, (kind, cost))| {
+                    let selected = *kind == props.tower_kind;
+                    let border = if selected { "#d29922" } else { "#30363d" };
+                    let style = format!("flex:1; display:flex; flex-direction:column; align-items:center; padding:4px 2px; border:1px solid {border}; font-size:11px;");
+                    let dot = format!("color:{};", pal.tower_color(kind));
+                    let on_select = props.on_select_tower.clone();
+                    let pick = kind.clone();
+                    let onclick = Callback::from(move |_| on_select.emit(pick.clone()));
+                    html! {<button {onclick} {style}>
+                        <span style={dot}>{"●"}</span>
+                        <span>{ format!("{} {}", i + 1, kind.name()) }</span>
+                        <span style="opacity:0.8;">{ format!("{}g", cost) }</span>
+                    </button>}
+                }) }
+            </div>
+        }
+        <div style="display:flex; gap:4px; align-items:center; font-size:12px;" title="Game speed (+/-)">
+            { for SIM_SPEEDS.iter().map(|&speed| {
+                let border = if speed == props.sim_speed { "#d29922" } else { "#30363d" };
+                let on_set = props.on_set_speed.clone();
+                let onclick = Callback::from(move |_| on_set.emit(speed));
+                html! {<button {onclick} style={format!("flex:1; padding:2px 4px; border:1px solid {border};")}>
+                    { format!("{}x", speed) }
+                </button>}
+            }) }
+            if props.can_step {
+                <button onclick={step_cb} style="flex:1; padding:2px 4px;" title="Advance the paused run by 0.1s">{"⏭"}</button>
+            }
+        </div>
         <button onclick={settings_cb} style="display:flex; align-items:center; gap:6px;">{"⚙"}<span>{"Settings"}</span></button>
-        <button onclick={upgrades_cb} style="display:flex; align-items:center; gap:6px;">{"🧬"}<span>{"Upgrades"}</span></button>
+        if props.show_upgrades {
+            <button onclick={upgrades_cb} style="display:flex; align-items:center; gap:6px;">{"🧬"}<span>{"Upgrades"}</span></button>
+        }
+        if props.show_scenarios {
+            <button onclick={scenarios_cb} style="display:flex; align-items:center; gap:6px;">{"🗺"}<span>{"Scenarios"}</span></button>
+        }
+        <button onclick={history_cb} style="display:flex; align-items:center; gap:6px;">{"📜"}<span>{"History"}</span></button>
         <button onclick={help_cb} style="display:flex; align-items:center; gap:6px;">{"❓"}<span>{"Help"}</span></button>
+        if let Some(key) = &props.undo_wall_key {
+            <button onclick={undo_wall_cb} style="font-size:12px; border-color:#d29922;">{ format!("Wall placed — Undo ({key})") }</button>
+        }
     </div>}
 }
@@ -1,4 +1,5 @@
-use crate::model::{MetaRecords, RunStats};
+use crate::model::{MetaRecords, RouteMode, RunStats};
+use crate::persistence::{self, LocalStorage};
 use crate::util::format_time;
 use yew::prelude::*;
 
@@ -8,11 +9,35 @@ pub struct GameOverOverlayProps {
     pub time_survived: u64,
     pub loops_completed: u32,
     pub blocks_mined: u32,
+    pub research_earned: u64,
+    // Lives refunded by kills just short of the loop's end
+    #[prop_or_default]
+    pub clutch_saves: u32,
+    // Everything else the run counted, for the breakdown
+    #[prop_or_default]
+    pub stats: RunStats,
+    // Longest survival in the run history
+    #[prop_or_default]
+    pub new_best: bool,
     pub restart: Callback<()>,
     pub to_upgrades: Callback<()>,
+    // Downloads the final board as a standalone HTML viewer
+    #[prop_or_default]
+    pub export_snapshot: Callback<()>,
+    // False for imported debug runs, which never set records
+    #[prop_or(true)]
+    pub counts_for_records: bool,
+    // The finished run's route: an End run reports breaches rather than loops
+    #[prop_or_default]
+    pub route_mode: RouteMode,
+    // Route for the runs that follow, toggled here before restarting
+    #[prop_or_default]
+    pub next_end_route: bool,
+    #[prop_or_default]
+    pub on_toggle_end_route: Callback<()>,
 }
 
-fn load_records() -> MetaRecords {
+pub fn load_records() -> MetaRecords {
     if let Some(win) = web_sys::window() {
         if let Ok(Some(store)) = win.local_storage() {
             if let Ok(Some(raw)) = store.get_item("md_records") {
@@ -25,13 +50,9 @@ fn load_records() -> MetaRecords {
     MetaRecords::default()
 }
 
-fn save_records(records: &MetaRecords) {
-    if let Some(win) = web_sys::window() {
-        if let Ok(Some(store)) = win.local_storage() {
-            if let Ok(s) = serde_json::to_string(records) {
-                let _ = store.set_item("md_records", &s);
-            }
-        }
+pub fn save_records(records: &MetaRecords) {
+    if let (Some(mut store), Ok(s)) = (LocalStorage::open(), serde_json::to_string(records)) {
+        persistence::write(&mut store, "md_records", &s);
     }
 }
 
@@ -50,16 +71,20 @@ pub fn GameOverOverlay(props: &GameOverOverlayProps) -> Html {
         let time = props.time_survived;
         let loops = props.loops_completed;
         let blocks = props.blocks_mined;
+        let research = props.research_earned;
         let records_updated = records_updated.clone();
         let new_records = new_records.clone();
         let records = records.clone();
+        let counts = props.counts_for_records;
         use_effect_with(show, move |_| {
-            if show && !*records_updated {
+            if show && counts && !*records_updated {
                 let mut r = load_records();
                 let stats = RunStats {
                     time_survived_secs: time,
                     loops_completed: loops,
                     blocks_mined: blocks,
+                    research_earned: research,
+                    ..Default::default()
                 };
                 let nr = r.update_from_stats(&stats);
                 save_records(&r);
@@ -78,6 +103,29 @@ pub fn GameOverOverlay(props: &GameOverOverlayProps) -> Html {
         Callback::from(move |_| cb.emit(()))
     };
 
+    let export_btn = {
+        let cb = props.export_snapshot.clone();
+        Callback::from(move |_| cb.emit(()))
+    };
+
+    let toggle_route = {
+        let cb = props.on_toggle_end_route.clone();
+        Callback::from(move |_| cb.emit(()))
+    };
+
+    let s = &props.stats;
+    let breakdown = [
+        ("Kills", s.enemies_killed.to_string()),
+        ("Damage Dealt", s.total_damage_dealt.to_string()),
+        ("Highest Hit", s.highest_single_hit.to_string()),
+        ("Research Earned", props.research_earned.to_string()),
+        ("Gold Earned", s.gold_earned.to_string()),
+        ("Gold Spent", s.gold_spent.to_string()),
+        ("Towers Built", s.towers_built.to_string()),
+        ("Towers Sold", s.towers_sold.to_string()),
+        ("Walls Built", s.walls_built.to_string()),
+    ];
+
     let nr = &*new_records;
     let rec = &*records;
     let record_marker = |key: &str| -> Html {
@@ -91,9 +139,27 @@ pub fn GameOverOverlay(props: &GameOverOverlayProps) -> Html {
     html! {
         <div style="position:absolute; top:50%; left:50%; transform:translate(-50%, -50%); background:rgba(0,0,0,0.85); border:2px solid #f85149; padding:24px 32px; border-radius:12px; text-align:center; min-width:320px;">
             <h2 style="margin:0 0 12px 0; color:#f85149;">{"Game Over"}</h2>
+            if props.new_best {
+                <div style="margin:0 0 10px 0; padding:4px 10px; border-radius:6px; background:rgba(210,153,34,0.2); color:#d29922; font-weight:bold;">{"🏆 New record! Your longest run yet"}</div>
+            }
             <p style="margin:4px 0;">{ format!("Time Survived: {}", format_time(props.time_survived)) }{ record_marker("time") }</p>
-            <p style="margin:4px 0;">{ format!("Loops Completed: {}", props.loops_completed) }{ record_marker("loops") }</p>
+            if props.route_mode == RouteMode::End {
+                <p style="margin:4px 0;">{ format!("Breaches: {}", s.breaches) }</p>
+            } else {
+                <p style="margin:4px 0;">{ format!("Loops Completed: {}", props.loops_completed) }{ record_marker("loops") }</p>
+            }
             <p style="margin:4px 0;">{ format!("Blocks Mined: {}", props.blocks_mined) }{ record_marker("blocks") }</p>
+            if props.clutch_saves > 0 {
+                <p style="margin:4px 0; color:#ff7bd1;">{ format!("Clutch Saves: {}", props.clutch_saves) }</p>
+            }
+            <div style="display:grid; grid-template-columns:1fr 1fr; gap:2px 20px; margin-top:10px; font-size:0.9em; text-align:left;">
+                { for breakdown.iter().map(|(label, value)| html! {
+                    <div style="display:flex; justify-content:space-between; gap:8px;">
+                        <span style="color:#8b949e;">{ *label }</span>
+                        <span style="font-variant-numeric:tabular-nums;">{ value }</span>
+                    </div>
+                }) }
+            </div>
             if rec.total_runs > 1 {
                 <div style="margin-top:12px; border-top:1px solid #30363d; padding-top:8px;">
                     <p style="margin:2px 0; font-size:0.85em; color:#8b949e;">{ format!("Best Time: {}", format_time(rec.best_time_secs)) }</p>
@@ -102,10 +168,17 @@ pub fn GameOverOverlay(props: &GameOverOverlayProps) -> Html {
                     <p style="margin:2px 0; font-size:0.85em; color:#8b949e;">{ format!("Total Runs: {}", rec.total_runs) }</p>
                 </div>
             }
-            <div style="margin-top:16px; display:flex; gap:12px; justify-content:center;">
+            <label style="display:block; margin-top:14px; font-size:0.9em;" title="Enemies head for an End tile buried in the rock and breach there instead of looping back to Start">
+                <input type="checkbox" checked={props.next_end_route} onclick={toggle_route} />
+                {" End route for the next run"}
+            </label>
+            <div style="margin-top:10px; display:flex; gap:12px; justify-content:center;">
                 <button onclick={restart_btn}>{"Restart Run"}</button>
                 <button onclick={upgrades_btn}>{"Upgrades"}</button>
             </div>
+            <button onclick={export_btn} style="margin-top:10px; font-size:12px;" title="A single HTML file with the final map and run stats; open it in any browser">
+                {"Export interactive snapshot"}
+            </button>
         </div>
     }
 }
@@ -0,0 +1,115 @@
+use crate::persistence::LocalStorage;
+use crate::run_history::{self, RunRecord};
+use crate::util::format_time;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct HistoryViewProps {
+    pub to_run: Callback<()>,
+    pub to_achievements: Callback<()>,
+}
+
+fn stored_history() -> Vec<RunRecord> {
+    LocalStorage::open()
+        .map(|s| run_history::load(&s))
+        .unwrap_or_default()
+}
+
+// "today", "yesterday", "5 days ago", counted in whole days since the epoch like the daily map
+fn played_ago(ms: f64, now_ms: f64) -> String {
+    let day = |t: f64| (t / 86_400_000.0).floor() as i64;
+    match day(now_ms) - day(ms) {
+        i64::MIN..=0 => "today".into(),
+        1 => "yesterday".into(),
+        n => format!("{n} days ago"),
+    }
+}
+
+// Past runs, longest survival first, with the personal best highlighted
+#[function_component]
+pub fn HistoryView(props: &HistoryViewProps) -> Html {
+    let history = use_state(stored_history);
+
+    let back_cb = {
+        let cb = props.to_run.clone();
+        Callback::from(move |_| cb.emit(()))
+    };
+    let achievements_cb = {
+        let cb = props.to_achievements.clone();
+        Callback::from(move |_| cb.emit(()))
+    };
+    let clear_cb = {
+        let history = history.clone();
+        Callback::from(move |_| {
+            let confirmed = web_sys::window()
+                .and_then(|w| {
+                    w.confirm_with_message("Clear the history of past runs? Records stay.")
+                        .ok()
+                })
+                .unwrap_or(false);
+            if confirmed {
+                if let Some(mut store) = LocalStorage::open() {
+                    run_history::clear(&mut store);
+                }
+                history.set(Vec::new());
+            }
+        })
+    };
+
+    let now = js_sys::Date::now();
+    // The all-time best, which may have left the capped list
+    let best_ts = LocalStorage::open()
+        .and_then(|s| run_history::load_best(&s))
+        .map(|r| r.timestamp_ms);
+    let rows = run_history::by_time_survived(&history);
+    let cell = "padding:4px 10px; text-align:right; font-variant-numeric:tabular-nums;";
+
+    html! {<div style="position:absolute; inset:0; overflow:auto; background:#0e1116; color:#e6edf3; display:flex; flex-direction:column; align-items:center; padding:24px 12px;">
+        <div style="width:100%; max-width:720px; display:flex; justify-content:space-between; align-items:center; margin-bottom:12px;">
+            <h2 style="margin:0;">{"Run History"}</h2>
+            <div style="display:flex; gap:8px;">
+                if !history.is_empty() {
+                    <button onclick={clear_cb}>{"Clear history"}</button>
+                }
+                <button onclick={achievements_cb}>{"Achievements"}</button>
+                <button onclick={back_cb}>{"Back"}</button>
+            </div>
+        </div>
+        if rows.is_empty() {
+            <p style="color:#8b949e;">{"No finished runs yet. Runs are listed here once they end or are restarted."}</p>
+        } else {
+            <table style="width:100%; max-width:720px; border-collapse:collapse; background:#161b22; border:1px solid #30363d; border-radius:8px; font-size:14px;">
+                <thead>
+                    <tr style="color:#8b949e; border-bottom:1px solid #30363d;">
+                        <th style={cell}>{"#"}</th>
+                        <th style={cell}>{"Time"}</th>
+                        <th style={cell}>{"Loops"}</th>
+                        <th style={cell}>{"Kills"}</th>
+                        <th style={cell}>{"Research"}</th>
+                        <th style={cell}>{"Grid"}</th>
+                        <th style={cell}>{"Played"}</th>
+                    </tr>
+                </thead>
+                <tbody>
+                    { for rows.iter().enumerate().map(|(i, r)| {
+                        let best = Some(r.timestamp_ms) == best_ts;
+                        let row_style = if best {
+                            "background:rgba(210,153,34,0.15); color:#d29922; font-weight:600;"
+                        } else {
+                            ""
+                        };
+                        html! {<tr style={row_style}>
+                            <td style={cell}>{ i + 1 }{ if best { " 🏆" } else { "" } }</td>
+                            <td style={cell}>{ format_time(r.time_survived_secs) }</td>
+                            <td style={cell}>{ r.loops_completed }</td>
+                            <td style={cell}>{ r.enemies_killed }</td>
+                            <td style={cell}>{ r.research_earned }</td>
+                            <td style={cell}>{ format!("{}×{}", r.grid_size.width, r.grid_size.height) }</td>
+                            <td style={format!("{cell} color:#8b949e;")}>{ played_ago(r.timestamp_ms, now) }</td>
+                        </tr>}
+                    }) }
+                </tbody>
+            </table>
+        }
+    </div>}
+}
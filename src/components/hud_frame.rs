@@ -0,0 +1,50 @@
+use crate::state::{HudLayout, HudMode};
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct HudFrameProps {
+    pub layout: HudLayout,
+    // Readouts and menus: the top drawer when stacked
+    pub drawer: Html,
+    // Things the player presses mid-run: the bottom action area when stacked
+    pub actions: Html,
+}
+
+// Positions the run HUD panels for the current layout mode. The frame itself never takes
+// pointer events (see .hud-frame in index.html), so the canvas underneath keeps its mouse math.
+#[function_component]
+pub fn HudFrame(props: &HudFrameProps) -> Html {
+    let drawer_open = use_state(|| true);
+    if props.layout.mode != HudMode::Stacked {
+        let style = format!(
+            "position:absolute; top:0; bottom:0; left:{0:.0}px; right:{0:.0}px;",
+            props.layout.inset_px
+        );
+        return html! {<div class="hud-frame" {style}>
+            { props.drawer.clone() }
+            { props.actions.clone() }
+        </div>};
+    }
+    let toggle_cb = {
+        let open = drawer_open.clone();
+        Callback::from(move |_| open.set(!*open))
+    };
+    let toggle_label = if *drawer_open {
+        "Hide HUD ▲"
+    } else {
+        "Show HUD ▼"
+    };
+    html! {<>
+        <div class="hud-frame" style="position:absolute; top:0; left:0; right:0; display:flex; flex-direction:column; align-items:center;">
+            if *drawer_open {
+                <div class="hud-stack" style="width:100%; max-height:45vh; overflow-y:auto; padding:8px; box-sizing:border-box; background:rgba(11,14,20,0.85); border-bottom:1px solid #30363d;">
+                    { props.drawer.clone() }
+                </div>
+            }
+            <button onclick={toggle_cb} style="padding:2px 12px; font-size:12px; border-top-left-radius:0; border-top-right-radius:0;">{ toggle_label }</button>
+        </div>
+        <div class="hud-frame hud-stack" style="position:absolute; bottom:0; left:0; right:0; padding:8px; box-sizing:border-box;">
+            { props.actions.clone() }
+        </div>
+    </>}
+}
@@ -6,6 +6,7 @@ pub struct IntroOverlayProps {
     pub game_over: bool,
     pub hide_intro: Callback<()>,
     pub to_upgrades: Callback<()>,
+    pub show_upgrades: bool,
 }
 
 #[function_component(IntroOverlay)]
@@ -28,16 +29,31 @@ pub fn intro_overlay(props: &IntroOverlayProps) -> Html {
             <ul style="margin:0 0 12px 18px; padding:0; list-style:disc; display:flex; flex-direction:column; gap:4px;">
                 <li>{"Hold Left Mouse on a Rock/Wall to mine it (progress bar fills)."}</li>
                 <li>{"Click an Empty path tile to place a Rock (cannot block all paths)."}</li>
-                <li>{"Hover a Rock and press 'T' to place a Tower (again to remove & refund)."}</li>
+                <li>{"Hover a Rock and press 'T' to place a Tower (again to sell it back)."}</li>
+                <li>{"Press 'U' over a Tower to buy it a level: more damage and range, up to level 5."}</li>
+                <li>{"Press 'R' over a Tower to choose its target: First, Last, Closest or Strongest."}</li>
+                <li>{"Press '1', '2' or '3' (or 'Q' to cycle) to choose Basic, Slow or Damage towers."}</li>
+                <li>{"Press 'X' for the exclusion brush: drag to paint tiles towers won't shoot into."}</li>
+                <li>{"Press 'G' to outline the rocks where a tower would cover the most path."}</li>
+                <li>{"Shift+click up to 5 rocks to queue them; they are mined in order without holding the button. Shift+click again to unqueue."}</li>
+                <li>{"Press 'P' to mark gold rocks near the mining frontier and total them by direction."}</li>
+                <li>{"Press 'B' to pick a room stamp (R rotates) and click to build it with tile credits."}</li>
+                <li>{"Press 'H' to show where recent damage landed; older hits fade out."}</li>
+                <li>{"Arrow keys move a tile cursor; hotkeys act on it until the mouse moves again."}</li>
                 <li>{"Press Space to Pause/Resume (also dismisses this screen)."}</li>
-                <li>{"Zoom with wheel or +/- buttons; drag (right/middle mouse) to pan."}</li>
+                <li>{"Press + / - to switch between 1x, 2x and 4x speed; while paused, ⏭ steps the run by 0.1s."}</li>
+                <li>{"Zoom with wheel or the zoom buttons; drag (right/middle mouse) or hold W/A/S/D (Shift+arrows) to pan."}</li>
+                <li>{"Ctrl+1..4 bookmarks the current view and Alt+1..4 glides back to it; the 📍 buttons do the same (double-click to rename)."}</li>
                 <li>{"Enemies loop the path; each completed loop costs 1 Life."}</li>
                 <li>{"Earn Research from kills; spend it in Upgrades between runs."}</li>
+                <li>{"Hover a Rock and press 'C' to raise a Scarecrow with Research: slower spawns until it wears out."}</li>
                 <li>{"Boost Rocks (colors) unlock via upgrades and change tower stats."}</li>
             </ul>
             <div style="display:flex; gap:12px; justify-content:center; margin-top:8px;">
                 <button onclick={start_btn}>{"Start"}</button>
-                <button onclick={upgrades_cb}>{"Upgrades"}</button>
+                if props.show_upgrades {
+                    <button onclick={upgrades_cb}>{"Upgrades"}</button>
+                }
                 <button onclick={hide_btn}>{"Close"}</button>
             </div>
             <div style="margin-top:12px; font-size:11px; opacity:0.6; text-align:center;">{"Tip: Place a tower early then mine to shape a longer looping path."}</div>
@@ -2,7 +2,7 @@ use yew::prelude::*;
 
 #[derive(Properties, PartialEq, Clone)]
 pub struct LegendRowProps {
-    pub color: &'static str,
+    pub color: AttrValue,
     pub label: &'static str,
     #[prop_or(false)]
     pub highlight: bool,
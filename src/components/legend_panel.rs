@@ -1,4 +1,6 @@
 use super::legend::LegendRow;
+use crate::model::EnemyKind;
+use crate::state::PaletteId;
 use yew::prelude::*;
 
 #[derive(Properties, PartialEq, Clone)]
@@ -11,6 +13,15 @@ pub struct LegendPanelProps {
     pub has_gold: bool,
     pub has_empty: bool,
     pub has_wall: bool,
+    #[prop_or_default]
+    pub has_magma: bool,
+    #[prop_or_default]
+    pub has_lava: bool,
+    #[prop_or_default]
+    pub has_healing: bool,
+    // Enemy kinds currently on the field
+    #[prop_or_default]
+    pub enemy_kinds: Vec<EnemyKind>,
     // Hover info / highlight flags
     pub hover_text: Option<String>,
     #[prop_or(false)]
@@ -29,20 +40,40 @@ pub struct LegendPanelProps {
     pub highlight_empty: bool,
     #[prop_or(false)]
     pub highlight_wall: bool,
+    // Sit above the minimap, which takes the corner
+    #[prop_or_default]
+    pub above_minimap: bool,
+    // Swatches match the board's palette
+    #[prop_or_default]
+    pub palette: PaletteId,
 }
 
 #[function_component]
 pub fn LegendPanel(props: &LegendPanelProps) -> Html {
-    html! {<div style="position:absolute; right:12px; bottom:12px; background:rgba(22,27,34,0.9); border:1px solid #30363d; border-radius:8px; padding:8px; min-width:170px;">
+    let pal = props.palette.palette();
+    let enemy_rows: Vec<Html> = props
+        .enemy_kinds
+        .iter()
+        .map(|&kind| {
+            let color = AttrValue::from(pal.enemy_color(kind));
+            html! { <LegendRow color={color} label={kind.name()}/> }
+        })
+        .collect();
+    let bottom = if props.above_minimap { 194 } else { 12 };
+    html! {<div style={format!("position:absolute; right:12px; bottom:{bottom}px; background:rgba(22,27,34,0.9); border:1px solid #30363d; border-radius:8px; padding:8px; min-width:170px;")}>
         <div style="font-weight:600; margin-bottom:4px;">{"Legend"}</div>
         { if let Some(t) = &props.hover_text { html!{<div style="font-size:11px; color:#8b949e; margin-bottom:6px;">{t}</div>} } else { html!{} } }
-        { if props.has_start { html!{ <LegendRow color="#58a6ff" label="Start" highlight={props.highlight_start}/> } } else { html!{} } }
-        { if props.has_entrance { html!{ <LegendRow color="#2ea043" label="Entrance" highlight={props.highlight_entrance}/> } } else { html!{} } }
-        { if props.has_exit { html!{ <LegendRow color="#f0883e" label="Exit" highlight={props.highlight_exit}/> } } else { html!{} } }
-        { if props.has_indestructible { html!{ <LegendRow color="#3c4454" label="Indestructible" highlight={props.highlight_indestructible}/> } } else { html!{} } }
-        { if props.has_basic { html!{ <LegendRow color="#1d2430" label="Rock" highlight={props.highlight_basic}/> } } else { html!{} } }
-        { if props.has_gold { html!{ <LegendRow color="#4d3b1f" label="Gold Rock" highlight={props.highlight_gold}/> } } else { html!{} } }
-        { if props.has_empty { html!{ <LegendRow color="#082235" label="Path" highlight={props.highlight_empty}/> } } else { html!{} } }
-        { if props.has_wall { html!{ <LegendRow color="#2a2f38" label="Wall" highlight={props.highlight_wall}/> } } else { html!{} } }
+        { if props.has_start { html!{ <LegendRow color={pal.start} label="Start" highlight={props.highlight_start}/> } } else { html!{} } }
+        { if props.has_entrance { html!{ <LegendRow color={pal.entrance} label="Entrance" highlight={props.highlight_entrance}/> } } else { html!{} } }
+        { if props.has_exit { html!{ <LegendRow color={pal.exit} label="Exit" highlight={props.highlight_exit}/> } } else { html!{} } }
+        { if props.has_indestructible { html!{ <LegendRow color={pal.indestructible} label="Indestructible" highlight={props.highlight_indestructible}/> } } else { html!{} } }
+        { if props.has_basic { html!{ <LegendRow color={pal.rock} label="Rock" highlight={props.highlight_basic}/> } } else { html!{} } }
+        { if props.has_gold { html!{ <LegendRow color={pal.gold_rock} label="Gold Rock" highlight={props.highlight_gold}/> } } else { html!{} } }
+        { if props.has_magma { html!{ <LegendRow color={pal.magma_rock} label="Magma Rock"/> } } else { html!{} } }
+        { if props.has_healing { html!{ <LegendRow color={pal.boost_healing} label="Healing Rock"/> } } else { html!{} } }
+        { if props.has_empty { html!{ <LegendRow color={pal.path} label="Path" highlight={props.highlight_empty}/> } } else { html!{} } }
+        { if props.has_lava { html!{ <LegendRow color={pal.lava} label="Lava"/> } } else { html!{} } }
+        { if props.has_wall { html!{ <LegendRow color={pal.wall} label="Wall" highlight={props.highlight_wall}/> } } else { html!{} } }
+        { for enemy_rows }
     </div>}
 }
@@ -0,0 +1,160 @@
+use crate::model::{
+    generate_map_candidates, play_area_size_for_level, ArrowDir, GridSize, MapCandidate, ThumbCell,
+    UpgradeId, UpgradeState,
+};
+use wasm_bindgen::JsCast;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
+use yew::prelude::*;
+
+const CANDIDATE_COUNT: usize = 4;
+const THUMB_PX: u32 = 132;
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct MapPickerProps {
+    pub show: bool,
+    pub upgrade_state: UpgradeState,
+    pub on_pick: Callback<u64>,
+    pub on_cancel: Callback<()>,
+}
+
+// Same seeds all day; reroll steps past them
+fn daily_base_seed() -> u64 {
+    let days = (js_sys::Date::now() / 86_400_000.0).floor() as u64;
+    days.wrapping_mul(1000)
+}
+
+// Matches the run canvas tile palette
+fn thumb_color(cell: ThumbCell) -> &'static str {
+    match cell {
+        ThumbCell::Rock => "#1d2430",
+        ThumbCell::Boost => "#203a5a",
+        ThumbCell::Gold => "#d29922",
+        ThumbCell::Empty => "#082235",
+        ThumbCell::Indestructible => "#596273",
+        ThumbCell::Exit => "#f0883e",
+        ThumbCell::Entrance => "#2ea043",
+        ThumbCell::Start => "#58a6ff",
+    }
+}
+
+fn orientation_label(dir: Option<ArrowDir>) -> &'static str {
+    match dir {
+        Some(ArrowDir::Up) => "Entrance ↑",
+        Some(ArrowDir::Down) => "Entrance ↓",
+        Some(ArrowDir::Left) => "Entrance ←",
+        Some(ArrowDir::Right) => "Entrance →",
+        None => "No entrance",
+    }
+}
+
+fn draw_thumbnail(canvas: &HtmlCanvasElement, cand: &MapCandidate) {
+    let Some(ctx) = canvas
+        .get_context("2d")
+        .ok()
+        .flatten()
+        .and_then(|c| c.dyn_into::<CanvasRenderingContext2d>().ok())
+    else {
+        return;
+    };
+    let w = canvas.width() as f64;
+    let h = canvas.height() as f64;
+    ctx.set_fill_style_str("#0e1116");
+    ctx.fill_rect(0.0, 0.0, w, h);
+    if cand.thumb_width == 0 || cand.thumb_height == 0 {
+        return;
+    }
+    let cell = (w / cand.thumb_width as f64).min(h / cand.thumb_height as f64);
+    let ox = (w - cell * cand.thumb_width as f64) * 0.5;
+    let oy = (h - cell * cand.thumb_height as f64) * 0.5;
+    for y in 0..cand.thumb_height {
+        for x in 0..cand.thumb_width {
+            let c = cand.thumb[(y * cand.thumb_width + x) as usize];
+            ctx.set_fill_style_str(thumb_color(c));
+            ctx.fill_rect(ox + x as f64 * cell, oy + y as f64 * cell, cell, cell);
+        }
+    }
+}
+
+#[function_component]
+pub fn MapPicker(props: &MapPickerProps) -> Html {
+    // Hooks run on every render; only the markup depends on `show`
+    let base_seed = use_state(daily_base_seed);
+    let canvas_refs = use_state(|| {
+        (0..CANDIDATE_COUNT)
+            .map(|_| NodeRef::default())
+            .collect::<Vec<_>>()
+    });
+    let size = play_area_size_for_level(props.upgrade_state.level(UpgradeId::PlayAreaSize));
+    let candidates = use_memo(
+        (props.show, *base_seed, size, props.upgrade_state.clone()),
+        |(show, seed, size, ups)| {
+            if !*show {
+                return Vec::new();
+            }
+            generate_map_candidates(
+                GridSize {
+                    width: *size,
+                    height: *size,
+                },
+                ups,
+                *seed,
+                CANDIDATE_COUNT,
+            )
+        },
+    );
+    {
+        let canvas_refs = canvas_refs.clone();
+        use_effect_with(candidates.clone(), move |cands| {
+            for (c, r) in cands.iter().zip(canvas_refs.iter()) {
+                if let Some(canvas) = r.cast::<HtmlCanvasElement>() {
+                    draw_thumbnail(&canvas, c);
+                }
+            }
+            || ()
+        });
+    }
+
+    let reroll_cb = {
+        let base_seed = base_seed.clone();
+        Callback::from(move |_| {
+            base_seed.set(base_seed.wrapping_add(CANDIDATE_COUNT as u64));
+        })
+    };
+    let cancel_cb = {
+        let cb = props.on_cancel.clone();
+        Callback::from(move |_| cb.emit(()))
+    };
+
+    if !props.show {
+        return html! {};
+    }
+    html! {<div style="position:absolute; inset:0; display:flex; align-items:center; justify-content:center; background:rgba(0,0,0,0.55); z-index:50;">
+        <div style="background:#161b22; border:1px solid #30363d; border-radius:12px; padding:16px 20px; display:flex; flex-direction:column; gap:14px;">
+            <div style="display:flex; justify-content:space-between; align-items:center; gap:16px;">
+                <h3 style="margin:0; font-size:18px;">{"Choose a Map"}</h3>
+                <div style="display:flex; gap:8px;">
+                    <button onclick={reroll_cb} style="padding:4px 8px;">{"Reroll"}</button>
+                    <button onclick={cancel_cb} style="padding:4px 8px;">{"Cancel"}</button>
+                </div>
+            </div>
+            <div style="display:grid; grid-template-columns:repeat(2, auto); gap:12px;">
+                { for candidates.iter().zip(canvas_refs.iter()).map(|(c, r)| {
+                    let seed = c.seed;
+                    let on_pick = props.on_pick.clone();
+                    let pick_cb = Callback::from(move |_| on_pick.emit(seed));
+                    html! {
+                        <div onclick={pick_cb} style="cursor:pointer; background:#0e1116; border:1px solid #30363d; border-radius:8px; padding:8px; display:flex; flex-direction:column; gap:4px; align-items:center;">
+                            <canvas ref={r.clone()} width={THUMB_PX.to_string()} height={THUMB_PX.to_string()} style="border-radius:4px;"></canvas>
+                            <div style="font-size:12px; opacity:0.85;">{ format!("Seed {}", c.seed) }</div>
+                            <div style="font-size:12px;">
+                                <span style="color:#d29922;">{ format!("Gold {}", c.summary.gold_count) }</span>
+                                { if c.summary.boost_count > 0 { format!(" · Boosts {}", c.summary.boost_count) } else { String::new() } }
+                            </div>
+                            <div style="font-size:12px; opacity:0.75;">{ orientation_label(c.summary.orientation) }</div>
+                        </div>
+                    }
+                }) }
+            </div>
+        </div>
+    </div>}
+}
@@ -0,0 +1,206 @@
+use crate::model::{GridSize, RunState, Shared, Tile};
+use crate::state::theme::Palette;
+use crate::state::{terrain_pixels, Camera, MinimapFit, MINIMAP_PX};
+use std::cell::Cell;
+use std::rc::Rc;
+use wasm_bindgen::{prelude::*, Clamped, JsCast};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, ImageData, MouseEvent};
+use yew::prelude::*;
+
+// The minimap's terrain image, rebuilt only when the board changes; the path, towers, enemies
+// and viewport box go over it every frame. Lives in RunView and is drawn from its frame loop.
+#[derive(Default)]
+pub struct MinimapLayer {
+    terrain: Option<(u64, Shared<Vec<Tile>>, &'static Palette)>,
+    canvas: Option<HtmlCanvasElement>,
+}
+
+impl MinimapLayer {
+    // One pixel per tile, redrawn when the run, its tiles or the palette changed since the last
+    // frame
+    fn terrain(&mut self, rs: &RunState, pal: &'static Palette) -> Option<&HtmlCanvasElement> {
+        let fresh = self.terrain.as_ref().is_some_and(|(run_id, tiles, drawn)| {
+            *run_id == rs.run_id && Shared::ptr_eq(tiles, &rs.tiles) && std::ptr::eq(*drawn, pal)
+        });
+        if self.canvas.is_none() {
+            self.canvas = web_sys::window()?
+                .document()?
+                .create_element("canvas")
+                .ok()?
+                .dyn_into::<HtmlCanvasElement>()
+                .ok();
+        }
+        let canvas = self.canvas.as_ref()?;
+        if !fresh {
+            let gs = rs.grid_size;
+            canvas.set_width(gs.width);
+            canvas.set_height(gs.height);
+            let ctx = canvas
+                .get_context("2d")
+                .ok()??
+                .dyn_into::<CanvasRenderingContext2d>()
+                .ok()?;
+            let pixels = terrain_pixels(rs, pal);
+            let image =
+                ImageData::new_with_u8_clamped_array_and_sh(Clamped(&pixels), gs.width, gs.height)
+                    .ok()?;
+            ctx.put_image_data(&image, 0.0, 0.0).ok()?;
+            self.terrain = Some((rs.run_id, rs.tiles.clone(), pal));
+        }
+        Some(canvas)
+    }
+
+    // Draws the minimap for `rs` in `pal` into `target`, with the box `cam` shows on a `view`
+    // canvas
+    pub fn draw(
+        &mut self,
+        target: &HtmlCanvasElement,
+        rs: &RunState,
+        pal: &'static Palette,
+        cam: &Camera,
+        view: (f64, f64),
+    ) -> Option<()> {
+        let ctx = target
+            .get_context("2d")
+            .ok()??
+            .dyn_into::<CanvasRenderingContext2d>()
+            .ok()?;
+        let gs = rs.grid_size;
+        let fit = MinimapFit::of(gs);
+        ctx.set_transform(1.0, 0.0, 0.0, 1.0, 0.0, 0.0).ok();
+        ctx.set_fill_style_str("#0e1116");
+        ctx.fill_rect(0.0, 0.0, MINIMAP_PX, MINIMAP_PX);
+        ctx.set_image_smoothing_enabled(false);
+        if let Some(terrain) = self.terrain(rs, pal) {
+            ctx.draw_image_with_html_canvas_element_and_dw_and_dh(
+                terrain,
+                fit.left,
+                fit.top,
+                gs.width as f64 * fit.scale,
+                gs.height as f64 * fit.scale,
+            )
+            .ok();
+        }
+        if rs.path_loop.len() >= 2 {
+            ctx.set_stroke_style_str("rgba(88,166,255,0.85)");
+            ctx.set_line_width(1.0);
+            ctx.begin_path();
+            for (i, p) in rs.path_loop.iter().enumerate() {
+                let (x, y) = fit.to_minimap(p.x as f64 + 0.5, p.y as f64 + 0.5);
+                if i == 0 {
+                    ctx.move_to(x, y);
+                } else {
+                    ctx.line_to(x, y);
+                }
+            }
+            ctx.stroke();
+        }
+        // Dots stay visible however small the tiles get
+        let dot = fit.scale.max(2.0);
+        ctx.set_fill_style_str("#2ea043");
+        for tw in &rs.towers {
+            let (x, y) = fit.to_minimap(tw.x as f64 + 0.5, tw.y as f64 + 0.5);
+            ctx.fill_rect(x - dot / 2.0, y - dot / 2.0, dot, dot);
+        }
+        ctx.set_fill_style_str("#f85149");
+        for e in &rs.enemies {
+            let (x, y) = fit.to_minimap(e.x, e.y);
+            ctx.fill_rect(x - dot / 2.0, y - dot / 2.0, dot, dot);
+        }
+        let (x, y, w, h) = fit.viewport(cam, view);
+        ctx.set_stroke_style_str("#e6edf3");
+        ctx.stroke_rect(x + 0.5, y + 0.5, w, h);
+        Some(())
+    }
+}
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct MinimapProps {
+    // RunView draws into this canvas (see MinimapLayer)
+    pub canvas_ref: NodeRef,
+    pub grid: GridSize,
+    // Tile position to centre the main camera on, sent on click and while dragging
+    pub on_jump: Callback<(f64, f64)>,
+}
+
+#[function_component]
+pub fn Minimap(props: &MinimapProps) -> Html {
+    // Listeners are added once; they read the grid and callback of the latest render
+    let latest = use_mut_ref(|| (props.grid, props.on_jump.clone()));
+    *latest.borrow_mut() = (props.grid, props.on_jump.clone());
+    {
+        let canvas_ref = props.canvas_ref.clone();
+        use_effect_with((), move |_| {
+            let window = web_sys::window().expect("window");
+            let canvas: HtmlCanvasElement = canvas_ref.cast().expect("minimap canvas");
+            let held = Rc::new(Cell::new(false));
+            let jump = {
+                let canvas = canvas.clone();
+                move |e: &MouseEvent| {
+                    let rect = canvas.get_bounding_client_rect();
+                    let (grid, on_jump) = latest.borrow().clone();
+                    on_jump.emit(MinimapFit::of(grid).to_world(
+                        e.client_x() as f64 - rect.left(),
+                        e.client_y() as f64 - rect.top(),
+                    ));
+                }
+            };
+            let mousedown_cb = {
+                let held = held.clone();
+                let jump = jump.clone();
+                Closure::wrap(Box::new(move |e: MouseEvent| {
+                    if e.button() == 0 {
+                        e.prevent_default();
+                        held.set(true);
+                        jump(&e);
+                    }
+                }) as Box<dyn FnMut(_)>)
+            };
+            let mousemove_cb = {
+                let held = held.clone();
+                Closure::wrap(Box::new(move |e: MouseEvent| {
+                    if held.get() {
+                        jump(&e);
+                    }
+                }) as Box<dyn FnMut(_)>)
+            };
+            let mouseup_cb = Closure::wrap(Box::new(move |_e: MouseEvent| {
+                held.set(false);
+            }) as Box<dyn FnMut(_)>);
+            canvas
+                .add_event_listener_with_callback(
+                    "mousedown",
+                    mousedown_cb.as_ref().unchecked_ref(),
+                )
+                .unwrap();
+            window
+                .add_event_listener_with_callback(
+                    "mousemove",
+                    mousemove_cb.as_ref().unchecked_ref(),
+                )
+                .unwrap();
+            window
+                .add_event_listener_with_callback("mouseup", mouseup_cb.as_ref().unchecked_ref())
+                .unwrap();
+            move || {
+                let _ = canvas.remove_event_listener_with_callback(
+                    "mousedown",
+                    mousedown_cb.as_ref().unchecked_ref(),
+                );
+                let _ = window.remove_event_listener_with_callback(
+                    "mousemove",
+                    mousemove_cb.as_ref().unchecked_ref(),
+                );
+                let _ = window.remove_event_listener_with_callback(
+                    "mouseup",
+                    mouseup_cb.as_ref().unchecked_ref(),
+                );
+            }
+        });
+    }
+    let size = MINIMAP_PX as u32;
+    html! {<div style="position:absolute; right:12px; bottom:12px; background:rgba(22,27,34,0.9); border:1px solid #30363d; border-radius:8px; padding:6px; line-height:0;">
+        <canvas ref={props.canvas_ref.clone()} width={size.to_string()} height={size.to_string()}
+            style={format!("display:block; width:{size}px; height:{size}px; cursor:crosshair;")}></canvas>
+    </div>}
+}
@@ -1,17 +1,30 @@
+pub mod achievement_toast;
+pub mod achievements_view;
 pub mod app;
+pub mod board_render;
 pub mod camera_controls;
 pub mod controls_panel;
 pub mod game_over_overlay;
+pub mod history_view;
+pub mod hud_frame;
 pub mod intro_overlay;
 pub mod legend;
 pub mod legend_panel;
+pub mod map_picker;
+pub mod minimap;
+pub mod perk_cards;
 pub mod run_view;
+pub mod scenario_overlay;
+pub mod scenario_picker;
 pub mod secondary_stats_panel;
 pub mod settings_modal;
+pub mod stamp_palette;
 pub mod stats_panel;
 pub mod tile_info_panel;
 pub mod time_display;
+pub mod tower_info_panel;
 pub mod tower_panel;
+pub mod tutorial_card;
 pub mod upgrade_summary_panel;
 pub mod upgrades_view;
 pub use app::App;
@@ -0,0 +1,43 @@
+use crate::model::Perk;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct PerkCardsProps {
+    pub show: bool,
+    pub perks: Vec<Perk>,
+    pub on_pick: Callback<Perk>,
+}
+
+fn perk_icon(perk: Perk) -> &'static str {
+    match perk {
+        Perk::FreeTower => "🗼",
+        Perk::BonusGold => "💰",
+        Perk::QuickPicks => "⛏",
+        Perk::LeakForgiven => "🛡",
+    }
+}
+
+// Pre-run loadout: one card may be taken before the first mine or tower starts the run
+#[function_component]
+pub fn PerkCards(props: &PerkCardsProps) -> Html {
+    if !props.show || props.perks.is_empty() {
+        return html! {};
+    }
+    html! {
+        <div style="position:absolute; bottom:16px; left:50%; transform:translateX(-50%); background:rgba(22,27,34,0.95); border:1px solid #30363d; border-radius:10px; padding:10px 14px; display:flex; flex-direction:column; gap:8px; z-index:30;">
+            <div style="font-size:13px; color:#8b949e; text-align:center;">{"Pick a starting perk (optional)"}</div>
+            <div style="display:flex; gap:10px;">
+                { for props.perks.iter().map(|&perk| {
+                    let on_pick = props.on_pick.clone();
+                    let pick_cb = Callback::from(move |_| on_pick.emit(perk));
+                    html! {
+                        <div onclick={pick_cb} style="cursor:pointer; width:140px; background:#0e1116; border:1px solid #30363d; border-radius:8px; padding:8px 10px; display:flex; flex-direction:column; gap:4px;">
+                            <div style="font-weight:600; font-size:14px;">{ format!("{} {}", perk_icon(perk), perk.name()) }</div>
+                            <div style="font-size:12px; opacity:0.85;">{ perk.description() }</div>
+                        </div>
+                    }
+                }) }
+            </div>
+        </div>
+    }
+}
@@ -2,28 +2,122 @@ use std::cell::RefCell;
 use std::rc::Rc;
 use wasm_bindgen::closure::Closure;
 use wasm_bindgen::JsCast;
-use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, HtmlElement, TouchEvent};
+use web_sys::{
+    CanvasRenderingContext2d, HtmlCanvasElement, HtmlElement, HtmlInputElement, TouchEvent,
+};
 use yew::prelude::*;
 
-use crate::model::{self, RunAction, RunState, TowerKind, UpgradeState};
-use crate::state::{compute_interactable_mask, Camera, Mining, TouchState};
-use crate::util::clog;
+use crate::audio;
+use crate::debug_dump;
+use crate::model::{
+    self, CatchUpMode, Feature, RouteMode, RunAction, RunState, TowerKind, UpgradeState,
+};
+use crate::persistence::{self, LocalStorage, Storage};
+use crate::save_transfer::SaveData;
+use crate::state::prospector::start_tile;
+use crate::state::{
+    advance_tutorial, bookmark_key, compute_interactable_mask, cues_between, edge_scroll_dir,
+    hud_layout::hud_layout, is_text_entry, key_hints, music_tempo, speed_key, tutorial_target,
+    AudioSettings, BookmarkKey, Bookmarks, Camera, CatchUp, EnemyMotion, ExclusionBrush,
+    FocusSource, FocusTile, FrameInputs, GamepadDpad, HotkeyAction, Hotkeys, HudLayoutSettings,
+    Mining, MusicDirector, PaletteId, PanDir, ProspectReadout, RenderScheduler, RewindBuffer,
+    StampCursor, StampPreview, ThemeSettings, TouchState, TowerTotals, TutorialState, WallDrag,
+    WallPreview, BOOKMARK_SLOTS, GAMEPAD_DPAD,
+};
+use crate::util::{copy_to_clipboard, download_text, log, recent_logs, set_console_level, Level};
+use crate::worker::{AnalysisClient, AnalysisKind, AnalysisResult, AnalysisWorker};
 // Replace direct legend row usage with modular components
 use super::{
-    camera_controls::CameraControls, controls_panel::ControlsPanel,
-    game_over_overlay::GameOverOverlay, intro_overlay::IntroOverlay, legend_panel::LegendPanel,
-    secondary_stats_panel::SecondaryStatsPanel, settings_modal::SettingsModal,
-    stats_panel::StatsPanel, tile_info_panel::TileInfoPanel, time_display::TimeDisplay,
+    board_render::{render_grid, BoardLayers, TerrainLayer},
+    camera_controls::CameraControls,
+    controls_panel::ControlsPanel,
+    game_over_overlay::{load_records, GameOverOverlay},
+    hud_frame::HudFrame,
+    intro_overlay::IntroOverlay,
+    legend_panel::LegendPanel,
+    map_picker::MapPicker,
+    minimap::{Minimap, MinimapLayer},
+    perk_cards::PerkCards,
+    scenario_overlay::{ScenarioObjective, ScenarioResult},
+    scenario_picker::ScenarioPicker,
+    secondary_stats_panel::SecondaryStatsPanel,
+    settings_modal::SettingsModal,
+    stamp_palette::StampPalette,
+    stats_panel::StatsPanel,
+    tile_info_panel::TileInfoPanel,
+    time_display::TimeDisplay,
+    tower_info_panel::TowerInfoPanel,
     tower_panel::TowerPanel,
+    tutorial_card::TutorialCard,
 };
 
+// Developer panel console thresholds, cycled in this order
+const CONSOLE_LEVELS: [Option<Level>; 5] = [
+    None,
+    Some(Level::Warn),
+    Some(Level::Info),
+    Some(Level::Debug),
+    Some(Level::Trace),
+];
+// Fixed sim step of the 16ms interval, also used by the developer single-step
+const SIM_TICK_DT: f64 = 0.016;
+// The Controls panel's frame step while paused
+const FRAME_STEP_SECS: f64 = 0.1;
+// How often the frame loop sweeps the cursor and focus tile for pickups
+const PICKUP_SWEEP_MS: f64 = 250.0;
+
+// GAMEPAD_DPAD buttons held on the first connected pad; all up when there is none
+fn gamepad_dpad(window: &web_sys::Window) -> [bool; 4] {
+    let mut pressed = [false; 4];
+    let Ok(pads) = window.navigator().get_gamepads() else {
+        return pressed;
+    };
+    let Some(pad) = pads
+        .iter()
+        .find_map(|p| p.dyn_into::<web_sys::Gamepad>().ok())
+    else {
+        return pressed;
+    };
+    let buttons = pad.buttons();
+    for (held, &(button, _)) in pressed.iter_mut().zip(GAMEPAD_DPAD.iter()) {
+        let button = buttons.get(button as u32);
+        *held = button
+            .dyn_into::<web_sys::GamepadButton>()
+            .is_ok_and(|b| b.pressed());
+    }
+    pressed
+}
+
+// Keeps part of the board on screen after the player pans or zooms
+fn clamp_camera(cam: &mut Camera, rs: &RunState, canvas: &HtmlCanvasElement) {
+    let gs = rs.grid_size;
+    cam.clamp_to_grid(
+        gs.width,
+        gs.height,
+        canvas.width() as f64,
+        canvas.height() as f64,
+        32.0,
+    );
+}
+
 #[derive(Properties, PartialEq, Clone)]
 pub struct RunViewProps {
     pub run_state: UseReducerHandle<RunState>,
     pub upgrade_state: UpgradeState,
+    // Minimized in the stats panel; edited on the upgrades screen
+    #[prop_or_default]
+    pub upgrade_queue: Vec<model::UpgradeId>,
     pub to_upgrades: Callback<()>,
+    #[prop_or_default]
+    pub to_history: Callback<()>,
+    // The run just over beat every run in the history
+    #[prop_or_default]
+    pub new_best: bool,
     pub restart_run: Callback<()>,
+    pub restart_run_with_seed: Callback<u64>,
     pub hard_reset: Callback<()>,
+    #[prop_or_default]
+    pub import_save: Callback<SaveData>,
 }
 
 #[function_component(RunView)]
@@ -55,8 +149,137 @@ pub fn run_view(props: &RunViewProps) -> Html {
         true // default ON
     });
     let show_damage_numbers_flag = use_mut_ref(|| true);
+    let show_health_bars = use_state(|| {
+        LocalStorage::open()
+            .and_then(|store| store.get("md_setting_show_health_bars"))
+            .is_none_or(|v| !(v == "0" || v == "false")) // default ON
+    });
+    let show_health_bars_flag = use_mut_ref(|| true);
+    let show_tile_labels = use_state(|| {
+        LocalStorage::open()
+            .and_then(|store| store.get("md_setting_tile_labels"))
+            .is_none_or(|v| !(v == "0" || v == "false")) // default ON; only visible when zoomed far in anyway
+    });
+    let show_tile_labels_flag = use_mut_ref(|| true);
+    // Prospector overlay: gold markers near the mining frontier (hotkey P)
+    let prospector = use_state(|| {
+        LocalStorage::open()
+            .and_then(|store| store.get("md_setting_prospector"))
+            .is_some_and(|v| v == "1" || v == "true")
+    });
+    let prospector_flag = use_mut_ref(|| false);
+    // Fewer canvas effects: no splash bursts, plain dots instead of overlay glyphs
+    let reduced_effects = use_state(|| {
+        LocalStorage::open()
+            .and_then(|store| store.get("md_setting_reduced_effects"))
+            .is_some_and(|v| v == "1" || v == "true")
+    });
+    let reduced_effects_flag = use_mut_ref(|| false);
+    // Scroll the board while the cursor rests at the canvas edge; off by default
+    let edge_scroll = use_state(|| {
+        LocalStorage::open()
+            .and_then(|store| store.get("md_setting_edge_scroll"))
+            .is_some_and(|v| v == "1" || v == "true")
+    });
+    let edge_scroll_flag = use_mut_ref(|| false);
+    // Whole-grid overview in the corner; on by default
+    let minimap = use_state(|| {
+        LocalStorage::open()
+            .and_then(|store| store.get("md_setting_minimap"))
+            .is_none_or(|v| v == "1" || v == "true")
+    });
+    let minimap_ref = use_node_ref();
+    let minimap_layer = use_mut_ref(MinimapLayer::default);
+    // Heartbeat cue while life is low; off by default
+    let heartbeat = use_state(|| {
+        LocalStorage::open()
+            .and_then(|store| store.get("md_setting_heartbeat"))
+            .is_some_and(|v| v == "1" || v == "true")
+    });
+    // Pause on a hidden tab instead of catching up on return; off by default
+    let pause_when_hidden = use_state(|| {
+        LocalStorage::open()
+            .and_then(|store| store.get("md_setting_pause_hidden"))
+            .is_some_and(|v| v == "1" || v == "true")
+    });
+    let pause_when_hidden_flag = use_mut_ref(|| false);
+    // Replay up to five minutes of hidden time instead of the capped few seconds; off by default
+    let long_catch_up = use_state(|| {
+        LocalStorage::open()
+            .and_then(|store| store.get("md_setting_long_catch_up"))
+            .is_some_and(|v| v == "1" || v == "true")
+    });
+    let catch_up_mode = use_mut_ref(CatchUpMode::default);
+    // Run whose low-life tip was dismissed or followed; the tip shows once per run
+    let low_life_tip_done = use_state(|| None::<u64>);
     let show_debug = use_state(|| false);
     let show_debug_flag = use_mut_ref(|| false);
+    // Developer rewind snapshots; only filled while the developer panel is open
+    let rewind = use_mut_ref(RewindBuffer::default);
+    // Total DPS and loop coverage for the stats panel, cached per version
+    let tower_totals = use_mut_ref(TowerTotals::default);
+    let prospect_readout = use_mut_ref(ProspectReadout::default);
+    // Live damage heatmap (H); not persisted
+    let heatmap = use_state(|| false);
+    let heatmap_flag = use_mut_ref(|| false);
+    // Tower placement history overlay (developer panel); not persisted
+    let tower_history = use_state(|| false);
+    let tower_history_flag = use_mut_ref(|| false);
+    // HUD layout thresholds, and the container size they're applied to (kept by the resize hook)
+    let hud_settings = use_state(|| {
+        LocalStorage::open()
+            .and_then(|store| store.get("md_setting_hud_layout"))
+            .and_then(|v| serde_json::from_str::<HudLayoutSettings>(&v).ok())
+            .unwrap_or_default()
+    });
+    let viewport = use_state_eq(|| (0.0f64, 0.0f64));
+    // Board palette and HUD scale; the draw closure reads the palette from the flag
+    let theme = use_state(|| {
+        LocalStorage::open()
+            .and_then(|store| store.get("md_setting_theme"))
+            .and_then(|v| serde_json::from_str::<ThemeSettings>(&v).ok())
+            .unwrap_or_default()
+    });
+    let palette_flag = use_mut_ref(PaletteId::default);
+    // First-run tutorial progress; the draw closure marks the current step's tile from the flag
+    let tutorial = use_state(|| {
+        LocalStorage::open()
+            .and_then(|store| store.get("md_tutorial"))
+            .and_then(|v| serde_json::from_str::<TutorialState>(&v).ok())
+            .unwrap_or_default()
+    });
+    let tutorial_flag = use_mut_ref(TutorialState::default);
+    // Set by the draw while the tutorial arrow bobs, so idle frames keep drawing it
+    let tutorial_bobbing = use_mut_ref(|| false);
+    // Rebindable hotkeys; the keydown closure reads the flag so a rebind applies at once
+    let hotkeys = use_state(|| {
+        LocalStorage::open()
+            .and_then(|store| store.get("md_hotkeys"))
+            .map(|v| Hotkeys::from_json(&v))
+            .unwrap_or_default()
+    });
+    let hotkeys_flag = use_mut_ref(Hotkeys::default);
+    // Music and effect volumes, and mute
+    let audio_settings = use_state(|| {
+        LocalStorage::open()
+            .and_then(|store| store.get("md_setting_audio"))
+            .and_then(|v| serde_json::from_str::<AudioSettings>(&v).ok())
+            .unwrap_or_default()
+    });
+    // Eased game intensity driving the background music
+    let music = use_mut_ref(MusicDirector::default);
+    // Last run snapshot the sound cues were read against
+    let heard_run = use_mut_ref(|| None::<UseReducerHandle<RunState>>);
+    // Developer console threshold, as an index into CONSOLE_LEVELS
+    let console_level = use_state(|| {
+        LocalStorage::open()
+            .and_then(|store| store.get("md_setting_log_level"))
+            .map_or(0, |v| {
+                v.parse::<usize>()
+                    .unwrap_or(0)
+                    .min(CONSOLE_LEVELS.len() - 1)
+            })
+    });
     // new: show secondary stats setting
     let show_secondary_stats = use_state(|| {
         if let Some(win) = web_sys::window() {
@@ -68,12 +291,82 @@ pub fn run_view(props: &RunViewProps) -> Html {
         }
         true
     });
+    let map_picker_enabled = use_state(|| {
+        LocalStorage::open()
+            .and_then(|store| store.get("md_setting_map_picker"))
+            .is_some_and(|v| v == "1" || v == "true")
+    });
+    let show_map_picker = use_state(|| false);
+    let show_scenario_picker = use_state(|| false);
+    // "I've played before": skip the progressive unlocks of the first runs
+    let skip_progression = use_state(|| {
+        LocalStorage::open()
+            .and_then(|store| store.get("md_setting_skip_progression"))
+            .is_some_and(|v| v == "1" || v == "true")
+    });
+    // Hardcore siege modifier, applied to each run before it starts
+    let siege = use_state(|| {
+        LocalStorage::open()
+            .and_then(|store| store.get("md_setting_siege"))
+            .is_some_and(|v| v == "1" || v == "true")
+    });
+    // End route instead of the loop, applied to each run before it starts
+    let end_route = use_state(|| {
+        LocalStorage::open()
+            .and_then(|store| store.get("md_setting_end_route"))
+            .is_some_and(|v| v == "1")
+    });
+    // Pacing hints for the first runs
+    let hints = use_state(|| {
+        LocalStorage::open()
+            .and_then(|store| store.get("md_setting_hints"))
+            .is_none_or(|v| !(v == "0" || v == "false")) // default ON; the reducer stops them after the early runs
+    });
+    // Kill rewards drop as pickups to sweep up with the cursor
+    let manual_collection = use_state(|| {
+        LocalStorage::open()
+            .and_then(|store| store.get("md_setting_manual_collection"))
+            .is_some_and(|v| v == "1" || v == "true")
+    });
+    // Pointer position in world tiles while it is over the board
+    let cursor_world = use_mut_ref(|| None::<(f64, f64)>);
     let open_settings = use_state(|| false);
+    // Touch-first screens get no key hint bar
+    let coarse_pointer = use_state(|| {
+        web_sys::window()
+            .and_then(|win| win.match_media("(pointer: coarse)").ok().flatten())
+            .is_some_and(|q| q.matches())
+    });
     let touch_state = use_mut_ref(|| TouchState::default());
     let tower_feedback = use_state(|| String::new());
-    let hover_tile = use_mut_ref(|| (-1_i32, -1_i32));
+    let focus_tile = use_mut_ref(FocusTile::default);
+    let tower_kind = use_state(|| model::TowerKind::Basic);
     let selected_tower_kind = use_mut_ref(|| model::TowerKind::Basic);
-    let hover_tile_effect = hover_tile.clone(); // clone for effects to avoid moving original
+    let wall_preview = use_mut_ref(WallPreview::default);
+    let wall_drag = use_mut_ref(WallDrag::default);
+    let catch_up = use_mut_ref(CatchUp::default);
+    let exclusion_brush = use_mut_ref(ExclusionBrush::default);
+    let stamp_cursor = use_state(StampCursor::default);
+    let stamp_cursor_flag = use_mut_ref(StampCursor::default);
+    let stamp_preview = use_mut_ref(StampPreview::default);
+    // Tower clicked for the info panel (Esc or a click elsewhere lets go)
+    let selected_tower = use_state(|| Option::<(u32, u32)>::None);
+    let selected_tower_flag = use_mut_ref(|| Option::<(u32, u32)>::None);
+    // Tower picked up with the info panel's Move, waiting for the click that sets it down
+    let moving_tower = use_state(|| Option::<(u32, u32)>::None);
+    let moving_tower_flag = use_mut_ref(|| Option::<(u32, u32)>::None);
+    // Camera bookmarks for this run (Ctrl+1..4 saves, Alt+1..4 recalls)
+    let bookmarks = use_state(Bookmarks::default);
+    let bookmarks_flag = use_mut_ref(Bookmarks::default);
+    let render_scheduler = use_mut_ref(RenderScheduler::default);
+    let terrain_layer = use_mut_ref(TerrainLayer::default);
+    // Where enemies stood a sim step ago, so frames between steps draw them partway along
+    let enemy_motion = use_mut_ref(|| EnemyMotion::new(SIM_TICK_DT * 1000.0));
+    let analysis_client = use_mut_ref(AnalysisClient::default);
+    let suggested_spots = use_mut_ref(Vec::<(u32, u32)>::new);
+    let toast = use_state(|| Option::<String>::None);
+    let debug_import_ref = use_node_ref();
+    let focus_tile_effect = focus_tile.clone(); // clone for effects to avoid moving original
     let tower_feedback_for_effect = tower_feedback.clone();
     // NEW: intro overlay visibility (persist across sessions)
     let show_intro = {
@@ -94,48 +387,450 @@ pub fn run_view(props: &RunViewProps) -> Html {
 
     // Effect: toggle path
     {
-        let draw_ref = draw_ref.clone();
+        let scheduler = render_scheduler.clone();
         let flag = *show_path;
         let show_path_flag_ref = show_path_flag.clone();
         use_effect_with(flag, move |_| {
             *show_path_flag_ref.borrow_mut() = flag;
-            if let Some(win) = web_sys::window() {
-                if let Ok(Some(store)) = win.local_storage() {
-                    let _ = store.set_item("md_setting_show_path", if flag { "1" } else { "0" });
+            if let Some(mut store) = LocalStorage::open() {
+                persistence::write(
+                    &mut store,
+                    "md_setting_show_path",
+                    if flag { "1" } else { "0" },
+                );
+            }
+            scheduler.borrow_mut().request_redraw();
+            || ()
+        });
+    }
+    // Effect: toggle tile labels
+    {
+        let scheduler = render_scheduler.clone();
+        let flag = *show_tile_labels;
+        let show_tile_labels_flag_ref = show_tile_labels_flag.clone();
+        use_effect_with(flag, move |_| {
+            *show_tile_labels_flag_ref.borrow_mut() = flag;
+            if let Some(mut store) = LocalStorage::open() {
+                persistence::write(
+                    &mut store,
+                    "md_setting_tile_labels",
+                    if flag { "1" } else { "0" },
+                );
+            }
+            scheduler.borrow_mut().request_redraw();
+            || ()
+        });
+    }
+    // Effect: toggle prospector overlay
+    {
+        let scheduler = render_scheduler.clone();
+        let flag = *prospector;
+        let prospector_flag_ref = prospector_flag.clone();
+        use_effect_with(flag, move |_| {
+            *prospector_flag_ref.borrow_mut() = flag;
+            if let Some(mut store) = LocalStorage::open() {
+                persistence::write(
+                    &mut store,
+                    "md_setting_prospector",
+                    if flag { "1" } else { "0" },
+                );
+            }
+            scheduler.borrow_mut().request_redraw();
+            || ()
+        });
+    }
+    // Effect: persist tutorial progress and hand it to the draw closure
+    {
+        let scheduler = render_scheduler.clone();
+        let progress = *tutorial;
+        let tutorial_flag = tutorial_flag.clone();
+        use_effect_with(progress, move |_| {
+            *tutorial_flag.borrow_mut() = progress;
+            let blob = serde_json::to_string(&progress).ok();
+            if let (Some(mut store), Some(v)) = (LocalStorage::open(), blob) {
+                persistence::write(&mut store, "md_tutorial", &v);
+            }
+            scheduler.borrow_mut().request_redraw();
+            || ()
+        });
+    }
+    // Effect: the tutorial moves on once the run shows the current step done
+    {
+        let tutorial = tutorial.clone();
+        let run_state = props.run_state.clone();
+        let stats = &props.run_state.stats;
+        let done = (
+            stats.blocks_mined,
+            stats.towers_built,
+            stats.enemies_killed,
+            stats.walls_built,
+        );
+        use_effect_with(done, move |_| {
+            let next = advance_tutorial(*tutorial, &run_state);
+            if next != *tutorial {
+                tutorial.set(next);
+            }
+            || ()
+        });
+    }
+    // Effect: persist the theme; a new palette redraws the cached terrain
+    {
+        let scheduler = render_scheduler.clone();
+        let settings = *theme;
+        let palette_flag = palette_flag.clone();
+        let terrain = terrain_layer.clone();
+        use_effect_with(settings, move |_| {
+            if *palette_flag.borrow() != settings.palette {
+                *palette_flag.borrow_mut() = settings.palette;
+                terrain.borrow_mut().invalidate();
+            }
+            let blob = serde_json::to_string(&settings).ok();
+            if let (Some(mut store), Some(v)) = (LocalStorage::open(), blob) {
+                persistence::write(&mut store, "md_setting_theme", &v);
+            }
+            scheduler.borrow_mut().request_redraw();
+            || ()
+        });
+    }
+    {
+        let settings = *hud_settings;
+        use_effect_with(settings, move |_| {
+            if let (Some(mut store), Ok(v)) =
+                (LocalStorage::open(), serde_json::to_string(&settings))
+            {
+                persistence::write(&mut store, "md_setting_hud_layout", &v);
+            }
+            || ()
+        });
+    }
+    {
+        let keys = (*hotkeys).clone();
+        let flag = hotkeys_flag.clone();
+        use_effect_with(keys, move |keys| {
+            *flag.borrow_mut() = keys.clone();
+            if let (Some(mut store), Ok(v)) = (LocalStorage::open(), serde_json::to_string(keys)) {
+                persistence::write(&mut store, "md_hotkeys", &v);
+            }
+            || ()
+        });
+    }
+    // Effect: stamp selection, mirrored for the event closures
+    {
+        let scheduler = render_scheduler.clone();
+        let cursor = *stamp_cursor;
+        let flag = stamp_cursor_flag.clone();
+        use_effect_with(cursor, move |_| {
+            *flag.borrow_mut() = cursor;
+            scheduler.borrow_mut().request_redraw();
+            || ()
+        });
+    }
+    // Effect: tower selection, mirrored for the event closures
+    {
+        let scheduler = render_scheduler.clone();
+        let selected = *selected_tower;
+        let flag = selected_tower_flag.clone();
+        use_effect_with(selected, move |_| {
+            *flag.borrow_mut() = selected;
+            scheduler.borrow_mut().request_redraw();
+            || ()
+        });
+    }
+    // Effect: pending tower move, mirrored for the event closures
+    {
+        let moving = *moving_tower;
+        let flag = moving_tower_flag.clone();
+        use_effect_with(moving, move |_| {
+            *flag.borrow_mut() = moving;
+            || ()
+        });
+    }
+    // Effect: camera bookmarks, mirrored for the event closures
+    {
+        let marks = (*bookmarks).clone();
+        let flag = bookmarks_flag.clone();
+        use_effect_with(marks, move |marks| {
+            *flag.borrow_mut() = marks.clone();
+            || ()
+        });
+    }
+    // Effect: tower kind selection, mirrored for the event closures
+    {
+        let scheduler = render_scheduler.clone();
+        let kind = (*tower_kind).clone();
+        let flag = selected_tower_kind.clone();
+        use_effect_with(kind, move |kind| {
+            *flag.borrow_mut() = kind.clone();
+            scheduler.borrow_mut().request_redraw();
+            || ()
+        });
+    }
+    // Effect: toggle reduced effects
+    {
+        let scheduler = render_scheduler.clone();
+        let flag = *reduced_effects;
+        let reduced_effects_flag_ref = reduced_effects_flag.clone();
+        use_effect_with(flag, move |_| {
+            *reduced_effects_flag_ref.borrow_mut() = flag;
+            if let Some(mut store) = LocalStorage::open() {
+                persistence::write(
+                    &mut store,
+                    "md_setting_reduced_effects",
+                    if flag { "1" } else { "0" },
+                );
+            }
+            scheduler.borrow_mut().request_redraw();
+            || ()
+        });
+    }
+    // Effect: persist edge scrolling; turning it off stops a scroll in progress
+    {
+        let flag = *edge_scroll;
+        let r = edge_scroll_flag.clone();
+        let camera = camera.clone();
+        use_effect_with(flag, move |_| {
+            *r.borrow_mut() = flag;
+            if !flag {
+                camera.borrow_mut().pan_edge = (0.0, 0.0);
+            }
+            if let Some(mut store) = LocalStorage::open() {
+                persistence::write(
+                    &mut store,
+                    "md_setting_edge_scroll",
+                    if flag { "1" } else { "0" },
+                );
+            }
+            || ()
+        });
+    }
+    // Effect: persist the minimap toggle; showing it needs a frame to fill the new canvas
+    {
+        let scheduler = render_scheduler.clone();
+        let flag = *minimap;
+        use_effect_with(flag, move |_| {
+            if let Some(mut store) = LocalStorage::open() {
+                persistence::write(
+                    &mut store,
+                    "md_setting_minimap",
+                    if flag { "1" } else { "0" },
+                );
+            }
+            scheduler.borrow_mut().request_redraw();
+            || ()
+        });
+    }
+    // Effect: persist the heartbeat toggle
+    {
+        let flag = *heartbeat;
+        use_effect_with(flag, move |_| {
+            if let Some(mut store) = LocalStorage::open() {
+                persistence::write(
+                    &mut store,
+                    "md_setting_heartbeat",
+                    if flag { "1" } else { "0" },
+                );
+            }
+            || ()
+        });
+    }
+    // Effect: persist the pause-when-hidden toggle
+    {
+        let flag = *pause_when_hidden;
+        let pause_when_hidden_flag = pause_when_hidden_flag.clone();
+        use_effect_with(flag, move |_| {
+            *pause_when_hidden_flag.borrow_mut() = flag;
+            if let Some(mut store) = LocalStorage::open() {
+                persistence::write(
+                    &mut store,
+                    "md_setting_pause_hidden",
+                    if flag { "1" } else { "0" },
+                );
+            }
+            || ()
+        });
+    }
+    // Effect: persist the long catch-up toggle
+    {
+        let flag = *long_catch_up;
+        let catch_up_mode = catch_up_mode.clone();
+        use_effect_with(flag, move |_| {
+            *catch_up_mode.borrow_mut() = if flag {
+                CatchUpMode::Long
+            } else {
+                CatchUpMode::Capped
+            };
+            if let Some(mut store) = LocalStorage::open() {
+                persistence::write(
+                    &mut store,
+                    "md_setting_long_catch_up",
+                    if flag { "1" } else { "0" },
+                );
+            }
+            || ()
+        });
+    }
+    // Effect: persist the audio settings and apply them to the mixer
+    {
+        let settings = *audio_settings;
+        use_effect_with(settings, move |_| {
+            let (music, sfx) = settings.gains();
+            audio::set_gains(music, sfx);
+            if let (Some(mut store), Ok(v)) =
+                (LocalStorage::open(), serde_json::to_string(&settings))
+            {
+                persistence::write(&mut store, "md_setting_audio", &v);
+            }
+            || ()
+        });
+    }
+    // Effect: schedule a second of music per sim second while the run is live; a paused or
+    // finished run lets the last notes ring out
+    {
+        let rs = props.run_state.clone();
+        let playing =
+            audio_settings.gains().0 > 0.0 && rs.started && !rs.is_paused && !rs.game_over;
+        let music = music.clone();
+        use_effect_with(
+            (playing, rs.stats.time_survived_secs),
+            move |(playing, _)| {
+                if *playing {
+                    let level = music.borrow_mut().tick(&rs);
+                    audio::play_music_second(level, music_tempo(level));
                 }
+                || ()
+            },
+        );
+    }
+    // Effect: the audio context may only start inside a user gesture, so the first click or key
+    // anywhere builds it
+    use_effect_with((), move |_| {
+        let window = web_sys::window();
+        let unlock_cb = Closure::wrap(Box::new(move |_e: web_sys::Event| {
+            audio::unlock();
+        }) as Box<dyn FnMut(_)>);
+        if let Some(win) = &window {
+            for event in ["pointerdown", "keydown"] {
+                let _ =
+                    win.add_event_listener_with_callback(event, unlock_cb.as_ref().unchecked_ref());
             }
-            if let Some(f) = &*draw_ref.borrow() {
-                f();
+        }
+        move || {
+            if let Some(win) = &window {
+                for event in ["pointerdown", "keydown"] {
+                    let _ = win.remove_event_listener_with_callback(
+                        event,
+                        unlock_cb.as_ref().unchecked_ref(),
+                    );
+                }
+            }
+        }
+    });
+    // Effect: sound cues for whatever changed since the last snapshot, the game-over sting
+    // among them
+    {
+        let heard_run = heard_run.clone();
+        let sfx_on = audio_settings.gains().1 > 0.0;
+        use_effect_with(props.run_state.clone(), move |rs| {
+            let prev = heard_run.borrow_mut().replace(rs.clone());
+            if let Some(prev) = prev.filter(|_| sfx_on) {
+                for cue in cues_between(&prev, rs) {
+                    audio::play_cue(cue);
+                }
             }
             || ()
         });
     }
+    // Effect: one heartbeat per sim second while the low-life warning is up
+    {
+        let rs = &*props.run_state;
+        let beating = *heartbeat && rs.low_life && rs.started && !rs.is_paused && !rs.game_over;
+        use_effect_with(
+            (beating, rs.stats.time_survived_secs),
+            move |(beating, _)| {
+                if *beating {
+                    audio::play_heartbeat();
+                }
+                || ()
+            },
+        );
+    }
     // Effect: toggle damage numbers
     {
-        let draw_ref = draw_ref.clone();
+        let scheduler = render_scheduler.clone();
         let flag = *show_damage_numbers;
         let show_damage_numbers_flag_ref = show_damage_numbers_flag.clone();
         use_effect_with(flag, move |_| {
             *show_damage_numbers_flag_ref.borrow_mut() = flag;
-            if let Some(win) = web_sys::window() {
-                if let Ok(Some(store)) = win.local_storage() {
-                    let _ = store.set_item(
-                        "md_setting_show_damage_numbers",
-                        if flag { "1" } else { "0" },
-                    );
-                }
+            if let Some(mut store) = LocalStorage::open() {
+                persistence::write(
+                    &mut store,
+                    "md_setting_show_damage_numbers",
+                    if flag { "1" } else { "0" },
+                );
             }
-            if let Some(f) = &*draw_ref.borrow() {
-                f();
+            scheduler.borrow_mut().request_redraw();
+            || ()
+        });
+    }
+    // Effect: toggle enemy health bars
+    {
+        let scheduler = render_scheduler.clone();
+        let flag = *show_health_bars;
+        let show_health_bars_flag_ref = show_health_bars_flag.clone();
+        use_effect_with(flag, move |_| {
+            *show_health_bars_flag_ref.borrow_mut() = flag;
+            if let Some(mut store) = LocalStorage::open() {
+                persistence::write(
+                    &mut store,
+                    "md_setting_show_health_bars",
+                    if flag { "1" } else { "0" },
+                );
             }
+            scheduler.borrow_mut().request_redraw();
             || ()
         });
     }
     {
         let flag = *show_debug;
         let r = show_debug_flag.clone();
+        let rewind = rewind.clone();
+        let scheduler = render_scheduler.clone();
         use_effect_with(flag, move |_| {
             *r.borrow_mut() = flag;
+            if !flag {
+                rewind.borrow_mut().clear();
+            }
+            scheduler.borrow_mut().request_redraw();
+            || ()
+        });
+    }
+    {
+        let flag = *heatmap;
+        let r = heatmap_flag.clone();
+        let scheduler = render_scheduler.clone();
+        use_effect_with(flag, move |_| {
+            *r.borrow_mut() = flag;
+            scheduler.borrow_mut().request_redraw();
+            || ()
+        });
+    }
+    {
+        let flag = *tower_history;
+        let r = tower_history_flag.clone();
+        let scheduler = render_scheduler.clone();
+        use_effect_with(flag, move |_| {
+            *r.borrow_mut() = flag;
+            scheduler.borrow_mut().request_redraw();
+            || ()
+        });
+    }
+    {
+        let idx = *console_level;
+        use_effect_with(idx, move |_| {
+            set_console_level(CONSOLE_LEVELS[idx]);
+            if let Some(mut store) = LocalStorage::open() {
+                persistence::write(&mut store, "md_setting_log_level", &idx.to_string());
+            }
             || ()
         });
     }
@@ -143,31 +838,215 @@ pub fn run_view(props: &RunViewProps) -> Html {
     {
         let flag = *show_secondary_stats;
         use_effect_with(flag, move |_| {
-            if let Some(win) = web_sys::window() {
-                if let Ok(Some(store)) = win.local_storage() {
-                    let _ = store.set_item(
-                        "md_setting_show_secondary_stats",
-                        if flag { "1" } else { "0" },
-                    );
-                }
+            if let Some(mut store) = LocalStorage::open() {
+                persistence::write(
+                    &mut store,
+                    "md_setting_show_secondary_stats",
+                    if flag { "1" } else { "0" },
+                );
             }
             || ()
         });
     }
+    // Effect: map picker setting persistence
+    {
+        let flag = *map_picker_enabled;
+        use_effect_with(flag, move |_| {
+            if let Some(mut store) = LocalStorage::open() {
+                persistence::write(
+                    &mut store,
+                    "md_setting_map_picker",
+                    if flag { "1" } else { "0" },
+                );
+            }
+            || ()
+        });
+    }
+    // Effect: skip progression setting persistence
+    {
+        let flag = *skip_progression;
+        use_effect_with(flag, move |_| {
+            if let Some(mut store) = LocalStorage::open() {
+                persistence::write(
+                    &mut store,
+                    "md_setting_skip_progression",
+                    if flag { "1" } else { "0" },
+                );
+            }
+            || ()
+        });
+    }
+    // Effect: siege setting persistence
+    {
+        let flag = *siege;
+        use_effect_with(flag, move |_| {
+            if let Some(mut store) = LocalStorage::open() {
+                persistence::write(&mut store, "md_setting_siege", if flag { "1" } else { "0" });
+            }
+            || ()
+        });
+    }
+    // Effect: fresh runs pick up the siege setting (the reducer ignores it once started)
+    {
+        let run_state = props.run_state.clone();
+        let on = *siege;
+        use_effect_with((props.run_state.run_id, on), move |_| {
+            if run_state.siege != on {
+                run_state.dispatch(RunAction::SetSiege { on });
+            }
+            || ()
+        });
+    }
+    // Effect: route setting persistence
+    {
+        let flag = *end_route;
+        use_effect_with(flag, move |_| {
+            if let Some(mut store) = LocalStorage::open() {
+                persistence::write(
+                    &mut store,
+                    "md_setting_end_route",
+                    if flag { "1" } else { "0" },
+                );
+            }
+            || ()
+        });
+    }
+    // Effect: fresh runs pick up the route setting (the reducer ignores it once started)
+    {
+        let run_state = props.run_state.clone();
+        let mode = if *end_route {
+            RouteMode::End
+        } else {
+            RouteMode::Loop
+        };
+        use_effect_with((props.run_state.run_id, mode), move |_| {
+            if run_state.route_mode != mode {
+                run_state.dispatch(RunAction::SetRouteMode { mode });
+            }
+            || ()
+        });
+    }
+    // Effect: hints setting persistence, applied to every run
+    {
+        let run_state = props.run_state.clone();
+        let on = *hints;
+        use_effect_with((props.run_state.run_id, on), move |_| {
+            if let Some(mut store) = LocalStorage::open() {
+                persistence::write(&mut store, "md_setting_hints", if on { "1" } else { "0" });
+            }
+            if run_state.hints != on {
+                run_state.dispatch(RunAction::SetHints { on });
+            }
+            || ()
+        });
+    }
+    // Effect: manual collection setting persistence, applied to every run
+    {
+        let run_state = props.run_state.clone();
+        let on = *manual_collection;
+        use_effect_with((props.run_state.run_id, on), move |_| {
+            if let Some(mut store) = LocalStorage::open() {
+                persistence::write(
+                    &mut store,
+                    "md_setting_manual_collection",
+                    if on { "1" } else { "0" },
+                );
+            }
+            if run_state.manual_collection != on {
+                run_state.dispatch(RunAction::SetManualCollection { on });
+            }
+            || ()
+        });
+    }
+    // Effect: tell the reducer which run this is (finished runs come from md_records)
+    {
+        let run_state = props.run_state.clone();
+        let skip = *skip_progression;
+        use_effect_with((props.run_state.run_id, skip), move |_| {
+            let run = if skip {
+                None
+            } else {
+                Some(load_records().total_runs + 1)
+            };
+            if run_state.progression_run != run {
+                run_state.dispatch(RunAction::SetProgression { run });
+            }
+            || ()
+        });
+    }
+    // Effect: announce streak increments and resets, then clear them so a repeat shows
+    {
+        let run_state = props.run_state.clone();
+        let toast = toast.clone();
+        use_effect_with(props.run_state.streak_notice.clone(), move |notice| {
+            if let Some(msg) = notice {
+                toast.set(Some(msg.clone()));
+                run_state.dispatch(RunAction::DismissNotices);
+            }
+            || ()
+        });
+    }
+    // Effect: announce towers lost to the siege, then clear the notice so a repeat shows
+    {
+        let run_state = props.run_state.clone();
+        let toast = toast.clone();
+        use_effect_with(props.run_state.siege_notice.clone(), move |notice| {
+            if let Some(msg) = notice {
+                toast.set(Some(msg.clone()));
+                run_state.dispatch(RunAction::DismissNotices);
+            }
+            || ()
+        });
+    }
+    // Effect: surface reducer-side feature lock rejections, then clear them so a repeat shows
+    {
+        let run_state = props.run_state.clone();
+        let tower_feedback = tower_feedback.clone();
+        use_effect_with(props.run_state.locked_notice.clone(), move |notice| {
+            if let Some(msg) = notice {
+                tower_feedback.set(msg.clone());
+                run_state.dispatch(RunAction::DismissNotices);
+            }
+            || ()
+        });
+    }
+    // Panic overlay: its debug bundle comes from whatever run the handle last held
+    {
+        let run_state_ref = run_state_ref.clone();
+        use_effect_with((), move |_| {
+            crate::crash_report::set_debug_source(move || {
+                let handle = run_state_ref.try_borrow().ok()?.clone();
+                Some(debug_dump::session_dump(
+                    &handle,
+                    &debug_dump::stored_upgrades(),
+                ))
+            });
+            || ()
+        });
+    }
     // Effect: update run handle each version
     {
         let run_state_ref = run_state_ref.clone();
         let current_handle = props.run_state.clone();
         let draw_ref_local = draw_ref.clone();
+        let show_debug_flag = show_debug_flag.clone();
+        let rewind = rewind.clone();
         let version = props.run_state.version;
         use_effect_with(version, move |_| {
             *run_state_ref.borrow_mut() = current_handle.clone();
+            crate::crash_report::record_snapshot(&current_handle);
+            if *show_debug_flag.borrow() {
+                rewind.borrow_mut().record(&current_handle);
+            }
             if let Some(i) = current_handle.last_mined_idx {
                 if i < current_handle.tiles.len() {
-                    clog(&format!(
-                        "Post-reducer: idx={} kind(now)={:?}",
-                        i, current_handle.tiles[i].kind
-                    ));
+                    log(
+                        Level::Trace,
+                        &format!(
+                            "Post-reducer: idx={} kind(now)={:?}",
+                            i, current_handle.tiles[i].kind
+                        ),
+                    );
                 }
             }
             if let Some(f) = &*draw_ref_local.borrow() {
@@ -183,11 +1062,44 @@ pub fn run_view(props: &RunViewProps) -> Html {
         let run_state = props.run_state.clone();
         let draw_ref_setup = draw_ref.clone();
         let mining_setup = mining.clone();
-        let hover_tile_effect_local = hover_tile_effect.clone();
+        let focus_tile_effect_local = focus_tile_effect.clone();
         let selected_tower_kind_effect = selected_tower_kind.clone();
+        let wall_preview_effect = wall_preview.clone();
+        let wall_drag_setup = wall_drag.clone();
         // Clone state handles so the originals remain usable in render scope
         let tower_feedback_clone = tower_feedback_for_effect.clone();
         let show_intro_clone = show_intro.clone();
+        let catch_up_setup = catch_up.clone();
+        let pause_when_hidden_flag = pause_when_hidden_flag.clone();
+        let catch_up_mode = catch_up_mode.clone();
+        let brush_setup = exclusion_brush.clone();
+        let toast_setup = toast.clone();
+        let show_debug_setup = show_debug.clone();
+        let scheduler_setup = render_scheduler.clone();
+        let terrain_setup = terrain_layer.clone();
+        let motion_setup = enemy_motion.clone();
+        let tutorial_setup = tutorial_flag.clone();
+        let tutorial_bobbing_setup = tutorial_bobbing.clone();
+        let analysis_client_setup = analysis_client.clone();
+        let spots_setup = suggested_spots.clone();
+        let prospector_setup = prospector.clone();
+        let heatmap_setup = heatmap.clone();
+        let viewport_setup = viewport.clone();
+        let stamp_cursor_setup = stamp_cursor_flag.clone();
+        let stamp_cursor_state = stamp_cursor.clone();
+        let stamp_preview_setup = stamp_preview.clone();
+        let selected_tower_setup = selected_tower_flag.clone();
+        let selected_tower_state = selected_tower.clone();
+        let moving_tower_setup = moving_tower_flag.clone();
+        let moving_tower_state = moving_tower.clone();
+        let tower_kind_setup = tower_kind.clone();
+        let bookmarks_setup = bookmarks.clone();
+        let bookmarks_flag_setup = bookmarks_flag.clone();
+        let minimap_ref_setup = minimap_ref.clone();
+        let minimap_layer_setup = minimap_layer.clone();
+        let cursor_world_setup = cursor_world.clone();
+        let hotkeys_setup = hotkeys_flag.clone();
+        let show_path_setup = show_path.clone();
         use_effect_with((), move |_| {
             // Use cloned handles inside effect
             let tower_feedback_handle = tower_feedback_clone.clone();
@@ -200,6 +1112,7 @@ pub fn run_view(props: &RunViewProps) -> Html {
                 let canvas = canvas.clone();
                 let document = document.clone();
                 let window = window.clone();
+                let viewport = viewport_setup.clone();
                 move || {
                     let nav_height: f64 = document
                         .get_element_by_id("top-bar")
@@ -219,6 +1132,7 @@ pub fn run_view(props: &RunViewProps) -> Html {
                         - nav_height;
                     canvas.set_width(width.max(0.0) as u32);
                     canvas.set_height(height.max(0.0) as u32);
+                    viewport.set((width.max(0.0), height.max(0.0)));
                 }
             };
             compute_and_apply_canvas_size();
@@ -256,10 +1170,30 @@ pub fn run_view(props: &RunViewProps) -> Html {
                 let mining = mining_setup.clone();
                 let show_path_flag = show_path_flag.clone();
                 let show_damage_numbers_flag = show_damage_numbers_flag.clone();
+                let show_health_bars_flag = show_health_bars_flag.clone();
+                let show_tile_labels_flag = show_tile_labels_flag.clone();
+                let prospector_flag = prospector_flag.clone();
+                let reduced_effects_flag = reduced_effects_flag.clone();
+                let palette_flag = palette_flag.clone();
                 let show_debug_flag = show_debug_flag.clone();
-                let hover_tile_draw = hover_tile_effect_local.clone();
+                let heatmap_flag = heatmap_flag.clone();
+                let tower_history_flag = tower_history_flag.clone();
+                let focus_tile_draw = focus_tile_effect_local.clone();
                 let tower_feedback_draw = tower_feedback_handle.clone();
                 let selected_tower_kind_draw = selected_tower_kind_handle.clone();
+                let wall_preview_draw = wall_preview_effect.clone();
+                let wall_drag_draw = wall_drag_setup.clone();
+                let scheduler_draw = scheduler_setup.clone();
+                let terrain_draw = terrain_setup.clone();
+                let motion_draw = motion_setup.clone();
+                let tutorial_draw = tutorial_setup.clone();
+                let tutorial_bobbing_draw = tutorial_bobbing_setup.clone();
+                let spots_draw = spots_setup.clone();
+                let stamp_cursor_draw = stamp_cursor_setup.clone();
+                let stamp_preview_draw = stamp_preview_setup.clone();
+                let selected_tower_draw = selected_tower_setup.clone();
+                let minimap_draw = minimap_ref_setup.clone();
+                let minimap_layer_draw = minimap_layer_setup.clone();
                 Rc::new(move || {
                     if !canvas.is_connected() {
                         return;
@@ -276,395 +1210,125 @@ pub fn run_view(props: &RunViewProps) -> Html {
                     let rs_handle = run_state_ref.borrow();
                     let rs = (**rs_handle).clone();
                     let show_path_on = *show_path_flag.borrow();
-                    let show_damage_nums_on = *show_damage_numbers_flag.borrow();
-                    let interact_mask = compute_interactable_mask(&rs);
-                    ctx.set_transform(1.0, 0.0, 0.0, 1.0, 0.0, 0.0).ok();
-                    ctx.set_fill_style_str("#0e1116");
-                    ctx.fill_rect(0.0, 0.0, w, h);
-                    ctx.set_transform(scale_px, 0.0, 0.0, scale_px, cam.offset_x, cam.offset_y)
-                        .ok();
+                    let now = js_sys::Date::now();
+                    scheduler_draw.borrow_mut().mark_drawn(
+                        rs.version,
+                        (cam.zoom, cam.offset_x, cam.offset_y),
+                        now,
+                    );
+                    let mut terrain = terrain_draw.borrow_mut();
+                    let mut motion = motion_draw.borrow_mut();
+                    motion.observe(&rs, now);
+                    render_grid(
+                        &ctx,
+                        &rs,
+                        &cam,
+                        (w, h),
+                        &mut terrain,
+                        &motion,
+                        BoardLayers {
+                            show_damage_numbers: *show_damage_numbers_flag.borrow(),
+                            show_health_bars: *show_health_bars_flag.borrow(),
+                            show_tile_labels: *show_tile_labels_flag.borrow(),
+                            prospector: *prospector_flag.borrow(),
+                            reduced_effects: *reduced_effects_flag.borrow(),
+                            heatmap: *heatmap_flag.borrow(),
+                            tower_history: *tower_history_flag.borrow(),
+                            palette: *palette_flag.borrow(),
+                        },
+                    );
+                    let interact_mask = terrain.mask();
                     let gs = rs.grid_size;
-                    ctx.set_fill_style_str("#161b22");
-                    ctx.fill_rect(0.0, 0.0, gs.width as f64, gs.height as f64);
-                    ctx.set_stroke_style_str("#2f3641");
-                    let line_w = (1.0f64 / scale_px).max(0.001f64);
-                    ctx.set_line_width(line_w);
-                    for x in 0..=gs.width {
-                        ctx.begin_path();
-                        ctx.move_to(x as f64, 0.0);
-                        ctx.line_to(x as f64, gs.height as f64);
-                        ctx.stroke();
-                    }
-                    for y in 0..=gs.height {
-                        ctx.begin_path();
-                        ctx.move_to(0.0, y as f64);
-                        ctx.line_to(gs.width as f64, y as f64);
-                        ctx.stroke();
-                    }
                     let margin = 0.1;
-                    for y in 0..gs.height {
-                        for x in 0..gs.width {
-                            let idx = (y * gs.width + x) as usize;
-                            match rs.tiles[idx].kind {
-                                model::TileKind::Rock { has_gold, boost } => {
-                                    let rx = x as f64 + margin;
-                                    let ry = y as f64 + margin;
-                                    let rw = 1.0 - 2.0 * margin;
-                                    let rh = rw;
-                                    let fill = match boost {
-                                        Some(model::BoostKind::Slow) => "#203a5a",
-                                        Some(model::BoostKind::Damage) => "#5a2320",
-                                        Some(model::BoostKind::Fire) => "#5a3520",
-                                        Some(model::BoostKind::Range) => "#204a3a",
-                                        Some(model::BoostKind::FireRate) => "#4a3a20",
-                                        None => {
-                                            if has_gold {
-                                                "#4d3b1f"
-                                            } else {
-                                                "#1d2430"
-                                            }
-                                        }
-                                    };
-                                    ctx.set_fill_style_str(fill);
-                                    ctx.fill_rect(rx, ry, rw, rh);
-                                    if has_gold && boost.is_some() {
-                                        ctx.set_fill_style_str("#d4af37");
-                                        ctx.fill_rect(
-                                            rx + rw * 0.35,
-                                            ry + rh * 0.35,
-                                            rw * 0.3,
-                                            rh * 0.3,
-                                        );
-                                    }
-                                    ctx.set_stroke_style_str("#3a4455");
-                                    ctx.set_line_width((1.0f64 / scale_px).max(0.001f64));
-                                    ctx.stroke_rect(rx, ry, rw, rh);
-                                }
-                                model::TileKind::Wall => {
-                                    let rx = x as f64 + margin;
-                                    let ry = y as f64 + margin;
-                                    let rw = 1.0 - 2.0 * margin;
-                                    let rh = rw;
-                                    ctx.set_fill_style_str("#2a2f38");
-                                    ctx.fill_rect(rx, ry, rw, rh);
-                                    ctx.set_stroke_style_str("#555e6b");
-                                    ctx.set_line_width((1.0f64 / scale_px).max(0.001f64));
-                                    ctx.stroke_rect(rx, ry, rw, rh);
-                                }
-                                model::TileKind::Start => {
-                                    let rx = x as f64;
-                                    let ry = y as f64;
-                                    ctx.set_fill_style_str("#082235");
-                                    ctx.fill_rect(rx, ry, 1.0, 1.0);
-                                    let cx = rx + 0.5;
-                                    let cy = ry + 0.5;
-                                    ctx.begin_path();
-                                    ctx.set_fill_style_str("#58a6ff");
-                                    ctx.arc(cx, cy, 0.30, 0.0, std::f64::consts::PI * 2.0).ok();
-                                    ctx.fill();
-                                    ctx.set_stroke_style_str("#1f6feb");
-                                    ctx.set_line_width((1.2f64 / scale_px).max(0.001f64));
-                                    ctx.stroke();
-                                }
-                                model::TileKind::Direction { dir, role } => {
-                                    let rx = x as f64;
-                                    let ry = y as f64;
-                                    ctx.set_fill_style_str("#082235");
-                                    ctx.fill_rect(rx, ry, 1.0, 1.0);
-                                    let color = match role {
-                                        model::DirRole::Entrance => "#2ea043",
-                                        model::DirRole::Exit => "#f0883e",
-                                    };
-                                    ctx.set_fill_style_str(color);
-                                    ctx.begin_path();
-                                    match dir {
-                                        model::ArrowDir::Right => {
-                                            ctx.move_to(rx + 0.25, ry + 0.20);
-                                            ctx.line_to(rx + 0.25, ry + 0.80);
-                                            ctx.line_to(rx + 0.80, ry + 0.50);
-                                        }
-                                        model::ArrowDir::Left => {
-                                            ctx.move_to(rx + 0.75, ry + 0.20);
-                                            ctx.line_to(rx + 0.75, ry + 0.80);
-                                            ctx.line_to(rx + 0.20, ry + 0.50);
-                                        }
-                                        model::ArrowDir::Up => {
-                                            ctx.move_to(rx + 0.20, ry + 0.75);
-                                            ctx.line_to(rx + 0.80, ry + 0.75);
-                                            ctx.line_to(rx + 0.50, ry + 0.20);
-                                        }
-                                        model::ArrowDir::Down => {
-                                            ctx.move_to(rx + 0.20, ry + 0.25);
-                                            ctx.line_to(rx + 0.80, ry + 0.25);
-                                            ctx.line_to(rx + 0.50, ry + 0.80);
-                                        }
-                                    }
-                                    ctx.close_path();
-                                    ctx.fill();
-                                }
-                                model::TileKind::Indestructible => {
-                                    let rx = x as f64 + margin;
-                                    let ry = y as f64 + margin;
-                                    let rw = 1.0 - 2.0 * margin;
-                                    let rh = rw;
-                                    ctx.set_fill_style_str("#3c4454");
-                                    ctx.fill_rect(rx, ry, rw, rh);
-                                    ctx.set_stroke_style_str("#596273");
-                                    ctx.set_line_width((1.0f64 / scale_px).max(0.001f64));
-                                    ctx.stroke_rect(rx, ry, rw, rh);
-                                }
-                                model::TileKind::Empty => {
-                                    let rx = x as f64;
-                                    let ry = y as f64;
-                                    ctx.set_fill_style_str("#082235");
-                                    ctx.fill_rect(rx, ry, 1.0, 1.0);
-                                }
-                                _ => {}
-                            }
-                            if !interact_mask[idx] {
-                                ctx.set_fill_style_str("rgba(0,0,0,0.35)");
-                                ctx.fill_rect(x as f64, y as f64, 1.0, 1.0);
-                            }
-                        }
-                    }
-                    ctx.set_line_width((1.0f64 / scale_px).max(0.001f64));
-                    for e in &rs.enemies {
-                        let radius = 0.28 * e.radius_scale;
-
-                        // Calculate enemy color based on debuffs
-                        let mut base_r = 255.0; // Default: Red/orange (hostile)
-                        let mut base_g = 80.0;
-                        let mut base_b = 50.0;
-
-                        // Apply debuff color tints by blending
-                        let mut has_slow = false;
-                        let mut has_poison = false;
-                        let mut has_burn = false;
-                        let mut has_freeze = false;
-                        let mut slow_strength = 0.0;
-                        let mut poison_strength = 0.0;
-                        let mut burn_strength = 0.0;
-                        let mut freeze_strength = 0.0;
-
-                        for debuff in &e.debuffs {
-                            match debuff.kind {
-                                model::DebuffKind::Slow => {
-                                    has_slow = true;
-                                    slow_strength = (debuff.remaining / 4.0).min(1.0);
-                                    // Fade over duration
-                                }
-                                model::DebuffKind::Poison => {
-                                    has_poison = true;
-                                    poison_strength = (debuff.remaining / 4.0).min(1.0);
-                                }
-                                model::DebuffKind::Burn => {
-                                    has_burn = true;
-                                    burn_strength = (debuff.remaining / 4.0).min(1.0);
-                                }
-                                model::DebuffKind::Freeze => {
-                                    has_freeze = true;
-                                    freeze_strength = (debuff.remaining / 4.0).min(1.0);
-                                }
-                            }
-                        }
-
-                        // Blend colors based on debuffs
-                        if has_burn {
-                            // Burn: Bright orange/yellow (fire) - most visually distinct
-                            base_r = 255.0 * (1.0 - burn_strength) + 255.0 * burn_strength;
-                            base_g = 80.0 * (1.0 - burn_strength) + 140.0 * burn_strength;
-                            base_b = 50.0 * (1.0 - burn_strength) + 0.0 * burn_strength;
-                        } else if has_freeze {
-                            base_r = 255.0 * (1.0 - freeze_strength) + 136.0 * freeze_strength;
-                            base_g = 80.0 * (1.0 - freeze_strength) + 204.0 * freeze_strength;
-                            base_b = 50.0 * (1.0 - freeze_strength) + 255.0 * freeze_strength;
-                        } else if has_slow && has_poison {
-                            // Both: Mix blue and green = cyan/teal
-                            let blend = (slow_strength + poison_strength) / 2.0;
-                            base_r = 255.0 * (1.0 - blend) + 0.0 * blend;
-                            base_g = 80.0 * (1.0 - blend) + 200.0 * blend;
-                            base_b = 50.0 * (1.0 - blend) + 180.0 * blend;
-                        } else if has_slow {
-                            // Slow: Blue/cyan tint
-                            base_r = 255.0 * (1.0 - slow_strength) + 50.0 * slow_strength;
-                            base_g = 80.0 * (1.0 - slow_strength) + 150.0 * slow_strength;
-                            base_b = 50.0 * (1.0 - slow_strength) + 255.0 * slow_strength;
-                        } else if has_poison {
-                            // Poison: Green/yellow-green tint
-                            base_r = 255.0 * (1.0 - poison_strength) + 100.0 * poison_strength;
-                            base_g = 80.0 * (1.0 - poison_strength) + 220.0 * poison_strength;
-                            base_b = 50.0 * (1.0 - poison_strength) + 50.0 * poison_strength;
-                        }
-
-                        let enemy_color = format!(
-                            "#{:02x}{:02x}{:02x}",
-                            base_r as u8, base_g as u8, base_b as u8
-                        );
-
-                        // Calculate HP percentage
-                        let hp_percent = if e.max_hp > 0 {
-                            (e.hp as f64 / e.max_hp as f64).clamp(0.0, 1.0)
-                        } else {
-                            1.0
-                        };
-
-                        // Draw background circle (dark, shows missing HP)
-                        ctx.begin_path();
-                        ctx.set_fill_style_str("#1a2332"); // Dark blue-gray
-                        ctx.arc(e.x, e.y, radius, 0.0, std::f64::consts::PI * 2.0)
-                            .ok();
-                        ctx.fill();
-
-                        // Draw HP circle on top (scales with HP percentage)
-                        // Color now indicates debuff status!
-                        let hp_radius = radius * hp_percent.sqrt(); // sqrt makes it area-based
-                        ctx.begin_path();
-                        ctx.set_fill_style_str(&enemy_color);
-                        ctx.arc(e.x, e.y, hp_radius, 0.0, std::f64::consts::PI * 2.0)
-                            .ok();
-                        ctx.fill();
-
-                        // Outline (always full size)
-                        ctx.begin_path();
-                        ctx.set_stroke_style_str("#a80032");
-                        ctx.set_line_width(0.04);
-                        ctx.arc(e.x, e.y, radius, 0.0, std::f64::consts::PI * 2.0)
-                            .ok();
-                        ctx.stroke();
-                    }
-                    for tw in &rs.towers {
-                        let cx = tw.x as f64 + 0.5;
-                        let cy = tw.y as f64 + 0.5;
-
-                        // Draw boost ring if tower has a boost
-                        if let Some(boost) = tw.boost {
-                            ctx.begin_path();
-                            let boost_color = match boost {
-                                model::BoostKind::Range => "#58a6ff",    // Blue (Healing)
-                                model::BoostKind::Damage => "#64dc37",   // Green (Poison)
-                                model::BoostKind::FireRate => "#f85149", // Red
-                                model::BoostKind::Slow => "#3296ff",     // Bright Blue (Cold)
-                                model::BoostKind::Fire => "#ff8c00",     // Orange (Fire/Burn)
-                            };
-                            ctx.set_stroke_style_str(boost_color);
-                            ctx.set_line_width(0.08);
-                            ctx.arc(cx, cy, 0.40, 0.0, std::f64::consts::PI * 2.0).ok();
-                            ctx.stroke();
-                        }
-
-                        ctx.begin_path();
-                        let color = match tw.kind {
-                            TowerKind::Basic => "#ffd700",
-                            TowerKind::Slow => "#58a6ff",
-                            TowerKind::Damage => "#f85149",
-                        };
-                        ctx.set_fill_style_str(color);
-                        ctx.arc(cx, cy, 0.30, 0.0, std::f64::consts::PI * 2.0).ok();
-                        ctx.fill();
-                        ctx.set_stroke_style_str("#111821");
-                        ctx.set_line_width(0.04);
-                        ctx.stroke();
-                    }
-                    if !rs.projectiles.is_empty() {
-                        ctx.set_fill_style_str("#fffb");
-                        for p in &rs.projectiles {
-                            ctx.begin_path();
-                            ctx.arc(p.x, p.y, 0.08, 0.0, std::f64::consts::PI * 2.0)
-                                .ok();
-                            ctx.fill();
+                    let m = mining.borrow();
+                    if m.mining_now()
+                        && m.tile_x >= 0
+                        && m.tile_y >= 0
+                        && (m.tile_x as u32) < gs.width
+                        && (m.tile_y as u32) < gs.height
+                    {
+                        let idx = (m.tile_y as u32 * gs.width + m.tile_x as u32) as usize;
+                        if matches!(
+                            rs.tiles[idx].kind,
+                            model::TileKind::Rock { .. } | model::TileKind::Wall
+                        ) {
+                            let rx = m.tile_x as f64 + margin;
+                            let ry = m.tile_y as f64
+                                + margin
+                                + (1.0 - 2.0 * margin) * (1.0 - m.progress.clamp(0.0, 1.0));
+                            let rw = 1.0 - 2.0 * margin;
+                            let rh = (1.0 - 2.0 * margin) * m.progress.clamp(0.0, 1.0);
+                            ctx.set_fill_style_str("rgba(46,160,67,0.7)");
+                            ctx.fill_rect(rx, ry, rw, rh);
                         }
                     }
-                    for se in &rs.splash_explosions {
-                        let life_ratio = (se.ttl / 0.25).clamp(0.0, 1.0);
-                        let alpha = life_ratio * 0.6;
-                        let current_radius = se.radius * (1.0 - life_ratio * 0.3);
-                        ctx.begin_path();
-                        ctx.arc(se.x, se.y, current_radius, 0.0, std::f64::consts::PI * 2.0)
-                            .ok();
-                        ctx.set_fill_style_str(&format!("rgba(255,160,50,{:.3})", alpha));
-                        ctx.fill();
-                        ctx.set_stroke_style_str(&format!("rgba(255,100,30,{:.3})", alpha));
-                        ctx.set_line_width(0.06);
-                        ctx.stroke();
-                    }
-                    // Damage numbers (floating text)
-                    if show_damage_nums_on && !rs.damage_numbers.is_empty() {
-                        let base_font_size = (0.2 / scale_px).max(0.5);
+                    // Mining queue: numbered markers, next first
+                    if !m.queue.is_empty() {
+                        ctx.set_font("bold 0.45px sans-serif");
                         ctx.set_text_align("center");
-                        for dn in &rs.damage_numbers {
-                            let life_ratio = (dn.ttl / 0.8_f64).clamp(0.0, 1.0);
-                            let rise = (0.8_f64 - dn.ttl).max(0.0_f64) * 0.30_f64;
-                            let alpha = life_ratio;
-                            if dn.is_heal {
-                                ctx.set_font(&format!(
-                                    "bold {}px sans-serif",
-                                    base_font_size * 1.2
-                                ));
-                                ctx.set_fill_style_str(&format!("rgba(46,160,67,{:.3})", alpha));
-                                ctx.fill_text(&format!("+{}", dn.amount), dn.x, dn.y - rise)
-                                    .ok();
-                            } else if dn.is_gold {
-                                if dn.is_crit {
-                                    ctx.set_font(&format!(
-                                        "bold {}px sans-serif",
-                                        base_font_size * 1.5
-                                    ));
-                                    ctx.set_fill_style_str(&format!(
-                                        "rgba(255,215,0,{:.3})",
-                                        alpha
-                                    ));
-                                } else {
-                                    ctx.set_font(&format!("{}px sans-serif", base_font_size));
-                                    ctx.set_fill_style_str(&format!(
-                                        "rgba(210,153,34,{:.3})",
-                                        alpha
-                                    ));
-                                }
-                                ctx.fill_text(&format!("+{}", dn.amount), dn.x, dn.y - rise)
-                                    .ok();
-                            } else {
-                                ctx.set_font(&format!("{}px sans-serif", base_font_size));
-                                ctx.set_fill_style_str(&format!("rgba(255,50,50,{:.3})", alpha));
-                                ctx.fill_text(&dn.amount.to_string(), dn.x, dn.y - rise)
-                                    .ok();
-                            }
+                        ctx.set_text_baseline("middle");
+                        for (i, &(qx, qy)) in m.queue.iter().enumerate() {
+                            ctx.set_fill_style_str("rgba(46,160,67,0.35)");
+                            ctx.fill_rect(qx as f64 + margin, qy as f64 + margin, 0.8, 0.8);
+                            ctx.set_fill_style_str("rgba(255,255,255,0.9)");
+                            ctx.fill_text(&(i + 1).to_string(), qx as f64 + 0.5, qy as f64 + 0.5)
+                                .ok();
                         }
                         ctx.set_text_align("start");
+                        ctx.set_text_baseline("alphabetic");
                     }
-                    let m = mining.borrow();
-                    if m.active && m.mouse_down {
-                        if m.tile_x >= 0
-                            && m.tile_y >= 0
-                            && (m.tile_x as u32) < gs.width
-                            && (m.tile_y as u32) < gs.height
-                        {
-                            let idx = (m.tile_y as u32 * gs.width + m.tile_x as u32) as usize;
-                            if matches!(
-                                rs.tiles[idx].kind,
-                                model::TileKind::Rock { .. } | model::TileKind::Wall
-                            ) {
-                                let rx = m.tile_x as f64 + margin;
-                                let ry = m.tile_y as f64
-                                    + margin
-                                    + (1.0 - 2.0 * margin) * (1.0 - m.progress.clamp(0.0, 1.0));
-                                let rw = 1.0 - 2.0 * margin;
-                                let rh = (1.0 - 2.0 * margin) * m.progress.clamp(0.0, 1.0);
-                                ctx.set_fill_style_str("rgba(46,160,67,0.7)");
-                                ctx.fill_rect(rx, ry, rw, rh);
-                            }
-                        }
+                    // Auto Miner: a faint pickaxe on the rock it takes next
+                    let auto_target = (rs.auto_mine_period > 0 && rs.started && !rs.game_over)
+                        .then(|| model::auto_mine_target(&rs, interact_mask))
+                        .flatten();
+                    if let Some(idx) = auto_target {
+                        let ax = (idx as u32 % gs.width) as f64;
+                        let ay = (idx as u32 / gs.width) as f64;
+                        ctx.set_font("0.3px sans-serif");
+                        ctx.set_global_alpha(0.55);
+                        ctx.fill_text("⛏", ax + 0.62, ay + 0.32).ok();
+                        ctx.set_global_alpha(1.0);
                     }
-                    if show_path_on {
-                        let path_for_draw: Vec<model::Position> = if !rs.path_loop.is_empty() {
-                            rs.path_loop.clone()
+                    // Tutorial: an outline and a bobbing arrow on the tile the step is about
+                    let tutorial_step = tutorial_draw.borrow().current();
+                    let tutorial_tile = tutorial_step
+                        .filter(|_| !rs.game_over)
+                        .and_then(|step| tutorial_target(step, &rs, interact_mask));
+                    *tutorial_bobbing_draw.borrow_mut() =
+                        tutorial_tile.is_some() && !*reduced_effects_flag.borrow();
+                    if let Some((tx, ty)) = tutorial_tile {
+                        let (x, y) = (tx as f64, ty as f64);
+                        let bob = if *reduced_effects_flag.borrow() {
+                            0.0
                         } else {
-                            rs.path.clone()
+                            0.08 * (now / 180.0).sin()
                         };
-                        if path_for_draw.is_empty() {
+                        ctx.set_stroke_style_str("#58a6ff");
+                        ctx.set_line_width(0.08);
+                        ctx.stroke_rect(x + 0.04, y + 0.04, 0.92, 0.92);
+                        let tip = y - 0.1 + bob;
+                        ctx.begin_path();
+                        ctx.move_to(x + 0.5, tip);
+                        ctx.line_to(x + 0.25, tip - 0.35);
+                        ctx.line_to(x + 0.75, tip - 0.35);
+                        ctx.close_path();
+                        ctx.set_fill_style_str("#58a6ff");
+                        ctx.fill();
+                    }
+                    if show_path_on {
+                        let segments = crate::state::loop_segments(&rs);
+                        let no_path = rs.path.is_empty();
+                        if no_path || segments.is_empty() {
+                            // Whatever loop is left is stale and drawn as such below
                             ctx.set_transform(1.0, 0.0, 0.0, 1.0, 0.0, 0.0).ok();
                             ctx.set_fill_style_str("rgba(255,80,80,0.9)");
                             ctx.set_font("12px sans-serif");
-                            ctx.fill_text("No path", 10.0, 40.0).ok();
+                            let note = if segments.is_empty() {
+                                "No path"
+                            } else {
+                                "No path (stale loop dashed)"
+                            };
+                            ctx.fill_text(note, 10.0, 40.0).ok();
                             ctx.set_transform(
                                 scale_px,
                                 0.0,
@@ -674,109 +1338,325 @@ pub fn run_view(props: &RunViewProps) -> Html {
                                 cam.offset_y,
                             )
                             .ok();
-                        } else if path_for_draw.len() >= 2 {
-                            ctx.set_stroke_style_str("#ff66ff");
-                            ctx.set_line_width((2.5f64 / scale_px).max(0.002f64));
+                        }
+                        let path_color = |kind| match kind {
+                            crate::state::SegmentKind::Searched => "#ff66ff",
+                            crate::state::SegmentKind::Stitched => "#e3b341",
+                            crate::state::SegmentKind::Stale => "rgba(248,81,73,0.9)",
+                        };
+                        ctx.set_line_width((2.5f64 / scale_px).max(0.002f64));
+                        for seg in &segments {
+                            // Stitched legs jump between tiles; dashed so they don't read as a route
+                            let dash = js_sys::Array::new();
+                            if seg.kind != crate::state::SegmentKind::Searched {
+                                dash.push(&0.18.into());
+                                dash.push(&0.12.into());
+                            }
+                            ctx.set_line_dash(&dash).ok();
+                            ctx.set_stroke_style_str(path_color(seg.kind));
                             ctx.begin_path();
-                            for (i, node) in path_for_draw.iter().enumerate() {
-                                let cx = node.x as f64 + 0.5;
-                                let cy = node.y as f64 + 0.5;
-                                if i == 0 {
-                                    ctx.move_to(cx, cy);
-                                } else {
-                                    ctx.line_to(cx, cy);
+                            ctx.move_to(seg.from.x as f64 + 0.5, seg.from.y as f64 + 0.5);
+                            ctx.line_to(seg.to.x as f64 + 0.5, seg.to.y as f64 + 0.5);
+                            ctx.stroke();
+                        }
+                        ctx.set_line_dash(&js_sys::Array::new()).ok();
+                        // Travel direction: a small arrowhead every few tiles
+                        let marks =
+                            crate::state::arrow_marks(&segments, crate::state::ARROW_SPACING_TILES);
+                        for (ax, ay, heading, kind) in marks {
+                            let (c, s) = (heading.cos(), heading.sin());
+                            ctx.set_fill_style_str(path_color(kind));
+                            ctx.begin_path();
+                            ctx.move_to(ax + c * 0.18, ay + s * 0.18);
+                            ctx.line_to(ax - c * 0.12 - s * 0.12, ay - s * 0.12 + c * 0.12);
+                            ctx.line_to(ax - c * 0.12 + s * 0.12, ay - s * 0.12 - c * 0.12);
+                            ctx.close_path();
+                            ctx.fill();
+                        }
+                        let start = rs
+                            .tiles
+                            .iter()
+                            .position(|t| t.kind == model::TileKind::Start);
+                        if let Some(idx) = start.filter(|_| !segments.is_empty()) {
+                            let (searched, stitched) = crate::state::leg_lengths(&segments);
+                            let sx = (idx as u32 % gs.width) as f64 + 0.5;
+                            let sy = (idx as u32 / gs.width) as f64;
+                            ctx.set_font("0.3px sans-serif");
+                            ctx.set_text_align("center");
+                            ctx.set_fill_style_str("#ff66ff");
+                            let label = format!(
+                                "Loop {:.1} ({:.1} + {:.1} stitched)",
+                                rs.loop_total_length, searched, stitched
+                            );
+                            ctx.fill_text(&label, sx, sy - 0.12).ok();
+                            ctx.set_text_align("start");
+                        }
+                    }
+                    let (hx, hy) = focus_tile_draw.borrow().tile();
+                    let stamp = *stamp_cursor_draw.borrow();
+                    let stamp_focus = focus_tile_draw.borrow().in_grid(gs);
+                    let dragging_walls = wall_drag_draw.borrow().active;
+                    if dragging_walls {
+                        // Queued drag walls, red from the first one that would block the path
+                        let drag = wall_drag_draw.borrow();
+                        for (i, p) in drag.tiles.iter().enumerate() {
+                            ctx.set_fill_style_str(if i < drag.valid {
+                                "rgba(139,148,158,0.55)"
+                            } else {
+                                "rgba(248,81,73,0.45)"
+                            });
+                            ctx.fill_rect(p.x as f64 + 0.05, p.y as f64 + 0.05, 0.9, 0.9);
+                        }
+                        let blocked = drag.tiles.len() - drag.valid;
+                        let msg = if blocked > 0 {
+                            format!(
+                                "Release to place {} walls ({} would block the path)",
+                                drag.valid, blocked
+                            )
+                        } else {
+                            format!("Release to place {} walls", drag.valid)
+                        };
+                        if *tower_feedback_draw != msg {
+                            tower_feedback_draw.set(msg);
+                        }
+                    } else if let (Some(id), Some(origin)) = (stamp.selected, stamp_focus) {
+                        // Stamp ghosts under the cursor; a failing tile is outlined in red
+                        let check = stamp_preview_draw
+                            .borrow_mut()
+                            .refresh(&rs, origin, id, stamp.rotation)
+                            .clone();
+                        for ((x, y), target) in model::stamp_cells(id, origin, stamp.rotation) {
+                            ctx.set_fill_style_str(match target {
+                                model::StampTarget::Open => "rgba(88,166,255,0.25)",
+                                model::StampTarget::Wall => "rgba(139,148,158,0.45)",
+                                model::StampTarget::Rock => "rgba(196,140,72,0.45)",
+                            });
+                            ctx.fill_rect(x as f64 + 0.05, y as f64 + 0.05, 0.9, 0.9);
+                        }
+                        let msg = match &check {
+                            Ok(cost) => {
+                                format!("Click: stamp {} (-{} credits)", id.def().name, cost)
+                            }
+                            Err(rej) => {
+                                if let Some((x, y)) = rej.tile {
+                                    ctx.set_stroke_style_str("#f85149");
+                                    ctx.set_line_width((2.0 / scale_px).max(0.001));
+                                    ctx.stroke_rect(x as f64 + 0.05, y as f64 + 0.05, 0.9, 0.9);
                                 }
+                                format!("{}: {}", id.def().name, rej.reason)
                             }
-                            ctx.stroke();
+                        };
+                        if *tower_feedback_draw != msg {
+                            tower_feedback_draw.set(msg);
                         }
-                    }
-                    let (hx, hy) = *hover_tile_draw.borrow();
-                    if hx >= 0 && hy >= 0 {
-                        if (hx as u32) < gs.width && (hy as u32) < gs.height {
-                            let selected_kind = selected_tower_kind_draw.borrow().clone();
-                            let selected_kind_label = match selected_kind {
-                                TowerKind::Basic => "Basic",
-                                TowerKind::Slow => "Slow",
-                                TowerKind::Damage => "Damage",
-                            };
-                            let idx = (hy as u32 * gs.width + hx as u32) as usize;
-                            let interact_ok = interact_mask[idx];
-                            let (color_opt, msg, show_range) = if !interact_ok {
+                    } else if hx >= 0
+                        && hy >= 0
+                        && (hx as u32) < gs.width
+                        && (hy as u32) < gs.height
+                    {
+                        let selected_kind = selected_tower_kind_draw.borrow().clone();
+                        let selected_cost = rs.current_tower_cost(&selected_kind);
+                        let idx = (hy as u32 * gs.width + hx as u32) as usize;
+                        let interact_ok = interact_mask[idx];
+                        let mut ghost_loop: Option<Vec<model::Position>> = None;
+                        // Some(valid) when the selected tower's ghost goes on the tile
+                        let mut ghost: Option<bool> = None;
+                        let walls_locked =
+                            model::feature_lock_message(rs.progression_run, Feature::Walls);
+                        let (color_opt, msg, show_range) = if !interact_ok {
+                            (
+                                Some("rgba(90,90,90,0.35)"),
+                                "Out of reach".to_string(),
+                                false,
+                            )
+                        } else if rs.game_over {
+                            // removed rs.is_paused here to allow placement while paused
+                            (
+                                Some("rgba(110,118,129,0.35)"),
+                                "Game Over".to_string(),
+                                false,
+                            )
+                        } else if let (model::TileKind::Empty, Some(msg)) =
+                            (&rs.tiles[idx].kind, &walls_locked)
+                        {
+                            (Some("rgba(110,118,129,0.35)"), msg.clone(), false)
+                        } else if matches!(rs.tiles[idx].kind, model::TileKind::Empty) {
+                            // Wall intent: preview how the loop would change
+                            let mut wp = wall_preview_draw.borrow_mut();
+                            wp.refresh(&rs, hx, hy);
+                            if wp.skipped {
                                 (
-                                    Some("rgba(90,90,90,0.35)"),
-                                    "Out of reach".to_string(),
+                                    Some("rgba(110,118,129,0.35)"),
+                                    "Wall here (hold Shift to preview path)".to_string(),
                                     false,
                                 )
-                            } else if rs.game_over {
-                                // removed rs.is_paused here to allow placement while paused
+                            } else if let Some(eff) = &wp.effect {
+                                if show_path_on {
+                                    ghost_loop = Some(eff.new_loop.clone());
+                                }
                                 (
-                                    Some("rgba(110,118,129,0.35)"),
-                                    "Game Over".to_string(),
+                                    Some("rgba(46,160,67,0.45)"),
+                                    format!("Wall here: loop {:+.1}", eff.delta()),
                                     false,
                                 )
-                            } else if !matches!(
-                                rs.tiles[idx].kind,
-                                model::TileKind::Rock { .. } | model::TileKind::Wall
-                            ) {
+                            } else {
                                 (
                                     Some("rgba(248,81,73,0.45)"),
-                                    "Need Rock/Wall".to_string(),
+                                    "Wall here: blocks path".to_string(),
                                     false,
                                 )
-                            } else if rs
-                                .towers
-                                .iter()
-                                .any(|t| t.x == hx as u32 && t.y == hy as u32)
-                            {
-                                (
-                                    Some("rgba(219,109,40,0.55)"),
-                                    "T: remove tower".to_string(),
-                                    true,
-                                )
-                            } else if rs.currencies.gold < rs.tower_cost {
-                                (
+                            }
+                        } else if let Some(tw) = rs.tower_at(hx as u32, hy as u32) {
+                            let upgrade = match model::tower_upgrade_cost(&rs, tw) {
+                                Some(cost) => format!("U: upgrade ({}g)", cost),
+                                None => "Max level".to_string(),
+                            };
+                            (
+                                Some("rgba(219,109,40,0.55)"),
+                                format!(
+                                    "T: sell tower · {} · R: target {}",
+                                    upgrade,
+                                    tw.target_mode.name()
+                                ),
+                                true,
+                            )
+                        } else {
+                            // Rock or Wall: a ghost of the selected tower, green where it
+                            // can go and red where it can't
+                            match model::placement_check_with_mask(
+                                &rs,
+                                interact_mask,
+                                hx as u32,
+                                hy as u32,
+                                &selected_kind,
+                            ) {
+                                model::PlacementResult::WrongTile => (
                                     Some("rgba(248,81,73,0.45)"),
-                                    format!("Need {} gold", rs.tower_cost),
+                                    "Need Rock/Wall".to_string(),
                                     false,
-                                )
+                                ),
+                                model::PlacementResult::Locked(msg) => {
+                                    (Some("rgba(110,118,129,0.35)"), msg, false)
+                                }
+                                model::PlacementResult::Valid => {
+                                    ghost = Some(true);
+                                    let boost_note = match &rs.tiles[idx].kind {
+                                        model::TileKind::Rock { boost: Some(b), .. } => {
+                                            format!(" +{} boost", b.name())
+                                        }
+                                        _ => String::new(),
+                                    };
+                                    let name = selected_kind.name();
+                                    let msg =
+                                        format!("T: place {name} ({selected_cost}g){boost_note}");
+                                    (None, msg, false)
+                                }
+                                why => {
+                                    ghost = Some(false);
+                                    (None, why.message(), false)
+                                }
+                            }
+                        };
+                        if let Some(c) = color_opt {
+                            ctx.set_fill_style_str(c);
+                            ctx.fill_rect(hx as f64, hy as f64, 1.0, 1.0);
+                        }
+                        if let Some(ghost) = ghost_loop.filter(|g| g.len() >= 2) {
+                            ctx.set_stroke_style_str("rgba(88,166,255,0.75)");
+                            ctx.set_line_width((1.5f64 / scale_px).max(0.002f64));
+                            ctx.begin_path();
+                            for (i, node) in ghost.iter().enumerate() {
+                                let cx = node.x as f64 + 0.5;
+                                let cy = node.y as f64 + 0.5;
+                                if i == 0 {
+                                    ctx.move_to(cx, cy);
+                                } else {
+                                    ctx.line_to(cx, cy);
+                                }
+                            }
+                            ctx.stroke();
+                        }
+                        if let Some(valid) = ghost {
+                            // Its real reach: kind multipliers and the tile's boost included
+                            let preview = model::preview_tower(
+                                &rs,
+                                hx as u32,
+                                hy as u32,
+                                selected_kind.clone(),
+                            );
+                            let (cx, cy) = (hx as f64 + 0.5, hy as f64 + 0.5);
+                            let (fill, line) = if valid {
+                                ("rgba(46,160,67,0.12)", "rgba(46,160,67,0.85)")
                             } else {
-                                (
-                                    Some("rgba(46,160,67,0.45)"),
-                                    format!(
-                                        "T: place {} ({}g)",
-                                        selected_kind_label, rs.tower_cost
-                                    ),
-                                    true,
-                                )
+                                ("rgba(248,81,73,0.12)", "rgba(248,81,73,0.85)")
                             };
-                            if let Some(c) = color_opt {
-                                ctx.set_fill_style_str(c);
-                                ctx.fill_rect(hx as f64, hy as f64, 1.0, 1.0);
-                            }
-                            if show_range {
-                                ctx.begin_path();
-                                ctx.set_line_width((1.0f64 / scale_px).max(0.001f64));
-                                ctx.set_stroke_style_str("rgba(56,139,253,0.5)");
-                                ctx.arc(
-                                    hx as f64 + 0.5,
-                                    hy as f64 + 0.5,
-                                    rs.tower_base_range,
-                                    0.0,
-                                    std::f64::consts::PI * 2.0,
-                                )
+                            ctx.begin_path();
+                            ctx.arc(cx, cy, preview.range, 0.0, std::f64::consts::PI * 2.0)
                                 .ok();
-                                ctx.stroke();
-                            }
-                            if *tower_feedback_draw != msg {
-                                tower_feedback_draw.set(msg);
-                            }
+                            ctx.set_fill_style_str(fill);
+                            ctx.fill();
+                            ctx.set_line_width((1.0f64 / scale_px).max(0.001f64));
+                            ctx.set_stroke_style_str(line);
+                            ctx.stroke();
+                            ctx.begin_path();
+                            ctx.arc(cx, cy, 0.30, 0.0, std::f64::consts::PI * 2.0).ok();
+                            let pal = palette_flag.borrow().palette();
+                            ctx.set_fill_style_str(&pal.tower_tint(&selected_kind));
+                            ctx.fill();
+                            ctx.set_line_width(0.04);
+                            ctx.stroke();
+                        }
+                        if show_range {
+                            // A built tower shows its real reach, levels and boosts included
+                            let range = rs
+                                .tower_at(hx as u32, hy as u32)
+                                .map_or(rs.tower_base_range, |tw| tw.range);
+                            ctx.begin_path();
+                            ctx.set_line_width((1.0f64 / scale_px).max(0.001f64));
+                            ctx.set_stroke_style_str("rgba(56,139,253,0.5)");
+                            ctx.arc(
+                                hx as f64 + 0.5,
+                                hy as f64 + 0.5,
+                                range,
+                                0.0,
+                                std::f64::consts::PI * 2.0,
+                            )
+                            .ok();
+                            ctx.stroke();
+                        }
+                        if *tower_feedback_draw != msg {
+                            tower_feedback_draw.set(msg);
                         }
                     }
+                    // Selected tower: outlined, with its reach kept on screen
+                    let selected = *selected_tower_draw.borrow();
+                    if let Some(tw) = selected.and_then(|(x, y)| rs.tower_at(x, y)) {
+                        ctx.set_line_width((2.0 / scale_px).max(0.001));
+                        ctx.set_stroke_style_str("#d29922");
+                        ctx.stroke_rect(tw.x as f64 + 0.05, tw.y as f64 + 0.05, 0.9, 0.9);
+                        ctx.begin_path();
+                        ctx.set_stroke_style_str("rgba(210,153,34,0.6)");
+                        ctx.arc(
+                            tw.x as f64 + 0.5,
+                            tw.y as f64 + 0.5,
+                            tw.range,
+                            0.0,
+                            std::f64::consts::PI * 2.0,
+                        )
+                        .ok();
+                        ctx.stroke();
+                    }
+                    // Worker-suggested tower spots (G)
+                    for &(sx, sy) in spots_draw.borrow().iter() {
+                        ctx.set_line_width((2.0 / scale_px).max(0.001));
+                        ctx.set_stroke_style_str("#d29922");
+                        ctx.stroke_rect(sx as f64 + 0.1, sy as f64 + 0.1, 0.8, 0.8);
+                    }
                     if *show_debug_flag.borrow() {
                         ctx.save();
                         ctx.set_transform(1.0, 0.0, 0.0, 1.0, 0.0, 0.0).ok();
-                        let pw = 200.0;
-                        let ph = 120.0;
+                        let pw = 240.0;
+                        let ph = 180.0;
                         let px = w - pw - 10.0;
                         let py = 10.0;
                         ctx.set_fill_style_str("rgba(14,17,22,0.85)");
@@ -802,12 +1682,67 @@ pub fn run_view(props: &RunViewProps) -> Html {
                             ctx.fill_text(&format!("Path: {} tiles", rs.path_loop.len()), tx, ty);
                         ty += 20.0;
                         let _ = ctx.fill_text(&format!("Sim: {:.1}s", rs.sim_time), tx, ty);
+                        ty += 20.0;
+                        let c = rs.projectile_culls;
+                        let _ = ctx.fill_text(
+                            &format!(
+                                "Culled: {} age / {} cap / {} oob",
+                                c.expired, c.capped, c.out_of_bounds
+                            ),
+                            tx,
+                            ty,
+                        );
+                        ty += 20.0;
+                        let sched = scheduler_draw.borrow();
+                        let _ = ctx.fill_text(
+                            &format!(
+                                "Frames: {} drawn / {} idle",
+                                sched.frames_drawn, sched.frames_skipped
+                            ),
+                            tx,
+                            ty,
+                        );
+                        ty += 20.0;
+                        let _ = ctx.fill_text(
+                            &format!("Draw: {:.2} ms/frame", sched.avg_frame_ms),
+                            tx,
+                            ty,
+                        );
                         ctx.restore();
                     }
+                    if let Some(mini) = minimap_draw.cast::<HtmlCanvasElement>() {
+                        minimap_layer_draw.borrow_mut().draw(
+                            &mini,
+                            &rs,
+                            palette_flag.borrow().palette(),
+                            &cam,
+                            (w, h),
+                        );
+                    }
                 })
             };
             *draw_ref_setup.borrow_mut() = Some(draw_closure.clone());
             (draw_closure)();
+            // Map analysis runs in a worker; answers for a board that has since changed are dropped
+            let analysis_worker: Rc<RefCell<Option<AnalysisWorker>>> = Rc::new(RefCell::new(None));
+            {
+                let client = analysis_client_setup.clone();
+                let spots = spots_setup.clone();
+                let scheduler = scheduler_setup.clone();
+                let feedback = tower_feedback_handle.clone();
+                *analysis_worker.borrow_mut() = AnalysisWorker::spawn(move |msg| {
+                    let Some(AnalysisResult::Spots(found)) = client.borrow_mut().accept(msg) else {
+                        return;
+                    };
+                    feedback.set(if found.is_empty() {
+                        "No rock in reach of the path".into()
+                    } else {
+                        format!("{} suggested tower spots outlined", found.len())
+                    });
+                    *spots.borrow_mut() = found.into_iter().map(|(x, y, _)| (x, y)).collect();
+                    scheduler.borrow_mut().request_redraw();
+                });
+            }
             // RAF loop
             let raf_id = Rc::new(RefCell::new(None));
             {
@@ -817,9 +1752,106 @@ pub fn run_view(props: &RunViewProps) -> Html {
                 let closure_cell: Rc<RefCell<Option<Closure<dyn FnMut()>>>> =
                     Rc::new(RefCell::new(None));
                 let closure_cell_clone = closure_cell.clone();
+                let run_state_ref_loop = run_state_ref.clone();
+                let catch_up_loop = catch_up_setup.clone();
+                let toast_loop = toast_setup.clone();
+                let camera_loop = camera.clone();
+                let mining_loop = mining_setup.clone();
+                let show_debug_loop = show_debug_flag.clone();
+                let scheduler_loop = scheduler_setup.clone();
+                let mut last_frame_ms = None::<f64>;
+                let mut last_sweep_ms = 0.0;
+                let cursor_loop = cursor_world_setup.clone();
+                let focus_loop = focus_tile_effect_local.clone();
+                let mut dpad = GamepadDpad::default();
+                let canvas_loop = canvas.clone();
+                let analysis_worker_loop = analysis_worker.clone();
+                let analysis_client_loop = analysis_client_setup.clone();
+                let spots_loop = spots_setup.clone();
+                let tutorial_bobbing_loop = tutorial_bobbing_setup.clone();
                 *closure_cell.borrow_mut() = Some(Closure::wrap(Box::new(move || {
-                    if let Some(f) = &*draw_ref_loop.borrow() {
+                    // Spread background catch-up over frames instead of one long stall
+                    {
+                        let mut cu = catch_up_loop.borrow_mut();
+                        let handle = run_state_ref_loop.borrow().clone();
+                        if let Some(action) = cu.next_batch() {
+                            handle.dispatch(action);
+                        } else if let Some(msg) = cu.finish(&handle) {
+                            toast_loop.set(Some(msg));
+                        }
+                    }
+                    // Keep the worker's board current; suggestions for the old one no longer apply
+                    if let Some(worker) = &*analysis_worker_loop.borrow() {
+                        let handle = run_state_ref_loop.borrow().clone();
+                        if let Some(msg) = analysis_client_loop.borrow_mut().sync(&handle) {
+                            worker.post(&msg);
+                            spots_loop.borrow_mut().clear();
+                        }
+                    }
+                    let now = js_sys::Date::now();
+                    let gliding = camera_loop.borrow_mut().step_glide(now);
+                    // Held pan keys and edge scrolling move the camera by real frame time
+                    let dt = last_frame_ms
+                        .replace(now)
+                        .map_or(0.0, |t| (now - t) / 1000.0);
+                    let panning = {
+                        let mut cam = camera_loop.borrow_mut();
+                        let moved = cam.apply_pan(dt);
+                        if moved {
+                            clamp_camera(&mut cam, &run_state_ref_loop.borrow(), &canvas_loop);
+                        }
+                        moved
+                    };
+                    // The first pad's D-pad steps the focus like the arrow keys
+                    if let Some((dx, dy)) = dpad.poll(gamepad_dpad(&window_loop)) {
+                        let gs = run_state_ref_loop.borrow().grid_size;
+                        focus_loop
+                            .borrow_mut()
+                            .step(FocusSource::Gamepad, dx, dy, gs);
+                        scheduler_loop.borrow_mut().request_redraw();
+                    }
+                    // Pickups under the cursor or keyboard/gamepad focus, a few times a second
+                    if now - last_sweep_ms >= PICKUP_SWEEP_MS {
+                        last_sweep_ms = now;
+                        let handle = run_state_ref_loop.borrow().clone();
+                        if !handle.pickups.is_empty() {
+                            let focus = focus_loop.borrow();
+                            let keyed = match focus.source {
+                                FocusSource::Keyboard | FocusSource::Gamepad => focus
+                                    .in_grid(handle.grid_size)
+                                    .map(|(x, y)| (x as f64 + 0.5, y as f64 + 0.5)),
+                                _ => None,
+                            };
+                            for (x, y) in (*cursor_loop.borrow()).into_iter().chain(keyed) {
+                                if model::pickup_in_reach(&handle, x, y) {
+                                    handle.dispatch(RunAction::CollectPickups { x, y });
+                                }
+                            }
+                        }
+                    }
+                    // Idle boards (paused, not started, game over) skip the draw entirely
+                    let inputs = {
+                        let rs = run_state_ref_loop.borrow();
+                        let cam = camera_loop.borrow();
+                        FrameInputs {
+                            sim_running: rs.started && !rs.is_paused && !rs.game_over,
+                            version: rs.version,
+                            camera: (cam.zoom, cam.offset_x, cam.offset_y),
+                            animating: mining_loop.borrow().active
+                                || gliding
+                                || panning
+                                || *tutorial_bobbing_loop.borrow(),
+                            hud_visible: *show_debug_loop.borrow(),
+                            now_ms: js_sys::Date::now(),
+                        }
+                    };
+                    let due = scheduler_loop.borrow_mut().should_draw(&inputs);
+                    if due && let Some(f) = &*draw_ref_loop.borrow() {
+                        let t0 = js_sys::Date::now();
                         f();
+                        scheduler_loop
+                            .borrow_mut()
+                            .record_frame(js_sys::Date::now() - t0);
                     }
                     if let Ok(id) = window_loop.request_animation_frame(
                         closure_cell_clone
@@ -850,14 +1882,21 @@ pub fn run_view(props: &RunViewProps) -> Html {
                 let mining = mining_setup.clone();
                 Closure::wrap(Box::new(move || {
                     let mut m = mining.borrow_mut();
-                    if !m.active || !m.mouse_down {
+                    if !m.mining_now() && m.queue.is_empty() {
                         return;
                     }
                     let handle = run_state_ref_ct.borrow().clone();
                     let rs_snap = (*handle).clone();
+                    if rs_snap.game_over {
+                        m.clear_queue();
+                    }
                     if rs_snap.is_paused {
                         return;
                     }
+                    // Queued rocks carry on by themselves once the current tile is done
+                    if !m.mining_now() && !m.advance_queue(&rs_snap) {
+                        return;
+                    }
                     let gs = rs_snap.grid_size;
                     if m.tile_x < 0
                         || m.tile_y < 0
@@ -875,21 +1914,16 @@ pub fn run_view(props: &RunViewProps) -> Html {
                         m.elapsed_secs += 0.016;
                         m.progress = (m.elapsed_secs / m.required_secs).min(1.0);
                         if m.progress >= 1.0 {
-                            clog(&format!("MiningComplete idx={}", idx));
+                            log(Level::Debug, &format!("MiningComplete idx={}", idx));
                             drop(m);
                             handle.dispatch(RunAction::MiningComplete { idx });
-                            let mut m2 = mining.borrow_mut();
-                            m2.active = false;
-                            m2.mouse_down = false;
-                            m2.progress = 0.0;
-                            m2.elapsed_secs = 0.0;
+                            mining.borrow_mut().complete(idx);
                         } else if !rs_snap.started {
                             drop(m);
                             handle.dispatch(RunAction::StartRun);
                         }
                     } else {
-                        m.active = false;
-                        m.mouse_down = false;
+                        m.stop();
                     }
                 }) as Box<dyn FnMut()>)
             };
@@ -904,7 +1938,7 @@ pub fn run_view(props: &RunViewProps) -> Html {
                 let run_state_ref_ct = run_state_ref.clone();
                 Closure::wrap(Box::new(move || {
                     let handle = run_state_ref_ct.borrow().clone();
-                    handle.dispatch(RunAction::SimTick { dt: 0.016 });
+                    handle.dispatch(RunAction::SimTick { dt: SIM_TICK_DT });
                 }) as Box<dyn FnMut()>)
             };
             let sim_tick_id = window
@@ -931,6 +1965,8 @@ pub fn run_view(props: &RunViewProps) -> Html {
             let wheel_cb = {
                 let camera = camera.clone();
                 let draw_ref = draw_ref_setup.clone();
+                let run_state_ref_w = run_state_ref.clone();
+                let canvas_w = canvas.clone();
                 Closure::wrap(Box::new(move |e: web_sys::WheelEvent| {
                     e.prevent_default();
                     let mut cam = camera.borrow_mut();
@@ -946,6 +1982,8 @@ pub fn run_view(props: &RunViewProps) -> Html {
                     let new_scale = cam.zoom * tile_px;
                     cam.offset_x = canvas_x - world_x * new_scale;
                     cam.offset_y = canvas_y - world_y * new_scale;
+                    cam.glide = None;
+                    clamp_camera(&mut cam, &run_state_ref_w.borrow(), &canvas_w);
                     drop(cam);
                     if let Some(f) = &*draw_ref.borrow() {
                         f();
@@ -955,27 +1993,97 @@ pub fn run_view(props: &RunViewProps) -> Html {
             canvas
                 .add_event_listener_with_callback("wheel", wheel_cb.as_ref().unchecked_ref())
                 .unwrap();
-            // Keydown + tower hotkey (Space + T)
+            // Keydown + tower hotkey (Space + T), exclusion brush (X), scarecrow (C), spots (G),
+            // prospector (P), stamps (B/R), heatmap (H), cursor (arrows), bookmarks (Ctrl/Alt+1..4),
+            // camera pan (WASD, Shift+arrows; held), developer panel (`)
             let keydown_cb = {
                 let run_state_ref_ct = run_state_ref.clone();
-                let hover_ref = hover_tile_effect_local.clone();
+                let focus_ref = focus_tile_effect_local.clone();
                 let tower_feedback_hotkey = tower_feedback_handle.clone();
                 let draw_ref_k = draw_ref_setup.clone();
                 let show_intro_handle_k = show_intro_handle.clone();
-                let show_debug_k = show_debug.clone();
+                let show_debug_k = show_debug_setup.clone();
+                let prospector_k = prospector_setup.clone();
+                let prospector_flag_k = prospector_flag.clone();
+                let heatmap_k = heatmap_setup.clone();
+                let heatmap_flag_k = heatmap_flag.clone();
+                let stamp_flag_k = stamp_cursor_setup.clone();
+                let stamp_state_k = stamp_cursor_state.clone();
+                let selected_flag_k = selected_tower_setup.clone();
+                let selected_state_k = selected_tower_state.clone();
+                let moving_state_k = moving_tower_state.clone();
                 let selected_tower_kind_k = selected_tower_kind_handle.clone();
+                let tower_kind_k = tower_kind_setup.clone();
+                let brush_k = brush_setup.clone();
+                let analysis_worker_k = analysis_worker.clone();
+                let analysis_client_k = analysis_client_setup.clone();
+                let camera_k = camera.clone();
+                let bookmarks_k = bookmarks_setup.clone();
+                let bookmarks_flag_k = bookmarks_flag_setup.clone();
+                let hotkeys_k = hotkeys_setup.clone();
+                let show_path_k = show_path_setup.clone();
+                let show_path_flag_k = show_path_flag.clone();
+                let canvas_k = canvas.clone();
                 Closure::wrap(Box::new(move |e: web_sys::KeyboardEvent| {
-                    // Spacebar: dismiss intro if showing, else toggle pause
+                    // Keys typed into a text field (bookmark names) aren't hotkeys
+                    if e.target()
+                        .and_then(|t| t.dyn_into::<HtmlInputElement>().ok())
+                        .is_some_and(|input| is_text_entry(&input.type_()))
+                    {
+                        return;
+                    }
                     let key = e.key();
                     let code = e.code();
-                    if code == "Space" || key == " " || key == "Space" || key == "Spacebar" {
+                    if let Some(bk) = bookmark_key(
+                        &code,
+                        e.ctrl_key(),
+                        e.alt_key(),
+                        e.shift_key(),
+                        e.meta_key(),
+                    ) {
+                        e.prevent_default();
+                        let run_id = run_state_ref_ct.borrow().run_id;
+                        match bk {
+                            BookmarkKey::Save(slot) => {
+                                let mut marks = bookmarks_flag_k.borrow().clone();
+                                marks.save(run_id, slot, &camera_k.borrow());
+                                let label = marks.get(run_id, slot).map(|b| b.label.clone());
+                                bookmarks_k.set(marks);
+                                tower_feedback_hotkey
+                                    .set(format!("Saved {}", label.unwrap_or_default()));
+                            }
+                            BookmarkKey::Recall(slot) => {
+                                let now = js_sys::Date::now();
+                                let mut cam = camera_k.borrow_mut();
+                                if !bookmarks_flag_k
+                                    .borrow()
+                                    .recall(run_id, slot, &mut cam, now)
+                                {
+                                    tower_feedback_hotkey.set(format!(
+                                        "No view in slot {0} yet (Ctrl+{0} saves one)",
+                                        slot + 1
+                                    ));
+                                }
+                            }
+                        }
+                        return;
+                    }
+                    // Pan keys are held: the RAF loop moves the camera until keyup
+                    if let Some(dir) = PanDir::from_code(&code, e.shift_key())
+                        .filter(|_| !e.ctrl_key() && !e.alt_key() && !e.meta_key())
+                    {
+                        e.prevent_default();
+                        camera_k.borrow_mut().hold_pan(dir, true);
+                        return;
+                    }
+                    let action = hotkeys_k.borrow().action(&code);
+                    if action == Some(HotkeyAction::TogglePause) {
                         e.prevent_default();
+                        // Dismisses the intro first
                         if *show_intro_handle_k {
                             show_intro_handle_k.set(false);
-                            if let Some(win) = web_sys::window() {
-                                if let Ok(Some(store)) = win.local_storage() {
-                                    let _ = store.set_item("md_intro_seen", "1");
-                                }
+                            if let Some(mut store) = LocalStorage::open() {
+                                persistence::write(&mut store, "md_intro_seen", "1");
                             }
                             return;
                         }
@@ -985,23 +2093,41 @@ pub fn run_view(props: &RunViewProps) -> Html {
                         }
                         return;
                     }
+                    if let Some(faster) = speed_key(&code) {
+                        let handle = run_state_ref_ct.borrow().clone();
+                        let speed = model::step_sim_speed(handle.sim_speed, faster);
+                        handle.dispatch(RunAction::SetSimSpeed { speed });
+                        tower_feedback_hotkey.set(format!("Speed {}x", speed));
+                        return;
+                    }
+                    // Fixed keys: 1/2/3 pick a tower kind, arrows move the keyboard cursor
                     match key.as_str() {
-                        "1" => {
-                            *selected_tower_kind_k.borrow_mut() = model::TowerKind::Basic;
-                            if let Some(f) = &*draw_ref_k.borrow() {
-                                f();
-                            }
+                        "1" | "2" | "3" => {
+                            let kind = match key.as_str() {
+                                "1" => model::TowerKind::Basic,
+                                "2" => model::TowerKind::Slow,
+                                _ => model::TowerKind::Damage,
+                            };
+                            tower_kind_k.set(kind);
                             return;
                         }
-                        "2" => {
-                            *selected_tower_kind_k.borrow_mut() = model::TowerKind::Slow;
-                            if let Some(f) = &*draw_ref_k.borrow() {
-                                f();
-                            }
+                        "Escape" if selected_flag_k.borrow().is_some() => {
+                            selected_state_k.set(None);
+                            moving_state_k.set(None);
                             return;
                         }
-                        "3" => {
-                            *selected_tower_kind_k.borrow_mut() = model::TowerKind::Damage;
+                        "ArrowUp" | "ArrowDown" | "ArrowLeft" | "ArrowRight" => {
+                            e.prevent_default();
+                            let (dx, dy) = match key.as_str() {
+                                "ArrowUp" => (0, -1),
+                                "ArrowDown" => (0, 1),
+                                "ArrowLeft" => (-1, 0),
+                                _ => (1, 0),
+                            };
+                            let gs = run_state_ref_ct.borrow().grid_size;
+                            focus_ref
+                                .borrow_mut()
+                                .step(FocusSource::Keyboard, dx, dy, gs);
                             if let Some(f) = &*draw_ref_k.borrow() {
                                 f();
                             }
@@ -1009,106 +2135,240 @@ pub fn run_view(props: &RunViewProps) -> Html {
                         }
                         _ => {}
                     }
-                    // T: place/remove tower
-                    if key == "t" || key == "T" {
-                        e.prevent_default();
-                        let (hx, hy) = *hover_ref.borrow();
-                        if hx < 0 || hy < 0 {
-                            return;
-                        }
-                        let kind = selected_tower_kind_k.borrow().clone();
-                        let handle = run_state_ref_ct.borrow().clone();
-                        let rs = (*handle).clone();
-                        if rs.game_over {
-                            return;
-                        }
-                        let was_paused = rs.is_paused; // remember paused state
-                        let gs = rs.grid_size;
-                        if (hx as u32) >= gs.width || (hy as u32) >= gs.height {
-                            return;
+                    let Some(action) = action else {
+                        return;
+                    };
+                    match action {
+                        HotkeyAction::CycleTowerKind => {
+                            tower_kind_k.set(selected_tower_kind_k.borrow().next());
                         }
-                        let interact_mask = compute_interactable_mask(&rs);
-                        let idx = (hy as u32 * gs.width + hx as u32) as usize;
-                        if !interact_mask[idx] {
-                            tower_feedback_hotkey.set("Out of reach".into());
-                            return;
+                        HotkeyAction::ExclusionBrush => {
+                            let mut b = brush_k.borrow_mut();
+                            b.active = !b.active;
+                            b.stroke = None;
+                            tower_feedback_hotkey.set(if b.active {
+                                format!(
+                                    "Exclusion brush: drag to paint no-target tiles ({} to exit)",
+                                    hotkeys_k.borrow().label(HotkeyAction::ExclusionBrush)
+                                )
+                            } else {
+                                "Exclusion brush off".into()
+                            });
                         }
-                        if let model::TileKind::Rock { .. } = rs.tiles[idx].kind {
-                            let has_t = rs
-                                .towers
-                                .iter()
-                                .any(|t| t.x == hx as u32 && t.y == hy as u32);
-                            if has_t {
-                                handle.dispatch(RunAction::RemoveTower {
-                                    x: hx as u32,
-                                    y: hy as u32,
-                                });
-                                tower_feedback_hotkey.set("Tower removed".into());
+                        // Place or sell a tower on the focused tile
+                        HotkeyAction::PlaceTower => {
+                            e.prevent_default();
+                            let (hx, hy) = focus_ref.borrow().tile();
+                            if hx < 0 || hy < 0 {
+                                return;
+                            }
+                            let kind = selected_tower_kind_k.borrow().clone();
+                            let handle = run_state_ref_ct.borrow().clone();
+                            let rs = (*handle).clone();
+                            if rs.game_over {
+                                return;
+                            }
+                            let was_paused = rs.is_paused; // remember paused state
+                            let gs = rs.grid_size;
+                            if (hx as u32) >= gs.width || (hy as u32) >= gs.height {
+                                return;
+                            }
+                            let (x, y) = (hx as u32, hy as u32);
+                            let idx = (y * gs.width + x) as usize;
+                            if rs.tower_at(x, y).is_some() && compute_interactable_mask(&rs)[idx] {
+                                handle.dispatch(RunAction::SellTower { x, y });
+                                tower_feedback_hotkey.set("Tower sold".into());
                                 // Do NOT auto-unpause on removal (spec only asks for placement)
-                            } else if rs.currencies.gold < rs.tower_cost {
-                                tower_feedback_hotkey.set(format!("Need {} gold", rs.tower_cost));
                             } else {
-                                if !rs.started {
-                                    handle.dispatch(RunAction::StartRun);
+                                let check = model::placement_check(&rs, x, y, &kind);
+                                if check.is_valid() {
+                                    handle.dispatch(RunAction::PlaceTowerAndStart {
+                                        x,
+                                        y,
+                                        kind,
+                                        unpause: was_paused,
+                                    });
+                                    tower_feedback_hotkey.set("Tower placed".into());
+                                } else {
+                                    tower_feedback_hotkey.set(check.message());
                                 }
-                                handle.dispatch(RunAction::PlaceTower {
-                                    x: hx as u32,
-                                    y: hy as u32,
-                                    kind: kind.clone(),
-                                });
-                                tower_feedback_hotkey.set("Tower placed".into());
-                                if was_paused {
-                                    handle.dispatch(RunAction::TogglePause);
+                            }
+                            if let Some(f) = &*draw_ref_k.borrow() {
+                                f();
+                            }
+                        }
+                        // Buy the focused tower's next level
+                        HotkeyAction::UpgradeTower => {
+                            let (hx, hy) = focus_ref.borrow().tile();
+                            let handle = run_state_ref_ct.borrow().clone();
+                            if hx < 0 || hy < 0 || handle.game_over {
+                                return;
+                            }
+                            let (x, y) = (hx as u32, hy as u32);
+                            let Some(tw) = handle.tower_at(x, y) else {
+                                tower_feedback_hotkey.set("No tower here".into());
+                                return;
+                            };
+                            match model::tower_upgrade_cost(&handle, tw) {
+                                None => tower_feedback_hotkey.set("Tower at max level".into()),
+                                Some(cost) if handle.currencies.gold < cost => {
+                                    tower_feedback_hotkey.set(format!("Need {} gold", cost))
+                                }
+                                Some(cost) => {
+                                    let level = tw.level + 1;
+                                    handle.dispatch(RunAction::UpgradeTower { x, y });
+                                    tower_feedback_hotkey.set(format!(
+                                        "Tower upgraded to level {} (-{}g)",
+                                        level, cost
+                                    ));
                                 }
                             }
-                        } else if let model::TileKind::Wall = rs.tiles[idx].kind {
-                            let has_t = rs
-                                .towers
-                                .iter()
-                                .any(|t| t.x == hx as u32 && t.y == hy as u32);
-                            if has_t {
-                                handle.dispatch(RunAction::RemoveTower {
-                                    x: hx as u32,
-                                    y: hy as u32,
-                                });
-                                tower_feedback_hotkey.set("Tower removed".into());
-                            } else if rs.currencies.gold < rs.tower_cost {
-                                tower_feedback_hotkey.set(format!("Need {} gold", rs.tower_cost));
-                            } else {
-                                if !rs.started {
-                                    handle.dispatch(RunAction::StartRun);
+                        }
+                        // Buy a scarecrow with research on the focused rock
+                        HotkeyAction::Scarecrow => {
+                            let (hx, hy) = focus_ref.borrow().tile();
+                            let handle = run_state_ref_ct.borrow().clone();
+                            if hx < 0 || hy < 0 || handle.game_over {
+                                return;
+                            }
+                            let (x, y) = (hx as u32, hy as u32);
+                            let kind = model::StructureKind::Scarecrow;
+                            match model::structure_placement_error(&handle, x, y, kind) {
+                                Some(msg) => tower_feedback_hotkey.set(msg),
+                                None => {
+                                    handle.dispatch(RunAction::SpendResearchOnStructure {
+                                        x,
+                                        y,
+                                        kind,
+                                    });
+                                    tower_feedback_hotkey.set(format!(
+                                        "Scarecrow raised (-{} research)",
+                                        kind.research_cost()
+                                    ));
                                 }
-                                handle.dispatch(RunAction::PlaceTower {
-                                    x: hx as u32,
-                                    y: hy as u32,
-                                    kind,
-                                });
-                                tower_feedback_hotkey.set("Tower placed".into());
-                                if was_paused {
-                                    handle.dispatch(RunAction::TogglePause);
+                            }
+                        }
+                        // Ask the analysis worker for the best tower spots
+                        HotkeyAction::SuggestSpots => {
+                            let range = run_state_ref_ct.borrow().tower_base_range;
+                            let msg = analysis_client_k
+                                .borrow_mut()
+                                .request(AnalysisKind::SuggestSpots { range, count: 3 });
+                            match (&*analysis_worker_k.borrow(), msg) {
+                                (Some(worker), Some(msg)) => {
+                                    worker.post(&msg);
+                                    tower_feedback_hotkey.set("Looking for tower spots...".into());
                                 }
+                                _ => tower_feedback_hotkey.set("Map analysis unavailable".into()),
                             }
-                        } else {
-                            tower_feedback_hotkey.set("Need Rock/Wall".into());
                         }
-                        if let Some(f) = &*draw_ref_k.borrow() {
-                            f();
+                        HotkeyAction::CycleStamp => {
+                            let mut cursor = *stamp_flag_k.borrow();
+                            cursor.cycle();
+                            stamp_state_k.set(cursor);
                         }
-                    }
-                    if key == "d" || key == "D" {
-                        show_debug_k.set(!*show_debug_k);
+                        // Rotates the selected stamp; without one, steps the tower's target mode
+                        HotkeyAction::TargetMode if stamp_flag_k.borrow().selected.is_some() => {
+                            let mut cursor = *stamp_flag_k.borrow();
+                            cursor.rotate();
+                            stamp_state_k.set(cursor);
+                        }
+                        HotkeyAction::TargetMode => {
+                            let (hx, hy) = focus_ref.borrow().tile();
+                            let handle = run_state_ref_ct.borrow().clone();
+                            if hx < 0 || hy < 0 {
+                                return;
+                            }
+                            let (x, y) = (hx as u32, hy as u32);
+                            match handle.tower_at(x, y) {
+                                Some(tw) => {
+                                    let next = tw.target_mode.next();
+                                    handle.dispatch(RunAction::CycleTargetMode { x, y });
+                                    tower_feedback_hotkey
+                                        .set(format!("Targeting: {}", next.name()));
+                                }
+                                None => tower_feedback_hotkey.set("No tower here".into()),
+                            }
+                        }
+                        // Overlay toggles; the flags track the live settings
+                        HotkeyAction::Prospector => prospector_k.set(!*prospector_flag_k.borrow()),
+                        HotkeyAction::Heatmap => heatmap_k.set(!*heatmap_flag_k.borrow()),
+                        HotkeyAction::ShowPath => show_path_k.set(!*show_path_flag_k.borrow()),
+                        HotkeyAction::CenterCamera => {
+                            let handle = run_state_ref_ct.borrow().clone();
+                            if let Some((sx, sy)) = start_tile(&handle.tiles, handle.grid_size) {
+                                let mut cam = camera_k.borrow_mut();
+                                cam.center_on(
+                                    sx as f64 + 0.5,
+                                    sy as f64 + 0.5,
+                                    canvas_k.width() as f64,
+                                    canvas_k.height() as f64,
+                                    32.0,
+                                );
+                                clamp_camera(&mut cam, &handle, &canvas_k);
+                                drop(cam);
+                                if let Some(f) = &*draw_ref_k.borrow() {
+                                    f();
+                                }
+                            }
+                        }
+                        HotkeyAction::CyclePanSpeed => {
+                            let speed = camera_k.borrow_mut().cycle_pan_speed();
+                            tower_feedback_hotkey.set(format!("Pan speed: {speed}x"));
+                        }
+                        HotkeyAction::UndoWall => {
+                            let handle = run_state_ref_ct.borrow().clone();
+                            if model::undoable_wall(&handle).is_some() {
+                                handle.dispatch(RunAction::UndoLastWall);
+                                tower_feedback_hotkey.set("Wall removed".into());
+                            } else {
+                                tower_feedback_hotkey.set("Nothing to undo".into());
+                            }
+                        }
+                        HotkeyAction::DebugPanel => show_debug_k.set(!*show_debug_k),
+                        HotkeyAction::TogglePause => {}
                     }
                 }) as Box<dyn FnMut(_)>)
             };
             window
                 .add_event_listener_with_callback("keydown", keydown_cb.as_ref().unchecked_ref())
                 .ok();
+            // Released pan keys stop the camera, whatever Shift is doing by then
+            let keyup_cb = {
+                let camera = camera.clone();
+                Closure::wrap(Box::new(move |e: web_sys::KeyboardEvent| {
+                    if let Some(dir) = PanDir::from_code(&e.code(), true) {
+                        camera.borrow_mut().hold_pan(dir, false);
+                    }
+                }) as Box<dyn FnMut(_)>)
+            };
+            window
+                .add_event_listener_with_callback("keyup", keyup_cb.as_ref().unchecked_ref())
+                .ok();
+            // Keys let go while another window had focus never send keyup
+            let blur_cb = {
+                let camera = camera.clone();
+                Closure::wrap(Box::new(move |_e: web_sys::Event| {
+                    camera.borrow_mut().release_pan();
+                }) as Box<dyn FnMut(_)>)
+            };
+            window
+                .add_event_listener_with_callback("blur", blur_cb.as_ref().unchecked_ref())
+                .ok();
             // Mouse events
             let mousedown_cb = {
                 let camera = camera.clone();
                 let mining = mining_setup.clone();
                 let run_state_ref_ct = run_state_ref.clone();
                 let draw_ref = draw_ref_setup.clone();
+                let brush = brush_setup.clone();
+                let stamp_md = stamp_cursor_setup.clone();
+                let wall_drag_md = wall_drag_setup.clone();
+                let tower_feedback_md = tower_feedback_handle.clone();
+                let selected_flag_md = selected_tower_setup.clone();
+                let selected_state_md = selected_tower_state.clone();
+                let moving_flag_md = moving_tower_setup.clone();
+                let moving_state_md = moving_tower_state.clone();
                 Closure::wrap(Box::new(move |e: web_sys::MouseEvent| {
                     if e.button() == 0 {
                         let cam = camera.borrow_mut();
@@ -1119,12 +2379,88 @@ pub fn run_view(props: &RunViewProps) -> Html {
                         drop(cam);
                         let handle = run_state_ref_ct.borrow().clone();
                         let rs = (*handle).clone();
+                        // Brush paints instead of mining, paused or not
+                        let mut b = brush.borrow_mut();
+                        if b.active {
+                            let (tx, ty) = (world_x.floor() as i32, world_y.floor() as i32);
+                            let gs = rs.grid_size;
+                            if tx >= 0
+                                && ty >= 0
+                                && (tx as u32) < gs.width
+                                && (ty as u32) < gs.height
+                            {
+                                let on = !rs.no_target.get(tx as u32, ty as u32);
+                                b.stroke = Some(on);
+                                b.last_tile = (tx, ty);
+                                handle.dispatch(RunAction::SetNoTarget {
+                                    x: tx as u32,
+                                    y: ty as u32,
+                                    on,
+                                });
+                            }
+                            return;
+                        }
+                        drop(b);
+                        // A selected stamp applies at the clicked tile instead of mining
+                        let stamp = *stamp_md.borrow();
+                        if let Some(id) = stamp.selected {
+                            let (tx, ty) = (world_x.floor() as i32, world_y.floor() as i32);
+                            let gs = rs.grid_size;
+                            if tx < 0 || ty < 0 || tx as u32 >= gs.width || ty as u32 >= gs.height {
+                                return;
+                            }
+                            let origin = (tx as u32, ty as u32);
+                            match model::stamp_check(&rs, origin, id, stamp.rotation) {
+                                Ok(cost) => {
+                                    handle.dispatch(RunAction::ApplyStamp {
+                                        origin,
+                                        stamp_id: id,
+                                        rotation: stamp.rotation,
+                                    });
+                                    tower_feedback_md.set(format!(
+                                        "{} stamped (-{} credits)",
+                                        id.def().name,
+                                        cost
+                                    ));
+                                }
+                                Err(rej) => tower_feedback_md.set(rej.reason),
+                            }
+                            return;
+                        }
+                        // Clicking a tower selects it, paused or not; any other click lets go
+                        let (tx, ty) = (world_x.floor() as i32, world_y.floor() as i32);
+                        let on_tower = (tx >= 0 && ty >= 0)
+                            .then(|| rs.tower_at(tx as u32, ty as u32))
+                            .flatten()
+                            .map(|tw| (tw.x, tw.y));
+                        // A pending move takes this click as the tower's new tile; clicking
+                        // another tower just selects that one instead
+                        let moving = *moving_flag_md.borrow();
+                        if let Some(from) = moving {
+                            moving_state_md.set(None);
+                            if on_tower.is_none() && rs.tower_at(from.0, from.1).is_some() {
+                                let to = (tx as u32, ty as u32);
+                                if tx >= 0 && ty >= 0 && model::can_move_tower(&rs, from, to) {
+                                    handle.dispatch(RunAction::MoveTower { from, to });
+                                    selected_state_md.set(Some(to));
+                                    tower_feedback_md.set("Tower moved".into());
+                                } else {
+                                    tower_feedback_md
+                                        .set("Towers can only move onto free rock or wall".into());
+                                }
+                                return;
+                            }
+                        }
+                        if *selected_flag_md.borrow() != on_tower {
+                            selected_state_md.set(on_tower);
+                        }
+                        if on_tower.is_some() {
+                            return;
+                        }
                         if rs.is_paused {
                             return;
                         }
                         let gs = rs.grid_size;
-                        let tx = world_x.floor() as i32;
-                        let ty = world_y.floor() as i32;
                         if tx >= 0 && ty >= 0 && (tx as u32) < gs.width && (ty as u32) < gs.height {
                             let idx = (ty as u32 * gs.width + tx as u32) as usize;
                             let interact_mask = compute_interactable_mask(&rs);
@@ -1132,37 +2468,39 @@ pub fn run_view(props: &RunViewProps) -> Html {
                                 return;
                             }
                             match rs.tiles[idx].kind {
-                                model::TileKind::Rock { .. } | model::TileKind::Wall => {
-                                    if !rs
-                                        .towers
-                                        .iter()
-                                        .any(|t| t.x == tx as u32 && t.y == ty as u32)
-                                    {
-                                        if !rs.started {
-                                            handle.dispatch(RunAction::StartRun);
-                                        }
-                                        let mut m = mining.borrow_mut();
-                                        m.tile_x = tx;
-                                        m.tile_y = ty;
-                                        let hardness = rs.tiles[idx].hardness.max(1) as f64;
-                                        let spd = rs.mining_speed.max(0.0001);
-                                        m.required_secs = hardness / spd;
-                                        m.elapsed_secs = 0.0;
-                                        m.progress = 0.0;
-                                        m.active = true;
-                                        m.mouse_down = true;
+                                model::TileKind::Rock { .. } | model::TileKind::Wall
+                                    if rs.tower_at(tx as u32, ty as u32).is_none()
+                                        && !rs
+                                            .structures
+                                            .iter()
+                                            .any(|s| s.x == tx as u32 && s.y == ty as u32) =>
+                                {
+                                    if !rs.started {
+                                        handle.dispatch(RunAction::StartRun);
+                                    }
+                                    let mut m = mining.borrow_mut();
+                                    if e.shift_key() {
+                                        // Shift queues the rock (or takes it back off);
+                                        // an idle miner starts on it right away
+                                        m.toggle_queued(tx, ty);
+                                        m.advance_queue(&rs);
+                                    } else {
+                                        // Holding on a rock takes over from the queue
+                                        m.clear_queue();
+                                        m.begin(&rs, tx, ty, true);
                                     }
                                 }
                                 model::TileKind::Empty => {
-                                    let mut m = mining.borrow_mut();
-                                    m.active = false;
-                                    m.mouse_down = false;
-                                    m.progress = 0.0;
-                                    m.elapsed_secs = 0.0;
-                                    handle.dispatch(RunAction::PlaceWall {
-                                        x: tx as u32,
-                                        y: ty as u32,
-                                    });
+                                    mining.borrow_mut().release();
+                                    // Shift starts a drag, placed as one run on mouseup
+                                    if e.shift_key() {
+                                        wall_drag_md.borrow_mut().start(&rs, tx, ty);
+                                    } else {
+                                        handle.dispatch(RunAction::PlaceWall {
+                                            x: tx as u32,
+                                            y: ty as u32,
+                                        });
+                                    }
                                 }
                                 _ => {}
                             }
@@ -1170,6 +2508,7 @@ pub fn run_view(props: &RunViewProps) -> Html {
                     } else {
                         let mut cam = camera.borrow_mut();
                         cam.panning = true;
+                        cam.glide = None;
                         cam.last_x = e.client_x() as f64;
                         cam.last_y = e.client_y() as f64;
                     }
@@ -1189,9 +2528,24 @@ pub fn run_view(props: &RunViewProps) -> Html {
                 let mining = mining_setup.clone();
                 let run_state_ref_ct = run_state_ref.clone();
                 let draw_ref = draw_ref_setup.clone();
-                let hover_tile_move = hover_tile_effect_local.clone();
+                let focus_tile_move = focus_tile_effect_local.clone();
+                let wall_preview_move = wall_preview_effect.clone();
+                let wall_drag_move = wall_drag_setup.clone();
+                let brush = brush_setup.clone();
+                let edge_scroll_move = edge_scroll_flag.clone();
+                let canvas_move = canvas.clone();
+                let cursor_move = cursor_world_setup.clone();
                 Closure::wrap(Box::new(move |e: web_sys::MouseEvent| {
+                    wall_preview_move.borrow_mut().shift_held = e.shift_key();
                     let mut cam = camera.borrow_mut();
+                    if *edge_scroll_move.borrow() {
+                        cam.pan_edge = edge_scroll_dir(
+                            e.offset_x() as f64,
+                            e.offset_y() as f64,
+                            canvas_move.width() as f64,
+                            canvas_move.height() as f64,
+                        );
+                    }
                     if cam.panning {
                         let x = e.client_x() as f64;
                         let y = e.client_y() as f64;
@@ -1201,6 +2555,7 @@ pub fn run_view(props: &RunViewProps) -> Html {
                         cam.last_y = y;
                         cam.offset_x += dx;
                         cam.offset_y += dy;
+                        clamp_camera(&mut cam, &run_state_ref_ct.borrow(), &canvas_move);
                         drop(cam);
                         if let Some(f) = &*draw_ref.borrow() {
                             f();
@@ -1212,9 +2567,28 @@ pub fn run_view(props: &RunViewProps) -> Html {
                     let world_x = ((e.offset_x() as f64) - cam.offset_x) / scale_px;
                     let world_y = ((e.offset_y() as f64) - cam.offset_y) / scale_px;
                     drop(cam);
+                    *cursor_move.borrow_mut() = Some((world_x, world_y));
                     let tx = world_x.floor() as i32;
                     let ty = world_y.floor() as i32;
-                    *hover_tile_move.borrow_mut() = (tx, ty);
+                    focus_tile_move.borrow_mut().on_mouse((tx, ty));
+                    if wall_drag_move.borrow().active {
+                        let handle = run_state_ref_ct.borrow().clone();
+                        wall_drag_move.borrow_mut().extend(&handle, tx, ty);
+                    }
+                    {
+                        let mut b = brush.borrow_mut();
+                        if let Some(on) = b.stroke.filter(|_| b.last_tile != (tx, ty)) {
+                            b.last_tile = (tx, ty);
+                            if tx >= 0 && ty >= 0 {
+                                let handle = run_state_ref_ct.borrow().clone();
+                                handle.dispatch(RunAction::SetNoTarget {
+                                    x: tx as u32,
+                                    y: ty as u32,
+                                    on,
+                                });
+                            }
+                        }
+                    }
                     {
                         let mut m = mining.borrow_mut();
                         if m.mouse_down && m.active {
@@ -1237,7 +2611,7 @@ pub fn run_view(props: &RunViewProps) -> Html {
                                                 m.tile_x = tx;
                                                 m.tile_y = ty;
                                                 let hardness = rs.tiles[idx].hardness.max(1) as f64;
-                                                let spd = rs.mining_speed.max(0.0001);
+                                                let spd = rs.effective_mining_speed().max(0.0001);
                                                 m.required_secs = hardness / spd;
                                                 m.elapsed_secs = 0.0;
                                                 m.progress = 0.0;
@@ -1270,16 +2644,30 @@ pub fn run_view(props: &RunViewProps) -> Html {
                 let camera = camera.clone();
                 let mining = mining_setup.clone();
                 let draw_ref = draw_ref_setup.clone();
+                let brush = brush_setup.clone();
+                let wall_drag_up = wall_drag_setup.clone();
+                let run_state_ref_up = run_state_ref.clone();
+                let tower_feedback_up = tower_feedback_handle.clone();
                 Closure::wrap(Box::new(move |_e: web_sys::MouseEvent| {
                     let mut cam = camera.borrow_mut();
                     cam.panning = false;
                     drop(cam);
-                    let mut m = mining.borrow_mut();
-                    m.mouse_down = false;
-                    m.active = false;
-                    m.progress = 0.0;
-                    m.elapsed_secs = 0.0;
-                    drop(m);
+                    brush.borrow_mut().stroke = None;
+                    let (tiles, valid) = wall_drag_up.borrow_mut().finish();
+                    if !tiles.is_empty() {
+                        let blocked = tiles.len() - valid;
+                        run_state_ref_up
+                            .borrow()
+                            .dispatch(RunAction::PlaceWalls { tiles });
+                        tower_feedback_up.set(if blocked > 0 {
+                            format!(
+                                "Placed {valid} walls; {blocked} left out (they would block the path)"
+                            )
+                        } else {
+                            format!("Placed {valid} walls")
+                        });
+                    }
+                    mining.borrow_mut().release();
                     if let Some(f) = &*draw_ref.borrow() {
                         f();
                     }
@@ -1288,6 +2676,20 @@ pub fn run_view(props: &RunViewProps) -> Html {
             window
                 .add_event_listener_with_callback("mouseup", mouseup_cb.as_ref().unchecked_ref())
                 .unwrap();
+            let mouseleave_cb = {
+                let camera = camera.clone();
+                let cursor_leave = cursor_world_setup.clone();
+                Closure::wrap(Box::new(move |_e: web_sys::MouseEvent| {
+                    camera.borrow_mut().pan_edge = (0.0, 0.0);
+                    *cursor_leave.borrow_mut() = None;
+                }) as Box<dyn FnMut(_)>)
+            };
+            canvas
+                .add_event_listener_with_callback(
+                    "mouseleave",
+                    mouseleave_cb.as_ref().unchecked_ref(),
+                )
+                .unwrap();
             let contextmenu_cb = {
                 Closure::wrap(Box::new(move |e: web_sys::Event| {
                     e.prevent_default();
@@ -1301,17 +2703,47 @@ pub fn run_view(props: &RunViewProps) -> Html {
                 .unwrap();
             let resize_cb = {
                 let compute_and_apply_canvas_size = compute_and_apply_canvas_size.clone();
-                let draw_ref = draw_ref_setup.clone();
+                let scheduler = scheduler_setup.clone();
+                let terrain = terrain_setup.clone();
                 Closure::wrap(Box::new(move |_e: web_sys::Event| {
                     compute_and_apply_canvas_size();
-                    if let Some(f) = &*draw_ref.borrow() {
-                        f();
-                    }
+                    terrain.borrow_mut().invalidate();
+                    scheduler.borrow_mut().request_redraw();
                 }) as Box<dyn FnMut(_)>)
             };
             window
                 .add_event_listener_with_callback("resize", resize_cb.as_ref().unchecked_ref())
                 .unwrap();
+            let visibility_cb = {
+                let document = document.clone();
+                let run_state_ref_ct = run_state_ref.clone();
+                let catch_up = catch_up_setup.clone();
+                let pause_when_hidden = pause_when_hidden_flag.clone();
+                let catch_up_mode = catch_up_mode.clone();
+                Closure::wrap(Box::new(move |_e: web_sys::Event| {
+                    let now = js_sys::Date::now();
+                    if document.hidden() {
+                        let handle = run_state_ref_ct.borrow().clone();
+                        let running = handle.started && !handle.is_paused && !handle.game_over;
+                        // A paused run has nothing to catch up on when the tab returns
+                        if *pause_when_hidden.borrow() && running {
+                            handle.dispatch(RunAction::TogglePause);
+                        } else {
+                            catch_up.borrow_mut().on_hidden(now);
+                        }
+                    } else {
+                        let handle = run_state_ref_ct.borrow().clone();
+                        let mode = *catch_up_mode.borrow();
+                        catch_up.borrow_mut().on_visible(now, &handle, mode);
+                    }
+                }) as Box<dyn FnMut(_)>)
+            };
+            document
+                .add_event_listener_with_callback(
+                    "visibilitychange",
+                    visibility_cb.as_ref().unchecked_ref(),
+                )
+                .unwrap();
             // Touch
             let touch_start_cb = {
                 let canvas_tc = canvas.clone();
@@ -1355,15 +2787,8 @@ pub fn run_view(props: &RunViewProps) -> Html {
                                             handle.dispatch(RunAction::StartRun);
                                         }
                                         let mut m = mining_tc.borrow_mut();
-                                        let hardness = rs_snap.tiles[idx].hardness.max(1) as f64;
-                                        let spd = rs_snap.mining_speed.max(0.0001);
-                                        m.tile_x = tx;
-                                        m.tile_y = ty;
-                                        m.required_secs = hardness / spd;
-                                        m.elapsed_secs = 0.0;
-                                        m.progress = 0.0;
-                                        m.active = true;
-                                        m.mouse_down = true;
+                                        m.clear_queue();
+                                        m.begin(&rs_snap, tx, ty, true);
                                     }
                                     model::TileKind::Empty => {
                                         handle.dispatch(RunAction::PlaceWall {
@@ -1431,7 +2856,8 @@ pub fn run_view(props: &RunViewProps) -> Html {
                                                 m.tile_y = ty;
                                                 let hardness =
                                                     rs_snap.tiles[idx].hardness.max(1) as f64;
-                                                let spd = rs_snap.mining_speed.max(0.0001);
+                                                let spd =
+                                                    rs_snap.effective_mining_speed().max(0.0001);
                                                 m.required_secs = hardness / spd;
                                                 m.elapsed_secs = 0.0;
                                                 m.progress = 0.0;
@@ -1454,6 +2880,7 @@ pub fn run_view(props: &RunViewProps) -> Html {
                                     let dy = cy - ts.last_touch_y;
                                     cam2.offset_x += dx;
                                     cam2.offset_y += dy;
+                                    clamp_camera(&mut cam2, &rs_snap, &canvas_tc);
                                     ts.last_touch_x = cx;
                                     ts.last_touch_y = cy;
                                 }
@@ -1484,13 +2911,7 @@ pub fn run_view(props: &RunViewProps) -> Html {
                             let mut cam = camera_tc.borrow_mut();
                             cam.panning = false;
                         }
-                        {
-                            let mut m = mining_tc.borrow_mut();
-                            m.active = false;
-                            m.mouse_down = false;
-                            m.progress = 0.0;
-                            m.elapsed_secs = 0.0;
-                        }
+                        mining_tc.borrow_mut().release();
                     }
                     e.prevent_default();
                 }) as Box<dyn FnMut(_)>)
@@ -1506,7 +2927,12 @@ pub fn run_view(props: &RunViewProps) -> Html {
                 .ok();
             // Cleanup
             let window_clone = window.clone();
+            let document_clone = document.clone();
             move || {
+                let _ = document_clone.remove_event_listener_with_callback(
+                    "visibilitychange",
+                    visibility_cb.as_ref().unchecked_ref(),
+                );
                 let _ = canvas.remove_event_listener_with_callback(
                     "wheel",
                     wheel_cb.as_ref().unchecked_ref(),
@@ -1519,6 +2945,10 @@ pub fn run_view(props: &RunViewProps) -> Html {
                     "mousemove",
                     mousemove_cb.as_ref().unchecked_ref(),
                 );
+                let _ = canvas.remove_event_listener_with_callback(
+                    "mouseleave",
+                    mouseleave_cb.as_ref().unchecked_ref(),
+                );
                 let _ = canvas.remove_event_listener_with_callback(
                     "contextmenu",
                     contextmenu_cb.as_ref().unchecked_ref(),
@@ -1551,12 +2981,20 @@ pub fn run_view(props: &RunViewProps) -> Html {
                     "keydown",
                     keydown_cb.as_ref().unchecked_ref(),
                 );
+                let _ = window_clone.remove_event_listener_with_callback(
+                    "keyup",
+                    keyup_cb.as_ref().unchecked_ref(),
+                );
+                let _ = window_clone
+                    .remove_event_listener_with_callback("blur", blur_cb.as_ref().unchecked_ref());
                 window_clone.clear_interval_with_handle(mining_tick_id);
                 window_clone.clear_interval_with_handle(sim_tick_id);
                 window_clone.clear_interval_with_handle(second_tick_id);
                 if let Some(id) = *raf_id.borrow() {
                     let _ = window_clone.cancel_animation_frame(id);
                 }
+                // Dropping the handle terminates the worker
+                analysis_worker.borrow_mut().take();
                 let _keep_alive = (
                     &mining_tick,
                     &sim_tick,
@@ -1564,15 +3002,40 @@ pub fn run_view(props: &RunViewProps) -> Html {
                     &wheel_cb,
                     &mousedown_cb,
                     &mousemove_cb,
+                    &mouseleave_cb,
                     &mouseup_cb,
                     &touch_start_cb,
                     &touch_move_cb,
                     &touch_end_cb,
                     &keydown_cb,
+                    &keyup_cb,
+                    &blur_cb,
+                    &visibility_cb,
                 );
             }
         });
     }
+    // Effect: toasts (catch-up summary, streak changes) fade after a few seconds
+    {
+        let toast = toast.clone();
+        use_effect_with((*toast).clone(), move |msg| {
+            let win = web_sys::window().filter(|_| msg.is_some());
+            let handle = win.as_ref().and_then(|win| {
+                let clear = Closure::once_into_js(move || toast.set(None));
+                win.set_timeout_with_callback_and_timeout_and_arguments_0(
+                    clear.unchecked_ref(),
+                    6000,
+                )
+                .ok()
+            });
+            // A newer toast (or unmounting) cancels the old timer so it can't clear the new one
+            move || {
+                if let (Some(win), Some(handle)) = (win, handle) {
+                    win.clear_timeout_with_handle(handle);
+                }
+            }
+        });
+    }
     // reset center on run id change
     {
         let camera_ref = camera.clone();
@@ -1647,15 +3110,24 @@ pub fn run_view(props: &RunViewProps) -> Html {
     let mut has_exit = false;
     let mut has_indestructible = false;
     let mut has_wall = false;
+    let mut has_magma = false;
+    let mut has_lava = false;
+    let mut has_healing = false;
     for t in &rs_snapshot.tiles {
         match &t.kind {
-            model::TileKind::Rock { has_gold: hg, .. } => {
+            model::TileKind::Rock { has_gold: hg, boost } => {
+                if *boost == Some(model::BoostKind::Healing) {
+                    has_healing = true;
+                }
                 if *hg {
                     has_gold = true;
+                } else if t.magma {
+                    has_magma = true;
                 } else {
                     has_basic = true;
                 }
             }
+            model::TileKind::Empty if t.magma => has_lava = true,
             model::TileKind::Empty => has_empty = true,
             model::TileKind::Start => has_start = true,
             model::TileKind::Direction { role, .. } => match role {
@@ -1667,6 +3139,10 @@ pub fn run_view(props: &RunViewProps) -> Html {
             _ => {}
         }
     }
+    let enemy_kinds: Vec<model::EnemyKind> = model::EnemyKind::ALL
+        .into_iter()
+        .filter(|k| rs_snapshot.enemies.iter().any(|e| e.kind == *k))
+        .collect();
 
     // Hover tile legend highlight mapping
     let (
@@ -1680,7 +3156,7 @@ pub fn run_view(props: &RunViewProps) -> Html {
         hl_empty,
         hl_wall,
     ) = {
-        let (hx, hy) = *hover_tile.borrow();
+        let (hx, hy) = focus_tile.borrow().tile();
         if hx >= 0 && hy >= 0 {
             let hx_u = hx as u32;
             let hy_u = hy as u32;
@@ -1740,14 +3216,9 @@ pub fn run_view(props: &RunViewProps) -> Html {
                         boost,
                     } => {
                         // Build rock name with boost type
-                        let boost_name = match boost {
-                            Some(model::BoostKind::Slow) => " (Cold)",
-                            Some(model::BoostKind::Damage) => " (Poison)",
-                            Some(model::BoostKind::Fire) => " (Fire)",
-                            Some(model::BoostKind::Range) => " (Healing)",
-                            Some(model::BoostKind::FireRate) => " (Fire Rate)",
-                            None => "",
-                        };
+                        let boost_name = boost
+                            .map(|b| format!(" ({})", b.name()))
+                            .unwrap_or_default();
 
                         let rock_label = if *hg {
                             format!("({},{}) Gold Rock{}", hx_u, hy_u, boost_name)
@@ -1809,8 +3280,97 @@ pub fn run_view(props: &RunViewProps) -> Html {
         }
     };
 
+    let repair_tower_cb: Callback<(u32, u32)> = {
+        let run_state = props.run_state.clone();
+        Callback::from(move |(x, y)| run_state.dispatch(RunAction::RepairTower { x, y }))
+    };
+    let toggle_siege_cb: Callback<()> = {
+        let siege = siege.clone();
+        Callback::from(move |()| siege.set(!*siege))
+    };
+    let toggle_end_route_cb: Callback<()> = {
+        let end_route = end_route.clone();
+        Callback::from(move |()| end_route.set(!*end_route))
+    };
+    let toggle_tower_exclusion_cb: Callback<(u32, u32)> = {
+        let run_state = props.run_state.clone();
+        Callback::from(move |(x, y)| {
+            run_state.dispatch(RunAction::ToggleTowerIgnoreExclusion { x, y })
+        })
+    };
+    let hovered_tower = {
+        let (hx, hy) = focus_tile.borrow().tile();
+        rs_snapshot.tower_at(hx as u32, hy as u32).cloned()
+    };
+    let hovered_tower_stats = hovered_tower
+        .as_ref()
+        .map(|tw| model::tower_stats(&rs_snapshot, tw));
+    let swap_options: Vec<(TowerKind, model::TowerStats, u64)> = hovered_tower
+        .iter()
+        .flat_map(|tw| {
+            let rs = &rs_snapshot;
+            TowerKind::ALL
+                .iter()
+                .filter(|k| **k != tw.kind)
+                .map(move |k| {
+                    let after = model::swapped_tower(rs, tw, k.clone());
+                    (
+                        k.clone(),
+                        model::tower_stats(rs, &after),
+                        model::swap_cost(rs, tw, k),
+                    )
+                })
+        })
+        .collect();
+    let swap_kind_cb: Callback<(u32, u32, TowerKind)> = {
+        let run_state = props.run_state.clone();
+        Callback::from(move |(x, y, kind)| {
+            run_state.dispatch(RunAction::ReplaceTowerKind { x, y, kind })
+        })
+    };
+    let selected_tower_info = selected_tower
+        .and_then(|(x, y)| rs_snapshot.tower_at(x, y))
+        .map(|tw| {
+            let rs = &rs_snapshot;
+            let refund =
+                model::sell_refund(rs.tower_cost_of(&tw.kind), rs.tower_refund_percent, tw.paid);
+            (
+                tw.clone(),
+                model::tower_stats(rs, tw),
+                model::tower_upgrade_cost(rs, tw),
+                model::leveled_tower(tw).map(|next| model::tower_stats(rs, &next)),
+                refund,
+            )
+        });
+    let sell_selected_cb: Callback<(u32, u32)> = {
+        let run_state = props.run_state.clone();
+        let selected_tower = selected_tower.clone();
+        Callback::from(move |(x, y)| {
+            run_state.dispatch(RunAction::SellTower { x, y });
+            selected_tower.set(None);
+        })
+    };
+    let upgrade_selected_cb: Callback<(u32, u32)> = {
+        let run_state = props.run_state.clone();
+        Callback::from(move |(x, y)| run_state.dispatch(RunAction::UpgradeTower { x, y }))
+    };
+    let deselect_tower_cb: Callback<()> = {
+        let selected_tower = selected_tower.clone();
+        let moving_tower = moving_tower.clone();
+        Callback::from(move |()| {
+            selected_tower.set(None);
+            moving_tower.set(None);
+        })
+    };
+    let move_selected_cb: Callback<(u32, u32)> = {
+        let moving_tower = moving_tower.clone();
+        Callback::from(move |at| {
+            let next = (*moving_tower != Some(at)).then_some(at);
+            moving_tower.set(next);
+        })
+    };
     let (hovered_tile_kind, hovered_tile_x, hovered_tile_y) = {
-        let (hx, hy) = *hover_tile.borrow();
+        let (hx, hy) = focus_tile.borrow().tile();
         if hx >= 0 && hy >= 0 {
             let hx_u = hx as u32;
             let hy_u = hy as u32;
@@ -1829,6 +3389,14 @@ pub fn run_view(props: &RunViewProps) -> Html {
     let rs_overlay = (*props.run_state).clone();
     let gold_ov = rs_overlay.currencies.gold;
     let research_ov = rs_overlay.currencies.research;
+    let queued_ov = props.upgrade_queue.first().map(|&id| {
+        let name = model::UPGRADE_DEFS
+            .iter()
+            .find(|d| d.id == id)
+            .map_or(id.key(), |d| d.display_name);
+        let cost = props.upgrade_state.next_cost(id).unwrap_or(0);
+        (name.to_string(), cost, props.upgrade_queue.len())
+    });
     let life_ov = rs_overlay.life;
     let time_ov = rs_overlay.stats.time_survived_secs;
     let paused_ov = rs_overlay.is_paused;
@@ -1854,6 +3422,7 @@ pub fn run_view(props: &RunViewProps) -> Html {
     let zoom_in_cb: Callback<()> = {
         let camera = camera.clone();
         let canvas_ref = canvas_ref.clone();
+        let run_state = props.run_state.clone();
         Callback::from(move |()| {
             if let Some(canvas) = canvas_ref.cast::<HtmlCanvasElement>() {
                 let mut cam = camera.borrow_mut();
@@ -1869,6 +3438,7 @@ pub fn run_view(props: &RunViewProps) -> Html {
                 let new_scale = cam.zoom * tile_px;
                 cam.offset_x = cx - world_x * new_scale;
                 cam.offset_y = cy - world_y * new_scale;
+                clamp_camera(&mut cam, &run_state, &canvas);
             }
             let _ = web_sys::window()
                 .unwrap()
@@ -1878,6 +3448,7 @@ pub fn run_view(props: &RunViewProps) -> Html {
     let zoom_out_cb: Callback<()> = {
         let camera = camera.clone();
         let canvas_ref = canvas_ref.clone();
+        let run_state = props.run_state.clone();
         Callback::from(move |()| {
             if let Some(canvas) = canvas_ref.cast::<HtmlCanvasElement>() {
                 let mut cam = camera.borrow_mut();
@@ -1893,6 +3464,7 @@ pub fn run_view(props: &RunViewProps) -> Html {
                 let new_scale = cam.zoom * tile_px;
                 cam.offset_x = cx - world_x * new_scale;
                 cam.offset_y = cy - world_y * new_scale;
+                clamp_camera(&mut cam, &run_state, &canvas);
             }
             let _ = web_sys::window()
                 .unwrap()
@@ -1901,10 +3473,15 @@ pub fn run_view(props: &RunViewProps) -> Html {
     };
     let pan_cb = |dx: f64, dy: f64| {
         let camera = camera.clone();
+        let canvas_ref = canvas_ref.clone();
+        let run_state = props.run_state.clone();
         Callback::from(move |()| {
             let mut cam = camera.borrow_mut();
             cam.offset_x += dx;
             cam.offset_y += dy;
+            if let Some(canvas) = canvas_ref.cast::<HtmlCanvasElement>() {
+                clamp_camera(&mut cam, &run_state, &canvas);
+            }
             drop(cam);
             let _ = web_sys::window()
                 .unwrap()
@@ -1961,10 +3538,114 @@ pub fn run_view(props: &RunViewProps) -> Html {
         let show_damage_numbers = show_damage_numbers.clone();
         Callback::from(move |()| show_damage_numbers.set(!*show_damage_numbers))
     };
+    let toggle_health_bars_cb: Callback<()> = {
+        let show_health_bars = show_health_bars.clone();
+        Callback::from(move |()| show_health_bars.set(!*show_health_bars))
+    };
     let toggle_secondary_stats_cb: Callback<()> = {
         let show_secondary_stats = show_secondary_stats.clone();
         Callback::from(move |()| show_secondary_stats.set(!*show_secondary_stats))
     };
+    let toggle_tile_labels_cb: Callback<()> = {
+        let show_tile_labels = show_tile_labels.clone();
+        Callback::from(move |()| show_tile_labels.set(!*show_tile_labels))
+    };
+    let toggle_hints_cb: Callback<()> = {
+        let hints = hints.clone();
+        Callback::from(move |()| hints.set(!*hints))
+    };
+    let toggle_manual_collection_cb: Callback<()> = {
+        let manual_collection = manual_collection.clone();
+        Callback::from(move |()| manual_collection.set(!*manual_collection))
+    };
+    let dismiss_hint_cb: Callback<MouseEvent> = {
+        let run_state = props.run_state.clone();
+        Callback::from(move |_| run_state.dispatch(RunAction::DismissHint))
+    };
+    let select_stamp_cb: Callback<Option<model::StampId>> = {
+        let stamp_cursor = stamp_cursor.clone();
+        Callback::from(move |selected| {
+            stamp_cursor.set(StampCursor {
+                selected,
+                ..*stamp_cursor
+            })
+        })
+    };
+    let rotate_stamp_cb: Callback<()> = {
+        let stamp_cursor = stamp_cursor.clone();
+        Callback::from(move |()| {
+            let mut cursor = *stamp_cursor;
+            cursor.rotate();
+            stamp_cursor.set(cursor);
+        })
+    };
+    let toggle_prospector_cb: Callback<()> = {
+        let prospector = prospector.clone();
+        Callback::from(move |()| prospector.set(!*prospector))
+    };
+    let change_hotkeys_cb: Callback<Hotkeys> = {
+        let hotkeys = hotkeys.clone();
+        Callback::from(move |keys| hotkeys.set(keys))
+    };
+    let change_theme_cb: Callback<ThemeSettings> = {
+        let theme = theme.clone();
+        Callback::from(move |t| theme.set(t))
+    };
+    let change_hud_settings_cb: Callback<HudLayoutSettings> = {
+        let hud_settings = hud_settings.clone();
+        Callback::from(move |s| hud_settings.set(s))
+    };
+    let toggle_minimap_cb: Callback<()> = {
+        let minimap = minimap.clone();
+        Callback::from(move |()| minimap.set(!*minimap))
+    };
+    // Minimap click or drag: centre the board on that tile
+    let minimap_jump_cb: Callback<(f64, f64)> = {
+        let camera = camera.clone();
+        let canvas_ref = canvas_ref.clone();
+        let run_state = props.run_state.clone();
+        let scheduler = render_scheduler.clone();
+        Callback::from(move |(x, y): (f64, f64)| {
+            if let Some(canvas) = canvas_ref.cast::<HtmlCanvasElement>() {
+                let mut cam = camera.borrow_mut();
+                cam.center_on(x, y, canvas.width() as f64, canvas.height() as f64, 32.0);
+                clamp_camera(&mut cam, &run_state, &canvas);
+                scheduler.borrow_mut().request_redraw();
+            }
+        })
+    };
+    let toggle_edge_scroll_cb: Callback<()> = {
+        let edge_scroll = edge_scroll.clone();
+        Callback::from(move |()| edge_scroll.set(!*edge_scroll))
+    };
+    let toggle_reduced_effects_cb: Callback<()> = {
+        let reduced_effects = reduced_effects.clone();
+        Callback::from(move |()| reduced_effects.set(!*reduced_effects))
+    };
+    let change_audio_cb: Callback<AudioSettings> = {
+        let audio_settings = audio_settings.clone();
+        Callback::from(move |s| audio_settings.set(s))
+    };
+    let toggle_heartbeat_cb: Callback<()> = {
+        let heartbeat = heartbeat.clone();
+        Callback::from(move |()| heartbeat.set(!*heartbeat))
+    };
+    let toggle_pause_when_hidden_cb: Callback<()> = {
+        let pause_when_hidden = pause_when_hidden.clone();
+        Callback::from(move |()| pause_when_hidden.set(!*pause_when_hidden))
+    };
+    let toggle_long_catch_up_cb: Callback<()> = {
+        let long_catch_up = long_catch_up.clone();
+        Callback::from(move |()| long_catch_up.set(!*long_catch_up))
+    };
+    let toggle_skip_progression_cb: Callback<()> = {
+        let skip_progression = skip_progression.clone();
+        Callback::from(move |()| skip_progression.set(!*skip_progression))
+    };
+    let toggle_map_picker_cb: Callback<()> = {
+        let map_picker_enabled = map_picker_enabled.clone();
+        Callback::from(move |()| map_picker_enabled.set(!*map_picker_enabled))
+    };
     let open_settings_cb: Callback<()> = {
         let open_settings = open_settings.clone();
         Callback::from(move |()| open_settings.set(true))
@@ -1975,9 +3656,197 @@ pub fn run_view(props: &RunViewProps) -> Html {
     };
     // restart & upgrades already callbacks with ()
     let restart_cb_unit: Callback<()> = {
+        let restart = props.restart_run.clone();
+        let map_picker_enabled = map_picker_enabled.clone();
+        let show_map_picker = show_map_picker.clone();
+        Callback::from(move |()| {
+            if *map_picker_enabled {
+                show_map_picker.set(true);
+            } else {
+                restart.emit(());
+            }
+        })
+    };
+    let pick_map_cb: Callback<u64> = {
+        let restart = props.restart_run_with_seed.clone();
+        let show_map_picker = show_map_picker.clone();
+        Callback::from(move |seed: u64| {
+            show_map_picker.set(false);
+            restart.emit(seed);
+        })
+    };
+    let restart_seed_cb: Callback<u64> = {
+        let restart = props.restart_run_with_seed.clone();
+        let open_settings = open_settings.clone();
+        Callback::from(move |seed: u64| {
+            open_settings.set(false);
+            restart.emit(seed);
+        })
+    };
+    let cycle_console_cb: Callback<MouseEvent> = {
+        let console_level = console_level.clone();
+        Callback::from(move |_| console_level.set((*console_level + 1) % CONSOLE_LEVELS.len()))
+    };
+    let undo_wall_cb: Callback<()> = {
+        let run_state = props.run_state.clone();
+        Callback::from(move |_| run_state.dispatch(RunAction::UndoLastWall))
+    };
+    let copy_debug_state_cb: Callback<MouseEvent> = {
+        let run_state = props.run_state.clone();
+        let upgrades = props.upgrade_state.clone();
+        let toast = toast.clone();
+        Callback::from(move |_| {
+            let dump = debug_dump::session_dump(&run_state, &upgrades);
+            copy_to_clipboard(&dump);
+            toast.set(Some(format!(
+                "Debug state copied ({} KB)",
+                dump.len() / 1024
+            )));
+        })
+    };
+    // Replaces the run with a pasted bundle, paused and marked as a debug run
+    let import_debug_state_cb: Callback<MouseEvent> = {
+        let run_state = props.run_state.clone();
+        let input_ref = debug_import_ref.clone();
+        let toast = toast.clone();
+        Callback::from(move |_| {
+            let Some(input) = input_ref.cast::<HtmlInputElement>() else {
+                return;
+            };
+            match debug_dump::import(&input.value()) {
+                Ok((state, bundle)) => {
+                    toast.set(Some(format!(
+                        "Imported run {} (seed {}) from v{}",
+                        state.run_id, bundle.map_seed, bundle.app_version
+                    )));
+                    input.set_value("");
+                    run_state.dispatch(RunAction::LoadSaved {
+                        state: Box::new(state),
+                    });
+                }
+                Err(err) => toast.set(Some(err.message())),
+            }
+        })
+    };
+    let toggle_tower_history_cb: Callback<MouseEvent> = {
+        let tower_history = tower_history.clone();
+        Callback::from(move |_| tower_history.set(!*tower_history))
+    };
+    let copy_logs_cb: Callback<MouseEvent> = {
+        let run_id = rs_overlay.run_id;
+        Callback::from(move |_| copy_to_clipboard(&recent_logs(Some(run_id))))
+    };
+    let export_snapshot_cb: Callback<()> = {
+        let run_state = props.run_state.clone();
+        Callback::from(move |()| {
+            let title = format!("Maze Defence run {}", run_state.run_id);
+            match crate::snapshot_export::export_html(&run_state, &title) {
+                Some(html) => download_text(
+                    &format!("maze-defence-run-{}.html", run_state.run_id),
+                    &html,
+                ),
+                None => log(Level::Warn, "snapshot export failed to serialize"),
+            }
+        })
+    };
+    let rewind_cb: Callback<usize> = {
+        let run_state = props.run_state.clone();
+        let rewind = rewind.clone();
+        Callback::from(move |i: usize| {
+            let snap = rewind.borrow().get(i).cloned();
+            if let Some(state) = snap {
+                run_state.dispatch(RunAction::RestoreSnapshot {
+                    state: Box::new(state),
+                });
+            }
+        })
+    };
+    let step_sim_cb: Callback<MouseEvent> = {
+        let run_state = props.run_state.clone();
+        Callback::from(move |_| run_state.dispatch(RunAction::StepSim { dt: SIM_TICK_DT }))
+    };
+    // Newest first, labelled by how far back each snapshot is
+    let rewind_buttons: Vec<Html> = {
+        let times = rewind.borrow().times();
+        let now = rs_overlay.stats.time_survived_secs;
+        times
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(i, &secs)| {
+                let cb = rewind_cb.clone();
+                let onclick = Callback::from(move |_: MouseEvent| cb.emit(i));
+                let label = format!("-{}s", now.saturating_sub(secs));
+                html! { <button {onclick} style="padding:2px 6px;" title={format!("Rewind to {secs}s")}>{label}</button> }
+            })
+            .collect()
+    };
+    let tower_totals_ov = (!rs_overlay.towers.is_empty()).then(|| {
+        let mut t = tower_totals.borrow_mut();
+        t.refresh(&rs_overlay);
+        (t.dps, t.coverage)
+    });
+    let can_step = rs_overlay.started && rs_overlay.is_paused && !rs_overlay.game_over;
+    let set_speed_cb: Callback<f64> = {
+        let run_state = props.run_state.clone();
+        Callback::from(move |speed| run_state.dispatch(RunAction::SetSimSpeed { speed }))
+    };
+    let frame_step_cb: Callback<()> = {
+        let run_state = props.run_state.clone();
+        Callback::from(move |()| {
+            run_state.dispatch(RunAction::StepSim {
+                dt: FRAME_STEP_SECS,
+            })
+        })
+    };
+    let pick_perk_cb: Callback<model::Perk> = {
+        let run_state = props.run_state.clone();
+        Callback::from(move |perk| run_state.dispatch(RunAction::ChoosePerk { perk }))
+    };
+    let show_perks = !rs_overlay.started
+        && !game_over
+        && rs_overlay.perk.is_none()
+        && rs_overlay.scenario.is_none()
+        && !*show_intro
+        && !*show_map_picker
+        && !*show_scenario_picker;
+    let open_scenarios_cb: Callback<()> = {
+        let show_scenario_picker = show_scenario_picker.clone();
+        Callback::from(move |()| show_scenario_picker.set(true))
+    };
+    let pick_scenario_cb: Callback<model::ScenarioId> = {
+        let run_state = props.run_state.clone();
+        let show_scenario_picker = show_scenario_picker.clone();
+        Callback::from(move |id| {
+            show_scenario_picker.set(false);
+            run_state.dispatch(RunAction::StartScenario { id });
+        })
+    };
+    let cancel_scenario_picker_cb: Callback<()> = {
+        let show_scenario_picker = show_scenario_picker.clone();
+        Callback::from(move |()| show_scenario_picker.set(false))
+    };
+    let scenario_result = rs_overlay.scenario.and_then(|r| {
+        r.outcome
+            .map(|o| (r.id, o, model::scenario_stars(&rs_overlay)))
+    });
+    let retry_scenario_cb: Callback<()> = {
+        let run_state = props.run_state.clone();
+        let id = rs_overlay.scenario.map(|r| r.id);
+        Callback::from(move |()| {
+            if let Some(id) = id {
+                run_state.dispatch(RunAction::StartScenario { id });
+            }
+        })
+    };
+    let to_endless_cb: Callback<()> = {
         let restart = props.restart_run.clone();
         Callback::from(move |()| restart.emit(()))
     };
+    let cancel_map_picker_cb: Callback<()> = {
+        let show_map_picker = show_map_picker.clone();
+        Callback::from(move |()| show_map_picker.set(false))
+    };
     let hard_reset_cb_unit: Callback<()> = {
         let hard = props.hard_reset.clone();
         let open_settings = open_settings.clone();
@@ -1991,55 +3860,34 @@ pub fn run_view(props: &RunViewProps) -> Html {
         Callback::from(move |()| cb.emit(()))
     };
 
-    // Path nodes debug string
-    let path_debug_text = {
-        if !*show_path {
-            String::new()
+    // Path nodes for the debug readout: the loop, or the bare path while there is none
+    let path_nodes_opt = show_path.then(|| {
+        let rsd = &*props.run_state;
+        if !rsd.path_loop.is_empty() {
+            rsd.path_loop.to_vec()
         } else {
-            let rsd = (*props.run_state).clone();
-            let source = if !rsd.path_loop.is_empty() {
-                &rsd.path_loop
-            } else {
-                &rsd.path
-            };
-            if source.is_empty() {
-                "(empty)".to_string()
-            } else {
-                let mut s = String::new();
-                for (i, p) in source.iter().enumerate() {
-                    if i > 0 {
-                        s.push_str(" -> ");
-                    }
-                    s.push_str(&format!("({},{})", p.x, p.y));
-                    if i > 14 {
-                        s.push_str(" ...");
-                        break;
-                    }
-                }
-                s
-            }
+            rsd.path.to_vec()
         }
-    };
-    // Path nodes text optional
-    let path_nodes_text_opt = if *show_path {
-        Some(path_debug_text.clone())
-    } else {
-        None
-    };
+    });
 
     // Intro overlay hide callback
     let hide_intro_cb: Callback<()> = {
         let show_intro = show_intro.clone();
         Callback::from(move |()| {
             show_intro.set(false);
-            if let Some(win) = web_sys::window() {
-                if let Ok(Some(store)) = win.local_storage() {
-                    let _ = store.set_item("md_intro_seen", "1");
-                }
+            if let Some(mut store) = LocalStorage::open() {
+                persistence::write(&mut store, "md_intro_seen", "1");
             }
         })
     };
 
+    let skip_tutorial_cb: Callback<()> = {
+        let tutorial = tutorial.clone();
+        Callback::from(move |()| tutorial.set(tutorial.skipped()))
+    };
+    // Shown once the intro is out of the way, and not over the end of a run
+    let tutorial_step = tutorial.current().filter(|_| !*show_intro && !game_over);
+
     // Help button callback (re-show intro without clearing seen flag)
     let show_help_cb: Callback<()> = {
         let show_intro = show_intro.clone();
@@ -2055,17 +3903,124 @@ pub fn run_view(props: &RunViewProps) -> Html {
 
     // Legend component boolean flags already computed
 
-    html! {<div style="position:relative; width:100vw; height:100vh;">
-        <canvas ref={canvas_ref.clone()} id="game-canvas" style="display:block; width:100%; height:100%;"></canvas>
+    // Progressive unlocks for the first runs
+    let progression = rs_overlay.progression_run;
+    let upgrades_unlocked = model::feature_allowed(progression, Feature::Upgrades);
+    let legend_unlocked = model::feature_allowed(progression, Feature::Legend);
+    let full_settings = model::feature_allowed(progression, Feature::FullSettings);
+    let tower_lock_hint = model::feature_lock_message(progression, Feature::Towers);
+    let exclusion_warning = model::exclusion_warning(&rs_overlay);
+    // Prospector readout: marked gold by direction from Start
+    let prospect_readout = if *prospector {
+        prospect_readout.borrow_mut().refresh(&rs_overlay)
+    } else {
+        None
+    };
+
+    let selected_kind = (*tower_kind).clone();
+    let tower_costs = TowerKind::ALL.map(|k| rs_overlay.current_tower_cost(&k));
+    let towers_unlocked = tower_lock_hint.is_none();
+    // Key hints for the focused tile, hidden behind overlays
+    let hint_actions = if *coarse_pointer
+        || *show_intro
+        || *open_settings
+        || *show_map_picker
+        || *show_scenario_picker
+        || show_perks
+    {
+        Vec::new()
+    } else {
+        let (hx, hy) = focus_tile.borrow().tile();
+        let focus = (hx >= 0 && hy >= 0).then_some((hx as u32, hy as u32));
+        let width = rs_overlay.grid_size.width;
+        let reachable = focus.is_some_and(|(x, y)| {
+            let layer = terrain_layer.borrow();
+            layer.mask().get((y * width + x) as usize).copied() == Some(true)
+        });
+        key_hints(&rs_overlay, focus, reachable, &selected_kind)
+    };
+    let select_tower_cb: Callback<TowerKind> = {
+        let tower_kind = tower_kind.clone();
+        Callback::from(move |kind| tower_kind.set(kind))
+    };
+    // Low-life tip: the biggest stretch of loop no tower reaches, once per run
+    let low_life_gap = rs_overlay
+        .low_life
+        .then(|| model::largest_uncovered_stretch(&rs_overlay))
+        .flatten()
+        .filter(|_| !game_over && *low_life_tip_done != Some(rs_overlay.run_id));
+    let dismiss_low_life_tip_cb: Callback<MouseEvent> = {
+        let done = low_life_tip_done.clone();
+        let run_id = rs_overlay.run_id;
+        Callback::from(move |_| done.set(Some(run_id)))
+    };
+    let show_low_life_gap_cb: Callback<MouseEvent> = {
+        let done = low_life_tip_done.clone();
+        let run_id = rs_overlay.run_id;
+        let camera = camera.clone();
+        let canvas_ref = canvas_ref.clone();
+        let mid = low_life_gap.map(|g| g.mid);
+        Callback::from(move |_| {
+            done.set(Some(run_id));
+            let (Some(canvas), Some(mid)) = (canvas_ref.cast::<HtmlCanvasElement>(), mid) else {
+                return;
+            };
+            let mut cam = camera.borrow_mut();
+            let scale_px = cam.zoom * 32.0;
+            let x = canvas.width() as f64 * 0.5 - scale_px * (mid.x as f64 + 0.5);
+            let y = canvas.height() as f64 * 0.5 - scale_px * (mid.y as f64 + 0.5);
+            cam.glide_to(x, y, js_sys::Date::now());
+        })
+    };
+    let bookmark_labels: Vec<Option<String>> = (0..BOOKMARK_SLOTS)
+        .map(|i| bookmarks.get(rs_overlay.run_id, i).map(|b| b.label.clone()))
+        .collect();
+    let save_bookmark_cb: Callback<usize> = {
+        let bookmarks = bookmarks.clone();
+        let camera = camera.clone();
+        let run_id = rs_overlay.run_id;
+        Callback::from(move |slot| {
+            let mut marks = (*bookmarks).clone();
+            marks.save(run_id, slot, &camera.borrow());
+            bookmarks.set(marks);
+        })
+    };
+    let recall_bookmark_cb: Callback<usize> = {
+        let bookmarks = bookmarks.clone();
+        let camera = camera.clone();
+        let run_id = rs_overlay.run_id;
+        Callback::from(move |slot| {
+            bookmarks.recall(run_id, slot, &mut camera.borrow_mut(), js_sys::Date::now());
+        })
+    };
+    let rename_bookmark_cb: Callback<(usize, String)> = {
+        let bookmarks = bookmarks.clone();
+        let run_id = rs_overlay.run_id;
+        Callback::from(move |(slot, label): (usize, String)| {
+            let mut marks = (*bookmarks).clone();
+            marks.rename(run_id, slot, &label);
+            bookmarks.set(marks);
+        })
+    };
+    let vignette_class = classes!("low-life-vignette", (*reduced_effects).then_some("still"));
+    let hud = hud_layout(viewport.0, viewport.1, &hud_settings);
+    let hud_drawer = html! {<>
         <TimeDisplay time_survived={time_ov} pause_label={pause_label_rv.to_string()} on_toggle_pause={toggle_pause_cb.clone()} />
-        <IntroOverlay show={*show_intro} game_over={game_over} hide_intro={hide_intro_cb} to_upgrades={to_upgrades_unit.clone()} />
-        <StatsPanel gold={gold_ov} life={life_ov} research={research_ov} />
-        <SecondaryStatsPanel run_id={rs_overlay.run_id} enemy_count={enemy_count} path_len={path_len} path_nodes_text={path_nodes_text_opt} show={*show_secondary_stats} />
-        <TowerPanel tower_feedback={tower_feedback_opt} />
-        <ControlsPanel to_upgrades={to_upgrades_unit.clone()} on_show_help={show_help_cb} on_open_settings={open_settings_cb} />
-        <CameraControls on_zoom_in={zoom_in_cb} on_zoom_out={zoom_out_cb} on_pan_left={pan_cb(-64.0,0.0)} on_pan_right={pan_cb(64.0,0.0)} on_pan_up={pan_cb(0.0,-64.0)} on_pan_down={pan_cb(0.0,64.0)} on_center={center_cb} />
-        <LegendPanel has_start={has_start} has_entrance={has_entrance} has_exit={has_exit} has_indestructible={has_indestructible} has_basic={has_basic} has_gold={has_gold} has_empty={has_empty} has_wall={has_wall}
+        <StatsPanel gold={gold_ov} life={life_ov} shield={rs_overlay.shield} tile_credits={rs_overlay.currencies.tile_credits} research={research_ov} research_progress={rs_overlay.research_progress} streak={rs_overlay.clean_streak} streak_mult={rs_overlay.streak_research_mult()} low_life={rs_overlay.low_life && !game_over} life_hit={rs_overlay.life_loss_flash().then_some(rs_overlay.last_life_loss_at)} reduced_effects={*reduced_effects} tower_totals={tower_totals_ov} interest={(rs_overlay.bank_interest_rate > 0.0).then_some(rs_overlay.last_interest)} queued={queued_ov}
+            session={rs_overlay.started.then(|| (rs_overlay.stats.enemies_killed, rs_overlay.damage_window.dps(rs_overlay.sim_time)))}
+            seed={rs_overlay.scenario.is_none().then_some(rs_overlay.map_seed)} />
+        <SecondaryStatsPanel run_id={rs_overlay.run_id} enemy_count={enemy_count} path_len={path_len} path_recomputing={rs_overlay.path_recomputing()} path_nodes={path_nodes_opt} show={*show_secondary_stats} />
+        <ControlsPanel to_upgrades={to_upgrades_unit.clone()} on_history={props.to_history.clone()} on_show_help={show_help_cb} on_open_settings={open_settings_cb} on_scenarios={open_scenarios_cb.clone()} show_upgrades={upgrades_unlocked} show_scenarios={upgrades_unlocked}
+            tower_kind={selected_kind} tower_costs={tower_costs} on_select_tower={select_tower_cb} show_towers={towers_unlocked}
+            undo_wall_key={model::undoable_wall(&rs_overlay).map(|_| hotkeys.label(HotkeyAction::UndoWall))} on_undo_wall={undo_wall_cb}
+            sim_speed={rs_overlay.sim_speed} on_set_speed={set_speed_cb} can_step={can_step} on_step={frame_step_cb} palette={theme.palette} />
+        if let Some(run) = rs_overlay.scenario {
+            <ScenarioObjective id={run.id} time_survived={time_ov} kills={rs_overlay.stats.enemies_killed} towers={rs_overlay.towers.len()} />
+        }
+        if legend_unlocked {
+        <LegendPanel has_start={has_start} has_entrance={has_entrance} has_exit={has_exit} has_indestructible={has_indestructible} has_basic={has_basic} has_gold={has_gold} has_empty={has_empty} has_wall={has_wall} has_magma={has_magma} has_lava={has_lava} has_healing={has_healing}
             hover_text={hover_text}
+            palette={theme.palette}
             highlight_start={hl_start}
             highlight_entrance={hl_entrance}
             highlight_exit={hl_exit}
@@ -2074,13 +4029,65 @@ pub fn run_view(props: &RunViewProps) -> Html {
             highlight_gold={hl_gold}
             highlight_empty={hl_empty}
             highlight_wall={hl_wall}
+            enemy_kinds={enemy_kinds}
+            above_minimap={*minimap}
         />
+        }
         <TileInfoPanel
             tile={hovered_tile_kind}
             tile_x={hovered_tile_x}
             tile_y={hovered_tile_y}
+            tower={hovered_tower}
+            on_toggle_tower_exclusion={toggle_tower_exclusion_cb}
+            siege={rs_snapshot.siege}
+            gold={rs_snapshot.currencies.gold}
+            on_repair_tower={repair_tower_cb}
+            tower_stats={hovered_tower_stats}
+            swap_options={swap_options}
+            on_swap_kind={swap_kind_cb}
             upgrade_state={props.upgrade_state.clone()}
         />
+    </>};
+    let hud_actions = html! {<>
+        <TowerPanel tower_feedback={tower_feedback_opt} locked_hint={tower_lock_hint} warning={exclusion_warning} hints={hint_actions} hotkeys={(*hotkeys).clone()} />
+        <CameraControls on_zoom_in={zoom_in_cb} on_zoom_out={zoom_out_cb} on_pan_left={pan_cb(-64.0,0.0)} on_pan_right={pan_cb(64.0,0.0)} on_pan_up={pan_cb(0.0,-64.0)} on_pan_down={pan_cb(0.0,64.0)} on_center={center_cb}
+            bookmarks={bookmark_labels} on_save_bookmark={save_bookmark_cb} on_recall_bookmark={recall_bookmark_cb} on_rename_bookmark={rename_bookmark_cb} />
+        if let Some((tower, stats, upgrade_cost, upgrade_stats, refund)) = selected_tower_info {
+            <TowerInfoPanel tower={tower.clone()} stats={stats} gold={rs_overlay.currencies.gold} upgrade_cost={upgrade_cost} upgrade_stats={upgrade_stats} sell_refund={refund}
+                moving={*moving_tower == Some((tower.x, tower.y))}
+                on_sell={sell_selected_cb} on_upgrade={upgrade_selected_cb} on_move={move_selected_cb} on_close={deselect_tower_cb} />
+        }
+        if *minimap {
+            <Minimap canvas_ref={minimap_ref.clone()} grid={rs_overlay.grid_size} on_jump={minimap_jump_cb} />
+        }
+        if !game_over && (rs_overlay.currencies.tile_credits > 0 || stamp_cursor.selected.is_some()) {
+            <StampPalette
+                selected={stamp_cursor.selected}
+                rotation={stamp_cursor.rotation}
+                credits={rs_overlay.currencies.tile_credits}
+                on_select={select_stamp_cb}
+                on_rotate={rotate_stamp_cb}
+            />
+        }
+    </>};
+
+    let root_style = format!(
+        "position:relative; width:100vw; height:100vh; --ui-scale:{};",
+        theme.scale()
+    );
+    html! {<div style={root_style}>
+        <canvas ref={canvas_ref.clone()} id="game-canvas" style="display:block; width:100%; height:100%;"></canvas>
+        if rs_overlay.low_life && !game_over {
+            <div class={vignette_class}></div>
+        }
+        if rs_overlay.life_loss_flash() && !*reduced_effects {
+            <div key={rs_overlay.last_life_loss_at.to_string()} class="life-loss-flash"></div>
+        }
+        <HudFrame layout={hud} drawer={hud_drawer} actions={hud_actions} />
+        <IntroOverlay show={*show_intro} game_over={game_over} hide_intro={hide_intro_cb} to_upgrades={to_upgrades_unit.clone()} show_upgrades={upgrades_unlocked} />
+        if let (Some(step), Some(progress)) = (tutorial_step, tutorial.progress()) {
+            <TutorialCard step={step} progress={progress} locked={step.lock_message(&rs_overlay)} on_skip={skip_tutorial_cb} />
+        }
         <SettingsModal
             show={*open_settings}
             on_close={close_settings_cb.clone()}
@@ -2088,10 +4095,118 @@ pub fn run_view(props: &RunViewProps) -> Html {
             on_toggle_path={toggle_path_cb}
             show_damage_numbers={*show_damage_numbers}
             on_toggle_damage_numbers={toggle_damage_numbers_cb}
+            show_health_bars={*show_health_bars}
+            on_toggle_health_bars={toggle_health_bars_cb}
+            show_tile_labels={*show_tile_labels}
+            on_toggle_tile_labels={toggle_tile_labels_cb}
+            hints={*hints}
+            on_toggle_hints={toggle_hints_cb}
+            manual_collection={*manual_collection}
+            on_toggle_manual_collection={toggle_manual_collection_cb}
+            prospector={*prospector}
+            on_toggle_prospector={toggle_prospector_cb}
+            reduced_effects={*reduced_effects}
+            on_toggle_reduced_effects={toggle_reduced_effects_cb}
+            edge_scroll={*edge_scroll}
+            on_toggle_edge_scroll={toggle_edge_scroll_cb}
+            minimap={*minimap}
+            on_toggle_minimap={toggle_minimap_cb}
+            heartbeat={*heartbeat}
+            on_toggle_heartbeat={toggle_heartbeat_cb}
+            pause_when_hidden={*pause_when_hidden}
+            on_toggle_pause_when_hidden={toggle_pause_when_hidden_cb}
+            long_catch_up={*long_catch_up}
+            on_toggle_long_catch_up={toggle_long_catch_up_cb}
             show_secondary_stats={*show_secondary_stats}
             on_toggle_secondary_stats={toggle_secondary_stats_cb}
+            map_picker={*map_picker_enabled}
+            on_toggle_map_picker={toggle_map_picker_cb}
+            skip_progression={*skip_progression}
+            on_toggle_skip_progression={toggle_skip_progression_cb}
+            siege={*siege}
+            on_toggle_siege={toggle_siege_cb}
+            hud_layout={*hud_settings}
+            on_change_hud_layout={change_hud_settings_cb}
+            theme={*theme}
+            on_change_theme={change_theme_cb}
+            hotkeys={(*hotkeys).clone()}
+            on_change_hotkeys={change_hotkeys_cb}
+            audio={*audio_settings}
+            on_change_audio={change_audio_cb}
+            full={full_settings}
             on_hard_reset={hard_reset_cb_unit.clone()}
+            upgrade_state={props.upgrade_state.clone()}
+            research={rs_overlay.currencies.research}
+            on_import_save={props.import_save.clone()}
+            seed={rs_overlay.map_seed}
+            on_restart_with_seed={restart_seed_cb}
+        />
+        <GameOverOverlay show={game_over && rs_overlay.scenario.is_none()} time_survived={time_ov} loops_completed={rs_overlay.stats.loops_completed} route_mode={rs_overlay.route_mode} next_end_route={*end_route} on_toggle_end_route={toggle_end_route_cb} blocks_mined={rs_overlay.stats.blocks_mined} research_earned={rs_overlay.stats.research_earned} clutch_saves={rs_overlay.stats.clutch_saves} stats={rs_overlay.stats} new_best={props.new_best} counts_for_records={!rs_overlay.debug_allowed} restart={restart_cb_unit} to_upgrades={to_upgrades_unit} export_snapshot={export_snapshot_cb} />
+        if let Some(gap) = low_life_gap {
+            <div style="position:absolute; top:96px; left:50%; transform:translateX(-50%); background:#161b22; border:1px solid #f85149; border-radius:8px; padding:6px 8px 6px 12px; font-size:13px; display:flex; align-items:center; gap:10px; box-shadow:0 4px 12px rgba(0,0,0,0.5); z-index:40;">
+                <span>{ format!("Life is low: {} loop tiles in a row have no tower cover", gap.len) }</span>
+                <button onclick={show_low_life_gap_cb} style="padding:2px 8px;">{"Show me"}</button>
+                <button onclick={dismiss_low_life_tip_cb} style="padding:2px 8px;" title="Dismiss">{"×"}</button>
+            </div>
+        } else if let Some(hint) = rs_overlay.hint {
+            <div style="position:absolute; top:96px; left:50%; transform:translateX(-50%); background:#161b22; border:1px solid #d29922; border-radius:8px; padding:6px 8px 6px 12px; font-size:13px; display:flex; align-items:center; gap:10px; box-shadow:0 4px 12px rgba(0,0,0,0.5); z-index:40;">
+                <span>{ format!("Hint: {}", hint.text()) }</span>
+                <button onclick={dismiss_hint_cb} style="padding:2px 8px;" title="Dismiss">{"×"}</button>
+            </div>
+        }
+        if let Some(q) = prospect_readout {
+            <div style="position:absolute; top:140px; left:50%; transform:translateX(-50%); background:#161b22cc; border:1px solid #30363d; border-radius:8px; padding:6px 10px; font-size:12px; display:grid; grid-template-columns:auto auto; gap:2px 14px; pointer-events:none; z-index:15;">
+                <span style="grid-column:1 / span 2; opacity:0.7;">{ format!("Prospector: {} gold in sight", q.total()) }</span>
+                <span>{ format!("NW {}", q.nw) }</span><span>{ format!("NE {}", q.ne) }</span>
+                <span>{ format!("SW {}", q.sw) }</span><span>{ format!("SE {}", q.se) }</span>
+            </div>
+        }
+        if let Some(msg) = (*toast).clone() {
+            <div style="position:absolute; top:56px; left:50%; transform:translateX(-50%); background:#161b22; border:1px solid #30363d; border-radius:8px; padding:8px 14px; font-size:13px; box-shadow:0 4px 12px rgba(0,0,0,0.5); pointer-events:none; z-index:40;">{ msg }</div>
+        }
+        if *show_debug {
+            <div style="position:absolute; top:198px; right:10px; width:240px; display:flex; gap:6px; font-size:12px; z-index:20;">
+                <button onclick={cycle_console_cb} style="flex:1; padding:4px 6px;">
+                    { format!("Console: {}", CONSOLE_LEVELS[*console_level].map_or("off", |l| l.label())) }
+                </button>
+                <button onclick={copy_logs_cb} style="flex:1; padding:4px 6px;">{"Copy recent logs"}</button>
+            </div>
+            <div style="position:absolute; top:230px; right:10px; width:240px; display:flex; flex-wrap:wrap; align-items:center; gap:4px; font-size:12px; z-index:20;">
+                <span style="opacity:0.7;">{"Rewind"}</span>
+                { for rewind_buttons }
+                <button onclick={step_sim_cb} disabled={!can_step} style="margin-left:auto; padding:2px 6px;" title="Advance one sim tick while paused">{"Step"}</button>
+            </div>
+            <div style="position:absolute; top:262px; right:10px; width:240px; display:flex; font-size:12px; z-index:20;">
+                <button onclick={toggle_tower_history_cb} style="flex:1; padding:4px 6px;" title="Tint tiles by the damage towers have dealt from them this run; numbers show at high zoom">
+                    { if *tower_history { "Tower history: on" } else { "Tower history: off" } }
+                </button>
+            </div>
+            <div style="position:absolute; top:294px; right:10px; width:240px; display:flex; gap:6px; font-size:12px; z-index:20;">
+                <button onclick={copy_debug_state_cb} style="flex:1; padding:4px 6px;" title="Run, upgrades, settings and recent logs as one string for a bug report">{"Copy debug state"}</button>
+            </div>
+            <div style="position:absolute; top:326px; right:10px; width:240px; display:flex; gap:6px; font-size:12px; z-index:20;">
+                <input ref={debug_import_ref.clone()} type="text" placeholder="Paste debug state" style="flex:1; min-width:0; font-size:12px;" />
+                <button onclick={import_debug_state_cb} style="padding:4px 6px;">{"Import"}</button>
+            </div>
+        }
+        <PerkCards
+            show={show_perks}
+            perks={model::offered_perks(rs_overlay.map_seed)}
+            on_pick={pick_perk_cb}
+        />
+        if let Some((id, outcome, stars)) = scenario_result {
+            <ScenarioResult {id} {outcome} {stars} retry={retry_scenario_cb} to_scenarios={open_scenarios_cb} to_endless={to_endless_cb} />
+        }
+        <ScenarioPicker
+            show={*show_scenario_picker}
+            on_pick={pick_scenario_cb}
+            on_cancel={cancel_scenario_picker_cb}
+        />
+        <MapPicker
+            show={*show_map_picker}
+            upgrade_state={props.upgrade_state.clone()}
+            on_pick={pick_map_cb}
+            on_cancel={cancel_map_picker_cb}
         />
-        <GameOverOverlay show={game_over} time_survived={time_ov} loops_completed={rs_overlay.stats.loops_completed} blocks_mined={rs_overlay.stats.blocks_mined} restart={restart_cb_unit} to_upgrades={to_upgrades_unit} />
     </div> }
 }
@@ -0,0 +1,102 @@
+use super::game_over_overlay::{load_records, save_records};
+use super::scenario_picker::star_text;
+use crate::model::{ScenarioGoal, ScenarioId, ScenarioOutcome};
+use crate::util::format_time;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct ScenarioObjectiveProps {
+    pub id: ScenarioId,
+    pub time_survived: u64,
+    pub kills: u64,
+    pub towers: usize,
+}
+
+// Goal progress and the tower cap while a scenario runs
+#[function_component]
+pub fn ScenarioObjective(props: &ScenarioObjectiveProps) -> Html {
+    let def = props.id.def();
+    let progress = match def.goal {
+        ScenarioGoal::SurviveSecs(s) => format!(
+            "{} left",
+            format_time(s.saturating_sub(props.time_survived))
+        ),
+        ScenarioGoal::Kills(n) => format!("{} / {} defeated", props.kills.min(n), n),
+    };
+    html! {<div style="position:absolute; top:12px; left:50%; transform:translateX(-50%); background:rgba(22,27,34,0.9); border:1px solid #30363d; border-radius:8px; padding:6px 12px; font-size:13px; display:flex; gap:12px; align-items:center;">
+        <strong>{ def.name }</strong>
+        <span>{ def.goal.text() }</span>
+        <span style="opacity:0.8;">{ progress }</span>
+        if let Some(max) = def.max_towers {
+            <span style="opacity:0.8;">{ format!("Towers {}/{}", props.towers, max) }</span>
+        }
+    </div>}
+}
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct ScenarioResultProps {
+    pub id: ScenarioId,
+    pub outcome: ScenarioOutcome,
+    pub stars: u8,
+    pub retry: Callback<()>,
+    pub to_scenarios: Callback<()>,
+    pub to_endless: Callback<()>,
+}
+
+#[function_component]
+pub fn ScenarioResult(props: &ScenarioResultProps) -> Html {
+    let new_best = use_state(|| false);
+    {
+        let id = props.id;
+        let stars = props.stars;
+        let new_best = new_best.clone();
+        use_effect_with((id, stars), move |_| {
+            let mut r = load_records();
+            if r.record_scenario(id, stars) {
+                save_records(&r);
+                new_best.set(true);
+            }
+            || ()
+        });
+    }
+    let retry_btn = {
+        let cb = props.retry.clone();
+        Callback::from(move |_| cb.emit(()))
+    };
+    let scenarios_btn = {
+        let cb = props.to_scenarios.clone();
+        Callback::from(move |_| cb.emit(()))
+    };
+    let endless_btn = {
+        let cb = props.to_endless.clone();
+        Callback::from(move |_| cb.emit(()))
+    };
+    let won = props.outcome == ScenarioOutcome::Won;
+    let (title, color) = if won {
+        ("Scenario Complete", "#2ea043")
+    } else {
+        ("Scenario Failed", "#f85149")
+    };
+    let panel_style = format!("background:rgba(0,0,0,0.85); border:2px solid {color}; position:absolute; top:50%; left:50%; transform:translate(-50%, -50%); padding:24px 32px; border-radius:12px; text-align:center; min-width:320px;");
+    let title_style = format!("margin:0 0 12px 0; color:{color};");
+
+    html! {
+        <div style={panel_style}>
+            <h2 style={title_style}>{ title }</h2>
+            <p style="margin:4px 0;">{ props.id.def().name }</p>
+            if won {
+                <p style="margin:4px 0; font-size:1.4em; color:#d29922;">
+                    { star_text(props.stars) }
+                    if *new_best {
+                        <span style="font-size:0.6em; font-weight:bold; margin-left:6px;">{"NEW!"}</span>
+                    }
+                </p>
+            }
+            <div style="margin-top:16px; display:flex; gap:12px; justify-content:center;">
+                <button onclick={retry_btn}>{"Retry"}</button>
+                <button onclick={scenarios_btn}>{"Scenarios"}</button>
+                <button onclick={endless_btn}>{"Endless Run"}</button>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,56 @@
+use crate::model::{MetaRecords, ScenarioId};
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct ScenarioPickerProps {
+    pub show: bool,
+    pub on_pick: Callback<ScenarioId>,
+    pub on_cancel: Callback<()>,
+}
+
+// Three slots, filled up to the best result
+pub fn star_text(stars: u8) -> String {
+    (0..3).map(|i| if i < stars { '★' } else { '☆' }).collect()
+}
+
+#[function_component]
+pub fn ScenarioPicker(props: &ScenarioPickerProps) -> Html {
+    if !props.show {
+        return html! {};
+    }
+    // Read on open so a fresh result shows up without a reload
+    let records: MetaRecords = super::game_over_overlay::load_records();
+    let cancel_cb = {
+        let cb = props.on_cancel.clone();
+        Callback::from(move |_| cb.emit(()))
+    };
+
+    html! {<div style="position:absolute; inset:0; display:flex; align-items:center; justify-content:center; background:rgba(0,0,0,0.55); z-index:50;">
+        <div style="background:#161b22; border:1px solid #30363d; border-radius:12px; padding:16px 20px; display:flex; flex-direction:column; gap:14px; max-width:420px;">
+            <div style="display:flex; justify-content:space-between; align-items:center; gap:16px;">
+                <h3 style="margin:0; font-size:18px;">{"Scenarios"}</h3>
+                <button onclick={cancel_cb} style="padding:4px 8px;">{"Cancel"}</button>
+            </div>
+            { for ScenarioId::ALL.iter().map(|&id| {
+                let def = id.def();
+                let on_pick = props.on_pick.clone();
+                let pick_cb = Callback::from(move |_| on_pick.emit(id));
+                let limits = match def.max_towers {
+                    Some(n) => format!("{} gold · {} lives · {} towers max", def.gold, def.life, n),
+                    None => format!("{} gold · {} lives", def.gold, def.life),
+                };
+                html! {
+                    <div onclick={pick_cb} style="cursor:pointer; background:#0e1116; border:1px solid #30363d; border-radius:8px; padding:8px 12px; display:flex; flex-direction:column; gap:4px;">
+                        <div style="display:flex; justify-content:space-between; gap:12px;">
+                            <strong>{ def.name }</strong>
+                            <span style="color:#d29922;">{ star_text(records.stars(id)) }</span>
+                        </div>
+                        <div style="font-size:12px; opacity:0.85;">{ def.blurb }</div>
+                        <div style="font-size:12px;">{ def.goal.text() }</div>
+                        <div style="font-size:12px; opacity:0.75;">{ limits }</div>
+                    </div>
+                }
+            }) }
+        </div>
+    </div>}
+}
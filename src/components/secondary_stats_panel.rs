@@ -1,3 +1,5 @@
+use crate::model::Position;
+use crate::state::{path_nodes_text, PATH_NODES_SHOWN};
 use yew::prelude::*;
 
 #[derive(Properties, PartialEq, Clone)]
@@ -5,18 +7,44 @@ pub struct SecondaryStatsPanelProps {
     pub run_id: u64,
     pub enemy_count: usize,
     pub path_len: usize,
-    pub path_nodes_text: Option<String>,
+    // A mined tile's new route is still being searched; path_len is the old one's
+    #[prop_or_default]
+    pub path_recomputing: bool,
+    // Route nodes while Show Path is on
+    #[prop_or_default]
+    pub path_nodes: Option<Vec<Position>>,
     pub show: bool,
 }
 
 #[function_component]
 pub fn SecondaryStatsPanel(props: &SecondaryStatsPanelProps) -> Html {
+    // Every node in a scrolling list instead of the first PATH_NODES_SHOWN
+    let all_nodes = use_state(|| false);
     if !props.show {
         return html! {};
     }
+    let toggle_all = {
+        let all_nodes = all_nodes.clone();
+        Callback::from(move |_| all_nodes.set(!*all_nodes))
+    };
     html! {<div style="position:absolute; left:12px; bottom:150px; background:rgba(22,27,34,0.9); border:1px solid #30363d; border-radius:8px; padding:8px 10px; min-width:210px; display:flex; flex-direction:column; gap:4px; font-size:12px; line-height:1.3;">
         <div style="display:flex; justify-content:space-between; gap:12px;"><span style="opacity:0.7;">{"Run"}</span><span style="color:#d29922; font-weight:600;">{props.run_id}</span></div>
         <div style="display:flex; justify-content:space-between; gap:12px;"><span style="opacity:0.7;">{"Enemies"}</span><span style="color:#f85149; font-weight:600;">{props.enemy_count}</span></div>
-        <div style="display:flex; justify-content:space-between; gap:12px;"><span style="opacity:0.7;">{"Path Len"}</span><span style="color:#58a6ff; font-weight:600;">{props.path_len}</span></div>
+        <div style="display:flex; justify-content:space-between; gap:12px;"><span style="opacity:0.7;">{"Path Len"}</span><span style="color:#58a6ff; font-weight:600;">
+            if props.path_recomputing {
+                <span class="path-spinner" title="Recomputing path"></span>
+            }
+            {props.path_len}</span></div>
+        if let Some(nodes) = props.path_nodes.as_ref() {
+            <div style="display:flex; justify-content:space-between; gap:12px; margin-top:4px;">
+                <span style="opacity:0.7;">{"Path Nodes"}</span>
+                if nodes.len() > PATH_NODES_SHOWN {
+                    <button onclick={toggle_all} style="padding:0 6px; font-size:11px;">{ if *all_nodes { "Less" } else { "All" } }</button>
+                }
+            </div>
+            <div style="max-width:260px; max-height:140px; overflow-y:auto; font-family:monospace; font-size:11px; opacity:0.85; word-break:break-word;">
+                { path_nodes_text(nodes, *all_nodes) }
+            </div>
+        }
     </div> }
 }
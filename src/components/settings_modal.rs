@@ -1,3 +1,11 @@
+use crate::model::UpgradeState;
+use crate::save_transfer::{self, SaveData};
+use crate::state::{
+    AudioSettings, HotkeyAction, Hotkeys, HudLayoutSettings, ThemeSettings, UI_SCALE_MAX,
+    UI_SCALE_MIN,
+};
+use crate::util::copy_to_clipboard;
+use web_sys::{HtmlInputElement, HtmlTextAreaElement, KeyboardEvent};
 use yew::prelude::*;
 
 #[derive(Properties, PartialEq, Clone)]
@@ -8,13 +16,69 @@ pub struct SettingsModalProps {
     pub on_toggle_path: Callback<()>,
     pub show_damage_numbers: bool,
     pub on_toggle_damage_numbers: Callback<()>,
+    pub show_health_bars: bool,
+    pub on_toggle_health_bars: Callback<()>,
+    pub show_tile_labels: bool,
+    pub on_toggle_tile_labels: Callback<()>,
+    pub hints: bool,
+    pub on_toggle_hints: Callback<()>,
+    pub manual_collection: bool,
+    pub on_toggle_manual_collection: Callback<()>,
+    pub prospector: bool,
+    pub on_toggle_prospector: Callback<()>,
+    pub reduced_effects: bool,
+    pub on_toggle_reduced_effects: Callback<()>,
+    pub edge_scroll: bool,
+    pub on_toggle_edge_scroll: Callback<()>,
+    pub minimap: bool,
+    pub on_toggle_minimap: Callback<()>,
+    pub heartbeat: bool,
+    pub on_toggle_heartbeat: Callback<()>,
+    pub pause_when_hidden: bool,
+    pub on_toggle_pause_when_hidden: Callback<()>,
+    pub long_catch_up: bool,
+    pub on_toggle_long_catch_up: Callback<()>,
     pub show_secondary_stats: bool,
     pub on_toggle_secondary_stats: Callback<()>,
+    pub map_picker: bool,
+    pub on_toggle_map_picker: Callback<()>,
+    pub skip_progression: bool,
+    pub on_toggle_skip_progression: Callback<()>,
+    pub siege: bool,
+    pub on_toggle_siege: Callback<()>,
+    pub hud_layout: HudLayoutSettings,
+    pub on_change_hud_layout: Callback<HudLayoutSettings>,
+    // Board palette and HUD scale; shown before the full settings unlock
+    pub theme: ThemeSettings,
+    pub on_change_theme: Callback<ThemeSettings>,
+    pub hotkeys: Hotkeys,
+    pub on_change_hotkeys: Callback<Hotkeys>,
+    pub audio: AudioSettings,
+    pub on_change_audio: Callback<AudioSettings>,
+    // Full settings unlock on the third run; until then only the skip toggle is shown
+    pub full: bool,
     pub on_hard_reset: Callback<()>,
+    // Current progress, exported as a save and shown against an import's preview
+    pub upgrade_state: UpgradeState,
+    pub research: u64,
+    pub on_import_save: Callback<SaveData>,
+    // The live map's seed, and a fresh run on a typed-in one
+    pub seed: u64,
+    pub on_restart_with_seed: Callback<u64>,
 }
 
 #[function_component]
 pub fn SettingsModal(props: &SettingsModalProps) -> Html {
+    // Controls row waiting for its new key, and why the last key was refused
+    let listening = use_state(|| Option::<HotkeyAction>::None);
+    let rebind_error = use_state(|| Option::<String>::None);
+    // Exported save string, a decoded import awaiting confirmation, and why a paste was refused
+    let export_text = use_state(|| Option::<String>::None);
+    let import_ref = use_node_ref();
+    let pending_import = use_state(|| Option::<SaveData>::None);
+    let import_error = use_state(|| Option::<String>::None);
+    let seed_ref = use_node_ref();
+    let seed_error = use_state(|| false);
     if !props.show {
         return html! {};
     }
@@ -31,10 +95,249 @@ pub fn SettingsModal(props: &SettingsModalProps) -> Html {
         let cb = props.on_toggle_damage_numbers.clone();
         Callback::from(move |_| cb.emit(()))
     };
+    let toggle_health_bars_cb = {
+        let cb = props.on_toggle_health_bars.clone();
+        Callback::from(move |_| cb.emit(()))
+    };
+    let toggle_tile_labels_cb = {
+        let cb = props.on_toggle_tile_labels.clone();
+        Callback::from(move |_| cb.emit(()))
+    };
+    let toggle_hints_cb = {
+        let cb = props.on_toggle_hints.clone();
+        Callback::from(move |_| cb.emit(()))
+    };
+    let toggle_manual_collection_cb = {
+        let cb = props.on_toggle_manual_collection.clone();
+        Callback::from(move |_| cb.emit(()))
+    };
+    let toggle_prospector_cb = {
+        let cb = props.on_toggle_prospector.clone();
+        Callback::from(move |_| cb.emit(()))
+    };
+    let toggle_reduced_effects_cb = {
+        let cb = props.on_toggle_reduced_effects.clone();
+        Callback::from(move |_| cb.emit(()))
+    };
+    let toggle_edge_scroll_cb = {
+        let cb = props.on_toggle_edge_scroll.clone();
+        Callback::from(move |_| cb.emit(()))
+    };
+    let toggle_minimap_cb = {
+        let cb = props.on_toggle_minimap.clone();
+        Callback::from(move |_| cb.emit(()))
+    };
+    let toggle_heartbeat_cb = {
+        let cb = props.on_toggle_heartbeat.clone();
+        Callback::from(move |_| cb.emit(()))
+    };
+    let toggle_pause_when_hidden_cb = {
+        let cb = props.on_toggle_pause_when_hidden.clone();
+        Callback::from(move |_| cb.emit(()))
+    };
+    let toggle_long_catch_up_cb = {
+        let cb = props.on_toggle_long_catch_up.clone();
+        Callback::from(move |_| cb.emit(()))
+    };
     let toggle_secondary_cb = {
         let cb = props.on_toggle_secondary_stats.clone();
         Callback::from(move |_| cb.emit(()))
     };
+    let toggle_map_picker_cb = {
+        let cb = props.on_toggle_map_picker.clone();
+        Callback::from(move |_| cb.emit(()))
+    };
+    let toggle_skip_progression_cb = {
+        let cb = props.on_toggle_skip_progression.clone();
+        Callback::from(move |_| cb.emit(()))
+    };
+    let toggle_siege_cb = {
+        let cb = props.on_toggle_siege.clone();
+        Callback::from(move |_| cb.emit(()))
+    };
+    // Each HUD layout button steps through its presets
+    let hud_cb = |step: fn(HudLayoutSettings) -> HudLayoutSettings| {
+        let cb = props.on_change_hud_layout.clone();
+        let cur = props.hud_layout;
+        Callback::from(move |_| cb.emit(step(cur)))
+    };
+    // Each volume slider writes its own field back into the settings
+    let volume_cb = |set: fn(&mut AudioSettings, u8)| {
+        let cb = props.on_change_audio.clone();
+        let cur = props.audio;
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            if let Ok(v) = input.value().parse::<u8>() {
+                let mut next = cur;
+                set(&mut next, v);
+                cb.emit(next);
+            }
+        })
+    };
+    let cycle_palette_cb = {
+        let cb = props.on_change_theme.clone();
+        let cur = props.theme;
+        Callback::from(move |_| {
+            cb.emit(ThemeSettings {
+                palette: cur.palette.next(),
+                ..cur
+            })
+        })
+    };
+    // The slider works in percent
+    let ui_scale_cb = {
+        let cb = props.on_change_theme.clone();
+        let cur = props.theme;
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            if let Ok(pct) = input.value().parse::<u32>() {
+                cb.emit(cur.with_scale(pct as f64 / 100.0));
+            }
+        })
+    };
+    let toggle_mute_cb = {
+        let cb = props.on_change_audio.clone();
+        let cur = props.audio;
+        Callback::from(move |_| {
+            cb.emit(AudioSettings {
+                muted: !cur.muted,
+                ..cur
+            })
+        })
+    };
+    let listen_cb = |action: HotkeyAction| {
+        let listening = listening.clone();
+        let rebind_error = rebind_error.clone();
+        Callback::from(move |_| {
+            listening.set(Some(action));
+            rebind_error.set(None);
+        })
+    };
+    // The next key pressed on a listening row becomes its binding; Escape cancels. The event
+    // stops here so the board doesn't act on it too.
+    let capture_key_cb = {
+        let listening = listening.clone();
+        let rebind_error = rebind_error.clone();
+        let cb = props.on_change_hotkeys.clone();
+        let cur = props.hotkeys.clone();
+        Callback::from(move |e: KeyboardEvent| {
+            let Some(action) = *listening else {
+                return;
+            };
+            e.prevent_default();
+            e.stop_propagation();
+            let code = e.code();
+            if code == "Escape" {
+                listening.set(None);
+                return;
+            }
+            let mut next = cur.clone();
+            match next.rebind(action, &code) {
+                Ok(()) => {
+                    listening.set(None);
+                    rebind_error.set(None);
+                    cb.emit(next);
+                }
+                Err(err) => rebind_error.set(Some(err.message(&code))),
+            }
+        })
+    };
+    let reset_hotkeys_cb = {
+        let listening = listening.clone();
+        let rebind_error = rebind_error.clone();
+        let cb = props.on_change_hotkeys.clone();
+        Callback::from(move |_| {
+            listening.set(None);
+            rebind_error.set(None);
+            cb.emit(Hotkeys::default());
+        })
+    };
+    let export_save_cb = {
+        let export_text = export_text.clone();
+        let ups = props.upgrade_state.clone();
+        let research = props.research;
+        Callback::from(move |_| export_text.set(Some(save_transfer::session_save(&ups, research))))
+    };
+    let copy_save_cb = {
+        let text = (*export_text).clone().unwrap_or_default();
+        Callback::from(move |_| copy_to_clipboard(&text))
+    };
+    let check_import_cb = {
+        let import_ref = import_ref.clone();
+        let pending_import = pending_import.clone();
+        let import_error = import_error.clone();
+        Callback::from(move |_| {
+            let Some(input) = import_ref.cast::<HtmlTextAreaElement>() else {
+                return;
+            };
+            match save_transfer::decode(&input.value()) {
+                Ok(data) => {
+                    import_error.set(None);
+                    pending_import.set(Some(data));
+                }
+                Err(err) => {
+                    pending_import.set(None);
+                    import_error.set(Some(err.message()));
+                }
+            }
+        })
+    };
+    let confirm_import_cb = {
+        let import_ref = import_ref.clone();
+        let pending_import = pending_import.clone();
+        let cb = props.on_import_save.clone();
+        Callback::from(move |_| {
+            if let Some(data) = (*pending_import).clone() {
+                if let Some(input) = import_ref.cast::<HtmlTextAreaElement>() {
+                    input.set_value("");
+                }
+                pending_import.set(None);
+                cb.emit(data);
+            }
+        })
+    };
+    let cancel_import_cb = {
+        let pending_import = pending_import.clone();
+        Callback::from(move |_| pending_import.set(None))
+    };
+    let import_preview = (*pending_import).as_ref().map(|data| {
+        let p = data.preview();
+        format!(
+            "Replaces {} research and {} upgrade levels with {} research, {} upgrade levels and {} past runs. Settings are replaced too.",
+            props.research,
+            props.upgrade_state.total_levels(),
+            p.research,
+            p.upgrade_levels,
+            p.runs
+        )
+    });
+    let audio = props.audio;
+    let scale_pct = (props.theme.scale() * 100.0).round() as u32;
+    let (scale_min, scale_max) = (
+        (UI_SCALE_MIN * 100.0).round().to_string(),
+        (UI_SCALE_MAX * 100.0).round().to_string(),
+    );
+    let hud = props.hud_layout;
+    let hud_narrow_label = format!("Stack panels below {}px wide", hud.narrow_below_px);
+    let hud_wide_label = format!("Ultra-wide from {:.1}:1", hud.ultrawide_ratio);
+    let hud_margin_label = format!("Ultra-wide margins: {}%", hud.ultrawide_margin_pct);
+    let restart_seed_cb = {
+        let seed_ref = seed_ref.clone();
+        let seed_error = seed_error.clone();
+        let cb = props.on_restart_with_seed.clone();
+        Callback::from(move |_| {
+            let Some(input) = seed_ref.cast::<HtmlInputElement>() else {
+                return;
+            };
+            match input.value().trim().parse::<u64>() {
+                Ok(seed) => {
+                    seed_error.set(false);
+                    cb.emit(seed);
+                }
+                Err(_) => seed_error.set(true),
+            }
+        })
+    };
     let hard_reset_cb = {
         let cb = props.on_hard_reset.clone();
         Callback::from(move |_| {
@@ -60,6 +363,27 @@ pub fn SettingsModal(props: &SettingsModalProps) -> Html {
                 <button onclick={close_cb.clone()} style="padding:4px 8px;">{"Close"}</button>
             </div>
             <div style="display:flex; flex-direction:column; gap:10px;">
+                <label style="display:flex; align-items:center; gap:8px; cursor:pointer;">
+                    <input type="checkbox" checked={props.skip_progression} onclick={toggle_skip_progression_cb} />
+                    <span>{"I've played before (unlock everything now)"}</span>
+                </label>
+                <label style="display:flex; align-items:center; gap:8px; cursor:pointer;">
+                    <input type="checkbox" checked={props.hints} onclick={toggle_hints_cb} />
+                    <span>{"Show Hints (first runs only)"}</span>
+                </label>
+                <label style="display:flex; align-items:center; gap:8px; cursor:pointer;">
+                    <input type="checkbox" checked={props.manual_collection} onclick={toggle_manual_collection_cb} />
+                    <span>{"Manual Collection (sweep the cursor over drops)"}</span>
+                </label>
+                <div style="display:flex; align-items:center; gap:8px;">
+                    <span style="min-width:110px;">{"Colors"}</span>
+                    <button onclick={cycle_palette_cb} style="flex:1; padding:4px 8px;" title="High contrast keeps boosts and enemies apart without relying on red and green">{ props.theme.palette.name() }</button>
+                </div>
+                <label style="display:flex; align-items:center; gap:8px;">
+                    <span style="min-width:110px;">{ format!("UI scale: {}%", scale_pct) }</span>
+                    <input type="range" min={scale_min} max={scale_max} step="5" value={scale_pct.to_string()} oninput={ui_scale_cb} style="flex:1;" />
+                </label>
+                if props.full {
                 <label style="display:flex; align-items:center; gap:8px; cursor:pointer;">
                     <input type="checkbox" checked={props.show_path} onclick={toggle_path_cb} />
                     <span>{"Show Path"}</span>
@@ -68,10 +392,133 @@ pub fn SettingsModal(props: &SettingsModalProps) -> Html {
                     <input type="checkbox" checked={props.show_damage_numbers} onclick={toggle_damage_cb} />
                     <span>{"Show Damage Numbers"}</span>
                 </label>
+                <label style="display:flex; align-items:center; gap:8px; cursor:pointer;">
+                    <input type="checkbox" checked={props.show_health_bars} onclick={toggle_health_bars_cb} />
+                    <span>{"Show Enemy Health Bars"}</span>
+                </label>
+                <label style="display:flex; align-items:center; gap:8px; cursor:pointer;">
+                    <input type="checkbox" checked={props.show_tile_labels} onclick={toggle_tile_labels_cb} />
+                    <span>{"Show Tile Labels When Zoomed In"}</span>
+                </label>
+                <label style="display:flex; align-items:center; gap:8px; cursor:pointer;">
+                    <input type="checkbox" checked={props.prospector} onclick={toggle_prospector_cb} />
+                    <span>{"Prospector Overlay (P)"}</span>
+                </label>
+                <label style="display:flex; align-items:center; gap:8px; cursor:pointer;">
+                    <input type="checkbox" checked={props.reduced_effects} onclick={toggle_reduced_effects_cb} />
+                    <span>{"Reduced Effects"}</span>
+                </label>
+                <label style="display:flex; align-items:center; gap:8px; cursor:pointer;">
+                    <input type="checkbox" checked={props.edge_scroll} onclick={toggle_edge_scroll_cb} />
+                    <span>{"Edge Scrolling (pan with WASD)"}</span>
+                </label>
+                <label style="display:flex; align-items:center; gap:8px; cursor:pointer;">
+                    <input type="checkbox" checked={props.minimap} onclick={toggle_minimap_cb} />
+                    <span>{"Minimap"}</span>
+                </label>
+                <label style="display:flex; align-items:center; gap:8px; cursor:pointer;">
+                    <input type="checkbox" checked={audio.muted} onclick={toggle_mute_cb} />
+                    <span>{"Mute All Sound"}</span>
+                </label>
+                <label style="display:flex; align-items:center; gap:8px;">
+                    <span style="min-width:110px;">{ format!("Master: {}%", audio.master_volume) }</span>
+                    <input type="range" min="0" max="100" step="5" value={audio.master_volume.to_string()} disabled={audio.muted} oninput={volume_cb(|s, v| s.master_volume = v)} style="flex:1;" />
+                </label>
+                <label style="display:flex; align-items:center; gap:8px;">
+                    <span style="min-width:110px;">{ format!("Music: {}%", audio.music_volume) }</span>
+                    <input type="range" min="0" max="100" step="5" value={audio.music_volume.to_string()} disabled={audio.muted} oninput={volume_cb(|s, v| s.music_volume = v)} style="flex:1;" />
+                </label>
+                <label style="display:flex; align-items:center; gap:8px;">
+                    <span style="min-width:110px;">{ format!("Effects: {}%", audio.sfx_volume) }</span>
+                    <input type="range" min="0" max="100" step="5" value={audio.sfx_volume.to_string()} disabled={audio.muted} oninput={volume_cb(|s, v| s.sfx_volume = v)} style="flex:1;" />
+                </label>
+                <label style="display:flex; align-items:center; gap:8px; cursor:pointer;">
+                    <input type="checkbox" checked={props.heartbeat} onclick={toggle_heartbeat_cb} />
+                    <span>{"Heartbeat Sound When Life Is Low"}</span>
+                </label>
+                <label style="display:flex; align-items:center; gap:8px; cursor:pointer;">
+                    <input type="checkbox" checked={props.pause_when_hidden} onclick={toggle_pause_when_hidden_cb} />
+                    <span>{"Pause When Hidden (otherwise catch up on return)"}</span>
+                </label>
+                <label style="display:flex; align-items:center; gap:8px; cursor:pointer;">
+                    <input type="checkbox" checked={props.long_catch_up} disabled={props.pause_when_hidden} onclick={toggle_long_catch_up_cb} />
+                    <span>{"Long Catch-up (up to 5 minutes instead of 5 seconds)"}</span>
+                </label>
                 <label style="display:flex; align-items:center; gap:8px; cursor:pointer;">
                     <input type="checkbox" checked={props.show_secondary_stats} onclick={toggle_secondary_cb} />
                     <span>{"Show Secondary Stats"}</span>
                 </label>
+                <label style="display:flex; align-items:center; gap:8px; cursor:pointer;">
+                    <input type="checkbox" checked={props.map_picker} onclick={toggle_map_picker_cb} />
+                    <span>{"Choose Map Before New Run"}</span>
+                </label>
+                <label style="display:flex; align-items:center; gap:8px; cursor:pointer;">
+                    <input type="checkbox" checked={props.siege} onclick={toggle_siege_cb} />
+                    <span>{"Hardcore Siege: enemies wear down adjacent towers (from next run)"}</span>
+                </label>
+                <div style="display:flex; flex-direction:column; gap:4px;">
+                    <span style="font-size:12px; opacity:0.8;">{ format!("Restart with seed (this map: {})", props.seed) }</span>
+                    <div style="display:flex; gap:6px;">
+                        <input ref={seed_ref} type="text" inputmode="numeric" placeholder={props.seed.to_string()} style="flex:1; font-family:monospace; font-size:12px;" />
+                        <button onclick={restart_seed_cb} style="padding:4px 8px; font-size:12px;">{"Restart"}</button>
+                    </div>
+                    if *seed_error {
+                        <span style="font-size:12px; color:#f85149;">{"Seeds are whole numbers, e.g. 12345"}</span>
+                    }
+                </div>
+                <div style="display:flex; flex-direction:column; gap:6px;">
+                    <span style="font-size:12px; opacity:0.8;">{"HUD layout (portrait always stacks)"}</span>
+                    <div style="display:flex; gap:6px; flex-wrap:wrap;">
+                        <button onclick={hud_cb(HudLayoutSettings::cycle_narrow)} style="padding:4px 8px; font-size:12px;">{ hud_narrow_label }</button>
+                        <button onclick={hud_cb(HudLayoutSettings::cycle_ultrawide)} style="padding:4px 8px; font-size:12px;">{ hud_wide_label }</button>
+                        <button onclick={hud_cb(HudLayoutSettings::cycle_margin)} style="padding:4px 8px; font-size:12px;">{ hud_margin_label }</button>
+                    </div>
+                </div>
+                <div style="display:flex; flex-direction:column; gap:4px;">
+                    <span style="font-size:12px; opacity:0.8;">{"Controls (click a row, then press a key; WASD, arrows and 1-3 are fixed)"}</span>
+                    { for HotkeyAction::ALL.iter().map(|a| {
+                        let waiting = *listening == Some(*a);
+                        let key = if waiting { "Press a key…".to_string() } else { props.hotkeys.label(*a) };
+                        let border = if waiting { "#58a6ff" } else { "#30363d" };
+                        html!{
+                            <button onclick={listen_cb(*a)} onkeydown={capture_key_cb.clone()}
+                                style={format!("display:flex; justify-content:space-between; gap:12px; padding:3px 8px; font-size:12px; border:1px solid {border};")}>
+                                <span>{ a.label() }</span>
+                                <kbd style="font-family:monospace;">{ key }</kbd>
+                            </button>
+                        }
+                    }) }
+                    { if let Some(msg) = &*rebind_error {
+                        html!{ <span style="font-size:12px; color:#f85149;">{ msg.clone() }</span> }
+                    } else { html!{} } }
+                    <button onclick={reset_hotkeys_cb} style="padding:4px 8px; font-size:12px; align-self:flex-start;">{"Reset controls to defaults"}</button>
+                </div>
+                }
+            </div>
+            <div style="display:flex; flex-direction:column; gap:6px;">
+                <span style="font-size:12px; opacity:0.8;">{"Transfer save (upgrades, research, run history and settings)"}</span>
+                <div style="display:flex; gap:6px;">
+                    <button onclick={export_save_cb} style="padding:4px 8px; font-size:12px;">{"Export save"}</button>
+                    if export_text.is_some() {
+                        <button onclick={copy_save_cb} style="padding:4px 8px; font-size:12px;">{"Copy"}</button>
+                    }
+                </div>
+                if let Some(text) = (*export_text).clone() {
+                    <textarea readonly=true value={text} rows="3" style="font-family:monospace; font-size:11px; resize:vertical;" />
+                }
+                <textarea ref={import_ref} rows="2" placeholder="Paste a save to import" style="font-family:monospace; font-size:11px; resize:vertical;" />
+                if let Some(preview) = import_preview {
+                    <span style="font-size:12px; color:#d29922;">{ preview }</span>
+                    <div style="display:flex; gap:6px;">
+                        <button onclick={confirm_import_cb} style="padding:4px 8px; font-size:12px;">{"Overwrite progress"}</button>
+                        <button onclick={cancel_import_cb} style="padding:4px 8px; font-size:12px;">{"Cancel"}</button>
+                    </div>
+                } else {
+                    <button onclick={check_import_cb} style="padding:4px 8px; font-size:12px; align-self:flex-start;">{"Import save"}</button>
+                }
+                if let Some(msg) = (*import_error).clone() {
+                    <span style="font-size:12px; color:#f85149;">{ msg }</span>
+                }
             </div>
             <div style="display:flex; gap:8px; flex-wrap:wrap;">
                 <button onclick={hard_reset_cb} style="background:#f85149; border:1px solid #b62324; color:#fff; flex:1;">{"Hard Reset (Wipe Progress)"}</button>
@@ -0,0 +1,42 @@
+use crate::model::{StampId, STAMP_CREDITS_PER_TILE};
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct StampPaletteProps {
+    pub selected: Option<StampId>,
+    pub rotation: u8,
+    pub credits: u64,
+    // Clicking the selected stamp again deselects it
+    pub on_select: Callback<Option<StampId>>,
+    pub on_rotate: Callback<()>,
+}
+
+#[function_component]
+pub fn StampPalette(props: &StampPaletteProps) -> Html {
+    let rotate_cb = {
+        let cb = props.on_rotate.clone();
+        Callback::from(move |_| cb.emit(()))
+    };
+    // Upper bound: cells already of the target kind cost nothing
+    let max_cost = |id: StampId| id.def().cells.len() as u64 * STAMP_CREDITS_PER_TILE;
+    html! {<div style="position:absolute; left:12px; top:50%; transform:translateY(-50%); background:rgba(22,27,34,0.9); border:1px solid #30363d; border-radius:8px; padding:8px 10px; display:flex; flex-direction:column; gap:6px; font-size:12px; min-width:130px;">
+        <div style="opacity:0.8;">{ format!("Stamps (B) · {} credits", props.credits) }</div>
+        { for StampId::ALL.iter().map(|&id| {
+            let def = id.def();
+            let active = props.selected == Some(id);
+            let onclick = {
+                let cb = props.on_select.clone();
+                Callback::from(move |_| cb.emit(if active { None } else { Some(id) }))
+            };
+            let border = if active { "#d29922" } else { "#30363d" };
+            html!{
+                <button {onclick} style={format!("padding:4px 6px; text-align:left; border:1px solid {};", border)}>
+                    { format!("{} (≤{})", def.name, max_cost(id)) }
+                </button>
+            }
+        }) }
+        if props.selected.is_some() {
+            <button onclick={rotate_cb} style="padding:4px 6px;">{ format!("Rotate (R): {}°", props.rotation as u32 * 90) }</button>
+        }
+    </div>}
+}
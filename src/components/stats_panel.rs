@@ -1,3 +1,4 @@
+use crate::util::copy_to_clipboard;
 use yew::prelude::*;
 
 #[derive(Properties, PartialEq, Clone)]
@@ -5,6 +6,49 @@ pub struct StatsPanelProps {
     pub gold: u64,
     pub life: u32,
     pub research: u64,
+    // Damage earned toward the next research point, 0..1
+    #[prop_or_default]
+    pub research_progress: f64,
+    pub streak: u32,
+    pub streak_mult: f64,
+    // Low-life warning: the life row grows and pulses
+    pub low_life: bool,
+    pub reduced_effects: bool,
+    // sim_time of a life loss still being flashed: the life number pulses once
+    #[prop_or_default]
+    pub life_hit: Option<f64>,
+    // Overheal shield points, spent before life
+    #[prop_or_default]
+    pub shield: u32,
+    // Earned by mining; each tower and wall takes some
+    #[prop_or_default]
+    pub tile_credits: u64,
+    // (total DPS, loop coverage 0..=1), once there are towers
+    #[prop_or_default]
+    pub tower_totals: Option<(f64, f64)>,
+    // Latest bank payment, once the Bank upgrade is owned
+    #[prop_or_default]
+    pub interest: Option<u64>,
+    // Head of the upgrade queue: (name, cost, entries queued)
+    #[prop_or_default]
+    pub queued: Option<(String, u64, usize)>,
+    // (kills, tower damage per second over the last 10s), once the run has started
+    #[prop_or_default]
+    pub session: Option<(u64, f64)>,
+    // Map seed, for replaying or sharing the layout; scenarios have none
+    #[prop_or_default]
+    pub seed: Option<u64>,
+}
+
+// Coverage readout: red under 40%, yellow under 75%, green from there
+fn coverage_color(pct: f64) -> &'static str {
+    if pct < 40.0 {
+        "#f85149"
+    } else if pct < 75.0 {
+        "#d29922"
+    } else {
+        "#2ea043"
+    }
 }
 
 #[function_component]
@@ -14,6 +58,23 @@ pub fn StatsPanel(props: &StatsPanelProps) -> Html {
     let label_style = "flex:1; font-weight:500;";
     let value_style =
         "min-width:70px; text-align:right; font-variant-numeric:tabular-nums; font-weight:600;";
+    let life_class = classes!(
+        props.low_life.then_some("low-life-stat"),
+        (props.low_life && props.reduced_effects).then_some("still")
+    );
+    let life_hit_class = classes!(
+        "life-value",
+        props.life_hit.is_some().then_some("life-hit"),
+        props.reduced_effects.then_some("still")
+    );
+    let totals = props.tower_totals.map(|(dps, coverage)| {
+        let pct = coverage * 100.0;
+        (
+            format!("{dps:.1}"),
+            format!("{pct:.0}%"),
+            coverage_color(pct),
+        )
+    });
     html! {
         <div style="position:absolute; top:12px; left:12px; background:rgba(22,27,34,0.9); border:1px solid #30363d; border-radius:8px; padding:10px 14px; min-width:230px; display:flex; flex-direction:column; gap:10px; font-size:14px;">
             <div style={row_style}>
@@ -21,16 +82,77 @@ pub fn StatsPanel(props: &StatsPanelProps) -> Html {
                 <span style={format!("{} color:#d4af37;", label_style)}>{"Gold"}</span>
                 <span style={format!("{} color:#d4af37;", value_style)}>{ props.gold }</span>
             </div>
-            <div style={row_style}>
+            <div style={row_style} class={life_class}>
                 <span style={format!("{} color:#f85149;", icon_style)}>{"❤"}</span>
                 <span style={format!("{} color:#f85149;", label_style)}>{"Life"}</span>
-                <span style={format!("{} color:#f85149;", value_style)}>{ props.life }</span>
+                <span style={format!("{} color:#f85149;", value_style)}>
+                    <span key={props.life_hit.map(|t| t.to_string()).unwrap_or_default()} class={life_hit_class}>{ props.life }</span>
+                    { if props.shield > 0 { html!{<span style="color:#7ee787;" title="Overheal shield: lost before life">{format!(" +{}🛡", props.shield)}</span>} } else { html!{} } }
+                </span>
             </div>
             <div style={row_style}>
                 <span style={format!("{} color:#58a6ff;", icon_style)}>{"🔬"}</span>
                 <span style={format!("{} color:#58a6ff;", label_style)}>{"Research"}</span>
                 <span style={format!("{} color:#58a6ff;", value_style)}>{ props.research }</span>
             </div>
+            <div style="height:3px; margin:-7px 0 0 28px; background:#21262d; border-radius:2px; overflow:hidden;" title="Tower damage toward the next research point">
+                <div style={format!("height:100%; width:{:.1}%; background:#58a6ff;", props.research_progress.clamp(0.0, 1.0) * 100.0)}></div>
+            </div>
+            <div style={row_style} title="Earned by mining rock; every tower placed takes one">
+                <span style={format!("{} color:#a5a5a5;", icon_style)}>{"🧱"}</span>
+                <span style={format!("{} color:#a5a5a5;", label_style)}>{"Tile Credits"}</span>
+                <span style={format!("{} color:#a5a5a5;", value_style)}>{ props.tile_credits }</span>
+            </div>
+            <div style={row_style} title="Leak-free minutes in a row multiply research income">
+                <span style={format!("{} color:#f0883e;", icon_style)}>{"🔥"}</span>
+                <span style={format!("{} color:#f0883e;", label_style)}>{"Streak"}</span>
+                <span style={format!("{} color:#f0883e;", value_style)}>{ format!("{} · x{:.1}", props.streak, props.streak_mult) }</span>
+            </div>
+            if let Some(interest) = props.interest {
+                <div style={row_style} title="Bank interest on held gold, paid every 10 seconds">
+                    <span style={format!("{} color:#d4af37;", icon_style)}>{"🏦"}</span>
+                    <span style={format!("{} color:#d4af37;", label_style)}>{"Interest"}</span>
+                    <span style={format!("{} color:#d4af37;", value_style)}>{ format!("+{interest}") }</span>
+                </div>
+            }
+            if let Some((name, cost, count)) = props.queued.clone() {
+                <div style={row_style} title="Upgrades queued on the upgrades screen, bought as research arrives">
+                    <span style={format!("{} color:#58a6ff;", icon_style)}>{"🛒"}</span>
+                    <span style={format!("{} color:#58a6ff;", label_style)}>{"Queue"}</span>
+                    <span style={format!("{} color:#58a6ff; font-size:12px;", value_style)}>
+                        { format!("{name} {cost}") }
+                        if count > 1 {
+                            { format!(" +{}", count - 1) }
+                        }
+                    </span>
+                </div>
+            }
+            if let Some((dps, coverage, color)) = totals {
+                <div style={row_style} title="Theoretical damage per second of all towers, and the share of the loop in range of at least one">
+                    <span style={format!("{} color:#c9d1d9;", icon_style)}>{"⚔"}</span>
+                    <span style={format!("{} color:#c9d1d9;", label_style)}>{"DPS"}</span>
+                    <span style={format!("{} color:#c9d1d9;", value_style)}>
+                        { dps }{" | Coverage: "}<span style={format!("color:{color};")}>{ coverage }</span>
+                    </span>
+                </div>
+            }
+            if let Some(seed) = props.seed {
+                <div style={format!("{row_style} font-size:12px; color:#8b949e; cursor:pointer;")} title="Click to copy. Settings > Restart with seed replays this map"
+                    onclick={Callback::from(move |_| copy_to_clipboard(&seed.to_string()))}>
+                    <span style={icon_style}>{"🌱"}</span>
+                    <span style={label_style}>{"Seed"}</span>
+                    <span style={format!("{value_style} font-family:monospace;")}>{ seed }</span>
+                </div>
+            }
+            if let Some((kills, live_dps)) = props.session {
+                <div style="border-top:1px solid #30363d; padding-top:8px; display:flex; flex-direction:column; gap:4px; font-size:12px; color:#8b949e;">
+                    <span style="font-weight:600; text-transform:uppercase; letter-spacing:0.05em;">{"Session"}</span>
+                    <div style={row_style} title="Tower damage actually dealt over the last 10 seconds">
+                        <span style={label_style}>{ format!("{kills} kills") }</span>
+                        <span style={value_style}>{ format!("{live_dps:.1} DPS") }</span>
+                    </div>
+                </div>
+            }
         </div>
     }
 }
@@ -1,4 +1,9 @@
-use crate::model::{BoostKind, TileKind, UpgradeId, UpgradeState};
+use crate::model::{
+    tower_repair_cost, BoostKind, TileKind, Tower, TowerKind, TowerStats, UpgradeId, UpgradeState,
+    BOOST_DAMAGE_MULT, BOOST_FIRE_RATE_MULT, BOOST_RANGE_BONUS, FREEZE_CHANCE_PER_LEVEL,
+    HEAL_AURA_RADIUS_PER_LEVEL, HEAL_PULSE_BASE, HEAL_PULSE_PER_POWER, HEAL_PULSE_SECS,
+    HEAL_SHIELD_PER_LEVEL, TOWER_MAX_LEVEL, VETERANCY_DAMAGE_BONUS, VETERANCY_THRESHOLDS,
+};
 use yew::prelude::*;
 
 #[derive(Properties, PartialEq, Clone)]
@@ -6,7 +11,17 @@ pub struct TileInfoPanelProps {
     pub tile: Option<TileKind>,
     pub tile_x: i32,
     pub tile_y: i32,
+    pub tower: Option<Tower>,
     pub upgrade_state: UpgradeState,
+    pub on_toggle_tower_exclusion: Callback<(u32, u32)>,
+    // Hardcore siege: towers show durability and can be repaired for gold
+    pub siege: bool,
+    pub gold: u64,
+    pub on_repair_tower: Callback<(u32, u32)>,
+    // Kind swap: the tower's current stats, then each other kind with its stats and gold cost
+    pub tower_stats: Option<TowerStats>,
+    pub swap_options: Vec<(TowerKind, TowerStats, u64)>,
+    pub on_swap_kind: Callback<(u32, u32, TowerKind)>,
 }
 
 fn boost_color(boost: &BoostKind) -> &'static str {
@@ -16,16 +31,7 @@ fn boost_color(boost: &BoostKind) -> &'static str {
         BoostKind::Fire => "#f97316",
         BoostKind::Range => "#22c55e",
         BoostKind::FireRate => "#eab308",
-    }
-}
-
-fn boost_name(boost: &BoostKind) -> &'static str {
-    match boost {
-        BoostKind::Slow => "Cold",
-        BoostKind::Damage => "Poison",
-        BoostKind::Fire => "Fire",
-        BoostKind::Range => "Healing",
-        BoostKind::FireRate => "Fire Rate",
+        BoostKind::Healing => "#7ee787",
     }
 }
 
@@ -34,13 +40,50 @@ fn boost_icon(boost: &BoostKind) -> &'static str {
         BoostKind::Slow => "❄",
         BoostKind::Damage => "☠",
         BoostKind::Fire => "🔥",
-        BoostKind::Range => "✚",
+        BoostKind::Range => "⌖",
         BoostKind::FireRate => "⚡",
+        BoostKind::Healing => "✚",
+    }
+}
+
+// Before/after rows; every stat is better when higher
+pub fn compare_table(before: &TowerStats, after: &TowerStats) -> Html {
+    let rows = [
+        ("Damage", before.damage, after.damage, 1),
+        ("Range", before.range, after.range, 2),
+        ("Fire Rate", before.fire_rate, after.fire_rate, 2),
+        ("DPS", before.dps, after.dps, 1),
+        ("Coverage", before.coverage as f64, after.coverage as f64, 0),
+    ];
+    html! {
+        <div style="margin-top:6px; padding:6px 8px; background:rgba(0,0,0,0.3); border-radius:4px; font-size:11px; display:grid; grid-template-columns:1fr auto auto; gap:2px 10px;">
+            <span style="color:#8b949e;">{""}</span>
+            <span style="color:#8b949e;">{"Now"}</span>
+            <span style="color:#8b949e;">{"After"}</span>
+            { for rows.iter().map(|&(label, b, a, prec)| {
+                let color = if a > b + 1e-9 {
+                    "#3fb950"
+                } else if a + 1e-9 < b {
+                    "#f85149"
+                } else {
+                    "#c9d1d9"
+                };
+                html! {
+                    <>
+                        <span style="color:#8b949e;">{label}</span>
+                        <span>{format!("{:.*}", prec, b)}</span>
+                        <span style={format!("color:{};", color)}>{format!("{:.*}", prec, a)}</span>
+                    </>
+                }
+            }) }
+        </div>
     }
 }
 
 #[function_component]
 pub fn TileInfoPanel(props: &TileInfoPanelProps) -> Html {
+    // Swap option under the pointer, previewed as a before/after table
+    let hovered_swap = use_state(|| None::<TowerKind>);
     let Some(tile) = &props.tile else {
         return html! {};
     };
@@ -59,13 +102,139 @@ pub fn TileInfoPanel(props: &TileInfoPanelProps) -> Html {
     let stat_label_style = "color:#8b949e;";
     let stat_value_style = "font-weight:500;";
 
+    let tower_section = if let Some(tw) = &props.tower {
+        let rank = tw.rank();
+        let chevrons = "⌃".repeat(rank as usize);
+        let bonus = (tw.veterancy_mult() - 1.0) * 100.0;
+        let next = match VETERANCY_THRESHOLDS.get(rank as usize) {
+            Some(t) => format!("{} dmg", t),
+            None => "Max".to_string(),
+        };
+        let toggle_zones_cb = {
+            let cb = props.on_toggle_tower_exclusion.clone();
+            let at = (tw.x, tw.y);
+            Callback::from(move |_| cb.emit(at))
+        };
+        let durability = if props.siege {
+            let cost = tower_repair_cost(tw);
+            let repair_cb = {
+                let cb = props.on_repair_tower.clone();
+                let at = (tw.x, tw.y);
+                Callback::from(move |_| cb.emit(at))
+            };
+            html! {
+                <>
+                    <div style={stat_row_style}>
+                        <span style={stat_label_style}>{"Durability"}</span>
+                        <span style={stat_value_style}>{format!("{:.0} / {:.0}", tw.hp.max(0.0), tw.max_hp)}</span>
+                    </div>
+                    if cost > 0 {
+                        <button onclick={repair_cb} disabled={props.gold < cost} style="margin-top:4px; width:100%; font-size:12px;">
+                            {format!("Repair ({} gold)", cost)}
+                        </button>
+                    }
+                </>
+            }
+        } else {
+            html! {}
+        };
+        let swap_buttons = props.swap_options.iter().map(|(kind, _, cost)| {
+            let onclick = {
+                let cb = props.on_swap_kind.clone();
+                let (at, kind) = ((tw.x, tw.y), kind.clone());
+                Callback::from(move |_| cb.emit((at.0, at.1, kind.clone())))
+            };
+            let onmouseenter = {
+                let hovered_swap = hovered_swap.clone();
+                let kind = kind.clone();
+                Callback::from(move |_| hovered_swap.set(Some(kind.clone())))
+            };
+            let onmouseleave = {
+                let hovered_swap = hovered_swap.clone();
+                Callback::from(move |_| hovered_swap.set(None))
+            };
+            html! {
+                <button {onclick} {onmouseenter} {onmouseleave} disabled={props.gold < *cost} style="flex:1; font-size:12px;">
+                    {format!("{} ({}g)", kind.name(), cost)}
+                </button>
+            }
+        });
+        let comparison = props
+            .tower_stats
+            .as_ref()
+            .zip(
+                props
+                    .swap_options
+                    .iter()
+                    .find(|(k, _, _)| Some(k) == hovered_swap.as_ref()),
+            )
+            .map(|(before, (_, after, _))| compare_table(before, after))
+            .unwrap_or_default();
+        let on_hit = tw
+            .kind_debuff()
+            .map(|d| format!("Slow {:.0}% for {:.1}s", d.strength * 100.0, d.remaining));
+        html! {
+            <div style={section_style}>
+                <div style="font-weight:600; display:flex; align-items:center; gap:6px;">
+                    <span>{format!("{} Tower", tw.kind.name())}</span>
+                    <span style="color:#ffd700;">{chevrons}</span>
+                </div>
+                <div style={stat_row_style}>
+                    <span style={stat_label_style}>{"Targeting (R)"}</span>
+                    <span style={stat_value_style}>{tw.target_mode.name()}</span>
+                </div>
+                <div style={stat_row_style}>
+                    <span style={stat_label_style}>{"Level"}</span>
+                    <span style={stat_value_style}>{format!("{} / {}", tw.level, TOWER_MAX_LEVEL)}</span>
+                </div>
+                <div style={stat_row_style}>
+                    <span style={stat_label_style}>{"Rank"}</span>
+                    <span style={stat_value_style}>{format!("{} / {}", rank, VETERANCY_THRESHOLDS.len())}</span>
+                </div>
+                <div style={stat_row_style}>
+                    <span style={stat_label_style}>{"Damage Dealt"}</span>
+                    <span style={stat_value_style}>{tw.damage_dealt}</span>
+                </div>
+                <div style={stat_row_style}>
+                    <span style={stat_label_style}>{"Veterancy Bonus"}</span>
+                    <span style={stat_value_style}>{format!("+{:.1}%", bonus)}</span>
+                </div>
+                <div style={stat_row_style}>
+                    <span style={stat_label_style}>{"Next Rank"}</span>
+                    <span style={stat_value_style}>{next}</span>
+                </div>
+                if let Some(text) = on_hit {
+                    <div style={stat_row_style}>
+                        <span style={stat_label_style}>{"On Hit"}</span>
+                        <span style={stat_value_style}>{text}</span>
+                    </div>
+                }
+                {durability}
+                <div style="margin-top:6px; font-size:11px; color:#8b949e;">
+                    {format!("Each rank: +{:.0}% damage. Kept when moved, lost when removed.", VETERANCY_DAMAGE_BONUS * 100.0)}
+                </div>
+                <label style="margin-top:6px; display:flex; align-items:center; gap:6px; font-size:12px; cursor:pointer;">
+                    <input type="checkbox" checked={tw.ignore_exclusion} onclick={toggle_zones_cb} />
+                    <span>{"Ignore no-target zones"}</span>
+                </label>
+                if !props.swap_options.is_empty() {
+                    <div style="margin-top:8px; font-size:11px; color:#8b949e;">{"Swap kind (hover to compare)"}</div>
+                    <div style="margin-top:4px; display:flex; gap:6px;">{ for swap_buttons }</div>
+                    {comparison}
+                }
+            </div>
+        }
+    } else {
+        html! {}
+    };
+
     match tile {
         TileKind::Rock { has_gold, boost } => {
             let base_name = if *has_gold { "Gold Rock" } else { "Rock" };
 
             let boost_section = if let Some(b) = boost {
                 let color = boost_color(b);
-                let name = boost_name(b);
+                let name = b.name();
                 let icon = boost_icon(b);
 
                 let (tower_stats, debuff_info) = match b {
@@ -73,18 +242,26 @@ pub fn TileInfoPanel(props: &TileInfoPanelProps) -> Html {
                         let range_bonus = 12.0 * l(UpgradeId::BoostColdRange);
                         let slow_pct = 50.0 * (1.0 + 0.10 * l(UpgradeId::BoostColdSlowAmount));
                         let duration = 1.0 + 1.0 * l(UpgradeId::BoostColdSlowDuration);
+                        let freeze = FREEZE_CHANCE_PER_LEVEL * l(UpgradeId::BoostColdFreezeChance);
                         let intrinsic = -30.0;
+                        let mut stats = vec![
+                            ("Tower Range", format!("{:+.0}% (intrinsic)", intrinsic)),
+                            ("Range Upgrade", format!("+{:.0}%", range_bonus)),
+                        ];
+                        if freeze > 0.0 {
+                            stats.push(("Freeze Chance", format!("{:.0}%", freeze * 100.0)));
+                        }
                         (
-                            vec![
-                                ("Tower Range", format!("{:+.0}% (intrinsic)", intrinsic)),
-                                ("Range Upgrade", format!("+{:.0}%", range_bonus)),
-                            ],
+                            stats,
                             Some(format!("Slow: {:.0}% for {:.1}s", slow_pct, duration)),
                         )
                     }
                     BoostKind::Damage => {
-                        let range_bonus = 15.0 * l(UpgradeId::BoostPoisonRange);
-                        let damage_bonus = 20.0 * l(UpgradeId::BoostPoisonRange);
+                        let range_bonus = 12.0 * l(UpgradeId::BoostPoisonRange);
+                        let damage_bonus = (BOOST_DAMAGE_MULT
+                            * (1.0 + 0.05 * l(UpgradeId::BoostPoisonDamage))
+                            - 1.0)
+                            * 100.0;
                         let dps = 1.0 * (1.0 + 0.05 * l(UpgradeId::BoostPoisonDamage));
                         let duration = 2.0 + 1.0 * l(UpgradeId::BoostPoisonDuration);
                         (
@@ -109,17 +286,38 @@ pub fn TileInfoPanel(props: &TileInfoPanelProps) -> Html {
                             Some(format!("Burn: {:.1} DPS for {:.1}s", dps, duration)),
                         )
                     }
-                    BoostKind::Range => {
-                        let range_upgrade = 10.0 * l(UpgradeId::BoostHealingPower);
-                        (
-                            vec![
-                                ("Tower Range", "+15% (intrinsic)".to_string()),
-                                ("Range Upgrade", format!("+{:.0}%", range_upgrade)),
-                            ],
-                            None,
-                        )
+                    BoostKind::Range => (
+                        vec![(
+                            "Tower Range",
+                            format!("+{BOOST_RANGE_BONUS:.0} tile (intrinsic)"),
+                        )],
+                        None,
+                    ),
+                    BoostKind::Healing => {
+                        let pulse = HEAL_PULSE_BASE
+                            + HEAL_PULSE_PER_POWER * l(UpgradeId::BoostHealingPower);
+                        let aura = HEAL_AURA_RADIUS_PER_LEVEL * l(UpgradeId::BoostHealingRadius);
+                        let shield =
+                            HEAL_SHIELD_PER_LEVEL * ups.level(UpgradeId::BoostHealingShield) as u32;
+                        let mut stats = vec![(
+                            "Heal Pulse",
+                            format!("+{pulse:.1} life / {HEAL_PULSE_SECS:.0}s"),
+                        )];
+                        if aura > 0.0 {
+                            stats.push(("Aura", format!("{aura:.1} tiles")));
+                        }
+                        if shield > 0 {
+                            stats.push(("Overheal Shield", format!("up to {shield}")));
+                        }
+                        (stats, None)
                     }
-                    BoostKind::FireRate => (vec![("Fire Rate", "+15%".to_string())], None),
+                    BoostKind::FireRate => (
+                        vec![(
+                            "Fire Rate",
+                            format!("+{:.0}%", (BOOST_FIRE_RATE_MULT - 1.0) * 100.0),
+                        )],
+                        None,
+                    ),
                 };
 
                 html! {
@@ -179,6 +377,7 @@ pub fn TileInfoPanel(props: &TileInfoPanelProps) -> Html {
                     </div>
                     {gold_info}
                     {boost_section}
+                    {tower_section}
                 </div>
             }
         }
@@ -211,6 +410,7 @@ pub fn TileInfoPanel(props: &TileInfoPanelProps) -> Html {
                     <div style="font-size:11px; color:#8b949e;">
                         {"Blocks enemy movement. Can be mined."}
                     </div>
+                    {tower_section}
                 </div>
             }
         }
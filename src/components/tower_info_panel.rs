@@ -0,0 +1,91 @@
+use crate::components::tile_info_panel::compare_table;
+use crate::model::{Tower, TowerStats, TOWER_MAX_LEVEL};
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct TowerInfoPanelProps {
+    // The clicked tower and its stats as they stand right now
+    pub tower: Tower,
+    pub stats: TowerStats,
+    pub gold: u64,
+    // None at max level
+    pub upgrade_cost: Option<u64>,
+    // Stats after the next level, compared while Upgrade is hovered; None at max level
+    pub upgrade_stats: Option<TowerStats>,
+    pub sell_refund: u64,
+    // A move is pending: the next board click picks the tile
+    pub moving: bool,
+    pub on_sell: Callback<(u32, u32)>,
+    pub on_upgrade: Callback<(u32, u32)>,
+    pub on_move: Callback<(u32, u32)>,
+    pub on_close: Callback<()>,
+}
+
+#[function_component]
+pub fn TowerInfoPanel(props: &TowerInfoPanelProps) -> Html {
+    let upgrade_hovered = use_state(|| false);
+    let tw = &props.tower;
+    let at = (tw.x, tw.y);
+    let sell_cb = {
+        let cb = props.on_sell.clone();
+        Callback::from(move |_| cb.emit(at))
+    };
+    let upgrade_cb = {
+        let cb = props.on_upgrade.clone();
+        Callback::from(move |_| cb.emit(at))
+    };
+    let move_cb = {
+        let cb = props.on_move.clone();
+        Callback::from(move |_| cb.emit(at))
+    };
+    let close_cb = {
+        let cb = props.on_close.clone();
+        Callback::from(move |_| cb.emit(()))
+    };
+    let rows = [
+        ("Damage", format!("{:.0}", props.stats.damage)),
+        ("Range", format!("{:.2}", props.stats.range)),
+        ("Fire rate", format!("{:.2}/s", props.stats.fire_rate)),
+        ("DPS", format!("{:.1}", props.stats.dps)),
+        ("Damage dealt", tw.damage_dealt.to_string()),
+        ("Kills", tw.kills.to_string()),
+        ("Targeting", tw.target_mode.name().to_string()),
+    ];
+    let upgrade_label = match props.upgrade_cost {
+        Some(cost) => format!("Upgrade ({}g)", cost),
+        None => "Max level".to_string(),
+    };
+    let upgrade_enter = {
+        let upgrade_hovered = upgrade_hovered.clone();
+        Callback::from(move |_| upgrade_hovered.set(true))
+    };
+    let upgrade_leave = {
+        let upgrade_hovered = upgrade_hovered.clone();
+        Callback::from(move |_| upgrade_hovered.set(false))
+    };
+    let comparison = props
+        .upgrade_stats
+        .as_ref()
+        .filter(|_| *upgrade_hovered)
+        .map(|after| compare_table(&props.stats, after))
+        .unwrap_or_default();
+    let can_upgrade = props.upgrade_cost.is_some_and(|c| props.gold >= c);
+    html! {<div style="position:absolute; left:12px; bottom:72px; background:rgba(22,27,34,0.92); border:1px solid #30363d; border-radius:8px; padding:10px 12px; min-width:200px; display:flex; flex-direction:column; gap:6px; font-size:13px;">
+        <div style="display:flex; justify-content:space-between; align-items:center; gap:8px;">
+            <strong>{ format!("{} Tower · Lv {}/{}", tw.kind.name(), tw.level, TOWER_MAX_LEVEL) }</strong>
+            <button onclick={close_cb} title="Deselect (Esc)" style="padding:0 6px;">{ "×" }</button>
+        </div>
+        { for rows.iter().map(|(label, value)| html!{
+            <div style="display:flex; justify-content:space-between; gap:12px;">
+                <span style="opacity:0.7;">{ *label }</span>
+                <span>{ value.clone() }</span>
+            </div>
+        }) }
+        <div style="display:flex; gap:6px; margin-top:4px;">
+            <button onclick={upgrade_cb} onmouseenter={upgrade_enter} onmouseleave={upgrade_leave} disabled={!can_upgrade} style="flex:1; padding:4px 6px;">{ upgrade_label }</button>
+            <button onclick={move_cb} title="Then click a rock or wall (Esc cancels)" style="flex:1; padding:4px 6px;">{ if props.moving { "Moving…" } else { "Move" } }</button>
+            <button onclick={sell_cb} style="flex:1; padding:4px 6px; color:#f85149;">{ format!("Sell (+{}g)", props.sell_refund) }</button>
+        </div>
+        {comparison}
+    </div> }
+}
@@ -1,14 +1,41 @@
+use crate::state::{HintAction, Hotkeys};
 use yew::prelude::*;
 
 #[derive(Properties, PartialEq, Clone)]
 pub struct TowerPanelProps {
     pub tower_feedback: Option<String>,
+    // Shown instead of the placement hint while towers are still locked
+    pub locked_hint: Option<String>,
+    // e.g. most of the path excluded from targeting
+    pub warning: Option<String>,
+    // Actions valid at the focused tile (state::key_hints); empty hides the bar
+    #[prop_or_default]
+    pub hints: Vec<HintAction>,
+    // Current bindings, for the keys shown beside each hint
+    #[prop_or_default]
+    pub hotkeys: Hotkeys,
 }
 
 #[function_component]
 pub fn TowerPanel(props: &TowerPanelProps) -> Html {
     html! {<div style="position:absolute; left:50%; bottom:28px; transform:translateX(-50%); background:rgba(22,27,34,0.92); border:1px solid #30363d; border-radius:10px; padding:10px 14px; display:flex; flex-direction:column; gap:6px; min-width:240px; text-align:center;">
-        <div style="font-size:13px; opacity:0.8;">{"Press 'T' to place/remove tower on Rock/Wall"}</div>
+        { if let Some(hint) = &props.locked_hint {
+            html!{ <div style="font-size:13px; opacity:0.8;">{ format!("🔒 {}", hint) }</div> }
+        } else if !props.hints.is_empty() {
+            html!{ <div style="font-size:12px; display:flex; flex-wrap:wrap; justify-content:center; gap:4px 12px;">
+                { for props.hints.iter().map(|a| html!{
+                    <span style="white-space:nowrap;">
+                        <kbd style="font-family:monospace; background:#1c2128; border:1px solid #30363d; border-radius:4px; padding:0 4px; margin-right:4px;">{ a.key(&props.hotkeys) }</kbd>
+                        <span style="opacity:0.8;">{ a.label() }</span>
+                    </span>
+                }) }
+            </div> }
+        } else {
+            html!{}
+        } }
+        { if let Some(w) = &props.warning {
+            html!{ <div style="font-size:12px; color:#f85149;">{ format!("⚠ {}", w) }</div> }
+        } else { html!{} } }
         { if let Some(msg) = &props.tower_feedback {
             if !msg.is_empty() {
                 html!{ <div style="font-size:12px; line-height:1.25; background:#1c2128; border:1px solid #30363d; padding:6px 8px; border-radius:6px;">{ msg.clone() }</div>}
@@ -0,0 +1,39 @@
+use yew::prelude::*;
+
+use crate::state::tutorial::TutorialStep;
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct TutorialCardProps {
+    pub step: TutorialStep,
+    // (step, total), 1-based
+    pub progress: (usize, usize),
+    // Why the step can't be done yet, e.g. "Towers unlock next run"
+    #[prop_or_default]
+    pub locked: Option<String>,
+    pub on_skip: Callback<()>,
+}
+
+// The current tutorial step; it moves on by itself once the run shows the step done
+#[function_component(TutorialCard)]
+pub fn tutorial_card(props: &TutorialCardProps) -> Html {
+    let (n, total) = props.progress;
+    let skip = {
+        let cb = props.on_skip.clone();
+        Callback::from(move |_| cb.emit(()))
+    };
+    html! {
+        <div style="position:absolute; bottom:84px; left:50%; transform:translateX(-50%); background:#161b22; border:1px solid #58a6ff; border-radius:10px; padding:12px 16px; max-width:360px; z-index:35; display:flex; flex-direction:column; gap:8px;">
+            <div style="display:flex; justify-content:space-between; gap:12px;">
+                <span style="font-weight:600;">{ props.step.title() }</span>
+                <span style="font-size:12px; color:#8b949e;">{ format!("Tutorial {n}/{total}") }</span>
+            </div>
+            <div style="font-size:13px; line-height:1.4;">{ props.step.text() }</div>
+            if let Some(msg) = props.locked.clone() {
+                <div style="font-size:12px; color:#d29922;">{ msg }</div>
+            }
+            <div style="display:flex; justify-content:flex-end;">
+                <button onclick={skip}>{"Skip tutorial"}</button>
+            </div>
+        </div>
+    }
+}
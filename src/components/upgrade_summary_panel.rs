@@ -1,4 +1,8 @@
-use crate::model::{UpgradeId, UpgradeState, UPGRADE_DEFS};
+use crate::model::{
+    auto_mine_period, tower_refund_percent, UpgradeId, UpgradeState, FREEZE_CHANCE_PER_LEVEL,
+    FREEZE_SECS, HEAL_AURA_RADIUS_PER_LEVEL, HEAL_PULSE_BASE, HEAL_PULSE_PER_POWER,
+    HEAL_PULSE_SECS, HEAL_SHIELD_PER_LEVEL, POISON_SPREAD_RADIUS, UPGRADE_DEFS,
+};
 use yew::prelude::*;
 
 #[derive(Properties, PartialEq, Clone)]
@@ -77,6 +81,14 @@ fn compute_stats(ups: &UpgradeState) -> Vec<(&'static str, Vec<StatLine>)> {
         });
     }
 
+    if lvl(UpgradeId::Bounce) > 0 {
+        combat.push(StatLine {
+            label: "Bounces",
+            value: format!("{}", lvl(UpgradeId::Bounce)),
+            color: "#f85149",
+        });
+    }
+
     if !combat.is_empty() {
         sections.push(("⚔ Combat", combat));
     }
@@ -152,11 +164,10 @@ fn compute_stats(ups: &UpgradeState) -> Vec<(&'static str, Vec<StatLine>)> {
         });
     }
 
-    let refund = 1.0 + 0.20 * l(UpgradeId::ResourceRecovery);
     if lvl(UpgradeId::ResourceRecovery) > 0 {
         economy.push(StatLine {
             label: "Tower Refund",
-            value: format!("{:.0}%", refund * 100.0),
+            value: format!("{}%", tower_refund_percent(ups)),
             color: "#d29922",
         });
     }
@@ -170,6 +181,23 @@ fn compute_stats(ups: &UpgradeState) -> Vec<(&'static str, Vec<StatLine>)> {
         });
     }
 
+    if lvl(UpgradeId::BankInterest) > 0 {
+        economy.push(StatLine {
+            label: "Bank Interest",
+            value: format!("{:.0}% / 10s", 3.0 * l(UpgradeId::BankInterest)),
+            color: "#d29922",
+        });
+    }
+
+    let auto_miner = lvl(UpgradeId::AutoMiner);
+    if auto_miner > 0 {
+        economy.push(StatLine {
+            label: "Auto Miner",
+            value: format!("1 rock / {}s", auto_mine_period(auto_miner)),
+            color: "#d29922",
+        });
+    }
+
     if !economy.is_empty() {
         sections.push(("💰 Economy", economy));
     }
@@ -205,6 +233,15 @@ fn compute_stats(ups: &UpgradeState) -> Vec<(&'static str, Vec<StatLine>)> {
             });
         }
 
+        if lvl(UpgradeId::BoostColdFreezeChance) > 0 {
+            let chance = FREEZE_CHANCE_PER_LEVEL * 100.0 * l(UpgradeId::BoostColdFreezeChance);
+            cold.push(StatLine {
+                label: "Freeze Chance",
+                value: format!("{:.0}% for {:.0}s", chance, FREEZE_SECS),
+                color: "#3296ff",
+            });
+        }
+
         sections.push(("❄ Cold Tiles", cold));
     }
 
@@ -239,6 +276,13 @@ fn compute_stats(ups: &UpgradeState) -> Vec<(&'static str, Vec<StatLine>)> {
                 color: "#a855f7",
             });
         }
+        if lvl(UpgradeId::BoostPoisonSpread) > 0 {
+            poison.push(StatLine {
+                label: "Spread",
+                value: format!("On death, {:.1} tiles", POISON_SPREAD_RADIUS),
+                color: "#a855f7",
+            });
+        }
 
         sections.push(("☠ Poison Tiles", poison));
     }
@@ -285,13 +329,35 @@ fn compute_stats(ups: &UpgradeState) -> Vec<(&'static str, Vec<StatLine>)> {
             color: "#22c55e",
         });
 
-        let range = 15.0 + 10.0 * l(UpgradeId::BoostHealingPower);
+        let pulse = HEAL_PULSE_BASE + HEAL_PULSE_PER_POWER * l(UpgradeId::BoostHealingPower);
         healing.push(StatLine {
-            label: "Tower Range",
-            value: format!("+{:.0}%", range),
+            label: "Heal Pulse",
+            value: format!("+{:.1} life / {:.0}s", pulse, HEAL_PULSE_SECS),
             color: "#22c55e",
         });
 
+        if lvl(UpgradeId::BoostHealingRadius) > 0 {
+            healing.push(StatLine {
+                label: "Aura",
+                value: format!(
+                    "{:.1} tiles",
+                    HEAL_AURA_RADIUS_PER_LEVEL * l(UpgradeId::BoostHealingRadius)
+                ),
+                color: "#22c55e",
+            });
+        }
+
+        if lvl(UpgradeId::BoostHealingShield) > 0 {
+            healing.push(StatLine {
+                label: "Overheal Shield",
+                value: format!(
+                    "up to {}",
+                    HEAL_SHIELD_PER_LEVEL * lvl(UpgradeId::BoostHealingShield) as u32
+                ),
+                color: "#22c55e",
+            });
+        }
+
         sections.push(("✚ Healing Tiles", healing));
     }
 
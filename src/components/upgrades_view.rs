@@ -1,16 +1,35 @@
+use super::game_over_overlay::load_records;
 use super::upgrade_summary_panel::UpgradeSummaryPanel;
 use crate::model::{
-    play_area_size_for_level, RunAction, RunState, UpgradeId, UpgradeState, UPGRADE_DEFS,
+    can_prestige, play_area_size_for_level, prestige, run_is_live, trickle_rate_per_min,
+    QueueEdit, ResearchTrickle, RunAction, RunState, UpgradeId, UpgradeState,
+    PRESTIGE_BONUS_PER_POINT, PRESTIGE_MIN_RESEARCH, UPGRADE_DEFS, UPGRADE_QUEUE_CAP,
 };
+use crate::persistence::{self, LocalStorage};
+use crate::state::{tour_locked_node, GuidedTour, TourEvent, UpgradesTourStep, UPGRADES_TOUR};
+use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
 use yew::prelude::*;
 
+// Ring around the tour's current target, with everything else dimmed by the same shadow
+const SPOTLIGHT: &str = "box-shadow:0 0 0 4px #58a6ff, 0 0 0 9999px rgba(1,4,9,0.72); z-index:60;";
+
 #[derive(Properties, PartialEq, Clone)]
 pub struct UpgradesViewProps {
     pub run_state: UseReducerHandle<RunState>,
     pub upgrade_state: UseStateHandle<UpgradeState>,
     pub to_run: Callback<()>,
     pub purchase: Callback<UpgradeId>,
+    // Upgrade levels waiting for research, owned by App
+    pub queue: Vec<UpgradeId>,
+    pub edit_queue: Callback<QueueEdit>,
+    // Passive research accumulator, owned by App
+    pub trickle: Rc<RefCell<ResearchTrickle>>,
+    // Confirmed here, carried out by App (see model::prestige)
+    pub prestige: Callback<()>,
 }
 
 fn compute_depths() -> HashMap<UpgradeId, usize> {
@@ -79,11 +98,13 @@ fn upgrade_symbol(id: UpgradeId) -> &'static str {
         BoostColdSlowAmount => "🥶",
         BoostColdSlowDuration => "🕒",
         BoostColdRange => "🎯",
+        BoostColdFreezeChance => "🧊",
         BoostPoisonUnlock => "☠",
         BoostPoisonFrequency => "🧪",
         BoostPoisonDamage => "☣",
         BoostPoisonDuration => "⏳",
         BoostPoisonRange => "🎯",
+        BoostPoisonSpread => "🦠",
         BoostFireUnlock => "🔥",
         BoostFireFrequency => "🌶",
         BoostFireDamage => "💥",
@@ -93,8 +114,13 @@ fn upgrade_symbol(id: UpgradeId) -> &'static str {
         BoostHealingUnlock => "✚",
         BoostHealingFrequency => "💚",
         BoostHealingPower => "🌿",
+        BoostHealingRadius => "💞",
+        BoostHealingShield => "🛡",
         PlayAreaSize => "⛶",
         SplashRadius => "💣",
+        Bounce => "↯",
+        BankInterest => "🏦",
+        AutoMiner => "⛏",
     }
 }
 
@@ -107,9 +133,37 @@ pub fn upgrades_view(props: &UpgradesViewProps) -> Html {
     let container_ref = use_node_ref();
     let hover_id = use_state(|| Option::<UpgradeId>::None);
     let summary_collapsed = use_state(|| false);
+    // First-visit walkthrough; finishing or skipping it stores md_tour_upgrades
+    let tour = use_state(|| {
+        let seen = web_sys::window()
+            .and_then(|w| w.local_storage().ok().flatten())
+            .and_then(|s| s.get_item("md_tour_upgrades").ok().flatten())
+            .is_some_and(|v| v == "1");
+        GuidedTour::new(UPGRADES_TOUR, seen)
+    });
+    let tour_event = {
+        let tour = tour.clone();
+        Callback::from(move |event: TourEvent| {
+            let mut next = (*tour).clone();
+            if next.handle(event)
+                && let Some(mut store) = LocalStorage::open()
+            {
+                persistence::write(&mut store, "md_tour_upgrades", "1");
+            }
+            tour.set(next);
+        })
+    };
+    let tour_step = tour.current();
 
     let research = props.run_state.currencies.research;
     let ups = (*props.upgrade_state).clone();
+    // Records only change at game over, so one read per visit is enough
+    let trickle_rate = *use_memo((), |_| trickle_rate_per_min(&load_records()));
+    let live = run_is_live(&props.run_state);
+    let trickle_capped = props.trickle.borrow().capped();
+    // Latest handle for the interval closure
+    let run_state_ref = use_mut_ref(|| props.run_state.clone());
+    *run_state_ref.borrow_mut() = props.run_state.clone();
 
     // Visibility: only show upgrades whose entire parent chain is visible
     // An upgrade is visible if:
@@ -141,6 +195,16 @@ pub fn upgrades_view(props: &UpgradesViewProps) -> Html {
         }
     }
 
+    // The tour reveals one locked node so its prerequisite edge can be shown
+    let tour_target = match tour_step {
+        Some(UpgradesTourStep::RootNode) => Some(UpgradeId::TowerDamage1),
+        Some(UpgradesTourStep::LockedNode) => tour_locked_node(&ups),
+        _ => None,
+    };
+    if let Some(id) = tour_target {
+        visible_ids.insert(id);
+    }
+
     // Auto-center on first mount
     {
         let offset = offset.clone();
@@ -154,6 +218,53 @@ pub fn upgrades_view(props: &UpgradesViewProps) -> Html {
         });
     }
 
+    // Passive research: one tick per second while this screen is open
+    {
+        let trickle = props.trickle.clone();
+        let run_state_ref = run_state_ref.clone();
+        use_effect_with((), move |_| {
+            let window = web_sys::window().unwrap();
+            let tick = Closure::wrap(Box::new(move || {
+                let handle = run_state_ref.borrow().clone();
+                let paid = trickle
+                    .borrow_mut()
+                    .advance(trickle_rate, 1.0, run_is_live(&handle));
+                if let Some(mut store) = LocalStorage::open() {
+                    persistence::write(
+                        &mut store,
+                        "md_research_carry",
+                        &trickle.borrow().carry.to_string(),
+                    );
+                }
+                if paid == 0 {
+                    return;
+                }
+                handle.dispatch(RunAction::SetResearch {
+                    amount: handle.currencies.research.saturating_add(paid),
+                });
+            }) as Box<dyn FnMut()>);
+            let tick_id = window
+                .set_interval_with_callback_and_timeout_and_arguments_0(
+                    tick.as_ref().unchecked_ref(),
+                    1000,
+                )
+                .unwrap();
+            move || {
+                window.clear_interval_with_handle(tick_id);
+                drop(tick);
+            }
+        });
+    }
+    let trickle_label = if trickle_rate <= 0.0 {
+        None
+    } else if live {
+        Some("passive research paused during a run".to_string())
+    } else if trickle_capped {
+        Some("passive research capped for this session".to_string())
+    } else {
+        Some(format!("+{:.1} RP/min (passive)", trickle_rate))
+    };
+
     // --- Layout prep ---
     let depths = compute_depths();
     let mut rings: HashMap<usize, Vec<UpgradeId>> = HashMap::new();
@@ -422,6 +533,12 @@ pub fn upgrades_view(props: &UpgradesViewProps) -> Html {
                     } else {
                         1.0
                     };
+                    // The tour's locked node: its prerequisite edge stands out
+                    let (stroke, width) = if locked && tour_target == Some(def.id) {
+                        ("#d29922", 4)
+                    } else {
+                        (stroke, width)
+                    };
                     edge_svg.push(html! {
                         <path d={format!("M{:.1},{:.1} Q{:.1},{:.1} {:.1},{:.1}", x1, y1, cx, cy, x2, y2)}
                               stroke={stroke}
@@ -544,20 +661,59 @@ pub fn upgrades_view(props: &UpgradesViewProps) -> Html {
             let hid2 = hover_id.clone();
             let on_leave = Callback::from(move |_| hid2.set(None));
             let purchase2 = purchase_cb.clone();
-            let onclick = Callback::from(move |_| purchase2.emit(idc));
+            let is_tour_target = tour_target == Some(def.id);
+            // Buying ends the tour; clicking the spotlighted node otherwise moves it on
+            let tour_click = if can_buy {
+                Some(TourEvent::Purchased)
+            } else {
+                is_tour_target.then_some(TourEvent::TargetClicked)
+            }
+            .filter(|_| tour_step.is_some());
+            let tour_event2 = tour_event.clone();
+            let onclick = Callback::from(move |_| {
+                purchase2.emit(idc);
+                if let Some(event) = tour_click {
+                    tour_event2.emit(event);
+                }
+            });
+            let spotlight = if is_tour_target { SPOTLIGHT } else { "" };
+            let queued = props.queue.iter().filter(|&&q| q == def.id).count();
+            // Unaffordable levels can wait in the queue instead
+            let queue_btn = (unlocked && lvl < max && !affordable).then(|| {
+                let edit = props.edit_queue.clone();
+                let full = props.queue.len() >= UPGRADE_QUEUE_CAP
+                    || lvl as usize + queued >= max as usize;
+                let onclick = Callback::from(move |e: MouseEvent| {
+                    e.stop_propagation();
+                    edit.emit(QueueEdit::Add(idc));
+                });
+                html! {
+                    <button title="Add to queue" disabled={full} {onclick}
+                            onmousedown={Callback::from(|e: MouseEvent| e.stop_propagation())}
+                            style="position:absolute; bottom:-6px; left:-10px; font-size:10px; padding:1px 4px; border-radius:6px; border:1px solid #30363d; background:#161b22; color:#58a6ff; cursor:pointer;">
+                        {"+Q"}
+                    </button>
+                }
+            });
             node_html.push(html! {
                 <div key={def.id.key()}
                      onmouseenter={on_enter}
                      onmouseleave={on_leave}
                      onclick={onclick}
                      aria-label={aria_tip}
-                     style={format!("position:absolute; left:{:.1}px; top:{:.1}px; width:{:.1}px; height:{:.1}px; margin-left:-{:.1}px; margin-top:-{:.1}px; display:flex; align-items:center; justify-content:center; font-size:{:.0}px; cursor:pointer; user-select:none; border:3px solid {}; background:{}; color:#fff; border-radius:50%; opacity:{:.2}; box-shadow:{}; transition:all 120ms ease;",
-                                    x, y, size, size, size / 2.0, size / 2.0, if is_hovered { 26.0 } else { 22.0 }, border, bg, dim, glow)}
+                     style={format!("position:absolute; left:{:.1}px; top:{:.1}px; width:{:.1}px; height:{:.1}px; margin-left:-{:.1}px; margin-top:-{:.1}px; display:flex; align-items:center; justify-content:center; font-size:{:.0}px; cursor:pointer; user-select:none; border:3px solid {}; background:{}; color:#fff; border-radius:50%; opacity:{:.2}; box-shadow:{}; transition:all 120ms ease; {}",
+                                    x, y, size, size, size / 2.0, size / 2.0, if is_hovered { 26.0 } else { 22.0 }, border, bg, if is_tour_target { 1.0 } else { dim }, glow, spotlight)}
                 >
                     { symbol }
                     <div style="position:absolute; bottom:-4px; right:-4px; font-size:11px; background:#161b22; padding:2px 4px; border-radius:6px; border:1px solid #30363d;">
                         { format!("{}/{}", lvl, max) }
                     </div>
+                    if queued > 0 {
+                        <div style="position:absolute; top:-6px; right:-8px; font-size:10px; background:#1f6feb; padding:1px 4px; border-radius:6px;">
+                            { format!("Q{}", queued) }
+                        </div>
+                    }
+                    { queue_btn.unwrap_or_default() }
                 </div>
             });
         }
@@ -630,13 +786,57 @@ pub fn upgrades_view(props: &UpgradesViewProps) -> Html {
             let refund = current.total_spent();
             let new_ups = UpgradeState {
                 tower_refund_rate_percent: current.tower_refund_rate_percent,
+                meta: current.meta,
                 ..Default::default()
             };
-            // preserve any future meta fields if added (only tower_refund_rate_percent now)
             upgrade_state.set(new_ups.clone());
             let new_amount = run_state.currencies.research.saturating_add(refund);
-            run_state.dispatch(RunAction::SetResearch { amount: new_amount });
-            run_state.dispatch(RunAction::ApplyUpgrades { ups: new_ups });
+            run_state.dispatch(RunAction::Batch(vec![
+                RunAction::SetResearch { amount: new_amount },
+                RunAction::ApplyUpgrades { ups: new_ups },
+            ]));
+        })
+    };
+
+    // --- Prestige (trade every level and all research for permanent points) ---
+    let prestige_ready = can_prestige(&props.upgrade_state, research);
+    let prestige_cb = {
+        let cb = props.prestige.clone();
+        let ups = (*props.upgrade_state).clone();
+        Callback::from(move |_| {
+            let Some(after) = prestige(&ups, research) else {
+                return;
+            };
+            let gained = after.meta.prestige_points - ups.meta.prestige_points;
+            let bonus = (after.meta.multiplier() - 1.0) * 100.0;
+            let levels = ups.total_levels();
+            let msg = format!(
+                "Prestige?\n\nYou lose: all {levels} upgrade levels and your {research} research.\nYou gain: {gained} prestige points, for +{bonus:.0}% tower damage and mining speed in every run from now on.\n\nRun history and records are kept."
+            );
+            let ok = web_sys::window()
+                .map(|w| w.confirm_with_message(&msg).unwrap_or(false))
+                .unwrap_or(true);
+            if ok {
+                cb.emit(());
+            }
+        })
+    };
+    let prestige_title = if prestige_ready {
+        format!(
+            "Reset upgrades and research for prestige points (+{:.0}% damage and mining speed each)",
+            PRESTIGE_BONUS_PER_POINT * 100.0
+        )
+    } else {
+        format!("Unlocks once {PRESTIGE_MIN_RESEARCH} research has been earned (spent plus held)")
+    };
+    let prestige_label = {
+        let meta = props.upgrade_state.meta;
+        (meta.prestige_points > 0).then(|| {
+            format!(
+                "Prestige {}: +{:.0}% damage and mining speed",
+                meta.prestige_count,
+                (meta.multiplier() - 1.0) * 100.0
+            )
         })
     };
 
@@ -726,6 +926,109 @@ pub fn upgrades_view(props: &UpgradesViewProps) -> Html {
     // --- Viewport / transform ---
     let (ox, oy) = *offset;
     let scale = *zoom;
+    // Queue panel: each entry shows the level it will buy, counting earlier entries of its kind
+    let queue_panel = if props.queue.is_empty() {
+        html! {}
+    } else {
+        let last = props.queue.len() - 1;
+        let mut ahead: HashMap<UpgradeId, u8> = HashMap::new();
+        let rows = props.queue.iter().enumerate().map(|(i, &id)| {
+            let n = ahead.entry(id).or_insert(0);
+            *n += 1;
+            let target = ups.level(id).saturating_add(*n);
+            let name = UPGRADE_DEFS
+                .iter()
+                .find(|d| d.id == id)
+                .map_or(id.key(), |d| d.display_name);
+            let btn = |label: &'static str, edit: QueueEdit, enabled: bool| {
+                let cb = props.edit_queue.clone();
+                html! {
+                    <button disabled={!enabled} onclick={Callback::from(move |_| cb.emit(edit))} style="padding:0 5px; font-size:11px;">{ label }</button>
+                }
+            };
+            html! {
+                <div style="display:flex; align-items:center; gap:4px; font-size:12px;">
+                    <span style="flex:1;">{ format!("{}. {} {}", i + 1, name, target) }</span>
+                    { btn("\u{2191}", QueueEdit::MoveUp(i), i > 0) }
+                    { btn("\u{2193}", QueueEdit::MoveDown(i), i < last) }
+                    { btn("\u{2715}", QueueEdit::Remove(i), true) }
+                </div>
+            }
+        });
+        html! {
+            <div style="position:absolute; top:64px; right:12px; background:#161b22dd; border:1px solid #30363d; border-radius:8px; padding:8px 10px; z-index:25; min-width:200px; display:flex; flex-direction:column; gap:4px;" onmousedown={stop_mouse_down.clone()}>
+                <div style="font-size:12px; color:#8b949e;">{ format!("Queue {}/{} (bought as research arrives)", props.queue.len(), UPGRADE_QUEUE_CAP) }</div>
+                { for rows }
+            </div>
+        }
+    };
+    let tour_card = match (tour_step, tour.progress()) {
+        (Some(step), Some((n, total))) => {
+            let next_label = if n == total { "Done" } else { "Next" };
+            let detail = match step {
+                UpgradesTourStep::RootNode => ups
+                    .next_cost(UpgradeId::TowerDamage1)
+                    .map(|c| format!("Next Tower Damage level: {} RP (you have {})", c, research)),
+                UpgradesTourStep::LockedNode => tour_target
+                    .and_then(|id| UPGRADE_DEFS.iter().find(|d| d.id == id))
+                    .map(|def| {
+                        let needs: Vec<String> = def
+                            .prerequisites
+                            .iter()
+                            .map(|p| format!("{} level {}", p.id.key(), p.level))
+                            .collect();
+                        format!("{} needs {}", def.display_name, needs.join(", "))
+                    }),
+                _ => None,
+            };
+            let next_cb = {
+                let cb = tour_event.clone();
+                Callback::from(move |_| cb.emit(TourEvent::Next))
+            };
+            let skip_cb = {
+                let cb = tour_event.clone();
+                Callback::from(move |_| cb.emit(TourEvent::Skip))
+            };
+            html! {
+                <div style="position:absolute; bottom:84px; left:50%; transform:translateX(-50%); background:#161b22; border:1px solid #58a6ff; border-radius:10px; padding:12px 16px; max-width:360px; z-index:70; display:flex; flex-direction:column; gap:8px;" onmousedown={stop_mouse_down.clone()}>
+                    <div style="display:flex; justify-content:space-between; gap:12px;">
+                        <span style="font-weight:600;">{ step.title() }</span>
+                        <span style="font-size:12px; color:#8b949e;">{ format!("{}/{}", n, total) }</span>
+                    </div>
+                    <div style="font-size:13px; line-height:1.4;">{ step.text() }</div>
+                    if let Some(detail) = detail {
+                        <div style="font-size:12px; color:#d29922;">{ detail }</div>
+                    }
+                    <div style="display:flex; justify-content:flex-end; gap:6px;">
+                        <button onclick={skip_cb}>{"Skip"}</button>
+                        <button onclick={next_cb}>{ next_label }</button>
+                    </div>
+                </div>
+            }
+        }
+        _ => html! {},
+    };
+    let spotlight_if = |step: UpgradesTourStep| {
+        if tour_step == Some(step) {
+            SPOTLIGHT
+        } else {
+            ""
+        }
+    };
+    let click_if = |step: UpgradesTourStep| {
+        let cb = tour_event.clone();
+        Callback::from(move |_| {
+            if tour_step == Some(step) {
+                cb.emit(TourEvent::TargetClicked);
+            }
+        })
+    };
+    // Nodes sit in the transformed layer; lift it over the panels while one is spotlighted
+    let tree_z = if tour_target.is_some() {
+        " z-index:40;"
+    } else {
+        ""
+    };
     let svg_edges = html! {<svg style="position:absolute; inset:0; overflow:visible; pointer-events:none;" width="100%" height="100%">{ for edge_svg }</svg>};
 
     html! {
@@ -737,10 +1040,16 @@ pub fn upgrades_view(props: &UpgradesViewProps) -> Html {
              onmouseup={mouseup.clone()}
              onmouseleave={mouseup}
         >
-            <div style="position:absolute; top:12px; left:50%; transform:translateX(-50%); background:#161b22dd; border:1px solid #30363d; border-radius:10px; padding:10px 28px; font-size:24px; font-weight:600; color:#58a6ff; letter-spacing:0.5px; z-index:30;">
+            <div style={format!("position:absolute; top:12px; left:50%; transform:translateX(-50%); background:#161b22dd; border:1px solid #30363d; border-radius:10px; padding:10px 28px; font-size:24px; font-weight:600; color:#58a6ff; letter-spacing:0.5px; z-index:30; {}", spotlight_if(UpgradesTourStep::Research))} onclick={click_if(UpgradesTourStep::Research)}>
                 { format!("Research: {}", research) }
+                if let Some(label) = trickle_label {
+                    <div style="font-size:12px; font-weight:400; color:#8b949e; text-align:center;">{ label }</div>
+                }
+                if let Some(label) = prestige_label {
+                    <div style="font-size:12px; font-weight:400; color:#d29922; text-align:center;">{ label }</div>
+                }
             </div>
-            <div style="position:absolute; top:12px; right:12px; background:#161b22dd; border:1px solid #30363d; border-radius:8px; padding:8px 10px; z-index:25; display:flex; gap:6px;" onmousedown={stop_mouse_down.clone()}>
+            <div style={format!("position:absolute; top:12px; right:12px; background:#161b22dd; border:1px solid #30363d; border-radius:8px; padding:8px 10px; z-index:25; display:flex; gap:6px; {}", spotlight_if(UpgradesTourStep::Back))} onmousedown={stop_mouse_down.clone()} onclick={click_if(UpgradesTourStep::Back)}>
                 <button onclick={{ let cb=props.to_run.clone(); Callback::from(move |_| cb.emit(())) }}> {"Back"} </button>
             </div>
             <div style="position:absolute; bottom:12px; left:12px; background:#161b22dd; border:1px solid #30363d; border-radius:8px; padding:8px; display:flex; gap:6px; z-index:25;" onmousedown={stop_mouse_down.clone()}>
@@ -748,11 +1057,12 @@ pub fn upgrades_view(props: &UpgradesViewProps) -> Html {
                 <button onclick={{ let zoom=zoom.clone(); Callback::from(move |_| zoom.set((*zoom*1.25).clamp(0.3,3.5))) }}> {"+"} </button>
                 <button onclick={{ let zoom=zoom.clone(); Callback::from(move |_| zoom.set((*zoom*0.8).clamp(0.3,3.5))) }}> {"-"} </button>
             </div>
-            <div style="position:absolute; bottom:12px; right:12px; background:#161b22dd; border:1px solid #30363d; border-radius:8px; padding:10px 14px; z-index:25;" onmousedown={stop_mouse_down.clone()}>
+            <div style="position:absolute; bottom:12px; right:12px; background:#161b22dd; border:1px solid #30363d; border-radius:8px; padding:10px 14px; z-index:25; display:flex; gap:8px;" onmousedown={stop_mouse_down.clone()}>
+                <button onclick={prestige_cb} disabled={!prestige_ready} title={prestige_title} style="background:#d29922; border:1px solid #d29922; color:#0d1117; font-weight:600; padding:6px 14px; border-radius:6px;">{"Prestige"}</button>
                 <button onclick={respec_cb} style="background:#f85149; border:1px solid #f85149; color:#fff; font-weight:600; padding:6px 14px; border-radius:6px;">{"Respec"}</button>
             </div>
             <div style={format!("position:absolute; inset:0; cursor:{};", if *dragging {"grabbing"} else {"grab"})}></div>
-            <div style={format!("position:absolute; inset:0; transform:translate({}px, {}px) scale({}); transform-origin:0 0;", ox, oy, scale)}>
+            <div style={format!("position:absolute; inset:0; transform:translate({}px, {}px) scale({}); transform-origin:0 0;{}", ox, oy, scale, tree_z)}>
                 { svg_edges }
                 { for node_html }
                 { tooltip }
@@ -765,6 +1075,8 @@ pub fn upgrades_view(props: &UpgradesViewProps) -> Html {
                     Callback::from(move |_| summary_collapsed.set(!*summary_collapsed))
                 }}
             />
+            { queue_panel }
+            { tour_card }
         </div>
     }
 }
@@ -0,0 +1,276 @@
+// Panic reporting. The hook draws its own overlay straight into the DOM because the yew
+// tree may be wedged once a panic has unwound through a callback.
+use crate::model::RunState;
+use std::cell::RefCell;
+use wasm_bindgen::JsValue;
+
+const OVERLAY_ID: &str = "md-crash-overlay";
+// Window global holding the last report; read by the Copy button and handy from devtools
+const REPORT_GLOBAL: &str = "__md_crash_report";
+// Same for the recent log buffer
+const LOGS_GLOBAL: &str = "__md_recent_logs";
+// And the debug bundle (see debug_dump), when a source is registered
+const DEBUG_GLOBAL: &str = "__md_debug_state";
+
+// Small summary of the run, refreshed on every reducer version
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RunSnapshot {
+    pub run_id: u64,
+    pub map_seed: u64,
+    pub version: u64,
+    pub grid_width: u32,
+    pub grid_height: u32,
+    pub started: bool,
+    pub paused: bool,
+    pub game_over: bool,
+    pub time_survived_secs: u64,
+    pub loops_completed: u32,
+    pub blocks_mined: u32,
+    pub life: u32,
+    pub gold: u64,
+    pub research: u64,
+    pub enemies: usize,
+    pub towers: usize,
+}
+impl RunSnapshot {
+    pub fn from_run(rs: &RunState) -> Self {
+        Self {
+            run_id: rs.run_id,
+            map_seed: rs.map_seed,
+            version: rs.version,
+            grid_width: rs.grid_size.width,
+            grid_height: rs.grid_size.height,
+            started: rs.started,
+            paused: rs.is_paused,
+            game_over: rs.game_over,
+            time_survived_secs: rs.stats.time_survived_secs,
+            loops_completed: rs.stats.loops_completed,
+            blocks_mined: rs.stats.blocks_mined,
+            life: rs.life,
+            gold: rs.currencies.gold,
+            research: rs.currencies.research,
+            enemies: rs.enemies.len(),
+            towers: rs.towers.len(),
+        }
+    }
+}
+
+type DebugSource = Box<dyn Fn() -> Option<String>>;
+
+thread_local! {
+    static LAST_SNAPSHOT: RefCell<Option<RunSnapshot>> = const { RefCell::new(None) };
+    static DEBUG_SOURCE: RefCell<Option<DebugSource>> = const { RefCell::new(None) };
+}
+
+// Builds the debug bundle for the overlay's Copy Debug State button; RunView registers one
+pub fn set_debug_source(source: impl Fn() -> Option<String> + 'static) {
+    DEBUG_SOURCE.with(|s| {
+        if let Ok(mut slot) = s.try_borrow_mut() {
+            *slot = Some(Box::new(source));
+        }
+    });
+}
+
+pub fn record_snapshot(rs: &RunState) {
+    let snap = RunSnapshot::from_run(rs);
+    LAST_SNAPSHOT.with(|s| {
+        if let Ok(mut slot) = s.try_borrow_mut() {
+            *slot = Some(snap);
+        }
+    });
+}
+
+pub fn build_report(panic_text: &str, backtrace: &str, snapshot: Option<&RunSnapshot>) -> String {
+    let mut out = String::new();
+    out.push_str("Maze Defence crash report\n");
+    out.push_str(&format!("version: {}\n\n", env!("CARGO_PKG_VERSION")));
+    out.push_str("panic:\n");
+    out.push_str(panic_text.trim_end());
+    out.push_str("\n\nrun:\n");
+    match snapshot {
+        Some(s) => {
+            out.push_str(&format!(
+                "  run_id={} seed={} state_version={}\n",
+                s.run_id, s.map_seed, s.version
+            ));
+            out.push_str(&format!(
+                "  grid={}x{} started={} paused={} game_over={}\n",
+                s.grid_width, s.grid_height, s.started, s.paused, s.game_over
+            ));
+            out.push_str(&format!(
+                "  time={}s loops={} mined={} life={}\n",
+                s.time_survived_secs, s.loops_completed, s.blocks_mined, s.life
+            ));
+            out.push_str(&format!(
+                "  gold={} research={} enemies={} towers={}\n",
+                s.gold, s.research, s.enemies, s.towers
+            ));
+        }
+        None => out.push_str("  (no run state recorded)\n"),
+    }
+    let bt = backtrace.trim();
+    if !bt.is_empty() {
+        out.push_str("\nbacktrace:\n");
+        out.push_str(bt);
+        out.push('\n');
+    }
+    out
+}
+
+// Called once from main() before the app renders
+pub fn install() {
+    std::panic::set_hook(Box::new(|info| {
+        let backtrace = std::backtrace::Backtrace::force_capture().to_string();
+        let snapshot = LAST_SNAPSHOT.with(|s| s.try_borrow().ok().and_then(|s| s.clone()));
+        let report = build_report(&info.to_string(), &backtrace, snapshot.as_ref());
+        web_sys::console::error_1(&JsValue::from_str(&report));
+        let logs = crate::util::recent_logs(snapshot.as_ref().map(|s| s.run_id));
+        let debug = DEBUG_SOURCE.with(|s| s.try_borrow().ok()?.as_ref()?());
+        show_overlay(&report, &logs, debug.as_deref());
+    }));
+}
+
+fn show_overlay(report: &str, logs: &str, debug: Option<&str>) {
+    let Some(win) = web_sys::window() else {
+        return;
+    };
+    let _ = js_sys::Reflect::set(
+        &win,
+        &JsValue::from_str(REPORT_GLOBAL),
+        &JsValue::from_str(report),
+    );
+    let _ = js_sys::Reflect::set(
+        &win,
+        &JsValue::from_str(LOGS_GLOBAL),
+        &JsValue::from_str(logs),
+    );
+    if let Some(debug) = debug {
+        let _ = js_sys::Reflect::set(
+            &win,
+            &JsValue::from_str(DEBUG_GLOBAL),
+            &JsValue::from_str(debug),
+        );
+    }
+    let Some(doc) = win.document() else {
+        return;
+    };
+    let Some(body) = doc.body() else {
+        return;
+    };
+    // A second panic only refreshes the text
+    if let Some(pre) = doc.get_element_by_id("md-crash-report-text") {
+        pre.set_text_content(Some(report));
+        return;
+    }
+    let Ok(overlay) = doc.create_element("div") else {
+        return;
+    };
+    let _ = overlay.set_attribute("id", OVERLAY_ID);
+    let _ = overlay.set_attribute(
+        "style",
+        "position:fixed; inset:0; z-index:1000; background:rgba(11,14,20,0.94); color:#c9d1d9; \
+         display:flex; align-items:center; justify-content:center; font-family:system-ui, sans-serif;",
+    );
+    let Ok(panel) = doc.create_element("div") else {
+        return;
+    };
+    let _ = panel.set_attribute(
+        "style",
+        "background:#161b22; border:1px solid #f85149; border-radius:12px; padding:18px 22px; \
+         max-width:720px; width:90%; display:flex; flex-direction:column; gap:12px;",
+    );
+    if let Ok(title) = doc.create_element("h2") {
+        let _ = title.set_attribute("style", "margin:0; font-size:20px; color:#f85149;");
+        title.set_text_content(Some("Something broke — copy this report"));
+        let _ = panel.append_child(&title);
+    }
+    if let Ok(pre) = doc.create_element("pre") {
+        let _ = pre.set_attribute("id", "md-crash-report-text");
+        let _ = pre.set_attribute(
+            "style",
+            "margin:0; max-height:50vh; overflow:auto; background:#0e1116; border:1px solid #30363d; \
+             border-radius:6px; padding:10px; font-size:12px; white-space:pre-wrap;",
+        );
+        pre.set_text_content(Some(report));
+        let _ = panel.append_child(&pre);
+    }
+    if let Ok(row) = doc.create_element("div") {
+        let _ = row.set_attribute("style", "display:flex; gap:8px; justify-content:flex-end;");
+        // Inline handlers: no Rust closures needed once the wasm instance is poisoned
+        let mut buttons = vec![
+            (
+                "Copy Report",
+                format!(
+                    "navigator.clipboard && navigator.clipboard.writeText(window.{REPORT_GLOBAL})"
+                ),
+            ),
+            (
+                "Copy Logs",
+                format!(
+                    "navigator.clipboard && navigator.clipboard.writeText(window.{LOGS_GLOBAL})"
+                ),
+            ),
+        ];
+        if debug.is_some() {
+            buttons.push((
+                "Copy Debug State",
+                format!(
+                    "navigator.clipboard && navigator.clipboard.writeText(window.{DEBUG_GLOBAL})"
+                ),
+            ));
+        }
+        // Reload keeps localStorage, so upgrades and research survive
+        buttons.push(("Reload", "window.location.reload()".to_string()));
+        for (label, handler) in buttons {
+            if let Ok(btn) = doc.create_element("button") {
+                let _ = btn.set_attribute("onclick", &handler);
+                let _ = btn.set_attribute("style", "padding:6px 12px;");
+                btn.set_text_content(Some(label));
+                let _ = row.append_child(&btn);
+            }
+        }
+        let _ = panel.append_child(&row);
+    }
+    let _ = overlay.append_child(&panel);
+    let _ = body.append_child(&overlay);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::GridSize;
+
+    #[test]
+    fn report_includes_panic_and_run_summary() {
+        let mut rs = RunState::new_with_upgrades_seeded(
+            GridSize {
+                width: 10,
+                height: 10,
+            },
+            &Default::default(),
+            77,
+        );
+        rs.currencies.gold = 12;
+        rs.stats.time_survived_secs = 95;
+        let snap = RunSnapshot::from_run(&rs);
+        let report = build_report(
+            "panicked at src/model.rs:1:1:\nindex out of bounds\n",
+            "0: frame",
+            Some(&snap),
+        );
+        assert!(report.contains("index out of bounds"));
+        assert!(report.contains("seed=77"));
+        assert!(report.contains("grid=10x10"));
+        assert!(report.contains("time=95s"));
+        assert!(report.contains("gold=12"));
+        assert!(report.contains("backtrace:\n0: frame"));
+    }
+
+    #[test]
+    fn report_without_snapshot_or_backtrace() {
+        let report = build_report("boom", "  ", None);
+        assert!(report.contains("panic:\nboom\n"));
+        assert!(report.contains("(no run state recorded)"));
+        assert!(!report.contains("backtrace:"));
+    }
+}
@@ -0,0 +1,219 @@
+// Debug bundle for bug reports: the whole run (tiles palette-encoded as in the autosave, with
+// enemies and projectiles kept), upgrades, settings and the recent log buffer, as one pasteable
+// string. The JSON is base64'd behind a versioned prefix so a clipped paste is refused outright
+// instead of half-loading. Imported runs come back paused and flagged debug_allowed.
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::{RunState, UpgradeState};
+use crate::persistence::{healing_boosts, palette_run_tiles, LocalStorage, Storage, SCHEMAS};
+use crate::util::{base64_decode, base64_encode, recent_logs};
+
+// v1 runs repeated the full tile in every run and v2 runs still named healing boosts Range;
+// both are upgraded on decode
+pub const DUMP_VERSION: u32 = 3;
+const DUMP_PREFIX: &str = "md-debug:";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DebugBundle {
+    pub version: u32,
+    pub app_version: String,
+    pub map_seed: u64,
+    // RunState::to_debug_save
+    pub run: String,
+    pub upgrades: UpgradeState,
+    // Raw md_setting_* and md_hotkeys blobs; for reading, not applied on import
+    pub settings: BTreeMap<String, String>,
+    pub logs: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DumpError {
+    // No prefix: not a dump at all, or only its tail was pasted
+    NotADump,
+    // Bad base64 or JSON, usually a truncated paste
+    Corrupt,
+    // Written by a newer build
+    Version(u32),
+    // The run inside no longer loads (see RunState::from_save)
+    BadRun,
+}
+
+impl DumpError {
+    pub fn message(self) -> String {
+        match self {
+            DumpError::NotADump => "Not a debug state (it should start with md-debug:)".into(),
+            DumpError::Corrupt => "Debug state is damaged or cut short".into(),
+            DumpError::Version(v) => {
+                format!("Debug state is format v{v}; this build reads v{DUMP_VERSION}")
+            }
+            DumpError::BadRun => "The run in this debug state doesn't load".into(),
+        }
+    }
+}
+
+// Everything worth knowing about the player's session right now
+pub fn collect(
+    rs: &RunState,
+    upgrades: &UpgradeState,
+    store: Option<&dyn Storage>,
+    logs: String,
+) -> DebugBundle {
+    let settings = SCHEMAS
+        .iter()
+        .filter(|s| s.key.starts_with("md_setting_") || s.key == "md_hotkeys")
+        .filter_map(|s| Some((s.key.to_string(), store?.get(s.key)?)))
+        .collect();
+    DebugBundle {
+        version: DUMP_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        map_seed: rs.map_seed,
+        run: rs.to_debug_save(),
+        upgrades: upgrades.clone(),
+        settings,
+        logs,
+    }
+}
+
+// The encoded bundle for the live session, settings read from localStorage
+pub fn session_dump(rs: &RunState, upgrades: &UpgradeState) -> String {
+    let store = LocalStorage::open();
+    let store = store.as_ref().map(|s| s as &dyn Storage);
+    encode(&collect(rs, upgrades, store, recent_logs(Some(rs.run_id))))
+}
+
+// Upgrades as last saved, for the panic overlay, which has no props to read them from
+pub fn stored_upgrades() -> UpgradeState {
+    LocalStorage::open()
+        .and_then(|s| s.get("md_upgrade_state"))
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+pub fn encode(bundle: &DebugBundle) -> String {
+    let json = serde_json::to_string(bundle).unwrap_or_default();
+    format!("{DUMP_PREFIX}{}", base64_encode(json.as_bytes()))
+}
+
+pub fn decode(raw: &str) -> Result<DebugBundle, DumpError> {
+    let body = raw
+        .trim()
+        .strip_prefix(DUMP_PREFIX)
+        .ok_or(DumpError::NotADump)?;
+    let bytes = base64_decode(body).ok_or(DumpError::Corrupt)?;
+    // Peek at the version first so a newer format isn't reported as damage
+    let head: serde_json::Value = serde_json::from_slice(&bytes).map_err(|_| DumpError::Corrupt)?;
+    let version = match head.get("version").and_then(|v| v.as_u64()) {
+        Some(v) if v <= u64::from(DUMP_VERSION) => v,
+        Some(v) => return Err(DumpError::Version(v as u32)),
+        None => return Err(DumpError::Corrupt),
+    };
+    let mut bundle: DebugBundle = serde_json::from_value(head).map_err(|_| DumpError::Corrupt)?;
+    if version < u64::from(DUMP_VERSION) {
+        let mut run: serde_json::Value =
+            serde_json::from_str(&bundle.run).map_err(|_| DumpError::Corrupt)?;
+        if version < 2 {
+            run = palette_run_tiles(run);
+        }
+        bundle.run = healing_boosts(run).to_string();
+        bundle.version = DUMP_VERSION;
+    }
+    Ok(bundle)
+}
+
+// The bundle's run, ready for RunAction::LoadSaved: paused, and marked so it can't pass for a
+// real run
+pub fn import(raw: &str) -> Result<(RunState, DebugBundle), DumpError> {
+    let bundle = decode(raw)?;
+    let mut run = RunState::from_save(&bundle.run).ok_or(DumpError::BadRun)?;
+    run.is_paused = true;
+    run.debug_allowed = true;
+    Ok((run, bundle))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{BoostKind, GridSize, UpgradeId};
+    use crate::persistence::MemoryStorage;
+
+    fn bundle(size: u32) -> DebugBundle {
+        let mut rs = RunState::new_with_upgrades_seeded(
+            GridSize {
+                width: size,
+                height: size,
+            },
+            &UpgradeState::default(),
+            42,
+        );
+        rs.currencies.gold = 77;
+        let mut ups = UpgradeState::default();
+        ups.levels.insert(UpgradeId::FireRate.key().into(), 2);
+        let mut store = MemoryStorage::default();
+        store.set("md_setting_minimap", "0");
+        store.set("md_research", "1234");
+        collect(&rs, &ups, Some(&store), "[info] run 1 started".into())
+    }
+
+    #[test]
+    fn debug_bundle_round_trips_through_json() {
+        let b = bundle(24);
+        assert_eq!(
+            b.settings.keys().collect::<Vec<_>>(),
+            vec!["md_setting_minimap"]
+        );
+        let dump = encode(&b);
+        assert!(dump.starts_with("md-debug:"));
+        assert_eq!(decode(&dump), Ok(b.clone()));
+        let (run, back) = import(&format!("  {dump}\n")).unwrap();
+        assert_eq!(back.upgrades, b.upgrades);
+        assert_eq!(run.currencies.gold, 77);
+        assert_eq!(run.map_seed, 42);
+        assert!(run.is_paused && run.debug_allowed);
+    }
+
+    #[test]
+    fn truncated_or_foreign_dumps_are_rejected() {
+        let dump = encode(&bundle(24));
+        assert_eq!(decode(&dump[..dump.len() - 5]), Err(DumpError::Corrupt));
+        // A cut on a quad boundary still decodes as base64, but not as JSON
+        let cut = DUMP_PREFIX.len() + (dump.len() - DUMP_PREFIX.len()) / 8 * 4;
+        assert_eq!(decode(&dump[..cut]), Err(DumpError::Corrupt));
+        assert_eq!(decode(&dump[9..]), Err(DumpError::NotADump));
+        let newer = encode(&DebugBundle {
+            version: DUMP_VERSION + 1,
+            ..bundle(8)
+        });
+        assert_eq!(decode(&newer), Err(DumpError::Version(DUMP_VERSION + 1)));
+    }
+
+    #[test]
+    fn v1_bundles_still_import() {
+        let run = std::fs::read_to_string(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/fixtures/storage/md_run_state.v1.json"
+        ))
+        .unwrap();
+        let dump = encode(&DebugBundle {
+            version: 1,
+            run,
+            ..bundle(8)
+        });
+        let (run, back) = import(&dump).unwrap();
+        assert_eq!(back.version, DUMP_VERSION);
+        assert_eq!(run.tiles.len(), 15);
+        assert_eq!(run.currencies.gold, 23);
+        assert_eq!(run.towers[1].boost, Some(BoostKind::Healing));
+    }
+
+    #[test]
+    fn large_board_bundle_stays_small() {
+        let b = bundle(255);
+        assert!(
+            b.run.len() < 200 * 1024,
+            "run save is {} bytes",
+            b.run.len()
+        );
+    }
+}
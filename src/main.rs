@@ -1,8 +1,25 @@
+mod achievements;
+mod audio;
 mod components;
+mod crash_report;
+mod debug_dump;
+mod map_gen;
 mod model;
+mod path;
+mod persistence;
+mod prestige;
+mod run_history;
+mod save_transfer;
+mod snapshot_export;
 mod state;
 mod util;
+mod worker;
 
 fn main() {
+    crash_report::install();
+    // Before any component reads localStorage
+    if let Some(mut store) = persistence::LocalStorage::open() {
+        persistence::migrate_all(&mut store);
+    }
     yew::Renderer::<components::App>::new().render();
 }
@@ -0,0 +1,161 @@
+// Gold veins. Instead of a per-tile roll, gold comes in random-walk veins of VEIN_MIN..=VEIN_MAX
+// connected rocks grown from seed points. There are ceil(tiles * gold_chance / mean vein size)
+// of them, so the gold tile count stays near what the per-tile roll gave on average.
+use crate::model::{GridSize, SeededRng};
+
+pub const VEIN_MIN: usize = 3;
+pub const VEIN_MAX: usize = 7;
+const VEIN_MEAN: f64 = (VEIN_MIN + VEIN_MAX) as f64 / 2.0;
+// Random picks a vein gets to find a free seed point or a free neighbour before it gives up
+const VEIN_TRIES: usize = 40;
+
+pub fn vein_count(gs: GridSize, gold_chance: f64) -> usize {
+    let tiles = gs.width as f64 * gs.height as f64;
+    (tiles * gold_chance.clamp(0.0, 1.0) / VEIN_MEAN).ceil() as usize
+}
+
+// Which tiles carry gold, per tile index. Only `eligible` tiles are used, so veins stay off the
+// Start cluster and anything else that isn't plain rock; the same seed gives the same veins.
+pub fn gold_veins(gs: GridSize, gold_chance: f64, seed: u64, eligible: &[bool]) -> Vec<bool> {
+    let mut rng = SeededRng::new(seed);
+    grow_veins(gs, vein_count(gs, gold_chance), eligible, || rng.next_f64())
+}
+
+// `roll` yields uniform values in [0, 1)
+fn grow_veins(
+    gs: GridSize,
+    veins: usize,
+    eligible: &[bool],
+    mut roll: impl FnMut() -> f64,
+) -> Vec<bool> {
+    let n = eligible.len();
+    let mut gold = vec![false; n];
+    if n == 0 {
+        return gold;
+    }
+    let w = gs.width as usize;
+    let free = |gold: &[bool], i: usize| eligible[i] && !gold[i];
+    let mut pick = |len: usize| (roll() * len as f64) as usize % len;
+    for _ in 0..veins {
+        let Some(seed) = (0..VEIN_TRIES).map(|_| pick(n)).find(|&i| free(&gold, i)) else {
+            continue;
+        };
+        let size = VEIN_MIN + pick(VEIN_MAX - VEIN_MIN + 1);
+        let mut vein = vec![seed];
+        gold[seed] = true;
+        let mut tries = 0;
+        // Each step branches off a random tile already in the vein, so veins come out as
+        // blobs and short forks rather than straight lines
+        while vein.len() < size && tries < VEIN_TRIES {
+            tries += 1;
+            let from = vein[pick(vein.len())];
+            let (x, y) = (from % w, from / w);
+            let next = match pick(4) {
+                0 if x + 1 < w => from + 1,
+                1 if x > 0 => from - 1,
+                2 if y + 1 < n / w => from + w,
+                3 if y > 0 => from - w,
+                _ => continue,
+            };
+            if free(&gold, next) {
+                gold[next] = true;
+                vein.push(next);
+            }
+        }
+    }
+    gold
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GS: GridSize = GridSize {
+        width: 40,
+        height: 40,
+    };
+
+    // Groups of orthogonally connected gold tiles
+    fn clusters(gold: &[bool]) -> Vec<usize> {
+        let w = GS.width as usize;
+        let mut seen = vec![false; gold.len()];
+        let mut sizes = Vec::new();
+        for start in 0..gold.len() {
+            if !gold[start] || seen[start] {
+                continue;
+            }
+            seen[start] = true;
+            let (mut stack, mut size) = (vec![start], 0);
+            while let Some(i) = stack.pop() {
+                size += 1;
+                let (x, y) = (i % w, i / w);
+                let near = [
+                    (x + 1 < w).then(|| i + 1),
+                    (x > 0).then(|| i - 1),
+                    (y + 1 < gold.len() / w).then(|| i + w),
+                    (y > 0).then(|| i - w),
+                ];
+                for j in near.into_iter().flatten() {
+                    if gold[j] && !seen[j] {
+                        seen[j] = true;
+                        stack.push(j);
+                    }
+                }
+            }
+            sizes.push(size);
+        }
+        sizes
+    }
+
+    #[test]
+    fn veins_are_deterministic_and_keep_the_expected_gold() {
+        let all = vec![true; 1600];
+        let a = gold_veins(GS, 0.12, 42, &all);
+        assert_eq!(a, gold_veins(GS, 0.12, 42, &all));
+        assert_ne!(a, gold_veins(GS, 0.12, 43, &all));
+        let count = a.iter().filter(|g| **g).count() as f64;
+        // 192 expected; veins that touch merge but never share tiles
+        assert!((count - 192.0).abs() <= 192.0 * 0.15, "{count} gold tiles");
+        assert_eq!(vein_count(GS, 0.12), 39);
+        assert_eq!(vein_count(GS, 0.0), 0);
+    }
+
+    #[test]
+    fn veins_are_connected_runs() {
+        let gold = gold_veins(GS, 0.05, 7, &vec![true; 1600]);
+        let sizes = clusters(&gold);
+        assert!(!sizes.is_empty());
+        // Sparse enough that few veins touch: almost every cluster is one whole vein
+        let whole = sizes
+            .iter()
+            .filter(|s| (VEIN_MIN..=VEIN_MAX).contains(s))
+            .count();
+        assert!(whole * 10 >= sizes.len() * 8, "{sizes:?}");
+    }
+
+    #[test]
+    fn veins_skip_ineligible_tiles() {
+        // Only the left half is rock
+        let eligible: Vec<bool> = (0..1600).map(|i| i % 40 < 20).collect();
+        let gold = gold_veins(GS, 0.2, 3, &eligible);
+        assert!(gold.iter().any(|g| *g));
+        assert!((0..1600).all(|i| !gold[i] || eligible[i]));
+    }
+
+    #[test]
+    fn an_injected_roll_drives_the_walk() {
+        // Seed at tile 0 with the smallest size, then always step right off the newest tile
+        let mut k = 0;
+        let roll = move || {
+            k += 1;
+            match k {
+                1 | 2 => 0.0,
+                _ if k % 2 == 1 => 0.99,
+                _ => 0.0,
+            }
+        };
+        let gold = grow_veins(GS, 1, &vec![true; 1600], roll);
+        assert_eq!(gold.iter().filter(|g| **g).count(), VEIN_MIN);
+        assert!(gold[0] && gold[1] && gold[2]);
+    }
+}
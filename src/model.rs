@@ -3,19 +3,75 @@
 //! TODOs are included to guide future implementation.
 
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
 use std::rc::Rc;
-use wasm_bindgen::JsValue;
 
-#[allow(dead_code)]
-const DEBUG_LOG: bool = false;
-#[allow(dead_code)]
-fn dlog(msg: &str) {
-    if DEBUG_LOG {
-        web_sys::console::log_1(&JsValue::from_str(msg));
+use crate::map_gen::gold_veins;
+use crate::path::{PathJob, PendingPath, SearchGrid};
+use crate::util::{format_time, log, random, Level};
+
+// -------- Basic structs --------
+// Copy-on-write field for the cold, bulky parts of RunState (board, path). Every action
+// clones the state, and clones share one allocation here; the first write after a clone pays
+// for the copy via Rc::make_mut, so a SimTick that only moves enemies never copies the board.
+// Serializes exactly as the inner value.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Shared<T: Clone>(Rc<T>);
+
+impl<T: Clone> Shared<T> {
+    pub fn new(value: T) -> Self {
+        Self(Rc::new(value))
+    }
+
+    // Same allocation, i.e. no write happened since one was cloned from the other
+    pub fn ptr_eq(a: &Self, b: &Self) -> bool {
+        Rc::ptr_eq(&a.0, &b.0)
+    }
+}
+
+impl<T: Clone> From<T> for Shared<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T: Clone> std::ops::Deref for Shared<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Clone> std::ops::DerefMut for Shared<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        Rc::make_mut(&mut self.0)
+    }
+}
+
+impl<T: Clone + FromIterator<A>, A> FromIterator<A> for Shared<T> {
+    fn from_iter<I: IntoIterator<Item = A>>(iter: I) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
+
+impl<'a, T: Clone> IntoIterator for &'a Shared<T>
+where
+    &'a T: IntoIterator,
+{
+    type Item = <&'a T as IntoIterator>::Item;
+    type IntoIter = <&'a T as IntoIterator>::IntoIter;
+    fn into_iter(self) -> Self::IntoIter {
+        (**self).into_iter()
+    }
+}
+
+impl<T: Clone + PartialEq> PartialEq for Shared<T> {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0) || *self.0 == *other.0
     }
 }
 
-// -------- Basic structs --------
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct GridSize {
     pub width: u32,
@@ -33,6 +89,39 @@ pub enum BoostKind {
     FireRate,
     Slow,
     Fire,
+    // Towers on it pulse life back to the player
+    Healing,
+}
+impl BoostKind {
+    // Tile names as the player sees them
+    pub fn name(self) -> &'static str {
+        match self {
+            BoostKind::Slow => "Cold",
+            BoostKind::Damage => "Poison",
+            BoostKind::Fire => "Fire",
+            BoostKind::Range => "Range",
+            BoostKind::FireRate => "Fire Rate",
+            BoostKind::Healing => "Healing",
+        }
+    }
+}
+// Flat bonuses for a tower built on a boost tile, before any boost upgrades
+pub const BOOST_DAMAGE_MULT: f64 = 1.5;
+pub const BOOST_RANGE_BONUS: f64 = 1.0;
+pub const BOOST_FIRE_RATE_MULT: f64 = 1.25;
+
+// (range_mult, range_bonus, damage_mult, fire_rate_mult) a tile boost bakes into its tower.
+// Shared by Tower::new and apply_upgrades_to_run so placement and re-application agree.
+pub fn boost_intrinsics(boost: Option<BoostKind>) -> (f64, f64, f64, f64) {
+    match boost {
+        Some(BoostKind::Slow) => (0.7, 0.0, 1.0, 1.0), // Cold: short-range area denial, slows on hit
+        Some(BoostKind::Fire) => (1.0, 0.0, 1.0, 1.0), // Fire: burn on hit
+        Some(BoostKind::Damage) => (1.0, 0.0, BOOST_DAMAGE_MULT, 1.0), // Poison: harder hits + DoT
+        Some(BoostKind::Range) => (1.0, BOOST_RANGE_BONUS, 1.0, 1.0),
+        Some(BoostKind::FireRate) => (1.0, 0.0, 1.0, BOOST_FIRE_RATE_MULT),
+        Some(BoostKind::Healing) => (1.0, 0.0, 1.0, 1.0), // Healing: heal pulses, no combat edge
+        None => (1.0, 0.0, 1.0, 1.0),
+    }
 }
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ArrowDir {
@@ -67,6 +156,31 @@ pub enum TileKind {
 pub struct Tile {
     pub kind: TileKind,
     pub hardness: u8,
+    // Magma pocket: a Rock over one mines out to lava, open ground that burns enemies on it
+    // and keeps towers off the tiles beside it
+    #[serde(default)]
+    pub magma: bool,
+}
+impl Tile {
+    pub fn is_lava(&self) -> bool {
+        self.magma && self.kind == TileKind::Empty
+    }
+}
+
+// Hardness by kind: open ground is 1, generated rock 3, and the fixed tiles never mine
+pub const EMPTY_HARDNESS: u8 = 1;
+pub const ROCK_HARDNESS: u8 = 3;
+pub const FIXED_HARDNESS: u8 = 255;
+
+// Hardness a freshly generated or carved tile of this kind gets
+pub fn default_hardness(kind: &TileKind) -> u8 {
+    match kind {
+        TileKind::Empty => EMPTY_HARDNESS,
+        TileKind::Rock { .. } | TileKind::Wall => ROCK_HARDNESS,
+        TileKind::Start | TileKind::Direction { .. } | TileKind::Indestructible | TileKind::End => {
+            FIXED_HARDNESS
+        }
+    }
 }
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Currencies {
@@ -79,6 +193,74 @@ pub struct RunStats {
     pub time_survived_secs: u64,
     pub loops_completed: u32,
     pub blocks_mined: u32,
+    #[serde(default)]
+    pub enemies_spawned: u64,
+    #[serde(default)]
+    pub enemies_killed: u64,
+    // In-run research income, after the streak multiplier
+    #[serde(default)]
+    pub research_earned: u64,
+    // Lives refunded by clutch kills
+    #[serde(default)]
+    pub clutch_saves: u32,
+    // Tower damage, direct and splash (damage over time and lava aren't counted)
+    #[serde(default)]
+    pub total_damage_dealt: u64,
+    #[serde(default)]
+    pub highest_single_hit: u32,
+    // Income from kills, pickups, gold veins, interest and perks; refunds from sales aren't
+    // income
+    #[serde(default)]
+    pub gold_earned: u64,
+    // Towers, tower upgrades, swaps and repairs
+    #[serde(default)]
+    pub gold_spent: u64,
+    #[serde(default)]
+    pub towers_built: u32,
+    #[serde(default)]
+    pub towers_sold: u32,
+    #[serde(default)]
+    pub walls_built: u32,
+    // End route: enemies that reached the End tile (the loop route counts loops instead)
+    #[serde(default)]
+    pub breaches: u32,
+}
+
+// Tower damage per whole sim second over the last DPS_WINDOW_SECS, for the HUD's live DPS
+pub const DPS_WINDOW_SECS: u64 = 10;
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct DamageWindow {
+    // (sim second, damage), oldest first
+    buckets: VecDeque<(u64, u64)>,
+}
+impl DamageWindow {
+    fn add(&mut self, now: f64, damage: u64) {
+        let sec = now.max(0.0) as u64;
+        match self.buckets.back_mut() {
+            Some((s, d)) if *s == sec => *d += damage,
+            _ => self.buckets.push_back((sec, damage)),
+        }
+        while self
+            .buckets
+            .front()
+            .is_some_and(|(s, _)| s + DPS_WINDOW_SECS <= sec)
+        {
+            self.buckets.pop_front();
+        }
+    }
+
+    // Damage per second over the window ending at `now`; a run younger than the window is
+    // averaged over its own length
+    pub fn dps(&self, now: f64) -> f64 {
+        let sec = now.max(0.0) as u64;
+        let total: u64 = self
+            .buckets
+            .iter()
+            .filter(|(s, _)| s + DPS_WINDOW_SECS > sec)
+            .map(|(_, d)| d)
+            .sum();
+        total as f64 / now.clamp(1.0, DPS_WINDOW_SECS as f64)
+    }
 }
 // -------- Debuff System --------
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -96,8 +278,111 @@ pub struct Debuff {
     pub strength: f64, // For Slow: speed multiplier (0.5 = 50% slow), For Poison: damage per second
 }
 
+// Whole damage points a DoT of `dps` deals while its timer runs down from `before` to `after`.
+// Counting the points left on the timer keeps short frames from rounding every tick to zero.
+fn dot_damage(dps: f64, before: f64, after: f64) -> u32 {
+    ((dps * before).ceil() - (dps * after.max(0.0)).ceil()).max(0.0) as u32
+}
+
+// Slow towers chill whatever they hit, on top of any boost debuff
+pub const SLOW_TOWER_STRENGTH: f64 = 0.35;
+pub const SLOW_TOWER_SECS: f64 = 1.5;
+// However slows stack, an enemy keeps this fraction of its speed; only Freeze stops it
+pub const SLOW_SPEED_FLOOR: f64 = 0.4;
+
+// A freeze stops an enemy outright for FREEZE_SECS; its expired debuff then lingers for
+// FREEZE_IMMUNITY_SECS, during which the enemy can't be frozen again
+pub const FREEZE_SECS: f64 = 1.0;
+pub const FREEZE_IMMUNITY_SECS: f64 = 2.0;
+pub const FREEZE_CHANCE_PER_LEVEL: f64 = 0.02;
+
+// Cold upgrades: (slow strength multiplier, extra slow seconds), for Cold tiles and Slow towers
+pub fn cold_slow_scaling(ups: &UpgradeState) -> (f64, f64) {
+    (
+        1.0 + 0.10 * ups.level(UpgradeId::BoostColdSlowAmount) as f64,
+        1.0 * ups.level(UpgradeId::BoostColdSlowDuration) as f64,
+    )
+}
+
+// Expired debuffs drop off, except a thawed freeze still inside its immunity window
+fn debuff_lingers(d: &Debuff) -> bool {
+    d.remaining > 0.0 || (d.kind == DebuffKind::Freeze && d.remaining > -FREEZE_IMMUNITY_SECS)
+}
+
+// Movement multiplier from an enemy's live debuffs
+pub fn debuff_speed_mult(debuffs: &[Debuff]) -> f64 {
+    let mut mult: f64 = 1.0;
+    for d in debuffs.iter().filter(|d| d.remaining > 0.0) {
+        match d.kind {
+            DebuffKind::Freeze => return 0.0,
+            DebuffKind::Slow => mult = mult.min(1.0 - d.strength),
+            DebuffKind::Poison | DebuffKind::Burn => {}
+        }
+    }
+    mult.max(SLOW_SPEED_FLOOR)
+}
+
+// Poison hits add up, to at most this many hits' damage per second
+pub const POISON_MAX_STACKS: f64 = 3.0;
+// Poison Spread hands a dying enemy's poison to the nearest other enemy this close
+pub const POISON_SPREAD_RADIUS: f64 = 1.5;
+
+// Re-applying a debuff refreshes its timer and keeps the stronger effect; only poison stacks
+fn inflict(e: &mut Enemy, d: Debuff) {
+    if let Some(existing) = e.debuffs.iter_mut().find(|x| x.kind == d.kind) {
+        existing.remaining = existing.remaining.max(d.remaining);
+        existing.strength = if d.kind == DebuffKind::Poison {
+            (existing.strength + d.strength)
+                .min(d.strength * POISON_MAX_STACKS)
+                .max(existing.strength)
+        } else {
+            existing.strength.max(d.strength)
+        };
+    } else {
+        e.debuffs.push(d);
+    }
+}
+
+// A slow landing on `e`: with `roll` under `freeze_chance` it freezes the enemy as well, unless
+// a freeze is still running or in its immunity window
+fn chill(e: &mut Enemy, slow: Debuff, freeze_chance: f64, roll: f64) {
+    let thawed = !e.debuffs.iter().any(|d| d.kind == DebuffKind::Freeze);
+    if thawed && roll < freeze_chance {
+        inflict(
+            e,
+            Debuff {
+                kind: DebuffKind::Freeze,
+                remaining: FREEZE_SECS,
+                strength: 1.0,
+            },
+        );
+    }
+    inflict(e, slow);
+}
+
+// Stable identity for enemies, towers and projectiles; unique within a run (run_id tells runs
+// apart). 0 is never handed out, so hand-built test entities can't collide with allocated ones.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub struct EntityId(pub u64);
+
+// Monotonic id counter; its own type so it can be borrowed beside the entity lists
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntityIds {
+    last: u64,
+}
+impl EntityIds {
+    pub fn alloc(&mut self) -> EntityId {
+        self.last += 1;
+        EntityId(self.last)
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Enemy {
+    #[serde(default)]
+    pub id: EntityId,
     pub x: f64,
     pub y: f64,
     pub speed_tps: f64,
@@ -110,6 +395,132 @@ pub struct Enemy {
     pub radius_scale: f64,
     pub loop_dist: f64,
     pub debuffs: Vec<Debuff>,
+    #[serde(default)]
+    pub kind: EnemyKind,
+    // Seconds left on the white flash from the last projectile hit
+    #[serde(default)]
+    pub hit_flash_ttl: f64,
+    // Seconds left of growing in at the Entrance
+    #[serde(default)]
+    pub spawn_anim_ttl: f64,
+}
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EnemyKind {
+    #[default]
+    Normal,
+    // Mini-boss that rebuilds rock on the Empty tiles it leaves behind
+    Mason,
+    // Quick and frail
+    Fast,
+    // Slow and heavily armoured; worth more research
+    Tank,
+    // Arrives SWARM_SIZE at a time, small and weak
+    Swarm,
+}
+// Multipliers on the spawner's time-scaled hp, speed and size
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KindScale {
+    pub hp: f64,
+    pub speed: f64,
+    pub size: f64,
+}
+impl EnemyKind {
+    pub const ALL: [EnemyKind; 5] = [
+        EnemyKind::Normal,
+        EnemyKind::Fast,
+        EnemyKind::Tank,
+        EnemyKind::Swarm,
+        EnemyKind::Mason,
+    ];
+    pub fn scale(self) -> KindScale {
+        let (hp, speed, size) = match self {
+            EnemyKind::Normal => (1.0, 1.0, 1.0),
+            EnemyKind::Mason => (MASON_HP_MULT, MASON_SPEED_MULT, 1.3),
+            EnemyKind::Fast => (0.6, 1.6, 0.8),
+            EnemyKind::Tank => (3.0, 0.6, 1.5),
+            EnemyKind::Swarm => (0.35, 1.15, 0.6),
+        };
+        KindScale { hp, speed, size }
+    }
+    // Research per kill, before the streak multiplier; a whole swarm is worth one
+    pub fn research_reward(self) -> f64 {
+        match self {
+            EnemyKind::Tank => 3.0,
+            EnemyKind::Swarm => 1.0 / SWARM_SIZE as f64,
+            _ => 1.0,
+        }
+    }
+    // Life lost when one gets round the loop
+    pub fn life_damage(self) -> u32 {
+        match self {
+            EnemyKind::Tank => 3,
+            EnemyKind::Mason => 2,
+            _ => 1,
+        }
+    }
+    pub fn name(self) -> &'static str {
+        match self {
+            EnemyKind::Normal => "Enemy",
+            EnemyKind::Mason => "Mason",
+            EnemyKind::Fast => "Runner",
+            EnemyKind::Tank => "Tank",
+            EnemyKind::Swarm => "Swarm",
+        }
+    }
+}
+pub const SWARM_SIZE: usize = 3;
+// Swarmlings move at slightly different speeds so the group spreads out along the loop
+const SWARM_SPEED_SPREAD: [f64; SWARM_SIZE] = [1.0, 0.9, 1.1];
+
+// Spawn weights against Normal (1.0), ramping in linearly between the two times in seconds:
+// runners from the first minute, tanks and swarms later
+const ARCHETYPE_RAMPS: [(EnemyKind, f64, f64, f64); 3] = [
+    (EnemyKind::Fast, 0.35, 30.0, 120.0),
+    (EnemyKind::Tank, 0.25, 90.0, 240.0),
+    (EnemyKind::Swarm, 0.2, 120.0, 300.0),
+];
+
+pub fn archetype_weights(secs: u64) -> Vec<(EnemyKind, f64)> {
+    let t = secs as f64;
+    std::iter::once((EnemyKind::Normal, 1.0))
+        .chain(ARCHETYPE_RAMPS.iter().map(|&(kind, weight, from, full)| {
+            (kind, weight * ((t - from) / (full - from)).clamp(0.0, 1.0))
+        }))
+        .collect()
+}
+
+// `roll` in [0, 1) picks by weight
+pub fn pick_archetype(secs: u64, roll: f64) -> EnemyKind {
+    let weights = archetype_weights(secs);
+    let total: f64 = weights.iter().map(|w| w.1).sum();
+    let mut left = roll * total;
+    for (kind, w) in weights {
+        if left < w {
+            return kind;
+        }
+        left -= w;
+    }
+    EnemyKind::Normal
+}
+// Manual collection: a kill's research and bounty wait at the death spot until the cursor or
+// focus tile passes within PICKUP_REACH_TILES, or expire after PICKUP_TTL_SECS at half value
+pub const PICKUP_TTL_SECS: f64 = 15.0;
+pub const PICKUP_REACH_TILES: f64 = 1.5;
+// Past this many the oldest expires early
+pub const PICKUP_CAP: usize = 200;
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Pickup {
+    pub x: f64,
+    pub y: f64,
+    // Before the streak multiplier, which applies when it is credited
+    pub research: f64,
+    pub gold: u64,
+    pub ttl: f64,
+}
+impl Pickup {
+    fn in_reach(&self, x: f64, y: f64) -> bool {
+        (self.x - x).hypot(self.y - y) <= PICKUP_REACH_TILES
+    }
 }
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct DamageNumber {
@@ -119,11 +530,36 @@ pub struct DamageNumber {
     pub ttl: f64,
     #[serde(default)]
     pub is_crit: bool,
+    // Vein gold from older saves; new gold rises as a FloatingText
     #[serde(default)]
     pub is_gold: bool,
     #[serde(default)]
     pub is_heal: bool,
+    // Clutch-save life refund (see CLUTCH_DISTANCE)
+    #[serde(default)]
+    pub is_clutch: bool,
+}
+
+// Reward text (research from kills, gold from veins and interest) rising off the board like a
+// damage number
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FloatingText {
+    pub x: f64,
+    pub y: f64,
+    pub text: String,
+    // CSS color
+    pub color: String,
+    pub ttl: f64,
+    // Drawn larger and bold
+    #[serde(default)]
+    pub is_crit: bool,
 }
+pub const FLOATING_TEXT_SECS: f64 = 1.0;
+// Oldest texts drop past this, so a kill storm can't swamp the frame
+pub const FLOATING_TEXT_CAP: usize = 100;
+pub const RESEARCH_TEXT_COLOR: &str = "#58a6ff";
+pub const GOLD_TEXT_COLOR: &str = "#d29922";
+pub const GOLD_CRIT_TEXT_COLOR: &str = "#ffd700";
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct SplashExplosion {
@@ -133,20 +569,230 @@ pub struct SplashExplosion {
     pub ttl: f64,
 }
 
+// Rock laid by a Mason; drawn with a fading tint
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FreshRock {
+    pub x: u32,
+    pub y: u32,
+    pub ttl: f64,
+}
+pub const FRESH_ROCK_TTL: f64 = 3.0;
+
+// The latest wall, which RunAction::UndoLastWall can take back for WALL_UNDO_SECS of sim time
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LastWall {
+    pub idx: usize,
+    // State version the wall was placed on; tells one placement's undo prompt from the next
+    pub version: u64,
+    pub placed_at: f64,
+    // An enemy has stood on the tile since it went down, which rules the undo out for good
+    #[serde(default)]
+    pub entered: bool,
+}
+pub const WALL_UNDO_SECS: f64 = 5.0;
+
+// Mason mini-boss tuning
+const MASON_MIN_TIME_SECS: u64 = 60;
+const MASON_SPAWN_CHANCE: f64 = 0.04;
+const MASON_HP_MULT: f64 = 4.0;
+const MASON_SPEED_MULT: f64 = 0.8;
+const MASON_REBUILD_CHANCE: f64 = 0.3;
+const MASON_SEED_SALT: u64 = 0x4D41_534F;
+const MASON_ROCK_HARDNESS: u8 = 2;
+
+// Lava hazard tuning: one magma rock per this many tiles, and damage dealt every pulse to each
+// enemy standing on lava
+const MAGMA_TILES_PER_POCKET: u32 = 400;
+pub const LAVA_PULSE_SECS: f64 = 0.5;
+pub const LAVA_PULSE_DAMAGE: u32 = 2;
+
+// One bit per tile, row-major
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TileBitset {
+    width: u32,
+    height: u32,
+    words: Vec<u64>,
+}
+impl TileBitset {
+    pub fn new(width: u32, height: u32) -> Self {
+        let bits = width as usize * height as usize;
+        Self {
+            width,
+            height,
+            words: vec![0; bits.div_ceil(64)],
+        }
+    }
+    fn bit(&self, x: u32, y: u32) -> Option<(usize, u64)> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let i = y as usize * self.width as usize + x as usize;
+        Some((i / 64, 1u64 << (i % 64)))
+    }
+    pub fn get(&self, x: u32, y: u32) -> bool {
+        self.bit(x, y)
+            .and_then(|(w, m)| self.words.get(w).map(|v| v & m != 0))
+            .unwrap_or(false)
+    }
+    // Out-of-range writes are ignored
+    pub fn set(&mut self, x: u32, y: u32, on: bool) {
+        if let Some((w, m)) = self.bit(x, y)
+            && let Some(v) = self.words.get_mut(w)
+        {
+            if on {
+                *v |= m
+            } else {
+                *v &= !m
+            }
+        }
+    }
+    pub fn fits(&self, gs: GridSize) -> bool {
+        self.width == gs.width && self.height == gs.height
+    }
+    pub fn count(&self) -> u32 {
+        self.words.iter().map(|w| w.count_ones()).sum()
+    }
+    // True if the tile under a world-space point is flagged
+    pub fn contains_point(&self, x: f64, y: f64) -> bool {
+        x >= 0.0 && y >= 0.0 && self.get(x.floor() as u32, y.floor() as u32)
+    }
+}
+
+// Live damage heatmap: one cell per HEAT_CELL_TILES x HEAT_CELL_TILES tiles, decayed every
+// second so it shows recent fighting without growing over a run
+pub const HEAT_CELL_TILES: u32 = 2;
+pub const HEAT_DECAY_PER_SEC: f32 = 0.95;
+// Cells below this after decay are cleared
+pub const HEAT_FLOOR: f32 = 0.01;
+// Heat that reads as full intensity even when nothing hotter is on the map
+pub const HEAT_MIN_SCALE: f32 = 20.0;
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct HeatGrid {
+    // Size in tiles; with an odd size the last cell column or row covers a single tile
+    tile_width: u32,
+    tile_height: u32,
+    cells: Vec<f32>,
+}
+impl HeatGrid {
+    pub fn new(gs: GridSize) -> Self {
+        let cells = gs.width.div_ceil(HEAT_CELL_TILES) * gs.height.div_ceil(HEAT_CELL_TILES);
+        Self {
+            tile_width: gs.width,
+            tile_height: gs.height,
+            cells: vec![0.0; cells as usize],
+        }
+    }
+    // Size in cells
+    pub fn width(&self) -> u32 {
+        self.tile_width.div_ceil(HEAT_CELL_TILES)
+    }
+    pub fn cells(&self) -> &[f32] {
+        &self.cells
+    }
+    // Cell under a world-space point; None off the grid
+    pub fn cell_index(&self, x: f64, y: f64) -> Option<usize> {
+        if x < 0.0 || y < 0.0 {
+            return None;
+        }
+        let (tx, ty) = (x.floor() as u32, y.floor() as u32);
+        (tx < self.tile_width && ty < self.tile_height)
+            .then(|| (ty / HEAT_CELL_TILES * self.width() + tx / HEAT_CELL_TILES) as usize)
+    }
+    pub fn add(&mut self, x: f64, y: f64, amount: f32) {
+        if let Some(i) = self.cell_index(x, y) {
+            self.cells[i] += amount;
+        }
+    }
+    pub fn decay(&mut self, factor: f32) {
+        for c in &mut self.cells {
+            *c *= factor;
+            if *c < HEAT_FLOOR {
+                *c = 0.0;
+            }
+        }
+    }
+    pub fn max(&self) -> f32 {
+        self.cells.iter().copied().fold(0.0, f32::max)
+    }
+}
+
+// Overlay opacity in 0..=1 on a log curve, so one big hit doesn't wash out everything else
+pub fn heat_intensity(value: f32, max: f32) -> f64 {
+    if value <= 0.0 {
+        return 0.0;
+    }
+    let scale = max.max(HEAT_MIN_SCALE) as f64;
+    ((value as f64).ln_1p() / scale.ln_1p()).min(1.0)
+}
+
+// Tower placement history: what towers achieved from each tile over the run, kept after they
+// are sold, moved away or crumble so rearranged mazes still show which spots paid off
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TowerTally {
+    pub kills: u64,
+    pub damage: u64,
+}
+impl TowerTally {
+    pub fn add(&mut self, other: TowerTally) {
+        self.kills = self.kills.saturating_add(other.kills);
+        self.damage = self.damage.saturating_add(other.damage);
+    }
+}
+
+// Folds a departing tower's tally into its tile's history
+fn retire_tower(rs: &mut RunState, tw: &Tower) {
+    if tw.here == TowerTally::default() {
+        return;
+    }
+    let idx = tw.y * rs.grid_size.width + tw.x;
+    rs.tower_history.entry(idx).or_default().add(tw.here);
+}
+
+// Lifetime tally per tile index: departed towers plus the ones standing there now
+pub fn placement_history(rs: &RunState) -> BTreeMap<u32, TowerTally> {
+    let mut out = rs.tower_history.clone();
+    for tw in rs.towers.iter().filter(|t| t.here != TowerTally::default()) {
+        out.entry(tw.y * rs.grid_size.width + tw.x)
+            .or_default()
+            .add(tw.here);
+    }
+    out
+}
+
+// Clean-play streak: each leak-free minute adds STREAK_STEP to the research multiplier
+pub const STREAK_MINUTE_SECS: u32 = 60;
+pub const STREAK_STEP: f64 = 0.1;
+pub const STREAK_MAX: u32 = 10;
+
+// Targeting exclusion: warn once zones cover this much of the loop
+pub const EXCLUSION_WARN_FRACTION: f64 = 0.8;
+
+// Boards this large search for a mined-open path over several SimTicks instead of in one
+pub const PATH_CHUNK_MIN_TILES: usize = 200 * 200;
+// Node expansions a pending path search gets per SimTick
+pub const PATH_EXPANSIONS_PER_TICK: usize = 4000;
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct RunState {
     pub grid_size: GridSize,
-    pub tiles: Vec<Tile>,
+    pub tiles: Shared<Vec<Tile>>,
     pub currencies: Currencies,
     pub stats: RunStats,
     pub life: u32,
     pub mining_speed: f64,
     pub started: bool,
     pub is_paused: bool,
-    pub path: Vec<Position>,
-    pub path_loop: Vec<Position>,
-    pub loop_cum_lengths: Vec<f64>,
+    // Game speed, one of SIM_SPEEDS
+    #[serde(default = "one")]
+    pub sim_speed: f64,
+    pub path: Shared<Vec<Position>>,
+    pub path_loop: Shared<Vec<Position>>,
+    pub loop_cum_lengths: Shared<Vec<f64>>,
     pub loop_total_length: f64,
+    // Loop distance of the Entrance: enemies appear there, and a lap runs from it back round to it
+    #[serde(default)]
+    pub loop_entry_dist: f64,
     pub enemies: Vec<Enemy>,
     pub last_enemy_spawn_time_secs: f64,
     pub version: u64,
@@ -156,7 +802,16 @@ pub struct RunState {
     pub towers: Vec<Tower>,
     pub tower_base_range: f64,
     pub tower_base_damage: u32,
+    // Prestige bonus on every shot; kept apart from the whole-number tower_base_damage so a
+    // single point isn't rounded away
+    #[serde(default = "one")]
+    pub prestige_damage_mult: f64,
+    // Basic tower price; see tower_cost_of for the other kinds
     pub tower_cost: u64,
+    #[serde(default = "default_tower_cost_slow")]
+    pub tower_cost_slow: u64,
+    #[serde(default = "default_tower_cost_damage")]
+    pub tower_cost_damage: u64,
     pub projectiles: Vec<Projectile>,
     pub run_id: u64,
     pub life_max: u32,
@@ -169,11 +824,14 @@ pub struct RunState {
     pub gold_bounty_mul: f64,
     pub damage_ramp_per_sec: f64,
     pub damage_numbers: Vec<DamageNumber>,
+    #[serde(default)]
+    pub floating_texts: Vec<FloatingText>,
     pub projectile_speed: f64,
     pub vampiric_heal_percent: f64,
     pub mining_gold_mul: f64,
     pub mining_crit_chance: f64,
-    pub tower_refund_mult: f64,
+    // Share of a tower's price returned on sale (set by apply_upgrades_to_run)
+    pub tower_refund_percent: u32,
     // NEW: track how many levels of StartingGold have already been applied to prevent repeated additive grants
     pub starting_gold_applied_level: u8,
     // Player power level based on total upgrades - used to scale enemy difficulty
@@ -184,11 +842,157 @@ pub struct RunState {
     pub fire_debuff_template: Option<Debuff>,
     // Fire spread radius (0.0 if BoostFireSpread not upgraded)
     pub fire_spread_radius: f64,
+    // Poison Spread: a poisoned enemy's poison moves on when it dies
+    #[serde(default)]
+    pub poison_spread: bool,
     pub freeze_chance: f64,
+    // Cold upgrade scaling for Slow tower hits (Cold tiles bake it into cold_debuff_template)
+    #[serde(default = "one")]
+    pub cold_slow_multiplier: f64,
+    #[serde(default)]
+    pub cold_slow_duration: f64,
+    // Life each Healing tower restores per pulse, and seconds since the last pulse
     pub healing_tile_heal_per_tick: f64,
     pub healing_tile_timer: f64,
+    // Kills within this distance of a Healing tower heal too (0.0 without Healing Aura)
+    #[serde(default)]
+    pub healing_aura_radius: f64,
+    // Healing not yet worth a whole life point
+    #[serde(default)]
+    pub heal_accum: f64,
+    // Overheal from Healing Shield, spent before life when an enemy completes a loop
+    #[serde(default)]
+    pub shield: u32,
+    #[serde(default)]
+    pub shield_max: u32,
+    // sim_time of the latest heal pulse, for its ring on the board
+    #[serde(default)]
+    pub last_heal_pulse_at: Option<f64>,
     pub projectile_splash_radius: f64,
+    // Extra hits per shot from the Bounce upgrade
+    #[serde(default)]
+    pub projectile_bounces: u8,
+    // Bank upgrade: share of held gold paid every BANK_INTEREST_PERIOD_SECS, up to the cap
+    #[serde(default)]
+    pub bank_interest_rate: f64,
+    #[serde(default)]
+    pub bank_interest_cap: u64,
+    // Size of the latest payment, for the stats panel
+    #[serde(default)]
+    pub last_interest: u64,
+    // Auto Miner upgrade: seconds between passive mines (0 without it) and seconds to the next
+    #[serde(default)]
+    pub auto_mine_period: u64,
+    #[serde(default)]
+    pub auto_mine_cooldown: u64,
+    // SeededRng state behind clutch-save rolls, seeded from the map seed
+    #[serde(default)]
+    pub clutch_rng: u64,
+    // SeededRng state behind Mason rebuild rolls, so a tick replays exactly
+    #[serde(default)]
+    pub mason_rng: u64,
     pub splash_explosions: Vec<SplashExplosion>,
+    #[serde(default)]
+    pub map_seed: u64,
+    #[serde(default)]
+    pub fresh_rock: Vec<FreshRock>,
+    // Current run number for progressive unlocks; None = everything unlocked
+    #[serde(default)]
+    pub progression_run: Option<u64>,
+    // Why the last gated action was rejected
+    #[serde(default)]
+    pub locked_notice: Option<String>,
+    // Player-painted tiles towers won't target enemies on
+    #[serde(default)]
+    pub no_target: TileBitset,
+    // Leak-free minutes in a row (capped) and seconds into the current one
+    #[serde(default)]
+    pub clean_streak: u32,
+    #[serde(default)]
+    pub clean_streak_secs: u32,
+    // Fractional research left over after the streak multiplier
+    #[serde(default)]
+    pub research_carry: f64,
+    // Research earned toward the next point from projectile damage, in [0, 1)
+    #[serde(default)]
+    pub research_progress: f64,
+    // Set on each streak change for the HUD to announce
+    #[serde(default)]
+    pub streak_notice: Option<String>,
+    // Research-bought structures (currently at most one scarecrow)
+    #[serde(default)]
+    pub structures: Vec<Structure>,
+    #[serde(default)]
+    pub projectile_culls: ProjectileCulls,
+    // Starting perk, taken before the run starts; at most one per run
+    #[serde(default)]
+    pub perk: Option<Perk>,
+    // Towers that can still be placed without paying gold
+    #[serde(default)]
+    pub free_towers: u32,
+    // Seconds left on the timed mining bonus
+    #[serde(default)]
+    pub perk_mining_secs: u32,
+    // One-shot: the next leak costs no life
+    #[serde(default)]
+    pub leak_shield: bool,
+    // Hardcore siege modifier, chosen before the run starts
+    #[serde(default)]
+    pub siege: bool,
+    // Loop or End route, chosen before the run starts
+    #[serde(default)]
+    pub route_mode: RouteMode,
+    // Set when a tower crumbles under siege, for the HUD to announce
+    #[serde(default)]
+    pub siege_notice: Option<String>,
+    // Pacing hints: player setting, the hint on screen, and those already shown this run
+    #[serde(default)]
+    pub hints: bool,
+    #[serde(default)]
+    pub hint: Option<HintId>,
+    #[serde(default)]
+    pub hints_shown: Vec<HintId>,
+    // Research price of the cheapest purchasable upgrade (set by apply_upgrades_to_run)
+    #[serde(default)]
+    pub cheapest_upgrade_cost: Option<u64>,
+    // Recent damage by area, for the live heatmap overlay
+    #[serde(default)]
+    pub heat: HeatGrid,
+    // Latched by low_life_next; drives the low-life warning in the HUD
+    #[serde(default)]
+    pub low_life: bool,
+    // sim_time of the latest life lost, for the HUD flash; 0 until then
+    #[serde(default)]
+    pub last_life_loss_at: f64,
+    // Puzzle run (see SCENARIO_DEFS); None for endless runs
+    #[serde(default)]
+    pub scenario: Option<ScenarioRun>,
+    // Ids for enemies, towers and projectiles; restarts with each run
+    #[serde(default)]
+    pub entity_ids: EntityIds,
+    // Kills and damage of towers that have since left a tile, by tile index; towers still
+    // standing carry theirs in Tower::here (see placement_history)
+    #[serde(default)]
+    pub tower_history: BTreeMap<u32, TowerTally>,
+    // Search for the new path after mining on a large board; `path` keeps the old route until
+    // it lands (see advance_path_job). Not saved: loading recomputes the path anyway.
+    #[serde(skip)]
+    pub path_job: Option<PendingPath>,
+    // Kill rewards drop as pickups instead of being credited (player setting)
+    #[serde(default)]
+    pub manual_collection: bool,
+    #[serde(default)]
+    pub pickups: Vec<Pickup>,
+    // Loaded from a debug bundle: developer tools stay on and the run never counts for records
+    #[serde(default)]
+    pub debug_allowed: bool,
+    #[serde(default)]
+    pub last_wall: Option<LastWall>,
+    #[serde(default)]
+    pub damage_window: DamageWindow,
+    // How enemies toughen and crowd in over the run
+    #[serde(default)]
+    pub difficulty: DifficultyCurve,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -197,20 +1001,99 @@ pub enum TowerKind {
     Slow,
     Damage,
 }
-#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
-pub struct Tower {
-    pub x: u32,
-    pub y: u32,
-    pub kind: TowerKind,
-    pub range: f64,
-    pub damage: u32,
+impl TowerKind {
+    pub const ALL: [TowerKind; 3] = [TowerKind::Basic, TowerKind::Slow, TowerKind::Damage];
+    pub fn name(&self) -> &'static str {
+        match self {
+            TowerKind::Basic => "Basic",
+            TowerKind::Slow => "Slow",
+            TowerKind::Damage => "Damage",
+        }
+    }
+    // Following kind in ALL order, wrapping
+    pub fn next(&self) -> TowerKind {
+        match self {
+            TowerKind::Basic => TowerKind::Slow,
+            TowerKind::Slow => TowerKind::Damage,
+            TowerKind::Damage => TowerKind::Basic,
+        }
+    }
+}
+// Which in-range enemy a tower shoots. First/Last go by progress along the loop (loop_dist),
+// Closest by distance to the tower, Strongest by remaining hp; ties go to the older enemy.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TargetMode {
+    #[default]
+    First,
+    Last,
+    Closest,
+    Strongest,
+}
+impl TargetMode {
+    pub fn name(self) -> &'static str {
+        match self {
+            TargetMode::First => "First",
+            TargetMode::Last => "Last",
+            TargetMode::Closest => "Closest",
+            TargetMode::Strongest => "Strongest",
+        }
+    }
+    // Drawn beside the tower
+    pub fn letter(self) -> &'static str {
+        &self.name()[..1]
+    }
+    pub fn next(self) -> TargetMode {
+        match self {
+            TargetMode::First => TargetMode::Last,
+            TargetMode::Last => TargetMode::Closest,
+            TargetMode::Closest => TargetMode::Strongest,
+            TargetMode::Strongest => TargetMode::First,
+        }
+    }
+}
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Tower {
+    #[serde(default)]
+    pub id: EntityId,
+    pub x: u32,
+    pub y: u32,
+    pub kind: TowerKind,
+    pub range: f64,
+    pub damage: u32,
     pub fire_rate: f64,
     pub cooldown_remaining: f64,
     pub boost: Option<BoostKind>,
     pub apply_debuff: Option<Debuff>,
+    // Lifetime damage dealt; drives veterancy rank
+    #[serde(default)]
+    pub damage_dealt: u64,
+    // Lifetime kills from its own shots
+    #[serde(default)]
+    pub kills: u64,
+    // Fires into no-target zones anyway
+    #[serde(default)]
+    pub ignore_exclusion: bool,
+    // Durability; only worn down under the siege modifier
+    #[serde(default = "siege_tower_hp")]
+    pub hp: f64,
+    #[serde(default = "siege_tower_hp")]
+    pub max_hp: f64,
+    // Gold paid to place it (0 for perk towers); a sale never refunds more
+    #[serde(default)]
+    pub paid: u64,
+    // Levels bought with UpgradeTower, up to TOWER_MAX_LEVEL
+    #[serde(default)]
+    pub level: u8,
+    #[serde(default)]
+    pub target_mode: TargetMode,
+    // Credited since it arrived on its current tile
+    #[serde(default)]
+    pub here: TowerTally,
 }
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Projectile {
+    #[serde(default)]
+    pub id: EntityId,
     pub x: f64,
     pub y: f64,
     pub vx: f64,
@@ -219,7 +1102,254 @@ pub struct Projectile {
     pub damage: u32,
     pub splash_radius: f64,
     pub apply_debuff: Option<Debuff>,
+    // Firing tower, for crediting damage dealt
+    #[serde(default)]
+    pub source: Option<EntityId>,
+
+    // Seconds since firing, for the lifetime cap
+    #[serde(default)]
+    pub age: f64,
+    // Further enemies this shot may hop to after landing (see advance_projectiles)
+    #[serde(default)]
+    pub bounces_remaining: u8,
+    // Slow tower chill (Tower::kind_debuff)
+    #[serde(default)]
+    pub slow: Option<Debuff>,
+}
+
+// A bouncing shot hops to the nearest other enemy within this many tiles, at reduced damage
+pub const BOUNCE_RANGE_TILES: f64 = 3.0;
+pub const BOUNCE_DAMAGE_FACTOR: f64 = 0.75;
+
+// Leak guards for projectiles (see advance_projectiles)
+pub const PROJECTILE_MAX_LIFETIME_SECS: f64 = 3.0;
+pub const PROJECTILE_CAP: usize = 512;
+pub const PROJECTILE_OOB_MARGIN_TILES: f64 = 2.0;
+// How long an enemy flashes white after a projectile lands on it
+pub const HIT_FLASH_SECS: f64 = 0.12;
+// Projectile damage that earns one research point, on top of the kill rewards
+pub const DAMAGE_PER_RESEARCH: f64 = 50.0;
+// How long a new enemy takes to grow to full size
+pub const SPAWN_ANIM_SECS: f64 = 0.3;
+
+// Projectiles dropped by each guard this run, shown in the debug overlay
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProjectileCulls {
+    pub expired: u64,
+    pub capped: u64,
+    pub out_of_bounds: u64,
+}
+
+// Research-bought structures placed on rock; each crumbles once its durability runs out
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StructureKind {
+    // Stretches the spawn interval; loses 1 durability per spawn it delays
+    Scarecrow,
+}
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Structure {
+    pub x: u32,
+    pub y: u32,
+    pub kind: StructureKind,
+    pub hp: u32,
+    pub max_hp: u32,
+}
+
+pub const SCARECROW_RESEARCH_COST: u64 = 15;
+pub const SCARECROW_DURABILITY: u32 = 20;
+pub const SCARECROW_SPAWN_INTERVAL_MULT: f64 = 1.15;
+
+impl StructureKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            StructureKind::Scarecrow => "Scarecrow",
+        }
+    }
+    pub fn research_cost(self) -> u64 {
+        match self {
+            StructureKind::Scarecrow => SCARECROW_RESEARCH_COST,
+        }
+    }
+    pub fn durability(self) -> u32 {
+        match self {
+            StructureKind::Scarecrow => SCARECROW_DURABILITY,
+        }
+    }
+}
+
+// Starting perks: three are offered before each run, one may be taken
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Perk {
+    FreeTower,
+    BonusGold,
+    QuickPicks,
+    LeakForgiven,
+}
+pub const PERK_POOL: [Perk; 4] = [
+    Perk::FreeTower,
+    Perk::BonusGold,
+    Perk::QuickPicks,
+    Perk::LeakForgiven,
+];
+pub const PERK_OFFER_COUNT: usize = 3;
+pub const PERK_BONUS_GOLD: u64 = 5;
+pub const PERK_MINING_BONUS: f64 = 0.2;
+pub const PERK_MINING_SECS: u32 = 120;
+
+impl Perk {
+    pub fn name(self) -> &'static str {
+        match self {
+            Perk::FreeTower => "Free Tower",
+            Perk::BonusGold => "Nest Egg",
+            Perk::QuickPicks => "Quick Picks",
+            Perk::LeakForgiven => "Second Chance",
+        }
+    }
+    pub fn description(self) -> String {
+        match self {
+            Perk::FreeTower => "Your first tower costs no gold".into(),
+            Perk::BonusGold => format!("Start with +{} gold", PERK_BONUS_GOLD),
+            Perk::QuickPicks => format!(
+                "+{:.0}% mining speed for the first {} minutes",
+                PERK_MINING_BONUS * 100.0,
+                PERK_MINING_SECS / 60
+            ),
+            Perk::LeakForgiven => "The first leak costs no life".into(),
+        }
+    }
+}
+
+// The perks offered for a map; seeded so a shared or daily seed offers the same cards
+pub fn offered_perks(seed: u64) -> Vec<Perk> {
+    let mut rng = SeededRng::new(seed ^ 0x5045_524B);
+    let mut pool = PERK_POOL.to_vec();
+    for i in (1..pool.len()).rev() {
+        let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+        pool.swap(i, j);
+    }
+    pool.truncate(PERK_OFFER_COUNT);
+    pool
+}
+
+// Veterancy: lifetime damage needed for each rank, and the damage bonus per rank
+pub const VETERANCY_THRESHOLDS: [u64; 3] = [100, 500, 2000];
+pub const VETERANCY_DAMAGE_BONUS: f64 = 0.05;
+
+// Hardcore siege modifier: enemies next to a tower wear down its durability
+pub const SIEGE_TOWER_HP: f64 = 20.0;
+// Durability lost per second for each adjacent enemy
+pub const SIEGE_CONTACT_DPS: f64 = 2.0;
+pub const SIEGE_REPAIR_GOLD_PER_HP: f64 = 0.25;
+
+fn siege_tower_hp() -> f64 {
+    SIEGE_TOWER_HP
+}
+
+pub const TOWER_COST_SLOW: u64 = 3;
+pub const TOWER_COST_DAMAGE: u64 = 4;
+fn one() -> f64 {
+    1.0
+}
+fn default_tower_cost_slow() -> u64 {
+    TOWER_COST_SLOW
+}
+fn default_tower_cost_damage() -> u64 {
+    TOWER_COST_DAMAGE
+}
+
+// Pacing hints: rules are re-checked this often, and stop for good after this many runs
+pub const HINT_INTERVAL_SECS: u64 = 10;
+pub const HINT_LAST_RUN: u64 = 5;
+pub const HINT_SHORT_LOOP: usize = 12;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HintId {
+    PlaceTower,
+    LengthenMaze,
+    DefendGate,
+    AffordUpgrade,
+}
+
+impl HintId {
+    // Priority order: the first rule that fires wins
+    pub const ALL: [HintId; 4] = [
+        HintId::PlaceTower,
+        HintId::LengthenMaze,
+        HintId::DefendGate,
+        HintId::AffordUpgrade,
+    ];
+
+    pub fn text(self) -> &'static str {
+        match self {
+            HintId::PlaceTower => "Place a tower (T)",
+            HintId::LengthenMaze => "Lengthen your maze by mining around the loop",
+            HintId::DefendGate => "Consider more towers near the gate",
+            HintId::AffordUpgrade => "You can afford an upgrade",
+        }
+    }
+
+    pub fn applies(self, rs: &RunState) -> bool {
+        let can_buy_tower = rs.currencies.gold >= rs.tower_cost || rs.free_towers > 0;
+        match self {
+            HintId::PlaceTower => {
+                feature_allowed(rs.progression_run, Feature::Towers)
+                    && rs.towers.is_empty()
+                    && can_buy_tower
+            }
+            HintId::LengthenMaze => {
+                rs.path_loop.len() < HINT_SHORT_LOOP && rs.currencies.tile_credits > 0
+            }
+            HintId::DefendGate => {
+                feature_allowed(rs.progression_run, Feature::Towers)
+                    && rs.life * 10 < rs.life_max * 3
+                    && rs.currencies.gold >= rs.tower_cost * 2
+            }
+            HintId::AffordUpgrade => {
+                feature_allowed(rs.progression_run, Feature::Upgrades)
+                    && rs
+                        .cheapest_upgrade_cost
+                        .is_some_and(|c| rs.currencies.research >= c)
+            }
+        }
+    }
+}
+
+// Highest-priority rule that fires and hasn't been shown yet this run
+pub fn next_hint(rs: &RunState) -> Option<HintId> {
+    HintId::ALL
+        .into_iter()
+        .find(|h| !rs.hints_shown.contains(h) && h.applies(rs))
+}
+
+// Hints are for early runs only; skipping progression counts as experienced
+pub fn hints_active(rs: &RunState) -> bool {
+    rs.hints && rs.progression_run.is_some_and(|n| n <= HINT_LAST_RUN)
+}
+
+// Every HINT_INTERVAL_SECS: drop a hint that no longer applies, then surface the next one
+fn tick_hints(rs: &mut RunState) {
+    if !rs
+        .stats
+        .time_survived_secs
+        .is_multiple_of(HINT_INTERVAL_SECS)
+    {
+        return;
+    }
+    if !hints_active(rs) {
+        rs.hint = None;
+        return;
+    }
+    if rs.hint.is_some_and(|h| !h.applies(rs)) {
+        rs.hint = None;
+    }
+    if rs.hint.is_none() {
+        rs.hint = next_hint(rs);
+        if let Some(h) = rs.hint {
+            rs.hints_shown.push(h);
+        }
+    }
 }
+
 impl Tower {
     pub fn new(
         x: u32,
@@ -235,259 +1365,154 @@ impl Tower {
             TowerKind::Damage => (0.7, base_damage.saturating_mul(2), 1.5),
         };
 
-        // Apply boost-specific range modifiers
-        let boost_range_mul = match boost {
-            Some(BoostKind::Slow) => 0.7, // Cold tiles: -30% range (short-range area denial)
-            Some(BoostKind::Fire) => 1.0, // Fire tiles: normal range
-            Some(BoostKind::Damage) => 1.0, // Poison tiles: normal range
-            Some(BoostKind::Range) => 1.15, // Healing tiles: +15% range (synergizes with healing theme)
-            Some(BoostKind::FireRate) => 1.0, // Fire rate tiles: normal range
-            None => 1.0,                    // No boost: normal range
-        };
+        let (boost_range_mul, boost_range_add, boost_dm, boost_frm) = boost_intrinsics(boost);
 
         Self {
+            id: EntityId::default(),
             x,
             y,
             kind,
-            range: base_range * r_mul * boost_range_mul,
-            damage: kind_damage,
-            fire_rate: fr,
+            range: base_range * r_mul * boost_range_mul + boost_range_add,
+            damage: ((kind_damage as f64 * boost_dm).round() as u32).max(1),
+            fire_rate: fr * boost_frm,
             cooldown_remaining: 0.0,
             boost,
             apply_debuff: None, // Will be set by apply_upgrades_to_run
+            damage_dealt: 0,
+            kills: 0,
+            ignore_exclusion: false,
+            hp: SIEGE_TOWER_HP,
+            max_hp: SIEGE_TOWER_HP,
+            paid: 0,
+            level: 0,
+            target_mode: TargetMode::First,
+            here: TowerTally::default(),
         }
     }
-}
-
-impl RunState {
-    fn create_run_base(
-        gs: GridSize,
-        gold_chance: f64,
-        boost_kinds: &[BoostKind],
-        _boost_freq_weight: f64,
-        cold_freq: f64,
-        poison_freq: f64,
-        healing_freq: f64,
-        fire_freq: f64,
-    ) -> Self {
-        let mut tiles = Vec::with_capacity((gs.width * gs.height) as usize);
-        let mut gold_tile_count = 0u32;
-        for _y in 0..gs.height {
-            for _x in 0..gs.width {
-                let r = js_sys::Math::random();
-                let has_gold = r < gold_chance;
-                if has_gold {
-                    gold_tile_count += 1;
-                }
-
-                // Per-boost-type spawn logic with individual frequency multipliers
-                // Each boost type gets an independent roll (no competition)
-                let boost = if boost_kinds.is_empty() {
-                    None
-                } else {
-                    let base_spawn_chance = 0.12;
-                    let mut candidates = Vec::new();
-
-                    // Check each boost type independently
-                    for &bk in boost_kinds {
-                        let boost_freq = match bk {
-                            BoostKind::Slow => cold_freq,
-                            BoostKind::Damage => poison_freq,
-                            BoostKind::Range => healing_freq,
-                            BoostKind::Fire => fire_freq,
-                            BoostKind::FireRate => 1.0,
-                        };
-                        let chance = (base_spawn_chance * boost_freq).min(0.25);
-                        if js_sys::Math::random() < chance {
-                            candidates.push(bk);
-                        }
-                    }
-
-                    // If multiple succeeded, pick one randomly
-                    if candidates.is_empty() {
-                        None
-                    } else {
-                        let idx =
-                            (js_sys::Math::random() * candidates.len() as f64).floor() as usize;
-                        Some(candidates[idx])
-                    }
-                };
-                tiles.push(Tile {
-                    kind: TileKind::Rock { has_gold, boost },
-                    hardness: 3,
-                });
-            }
-        }
-
-        // Ensure minimum gold tiles based on grid size
-        let total_tiles = (gs.width * gs.height) as u32;
-        let min_gold_tiles = (total_tiles as f64 * 0.08).round() as u32; // At least 8% of tiles
-        if gold_tile_count < min_gold_tiles {
-            let needed = min_gold_tiles - gold_tile_count;
-            let mut added = 0u32;
-            for idx in 0..tiles.len() {
-                if added >= needed {
-                    break;
-                }
-                if let TileKind::Rock {
-                    has_gold: false,
-                    boost,
-                } = tiles[idx].kind
-                {
-                    tiles[idx].kind = TileKind::Rock {
-                        has_gold: true,
-                        boost,
-                    };
-                    added += 1;
-                }
-            }
-        }
-        // carve start cluster centrally with corridor similar to original implementation
-        let sx = (gs.width / 2) as i32;
-        let sy = (gs.height / 2) as i32; // center
-        let orient = (js_sys::Math::random() * 4.0).floor() as i32;
-        let (dx1, dy1, adir) = match orient {
-            0 => (1, 0, ArrowDir::Right),
-            1 => (0, 1, ArrowDir::Down),
-            2 => (-1, 0, ArrowDir::Left),
-            _ => (0, -1, ArrowDir::Up),
+    // (range, damage) multipliers from the tower's own level
+    pub fn level_mults(&self) -> (f64, f64) {
+        tower_level_mults(self.level)
+    }
+    pub fn rank(&self) -> u32 {
+        VETERANCY_THRESHOLDS
+            .iter()
+            .filter(|&&t| self.damage_dealt >= t)
+            .count() as u32
+    }
+    // Each rank multiplies damage by (1 + bonus), so ranks compound
+    pub fn veterancy_mult(&self) -> f64 {
+        (1.0 + VETERANCY_DAMAGE_BONUS).powi(self.rank() as i32)
+    }
+    // Per-shot damage before crits and prestige. Order: upgrade/boost-derived `damage` (set by
+    // apply_upgrades_to_run) x level multiplier x veterancy multiplier; RunState::shot_damage
+    // adds the prestige multiplier, then crit is rolled on top.
+    pub fn shot_damage(&self) -> f64 {
+        self.damage as f64 * self.level_mults().1 * self.veterancy_mult()
+    }
+    pub fn may_target(&self, e: &Enemy, no_target: &TileBitset) -> bool {
+        self.ignore_exclusion || !no_target.contains_point(e.x, e.y)
+    }
+    // Index of the enemy this tower shoots next, per its target mode. First and Last go by
+    // progress round the lap in `lap`.
+    pub fn pick_target(
+        &self,
+        enemies: &[Enemy],
+        no_target: &TileBitset,
+        lap: LapFrame,
+    ) -> Option<usize> {
+        let (cx, cy) = (self.x as f64 + 0.5, self.y as f64 + 0.5);
+        let dist2 = |e: &Enemy| (e.x - cx).powi(2) + (e.y - cy).powi(2);
+        // Higher is better
+        let score = |e: &Enemy| match self.target_mode {
+            TargetMode::First => lap.progress(e.loop_dist),
+            TargetMode::Last => -lap.progress(e.loop_dist),
+            TargetMode::Closest => -dist2(e),
+            TargetMode::Strongest => e.hp as f64,
         };
-        let set_kind = |tiles: &mut Vec<Tile>, x: i32, y: i32, kind: TileKind| {
-            if x >= 0 && y >= 0 && (x as u32) < gs.width && (y as u32) < gs.height {
-                let idx = (y as u32 * gs.width + x as u32) as usize;
-                tiles[idx].kind = kind;
-                tiles[idx].hardness = 255;
+        let mut best: Option<(usize, f64)> = None;
+        for (i, e) in enemies.iter().enumerate() {
+            if !self.may_target(e, no_target) || dist2(e) > self.range * self.range {
+                continue;
             }
-        };
-        let make_empty = |tiles: &mut Vec<Tile>, x: i32, y: i32| {
-            if x >= 0 && y >= 0 && (x as u32) < gs.width && (y as u32) < gs.height {
-                let idx = (y as u32 * gs.width + x as u32) as usize;
-                if matches!(tiles[idx].kind, TileKind::Rock { .. } | TileKind::Wall) {
-                    tiles[idx].kind = TileKind::Empty;
-                    tiles[idx].hardness = 1;
-                }
+            let s = score(e);
+            if best.is_none_or(|(_, b)| s > b) {
+                best = Some((i, s));
             }
-        };
-        set_kind(&mut tiles, sx, sy, TileKind::Start);
-        set_kind(
-            &mut tiles,
-            sx + dx1,
-            sy + dy1,
-            TileKind::Direction {
-                dir: adir,
-                role: DirRole::Entrance,
-            },
-        );
-        set_kind(
-            &mut tiles,
-            sx - dx1,
-            sy - dy1,
-            TileKind::Direction {
-                dir: adir,
-                role: DirRole::Exit,
-            },
-        );
-        // indestructibles perpendicular to force single corridor start
-        match adir {
-            ArrowDir::Left | ArrowDir::Right => {
-                set_kind(&mut tiles, sx, sy - 1, TileKind::Indestructible);
-                set_kind(&mut tiles, sx, sy + 1, TileKind::Indestructible);
-            }
-            _ => {
-                set_kind(&mut tiles, sx - 1, sy, TileKind::Indestructible);
-                set_kind(&mut tiles, sx + 1, sy, TileKind::Indestructible);
-            }
-        }
-        // carve short L-shaped corridor outwards from entrance & exit directions
-        make_empty(&mut tiles, sx + 2 * dx1, sy + 2 * dy1);
-        let sign = if js_sys::Math::random() < 0.5 { 1 } else { -1 };
-        let px = -dy1 * sign;
-        let py = dx1 * sign;
-        for k in 1..=3 {
-            make_empty(&mut tiles, sx + 2 * dx1 + k * px, sy + 2 * dy1 + k * py);
-        }
-        for k in 1..=4 {
-            make_empty(
-                &mut tiles,
-                sx + 2 * dx1 + 3 * px - k * dx1,
-                sy + 2 * dy1 + 3 * py - k * dy1,
-            );
-        }
-        for k in 1..=3 {
-            make_empty(
-                &mut tiles,
-                sx - 2 * dx1 + (3 - k) * px,
-                sy - 2 * dy1 + (3 - k) * py,
-            );
         }
-        make_empty(&mut tiles, sx - 2 * dx1, sy - 2 * dy1);
-        // build initial state
-        let mut rs = RunState {
-            grid_size: gs,
-            tiles,
-            currencies: Currencies {
-                gold: 2, // lowered starting gold (was 5)
-                ..Default::default()
-            },
-            stats: RunStats::default(),
-            life: 10, // lowered starting life (was 20)
-            // Slowed baseline mining speed (was 6.0); higher hardness now takes meaningfully longer
-            mining_speed: 1.0,
-            started: false,
-            is_paused: false,
-            path: Vec::new(),
-            path_loop: Vec::new(),
-            loop_cum_lengths: Vec::new(),
-            loop_total_length: 0.0,
-            enemies: Vec::new(),
-            last_enemy_spawn_time_secs: 0.0,
-            version: 0,
-            game_over: false,
-            last_mined_idx: None,
-            sim_time: 0.0,
-            towers: Vec::new(),
-            tower_base_range: 3.5,
-            tower_base_damage: 2,
-            tower_cost: 2,
-            projectiles: Vec::new(),
-            run_id: 0,
-            life_max: 10, // lowered base life max
-            life_regen_per_sec: 0.0,
-            life_regen_accum: 0.0,
-            tower_fire_rate_global: 1.0,
-            crit_chance: 0.0,
-            crit_damage_mult: 1.0,
-            gold_bounty_per_kill: 0,
-            gold_bounty_mul: 1.0,
-            damage_ramp_per_sec: 0.0,
-            damage_numbers: Vec::new(),
-            projectile_speed: 8.0,
-            vampiric_heal_percent: 0.0,
-            mining_gold_mul: 1.0,
-            mining_crit_chance: 0.0,
-            tower_refund_mult: 1.0,
-            starting_gold_applied_level: 0,
-            player_power_level: 0.0,
-            cold_debuff_template: None,
-            poison_debuff_template: None,
-            fire_debuff_template: None,
-            fire_spread_radius: 0.0,
-            freeze_chance: 0.0,
-            healing_tile_heal_per_tick: 0.0,
-            healing_tile_timer: 0.0,
-            projectile_splash_radius: 0.0,
-            splash_explosions: Vec::new(),
-        };
-        rs.path = compute_path(&rs);
-        rs.path_loop = build_loop_path(&rs);
-        update_loop_geometry(&mut rs);
-        rs
+        best.map(|(i, _)| i)
     }
-    pub fn new_basic(gs: GridSize) -> Self {
-        Self::create_run_base(gs, 0.12, &[], 1.0, 1.0, 1.0, 1.0, 1.0)
+    // The kind's own on-hit effect, separate from the boost's `apply_debuff`
+    pub fn kind_debuff(&self) -> Option<Debuff> {
+        (self.kind == TowerKind::Slow).then_some(Debuff {
+            kind: DebuffKind::Slow,
+            remaining: SLOW_TOWER_SECS,
+            strength: SLOW_TOWER_STRENGTH,
+        })
     }
-    pub fn new_with_upgrades(base: GridSize, ups: &UpgradeState) -> Self {
-        let grid = base; // no expansion yet
+}
+
+// -------- Seeded map generation --------
+// splitmix64: the map layout is fully determined by its seed so layouts can be previewed
+#[derive(Clone, Debug)]
+pub struct SeededRng {
+    state: u64,
+}
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+// How the enemy route ends. Loop: Start -> Entrance -> ... -> Exit and around again, each lap
+// costing a life. End: an End tile sits buried in rock away from Start; once mined through to,
+// the route runs Start -> Entrance -> ... -> End and enemies getting there breach (a life each)
+// and are gone. Until then an End run loops as usual.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RouteMode {
+    #[default]
+    Loop,
+    End,
+}
+
+// Map layout only ever rolls SeededRng on the map seed (terrain, gold veins, the End, perk
+// offers), so a seed with the same grid size and upgrade levels replays a map exactly. Rolls
+// during play (crits, mining crits, freeze, Mason spawns, archetypes) stay on util::random: a
+// seed replays the map, not the fight. Mason rebuilds and clutch saves keep their own streams
+// on the run.
+pub fn random_map_seed() -> u64 {
+    (random() * u32::MAX as f64) as u64
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MapGenParams {
+    pub gold_chance: f64,
+    pub boost_kinds: Vec<BoostKind>,
+    pub cold_freq: f64,
+    pub poison_freq: f64,
+    pub healing_freq: f64,
+    pub fire_freq: f64,
+}
+impl MapGenParams {
+    pub fn basic() -> Self {
+        Self {
+            gold_chance: 0.12,
+            boost_kinds: Vec::new(),
+            cold_freq: 1.0,
+            poison_freq: 1.0,
+            healing_freq: 1.0,
+            fire_freq: 1.0,
+        }
+    }
+    pub fn from_upgrades(ups: &UpgradeState) -> Self {
         let gold_chance = (0.12 + 0.05 * ups.level(UpgradeId::GoldTileChance) as f64).min(0.95);
         let mut boosts: Vec<BoostKind> = Vec::new();
         if ups.level(UpgradeId::BoostColdUnlock) > 0 {
@@ -497,36 +1522,570 @@ impl RunState {
             boosts.push(BoostKind::Damage);
         }
         if ups.level(UpgradeId::BoostHealingUnlock) > 0 {
-            boosts.push(BoostKind::Range);
+            boosts.push(BoostKind::Healing);
         }
         if ups.level(UpgradeId::BoostFireUnlock) > 0 {
             boosts.push(BoostKind::Fire);
         }
-        let freq = 1.0
-            + 0.05
-                * (ups.level(UpgradeId::BoostColdFrequency)
-                    + ups.level(UpgradeId::BoostPoisonFrequency)
-                    + ups.level(UpgradeId::BoostHealingFrequency)
-                    + ups.level(UpgradeId::BoostFireFrequency)) as f64;
-
-        // Calculate per-boost-type frequency multipliers
-        let cold_freq = 1.0 + 0.05 * ups.level(UpgradeId::BoostColdFrequency) as f64;
-        let poison_freq = 1.0 + 0.05 * ups.level(UpgradeId::BoostPoisonFrequency) as f64;
-        let healing_freq = 1.0 + 0.05 * ups.level(UpgradeId::BoostHealingFrequency) as f64;
-        let fire_freq = 1.0 + 0.05 * ups.level(UpgradeId::BoostFireFrequency) as f64;
-
-        let mut rs = Self::create_run_base(
-            grid,
+        // Per-boost-type frequency multipliers
+        Self {
             gold_chance,
-            &boosts,
-            freq,
-            cold_freq,
-            poison_freq,
-            healing_freq,
-            fire_freq,
-        );
-        apply_upgrades_to_run(&mut rs, ups);
-        rs
+            boost_kinds: boosts,
+            cold_freq: 1.0 + 0.05 * ups.level(UpgradeId::BoostColdFrequency) as f64,
+            poison_freq: 1.0 + 0.05 * ups.level(UpgradeId::BoostPoisonFrequency) as f64,
+            healing_freq: 1.0 + 0.05 * ups.level(UpgradeId::BoostHealingFrequency) as f64,
+            fire_freq: 1.0 + 0.05 * ups.level(UpgradeId::BoostFireFrequency) as f64,
+        }
+    }
+}
+
+fn generate_tiles(gs: GridSize, params: &MapGenParams, seed: u64) -> Vec<Tile> {
+    let mut rng = SeededRng::new(seed);
+    let mut tiles = Vec::with_capacity((gs.width * gs.height) as usize);
+    for _y in 0..gs.height {
+        for _x in 0..gs.width {
+            // Per-boost-type spawn logic with individual frequency multipliers
+            // Each boost type gets an independent roll (no competition)
+            let boost = if params.boost_kinds.is_empty() {
+                None
+            } else {
+                let base_spawn_chance = 0.12;
+                let mut candidates = Vec::new();
+
+                // Check each boost type independently
+                for &bk in &params.boost_kinds {
+                    let boost_freq = match bk {
+                        BoostKind::Slow => params.cold_freq,
+                        BoostKind::Damage => params.poison_freq,
+                        BoostKind::Healing => params.healing_freq,
+                        BoostKind::Fire => params.fire_freq,
+                        BoostKind::Range | BoostKind::FireRate => 1.0,
+                    };
+                    let chance = (base_spawn_chance * boost_freq).min(0.25);
+                    if rng.next_f64() < chance {
+                        candidates.push(bk);
+                    }
+                }
+
+                // If multiple succeeded, pick one randomly
+                if candidates.is_empty() {
+                    None
+                } else {
+                    let idx = (rng.next_f64() * candidates.len() as f64).floor() as usize;
+                    Some(candidates[idx])
+                }
+            };
+            tiles.push(Tile {
+                kind: TileKind::Rock {
+                    has_gold: false,
+                    boost,
+                },
+                hardness: ROCK_HARDNESS,
+                magma: false,
+            });
+        }
+    }
+
+    let total_tiles = gs.width * gs.height;
+    // carve start cluster centrally with corridor similar to original implementation
+    let sx = (gs.width / 2) as i32;
+    let sy = (gs.height / 2) as i32; // center
+    let orient = (rng.next_f64() * 4.0).floor() as i32;
+    let (dx1, dy1, adir) = match orient {
+        0 => (1, 0, ArrowDir::Right),
+        1 => (0, 1, ArrowDir::Down),
+        2 => (-1, 0, ArrowDir::Left),
+        _ => (0, -1, ArrowDir::Up),
+    };
+    let set_kind = |tiles: &mut Vec<Tile>, x: i32, y: i32, kind: TileKind| {
+        if x >= 0 && y >= 0 && (x as u32) < gs.width && (y as u32) < gs.height {
+            let idx = (y as u32 * gs.width + x as u32) as usize;
+            tiles[idx].kind = kind;
+            tiles[idx].hardness = FIXED_HARDNESS;
+        }
+    };
+    let make_empty = |tiles: &mut Vec<Tile>, x: i32, y: i32| {
+        if x >= 0 && y >= 0 && (x as u32) < gs.width && (y as u32) < gs.height {
+            let idx = (y as u32 * gs.width + x as u32) as usize;
+            if matches!(tiles[idx].kind, TileKind::Rock { .. } | TileKind::Wall) {
+                tiles[idx].kind = TileKind::Empty;
+                tiles[idx].hardness = EMPTY_HARDNESS;
+            }
+        }
+    };
+    set_kind(&mut tiles, sx, sy, TileKind::Start);
+    set_kind(
+        &mut tiles,
+        sx + dx1,
+        sy + dy1,
+        TileKind::Direction {
+            dir: adir,
+            role: DirRole::Entrance,
+        },
+    );
+    set_kind(
+        &mut tiles,
+        sx - dx1,
+        sy - dy1,
+        TileKind::Direction {
+            dir: adir,
+            role: DirRole::Exit,
+        },
+    );
+    // indestructibles perpendicular to force single corridor start
+    match adir {
+        ArrowDir::Left | ArrowDir::Right => {
+            set_kind(&mut tiles, sx, sy - 1, TileKind::Indestructible);
+            set_kind(&mut tiles, sx, sy + 1, TileKind::Indestructible);
+        }
+        _ => {
+            set_kind(&mut tiles, sx - 1, sy, TileKind::Indestructible);
+            set_kind(&mut tiles, sx + 1, sy, TileKind::Indestructible);
+        }
+    }
+    // carve short L-shaped corridor outwards from entrance & exit directions
+    make_empty(&mut tiles, sx + 2 * dx1, sy + 2 * dy1);
+    let sign = if rng.next_f64() < 0.5 { 1 } else { -1 };
+    let px = -dy1 * sign;
+    let py = dx1 * sign;
+    for k in 1..=3 {
+        make_empty(&mut tiles, sx + 2 * dx1 + k * px, sy + 2 * dy1 + k * py);
+    }
+    for k in 1..=4 {
+        make_empty(
+            &mut tiles,
+            sx + 2 * dx1 + 3 * px - k * dx1,
+            sy + 2 * dy1 + 3 * py - k * dy1,
+        );
+    }
+    for k in 1..=3 {
+        make_empty(
+            &mut tiles,
+            sx - 2 * dx1 + (3 - k) * px,
+            sy - 2 * dy1 + (3 - k) * py,
+        );
+    }
+    make_empty(&mut tiles, sx - 2 * dx1, sy - 2 * dy1);
+    // Gold veins go in once the Start cluster is carved, on rock only
+    let rock: Vec<bool> = tiles
+        .iter()
+        .map(|t| matches!(t.kind, TileKind::Rock { .. }))
+        .collect();
+    let veins = gold_veins(gs, params.gold_chance, rng.next_u64(), &rock);
+    for (t, gold) in tiles.iter_mut().zip(veins) {
+        if let TileKind::Rock { has_gold, .. } = &mut t.kind {
+            *has_gold = gold;
+        }
+    }
+    // Magma pockets go under plain rock only, so gold and boosts are never lost to them
+    let pockets = total_tiles.div_ceil(MAGMA_TILES_PER_POCKET);
+    let mut placed = 0;
+    for _ in 0..pockets * 8 {
+        if placed == pockets {
+            break;
+        }
+        let idx = (rng.next_f64() * tiles.len() as f64) as usize;
+        let t = &mut tiles[idx];
+        let plain = t.kind
+            == TileKind::Rock {
+                has_gold: false,
+                boost: None,
+            };
+        if plain && !t.magma {
+            t.magma = true;
+            placed += 1;
+        }
+    }
+    tiles
+}
+
+// End route placement: the End sits about a third of the board's short side from Start (never
+// closer than END_MIN_DISTANCE) with only rock around it, so it has to be mined toward
+const END_MIN_DISTANCE: f64 = 4.0;
+const END_PLACEMENT_TRIES: usize = 32;
+// Keeps the End's rolls apart from the terrain's, so the same seed gives the same map either way
+const END_SEED_SALT: u64 = 0x454e_445f_5449_4c45;
+
+fn place_end(tiles: &mut [Tile], gs: GridSize, seed: u64) {
+    let mut rng = SeededRng::new(seed ^ END_SEED_SALT);
+    let (sx, sy) = ((gs.width / 2) as i32, (gs.height / 2) as i32);
+    let reach = (gs.width.min(gs.height) as f64 / 3.0).max(END_MIN_DISTANCE);
+    let buried = |tiles: &[Tile], x: i32, y: i32| {
+        (-1..=1).all(|dy| {
+            (-1..=1).all(|dx| {
+                let (nx, ny) = (x + dx, y + dy);
+                nx >= 0
+                    && ny >= 0
+                    && (nx as u32) < gs.width
+                    && (ny as u32) < gs.height
+                    && matches!(
+                        tiles[(ny as u32 * gs.width + nx as u32) as usize].kind,
+                        TileKind::Rock { .. }
+                    )
+            })
+        })
+    };
+    let mut spot = None;
+    for _ in 0..END_PLACEMENT_TRIES {
+        let a = rng.next_f64() * std::f64::consts::TAU;
+        let x = sx + (a.cos() * reach).round() as i32;
+        let y = sy + (a.sin() * reach).round() as i32;
+        if buried(tiles, x, y) {
+            spot = Some((x, y));
+            break;
+        }
+    }
+    // Boards too small for the ring: the buried rock farthest from Start
+    let spot = spot.or_else(|| {
+        (0..gs.height as i32)
+            .flat_map(|y| (0..gs.width as i32).map(move |x| (x, y)))
+            .filter(|&(x, y)| buried(tiles, x, y))
+            .max_by_key(|&(x, y)| (x - sx).abs() + (y - sy).abs())
+    });
+    if let Some((x, y)) = spot {
+        tiles[(y as u32 * gs.width + x as u32) as usize] = Tile {
+            kind: TileKind::End,
+            hardness: FIXED_HARDNESS,
+            magma: false,
+        };
+    }
+}
+
+// Switches a run between the routes before it starts, burying or removing its End
+fn set_route_mode(rs: &mut RunState, mode: RouteMode) {
+    if rs.route_mode == mode {
+        return;
+    }
+    rs.route_mode = mode;
+    let gs = rs.grid_size;
+    match mode {
+        RouteMode::End => place_end(&mut rs.tiles, gs, rs.map_seed),
+        RouteMode::Loop => {
+            for t in rs.tiles.iter_mut().filter(|t| t.kind == TileKind::End) {
+                *t = Tile {
+                    kind: TileKind::Rock {
+                        has_gold: false,
+                        boost: None,
+                    },
+                    hardness: ROCK_HARDNESS,
+                    magma: false,
+                };
+            }
+        }
+    }
+    rs.path = compute_path(rs).into();
+    rs.path_loop = build_loop_path(rs).into();
+    update_loop_geometry(rs);
+}
+
+// Open tile under world point (x, y) is lava
+fn lava_under(tiles: &[Tile], gs: GridSize, x: f64, y: f64) -> bool {
+    x >= 0.0
+        && y >= 0.0
+        && (x as u32) < gs.width
+        && (y as u32) < gs.height
+        && tiles[(y as u32 * gs.width + x as u32) as usize].is_lava()
+}
+
+// Towers can't stand orthogonally next to lava; diagonal neighbours are fine
+pub fn too_hot_for_tower(rs: &RunState, x: u32, y: u32) -> bool {
+    let gs = rs.grid_size;
+    [(-1, 0), (1, 0), (0, -1), (0, 1)].iter().any(|&(dx, dy)| {
+        let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+        nx >= 0
+            && ny >= 0
+            && (nx as u32) < gs.width
+            && (ny as u32) < gs.height
+            && rs.tiles[(ny as u32 * gs.width + nx as u32) as usize].is_lava()
+    })
+}
+
+// -------- Map candidates (pre-run layout picker) --------
+// Variant order is thumbnail priority when a cell covers several tiles
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ThumbCell {
+    Rock,
+    Boost,
+    Gold,
+    Empty,
+    Indestructible,
+    Exit,
+    Entrance,
+    Start,
+}
+impl ThumbCell {
+    fn from_kind(kind: &TileKind) -> Self {
+        match kind {
+            TileKind::Rock { boost: Some(_), .. } => ThumbCell::Boost,
+            TileKind::Rock { has_gold: true, .. } => ThumbCell::Gold,
+            TileKind::Rock { .. } | TileKind::Wall => ThumbCell::Rock,
+            TileKind::Empty | TileKind::End => ThumbCell::Empty,
+            TileKind::Indestructible => ThumbCell::Indestructible,
+            TileKind::Direction {
+                role: DirRole::Exit,
+                ..
+            } => ThumbCell::Exit,
+            TileKind::Direction {
+                role: DirRole::Entrance,
+                ..
+            } => ThumbCell::Entrance,
+            TileKind::Start => ThumbCell::Start,
+        }
+    }
+}
+#[derive(Clone, Debug, PartialEq)]
+pub struct MapSummary {
+    pub gold_count: u32,
+    pub boost_count: u32,
+    pub orientation: Option<ArrowDir>,
+}
+pub fn summarize_map(tiles: &[Tile]) -> MapSummary {
+    let mut summary = MapSummary {
+        gold_count: 0,
+        boost_count: 0,
+        orientation: None,
+    };
+    for t in tiles {
+        match t.kind {
+            TileKind::Rock { has_gold, boost } => {
+                if has_gold {
+                    summary.gold_count += 1;
+                }
+                if boost.is_some() {
+                    summary.boost_count += 1;
+                }
+            }
+            TileKind::Direction {
+                dir,
+                role: DirRole::Entrance,
+            } => summary.orientation = Some(dir),
+            _ => {}
+        }
+    }
+    summary
+}
+#[derive(Clone, Debug, PartialEq)]
+pub struct MapCandidate {
+    pub seed: u64,
+    pub summary: MapSummary,
+    // Downsampled layout: each cell covers thumb_stride x thumb_stride tiles
+    pub thumb_stride: u32,
+    pub thumb_width: u32,
+    pub thumb_height: u32,
+    pub thumb: Vec<ThumbCell>,
+}
+// Wider grids sample every other tile for thumbnails
+const THUMB_FULL_RES_MAX: u32 = 40;
+// Tiles only (no pathfinding), so generating several large candidates stays cheap
+pub fn generate_map_candidates(
+    gs: GridSize,
+    ups: &UpgradeState,
+    first_seed: u64,
+    count: usize,
+) -> Vec<MapCandidate> {
+    let params = MapGenParams::from_upgrades(ups);
+    let stride = if gs.width.max(gs.height) > THUMB_FULL_RES_MAX {
+        2
+    } else {
+        1
+    };
+    let tw = gs.width.div_ceil(stride);
+    let th = gs.height.div_ceil(stride);
+    (0..count as u64)
+        .map(|i| {
+            let seed = first_seed.wrapping_add(i);
+            let tiles = generate_tiles(gs, &params, seed);
+            let mut thumb = vec![ThumbCell::Rock; (tw * th) as usize];
+            for y in 0..gs.height {
+                for x in 0..gs.width {
+                    let cell = ThumbCell::from_kind(&tiles[(y * gs.width + x) as usize].kind);
+                    let ti = ((y / stride) * tw + x / stride) as usize;
+                    // Keep the most informative tile of each block (start corridor first)
+                    if cell > thumb[ti] {
+                        thumb[ti] = cell;
+                    }
+                }
+            }
+            MapCandidate {
+                seed,
+                summary: summarize_map(&tiles),
+                thumb_stride: stride,
+                thumb_width: tw,
+                thumb_height: th,
+                thumb,
+            }
+        })
+        .collect()
+}
+
+impl RunState {
+    fn create_run_base(gs: GridSize, params: &MapGenParams, seed: u64, mode: RouteMode) -> Self {
+        let mut tiles = generate_tiles(gs, params, seed);
+        if mode == RouteMode::End {
+            place_end(&mut tiles, gs, seed);
+        }
+        // build initial state
+        let mut rs = RunState {
+            grid_size: gs,
+            tiles: tiles.into(),
+            currencies: Currencies {
+                gold: 2, // lowered starting gold (was 5)
+                // Enough for the opening tower; the rest come from mining
+                tile_credits: TOWER_TILE_CREDITS,
+                ..Default::default()
+            },
+            stats: RunStats::default(),
+            life: 10, // lowered starting life (was 20)
+            // Slowed baseline mining speed (was 6.0); higher hardness now takes meaningfully longer
+            mining_speed: 1.0,
+            started: false,
+            is_paused: false,
+            sim_speed: 1.0,
+            path: Shared::default(),
+            path_loop: Shared::default(),
+            loop_cum_lengths: Shared::default(),
+            loop_total_length: 0.0,
+            loop_entry_dist: 0.0,
+            enemies: Vec::new(),
+            last_enemy_spawn_time_secs: 0.0,
+            version: 0,
+            game_over: false,
+            last_mined_idx: None,
+            sim_time: 0.0,
+            towers: Vec::new(),
+            tower_base_range: 3.5,
+            tower_base_damage: 2,
+            prestige_damage_mult: 1.0,
+            tower_cost: 2,
+            tower_cost_slow: TOWER_COST_SLOW,
+            tower_cost_damage: TOWER_COST_DAMAGE,
+            projectiles: Vec::new(),
+            run_id: 0,
+            life_max: 10, // lowered base life max
+            life_regen_per_sec: 0.0,
+            life_regen_accum: 0.0,
+            tower_fire_rate_global: 1.0,
+            crit_chance: 0.0,
+            crit_damage_mult: 1.0,
+            gold_bounty_per_kill: 0,
+            gold_bounty_mul: 1.0,
+            damage_ramp_per_sec: 0.0,
+            damage_numbers: Vec::new(),
+            floating_texts: Vec::new(),
+            projectile_speed: 8.0,
+            vampiric_heal_percent: 0.0,
+            mining_gold_mul: 1.0,
+            mining_crit_chance: 0.0,
+            tower_refund_percent: 100,
+            starting_gold_applied_level: 0,
+            player_power_level: 0.0,
+            cold_debuff_template: None,
+            poison_debuff_template: None,
+            fire_debuff_template: None,
+            fire_spread_radius: 0.0,
+            poison_spread: false,
+            freeze_chance: 0.0,
+            cold_slow_multiplier: 1.0,
+            cold_slow_duration: 0.0,
+            healing_tile_heal_per_tick: 0.0,
+            healing_tile_timer: 0.0,
+            healing_aura_radius: 0.0,
+            heal_accum: 0.0,
+            shield: 0,
+            shield_max: 0,
+            last_heal_pulse_at: None,
+            projectile_splash_radius: 0.0,
+            projectile_bounces: 0,
+            bank_interest_rate: 0.0,
+            bank_interest_cap: 0,
+            auto_mine_period: 0,
+            auto_mine_cooldown: 0,
+            last_interest: 0,
+            clutch_rng: seed,
+            mason_rng: seed ^ MASON_SEED_SALT,
+            splash_explosions: Vec::new(),
+            map_seed: seed,
+            fresh_rock: Vec::new(),
+            progression_run: None,
+            locked_notice: None,
+            no_target: TileBitset::new(gs.width, gs.height),
+            clean_streak: 0,
+            clean_streak_secs: 0,
+            research_carry: 0.0,
+            research_progress: 0.0,
+            streak_notice: None,
+            structures: Vec::new(),
+            projectile_culls: ProjectileCulls::default(),
+            perk: None,
+            free_towers: 0,
+            perk_mining_secs: 0,
+            leak_shield: false,
+            last_life_loss_at: 0.0,
+            siege: false,
+            siege_notice: None,
+            route_mode: mode,
+            hints: false,
+            hint: None,
+            hints_shown: Vec::new(),
+            cheapest_upgrade_cost: None,
+            heat: HeatGrid::new(gs),
+            low_life: false,
+            scenario: None,
+            entity_ids: EntityIds::default(),
+            tower_history: BTreeMap::new(),
+            path_job: None,
+            manual_collection: false,
+            pickups: Vec::new(),
+            debug_allowed: false,
+            last_wall: None,
+            damage_window: DamageWindow::default(),
+            difficulty: DifficultyCurve::default(),
+        };
+        rs.path = compute_path(&rs).into();
+        rs.path_loop = build_loop_path(&rs).into();
+        update_loop_geometry(&mut rs);
+        rs
+    }
+    pub fn new_basic(gs: GridSize) -> Self {
+        Self::create_run_base(
+            gs,
+            &MapGenParams::basic(),
+            random_map_seed(),
+            RouteMode::Loop,
+        )
+    }
+    pub fn new_with_upgrades(base: GridSize, ups: &UpgradeState, mode: RouteMode) -> Self {
+        Self::new_with_route(base, ups, random_map_seed(), mode)
+    }
+    pub fn new_with_upgrades_seeded(base: GridSize, ups: &UpgradeState, seed: u64) -> Self {
+        Self::new_with_route(base, ups, seed, RouteMode::Loop)
+    }
+    pub fn new_with_route(base: GridSize, ups: &UpgradeState, seed: u64, mode: RouteMode) -> Self {
+        let grid = base; // no expansion yet
+        let mut rs = Self::create_run_base(grid, &MapGenParams::from_upgrades(ups), seed, mode);
+        apply_upgrades_to_run(&mut rs, ups);
+        rs
+    }
+    // Scenario start: its layout and purse on base (un-upgraded) stats
+    pub fn from_scenario(id: ScenarioId) -> Self {
+        let def = id.def();
+        let (gs, tiles) = tiles_from_rows(def.rows);
+        let mut rs = Self::from_tiles(gs, tiles);
+        apply_upgrades_to_run(&mut rs, &UpgradeState::default());
+        rs.currencies.gold = def.gold;
+        rs.life_max = def.life;
+        rs.life = def.life;
+        rs.scenario = Some(ScenarioRun { id, outcome: None });
+        rs
+    }
+    // Terrain-only state for the analysis worker, which is sent tiles rather than the live run
+    pub fn from_tiles(gs: GridSize, tiles: Vec<Tile>) -> Self {
+        let mut rs = Self::create_run_base(gs, &MapGenParams::basic(), 0, RouteMode::Loop);
+        rs.tiles = tiles.into();
+        rs.path = compute_path(&rs).into();
+        rs.path_loop = build_loop_path(&rs).into();
+        update_loop_geometry(&mut rs);
+        rs
     }
 }
 
@@ -550,139 +2109,173 @@ fn find_entrance_exit(rs: &RunState) -> Option<((i32, i32, ArrowDir), (i32, i32,
         _ => None,
     }
 }
-fn a_star(rs: &RunState, start: (i32, i32), goal: (i32, i32)) -> Vec<Position> {
-    use std::cmp::Ordering;
-    use std::collections::{BinaryHeap, HashMap};
-    let (sx, sy) = start;
-    let (gx, gy) = goal;
+fn find_end(rs: &RunState) -> Option<Position> {
+    let i = rs.tiles.iter().position(|t| t.kind == TileKind::End)?;
+    let w = rs.grid_size.width;
+    Some(Position {
+        x: i as u32 % w,
+        y: i as u32 / w,
+    })
+}
+// Start and goal candidates are the open tiles beside the entrance and exit markers, or beside
+// the End once anything next to it is open. None when the board has no entrance/exit pair or
+// nothing open next to one of them.
+fn path_job(rs: &RunState) -> Option<PathJob> {
+    path_job_walled(rs, &[])
+}
+// path_job on the board with the tiles at `walls` taken as walled, so a preview can search a
+// hypothetical board without copying the run
+fn path_job_walled(rs: &RunState, walls: &[usize]) -> Option<PathJob> {
+    let ((ex, ey, _), (mut xx, mut xy, _)) = find_entrance_exit(rs)?;
     let gs = rs.grid_size;
-    let inb = |x: i32, y: i32| x >= 0 && y >= 0 && (x as u32) < gs.width && (y as u32) < gs.height;
-    if !inb(sx, sy) || !inb(gx, gy) {
-        return vec![];
-    }
-    let idx = |x: i32, y: i32| (y as u32 * gs.width + x as u32) as usize;
-    if !matches!(rs.tiles[idx(sx, sy)].kind, TileKind::Empty)
-        || !matches!(rs.tiles[idx(gx, gy)].kind, TileKind::Empty)
-    {
-        return vec![];
-    }
-    #[derive(Copy, Clone, Eq, PartialEq)]
-    struct Node {
-        f: u32,
-        idx: usize,
-    }
-    impl Ord for Node {
-        fn cmp(&self, o: &Self) -> Ordering {
-            o.f.cmp(&self.f).then_with(|| self.idx.cmp(&o.idx))
+    let empty = |i: usize| matches!(rs.tiles[i].kind, TileKind::Empty) && !walls.contains(&i);
+    let open = |x: i32, y: i32| {
+        (x >= 0 && y >= 0 && (x as u32) < gs.width && (y as u32) < gs.height)
+            .then_some((x as u32, y as u32))
+            .filter(|&(x, y)| empty((y * gs.width + x) as usize))
+    };
+    let dirs = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+    let starts: Vec<_> = dirs
+        .iter()
+        .filter_map(|(dx, dy)| open(ex + dx, ey + dy))
+        .collect();
+    if let Some(end) = find_end(rs) {
+        let (x, y) = (end.x as i32, end.y as i32);
+        if dirs.iter().any(|(dx, dy)| open(x + dx, y + dy).is_some()) {
+            (xx, xy) = (x, y);
         }
     }
-    impl PartialOrd for Node {
-        fn partial_cmp(&self, o: &Self) -> Option<Ordering> {
-            Some(self.cmp(o))
-        }
+    let goals: Vec<_> = dirs
+        .iter()
+        .filter_map(|(dx, dy)| open(xx + dx, xy + dy))
+        .collect();
+    if starts.is_empty() || goals.is_empty() {
+        return None;
     }
-    let mut open = BinaryHeap::new();
-    let mut g = HashMap::new();
-    let mut parent = vec![None; (gs.width * gs.height) as usize];
-    let h = |x: i32, y: i32| ((x - gx).abs() + (y - gy).abs()) as u32;
-    let sidx = idx(sx, sy);
-    let gidx = idx(gx, gy);
-    g.insert(sidx, 0u32);
-    open.push(Node {
-        f: h(sx, sy),
-        idx: sidx,
-    });
-    let dirs = [(1, 0), (-1, 0), (0, 1), (0, -1)];
-    while let Some(Node { idx: ci, .. }) = open.pop() {
-        if ci == gidx {
-            break;
-        }
-        let cx = (ci as u32 % gs.width) as i32;
-        let cy = (ci as u32 / gs.width) as i32;
-        let g_here = *g.get(&ci).unwrap();
-        for (dx, dy) in dirs {
-            let nx = cx + dx;
-            let ny = cy + dy;
-            if !inb(nx, ny) {
-                continue;
-            }
-            let ni = idx(nx, ny);
-            if !matches!(rs.tiles[ni].kind, TileKind::Empty) {
-                continue;
+    let walkable = (0..rs.tiles.len()).map(empty).collect();
+    // The current path (if any) biases ties so the route doesn't flip between equals
+    let grid = SearchGrid::new(gs, walkable, &rs.path);
+    Some(PathJob::new(grid, &starts, &goals))
+}
+pub fn compute_path(rs: &RunState) -> Vec<Position> {
+    path_job(rs).map(PathJob::run).unwrap_or_default()
+}
+fn install_path(rs: &mut RunState, path: Vec<Position>) {
+    rs.path = path.into();
+    rs.path_loop = build_loop_path(rs).into();
+    update_loop_geometry(rs);
+    remap_enemies_onto_loop(rs);
+    rs.path_job = None;
+}
+// Mining only opens tiles, so the current path stays walkable while a large board searches for
+// the new one over the next SimTicks. A search still running is replaced by one that also sees
+// this tile.
+// Clears a mined Rock or Wall: credits, gold, stats and the path follow
+fn complete_mining(rs: &mut RunState, idx: usize) {
+    rs.last_mined_idx = Some(idx);
+    let (mx, my) = (
+        idx as u32 % rs.grid_size.width,
+        idx as u32 / rs.grid_size.width,
+    );
+    rs.structures.retain(|s| (s.x, s.y) != (mx, my));
+    match rs.tiles[idx].kind {
+        TileKind::Rock { has_gold, .. } => {
+            rs.tiles[idx].kind = TileKind::Empty;
+            rs.tiles[idx].hardness = EMPTY_HARDNESS;
+            rs.stats.blocks_mined = rs.stats.blocks_mined.saturating_add(1);
+            rs.currencies.tile_credits = rs.currencies.tile_credits.saturating_add(1);
+            if has_gold {
+                let mut g = 1.0 * rs.mining_gold_mul;
+                let is_mining_crit =
+                    rs.mining_crit_chance > 0.0 && random() < rs.mining_crit_chance;
+                if is_mining_crit {
+                    g *= 2.0;
+                }
+                let gold_earned = g.round() as u64;
+                earn_gold(rs, gold_earned);
+                // Show floating gold at mined tile
+                let tx = (idx as u32 % rs.grid_size.width) as f64 + 0.5;
+                let ty = (idx as u32 / rs.grid_size.width) as f64 + 0.5;
+                let color = if is_mining_crit {
+                    GOLD_CRIT_TEXT_COLOR
+                } else {
+                    GOLD_TEXT_COLOR
+                };
+                let text = format!("+{}g", gold_earned);
+                float_text(rs, tx, ty, text, color, is_mining_crit);
             }
-            let tentative = g_here + 1;
-            if tentative < *g.get(&ni).unwrap_or(&u32::MAX) {
-                g.insert(ni, tentative);
-                parent[ni] = Some(ci);
-                let f = tentative + h(nx, ny);
-                open.push(Node { f, idx: ni });
+            if !opens_dead_end(rs, idx) {
+                recompute_path_after_mining(rs);
             }
         }
-    }
-    if parent[gidx].is_none() && sidx != gidx {
-        return vec![];
-    }
-    let mut rev = Vec::new();
-    let mut cur = Some(gidx);
-    while let Some(i) = cur {
-        rev.push(i);
-        if i == sidx {
-            break;
+        TileKind::Wall => {
+            rs.tiles[idx].kind = TileKind::Empty;
+            rs.tiles[idx].hardness = EMPTY_HARDNESS;
+            rs.currencies.tile_credits = rs.currencies.tile_credits.saturating_add(1);
+            if !opens_dead_end(rs, idx) {
+                recompute_path_after_mining(rs);
+            }
         }
-        cur = parent[i];
+        _ => {}
     }
-    rev.reverse();
-    rev.into_iter()
-        .map(|i| Position {
-            x: (i as u32 % gs.width) as u32,
-            y: (i as u32 / gs.width) as u32,
-        })
-        .collect()
 }
-pub fn compute_path(rs: &RunState) -> Vec<Position> {
-    let Some(((ex, ey, _), (xx, xy, _))) = find_entrance_exit(rs) else {
-        return vec![];
-    }; // neighbors of entrance/exit dir tiles
-    let mut starts = Vec::new();
-    let mut goals = Vec::new();
+// Tile `idx` is a Rock or Wall the player can reach on the board as it stands. A structure on
+// it goes down with the rock, so unlike state::mining::can_mine that doesn't stop it.
+fn minable_now(rs: &RunState, idx: usize) -> bool {
+    matches!(rs.tiles[idx].kind, TileKind::Rock { .. } | TileKind::Wall)
+        && crate::state::compute_interactable_mask(rs)[idx]
+}
+// The just-opened tile `idx` is a dead end: at most one open neighbour, and no Start, marker or
+// End beside it to add a start or goal. No route runs through a dead end, and the searches order
+// their nodes totally, so everything else is expanded as before: a settled path (no search
+// pending) comes out of a recompute unchanged, loop bridges included.
+fn opens_dead_end(rs: &RunState, idx: usize) -> bool {
+    if rs.path_job.is_some() {
+        return false;
+    }
+    let gs = rs.grid_size;
+    let w = gs.width as i32;
+    let (x, y) = (idx as i32 % w, idx as i32 / w);
+    let mut open = 0;
     for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
-        let sx = ex + dx;
-        let sy = ey + dy;
-        let gx = xx + dx;
-        let gy = xy + dy;
-        let inb = |x: i32, y: i32| {
-            x >= 0 && y >= 0 && (x as u32) < rs.grid_size.width && (y as u32) < rs.grid_size.height
-        };
-        if inb(sx, sy) {
-            let idx = (sy as u32 * rs.grid_size.width + sx as u32) as usize;
-            if matches!(rs.tiles[idx].kind, TileKind::Empty) {
-                starts.push((sx, sy));
-            }
+        let (nx, ny) = (x + dx, y + dy);
+        if nx < 0 || ny < 0 || nx as u32 >= gs.width || ny as u32 >= gs.height {
+            continue;
         }
-        if inb(gx, gy) {
-            let idx = (gy as u32 * rs.grid_size.width + gx as u32) as usize;
-            if matches!(rs.tiles[idx].kind, TileKind::Empty) {
-                goals.push((gx, gy));
-            }
+        match rs.tiles[(ny as u32 * gs.width + nx as u32) as usize].kind {
+            TileKind::Empty => open += 1,
+            TileKind::Rock { .. } | TileKind::Wall | TileKind::Indestructible => {}
+            TileKind::Start | TileKind::Direction { .. } | TileKind::End => return false,
         }
     }
-    if starts.is_empty() || goals.is_empty() {
-        return vec![];
+    open <= 1
+}
+fn recompute_path_after_mining(rs: &mut RunState) {
+    if rs.tiles.len() < PATH_CHUNK_MIN_TILES {
+        install_path(rs, compute_path(rs));
+        return;
     }
-    let mut best: Option<Vec<Position>> = None;
-    for s in &starts {
-        for g in &goals {
-            let p = a_star(rs, *s, *g);
-            if p.len() > 1 {
-                if best.as_ref().map(|b| p.len() < b.len()).unwrap_or(true) {
-                    best = Some(p);
-                }
-            }
-        }
+    match path_job(rs) {
+        Some(job) => rs.path_job = Some(PendingPath::new(job)),
+        None => install_path(rs, Vec::new()),
+    }
+}
+// One SimTick's share of a pending search, installing the path when it completes. False when
+// nothing was pending.
+fn advance_path_job(rs: &mut RunState) -> bool {
+    let Some(job) = rs.path_job.clone() else {
+        return false;
+    };
+    if job.advance(PATH_EXPANSIONS_PER_TICK) {
+        install_path(rs, job.result());
     }
-    best.unwrap_or_default()
+    true
 }
 fn build_loop_path(rs: &RunState) -> Vec<Position> {
+    loop_path_walled(rs, &rs.path, &[])
+}
+// build_loop_path for `path` on the board with the tiles at `walls` taken as walled
+fn loop_path_walled(rs: &RunState, path: &[Position], walls: &[usize]) -> Vec<Position> {
     let mut start = None;
     let mut ent = None;
     let mut exit = None;
@@ -706,12 +2299,17 @@ fn build_loop_path(rs: &RunState) -> Vec<Position> {
     let (Some(s), Some(en), Some(ex)) = (start, ent, exit) else {
         return vec![];
     };
+    // A path that was searched out to the End finishes there instead of at the Exit
+    let ex = match (find_end(rs), path.last()) {
+        (Some(end), Some(p)) if p.x.abs_diff(end.x) + p.y.abs_diff(end.y) == 1 => end,
+        _ => ex,
+    };
     let mut nodes = Vec::new();
     nodes.push(s);
     if nodes.last() != Some(&en) {
         nodes.push(en);
     }
-    for p in &rs.path {
+    for p in path {
         if *p != s && *p != en && *p != ex {
             nodes.push(*p);
         }
@@ -725,11 +2323,62 @@ fn build_loop_path(rs: &RunState) -> Vec<Position> {
             clean.push(n);
         }
     }
-    clean
+    let closes = rs.tiles[(ex.y * rs.grid_size.width + ex.x) as usize].kind != TileKind::End;
+    join_loop(rs, &clean, closes, walls).unwrap_or_default()
+}
+// The path should begin beside the Entrance and end beside the Exit, but nothing about the
+// concatenation above guarantees it. Each gap between consecutive nodes (and on the leg closing
+// back to Start) is bridged over open tiles; None when one can't be, since a loop cutting through
+// rock would have enemies walking through it. No loop means no spawns until there is one again.
+fn join_loop(
+    rs: &RunState,
+    nodes: &[Position],
+    closes: bool,
+    walls: &[usize],
+) -> Option<Vec<Position>> {
+    let mut joined: Vec<Position> = Vec::with_capacity(nodes.len());
+    for &n in nodes {
+        let gap_from = joined.last().copied().filter(|&prev| !one_step(prev, n));
+        if let Some(prev) = gap_from {
+            joined.extend(bridge(rs, prev, n, walls)?);
+        }
+        joined.push(n);
+    }
+    let (first, last) = (*joined.first()?, *joined.last()?);
+    if closes && !one_step(last, first) {
+        joined.extend(bridge(rs, last, first, walls)?);
+    }
+    Some(joined)
+}
+fn one_step(a: Position, b: Position) -> bool {
+    a.x.abs_diff(b.x) + a.y.abs_diff(b.y) == 1
+}
+// Tiles strictly between `from` and `to` on a shortest walk over the tiles enemies walk: Empty
+// ones not in `walls`, and the Start and markers the loop runs through
+fn bridge(rs: &RunState, from: Position, to: Position, walls: &[usize]) -> Option<Vec<Position>> {
+    let w = rs.grid_size.width;
+    let ends = [(from.y * w + from.x) as usize, (to.y * w + to.x) as usize];
+    let walkable = rs
+        .tiles
+        .iter()
+        .enumerate()
+        .map(|(i, t)| {
+            let open = match t.kind {
+                TileKind::Empty => !walls.contains(&i),
+                TileKind::Start | TileKind::Direction { .. } => true,
+                _ => false,
+            };
+            open || ends.contains(&i)
+        })
+        .collect();
+    let grid = SearchGrid::new(rs.grid_size, walkable, &[]);
+    let route = PathJob::new(grid, &[(from.x, from.y)], &[(to.x, to.y)]).run();
+    (route.len() > 2).then(|| route[1..route.len() - 1].to_vec())
 }
 fn update_loop_geometry(rs: &mut RunState) {
     rs.loop_cum_lengths.clear();
     rs.loop_total_length = 0.0;
+    rs.loop_entry_dist = 0.0;
     if rs.path_loop.len() < 2 {
         return;
     }
@@ -744,41 +2393,335 @@ fn update_loop_geometry(rs: &mut RunState) {
         acc += d;
         rs.loop_cum_lengths.push(acc);
     }
-    let first = rs.path_loop[0];
-    let last = *rs.path_loop.last().unwrap();
+    rs.loop_entry_dist = loop_entry_index(rs).map_or(0.0, |i| rs.loop_cum_lengths[i]);
+    rs.loop_total_length = acc + closing_length(rs, &rs.path_loop);
+}
+// The leg from the route's last node back to its first: none for an open route, which stops at
+// its End, and the closing step of a loop otherwise
+fn closing_length(rs: &RunState, nodes: &[Position]) -> f64 {
+    let (Some(first), Some(last)) = (nodes.first(), nodes.last()) else {
+        return 0.0;
+    };
+    if rs.tiles[(last.y * rs.grid_size.width + last.x) as usize].kind == TileKind::End {
+        return 0.0;
+    }
     let dx = first.x as f64 - last.x as f64;
     let dy = first.y as f64 - last.y as f64;
-    rs.loop_total_length = acc + (dx * dx + dy * dy).sqrt();
+    (dx * dx + dy * dy).sqrt()
 }
 
-// -------- Upgrades (new tree) --------
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
-pub enum UpgradeId {
-    TowerDamage1,
-    FireRate,
-    CritChance,
-    CritDamage,
-    ProjectileSpeed,
-    HealthStart,
-    VampiricHealing,
-    LifeRegen,
-    MiningSpeed,
-    ResourceRecovery,
-    GoldTileChance,
-    GoldTileReward,
-    StartingGold,
-    MiningCrit,
-    KillBounty,
-    BoostColdUnlock,
+// Node of the loop enemies enter by: the Entrance, or failing that the path's first step
+fn loop_entry_index(rs: &RunState) -> Option<usize> {
+    let w = rs.grid_size.width;
+    let is_entrance = |p: &Position| {
+        matches!(
+            rs.tiles[(p.y * w + p.x) as usize].kind,
+            TileKind::Direction {
+                role: DirRole::Entrance,
+                ..
+            }
+        )
+    };
+    rs.path_loop
+        .iter()
+        .position(is_entrance)
+        .or_else(|| rs.path_loop.iter().position(|p| Some(p) == rs.path.first()))
+}
+
+// Where laps begin and end on the current loop, copied out so enemy progress can be measured
+// while the enemies themselves are borrowed
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LapFrame {
+    pub total: f64,
+    pub entry: f64,
+    pub open: bool,
+}
+impl LapFrame {
+    // How far round its lap an enemy at loop distance `d` is. On a loop a lap runs from the
+    // Entrance back round to it; an open route is walked once, to its End.
+    pub fn progress(&self, d: f64) -> f64 {
+        if self.open || self.total <= 0.0 {
+            d
+        } else {
+            (d - self.entry).rem_euclid(self.total)
+        }
+    }
+}
+
+// The route ends at an End tile rather than closing into a loop. Enemies walk it once and
+// loop_total_length is where they breach.
+pub fn route_is_open(rs: &RunState) -> bool {
+    rs.path_loop.last().is_some_and(|p| {
+        rs.tiles
+            .get((p.y * rs.grid_size.width + p.x) as usize)
+            .map(|t| &t.kind)
+            == Some(&TileKind::End)
+    })
+}
+
+// Arclength of the point on the route nearest to (x, y) (tile-center coords); an open route
+// has no closing segment back to its first node
+fn project_onto_loop(
+    nodes: &[Position],
+    cum: &[f64],
+    total: f64,
+    open: bool,
+    x: f64,
+    y: f64,
+) -> f64 {
+    if nodes.len() < 2 || total <= 0.0 {
+        return 0.0;
+    }
+    let mut best_d2 = f64::MAX;
+    let mut best = 0.0;
+    let segments = if open { nodes.len() - 1 } else { nodes.len() };
+    for i in 0..segments {
+        let a = nodes[i];
+        let b = nodes[(i + 1) % nodes.len()];
+        let (ax, ay) = (a.x as f64 + 0.5, a.y as f64 + 0.5);
+        let (dx, dy) = (b.x as f64 - a.x as f64, b.y as f64 - a.y as f64);
+        let len2 = dx * dx + dy * dy;
+        let t = if len2 > 0.0 {
+            (((x - ax) * dx + (y - ay) * dy) / len2).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let px = ax + dx * t;
+        let py = ay + dy * t;
+        let d2 = (px - x).powi(2) + (py - y).powi(2);
+        if d2 < best_d2 {
+            best_d2 = d2;
+            best = cum.get(i).copied().unwrap_or(0.0) + len2.sqrt() * t;
+        }
+    }
+    if open { best.min(total) } else { best % total }
+}
+
+// Re-anchor enemies on a rebuilt loop so they keep their place instead of jumping. Distances
+// come from where they stand, so a loop that got shorter than an old loop_dist can't leave
+// anyone past its end; with no loop left at all everyone waits at 0.
+fn remap_enemies_onto_loop(rs: &mut RunState) {
+    let total = rs.loop_total_length;
+    let open = route_is_open(rs);
+    for e in &mut rs.enemies {
+        e.loop_dist = project_onto_loop(&rs.path_loop, &rs.loop_cum_lengths, total, open, e.x, e.y);
+    }
+}
+
+// Mason rebuild: Empty -> Rock, skipped for protected tiles, if it would sever the loop,
+// or if it would strand the Mason (its current tile `mason_at` dropping off the loop)
+fn mason_rebuild_rock(rs: &mut RunState, x: u32, y: u32, mason_at: Option<(u32, u32)>) -> bool {
+    let gs = rs.grid_size;
+    if x >= gs.width || y >= gs.height {
+        return false;
+    }
+    let idx = (y * gs.width + x) as usize;
+    // Only plain Empty tiles; Start/Direction tiles are never Empty
+    if !matches!(rs.tiles[idx].kind, TileKind::Empty) || rs.tower_at(x, y).is_some() {
+        return false;
+    }
+    let old = rs.tiles[idx].clone();
+    rs.tiles[idx] = Tile {
+        kind: TileKind::Rock {
+            has_gold: false,
+            boost: None,
+        },
+        hardness: MASON_ROCK_HARDNESS,
+        magma: old.magma,
+    };
+    let path = compute_path(rs);
+    if path.is_empty() {
+        rs.tiles[idx] = old;
+        return false;
+    }
+    let old_path = std::mem::replace(&mut rs.path, path.into());
+    let path_loop = build_loop_path(rs);
+    let strands =
+        mason_at.is_some_and(|(mx, my)| !path_loop.iter().any(|p| p.x == mx && p.y == my));
+    if path_loop.len() < 2 || strands {
+        rs.tiles[idx] = old;
+        rs.path = old_path;
+        return false;
+    }
+    rs.path_loop = path_loop.into();
+    update_loop_geometry(rs);
+    remap_enemies_onto_loop(rs);
+    // Searched on the newest board, so a pending search would only undo it
+    rs.path_job = None;
+    rs.fresh_rock.push(FreshRock {
+        x,
+        y,
+        ttl: FRESH_ROCK_TTL,
+    });
+    true
+}
+
+// Hypothetical loop change from walling off a tile (used for hover previews)
+#[derive(Clone, Debug, PartialEq)]
+pub struct PathDelta {
+    pub old_length: f64,
+    pub new_length: f64,
+    pub new_loop: Vec<Position>,
+}
+impl PathDelta {
+    // Signed; walls should never shorten the loop but a stale path could make it negative
+    pub fn delta(&self) -> f64 {
+        self.new_length - self.old_length
+    }
+}
+// Returns None if the tile can't take a wall or the wall would break the path. Searched on the
+// board as it stands with the tile taken as walled; a tile off the settled route needs no search
+// at all, since lay_walls keeps that route as it is
+pub fn wall_placement_effect(rs: &RunState, x: u32, y: u32) -> Option<PathDelta> {
+    let gs = rs.grid_size;
+    if x >= gs.width || y >= gs.height {
+        return None;
+    }
+    let idx = (y * gs.width + x) as usize;
+    if !matches!(rs.tiles[idx].kind, TileKind::Empty) {
+        return None;
+    }
+    let new_loop = if off_settled_route(rs, idx) {
+        rs.path_loop.to_vec()
+    } else {
+        let path = path_job_walled(rs, &[idx]).map(PathJob::run)?;
+        if path.is_empty() {
+            return None;
+        }
+        loop_path_walled(rs, &path, &[idx])
+    };
+    let segments: f64 = new_loop
+        .windows(2)
+        .map(|w| {
+            let dx = w[1].x as f64 - w[0].x as f64;
+            let dy = w[1].y as f64 - w[0].y as f64;
+            (dx * dx + dy * dy).sqrt()
+        })
+        .sum();
+    Some(PathDelta {
+        old_length: rs.loop_total_length,
+        new_length: segments + closing_length(rs, &new_loop),
+        new_loop,
+    })
+}
+// Walls tiles in order, skipping any that aren't Empty and stopping at the first that would
+// leave no path, so a drag lands its valid prefix. The path is installed once at the end and
+// the last wall laid becomes the undoable one
+fn lay_walls(rs: &mut RunState, tiles: &[Position]) {
+    let gs = rs.grid_size;
+    let mut laid = None;
+    // Set once a wall moved the route; the walls after it are checked against that one
+    let mut rerouted = None;
+    for p in tiles.iter().filter(|p| p.x < gs.width && p.y < gs.height) {
+        let idx = (p.y * gs.width + p.x) as usize;
+        // Each wall can cut the next tile of a drag off, so reach is checked against the board
+        // as it now stands
+        if !matches!(rs.tiles[idx].kind, TileKind::Empty)
+            || !crate::state::compute_interactable_mask(rs)[idx]
+        {
+            continue;
+        }
+        rs.tiles[idx].kind = TileKind::Wall;
+        if rerouted.is_some() || !off_settled_route(rs, idx) {
+            let path = compute_path(rs);
+            if path.is_empty() {
+                rs.tiles[idx].kind = TileKind::Empty;
+                break;
+            }
+            rerouted = Some(path);
+        }
+        laid = Some(idx);
+        rs.stats.walls_built += 1;
+    }
+    if let Some(path) = rerouted {
+        install_path(rs, path);
+    }
+    if let Some(idx) = laid {
+        rs.last_wall = Some(LastWall {
+            idx,
+            version: rs.version,
+            placed_at: rs.sim_time,
+            entered: enemy_on(rs, idx),
+        });
+    }
+}
+
+// Tile `idx` is on neither the path nor the loop, and the path is settled (found, no search
+// pending). Closing a tile off a shortest route leaves that route shortest, and the search's
+// nudge toward the current path makes it the one found again; see opens_dead_end for the loop.
+fn off_settled_route(rs: &RunState, idx: usize) -> bool {
+    let w = rs.grid_size.width;
+    let at = |p: &Position| (p.y * w + p.x) as usize == idx;
+    rs.path_job.is_none()
+        && !rs.path.is_empty()
+        && !rs.path.iter().any(at)
+        && !rs.path_loop.iter().any(at)
+}
+
+// Whether walling every Empty tile in `tiles` still leaves a path: one search with the whole
+// stroke taken as walled
+pub fn walls_keep_path(rs: &RunState, tiles: &[Position]) -> bool {
+    let gs = rs.grid_size;
+    let walls: Vec<usize> = tiles
+        .iter()
+        .filter(|p| p.x < gs.width && p.y < gs.height)
+        .map(|p| (p.y * gs.width + p.x) as usize)
+        .collect();
+    path_job_walled(rs, &walls).is_some_and(|job| !job.run().is_empty())
+}
+
+// Whether an enemy stands on tile `idx`
+fn enemy_on(rs: &RunState, idx: usize) -> bool {
+    let width = rs.grid_size.width as usize;
+    let (x, y) = ((idx % width) as f64, (idx / width) as f64);
+    rs.enemies
+        .iter()
+        .any(|e| e.x.floor() == x && e.y.floor() == y)
+}
+
+// The latest wall if it can still be taken back: placed under WALL_UNDO_SECS ago, still a wall
+// (not mined or stamped over since) and never entered by an enemy
+pub fn undoable_wall(rs: &RunState) -> Option<&LastWall> {
+    let w = rs.last_wall.as_ref()?;
+    let fresh = rs.sim_time - w.placed_at <= WALL_UNDO_SECS;
+    (fresh
+        && !rs.game_over
+        && !w.entered
+        && !enemy_on(rs, w.idx)
+        && matches!(rs.tiles.get(w.idx).map(|t| &t.kind), Some(TileKind::Wall)))
+    .then_some(w)
+}
+
+// -------- Upgrades (new tree) --------
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum UpgradeId {
+    TowerDamage1,
+    FireRate,
+    CritChance,
+    CritDamage,
+    ProjectileSpeed,
+    HealthStart,
+    VampiricHealing,
+    LifeRegen,
+    MiningSpeed,
+    ResourceRecovery,
+    GoldTileChance,
+    GoldTileReward,
+    StartingGold,
+    MiningCrit,
+    KillBounty,
+    BoostColdUnlock,
     BoostColdFrequency,
     BoostColdSlowAmount,
     BoostColdSlowDuration,
     BoostColdRange,
+    BoostColdFreezeChance,
     BoostPoisonUnlock,
     BoostPoisonFrequency,
     BoostPoisonDamage,
     BoostPoisonDuration,
     BoostPoisonRange,
+    BoostPoisonSpread,
     BoostFireUnlock,
     BoostFireFrequency,
     BoostFireDamage,
@@ -788,10 +2731,18 @@ pub enum UpgradeId {
     BoostHealingUnlock,
     BoostHealingFrequency,
     BoostHealingPower,
+    BoostHealingRadius,
+    BoostHealingShield,
     // New meta progression upgrade for expanding grid
     PlayAreaSize,
     // AoE/Splash damage - projectiles damage multiple enemies in radius
     SplashRadius,
+    // Shots hop on to a nearby enemy after landing
+    Bounce,
+    // Periodic interest on banked gold
+    BankInterest,
+    // Passively mines a rock beside the path every few seconds
+    AutoMiner,
 }
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Prereq {
@@ -1012,6 +2963,16 @@ pub static UPGRADE_DEFS: &[UpgradeDef] = &[
         effect_per_level: "+12% range for cold towers",
         prerequisites: prereqs!(BoostColdUnlock:1),
     },
+    UpgradeDef {
+        id: UpgradeId::BoostColdFreezeChance,
+        display_name: "Cold Freeze Chance",
+        category: "Boost",
+        max_level: 5,
+        base_cost: 40,
+        cost_multiplier: 1.75,
+        effect_per_level: "+2% chance a slowing hit freezes for 1s",
+        prerequisites: prereqs!(BoostColdSlowDuration:1),
+    },
     UpgradeDef {
         id: UpgradeId::BoostPoisonUnlock,
         display_name: "Unlock Poison Tiles",
@@ -1062,6 +3023,16 @@ pub static UPGRADE_DEFS: &[UpgradeDef] = &[
         effect_per_level: "+12% range for poison towers",
         prerequisites: prereqs!(BoostPoisonUnlock:1),
     },
+    UpgradeDef {
+        id: UpgradeId::BoostPoisonSpread,
+        display_name: "Poison Spread",
+        category: "Boost",
+        max_level: 1,
+        base_cost: 60,
+        cost_multiplier: 1.0,
+        effect_per_level: "Poisoned enemies pass their poison on when they die",
+        prerequisites: prereqs!(BoostPoisonDuration:2),
+    },
     UpgradeDef {
         id: UpgradeId::BoostFireUnlock,
         display_name: "Unlock Fire Tiles",
@@ -1149,9 +3120,29 @@ pub static UPGRADE_DEFS: &[UpgradeDef] = &[
         max_level: 5,
         base_cost: 30,
         cost_multiplier: 1.65,
-        effect_per_level: "+10% range for towers on healing tiles",
+        effect_per_level: "+0.5 life per heal pulse",
         prerequisites: prereqs!(BoostHealingUnlock:1),
     },
+    UpgradeDef {
+        id: UpgradeId::BoostHealingRadius,
+        display_name: "Healing Aura",
+        category: "Boost",
+        max_level: 3,
+        base_cost: 40,
+        cost_multiplier: 1.7,
+        effect_per_level: "+1.5 tile aura; kills inside heal 0.25 life",
+        prerequisites: prereqs!(BoostHealingPower:2),
+    },
+    UpgradeDef {
+        id: UpgradeId::BoostHealingShield,
+        display_name: "Healing Shield",
+        category: "Boost",
+        max_level: 3,
+        base_cost: 55,
+        cost_multiplier: 1.8,
+        effect_per_level: "Overheal keeps up to +2 shield",
+        prerequisites: prereqs!(BoostHealingPower:3),
+    },
     UpgradeDef {
         id: UpgradeId::PlayAreaSize,
         display_name: "Play Area Size",
@@ -1172,6 +3163,36 @@ pub static UPGRADE_DEFS: &[UpgradeDef] = &[
         effect_per_level: "+0.5 splash radius",
         prerequisites: prereqs!(TowerDamage1:3),
     },
+    UpgradeDef {
+        id: UpgradeId::Bounce,
+        display_name: "Bounce",
+        category: "Damage",
+        max_level: 3,
+        base_cost: 60,
+        cost_multiplier: 1.9,
+        effect_per_level: "+1 bounce (75% damage per hop)",
+        prerequisites: prereqs!(TowerDamage1:3),
+    },
+    UpgradeDef {
+        id: UpgradeId::BankInterest,
+        display_name: "Bank",
+        category: "Economy",
+        max_level: 5,
+        base_cost: 70,
+        cost_multiplier: 1.9,
+        effect_per_level: "+3% interest every 10s",
+        prerequisites: prereqs!(StartingGold:3),
+    },
+    UpgradeDef {
+        id: UpgradeId::AutoMiner,
+        display_name: "Auto Miner",
+        category: "Economy",
+        max_level: 3,
+        base_cost: 80,
+        cost_multiplier: 1.9,
+        effect_per_level: "Mines a rock by the path every 12s / 9s / 6s",
+        prerequisites: prereqs!(MiningSpeed:5),
+    },
 ];
 // Progression of square grid sizes for PlayAreaSize levels 0..=10
 pub const PLAY_AREA_SIZES: &[u32] = &[10, 14, 18, 24, 32, 40, 52, 64, 80, 96, 112];
@@ -1206,11 +3227,13 @@ impl UpgradeId {
             UpgradeId::BoostColdSlowAmount => "BoostColdSlowAmount",
             UpgradeId::BoostColdSlowDuration => "BoostColdSlowDuration",
             UpgradeId::BoostColdRange => "BoostColdRange",
+            UpgradeId::BoostColdFreezeChance => "BoostColdFreezeChance",
             UpgradeId::BoostPoisonUnlock => "BoostPoisonUnlock",
             UpgradeId::BoostPoisonFrequency => "BoostPoisonFrequency",
             UpgradeId::BoostPoisonDamage => "BoostPoisonDamage",
             UpgradeId::BoostPoisonDuration => "BoostPoisonDuration",
             UpgradeId::BoostPoisonRange => "BoostPoisonRange",
+            UpgradeId::BoostPoisonSpread => "BoostPoisonSpread",
             UpgradeId::BoostFireUnlock => "BoostFireUnlock",
             UpgradeId::BoostFireFrequency => "BoostFireFrequency",
             UpgradeId::BoostFireDamage => "BoostFireDamage",
@@ -1220,8 +3243,13 @@ impl UpgradeId {
             UpgradeId::BoostHealingUnlock => "BoostHealingUnlock",
             UpgradeId::BoostHealingFrequency => "BoostHealingFrequency",
             UpgradeId::BoostHealingPower => "BoostHealingPower",
+            UpgradeId::BoostHealingRadius => "BoostHealingRadius",
+            UpgradeId::BoostHealingShield => "BoostHealingShield",
             UpgradeId::PlayAreaSize => "PlayAreaSize",
             UpgradeId::SplashRadius => "SplashRadius",
+            UpgradeId::Bounce => "Bounce",
+            UpgradeId::BankInterest => "BankInterest",
+            UpgradeId::AutoMiner => "AutoMiner",
         }
     }
 }
@@ -1229,6 +3257,9 @@ impl UpgradeId {
 pub struct UpgradeState {
     pub levels: std::collections::HashMap<String, u8>,
     pub tower_refund_rate_percent: u8,
+    // Stored on its own under md_meta_state, so a respec or prestige can't lose it with the levels
+    #[serde(skip)]
+    pub meta: MetaState,
 }
 impl Default for UpgradeState {
     fn default() -> Self {
@@ -1240,6 +3271,7 @@ impl Default for UpgradeState {
         Self {
             levels,
             tower_refund_rate_percent: 100,
+            meta: MetaState::default(),
         }
     }
 }
@@ -1274,6 +3306,10 @@ impl UpgradeState {
             self.levels.insert(id.key().into(), c + 1);
         }
     }
+    // Bought levels across every upgrade
+    pub fn total_levels(&self) -> u32 {
+        self.levels.values().map(|l| u32::from(*l)).sum()
+    }
     pub fn total_spent(&self) -> u64 {
         let mut sum = 0u64;
         for def in UPGRADE_DEFS {
@@ -1290,1064 +3326,8811 @@ impl UpgradeState {
     }
 }
 
-#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
-pub struct MetaRecords {
-    #[serde(default)]
-    pub best_time_secs: u64,
-    #[serde(default)]
-    pub best_loops: u32,
+// -------- Prestige --------
+// Once enough research has gone through the tree, levels and research can be traded for
+// prestige points: each is a permanent PRESTIGE_BONUS_PER_POINT on tower damage and mining speed
+pub const PRESTIGE_MIN_RESEARCH: u64 = 8_000;
+pub const PRESTIGE_BONUS_PER_POINT: f64 = 0.02;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetaState {
     #[serde(default)]
-    pub best_blocks_mined: u32,
+    pub prestige_points: u32,
     #[serde(default)]
-    pub total_runs: u64,
+    pub prestige_count: u32,
 }
 
-impl MetaRecords {
-    pub fn update_from_stats(&mut self, stats: &RunStats) -> Vec<&'static str> {
-        let mut new_records = Vec::new();
-        self.total_runs += 1;
-        if stats.time_survived_secs > self.best_time_secs {
-            self.best_time_secs = stats.time_survived_secs;
-            new_records.push("time");
-        }
-        if stats.loops_completed > self.best_loops {
-            self.best_loops = stats.loops_completed;
-            new_records.push("loops");
-        }
-        if stats.blocks_mined > self.best_blocks_mined {
-            self.best_blocks_mined = stats.blocks_mined;
-            new_records.push("blocks");
-        }
-        new_records
+impl MetaState {
+    pub fn multiplier(&self) -> f64 {
+        1.0 + PRESTIGE_BONUS_PER_POINT * self.prestige_points as f64
     }
 }
 
-pub fn calculate_boost_multipliers(
-    boost: Option<BoostKind>,
-    ups: &UpgradeState,
-) -> (f64, f64, f64) {
-    // Returns (range_mult, damage_mult, fire_rate_mult)
-    let Some(b) = boost else {
-        return (1.0, 1.0, 1.0);
-    };
+// Square root of the research spent, in hundreds, so the first points come quickest
+pub fn prestige_points_for(spent: u64) -> u32 {
+    ((spent / 100) as f64).sqrt() as u32
+}
 
-    use UpgradeId::*;
-    match b {
-        BoostKind::Range => {
-            // Healing tiles: range boost from BoostHealingPower
-            let range = 1.0 + 0.10 * ups.level(BoostHealingPower) as f64;
-            (range, 1.0, 1.0)
-        }
-        BoostKind::Damage => {
-            // Poison tiles: damage boost + range boost
-            let damage = 1.0 + 0.05 * ups.level(BoostPoisonDamage) as f64;
-            let range = 1.0 + 0.12 * ups.level(BoostPoisonRange) as f64;
-            (range, damage, 1.0)
+// Research earned toward a prestige: what the levels cost plus what's still unspent
+pub fn can_prestige(ups: &UpgradeState, research: u64) -> bool {
+    ups.total_spent().saturating_add(research) >= PRESTIGE_MIN_RESEARCH
+}
+
+// Upgrades after a prestige: every level gone, points added; None below the threshold
+pub fn prestige(ups: &UpgradeState, research: u64) -> Option<UpgradeState> {
+    if !can_prestige(ups, research) {
+        return None;
+    }
+    Some(UpgradeState {
+        tower_refund_rate_percent: ups.tower_refund_rate_percent,
+        meta: MetaState {
+            prestige_points: ups.meta.prestige_points + prestige_points_for(ups.total_spent()),
+            prestige_count: ups.meta.prestige_count + 1,
+        },
+        ..Default::default()
+    })
+}
+
+// -------- Upgrade queue --------
+// Levels queued from the upgrades screen and bought in order as research arrives
+pub const UPGRADE_QUEUE_CAP: usize = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QueueEdit {
+    // One more level of this upgrade at the back
+    Add(UpgradeId),
+    Remove(usize),
+    // Swap with the entry before / after
+    MoveUp(usize),
+    MoveDown(usize),
+}
+
+// Applies an edit; an Add is refused when the queue is full or the levels already queued
+// would max the upgrade
+pub fn edit_upgrade_queue(queue: &mut Vec<UpgradeId>, ups: &UpgradeState, edit: QueueEdit) {
+    match edit {
+        QueueEdit::Add(id) => {
+            let queued = queue.iter().filter(|&&q| q == id).count();
+            let room = (ups.max_level(id).saturating_sub(ups.level(id))) as usize;
+            if queue.len() < UPGRADE_QUEUE_CAP && queued < room {
+                queue.push(id);
+            }
         }
-        BoostKind::FireRate => {
-            // Future: Fire rate boost tiles (not unlocked yet)
-            (1.0, 1.0, 1.15)
+        QueueEdit::Remove(i) => {
+            if i < queue.len() {
+                queue.remove(i);
+            }
         }
-        BoostKind::Slow => {
-            // Cold tiles: range boost (compensates for short base range)
-            let range = 1.0 + 0.12 * ups.level(BoostColdRange) as f64;
-            (range, 1.0, 1.0)
+        QueueEdit::MoveUp(i) => {
+            if i > 0 && i < queue.len() {
+                queue.swap(i - 1, i);
+            }
         }
-        BoostKind::Fire => {
-            // Fire tiles: range boost
-            let range = 1.0 + 0.12 * ups.level(BoostFireRange) as f64;
-            (range, 1.0, 1.0)
+        QueueEdit::MoveDown(i) => {
+            if i + 1 < queue.len() {
+                queue.swap(i, i + 1);
+            }
         }
     }
 }
 
-// Helper function to calculate debuff to apply from tower boost
-pub fn calculate_debuff_from_boost(boost: Option<BoostKind>, ups: &UpgradeState) -> Option<Debuff> {
-    let Some(b) = boost else {
-        return None;
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct QueuePurchases {
+    // In purchase order
+    pub bought: Vec<UpgradeId>,
+    pub spent: u64,
+    pub remaining: Vec<UpgradeId>,
+}
+
+// Walks the queue in order. An entry whose prerequisites aren't met is skipped but kept; an
+// affordable one is bought; the first one that isn't affordable ends the walk, saving research
+// for it. Maxed entries are dropped. A purchase can unblock a skipped entry, so the walk
+// repeats until nothing changes. PlayAreaSize rebuilds the board, so it waits out a live run.
+pub fn process_upgrade_queue(
+    queue: &[UpgradeId],
+    ups: &UpgradeState,
+    research: u64,
+    live: bool,
+) -> QueuePurchases {
+    let mut ups = ups.clone();
+    let mut research = research;
+    let mut out = QueuePurchases {
+        remaining: queue.to_vec(),
+        ..Default::default()
     };
+    let mut changed = true;
+    while changed {
+        changed = false;
+        let mut i = 0;
+        while i < out.remaining.len() {
+            let id = out.remaining[i];
+            let Some(cost) = ups.next_cost(id) else {
+                out.remaining.remove(i);
+                changed = true;
+                continue;
+            };
+            if !ups.is_unlocked(id) || (live && id == UpgradeId::PlayAreaSize) {
+                i += 1;
+                continue;
+            }
+            if cost > research {
+                break;
+            }
+            research -= cost;
+            out.spent += cost;
+            ups.purchase(id);
+            out.bought.push(id);
+            out.remaining.remove(i);
+            changed = true;
+        }
+    }
+    out
+}
 
-    use UpgradeId::*;
-    match b {
-        BoostKind::Slow => {
-            // Cold tiles apply slow debuff
-            let base_slow = 0.5; // 50% slow baseline
-            let slow_amount = base_slow * (1.0 + 0.10 * ups.level(BoostColdSlowAmount) as f64);
-            let duration = 1.0 + 1.0 * ups.level(BoostColdSlowDuration) as f64;
-            Some(Debuff {
-                kind: DebuffKind::Slow,
-                remaining: duration,
-                strength: slow_amount,
-            })
+// -------- Progressive unlocks --------
+// First runs hide mechanics; MetaRecords::total_runs + 1 is the current run number
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Feature {
+    Towers,
+    Upgrades,
+    Walls,
+    Legend,
+    FullSettings,
+}
+impl Feature {
+    // 1-based run on which the feature becomes available
+    pub fn unlock_run(self) -> u64 {
+        match self {
+            Feature::Towers | Feature::Upgrades => 2,
+            Feature::Walls | Feature::Legend | Feature::FullSettings => 3,
         }
-        BoostKind::Damage => {
-            // Poison tiles apply poison DoT debuff
-            let base_dps = 1.0;
-            let dps = base_dps * (1.0 + 0.05 * ups.level(BoostPoisonDamage) as f64);
-            let duration = 2.0 + 1.0 * ups.level(BoostPoisonDuration) as f64;
-            Some(Debuff {
-                kind: DebuffKind::Poison,
-                remaining: duration,
-                strength: dps,
-            })
+    }
+    pub fn label(self) -> &'static str {
+        match self {
+            Feature::Towers => "Towers",
+            Feature::Upgrades => "Upgrades",
+            Feature::Walls => "Walls",
+            Feature::Legend => "Legend",
+            Feature::FullSettings => "Settings",
         }
-        BoostKind::Fire => {
-            // Fire tiles apply burn DoT debuff
-            let base_dps = 0.5; // Weaker than poison (0.5 vs 1.0) since it can spread
-            let dps = base_dps * (1.0 + 0.10 * ups.level(BoostFireDamage) as f64);
-            let duration = 3.0 + 1.0 * ups.level(BoostFireDuration) as f64;
-            Some(Debuff {
-                kind: DebuffKind::Burn,
-                remaining: duration,
-                strength: dps,
-            })
+    }
+    pub fn locked_message(self, run_number: u64) -> String {
+        let at = self.unlock_run();
+        if at == run_number + 1 {
+            format!("{} unlock next run", self.label())
+        } else {
+            format!("{} unlock on run {}", self.label(), at)
         }
-        BoostKind::Range | BoostKind::FireRate => {
-            // These boosts don't apply debuffs
-            None
+    }
+}
+pub fn feature_allowed(run_number: Option<u64>, feature: Feature) -> bool {
+    run_number.is_none_or(|n| n >= feature.unlock_run())
+}
+// Some(reason) while the feature is still locked
+pub fn feature_lock_message(run_number: Option<u64>, feature: Feature) -> Option<String> {
+    run_number
+        .filter(|_| !feature_allowed(run_number, feature))
+        .map(|n| feature.locked_message(n))
+}
+// Reducer-side gate so hotkeys can't bypass hidden controls
+fn feature_gate(rs: &mut RunState, feature: Feature) -> bool {
+    match feature_lock_message(rs.progression_run, feature) {
+        Some(msg) => {
+            rs.locked_notice = Some(msg);
+            false
         }
+        None => true,
     }
 }
 
-pub fn apply_upgrades_to_run(run: &mut RunState, ups: &UpgradeState) {
-    use UpgradeId::*;
-    let l = |id: UpgradeId| ups.level(id) as f64;
-
-    // Calculate player power level based on total upgrade levels
-    // This is used to scale enemy difficulty appropriately
-    let mut total_upgrade_levels = 0u32;
-    for def in UPGRADE_DEFS {
-        total_upgrade_levels += ups.level(def.id) as u32;
+// Share of loop tiles covered by no-target zones
+pub fn excluded_path_fraction(rs: &RunState) -> f64 {
+    if rs.path_loop.is_empty() {
+        return 0.0;
     }
-    run.player_power_level = total_upgrade_levels as f64;
-
-    // Pre-calculate debuff templates for towers placed mid-game
-    run.cold_debuff_template = calculate_debuff_from_boost(Some(BoostKind::Slow), ups);
-    run.poison_debuff_template = calculate_debuff_from_boost(Some(BoostKind::Damage), ups);
-    run.fire_debuff_template = calculate_debuff_from_boost(Some(BoostKind::Fire), ups);
-    run.freeze_chance = 0.02 * l(BoostColdSlowAmount);
+    let n = rs
+        .path_loop
+        .iter()
+        .filter(|p| rs.no_target.get(p.x, p.y))
+        .count();
+    n as f64 / rs.path_loop.len() as f64
+}
+pub fn exclusion_warning(rs: &RunState) -> Option<String> {
+    let f = excluded_path_fraction(rs);
+    (f > EXCLUSION_WARN_FRACTION)
+        .then(|| format!("{:.0}% of the path is excluded from targeting", f * 100.0))
+}
 
-    // Calculate fire spread radius (BoostFireSpread upgrade)
-    run.fire_spread_radius = if ups.level(BoostFireSpread) > 0 {
-        ups.level(BoostFireSpread) as f64
-    } else {
-        0.0
-    };
+impl RunState {
+    // Gold from mining one gold rock, before crits
+    pub fn gold_per_rock(&self) -> u64 {
+        self.mining_gold_mul.round() as u64
+    }
 
-    run.mining_speed = 2.0 * (1.0 + 0.08 * l(MiningSpeed));
-    run.tower_base_damage = (2.0 * (1.0 + 0.12 * l(TowerDamage1))) as u32;
-    run.tower_fire_rate_global = 1.0 + 0.08 * l(FireRate);
-    run.crit_chance = 0.03 * l(CritChance);
-    run.crit_damage_mult = 1.0 + 0.25 * l(CritDamage);
-    run.projectile_speed = 8.0 * (1.0 + 0.15 * l(ProjectileSpeed));
-    run.life_regen_per_sec = 0.5 * l(LifeRegen);
-    run.vampiric_heal_percent = 0.01 * l(VampiricHealing);
-    let hp_level = l(BoostHealingPower);
-    if hp_level > 0.0 {
-        run.healing_tile_heal_per_tick = 0.5 + 0.5 * hp_level;
+    // Mining speed including the timed perk bonus
+    pub fn effective_mining_speed(&self) -> f64 {
+        if self.perk_mining_secs > 0 {
+            self.mining_speed * (1.0 + PERK_MINING_BONUS)
+        } else {
+            self.mining_speed
+        }
     }
-    run.mining_gold_mul = 1.0 + 0.15 * l(GoldTileReward);
-    run.mining_crit_chance = 0.05 * l(MiningCrit);
-    run.gold_bounty_per_kill = ups.level(KillBounty) as u64;
-    run.tower_refund_mult = 1.0 + 0.20 * l(ResourceRecovery);
-    run.projectile_splash_radius = 0.5 * l(SplashRadius);
-    if run.stats.time_survived_secs == 0 && !run.started {
-        // Apply life & starting gold only once while pre-run (before any survival time or start)
-        run.life_max = 10 + 5 * ups.level(HealthStart) as u32;
-        run.life = run.life_max;
-        let sg_level = ups.level(StartingGold);
-        if sg_level > run.starting_gold_applied_level {
-            let delta_levels = sg_level - run.starting_gold_applied_level;
-            // Each level grants +2 starting gold (matches upgrade definition)
-            run.currencies.gold = run.currencies.gold.saturating_add(2 * delta_levels as u64);
-            run.starting_gold_applied_level = sg_level;
+    // A tower's per-shot damage with the prestige bonus, before crits
+    pub fn shot_damage(&self, tw: &Tower) -> f64 {
+        tw.shot_damage() * self.prestige_damage_mult
+    }
+    // List price of `kind`, which upgrades, swaps and refunds go by
+    pub fn tower_cost_of(&self, kind: &TowerKind) -> u64 {
+        match kind {
+            TowerKind::Basic => self.tower_cost,
+            TowerKind::Slow => self.tower_cost_slow,
+            TowerKind::Damage => self.tower_cost_damage,
         }
     }
-    run.life_max = 10 + 5 * ups.level(HealthStart) as u32; // keep max updated for mid-run effects (no gold change mid-run)
-    if run.life > run.life_max {
-        run.life = run.life_max;
+    // Where laps start and end on the loop as it stands
+    pub fn lap_frame(&self) -> LapFrame {
+        LapFrame {
+            total: self.loop_total_length,
+            entry: self.loop_entry_dist,
+            open: route_is_open(self),
+        }
     }
-    for tw in &mut run.towers {
-        let (rm, base_damage, fr) = match tw.kind {
-            TowerKind::Basic => (1.0, run.tower_base_damage, 1.0),
-            TowerKind::Slow => (1.3, (run.tower_base_damage / 2).max(1), 0.6),
-            TowerKind::Damage => (0.7, run.tower_base_damage.saturating_mul(2), 1.5),
-        };
-        // Apply boost multipliers
-        let (boost_rm, boost_dm, boost_frm) = calculate_boost_multipliers(tw.boost, ups);
+    // Gold a new `kind` costs with the towers already standing
+    pub fn current_tower_cost(&self, kind: &TowerKind) -> u64 {
+        scaled_tower_cost(self.tower_cost_of(kind), self.towers.len())
+    }
+    // Whether placing `kind` would go through: a tile credit, and a perk tower or enough gold
+    pub fn can_afford_tower(&self, kind: &TowerKind) -> bool {
+        self.tower_purchase_error(kind).is_none()
+    }
+    // What keeps `kind` from being bought right now, worded for placement feedback
+    pub fn tower_purchase_error(&self, kind: &TowerKind) -> Option<String> {
+        self.tower_shortfall(kind).map(|r| r.message())
+    }
+    // NeedGold or NeedTileCredit when `kind` can't be paid for
+    pub fn tower_shortfall(&self, kind: &TowerKind) -> Option<PlacementResult> {
+        let cost = self.current_tower_cost(kind);
+        if self.free_towers == 0 && self.currencies.gold < cost {
+            return Some(PlacementResult::NeedGold(cost));
+        }
+        (self.currencies.tile_credits < TOWER_TILE_CREDITS)
+            .then_some(PlacementResult::NeedTileCredit)
+    }
+    pub fn streak_research_mult(&self) -> f64 {
+        1.0 + STREAK_STEP * self.clean_streak.min(STREAK_MAX) as f64
+    }
+    // Life was lost within the last LIFE_LOSS_FLASH_SECS of sim time
+    pub fn life_loss_flash(&self) -> bool {
+        self.last_life_loss_at > 0.0
+            && self.sim_time - self.last_life_loss_at < LIFE_LOSS_FLASH_SECS
+    }
+    // A mined tile's new path is still being searched; `path` is the old route meanwhile
+    pub fn path_recomputing(&self) -> bool {
+        self.path_job.as_ref().is_some_and(|job| !job.is_done())
+    }
+    pub fn tower_at(&self, x: u32, y: u32) -> Option<&Tower> {
+        self.towers.iter().find(|t| t.x == x && t.y == y)
+    }
+    pub fn tower_at_mut(&mut self, x: u32, y: u32) -> Option<&mut Tower> {
+        self.towers.iter_mut().find(|t| t.x == x && t.y == y)
+    }
+    fn tower_index_at(&self, x: u32, y: u32) -> Option<usize> {
+        self.towers.iter().position(|t| t.x == x && t.y == y)
+    }
+}
+// -------- Autosave --------
+// The in-progress run, saved each second under md_run_state. Enemies, projectiles and effects
+// are dropped (a resumed run starts with a clear field, paused) and path geometry is re-derived
+// on load. Tiles go in as (count, palette index) runs over a table of the distinct tiles: a
+// 255x255 board is mostly rock, but mined gaps and boosted rock still break it into thousands of
+// runs, and repeating the full tile in each one ran to several hundred KB.
+#[derive(Serialize, Deserialize)]
+struct RunSave {
+    palette: Vec<Tile>,
+    tiles: Vec<(u32, u32)>,
+    run: RunState,
+}
 
-        // Apply boost-specific intrinsic range modifiers (matches Tower::new)
-        let boost_range_mul = match tw.boost {
-            Some(BoostKind::Slow) => 0.7,     // Cold tiles: -30% range
-            Some(BoostKind::Fire) => 1.0,     // Fire tiles: normal range
-            Some(BoostKind::Damage) => 1.0,   // Poison tiles: normal range
-            Some(BoostKind::Range) => 1.15,   // Healing tiles: +15% range (intrinsic)
-            Some(BoostKind::FireRate) => 1.0, // Fire rate tiles: normal range
-            None => 1.0,                      // No boost: normal range
-        };
+impl RunState {
+    pub fn to_save(&self) -> String {
+        self.encode_save(false)
+    }
 
-        tw.range = run.tower_base_range * rm * boost_rm * boost_range_mul;
-        tw.damage = ((base_damage as f64) * boost_dm).round() as u32;
-        if tw.damage == 0 {
-            tw.damage = 1;
+    // As to_save, but keeping enemies, projectiles and effects mid-flight (debug bundles)
+    pub fn to_debug_save(&self) -> String {
+        self.encode_save(true)
+    }
+
+    fn encode_save(&self, keep_field: bool) -> String {
+        let mut run = self.clone();
+        let mut palette: Vec<Tile> = Vec::new();
+        let mut tiles: Vec<(u32, u32)> = Vec::new();
+        for t in std::mem::take(&mut run.tiles).iter() {
+            let i = match palette.iter().position(|p| p == t) {
+                Some(i) => i as u32,
+                None => {
+                    palette.push(t.clone());
+                    palette.len() as u32 - 1
+                }
+            };
+            match tiles.last_mut() {
+                Some((n, last)) if *last == i => *n += 1,
+                _ => tiles.push((1, i)),
+            }
+        }
+        if !keep_field {
+            run.enemies.clear();
+            run.projectiles.clear();
+            run.damage_numbers.clear();
+            run.floating_texts.clear();
+            run.splash_explosions.clear();
         }
-        tw.fire_rate = fr * run.tower_fire_rate_global * boost_frm;
+        run.path_loop.clear();
+        run.loop_cum_lengths.clear();
+        run.heat = HeatGrid::default();
+        serde_json::to_string(&RunSave {
+            palette,
+            tiles,
+            run,
+        })
+        .unwrap_or_default()
+    }
 
-        // Calculate debuff to apply from tower's boost
-        tw.apply_debuff = calculate_debuff_from_boost(tw.boost, ups);
+    // None when the blob no longer parses or doesn't describe a valid board; callers start fresh
+    pub fn from_save(raw: &str) -> Option<RunState> {
+        let RunSave {
+            palette,
+            tiles,
+            mut run,
+        } = serde_json::from_str(raw).ok()?;
+        let mut expanded = Vec::new();
+        for (n, i) in tiles {
+            let t = palette.get(i as usize)?;
+            expanded.extend(std::iter::repeat_n(t.clone(), n as usize));
+        }
+        run.tiles = expanded.into();
+        let gs = run.grid_size;
+        if run.tiles.len() != (gs.width * gs.height) as usize {
+            return None;
+        }
+        run.heat = HeatGrid::new(gs);
+        // Saves from before entity ids load every tower as id 0; those and any repeats get fresh
+        // ids past the highest one in use
+        let top = (run.towers.iter().map(|t| t.id))
+            .chain(run.enemies.iter().map(|e| e.id))
+            .chain(run.projectiles.iter().map(|p| p.id))
+            .max()
+            .unwrap_or_default();
+        run.entity_ids.last = run.entity_ids.last.max(top.0);
+        let mut seen = std::collections::HashSet::new();
+        for tw in run.towers.iter_mut() {
+            if tw.id == EntityId::default() || !seen.insert(tw.id) {
+                tw.id = run.entity_ids.alloc();
+            }
+        }
+        // The saved path only breaks ties, so the route comes back as it was
+        run.path = compute_path(&run).into();
+        run.path_loop = build_loop_path(&run).into();
+        update_loop_geometry(&mut run);
+        run.is_paused = true;
+        let broken = crate::state::invariants::check(&run);
+        if !broken.is_empty() {
+            log(
+                Level::Warn,
+                &format!("discarding saved run {}: {:?}", run.run_id, broken),
+            );
+            return None;
+        }
+        Some(run)
     }
 }
 
-// === Actions & Reducer ===
-#[derive(Clone, Debug)]
-pub enum RunAction {
-    TogglePause,
-    StartRun,
-    TickSecond,
-    MiningComplete { idx: usize },
-    SimTick { dt: f64 },
-    ResetRun,
-    ResetRunWithUpgrades { ups: UpgradeState },
-    PlaceWall { x: u32, y: u32 },
-    PlaceTower { x: u32, y: u32, kind: TowerKind },
-    RemoveTower { x: u32, y: u32 },
-    SpendResearch { amount: u64 },
-    ApplyUpgrades { ups: UpgradeState },
-    SetResearch { amount: u64 },
+// -------- Scenarios --------
+// Handcrafted puzzle maps: a fixed layout and purse, a scripted enemy schedule in place of the
+// spawner, no meta upgrades, and a goal checked once a second. Stars are kept in MetaRecords.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScenarioId {
+    RingRoad,
+    FourTowers,
+    GoldRush,
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScenarioGoal {
+    SurviveSecs(u64),
+    Kills(u64),
+}
+// `count` enemies of `kind`, released one a second from `at_secs` (the first tick is second 1)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScenarioWave {
+    pub at_secs: u64,
+    pub kind: EnemyKind,
+    pub count: u32,
+    pub hp: u32,
+}
+pub struct ScenarioDef {
+    pub id: ScenarioId,
+    pub name: &'static str,
+    pub blurb: &'static str,
+    // Layout in tiles_from_rows notation
+    pub rows: &'static [&'static str],
+    pub gold: u64,
+    pub life: u32,
+    pub max_towers: Option<usize>,
+    pub goal: ScenarioGoal,
+    pub waves: &'static [ScenarioWave],
 }
+const fn wave(at_secs: u64, kind: EnemyKind, count: u32, hp: u32) -> ScenarioWave {
+    ScenarioWave {
+        at_secs,
+        kind,
+        count,
+        hp,
+    }
+}
+use EnemyKind::{Mason as EM, Normal as EN};
+pub const SCENARIO_DEFS: &[ScenarioDef] = &[
+    ScenarioDef {
+        id: ScenarioId::RingRoad,
+        name: "Ring Road",
+        blurb: "One road around the hill. Hold it for two minutes.",
+        rows: &[
+            "#############",
+            "#...........#",
+            "#.####G####.#",
+            "#.####I####.#",
+            "#....XSE....#",
+            "######I######",
+            "#############",
+        ],
+        gold: 6,
+        life: 10,
+        max_towers: None,
+        goal: ScenarioGoal::SurviveSecs(120),
+        waves: &[
+            wave(3, EN, 5, 6),
+            wave(20, EN, 8, 8),
+            wave(45, EN, 10, 12),
+            wave(70, EM, 1, 60),
+            wave(80, EN, 12, 16),
+            wave(100, EN, 15, 20),
+        ],
+    },
+    ScenarioDef {
+        id: ScenarioId::FourTowers,
+        name: "Four Towers",
+        blurb: "Two roads, four towers. Take down 40 enemies.",
+        rows: &[
+            "###############",
+            "#.............#",
+            "#.#####G#####.#",
+            "#.#####I#####.#",
+            "#......XSE....#",
+            "#.#####I#####.#",
+            "#.#####G#####.#",
+            "#.............#",
+            "###############",
+        ],
+        gold: 12,
+        life: 10,
+        max_towers: Some(4),
+        goal: ScenarioGoal::Kills(40),
+        waves: &[
+            wave(3, EN, 10, 6),
+            wave(25, EN, 12, 10),
+            wave(50, EN, 12, 14),
+            wave(75, EN, 12, 18),
+        ],
+    },
+    ScenarioDef {
+        id: ScenarioId::GoldRush,
+        name: "Gold Rush",
+        blurb:
+            "Three lives and an empty purse, but the walls are full of gold. Last three minutes.",
+        rows: &[
+            "#############",
+            "#GGGGGGGGGGG#",
+            "#...........#",
+            "#.GGGGGGGGG.#",
+            "#.GGGGIGGGG.#",
+            "#....XSE....#",
+            "######I######",
+            "#############",
+        ],
+        gold: 2,
+        life: 3,
+        max_towers: None,
+        goal: ScenarioGoal::SurviveSecs(180),
+        waves: &[
+            wave(10, EN, 6, 6),
+            wave(35, EN, 10, 10),
+            wave(65, EN, 12, 16),
+            wave(95, EM, 2, 80),
+            wave(110, EN, 15, 22),
+            wave(145, EN, 20, 28),
+        ],
+    },
+];
 
-impl yew::Reducible for RunState {
-    type Action = RunAction;
-    fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
-        use RunAction::*;
-        if let ResetRunWithUpgrades { ups } = &action {
-            let prev_r = self.currencies.research;
-            let size = play_area_size_for_level(ups.level(UpgradeId::PlayAreaSize));
-            let mut fresh = RunState::new_with_upgrades(
-                GridSize {
-                    width: size,
-                    height: size,
-                },
-                ups,
-            );
-            fresh.currencies.research = prev_r;
-            fresh.run_id = self.run_id + 1;
-            return Rc::new(fresh);
+impl ScenarioId {
+    pub const ALL: [ScenarioId; 3] = [
+        ScenarioId::RingRoad,
+        ScenarioId::FourTowers,
+        ScenarioId::GoldRush,
+    ];
+    pub fn def(self) -> &'static ScenarioDef {
+        SCENARIO_DEFS.iter().find(|d| d.id == self).unwrap()
+    }
+    // MetaRecords key
+    pub fn key(self) -> &'static str {
+        match self {
+            ScenarioId::RingRoad => "RingRoad",
+            ScenarioId::FourTowers => "FourTowers",
+            ScenarioId::GoldRush => "GoldRush",
         }
-        if matches!(action, ResetRun) {
-            let prev_r = self.currencies.research;
-            let mut fresh = RunState::new_basic(self.grid_size);
-            fresh.currencies.research = prev_r;
-            fresh.run_id = self.run_id + 1;
-            return Rc::new(fresh);
+    }
+}
+
+impl ScenarioGoal {
+    pub fn text(self) -> String {
+        match self {
+            ScenarioGoal::SurviveSecs(s) => format!("Survive {}", format_time(s)),
+            ScenarioGoal::Kills(n) => format!("Defeat {n} enemies"),
         }
-        let mut new = (*self).clone();
-        match action {
-            TogglePause => {
-                if !new.game_over {
-                    new.is_paused = !new.is_paused;
-                }
-            }
-            StartRun => {
-                if !new.started && !new.game_over {
-                    new.started = true;
-                }
-            }
-            TickSecond => {
-                if new.started && !new.is_paused && !new.game_over {
-                    new.stats.time_survived_secs = new.stats.time_survived_secs.saturating_add(1);
-                    if new.life < new.life_max && new.life_regen_per_sec > 0.0 {
-                        new.life_regen_accum += new.life_regen_per_sec;
-                        if new.life_regen_accum >= 1.0 {
-                            let gain = new.life_regen_accum.floor() as u32;
-                            new.life_regen_accum -= gain as f64;
-                            new.life = (new.life + gain).min(new.life_max);
-                        }
-                    }
-                    if new.healing_tile_heal_per_tick > 0.0 {
-                        new.healing_tile_timer += 1.0;
-                        if new.healing_tile_timer >= 2.0 {
-                            new.healing_tile_timer -= 2.0;
-                            let healing_tower_count = new
-                                .towers
-                                .iter()
-                                .filter(|t| matches!(t.boost, Some(BoostKind::Range)))
-                                .count();
-                            if healing_tower_count > 0 && new.life < new.life_max {
-                                let total_heal =
-                                    (new.healing_tile_heal_per_tick * healing_tower_count as f64)
-                                        .round() as u32;
-                                let old_life = new.life;
-                                new.life = (new.life + total_heal).min(new.life_max);
-                                let healed = new.life - old_life;
-                                if healed > 0 {
-                                    if let Some(ht) = new
-                                        .towers
-                                        .iter()
-                                        .find(|t| matches!(t.boost, Some(BoostKind::Range)))
-                                    {
-                                        new.damage_numbers.push(DamageNumber {
-                                            x: ht.x as f64 + 0.5,
-                                            y: ht.y as f64 + 0.5,
-                                            amount: healed,
-                                            ttl: 1.0,
-                                            is_crit: false,
-                                            is_gold: false,
-                                            is_heal: true,
-                                        });
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            MiningComplete { idx } => {
-                if !new.game_over && idx < new.tiles.len() {
-                    new.last_mined_idx = Some(idx);
-                    match new.tiles[idx].kind {
-                        TileKind::Rock { has_gold, .. } => {
-                            new.tiles[idx].kind = TileKind::Empty;
-                            new.tiles[idx].hardness = 1;
-                            new.stats.blocks_mined = new.stats.blocks_mined.saturating_add(1);
-                            new.currencies.tile_credits =
-                                new.currencies.tile_credits.saturating_add(1);
-                            if has_gold {
-                                let mut g = 1.0 * new.mining_gold_mul;
-                                let is_mining_crit = new.mining_crit_chance > 0.0
-                                    && js_sys::Math::random() < new.mining_crit_chance;
-                                if is_mining_crit {
-                                    g *= 2.0;
-                                }
-                                let gold_earned = g.round() as u64;
-                                new.currencies.gold =
-                                    new.currencies.gold.saturating_add(gold_earned);
-                                // Show floating gold number at mined tile
-                                let tx = (idx as u32 % new.grid_size.width) as f64 + 0.5;
-                                let ty = (idx as u32 / new.grid_size.width) as f64 + 0.5;
-                                new.damage_numbers.push(DamageNumber {
-                                    x: tx,
-                                    y: ty,
-                                    amount: gold_earned as u32,
-                                    ttl: 1.0,
-                                    is_crit: is_mining_crit,
-                                    is_gold: true,
-                                    is_heal: false,
-                                });
-                            }
-                            new.path = compute_path(&new);
-                            new.path_loop = build_loop_path(&new);
-                            update_loop_geometry(&mut new);
-                        }
-                        TileKind::Wall => {
-                            new.tiles[idx].kind = TileKind::Empty;
-                            new.tiles[idx].hardness = 1;
-                            new.currencies.tile_credits =
-                                new.currencies.tile_credits.saturating_add(1);
-                            new.path = compute_path(&new);
-                            new.path_loop = build_loop_path(&new);
-                            update_loop_geometry(&mut new);
-                        }
-                        _ => {}
-                    }
-                }
-            }
-            SimTick { dt } => {
-                if !(new.started && !new.is_paused && !new.game_over) {
-                    return self;
-                }
-                new.sim_time += dt;
-                {
-                    let t = new.stats.time_survived_secs as f64;
-                    // Gradual spawn rate progression - gives more breathing room
-                    let max_interval = 2.0;
-                    let min_interval = 0.5; // Not as aggressive (was 0.3)
-                    let spawn_interval = (max_interval - t * 0.015).max(min_interval); // Slower progression (was 0.025)
-                    if (new.stats.time_survived_secs as f64 - new.last_enemy_spawn_time_secs)
-                        >= spawn_interval
-                        && !new.path_loop.is_empty()
-                    {
-                        if let Some((idx, _tile)) = new
-                            .tiles
-                            .iter()
-                            .enumerate()
-                            .find(|(_, t)| matches!(t.kind, TileKind::Start))
-                        {
-                            let sx = (idx as u32) % new.grid_size.width;
-                            let sy = (idx as u32) / new.grid_size.width;
-
-                            // Difficulty scales with: time, loops, AND player power
-                            // This creates a good progression curve:
-                            // - New players (power=0): Easy enemies, can farm research
-                            // - Mid players (power=10-20): Moderate challenge
-                            // - Late players (power=30+): Serious challenge
-
-                            let time_factor = t / 50.0; // Every 50 seconds adds +1 difficulty (much slower!)
-                            let loop_factor = new.stats.loops_completed as f64;
-                            let base_difficulty = time_factor + loop_factor;
-
-                            // Player power scaling: each 15 upgrade levels = +1 difficulty multiplier
-                            // This means upgrades make you stronger for longer before difficulty catches up
-                            let power_mult = 1.0 + (new.player_power_level / 15.0);
-                            let difficulty = base_difficulty * power_mult;
-
-                            // Much gentler exponential HP scaling
-                            let base_hp = 5.0;
-                            let hp_mult = (1.0 + difficulty * 0.10).powf(1.25); // Very gentle curve
-                            let hp = (base_hp * hp_mult).round() as u32;
-
-                            // Speed scales very slowly
-                            let speed = 1.5 + difficulty * 0.05; // Very slow speed increase
-
-                            // Visual scaling - enemies grow larger as they get stronger
-                            let size_scale = (1.0 + difficulty * 0.04).min(2.0); // Was 0.05
-
-                            new.enemies.push(Enemy {
-                                x: sx as f64 + 0.5,
-                                y: sy as f64 + 0.5,
-                                speed_tps: speed,
-                                hp,
-                                max_hp: hp,
-                                spawned_at: new.stats.time_survived_secs,
-                                path_index: 0,
-                                dir_dx: 1.0,
-                                dir_dy: 0.0,
-                                radius_scale: size_scale,
-                                loop_dist: 0.0,
-                                debuffs: Vec::new(),
-                            });
-                            new.last_enemy_spawn_time_secs = new.stats.time_survived_secs as f64;
-                        }
-                    }
-                }
-                if !new.towers.is_empty() && !new.enemies.is_empty() {
-                    for tw in &mut new.towers {
-                        if tw.cooldown_remaining > 0.0 {
-                            tw.cooldown_remaining -= dt;
-                        }
-                        if tw.cooldown_remaining > 0.0 {
-                            continue;
-                        }
-                        let cx = tw.x as f64 + 0.5;
-                        let cy = tw.y as f64 + 0.5;
-                        let mut target = None::<usize>;
-                        for (i, e) in new.enemies.iter().enumerate() {
-                            let dx = e.x - cx;
-                            let dy = e.y - cy;
-                            if dx * dx + dy * dy <= tw.range * tw.range {
-                                target = Some(i);
-                                break;
-                            }
-                        }
-                        if let Some(i) = target {
-                            let e = &new.enemies[i];
+    }
+}
 
-                            // Predictive aiming: aim at where enemy will be, not where it is
-                            // Calculate initial travel time based on current position
-                            let dx0 = e.x - cx;
-                            let dy0 = e.y - cy;
-                            let dist0 = (dx0 * dx0 + dy0 * dy0).sqrt().max(1e-6);
-                            let speed = new.projectile_speed;
-                            let initial_travel = dist0 / speed;
+impl ScenarioDef {
+    // Enemies the schedule releases at second `secs`
+    pub fn spawns_at(&self, secs: u64) -> impl Iterator<Item = &ScenarioWave> {
+        self.waves
+            .iter()
+            .filter(move |w| secs >= w.at_secs && secs < w.at_secs + w.count as u64)
+    }
+    // Second of the last scheduled release
+    pub fn last_spawn_secs(&self) -> u64 {
+        self.waves
+            .iter()
+            .map(|w| w.at_secs + w.count.saturating_sub(1) as u64)
+            .max()
+            .unwrap_or(0)
+    }
+}
 
-                            // Predict where enemy will be after that time
-                            // Account for slow debuff in prediction
-                            let mut enemy_speed_mult: f64 = 1.0;
-                            for debuff in &e.debuffs {
-                                if debuff.remaining <= 0.0 {
-                                    continue;
-                                }
-                                match debuff.kind {
-                                    DebuffKind::Freeze => {
-                                        enemy_speed_mult = 0.0;
-                                        break;
-                                    }
-                                    DebuffKind::Slow => {
-                                        enemy_speed_mult =
-                                            enemy_speed_mult.min(1.0 - debuff.strength);
-                                    }
-                                    DebuffKind::Poison | DebuffKind::Burn => {}
-                                }
-                            }
-                            let enemy_vx = e.dir_dx * e.speed_tps * enemy_speed_mult;
-                            let enemy_vy = e.dir_dy * e.speed_tps * enemy_speed_mult;
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScenarioOutcome {
+    Won,
+    Lost,
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScenarioRun {
+    pub id: ScenarioId,
+    // Set once, by tick_scenario or on a fatal leak
+    pub outcome: Option<ScenarioOutcome>,
+}
 
-                            // Predicted position (linear approximation)
-                            let pred_x = e.x + enemy_vx * initial_travel;
-                            let pred_y = e.y + enemy_vy * initial_travel;
+// One star for the win, one for keeping half the lives, one for losing none
+pub fn scenario_stars(rs: &RunState) -> u8 {
+    match rs.scenario {
+        Some(ScenarioRun {
+            outcome: Some(ScenarioOutcome::Won),
+            ..
+        }) => 1 + u8::from(rs.life * 2 >= rs.life_max) + u8::from(rs.life == rs.life_max),
+        _ => 0,
+    }
+}
 
-                            // Aim at predicted position
-                            let dx = pred_x - cx;
-                            let dy = pred_y - cy;
-                            let dist = (dx * dx + dy * dy).sqrt().max(1e-6);
-                            let travel = dist / speed;
+// Map rows to tiles: '.' empty, '#' rock, 'G' gold rock, 'W' wall, 'I' indestructible,
+// 'S' start, 'E' entrance and 'X' exit (both pointing right)
+pub fn tiles_from_rows(rows: &[&str]) -> (GridSize, Vec<Tile>) {
+    let gs = GridSize {
+        width: rows[0].len() as u32,
+        height: rows.len() as u32,
+    };
+    let tiles = rows
+        .iter()
+        .flat_map(|r| r.chars())
+        .map(|c| {
+            let kind = match c {
+                '.' => TileKind::Empty,
+                'W' => TileKind::Wall,
+                'I' => TileKind::Indestructible,
+                'S' => TileKind::Start,
+                'E' => TileKind::Direction {
+                    dir: ArrowDir::Right,
+                    role: DirRole::Entrance,
+                },
+                'X' => TileKind::Direction {
+                    dir: ArrowDir::Right,
+                    role: DirRole::Exit,
+                },
+                'G' => TileKind::Rock {
+                    has_gold: true,
+                    boost: None,
+                },
+                _ => TileKind::Rock {
+                    has_gold: false,
+                    boost: None,
+                },
+            };
+            Tile {
+                hardness: default_hardness(&kind),
+                kind,
+                magma: false,
+            }
+        })
+        .collect();
+    (gs, tiles)
+}
 
-                            let mut dmg = tw.damage as f64;
-                            if new.crit_chance > 0.0 && js_sys::Math::random() < new.crit_chance {
-                                dmg *= new.crit_damage_mult;
-                            }
-                            if dmg < 1.0 {
-                                dmg = 1.0;
-                            }
-                            new.projectiles.push(Projectile {
-                                x: cx,
-                                y: cy,
-                                vx: dx / dist * speed,
-                                vy: dy / dist * speed,
-                                remaining: travel,
-                                damage: dmg.round() as u32,
-                                splash_radius: new.projectile_splash_radius,
-                                apply_debuff: tw.apply_debuff.clone(),
-                            });
-                            tw.cooldown_remaining =
-                                1.0 / (tw.fire_rate * new.tower_fire_rate_global.max(0.01));
-                        }
-                    }
-                }
-                if !new.projectiles.is_empty() {
-                    let mut kills = 0u64;
-                    let mut i = 0;
-                    while i < new.projectiles.len() {
-                        let mut remove = false;
-                        {
-                            let p = &mut new.projectiles[i];
-                            p.x += p.vx * dt;
-                            p.y += p.vy * dt;
-                            p.remaining -= dt;
-                            if p.remaining <= 0.0 {
-                                let ix = p.x;
-                                let iy = p.y;
-                                let splash_radius = p.splash_radius;
-                                let p_damage = p.damage;
-                                let p_debuff = p.apply_debuff.clone();
-                                let mut hit = None;
-                                let mut best = 0.3f64 * 0.3;
-                                for (ei, e) in new.enemies.iter().enumerate() {
-                                    let dx = e.x - ix;
-                                    let dy = e.y - iy;
-                                    let d2 = dx * dx + dy * dy;
-                                    if d2 <= best {
-                                        best = d2;
-                                        hit = Some(ei);
-                                    }
-                                }
-                                if let Some(h) = hit {
-                                    if let Some(e) = new.enemies.get_mut(h) {
-                                        let applied = p_damage.min(e.hp);
-                                        if p_damage >= e.hp {
-                                            e.hp = 0;
-                                        } else {
-                                            e.hp -= p_damage;
-                                        }
-                                        if new.vampiric_heal_percent > 0.0
-                                            && new.life < new.life_max
-                                        {
-                                            let heal = (applied as f64 * new.vampiric_heal_percent)
-                                                .floor()
-                                                as u32;
-                                            if heal > 0 {
-                                                new.life = (new.life + heal).min(new.life_max);
-                                            }
-                                        }
-                                        new.damage_numbers.push(DamageNumber {
-                                            x: e.x,
-                                            y: e.y,
-                                            amount: applied,
-                                            ttl: 0.8,
-                                            is_crit: false,
-                                            is_gold: false,
-                                            is_heal: false,
-                                        });
-
-                                        if let Some(debuff) = &p_debuff {
-                                            let mut applied_debuff = debuff.clone();
-                                            if matches!(debuff.kind, DebuffKind::Slow)
-                                                && new.freeze_chance > 0.0
-                                                && js_sys::Math::random() < new.freeze_chance
-                                            {
-                                                applied_debuff = Debuff {
-                                                    kind: DebuffKind::Freeze,
-                                                    remaining: 2.0,
-                                                    strength: 1.0,
-                                                };
-                                            }
-                                            if let Some(existing) = e
-                                                .debuffs
-                                                .iter_mut()
-                                                .find(|d| d.kind == applied_debuff.kind)
-                                            {
-                                                existing.remaining = applied_debuff.remaining;
-                                                existing.strength =
-                                                    existing.strength.max(applied_debuff.strength);
-                                            } else {
-                                                e.debuffs.push(applied_debuff);
-                                            }
-                                        }
-                                    }
+// Per-second scenario bookkeeping: scripted spawns, then the goal
+fn tick_scenario(rs: &mut RunState) {
+    let Some(run) = rs.scenario.filter(|r| r.outcome.is_none()) else {
+        return;
+    };
+    let def = run.id.def();
+    let secs = rs.stats.time_survived_secs;
+    let due: Vec<ScenarioWave> = def.spawns_at(secs).copied().collect();
+    for w in due {
+        spawn_archetype(rs, w.kind, w.hp, ENEMY_BASE_SPEED, 1.0);
+    }
+    let outcome = match def.goal {
+        ScenarioGoal::SurviveSecs(goal) => (secs >= goal).then_some(ScenarioOutcome::Won),
+        ScenarioGoal::Kills(goal) if rs.stats.enemies_killed >= goal => Some(ScenarioOutcome::Won),
+        // Schedule spent and the field clear: the goal can no longer be met
+        ScenarioGoal::Kills(_) => (secs >= def.last_spawn_secs() && rs.enemies.is_empty())
+            .then_some(ScenarioOutcome::Lost),
+    };
+    if let Some(o) = outcome {
+        rs.scenario = Some(ScenarioRun {
+            outcome: Some(o),
+            ..run
+        });
+        rs.game_over = true;
+        log(
+            Level::Info,
+            &format!("scenario {} {:?} at {}s", def.name, o, secs),
+        );
+    }
+}
+
+pub const ENEMY_BASE_SPEED: f64 = 1.5;
+
+// Enemy strength and numbers as functions of survival time alone, so holding enemies off the
+// loop doesn't hold the run at its opening strength. Kept on the run so a difficulty setting can
+// scale it.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DifficultyCurve {
+    pub base_hp: f64,
+    // HP multiplier per 10 survived seconds
+    pub hp_growth: f64,
+    // Tiles per second gained each survived minute, up to max_speed
+    pub speed_per_min: f64,
+    pub max_speed: f64,
+    // Spawn gap starts at max_interval and closes in on min_interval; after interval_half_secs
+    // half of the difference is gone
+    pub max_interval: f64,
+    pub min_interval: f64,
+    pub interval_half_secs: f64,
+    // Spawns wait while this many enemies are out, a cap that grows each survived minute
+    pub enemy_cap: f64,
+    pub enemy_cap_per_min: f64,
+}
+impl Default for DifficultyCurve {
+    fn default() -> Self {
+        Self {
+            base_hp: 5.0,
+            hp_growth: 1.03,
+            speed_per_min: 0.06,
+            max_speed: 3.0,
+            max_interval: 2.0,
+            min_interval: 0.2,
+            interval_half_secs: 90.0,
+            enemy_cap: 40.0,
+            enemy_cap_per_min: 4.0,
+        }
+    }
+}
+impl DifficultyCurve {
+    pub fn hp_at(&self, secs: f64) -> f64 {
+        self.base_hp * self.hp_growth.powf(secs / 10.0)
+    }
+    pub fn speed_at(&self, secs: f64) -> f64 {
+        (ENEMY_BASE_SPEED + self.speed_per_min * secs / 60.0).min(self.max_speed)
+    }
+    pub fn interval_at(&self, secs: f64) -> f64 {
+        let span = self.max_interval - self.min_interval;
+        self.min_interval + span / (1.0 + secs / self.interval_half_secs)
+    }
+    pub fn enemy_cap_at(&self, secs: f64) -> usize {
+        (self.enemy_cap + self.enemy_cap_per_min * secs / 60.0) as usize
+    }
+}
+// Upgraded players face tougher enemies: HP grows this much per player power level
+pub const HP_PER_POWER_LEVEL: f64 = 0.03;
+
+// HP of a spawn right now, before its archetype's scaling
+pub fn spawn_hp(rs: &RunState) -> u32 {
+    let power_mult = 1.0 + rs.player_power_level * HP_PER_POWER_LEVEL;
+    let secs = rs.stats.time_survived_secs as f64;
+    (rs.difficulty.hp_at(secs) * power_mult).round() as u32
+}
+
+// One spawn event: the kind's speed and size scaling on top of the given baseline, and a full
+// group for swarms. `hp` is per enemy and already final.
+fn spawn_archetype(rs: &mut RunState, kind: EnemyKind, hp: u32, speed: f64, size_scale: f64) {
+    let s = kind.scale();
+    let (speed, size_scale) = (speed * s.speed, size_scale * s.size);
+    if kind == EnemyKind::Swarm {
+        for spread in SWARM_SPEED_SPREAD {
+            spawn_enemy(rs, kind, hp, speed * spread, size_scale);
+        }
+    } else {
+        spawn_enemy(rs, kind, hp, speed, size_scale);
+    }
+}
+
+// New enemy at the Entrance, heading along the loop; at Start while there's no loop. Shared by
+// the spawner and scenario schedules.
+fn spawn_enemy(rs: &mut RunState, kind: EnemyKind, hp: u32, speed: f64, size_scale: f64) {
+    let Some(idx) = rs
+        .tiles
+        .iter()
+        .position(|t| matches!(t.kind, TileKind::Start))
+    else {
+        return;
+    };
+    let w = rs.grid_size.width;
+    let start = Position {
+        x: idx as u32 % w,
+        y: idx as u32 / w,
+    };
+    let (at, next) = match loop_entry_index(rs).filter(|_| rs.path_loop.len() >= 2) {
+        Some(i) => (i, (i + 1) % rs.path_loop.len()),
+        None => (0, 0),
+    };
+    let node = rs.path_loop.get(at).copied().unwrap_or(start);
+    let ahead = rs.path_loop.get(next).copied().unwrap_or(node);
+    let dx = ahead.x as f64 - node.x as f64;
+    let dy = ahead.y as f64 - node.y as f64;
+    let len = (dx * dx + dy * dy).sqrt();
+    let (dir_dx, dir_dy) = if len > 0.0 {
+        (dx / len, dy / len)
+    } else {
+        (1.0, 0.0)
+    };
+    let id = rs.entity_ids.alloc();
+    rs.enemies.push(Enemy {
+        id,
+        x: node.x as f64 + 0.5,
+        y: node.y as f64 + 0.5,
+        speed_tps: speed,
+        hp,
+        max_hp: hp,
+        spawned_at: rs.stats.time_survived_secs,
+        path_index: next,
+        dir_dx,
+        dir_dy,
+        radius_scale: size_scale,
+        loop_dist: rs.loop_entry_dist,
+        debuffs: Vec::new(),
+        kind,
+        hit_flash_ttl: 0.0,
+        spawn_anim_ttl: SPAWN_ANIM_SECS,
+    });
+    rs.stats.enemies_spawned = rs.stats.enemies_spawned.saturating_add(1);
+    wear_scarecrows(rs);
+}
+
+// Seconds between spawns: tightens with survival time, stretched while a scarecrow stands
+pub fn spawn_interval(rs: &RunState) -> f64 {
+    let secs = rs.stats.time_survived_secs as f64;
+    let base = rs.difficulty.interval_at(secs);
+    if rs
+        .structures
+        .iter()
+        .any(|s| s.kind == StructureKind::Scarecrow)
+    {
+        base * SCARECROW_SPAWN_INTERVAL_MULT
+    } else {
+        base
+    }
+}
+
+// Why a structure can't go at (x, y), shared by the reducer and the hotkey feedback
+pub fn structure_placement_error(
+    rs: &RunState,
+    x: u32,
+    y: u32,
+    kind: StructureKind,
+) -> Option<String> {
+    let gs = rs.grid_size;
+    if x >= gs.width || y >= gs.height {
+        return Some("Out of bounds".into());
+    }
+    let idx = (y * gs.width + x) as usize;
+    if !matches!(rs.tiles[idx].kind, TileKind::Rock { .. }) {
+        return Some("Need Rock".into());
+    }
+    if !crate::state::compute_interactable_mask(rs)[idx] {
+        return Some("Out of reach".into());
+    }
+    if rs.tower_at(x, y).is_some() {
+        return Some("Tile has a tower".into());
+    }
+    if rs.structures.iter().any(|s| s.kind == kind) {
+        return Some(format!("Only one {} at a time", kind.name()));
+    }
+    if rs.currencies.research < kind.research_cost() {
+        return Some(format!("Need {} research", kind.research_cost()));
+    }
+    None
+}
+
+// -------- Room stamps --------
+// Multi-tile blueprints bought with tile credits and applied in one action. Offsets are
+// relative to the origin tile at rotation 0; every cell must pass the single-tile rules.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StampId {
+    Pocket,
+    Zigzag,
+    Platform,
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StampTarget {
+    // Mined out, like a rock or wall broken by hand
+    Open,
+    Wall,
+    // Plain rock laid on an empty tile, for towers to stand on
+    Rock,
+}
+pub struct StampDef {
+    pub id: StampId,
+    pub name: &'static str,
+    pub cells: &'static [(i32, i32, StampTarget)],
+}
+use StampTarget::{Open as SO, Rock as SR, Wall as SW};
+pub const STAMP_DEFS: &[StampDef] = &[
+    StampDef {
+        id: StampId::Pocket,
+        name: "Pocket",
+        // 3x3 opening walled on the left, right and top
+        cells: &[
+            (0, 0, SO),
+            (1, 0, SO),
+            (2, 0, SO),
+            (0, 1, SO),
+            (1, 1, SO),
+            (2, 1, SO),
+            (0, 2, SO),
+            (1, 2, SO),
+            (2, 2, SO),
+            (-1, -1, SW),
+            (0, -1, SW),
+            (1, -1, SW),
+            (2, -1, SW),
+            (3, -1, SW),
+            (-1, 0, SW),
+            (-1, 1, SW),
+            (-1, 2, SW),
+            (3, 0, SW),
+            (3, 1, SW),
+            (3, 2, SW),
+        ],
+    },
+    StampDef {
+        id: StampId::Zigzag,
+        name: "Zigzag",
+        cells: &[
+            (0, 0, SO),
+            (1, 0, SO),
+            (1, 1, SO),
+            (2, 1, SO),
+            (2, 2, SO),
+            (3, 2, SO),
+        ],
+    },
+    StampDef {
+        id: StampId::Platform,
+        name: "Platform",
+        cells: &[(0, 0, SR), (1, 0, SR), (0, 1, SR), (1, 1, SR)],
+    },
+];
+// Tile credits per converted tile; cells already of the target kind are free
+pub const STAMP_CREDITS_PER_TILE: u64 = 1;
+
+impl StampId {
+    pub const ALL: [StampId; 3] = [StampId::Pocket, StampId::Zigzag, StampId::Platform];
+    pub fn def(self) -> &'static StampDef {
+        STAMP_DEFS.iter().find(|d| d.id == self).unwrap()
+    }
+}
+
+// Quarter turns clockwise (screen y points down)
+pub fn rotate_offset(dx: i32, dy: i32, rotation: u8) -> (i32, i32) {
+    match rotation % 4 {
+        0 => (dx, dy),
+        1 => (-dy, dx),
+        2 => (-dx, -dy),
+        _ => (dy, -dx),
+    }
+}
+
+// Absolute tiles of a stamp, in definition order; may lie off the grid
+pub fn stamp_cells(
+    id: StampId,
+    origin: (u32, u32),
+    rotation: u8,
+) -> Vec<((i32, i32), StampTarget)> {
+    id.def()
+        .cells
+        .iter()
+        .map(|&(dx, dy, target)| {
+            let (rx, ry) = rotate_offset(dx, dy, rotation);
+            ((origin.0 as i32 + rx, origin.1 as i32 + ry), target)
+        })
+        .collect()
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StampReject {
+    // The cell that failed, when one tile is to blame
+    pub tile: Option<(i32, i32)>,
+    pub reason: String,
+}
+
+// Credit cost of a stamp that would apply cleanly
+pub fn stamp_check(
+    rs: &RunState,
+    origin: (u32, u32),
+    id: StampId,
+    rotation: u8,
+) -> Result<u64, StampReject> {
+    simulate_stamp(rs, origin, id, rotation).map(|(_, cost)| cost)
+}
+
+// Applies the stamp to a scratch copy as it would go by hand: rocks are mined through
+// complete_mining in frontier order, walls go down through lay_walls, then rock is laid with the
+// path checked after each. Returns the stamped run and its credit cost.
+fn simulate_stamp(
+    rs: &RunState,
+    origin: (u32, u32),
+    id: StampId,
+    rotation: u8,
+) -> Result<(RunState, u64), StampReject> {
+    let reject = |tile: (i32, i32), reason: &str| {
+        Err(StampReject {
+            tile: Some(tile),
+            reason: reason.into(),
+        })
+    };
+    let gs = rs.grid_size;
+    let mut open = Vec::new();
+    let mut fill = Vec::new();
+    for ((x, y), target) in stamp_cells(id, origin, rotation) {
+        if x < 0 || y < 0 || x as u32 >= gs.width || y as u32 >= gs.height {
+            return reject((x, y), "Off the map");
+        }
+        let (ux, uy) = (x as u32, y as u32);
+        let idx = (uy * gs.width + ux) as usize;
+        let occupied = rs.towers.iter().any(|t| (t.x, t.y) == (ux, uy))
+            || rs.structures.iter().any(|s| (s.x, s.y) == (ux, uy));
+        match (target, &rs.tiles[idx].kind) {
+            (StampTarget::Open, TileKind::Empty)
+            | (StampTarget::Wall, TileKind::Wall)
+            | (StampTarget::Rock, TileKind::Rock { .. }) => {}
+            _ if occupied => return reject((x, y), "Tile is occupied"),
+            (StampTarget::Open, TileKind::Rock { .. } | TileKind::Wall) => open.push((x, y)),
+            (StampTarget::Wall | StampTarget::Rock, TileKind::Empty) => fill.push((x, y, target)),
+            _ => return reject((x, y), "Can't convert this tile"),
+        }
+    }
+    // Laying walls or rock needs walls unlocked; pure mining stamps don't
+    if let Some(msg) =
+        feature_lock_message(rs.progression_run, Feature::Walls).filter(|_| !fill.is_empty())
+    {
+        return Err(StampReject {
+            tile: None,
+            reason: msg,
+        });
+    }
+    let cost = (open.len() + fill.len()) as u64 * STAMP_CREDITS_PER_TILE;
+    if cost == 0 {
+        return Err(StampReject {
+            tile: None,
+            reason: "Nothing to change".into(),
+        });
+    }
+    if rs.currencies.tile_credits < cost {
+        return Err(StampReject {
+            tile: None,
+            reason: format!("Need {} tile credits", cost),
+        });
+    }
+    let mut sim = rs.clone();
+    sim.currencies.tile_credits -= cost;
+    let at = |x: i32, y: i32| (y as u32 * gs.width + x as u32) as usize;
+    // Interior rocks become reachable as their neighbours are mined
+    while !open.is_empty() {
+        let mask = crate::state::compute_interactable_mask(&sim);
+        let (now, later): (Vec<_>, Vec<_>) = open.into_iter().partition(|&(x, y)| mask[at(x, y)]);
+        if now.is_empty() {
+            return reject(later[0], "Out of reach");
+        }
+        for (x, y) in now {
+            complete_mining(&mut sim, at(x, y));
+        }
+        open = later;
+    }
+    let walls: Vec<Position> = fill
+        .iter()
+        .filter(|&&(_, _, target)| target == StampTarget::Wall)
+        .map(|&(x, y, _)| Position {
+            x: x as u32,
+            y: y as u32,
+        })
+        .collect();
+    lay_walls(&mut sim, &walls);
+    // lay_walls stops at the first wall it can't lay; that tile is the one to blame
+    let unlaid = |p: &&Position| sim.tiles[(p.y * gs.width + p.x) as usize].kind != TileKind::Wall;
+    if let Some(p) = walls.iter().find(unlaid) {
+        let (x, y) = (p.x as i32, p.y as i32);
+        if !crate::state::compute_interactable_mask(&sim)[at(x, y)] {
+            return reject((x, y), "Out of reach");
+        }
+        return reject((x, y), "Would block the path");
+    }
+    let mut rerouted = None;
+    for &(x, y, _) in fill.iter().filter(|f| f.2 == StampTarget::Rock) {
+        if !crate::state::compute_interactable_mask(&sim)[at(x, y)] {
+            return reject((x, y), "Out of reach");
+        }
+        sim.tiles[at(x, y)].kind = TileKind::Rock {
+            has_gold: false,
+            boost: None,
+        };
+        sim.tiles[at(x, y)].hardness = MASON_ROCK_HARDNESS;
+        let path = compute_path(&sim);
+        if path.is_empty() {
+            return reject((x, y), "Would block the path");
+        }
+        rerouted = Some(path);
+    }
+    if let Some(path) = rerouted {
+        install_path(&mut sim, path);
+    }
+    Ok((sim, cost))
+}
+
+// A spawn happened under a scarecrow: it wears down and crumbles when depleted
+fn wear_scarecrows(rs: &mut RunState) {
+    for s in &mut rs.structures {
+        if s.kind == StructureKind::Scarecrow {
+            s.hp = s.hp.saturating_sub(1);
+        }
+    }
+    rs.structures.retain(|s| s.hp > 0);
+}
+
+// Gold to bring a tower back to full durability; 0 when undamaged
+pub fn tower_repair_cost(tw: &Tower) -> u64 {
+    let missing = tw.max_hp - tw.hp;
+    if missing <= 0.0 {
+        return 0;
+    }
+    ((missing * SIEGE_REPAIR_GOLD_PER_HP).ceil() as u64).max(1)
+}
+
+// Siege: enemies whose tile touches a tower's (diagonals included) chip its durability.
+// Crumbled towers are removed without refund; the rock they stood on stays.
+fn siege_contact(rs: &mut RunState, dt: f64) {
+    for tw in &mut rs.towers {
+        let adjacent = rs
+            .enemies
+            .iter()
+            .filter(|e| {
+                (e.x.floor() - tw.x as f64).abs() <= 1.0 && (e.y.floor() - tw.y as f64).abs() <= 1.0
+            })
+            .count();
+        tw.hp -= adjacent as f64 * SIEGE_CONTACT_DPS * dt;
+    }
+    if let Some(tw) = rs.towers.iter().find(|t| t.hp <= 0.0) {
+        let msg = format!("Tower at ({}, {}) crumbled", tw.x, tw.y);
+        log(Level::Info, &msg);
+        rs.siege_notice = Some(msg);
+    }
+    let (standing, crumbled): (Vec<Tower>, Vec<Tower>) = std::mem::take(&mut rs.towers)
+        .into_iter()
+        .partition(|t| t.hp > 0.0);
+    rs.towers = standing;
+    for tw in &crumbled {
+        retire_tower(rs, tw);
+    }
+}
+
+// All in-run research income goes through here so the streak multiplier applies;
+// SetResearch (restoring saved research) deliberately bypasses it
+fn add_research(rs: &mut RunState, base: f64) {
+    let total = base * rs.streak_research_mult() + rs.research_carry;
+    let whole = total.floor();
+    rs.research_carry = total - whole;
+    rs.currencies.research = rs.currencies.research.saturating_add(whole as u64);
+    rs.stats.research_earned = rs.stats.research_earned.saturating_add(whole as u64);
+}
+
+// Trickle for damage that landed, so a maze too weak to finish kills still earns something.
+// Counted in whole damage, so many small hits add up to exactly one point per
+// DAMAGE_PER_RESEARCH; each point then goes through add_research like a kill's.
+fn add_damage_research(rs: &mut RunState, dealt: u64) {
+    if dealt == 0 {
+        return;
+    }
+    let damage = (rs.research_progress * DAMAGE_PER_RESEARCH).round() + dealt as f64;
+    let whole = (damage / DAMAGE_PER_RESEARCH).floor();
+    rs.research_progress = (damage - whole * DAMAGE_PER_RESEARCH) / DAMAGE_PER_RESEARCH;
+    if whole > 0.0 {
+        add_research(rs, whole);
+    }
+}
+// Difficulty scales with: time, loops, AND player power. Enemy HP and speed follow the run's
+// DifficultyCurve instead; this one sizes enemies and sets the clutch odds.
+// This creates a good progression curve:
+// - New players (power=0): Easy enemies, can farm research
+// - Mid players (power=10-20): Moderate challenge
+// - Late players (power=30+): Serious challenge
+pub fn spawn_difficulty(rs: &RunState) -> f64 {
+    let time_factor = rs.stats.time_survived_secs as f64 / 50.0; // Every 50 seconds adds +1 difficulty (much slower!)
+    // Breaches stand in for loops on an End route
+    let loop_factor = rs.stats.loops_completed as f64 + rs.stats.breaches as f64;
+    let base_difficulty = time_factor + loop_factor;
+
+    // Player power scaling: each 15 upgrade levels = +1 difficulty multiplier
+    // This means upgrades make you stronger for longer before difficulty catches up
+    let power_mult = 1.0 + (rs.player_power_level / 15.0);
+    base_difficulty * power_mult
+}
+// Clutch save: a kill with less than CLUTCH_DISTANCE tiles of loop left may refund a life.
+// The odds fall as the run gets harder: (spawn difficulty below, chance), then CLUTCH_CHANCE_HARD
+pub const CLUTCH_DISTANCE: f64 = 1.0;
+pub const CLUTCH_CHANCES: [(f64, f64); 2] = [(3.0, 0.5), (8.0, 0.25)];
+pub const CLUTCH_CHANCE_HARD: f64 = 0.10;
+
+pub fn clutch_chance(difficulty: f64) -> f64 {
+    CLUTCH_CHANCES
+        .iter()
+        .find(|(below, _)| difficulty < *below)
+        .map_or(CLUTCH_CHANCE_HARD, |(_, chance)| *chance)
+}
+// Strictly under CLUTCH_DISTANCE: an enemy a full tile out wasn't that close
+pub fn is_clutch_kill(loop_total: f64, loop_dist: f64) -> bool {
+    loop_total > 0.0 && loop_total - loop_dist < CLUTCH_DISTANCE
+}
+// Rolls a refund for each clutch kill at (x, y); nothing to win at full life
+fn clutch_refunds(rs: &mut RunState, kills: &[(f64, f64)]) {
+    let chance = clutch_chance(spawn_difficulty(rs));
+    let mut rng = SeededRng::new(rs.clutch_rng);
+    for &(x, y) in kills {
+        if rs.life >= rs.life_max {
+            break;
+        }
+        if rng.next_f64() < chance {
+            rs.life += 1;
+            rs.stats.clutch_saves = rs.stats.clutch_saves.saturating_add(1);
+            rs.damage_numbers.push(DamageNumber {
+                x,
+                y,
+                amount: 1,
+                ttl: 0.8,
+                is_crit: false,
+                is_gold: false,
+                is_heal: false,
+                is_clutch: true,
+            });
+        }
+    }
+    rs.clutch_rng = rng.state;
+}
+pub const BANK_INTEREST_PERIOD_SECS: u64 = 10;
+pub const BANK_INTEREST_PER_LEVEL: f64 = 0.03;
+// Largest single payment per Bank level, so a big purse can't compound away
+pub const BANK_INTEREST_CAP_PER_LEVEL: u64 = 50;
+
+// Pays interest on every BANK_INTEREST_PERIOD_SECS-th survived second
+fn tick_bank_interest(rs: &mut RunState) {
+    if rs.bank_interest_rate <= 0.0
+        || !rs
+            .stats
+            .time_survived_secs
+            .is_multiple_of(BANK_INTEREST_PERIOD_SECS)
+    {
+        return;
+    }
+    let interest = ((rs.currencies.gold as f64 * rs.bank_interest_rate).floor() as u64)
+        .min(rs.bank_interest_cap);
+    earn_gold(rs, interest);
+    rs.last_interest = interest;
+    let start = rs.tiles.iter().position(|t| t.kind == TileKind::Start);
+    if let Some(idx) = start.filter(|_| interest > 0) {
+        let x = (idx as u32 % rs.grid_size.width) as f64 + 0.5;
+        let y = (idx as u32 / rs.grid_size.width) as f64 + 0.5;
+        float_text(rs, x, y, format!("+{}g", interest), GOLD_TEXT_COLOR, false);
+    }
+}
+// Auto Miner interval by level; 0 means no passive mining
+pub const AUTO_MINE_SECS: [u64; 3] = [12, 9, 6];
+
+pub fn auto_mine_period(level: u8) -> u64 {
+    match level {
+        0 => 0,
+        l => AUTO_MINE_SECS[(l as usize).min(AUTO_MINE_SECS.len()) - 1],
+    }
+}
+
+// The rock the Auto Miner takes next: the softest interactable Rock orthogonally beside the
+// current path, lowest index on ties. Tiles with a tower or structure on them are left alone,
+// as are the player's walls.
+pub fn auto_mine_target(rs: &RunState, mask: &[bool]) -> Option<usize> {
+    let gs = rs.grid_size;
+    let route = if rs.path_loop.is_empty() {
+        &rs.path
+    } else {
+        &rs.path_loop
+    };
+    let mut best: Option<(u8, usize)> = None;
+    for p in route.iter() {
+        let near = [
+            (p.x + 1 < gs.width).then(|| (p.x + 1, p.y)),
+            (p.x > 0).then(|| (p.x - 1, p.y)),
+            (p.y + 1 < gs.height).then(|| (p.x, p.y + 1)),
+            (p.y > 0).then(|| (p.x, p.y - 1)),
+        ];
+        for (x, y) in near.into_iter().flatten() {
+            let idx = (y * gs.width + x) as usize;
+            let tile = &rs.tiles[idx];
+            if !matches!(tile.kind, TileKind::Rock { .. })
+                || !mask.get(idx).copied().unwrap_or(false)
+                || rs.tower_at(x, y).is_some()
+                || rs.structures.iter().any(|s| (s.x, s.y) == (x, y))
+            {
+                continue;
+            }
+            if best.is_none_or(|b| (tile.hardness, idx) < b) {
+                best = Some((tile.hardness, idx));
+            }
+        }
+    }
+    best.map(|(_, idx)| idx)
+}
+
+// Counts down to the next passive mine, which goes through the same completion as a manual one
+fn tick_auto_miner(rs: &mut RunState) {
+    if rs.auto_mine_period == 0 {
+        return;
+    }
+    rs.auto_mine_cooldown = rs.auto_mine_cooldown.saturating_sub(1);
+    if rs.auto_mine_cooldown > 0 {
+        return;
+    }
+    rs.auto_mine_cooldown = rs.auto_mine_period;
+    let mask = crate::state::compute_interactable_mask(rs);
+    if let Some(idx) = auto_mine_target(rs, &mask) {
+        complete_mining(rs, idx);
+    }
+}
+// Healing tiles: each tower standing on one restores life every HEAL_PULSE_SECS
+pub const HEAL_PULSE_SECS: f64 = 3.0;
+pub const HEAL_PULSE_BASE: f64 = 1.0;
+pub const HEAL_PULSE_PER_POWER: f64 = 0.5;
+pub const HEAL_AURA_RADIUS_PER_LEVEL: f64 = 1.5;
+pub const HEAL_PER_AURA_KILL: f64 = 0.25;
+pub const HEAL_SHIELD_PER_LEVEL: u32 = 2;
+// Shield is temporary: one point wears off every this many seconds
+pub const SHIELD_DECAY_SECS: u64 = 10;
+
+fn healing_towers(rs: &RunState) -> Vec<(f64, f64)> {
+    rs.towers
+        .iter()
+        .filter(|t| t.boost == Some(BoostKind::Healing))
+        .map(|t| (t.x as f64 + 0.5, t.y as f64 + 0.5))
+        .collect()
+}
+
+// Whole points of healing go to life, then past a full life into the shield up to shield_max;
+// fractions wait in heal_accum. The amount restored floats up from (x, y).
+fn heal(rs: &mut RunState, amount: f64, x: f64, y: f64) {
+    rs.heal_accum += amount;
+    let whole = rs.heal_accum.floor();
+    rs.heal_accum -= whole;
+    let whole = whole as u32;
+    let to_life = whole.min(rs.life_max.saturating_sub(rs.life));
+    let to_shield = (whole - to_life).min(rs.shield_max.saturating_sub(rs.shield));
+    rs.life += to_life;
+    rs.shield += to_shield;
+    if to_life + to_shield > 0 {
+        rs.damage_numbers.push(DamageNumber {
+            x,
+            y,
+            amount: to_life + to_shield,
+            ttl: 1.0,
+            is_crit: false,
+            is_gold: false,
+            is_heal: true,
+            is_clutch: false,
+        });
+    }
+}
+
+fn tick_healing(rs: &mut RunState) {
+    if rs.shield > 0
+        && rs
+            .stats
+            .time_survived_secs
+            .is_multiple_of(SHIELD_DECAY_SECS)
+    {
+        rs.shield -= 1;
+    }
+    if rs.healing_tile_heal_per_tick <= 0.0 {
+        return;
+    }
+    rs.healing_tile_timer += 1.0;
+    if rs.healing_tile_timer < HEAL_PULSE_SECS {
+        return;
+    }
+    rs.healing_tile_timer -= HEAL_PULSE_SECS;
+    let healers = healing_towers(rs);
+    if let Some(&(x, y)) = healers.first() {
+        rs.last_heal_pulse_at = Some(rs.sim_time);
+        heal(rs, rs.healing_tile_heal_per_tick * healers.len() as f64, x, y);
+    }
+}
+
+// Healing Aura: each kill within healing_aura_radius of a Healing tower heals a little
+fn aura_heal(rs: &mut RunState, kills: &[(f64, f64)]) {
+    let r2 = rs.healing_aura_radius * rs.healing_aura_radius;
+    for (hx, hy) in healing_towers(rs) {
+        let inside = kills
+            .iter()
+            .filter(|(x, y)| (x - hx).powi(2) + (y - hy).powi(2) <= r2)
+            .count();
+        if inside > 0 {
+            heal(rs, HEAL_PER_AURA_KILL * inside as f64, hx, hy);
+        }
+    }
+}
+// One leak-free second; a full minute extends the streak
+fn tick_clean_streak(rs: &mut RunState) {
+    rs.clean_streak_secs += 1;
+    if rs.clean_streak_secs >= STREAK_MINUTE_SECS {
+        rs.clean_streak_secs = 0;
+        if rs.clean_streak < STREAK_MAX {
+            rs.clean_streak += 1;
+            rs.streak_notice = Some(format!(
+                "🔥 Clean streak {}: research x{:.1}",
+                rs.clean_streak,
+                rs.streak_research_mult()
+            ));
+        }
+    }
+}
+fn break_clean_streak(rs: &mut RunState) {
+    if rs.clean_streak > 0 {
+        rs.streak_notice = Some("Streak lost: an enemy leaked".to_string());
+    }
+    rs.clean_streak = 0;
+    rs.clean_streak_secs = 0;
+}
+
+// In-world label for a tile at high zoom: tower damage (scaled by damage_mul, the prestige
+// bonus), gold yield, hardness, or distance along the loop for path tiles
+pub fn tile_label(
+    tile: &Tile,
+    tower: Option<&Tower>,
+    loop_dist: Option<f64>,
+    gold_mul: f64,
+    damage_mul: f64,
+) -> Option<String> {
+    if let Some(tw) = tower {
+        return Some(format!("{:.0}", tw.shot_damage() * damage_mul));
+    }
+    match tile.kind {
+        TileKind::Rock { has_gold: true, .. } => {
+            Some(format!("{}g", (1.0 * gold_mul).round() as u64))
+        }
+        TileKind::Rock { .. } | TileKind::Wall => Some(tile.hardness.to_string()),
+        TileKind::Empty => loop_dist.map(|d| format!("{:.0}", d)),
+        _ => None,
+    }
+}
+
+// -------- Run Digest --------
+// What changed between two snapshots of the same run
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RunDigest {
+    pub enemies_spawned: u64,
+    pub enemies_killed: u64,
+    pub life_lost: u32,
+    pub gold_gained: u64,
+}
+impl RunDigest {
+    pub fn between(before: &RunState, after: &RunState) -> Self {
+        Self {
+            enemies_spawned: after
+                .stats
+                .enemies_spawned
+                .saturating_sub(before.stats.enemies_spawned),
+            enemies_killed: after
+                .stats
+                .enemies_killed
+                .saturating_sub(before.stats.enemies_killed),
+            life_lost: before.life.saturating_sub(after.life),
+            gold_gained: after.currencies.gold.saturating_sub(before.currencies.gold),
+        }
+    }
+    pub fn summary(&self) -> String {
+        let mut out = format!(
+            "{} enemies spawned, {} killed",
+            self.enemies_spawned, self.enemies_killed
+        );
+        if self.life_lost > 0 {
+            out.push_str(&format!(", life -{}", self.life_lost));
+        }
+        out
+    }
+}
+
+// -------- Background Catch-up --------
+// Game speeds on offer. At speed N every SimTick covers N times its dt and every TickSecond
+// runs N seconds, so the survived clock keeps pace with the world.
+pub const SIM_SPEEDS: [f64; 3] = [1.0, 2.0, 4.0];
+
+// The next offered speed up or down from `current`, staying put at either end
+pub fn step_sim_speed(current: f64, faster: bool) -> f64 {
+    let i = SIM_SPEEDS.iter().position(|&s| s == current).unwrap_or(0);
+    let j = if faster {
+        (i + 1).min(SIM_SPEEDS.len() - 1)
+    } else {
+        i.saturating_sub(1)
+    };
+    SIM_SPEEDS[j]
+}
+
+// Returning to a throttled tab simulates at most this much of the missed time
+pub const CATCH_UP_MAX_SECS: f64 = 5.0;
+// Coarser than the 16ms live tick so catch-up finishes quickly
+pub const CATCH_UP_STEP_SECS: f64 = 0.05;
+// SimTicks dispatched per animation frame while catching up
+pub const CATCH_UP_STEPS_PER_FRAME: u32 = 10;
+// The opt-in long catch-up: minutes of missed time in coarser steps and bigger batches
+pub const LONG_CATCH_UP_MAX_SECS: f64 = 300.0;
+pub const LONG_CATCH_UP_STEP_SECS: f64 = 0.1;
+pub const LONG_CATCH_UP_STEPS_PER_FRAME: u32 = 50;
+// Floating numbers kept after a catch-up batch; older ones are dropped
+pub const CATCH_UP_MAX_DAMAGE_NUMBERS: usize = 24;
+// Missed time shorter than this is not worth a catch-up
+const CATCH_UP_MIN_SECS: f64 = 0.5;
+
+// How much hidden-tab time is replayed. Capped keeps the fairness cap; Long is the player
+// setting that lets the run carry on for up to five minutes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CatchUpMode {
+    #[default]
+    Capped,
+    Long,
+}
+
+impl CatchUpMode {
+    pub fn max_secs(self) -> f64 {
+        match self {
+            Self::Capped => CATCH_UP_MAX_SECS,
+            Self::Long => LONG_CATCH_UP_MAX_SECS,
+        }
+    }
+    pub fn step_secs(self) -> f64 {
+        match self {
+            Self::Capped => CATCH_UP_STEP_SECS,
+            Self::Long => LONG_CATCH_UP_STEP_SECS,
+        }
+    }
+    pub fn steps_per_frame(self) -> u32 {
+        match self {
+            Self::Capped => CATCH_UP_STEPS_PER_FRAME,
+            Self::Long => LONG_CATCH_UP_STEPS_PER_FRAME,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CatchUpPlan {
+    // SimTick count for each consecutive frame
+    pub batches: Vec<u32>,
+    pub step_secs: f64,
+    pub simulated_secs: f64,
+    pub discarded_secs: f64,
+}
+pub fn plan_catch_up(elapsed_secs: f64) -> CatchUpPlan {
+    plan_catch_up_in(CatchUpMode::Capped, elapsed_secs)
+}
+pub fn plan_long_catch_up(elapsed_secs: f64) -> CatchUpPlan {
+    plan_catch_up_in(CatchUpMode::Long, elapsed_secs)
+}
+fn plan_catch_up_in(mode: CatchUpMode, elapsed_secs: f64) -> CatchUpPlan {
+    let step_secs = mode.step_secs();
+    let elapsed = if elapsed_secs.is_finite() {
+        elapsed_secs.max(0.0)
+    } else {
+        0.0
+    };
+    if elapsed < CATCH_UP_MIN_SECS {
+        return CatchUpPlan {
+            step_secs,
+            discarded_secs: elapsed,
+            ..Default::default()
+        };
+    }
+    let capped = elapsed.min(mode.max_secs());
+    let steps = (capped / step_secs).round() as u32;
+    let mut batches = Vec::new();
+    let mut left = steps;
+    while left > 0 {
+        let n = left.min(mode.steps_per_frame());
+        batches.push(n);
+        left -= n;
+    }
+    let simulated = steps as f64 * step_secs;
+    CatchUpPlan {
+        batches,
+        step_secs,
+        simulated_secs: simulated,
+        discarded_secs: (elapsed - simulated).max(0.0),
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MetaRecords {
+    #[serde(default)]
+    pub best_time_secs: u64,
+    #[serde(default)]
+    pub best_loops: u32,
+    #[serde(default)]
+    pub best_blocks_mined: u32,
+    #[serde(default)]
+    pub total_runs: u64,
+    // Best research income rate over runs of at least TRICKLE_MIN_RUN_SECS
+    #[serde(default)]
+    pub best_research_per_min: f64,
+    // Best stars per scenario, by ScenarioId::key
+    #[serde(default)]
+    pub scenario_stars: std::collections::HashMap<String, u8>,
+}
+
+impl MetaRecords {
+    pub fn update_from_stats(&mut self, stats: &RunStats) -> Vec<&'static str> {
+        let mut new_records = Vec::new();
+        self.total_runs += 1;
+        if stats.time_survived_secs > self.best_time_secs {
+            self.best_time_secs = stats.time_survived_secs;
+            new_records.push("time");
+        }
+        if stats.loops_completed > self.best_loops {
+            self.best_loops = stats.loops_completed;
+            new_records.push("loops");
+        }
+        if stats.blocks_mined > self.best_blocks_mined {
+            self.best_blocks_mined = stats.blocks_mined;
+            new_records.push("blocks");
+        }
+        if stats.time_survived_secs >= TRICKLE_MIN_RUN_SECS {
+            let rate = stats.research_earned as f64 * 60.0 / stats.time_survived_secs as f64;
+            self.best_research_per_min = self.best_research_per_min.max(rate);
+        }
+        new_records
+    }
+    pub fn stars(&self, id: ScenarioId) -> u8 {
+        self.scenario_stars.get(id.key()).copied().unwrap_or(0)
+    }
+    // Keeps the best result; true when `stars` beats it
+    pub fn record_scenario(&mut self, id: ScenarioId, stars: u8) -> bool {
+        let better = stars > self.stars(id);
+        if better {
+            self.scenario_stars.insert(id.key().into(), stars);
+        }
+        better
+    }
+}
+
+// -------- Passive research trickle --------
+// While no run is live, research trickles in at a fraction of the best run's income rate
+pub const TRICKLE_EFFICIENCY: f64 = 0.10;
+// Shorter runs are too noisy to set the rate
+pub const TRICKLE_MIN_RUN_SECS: u64 = 60;
+pub const TRICKLE_SESSION_CAP_SECS: f64 = 30.0 * 60.0;
+
+pub fn trickle_rate_per_min(records: &MetaRecords) -> f64 {
+    records.best_research_per_min * TRICKLE_EFFICIENCY
+}
+
+// A started run that hasn't ended; paused runs count as live
+pub fn run_is_live(rs: &RunState) -> bool {
+    rs.started && !rs.game_over
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ResearchTrickle {
+    // Fractional research not yet paid out; persisted across reloads
+    pub carry: f64,
+    // Idle time accumulated this session, up to the cap
+    pub session_secs: f64,
+}
+
+impl ResearchTrickle {
+    pub fn capped(&self) -> bool {
+        self.session_secs >= TRICKLE_SESSION_CAP_SECS
+    }
+
+    // Advances the clock by dt seconds and returns whole research points to pay out
+    pub fn advance(&mut self, rate_per_min: f64, dt_secs: f64, live: bool) -> u64 {
+        if live || rate_per_min <= 0.0 {
+            return 0;
+        }
+        let dt = dt_secs
+            .min(TRICKLE_SESSION_CAP_SECS - self.session_secs)
+            .max(0.0);
+        self.session_secs += dt;
+        self.carry += rate_per_min * dt / 60.0;
+        let whole = self.carry.floor();
+        self.carry -= whole;
+        whole as u64
+    }
+}
+
+pub fn calculate_boost_multipliers(
+    boost: Option<BoostKind>,
+    ups: &UpgradeState,
+) -> (f64, f64, f64) {
+    // Returns (range_mult, damage_mult, fire_rate_mult)
+    let Some(b) = boost else {
+        return (1.0, 1.0, 1.0);
+    };
+
+    use UpgradeId::*;
+    match b {
+        BoostKind::Damage => {
+            // Poison tiles: damage boost + range boost
+            let damage = 1.0 + 0.05 * ups.level(BoostPoisonDamage) as f64;
+            let range = 1.0 + 0.12 * ups.level(BoostPoisonRange) as f64;
+            (range, damage, 1.0)
+        }
+        BoostKind::Range | BoostKind::FireRate => {
+            // No upgrades yet; the flat bonus is in boost_intrinsics
+            (1.0, 1.0, 1.0)
+        }
+        BoostKind::Healing => {
+            // Healing upgrades act on the player's life, not the tower
+            (1.0, 1.0, 1.0)
+        }
+        BoostKind::Slow => {
+            // Cold tiles: range boost (compensates for short base range)
+            let range = 1.0 + 0.12 * ups.level(BoostColdRange) as f64;
+            (range, 1.0, 1.0)
+        }
+        BoostKind::Fire => {
+            // Fire tiles: range boost
+            let range = 1.0 + 0.12 * ups.level(BoostFireRange) as f64;
+            (range, 1.0, 1.0)
+        }
+    }
+}
+
+// Helper function to calculate debuff to apply from tower boost
+pub fn calculate_debuff_from_boost(boost: Option<BoostKind>, ups: &UpgradeState) -> Option<Debuff> {
+    let Some(b) = boost else {
+        return None;
+    };
+
+    use UpgradeId::*;
+    match b {
+        BoostKind::Slow => {
+            // Cold tiles apply slow debuff
+            let base_slow = 0.5; // 50% slow baseline
+            let (mult, extra_secs) = cold_slow_scaling(ups);
+            Some(Debuff {
+                kind: DebuffKind::Slow,
+                remaining: 1.0 + extra_secs,
+                strength: base_slow * mult,
+            })
+        }
+        BoostKind::Damage => {
+            // Poison tiles apply poison DoT debuff
+            let base_dps = 1.0;
+            let dps = base_dps * (1.0 + 0.05 * ups.level(BoostPoisonDamage) as f64);
+            let duration = 2.0 + 1.0 * ups.level(BoostPoisonDuration) as f64;
+            Some(Debuff {
+                kind: DebuffKind::Poison,
+                remaining: duration,
+                strength: dps,
+            })
+        }
+        BoostKind::Fire => {
+            // Fire tiles apply burn DoT debuff
+            let base_dps = 0.5; // Weaker than poison (0.5 vs 1.0) since it can spread
+            let dps = base_dps * (1.0 + 0.10 * ups.level(BoostFireDamage) as f64);
+            let duration = 3.0 + 1.0 * ups.level(BoostFireDuration) as f64;
+            Some(Debuff {
+                kind: DebuffKind::Burn,
+                remaining: duration,
+                strength: dps,
+            })
+        }
+        BoostKind::Range | BoostKind::FireRate | BoostKind::Healing => {
+            // These boosts don't apply debuffs
+            None
+        }
+    }
+}
+
+pub fn apply_upgrades_to_run(run: &mut RunState, ups: &UpgradeState) {
+    use UpgradeId::*;
+    let l = |id: UpgradeId| ups.level(id) as f64;
+
+    // Calculate player power level based on total upgrade levels
+    // This is used to scale enemy difficulty appropriately
+    let mut total_upgrade_levels = 0u32;
+    for def in UPGRADE_DEFS {
+        total_upgrade_levels += ups.level(def.id) as u32;
+    }
+    run.player_power_level = total_upgrade_levels as f64;
+
+    // Pre-calculate debuff templates for towers placed mid-game
+    run.cold_debuff_template = calculate_debuff_from_boost(Some(BoostKind::Slow), ups);
+    run.poison_debuff_template = calculate_debuff_from_boost(Some(BoostKind::Damage), ups);
+    run.fire_debuff_template = calculate_debuff_from_boost(Some(BoostKind::Fire), ups);
+    run.freeze_chance = FREEZE_CHANCE_PER_LEVEL * l(BoostColdFreezeChance);
+    (run.cold_slow_multiplier, run.cold_slow_duration) = cold_slow_scaling(ups);
+    run.poison_spread = ups.level(BoostPoisonSpread) > 0;
+
+    // Calculate fire spread radius (BoostFireSpread upgrade)
+    run.fire_spread_radius = if ups.level(BoostFireSpread) > 0 {
+        ups.level(BoostFireSpread) as f64
+    } else {
+        0.0
+    };
+
+    let prestige = ups.meta.multiplier();
+    run.mining_speed = 2.0 * (1.0 + 0.08 * l(MiningSpeed)) * prestige;
+    run.tower_base_damage = (2.0 * (1.0 + 0.12 * l(TowerDamage1))) as u32;
+    run.prestige_damage_mult = prestige;
+    run.tower_fire_rate_global = 1.0 + 0.08 * l(FireRate);
+    run.crit_chance = 0.03 * l(CritChance);
+    run.crit_damage_mult = 1.0 + 0.25 * l(CritDamage);
+    run.projectile_speed = 8.0 * (1.0 + 0.15 * l(ProjectileSpeed));
+    run.life_regen_per_sec = 0.5 * l(LifeRegen);
+    run.vampiric_heal_percent = 0.01 * l(VampiricHealing);
+    run.healing_tile_heal_per_tick = if ups.level(BoostHealingUnlock) > 0 {
+        HEAL_PULSE_BASE + HEAL_PULSE_PER_POWER * l(BoostHealingPower)
+    } else {
+        0.0
+    };
+    run.healing_aura_radius = HEAL_AURA_RADIUS_PER_LEVEL * l(BoostHealingRadius);
+    run.shield_max = HEAL_SHIELD_PER_LEVEL * ups.level(BoostHealingShield) as u32;
+    run.shield = run.shield.min(run.shield_max);
+    run.mining_gold_mul = 1.0 + 0.15 * l(GoldTileReward);
+    run.mining_crit_chance = 0.05 * l(MiningCrit);
+    run.gold_bounty_per_kill = ups.level(KillBounty) as u64;
+    run.tower_refund_percent = tower_refund_percent(ups);
+    run.projectile_splash_radius = 0.5 * l(SplashRadius);
+    run.projectile_bounces = ups.level(Bounce);
+    run.bank_interest_rate = BANK_INTEREST_PER_LEVEL * l(BankInterest);
+    run.bank_interest_cap = BANK_INTEREST_CAP_PER_LEVEL * ups.level(BankInterest) as u64;
+    run.auto_mine_period = auto_mine_period(ups.level(AutoMiner));
+    if run.auto_mine_cooldown == 0 || run.auto_mine_cooldown > run.auto_mine_period {
+        run.auto_mine_cooldown = run.auto_mine_period;
+    }
+    run.cheapest_upgrade_cost = UPGRADE_DEFS
+        .iter()
+        .filter(|d| ups.can_purchase(d.id))
+        .filter_map(|d| ups.next_cost(d.id))
+        .min();
+    if run.stats.time_survived_secs == 0 && !run.started {
+        // Apply life & starting gold only once while pre-run (before any survival time or start)
+        run.life_max = 10 + 5 * ups.level(HealthStart) as u32;
+        run.life = run.life_max;
+        let sg_level = ups.level(StartingGold);
+        if sg_level > run.starting_gold_applied_level {
+            let delta_levels = sg_level - run.starting_gold_applied_level;
+            // Each level grants +2 starting gold (matches upgrade definition)
+            run.currencies.gold = run.currencies.gold.saturating_add(2 * delta_levels as u64);
+            run.starting_gold_applied_level = sg_level;
+        }
+    }
+    run.life_max = 10 + 5 * ups.level(HealthStart) as u32; // keep max updated for mid-run effects (no gold change mid-run)
+    if run.life > run.life_max {
+        run.life = run.life_max;
+    }
+    for tw in &mut run.towers {
+        let (rm, base_damage, fr) = match tw.kind {
+            TowerKind::Basic => (1.0, run.tower_base_damage, 1.0),
+            TowerKind::Slow => (1.3, (run.tower_base_damage / 2).max(1), 0.6),
+            TowerKind::Damage => (0.7, run.tower_base_damage.saturating_mul(2), 1.5),
+        };
+        // Apply boost multipliers
+        let (boost_rm, boost_dm, boost_frm) = calculate_boost_multipliers(tw.boost, ups);
+
+        // Flat tile bonuses (matches Tower::new)
+        let (tile_rm, tile_range_add, tile_dm, tile_frm) = boost_intrinsics(tw.boost);
+
+        // The tower's own level composes with everything above; its damage share is applied
+        // per shot (Tower::shot_damage) so it never compounds rounding
+        let (level_rm, _) = tw.level_mults();
+
+        tw.range = (run.tower_base_range * rm * boost_rm * tile_rm + tile_range_add) * level_rm;
+        tw.damage = ((base_damage as f64) * boost_dm * tile_dm).round() as u32;
+        if tw.damage == 0 {
+            tw.damage = 1;
+        }
+        tw.fire_rate = fr * run.tower_fire_rate_global * boost_frm * tile_frm;
+
+        // Calculate debuff to apply from tower's boost
+        tw.apply_debuff = calculate_debuff_from_boost(tw.boost, ups);
+    }
+}
+
+// Base refund rate plus ResourceRecovery, capped at a full refund
+pub const REFUND_PERCENT_PER_LEVEL: u32 = 20;
+
+pub fn tower_refund_percent(ups: &UpgradeState) -> u32 {
+    let bonus = REFUND_PERCENT_PER_LEVEL * ups.level(UpgradeId::ResourceRecovery) as u32;
+    (ups.tower_refund_rate_percent as u32 + bonus).min(100)
+}
+
+// Gold back for selling a tower: rounded down, never more than was paid for it
+pub fn sell_refund(tower_cost: u64, refund_percent: u32, paid: u64) -> u64 {
+    (tower_cost * refund_percent.min(100) as u64 / 100).min(paid)
+}
+
+// -------- Tower comparison --------
+// Effective numbers as the sim uses them, for side-by-side comparisons
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TowerStats {
+    pub damage: f64,
+    pub range: f64,
+    // Shots per second
+    pub fire_rate: f64,
+    // Expected damage per second, crits included
+    pub dps: f64,
+    // Loop tiles within range
+    pub coverage: u32,
+}
+
+// Loop tiles within `range` of the centre of tile (x, y)
+pub fn loop_tiles_in_range(rs: &RunState, x: u32, y: u32, range: f64) -> u32 {
+    let r2 = range * range;
+    rs.path_loop
+        .iter()
+        .filter(|p| {
+            let dx = p.x as f64 - x as f64;
+            let dy = p.y as f64 - y as f64;
+            dx * dx + dy * dy <= r2
+        })
+        .count() as u32
+}
+
+// Low-life warning thresholds, as a percentage of life_max. The gap keeps the warning from
+// flickering while life hovers around one line.
+pub const LOW_LIFE_ENTER_PCT: u32 = 25;
+pub const LOW_LIFE_EXIT_PCT: u32 = 40;
+
+// Next warning state: enters below LOW_LIFE_ENTER_PCT, holds until above LOW_LIFE_EXIT_PCT
+pub fn low_life_next(active: bool, life: u32, life_max: u32) -> bool {
+    let pct = life as u64 * 100;
+    if active {
+        pct <= life_max as u64 * LOW_LIFE_EXIT_PCT as u64
+    } else {
+        pct < life_max as u64 * LOW_LIFE_ENTER_PCT as u64
+    }
+}
+
+// How long the HUD flashes red after life is lost
+pub const LIFE_LOSS_FLASH_SECS: f64 = 0.3;
+
+// One enemy got round: the Leak Forgiven perk or a shield point takes the whole hit, else life
+// drops by `damage` and the run ends once that reaches what is left. True when it wasn't absorbed.
+fn leak_life(rs: &mut RunState, damage: u32) -> bool {
+    if rs.leak_shield {
+        rs.leak_shield = false;
+        return false;
+    }
+    if rs.shield > 0 {
+        rs.shield -= 1;
+        return false;
+    }
+    if rs.life > 0 {
+        rs.life = rs.life.saturating_sub(damage);
+        rs.last_life_loss_at = rs.sim_time;
+        if rs.life == 0 {
+            rs.game_over = true;
+            if let Some(run) = &mut rs.scenario {
+                run.outcome.get_or_insert(ScenarioOutcome::Lost);
+            }
+            log(
+                Level::Info,
+                &format!(
+                    "run {} over at {}s, {} loops, {} breaches",
+                    rs.run_id,
+                    rs.stats.time_survived_secs,
+                    rs.stats.loops_completed,
+                    rs.stats.breaches
+                ),
+            );
+        }
+    }
+    true
+}
+
+// Consecutive loop tiles that no tower reaches; `start` indexes path_loop
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UncoveredStretch {
+    pub start: usize,
+    pub len: usize,
+    pub mid: Position,
+}
+
+// Per path_loop entry: whether any tower reaches it
+fn loop_covered(rs: &RunState) -> Vec<bool> {
+    rs.path_loop
+        .iter()
+        .map(|p| {
+            rs.towers.iter().any(|tw| {
+                let dx = p.x as f64 - tw.x as f64;
+                let dy = p.y as f64 - tw.y as f64;
+                dx * dx + dy * dy <= tw.range * tw.range
+            })
+        })
+        .collect()
+}
+
+// Fraction of loop tiles within range of at least one tower; overlapping towers count a
+// tile once
+pub fn loop_coverage(rs: &RunState) -> f64 {
+    let covered = loop_covered(rs);
+    if covered.is_empty() {
+        return 0.0;
+    }
+    covered.iter().filter(|c| **c).count() as f64 / covered.len() as f64
+}
+
+// Longest uncovered stretch of the loop, wrapping past its end; the first one wins ties
+pub fn largest_uncovered_stretch(rs: &RunState) -> Option<UncoveredStretch> {
+    let n = rs.path_loop.len();
+    let covered = loop_covered(rs);
+    let stretch = |start: usize, len: usize| UncoveredStretch {
+        start,
+        len,
+        mid: rs.path_loop[(start + len / 2) % n],
+    };
+    let Some(first) = covered.iter().position(|c| *c) else {
+        return (n > 0).then(|| stretch(0, n));
+    };
+    // Scan one lap from just after a covered tile, so a stretch across the seam counts once
+    let mut best: Option<(usize, usize)> = None;
+    let (mut start, mut len) = (0, 0);
+    for k in 1..=n {
+        let i = (first + k) % n;
+        if !covered[i] {
+            if len == 0 {
+                start = i;
+            }
+            len += 1;
+        } else if len > 0 {
+            if best.is_none_or(|(_, l)| len > l) {
+                best = Some((start, len));
+            }
+            len = 0;
+        }
+    }
+    best.map(|(start, len)| stretch(start, len))
+}
+
+// Average damage multiplier from crits: a crit_chance share of shots deal crit_damage_mult
+pub fn crit_expectation(rs: &RunState) -> f64 {
+    1.0 + rs.crit_chance.clamp(0.0, 1.0) * (rs.crit_damage_mult - 1.0)
+}
+
+// (damage, fire_rate, dps) with the same floor and cooldown as the firing code in SimTick
+fn tower_output(rs: &RunState, tw: &Tower) -> (f64, f64, f64) {
+    let damage = rs.shot_damage(tw).max(1.0);
+    let fire_rate = tw.fire_rate * rs.tower_fire_rate_global.max(0.01);
+    (damage, fire_rate, damage * crit_expectation(rs) * fire_rate)
+}
+
+pub fn tower_stats(rs: &RunState, tw: &Tower) -> TowerStats {
+    let (damage, fire_rate, dps) = tower_output(rs, tw);
+    TowerStats {
+        damage,
+        range: tw.range,
+        fire_rate,
+        dps,
+        coverage: loop_tiles_in_range(rs, tw.x, tw.y, tw.range),
+    }
+}
+
+// Theoretical damage per second of every tower firing nonstop, crits included
+pub fn total_tower_dps(rs: &RunState) -> f64 {
+    rs.towers.iter().map(|tw| tower_output(rs, tw).2).sum()
+}
+
+// Per-tower levels bought with gold; each adds a flat share of the tower's damage and range
+pub const TOWER_MAX_LEVEL: u8 = 5;
+pub const TOWER_LEVEL_DAMAGE_BONUS: f64 = 0.25;
+pub const TOWER_LEVEL_RANGE_BONUS: f64 = 0.05;
+pub const TOWER_LEVEL_COST_GROWTH: f64 = 1.5;
+
+pub fn tower_level_mults(level: u8) -> (f64, f64) {
+    let l = level as f64;
+    (
+        1.0 + TOWER_LEVEL_RANGE_BONUS * l,
+        1.0 + TOWER_LEVEL_DAMAGE_BONUS * l,
+    )
+}
+
+// Each tower standing makes the next one dearer, so covering the maze takes an economy
+pub const TOWER_COST_GROWTH: f64 = 1.15;
+// Tile credits (earned by mining) a new tower takes; selling doesn't give them back
+pub const TOWER_TILE_CREDITS: u64 = 1;
+
+pub fn scaled_tower_cost(base: u64, standing: usize) -> u64 {
+    (base as f64 * TOWER_COST_GROWTH.powi(standing.min(i32::MAX as usize) as i32)).round() as u64
+}
+
+// Gold for the tower's next level: its kind's price grown TOWER_LEVEL_COST_GROWTH per level
+// already held; None at the cap
+pub fn tower_upgrade_cost(rs: &RunState, tw: &Tower) -> Option<u64> {
+    (tw.level < TOWER_MAX_LEVEL).then(|| {
+        (rs.tower_cost_of(&tw.kind) as f64 * TOWER_LEVEL_COST_GROWTH.powi(tw.level as i32)).round()
+            as u64
+    })
+}
+
+// Moves a tower to `level`, rescaling its range; the damage share follows from the level
+fn set_tower_level(tw: &mut Tower, level: u8) {
+    tw.range *= tower_level_mults(level).0 / tw.level_mults().0;
+    tw.level = level;
+}
+
+// What UpgradeTower would leave on the tile, for the before/after preview; None at the cap
+pub fn leveled_tower(tw: &Tower) -> Option<Tower> {
+    (tw.level < TOWER_MAX_LEVEL).then(|| {
+        let mut out = tw.clone();
+        set_tower_level(&mut out, tw.level + 1);
+        out
+    })
+}
+
+// What ReplaceTowerKind would leave on the tile: a fresh tower of the new kind that keeps
+// the old one's veterancy, level, targeting, zone setting and durability
+pub fn swapped_tower(rs: &RunState, tw: &Tower, kind: TowerKind) -> Tower {
+    let paid = rs.tower_cost_of(&kind);
+    let mut out = build_tower(rs, tw.x, tw.y, kind, tw.boost);
+    out.id = tw.id;
+    out.damage_dealt = tw.damage_dealt;
+    out.kills = tw.kills;
+    out.here = tw.here;
+    out.ignore_exclusion = tw.ignore_exclusion;
+    out.target_mode = tw.target_mode;
+    out.hp = tw.hp;
+    out.max_hp = tw.max_hp;
+    out.paid = paid;
+    set_tower_level(&mut out, tw.level);
+    out
+}
+
+// Gold a kind swap costs: the new kind's price less the old tower's sale refund
+pub fn swap_cost(rs: &RunState, tw: &Tower, kind: &TowerKind) -> u64 {
+    let refund = sell_refund(rs.tower_cost_of(&tw.kind), rs.tower_refund_percent, tw.paid);
+    rs.tower_cost_of(kind).saturating_sub(refund)
+}
+
+// A new tower as placement builds it: kind stats plus the tile boost's debuff
+fn build_tower(rs: &RunState, x: u32, y: u32, kind: TowerKind, boost: Option<BoostKind>) -> Tower {
+    let mut tower = Tower::new(x, y, kind, rs.tower_base_range, rs.tower_base_damage, boost);
+    // Set debuff based on boost type using pre-calculated templates
+    tower.apply_debuff = match boost {
+        Some(BoostKind::Slow) => rs.cold_debuff_template.clone(),
+        Some(BoostKind::Damage) => rs.poison_debuff_template.clone(),
+        Some(BoostKind::Fire) => rs.fire_debuff_template.clone(),
+        _ => None,
+    };
+    tower
+}
+
+// Scenario tower cap reached
+pub fn at_tower_limit(rs: &RunState) -> bool {
+    rs.scenario
+        .and_then(|r| r.id.def().max_towers)
+        .is_some_and(|max| rs.towers.len() >= max)
+}
+
+// Whether a tower of some kind can go on a tile right now, and if not the first reason why
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PlacementResult {
+    Valid,
+    // Off the board, or not next to the open area
+    OutOfReach,
+    GameOver,
+    // Towers stand on Rock or Wall only
+    WrongTile,
+    // A tower or structure is already there
+    Occupied,
+    TooHot,
+    // Towers not unlocked yet in progression; holds the lock message
+    Locked(String),
+    TowerLimit,
+    NeedGold(u64),
+    NeedTileCredit,
+}
+
+impl PlacementResult {
+    pub fn is_valid(&self) -> bool {
+        *self == PlacementResult::Valid
+    }
+    // Placement feedback; empty when valid
+    pub fn message(&self) -> String {
+        match self {
+            PlacementResult::Valid => String::new(),
+            PlacementResult::OutOfReach => "Out of reach".to_string(),
+            PlacementResult::GameOver => "Game Over".to_string(),
+            PlacementResult::WrongTile => "Need Rock/Wall".to_string(),
+            PlacementResult::Occupied => "Tile taken".to_string(),
+            PlacementResult::TooHot => "Too hot: next to lava".to_string(),
+            PlacementResult::Locked(msg) => msg.clone(),
+            PlacementResult::TowerLimit => "Tower limit reached".to_string(),
+            PlacementResult::NeedGold(cost) => format!("Need {} gold", cost),
+            PlacementResult::NeedTileCredit => "Need a tile credit: mine some rock".to_string(),
+        }
+    }
+}
+
+// The one placement rule, shared by the hover ghost, the hotkey and the reducer
+pub fn placement_check(rs: &RunState, x: u32, y: u32, kind: &TowerKind) -> PlacementResult {
+    let mask = crate::state::compute_interactable_mask(rs);
+    placement_check_with_mask(rs, &mask, x, y, kind)
+}
+
+// placement_check against an interactable mask the caller already holds (the draw's cached one)
+pub fn placement_check_with_mask(
+    rs: &RunState,
+    mask: &[bool],
+    x: u32,
+    y: u32,
+    kind: &TowerKind,
+) -> PlacementResult {
+    let site = site_check(rs, mask, x, y);
+    if !site.is_valid() {
+        return site;
+    }
+    if let Some(msg) = feature_lock_message(rs.progression_run, Feature::Towers) {
+        return PlacementResult::Locked(msg);
+    }
+    if at_tower_limit(rs) {
+        return PlacementResult::TowerLimit;
+    }
+    rs.tower_shortfall(kind).unwrap_or(PlacementResult::Valid)
+}
+
+// Whether a tower may stand on (x, y), leaving out what building one costs; moves use it alone
+fn site_check(rs: &RunState, mask: &[bool], x: u32, y: u32) -> PlacementResult {
+    let gs = rs.grid_size;
+    if x >= gs.width || y >= gs.height {
+        return PlacementResult::OutOfReach;
+    }
+    let idx = (y * gs.width + x) as usize;
+    if !mask[idx] {
+        return PlacementResult::OutOfReach;
+    }
+    if rs.game_over {
+        return PlacementResult::GameOver;
+    }
+    if !matches!(rs.tiles[idx].kind, TileKind::Rock { .. } | TileKind::Wall) {
+        return PlacementResult::WrongTile;
+    }
+    if rs.tower_at(x, y).is_some() || rs.structures.iter().any(|s| s.x == x && s.y == y) {
+        return PlacementResult::Occupied;
+    }
+    if too_hot_for_tower(rs, x, y) {
+        return PlacementResult::TooHot;
+    }
+    PlacementResult::Valid
+}
+
+// The tower `kind` would be on (x, y), tile boost included, for previewing its reach
+pub fn preview_tower(rs: &RunState, x: u32, y: u32, kind: TowerKind) -> Tower {
+    let idx = (y * rs.grid_size.width + x) as usize;
+    let boost = match &rs.tiles[idx].kind {
+        TileKind::Rock { boost, .. } => *boost,
+        _ => None,
+    };
+    build_tower(rs, x, y, kind, boost)
+}
+
+// The boost a tower moved to (x, y) would stand on; None when a new tower couldn't stand there
+fn move_target_boost(rs: &RunState, x: u32, y: u32) -> Option<Option<BoostKind>> {
+    let mask = crate::state::compute_interactable_mask(rs);
+    if !site_check(rs, &mask, x, y).is_valid() {
+        return None;
+    }
+    match &rs.tiles[(y * rs.grid_size.width + x) as usize].kind {
+        TileKind::Rock { boost, .. } => Some(*boost),
+        _ => Some(None),
+    }
+}
+
+// Whether MoveTower { from, to } would go through
+pub fn can_move_tower(rs: &RunState, from: (u32, u32), to: (u32, u32)) -> bool {
+    rs.tower_at(from.0, from.1).is_some() && move_target_boost(rs, to.0, to.1).is_some()
+}
+
+// Shared by PlaceTower and PlaceTowerAndStart; returns whether a tower was placed
+fn place_tower(rs: &mut RunState, x: u32, y: u32, kind: TowerKind) -> bool {
+    if !placement_check(rs, x, y, &kind).is_valid() {
+        return false;
+    }
+    let paid = if rs.free_towers > 0 {
+        rs.free_towers -= 1;
+        0
+    } else {
+        let cost = rs.current_tower_cost(&kind);
+        spend_gold(rs, cost);
+        cost
+    };
+    rs.currencies.tile_credits -= TOWER_TILE_CREDITS;
+    rs.stats.towers_built += 1;
+    let mut tower = preview_tower(rs, x, y, kind);
+    tower.id = rs.entity_ids.alloc();
+    tower.paid = paid;
+    rs.towers.push(tower);
+    true
+}
+
+// Reaps kills, then passes DoTs on from the dead: a burning enemy scorches everyone within
+// fire_spread_radius at half strength, and with Poison Spread a poisoned one hands its
+// remaining poison to the nearest survivor within POISON_SPREAD_RADIUS
+fn reap_and_spread(rs: &mut RunState) {
+    let mut burns: Vec<(f64, f64, Debuff)> = Vec::new();
+    let mut poisons: Vec<(f64, f64, Debuff)> = Vec::new();
+    for e in rs.enemies.iter().filter(|e| e.hp == 0) {
+        for d in e.debuffs.iter().filter(|d| d.remaining > 0.0) {
+            match d.kind {
+                DebuffKind::Burn if rs.fire_spread_radius > 0.0 => {
+                    burns.push((e.x, e.y, d.clone()))
+                }
+                DebuffKind::Poison if rs.poison_spread => poisons.push((e.x, e.y, d.clone())),
+                _ => {}
+            }
+        }
+    }
+    // Remove the dead first so nothing spreads onto them
+    reap_kills(rs);
+    let spread_radius_sq = rs.fire_spread_radius * rs.fire_spread_radius;
+    for (sx, sy, burn) in burns {
+        for e in &mut rs.enemies {
+            let dx = e.x - sx;
+            let dy = e.y - sy;
+            if dx * dx + dy * dy <= spread_radius_sq {
+                // Weakened burn (50% strength, 50% duration); an existing burn keeps the stronger
+                inflict(
+                    e,
+                    Debuff {
+                        kind: DebuffKind::Burn,
+                        remaining: burn.remaining * 0.5,
+                        strength: burn.strength * 0.5,
+                    },
+                );
+            }
+        }
+    }
+    for (sx, sy, poison) in poisons {
+        let nearest = rs
+            .enemies
+            .iter_mut()
+            .map(|e| ((e.x - sx).powi(2) + (e.y - sy).powi(2), e))
+            .filter(|(d2, _)| *d2 <= POISON_SPREAD_RADIUS * POISON_SPREAD_RADIUS)
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+        if let Some((_, e)) = nearest {
+            inflict(e, poison);
+        }
+    }
+}
+
+// One second of the survived clock and everything paced by it
+fn tick_second(rs: &mut RunState) {
+    rs.stats.time_survived_secs = rs.stats.time_survived_secs.saturating_add(1);
+    tick_clean_streak(rs);
+    tick_bank_interest(rs);
+    tick_auto_miner(rs);
+    tick_hints(rs);
+    tick_scenario(rs);
+    rs.heat.decay(HEAT_DECAY_PER_SEC);
+    rs.perk_mining_secs = rs.perk_mining_secs.saturating_sub(1);
+    if rs.life < rs.life_max && rs.life_regen_per_sec > 0.0 {
+        rs.life_regen_accum += rs.life_regen_per_sec;
+        if rs.life_regen_accum >= 1.0 {
+            let gain = rs.life_regen_accum.floor() as u32;
+            rs.life_regen_accum -= gain as f64;
+            rs.life = (rs.life + gain).min(rs.life_max);
+        }
+    }
+    tick_healing(rs);
+}
+
+// Moves projectiles and resolves impacts. Projectiles that outlive PROJECTILE_MAX_LIFETIME_SECS,
+// stray past the grid, or exceed PROJECTILE_CAP (oldest first) are dropped without hitting
+// Removes dead enemies and pays for them: kill count, research, bounty gold, clutch refunds
+fn reap_kills(rs: &mut RunState) {
+    let total = rs.loop_total_length;
+    let lap = rs.lap_frame();
+    let manual = rs.manual_collection;
+    let mut kills = 0u64;
+    let mut kill_research = 0.0;
+    let mut clutch_kills: Vec<(f64, f64)> = Vec::new();
+    // Where each kill fell and its research; dropped as pickups under manual collection
+    let mut drops: Vec<(f64, f64, f64)> = Vec::new();
+    let aura = rs.healing_aura_radius > 0.0;
+    let mut aura_kills: Vec<(f64, f64)> = Vec::new();
+    rs.enemies.retain(|e| {
+        if e.hp == 0 {
+            kills = kills.saturating_add(1);
+            if aura {
+                aura_kills.push((e.x, e.y));
+            }
+            kill_research += e.kind.research_reward();
+            drops.push((e.x, e.y, e.kind.research_reward()));
+            if is_clutch_kill(total, lap.progress(e.loop_dist)) {
+                clutch_kills.push((e.x, e.y));
+            }
+            false
+        } else {
+            true
+        }
+    });
+    if !clutch_kills.is_empty() {
+        clutch_refunds(rs, &clutch_kills);
+    }
+    if !aura_kills.is_empty() {
+        aura_heal(rs, &aura_kills);
+    }
+    if kills > 0 {
+        rs.stats.enemies_killed = rs.stats.enemies_killed.saturating_add(kills);
+        if manual {
+            for (x, y, research) in drops {
+                let gold = rs.gold_bounty_per_kill;
+                drop_pickup(
+                    rs,
+                    Pickup {
+                        x,
+                        y,
+                        research,
+                        gold,
+                        ttl: PICKUP_TTL_SECS,
+                    },
+                );
+            }
+        } else {
+            add_research(rs, kill_research);
+            if rs.gold_bounty_per_kill > 0 {
+                earn_gold(rs, kills * rs.gold_bounty_per_kill);
+            }
+            let mult = rs.streak_research_mult();
+            for (x, y, research) in drops {
+                let text = research_label(research * mult);
+                float_text(rs, x, y, text, RESEARCH_TEXT_COLOR, false);
+            }
+        }
+    }
+}
+
+// "+1 RP", or to a tenth for a share of one (a Swarm member, a streak bonus)
+fn research_label(research: f64) -> String {
+    if (research - research.round()).abs() < 1e-9 {
+        format!("+{:.0} RP", research)
+    } else {
+        format!("+{:.1} RP", research)
+    }
+}
+
+fn float_text(rs: &mut RunState, x: f64, y: f64, text: String, color: &str, is_crit: bool) {
+    rs.floating_texts.push(FloatingText {
+        x,
+        y,
+        text,
+        color: color.to_string(),
+        ttl: FLOATING_TEXT_SECS,
+        is_crit,
+    });
+    let excess = rs.floating_texts.len().saturating_sub(FLOATING_TEXT_CAP);
+    if excess > 0 {
+        rs.floating_texts.drain(..excess);
+    }
+}
+
+// Pays out `share` of a pickup's research and gold
+fn credit_pickup(rs: &mut RunState, p: &Pickup, share: f64) {
+    add_research(rs, p.research * share);
+    let gold = (p.gold as f64 * share).floor() as u64;
+    earn_gold(rs, gold);
+}
+
+fn earn_gold(rs: &mut RunState, gold: u64) {
+    rs.currencies.gold = rs.currencies.gold.saturating_add(gold);
+    rs.stats.gold_earned = rs.stats.gold_earned.saturating_add(gold);
+}
+
+// Callers check the price is affordable first
+fn spend_gold(rs: &mut RunState, gold: u64) {
+    rs.currencies.gold -= gold;
+    rs.stats.gold_spent = rs.stats.gold_spent.saturating_add(gold);
+}
+
+fn drop_pickup(rs: &mut RunState, p: Pickup) {
+    rs.pickups.push(p);
+    if rs.pickups.len() > PICKUP_CAP {
+        let oldest = rs.pickups.remove(0);
+        credit_pickup(rs, &oldest, 0.5);
+    }
+}
+
+// Counts pickups down; those that run out are credited at half value
+fn age_pickups(rs: &mut RunState, dt: f64) {
+    if rs.pickups.is_empty() {
+        return;
+    }
+    for p in &mut rs.pickups {
+        p.ttl -= dt;
+    }
+    let (expired, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut rs.pickups)
+        .into_iter()
+        .partition(|p| p.ttl <= 0.0);
+    rs.pickups = kept;
+    for p in &expired {
+        credit_pickup(rs, p, 0.5);
+    }
+}
+
+// Whether a CollectPickups at world (x, y) would pick anything up
+pub fn pickup_in_reach(rs: &RunState, x: f64, y: f64) -> bool {
+    rs.pickups.iter().any(|p| p.in_reach(x, y))
+}
+
+// Every hit on an enemy goes through here; returns the damage actually taken
+fn apply_damage(e: &mut Enemy, heat: &mut HeatGrid, amount: u32) -> u32 {
+    let applied = amount.min(e.hp);
+    e.hp -= applied;
+    heat.add(e.x, e.y, applied as f32);
+    applied
+}
+
+// Splash damage share at `dist` from the impact: full at the centre, half at the edge
+pub fn splash_falloff(dist: f64, radius: f64) -> f64 {
+    if radius <= 0.0 {
+        return 0.0;
+    }
+    1.0 - 0.5 * (dist / radius).clamp(0.0, 1.0)
+}
+
+// Nearest live enemy other than `from` within bounce range of it
+fn bounce_target(enemies: &[Enemy], from: usize) -> Option<usize> {
+    let (fx, fy) = (enemies[from].x, enemies[from].y);
+    enemies
+        .iter()
+        .enumerate()
+        .filter(|(i, e)| *i != from && e.hp > 0)
+        .map(|(i, e)| (i, (e.x - fx).powi(2) + (e.y - fy).powi(2)))
+        .filter(|(_, d2)| *d2 <= BOUNCE_RANGE_TILES * BOUNCE_RANGE_TILES)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(i, _)| i)
+}
+
+fn advance_projectiles(rs: &mut RunState, dt: f64) {
+    let over = rs.projectiles.len().saturating_sub(PROJECTILE_CAP);
+    if over > 0 {
+        rs.projectiles.drain(..over);
+        rs.projectile_culls.capped += over as u64;
+        log(Level::Warn, &format!("projectile cap hit, culled {over}"));
+    }
+    let gs = rs.grid_size;
+    let mut i = 0;
+    while i < rs.projectiles.len() {
+        let mut remove = false;
+        {
+            let p = &mut rs.projectiles[i];
+            p.x += p.vx * dt;
+            p.y += p.vy * dt;
+            p.remaining -= dt;
+            p.age += dt;
+            let margin = PROJECTILE_OOB_MARGIN_TILES;
+            let out_of_bounds = p.x < -margin
+                || p.y < -margin
+                || p.x > gs.width as f64 + margin
+                || p.y > gs.height as f64 + margin;
+            if p.remaining > 0.0 && p.age >= PROJECTILE_MAX_LIFETIME_SECS {
+                rs.projectile_culls.expired += 1;
+                remove = true;
+            } else if p.remaining > 0.0 && out_of_bounds {
+                rs.projectile_culls.out_of_bounds += 1;
+                remove = true;
+            } else if p.remaining <= 0.0 {
+                let ix = p.x;
+                let iy = p.y;
+                let splash_radius = p.splash_radius;
+                let p_damage = p.damage;
+                let p_debuff = p.apply_debuff.clone();
+                let p_slow = p.slow.clone();
+                let p_source = p.source;
+                let p_bounces = p.bounces_remaining;
+                let p_speed = (p.vx * p.vx + p.vy * p.vy).sqrt();
+                let mut dealt = 0u64;
+                let mut killed = 0u64;
+                let mut hit = None;
+                let mut best = 0.3f64 * 0.3;
+                for (ei, e) in rs.enemies.iter().enumerate() {
+                    let dx = e.x - ix;
+                    let dy = e.y - iy;
+                    let d2 = dx * dx + dy * dy;
+                    if d2 <= best {
+                        best = d2;
+                        hit = Some(ei);
+                    }
+                }
+                if let Some(e) = hit.and_then(|h| rs.enemies.get_mut(h)) {
+                    let applied = apply_damage(e, &mut rs.heat, p_damage);
+                    e.hit_flash_ttl = HIT_FLASH_SECS;
+                    rs.stats.highest_single_hit = rs.stats.highest_single_hit.max(applied);
+                    dealt += applied as u64;
+                    killed += u64::from(applied > 0 && e.hp == 0);
+                    if rs.vampiric_heal_percent > 0.0 && rs.life < rs.life_max {
+                        let heal = (applied as f64 * rs.vampiric_heal_percent).floor() as u32;
+                        if heal > 0 {
+                            rs.life = (rs.life + heal).min(rs.life_max);
+                        }
+                    }
+                    rs.damage_numbers.push(DamageNumber {
+                        x: e.x,
+                        y: e.y,
+                        amount: applied,
+                        ttl: 0.8,
+                        is_crit: false,
+                        is_gold: false,
+                        is_heal: false,
+                        is_clutch: false,
+                    });
+
+                    // One freeze roll per hit, however many slows it carries
+                    let roll = if rs.freeze_chance > 0.0 {
+                        random()
+                    } else {
+                        1.0
+                    };
+                    if let Some(debuff) = &p_debuff {
+                        if debuff.kind == DebuffKind::Slow {
+                            chill(e, debuff.clone(), rs.freeze_chance, roll);
+                        } else {
+                            inflict(e, debuff.clone());
+                        }
+                    }
+                    if let Some(slow) = &p_slow {
+                        chill(e, slow.clone(), rs.freeze_chance, roll);
+                    }
+                }
+
+                if splash_radius > 0.0 {
+                    rs.splash_explosions.push(SplashExplosion {
+                        x: ix,
+                        y: iy,
+                        radius: splash_radius,
+                        ttl: 0.25,
+                    });
+
+                    let splash_radius_sq = splash_radius * splash_radius;
+                    for (ei, e) in rs.enemies.iter_mut().enumerate() {
+                        if Some(ei) == hit {
+                            continue;
+                        }
+                        let dx = e.x - ix;
+                        let dy = e.y - iy;
+                        let d2 = dx * dx + dy * dy;
+                        let splash_damage = (p_damage as f64
+                            * splash_falloff(d2.sqrt(), splash_radius))
+                        .round() as u32;
+                        if d2 <= splash_radius_sq && splash_damage > 0 {
+                            let applied = apply_damage(e, &mut rs.heat, splash_damage);
+                            e.hit_flash_ttl = HIT_FLASH_SECS;
+                            dealt += applied as u64;
+                            killed += u64::from(applied > 0 && e.hp == 0);
+                            rs.damage_numbers.push(DamageNumber {
+                                x: e.x,
+                                y: e.y,
+                                amount: applied,
+                                ttl: 0.8,
+                                is_crit: false,
+                                is_gold: false,
+                                is_heal: false,
+                                is_clutch: false,
+                            });
+                        }
+                    }
+                }
+                rs.stats.total_damage_dealt = rs.stats.total_damage_dealt.saturating_add(dealt);
+                rs.damage_window.add(rs.sim_time, dealt);
+                add_damage_research(rs, dealt);
+                // Credit the firing tower for veterancy and its tile's history
+                if let Some(tw) = p_source.and_then(|id| rs.towers.iter_mut().find(|t| t.id == id))
+                {
+                    tw.damage_dealt = tw.damage_dealt.saturating_add(dealt);
+                    tw.kills = tw.kills.saturating_add(killed);
+                    tw.here.add(TowerTally {
+                        kills: killed,
+                        damage: dealt,
+                    });
+                }
+                // Bounce: hop from the struck enemy to the nearest other live one in range
+                let next = hit
+                    .filter(|_| p_bounces > 0 && p_speed > 0.0)
+                    .and_then(|h| bounce_target(&rs.enemies, h));
+                if let Some((h, t)) = hit.zip(next) {
+                    let (from, to) = (&rs.enemies[h], &rs.enemies[t]);
+                    let (dx, dy) = (to.x - from.x, to.y - from.y);
+                    let dist = (dx * dx + dy * dy).sqrt().max(1e-6);
+                    let p = &mut rs.projectiles[i];
+                    p.x = from.x;
+                    p.y = from.y;
+                    p.vx = dx / dist * p_speed;
+                    p.vy = dy / dist * p_speed;
+                    p.remaining = dist / p_speed;
+                    p.damage = ((p_damage as f64 * BOUNCE_DAMAGE_FACTOR).round() as u32).max(1);
+                    p.bounces_remaining -= 1;
+                } else {
+                    remove = true;
+                }
+            }
+        }
+        if remove {
+            rs.projectiles.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+}
+
+// === Actions & Reducer ===
+#[derive(Clone, Debug)]
+pub enum RunAction {
+    TogglePause,
+    StartRun,
+    TickSecond,
+    // Game speed; anything but one of SIM_SPEEDS is ignored
+    SetSimSpeed {
+        speed: f64,
+    },
+    MiningComplete {
+        idx: usize,
+    },
+    SimTick {
+        dt: f64,
+    },
+    // Fresh basic map at the current size. Restarts go through ResetRunWithSeed, which sizes
+    // the map from the upgrades and keeps the route mode.
+    #[allow(dead_code)]
+    ResetRun,
+    ResetRunWithUpgrades {
+        ups: UpgradeState,
+    },
+    ResetRunWithSeed {
+        ups: UpgradeState,
+        seed: u64,
+    },
+    // Fresh puzzle run; keeps research like the other resets
+    StartScenario {
+        id: ScenarioId,
+    },
+    // Restart into a run generated ahead of time (see state::next_run); bookkeeping as for
+    // ResetRunWithSeed
+    StartPregenerated {
+        state: Box<RunState>,
+    },
+    // Resume an autosaved run (see RunState::from_save) in place of the current one;
+    // research is stored on its own, so the current balance wins
+    LoadSaved {
+        state: Box<RunState>,
+    },
+    // Developer rewind: swap in an earlier snapshot exactly as it was, paused for inspection
+    RestoreSnapshot {
+        state: Box<RunState>,
+    },
+    // Single step: one SimTick of `dt` on a paused run, which stays paused
+    StepSim {
+        dt: f64,
+    },
+    // Time missed while the tab was hidden, replayed in the mode's sub-steps with a TickSecond
+    // per whole second; capped at the mode's maximum and stops at game over
+    CatchUp {
+        elapsed_secs: f64,
+        mode: CatchUpMode,
+    },
+    PlaceWall {
+        x: u32,
+        y: u32,
+    },
+    // A dragged run of walls in one step; see lay_walls for which of them land
+    PlaceWalls {
+        tiles: Vec<Position>,
+    },
+    // Takes back the latest wall while undoable_wall allows it; otherwise a no-op
+    UndoLastWall,
+    SetProgression {
+        run: Option<u64>,
+    },
+    PlaceTower {
+        x: u32,
+        y: u32,
+        kind: TowerKind,
+    },
+    // Atomic hotkey placement: starts the run (and optionally unpauses) only if the tower lands
+    PlaceTowerAndStart {
+        x: u32,
+        y: u32,
+        kind: TowerKind,
+        unpause: bool,
+    },
+    // Refunds tower_refund_percent of the tower price, capped at what was paid
+    SellTower {
+        x: u32,
+        y: u32,
+    },
+    // Buys the tower's next level for tower_upgrade_cost gold
+    UpgradeTower {
+        x: u32,
+        y: u32,
+    },
+    // Rebuilds a tower as another kind for swap_cost gold
+    ReplaceTowerKind {
+        x: u32,
+        y: u32,
+        kind: TowerKind,
+    },
+    // Tower info panel's Move: the tower picks up onto any tile a new one could take, cost aside
+    // (see can_move_tower)
+    MoveTower {
+        from: (u32, u32),
+        to: (u32, u32),
+    },
+    // Paint a targeting exclusion tile; a drag stroke repeats the first tile's new value
+    SetNoTarget {
+        x: u32,
+        y: u32,
+        on: bool,
+    },
+    ToggleTowerIgnoreExclusion {
+        x: u32,
+        y: u32,
+    },
+    // Steps the tower's TargetMode
+    CycleTargetMode {
+        x: u32,
+        y: u32,
+    },
+    // Take one of the offered starting perks; only before the run starts
+    ChoosePerk {
+        perk: Perk,
+    },
+    // Research-bought structure on a reachable rock tile
+    SpendResearchOnStructure {
+        x: u32,
+        y: u32,
+        kind: StructureKind,
+    },
+    // Hardcore siege modifier; only before the run starts
+    SetSiege {
+        on: bool,
+    },
+    // Loop or End route; only before the run starts
+    SetRouteMode {
+        mode: RouteMode,
+    },
+    // Restore a siege-worn tower's durability for gold
+    RepairTower {
+        x: u32,
+        y: u32,
+    },
+    // Room stamp bought with tile credits; all-or-nothing
+    ApplyStamp {
+        origin: (u32, u32),
+        stamp_id: StampId,
+        rotation: u8,
+    },
+    // Pacing hints setting; switching off also clears the hint on screen
+    SetHints {
+        on: bool,
+    },
+    DismissHint,
+    // The HUD has shown the pending notices, so a repeat of the same one shows again
+    DismissNotices,
+    // Manual collection setting; switching off pays out what is still lying around
+    SetManualCollection {
+        on: bool,
+    },
+    // Cursor or focus tile at world (x, y): collects the pickups in reach at full value
+    CollectPickups {
+        x: f64,
+        y: f64,
+    },
+    SpendResearch {
+        amount: u64,
+    },
+    ApplyUpgrades {
+        ups: UpgradeState,
+    },
+    // Pays `cost` research and applies `ups` (resetting the run when `reset`), or does
+    // nothing at all when the research falls short
+    PurchaseUpgrades {
+        cost: u64,
+        ups: UpgradeState,
+        reset: bool,
+    },
+    SetResearch {
+        amount: u64,
+    },
+    // Several actions as one: applied in order to a single copy of the state, with one version
+    // bump for the lot (none if nothing changed)
+    Batch(Vec<RunAction>),
+}
+
+impl yew::Reducible for RunState {
+    type Action = RunAction;
+    fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
+        let next = self.apply(action);
+        debug_assert!(
+            crate::state::invariants::check(&next).is_empty(),
+            "invariants broken: {:#?}",
+            crate::state::invariants::check(&next)
+        );
+        next
+    }
+}
+
+impl RunState {
+    fn apply(self: Rc<Self>, action: RunAction) -> Rc<Self> {
+        use RunAction::*;
+        if let ResetRunWithUpgrades { ups } = &action {
+            let prev_r = self.currencies.research;
+            let size = play_area_size_for_level(ups.level(UpgradeId::PlayAreaSize));
+            let mut fresh = RunState::new_with_upgrades(
+                GridSize {
+                    width: size,
+                    height: size,
+                },
+                ups,
+                self.route_mode,
+            );
+            fresh.currencies.research = prev_r;
+            fresh.run_id = self.run_id + 1;
+            fresh.progression_run = self.progression_run;
+            log(
+                Level::Info,
+                &format!("run {} started, seed {}", fresh.run_id, fresh.map_seed),
+            );
+            return Rc::new(fresh);
+        }
+        if let LoadSaved { state } = action {
+            log(
+                Level::Info,
+                &format!(
+                    "run {} resumed at {}s",
+                    state.run_id, state.stats.time_survived_secs
+                ),
+            );
+            let mut resumed = *state;
+            resumed.currencies.research = self.currencies.research;
+            return Rc::new(resumed);
+        }
+        if let RestoreSnapshot { state } = action {
+            log(
+                Level::Debug,
+                &format!("rewound to {}s", state.stats.time_survived_secs),
+            );
+            let mut restored = *state;
+            restored.is_paused = true;
+            restored.version = self.version.wrapping_add(1);
+            return Rc::new(restored);
+        }
+        if let Batch(actions) = action {
+            let mut rc = Rc::clone(&self);
+            for a in actions {
+                rc = rc.apply(a);
+            }
+            if !Rc::ptr_eq(&rc, &self) {
+                Rc::make_mut(&mut rc).version = self.version.wrapping_add(1);
+            }
+            return rc;
+        }
+        if let PurchaseUpgrades { cost, ups, reset } = action {
+            if self.currencies.research < cost {
+                return self;
+            }
+            let apply = if reset {
+                ResetRunWithUpgrades { ups }
+            } else {
+                ApplyUpgrades { ups }
+            };
+            return self.apply(Batch(vec![SpendResearch { amount: cost }, apply]));
+        }
+        if let PlaceTowerAndStart {
+            x,
+            y,
+            kind,
+            unpause,
+        } = action
+        {
+            // PlaceTower, then StartRun and TogglePause only once the tower has landed
+            let placed = Rc::clone(&self).apply(PlaceTower { x, y, kind });
+            if placed.towers.len() == self.towers.len() {
+                return placed;
+            }
+            let mut rest = vec![StartRun];
+            if unpause && placed.is_paused {
+                rest.push(TogglePause);
+            }
+            let mut rc = placed.apply(Batch(rest));
+            Rc::make_mut(&mut rc).version = self.version.wrapping_add(1);
+            return rc;
+        }
+        if let StepSim { dt } = action {
+            if !(self.started && self.is_paused && !self.game_over) {
+                return self;
+            }
+            let mut running = (*self).clone();
+            running.is_paused = false;
+            // A step is exactly `dt` of world time at any game speed
+            running.sim_speed = 1.0;
+            let mut stepped = Rc::new(running).apply(SimTick { dt });
+            let s = Rc::make_mut(&mut stepped);
+            s.is_paused = true;
+            s.sim_speed = self.sim_speed;
+            return stepped;
+        }
+        if let CatchUp { elapsed_secs, mode } = action {
+            let step_secs = mode.step_secs();
+            let secs = if elapsed_secs.is_finite() {
+                elapsed_secs.clamp(0.0, mode.max_secs())
+            } else {
+                0.0
+            };
+            // Whole sub-steps, counted rather than subtracted so no float drift creeps in
+            let steps = (secs / step_secs).round() as u32;
+            let per_second = (1.0 / step_secs).round() as u32;
+            // Missed time is real time: replayed at 1x whatever the game speed
+            let speed = self.sim_speed;
+            let mut rc = self;
+            if speed != 1.0 {
+                Rc::make_mut(&mut rc).sim_speed = 1.0;
+            }
+            for i in 1..=steps {
+                if !rc.started || rc.is_paused || rc.game_over {
+                    break;
+                }
+                rc = rc.apply(SimTick { dt: step_secs });
+                if i.is_multiple_of(per_second) {
+                    rc = rc.apply(TickSecond);
+                }
+            }
+            let excess = rc
+                .damage_numbers
+                .len()
+                .saturating_sub(CATCH_UP_MAX_DAMAGE_NUMBERS);
+            if excess > 0 {
+                Rc::make_mut(&mut rc).damage_numbers.drain(..excess);
+            }
+            if rc.sim_speed != speed {
+                Rc::make_mut(&mut rc).sim_speed = speed;
+            }
+            return rc;
+        }
+        if let ResetRunWithSeed { ups, seed } = &action {
+            let prev_r = self.currencies.research;
+            let size = play_area_size_for_level(ups.level(UpgradeId::PlayAreaSize));
+            let mut fresh = RunState::new_with_route(
+                GridSize {
+                    width: size,
+                    height: size,
+                },
+                ups,
+                *seed,
+                self.route_mode,
+            );
+            fresh.currencies.research = prev_r;
+            fresh.run_id = self.run_id + 1;
+            fresh.progression_run = self.progression_run;
+            log(
+                Level::Info,
+                &format!("run {} started, seed {}", fresh.run_id, fresh.map_seed),
+            );
+            return Rc::new(fresh);
+        }
+        if let StartPregenerated { state } = action {
+            let mut fresh = *state;
+            // Prepared as a loop; the End is buried from the same seed
+            set_route_mode(&mut fresh, self.route_mode);
+            fresh.currencies.research = self.currencies.research;
+            fresh.run_id = self.run_id + 1;
+            fresh.progression_run = self.progression_run;
+            log(
+                Level::Info,
+                &format!(
+                    "run {} started, seed {} (pregenerated)",
+                    fresh.run_id, fresh.map_seed
+                ),
+            );
+            return Rc::new(fresh);
+        }
+        if let StartScenario { id } = action {
+            let mut fresh = RunState::from_scenario(id);
+            fresh.currencies.research = self.currencies.research;
+            fresh.run_id = self.run_id + 1;
+            fresh.progression_run = self.progression_run;
+            log(
+                Level::Info,
+                &format!("run {} started, scenario {}", fresh.run_id, id.def().name),
+            );
+            return Rc::new(fresh);
+        }
+        if matches!(action, ResetRun) {
+            let prev_r = self.currencies.research;
+            let mut fresh = RunState::new_basic(self.grid_size);
+            fresh.currencies.research = prev_r;
+            fresh.run_id = self.run_id + 1;
+            fresh.progression_run = self.progression_run;
+            log(
+                Level::Info,
+                &format!("run {} started, seed {}", fresh.run_id, fresh.map_seed),
+            );
+            return Rc::new(fresh);
+        }
+        // Held only here (a Batch past its first change, CatchUp) the state is changed in place
+        let (mut new, shared) = match Rc::try_unwrap(self) {
+            Ok(owned) => (owned, None),
+            Err(rc) => ((*rc).clone(), Some(rc)),
+        };
+        match action {
+            TogglePause => {
+                if !new.game_over {
+                    new.is_paused = !new.is_paused;
+                }
+            }
+            StartRun => {
+                if !new.started && !new.game_over {
+                    new.started = true;
+                }
+            }
+            TickSecond => {
+                // One world second per speed step; a game over partway stops the rest
+                for _ in 0..new.sim_speed.round().max(1.0) as u32 {
+                    if new.started && !new.is_paused && !new.game_over {
+                        tick_second(&mut new);
+                    }
+                }
+            }
+            SetSimSpeed { speed } => {
+                if SIM_SPEEDS.contains(&speed) {
+                    new.sim_speed = speed;
+                }
+            }
+            MiningComplete { idx } => {
+                // Timed against an earlier snapshot: a wall or path change since may have put
+                // the tile out of reach
+                if !new.game_over && idx < new.tiles.len() && minable_now(&new, idx) {
+                    complete_mining(&mut new, idx);
+                }
+            }
+            SimTick { dt } => {
+                // Paths keep settling before the first wave and while paused
+                let searching = advance_path_job(&mut new);
+                if !(new.started && !new.is_paused && !new.game_over) {
+                    if !searching {
+                        return shared.unwrap_or_else(|| Rc::new(new));
+                    }
+                    new.version = new.version.wrapping_add(1);
+                    return Rc::new(new);
+                }
+                // Everything below runs on world time: cooldowns, debuffs, TTLs and movement
+                let dt = dt * new.sim_speed;
+                new.sim_time += dt;
+                {
+                    let spawn_interval = spawn_interval(&new);
+                    let secs = new.stats.time_survived_secs;
+                    // Every interval due since the last spawn, so a TickSecond covering several
+                    // world seconds at 2x/4x spawns as many as 1x would have
+                    while (secs as f64 - new.last_enemy_spawn_time_secs) >= spawn_interval
+                        && !new.path_loop.is_empty()
+                        && new.scenario.is_none()
+                    {
+                        // Over the cap the spawn just waits for the field to thin out
+                        if new.enemies.len() >= new.difficulty.enemy_cap_at(secs as f64) {
+                            break;
+                        }
+                        let difficulty = spawn_difficulty(&new);
+                        let hp = spawn_hp(&new);
+                        let speed = new.difficulty.speed_at(secs as f64);
+
+                        // Visual scaling - enemies grow larger as they get stronger
+                        let size_scale = (1.0 + difficulty * 0.04).min(2.0); // Was 0.05
+
+                        // Occasional Mason mini-boss once the run is underway, otherwise an
+                        // archetype weighted by survival time
+                        let kind = if secs >= MASON_MIN_TIME_SECS && random() < MASON_SPAWN_CHANCE {
+                            EnemyKind::Mason
+                        } else {
+                            pick_archetype(secs, random())
+                        };
+                        let hp = ((hp as f64 * kind.scale().hp).round() as u32).max(1);
+                        spawn_archetype(&mut new, kind, hp, speed, size_scale);
+                        new.last_enemy_spawn_time_secs += spawn_interval;
+                    }
+                    // Time spent waiting (full field, no loop) isn't paid back as a burst
+                    new.last_enemy_spawn_time_secs = new
+                        .last_enemy_spawn_time_secs
+                        .max(secs as f64 - spawn_interval);
+                }
+                if !new.towers.is_empty() && !new.enemies.is_empty() {
+                    let lap = new.lap_frame();
+                    for tw in &mut new.towers {
+                        if tw.cooldown_remaining > 0.0 {
+                            tw.cooldown_remaining -= dt;
+                        }
+                        if tw.cooldown_remaining > 0.0 {
+                            continue;
+                        }
+                        let cx = tw.x as f64 + 0.5;
+                        let cy = tw.y as f64 + 0.5;
+                        if let Some(i) = tw.pick_target(&new.enemies, &new.no_target, lap) {
+                            let e = &new.enemies[i];
+
+                            // Predictive aiming: aim at where enemy will be, not where it is
+                            // Calculate initial travel time based on current position
+                            let dx0 = e.x - cx;
+                            let dy0 = e.y - cy;
+                            let dist0 = (dx0 * dx0 + dy0 * dy0).sqrt().max(1e-6);
+                            let speed = new.projectile_speed;
+                            let initial_travel = dist0 / speed;
+
+                            // Predict where enemy will be after that time
+                            // Account for slow debuff in prediction
+                            let enemy_speed_mult = debuff_speed_mult(&e.debuffs);
+                            let enemy_vx = e.dir_dx * e.speed_tps * enemy_speed_mult;
+                            let enemy_vy = e.dir_dy * e.speed_tps * enemy_speed_mult;
+
+                            // Predicted position (linear approximation)
+                            let pred_x = e.x + enemy_vx * initial_travel;
+                            let pred_y = e.y + enemy_vy * initial_travel;
+
+                            // Aim at predicted position
+                            let dx = pred_x - cx;
+                            let dy = pred_y - cy;
+                            let dist = (dx * dx + dy * dy).sqrt().max(1e-6);
+                            let travel = dist / speed;
+
+                            let mut dmg = tw.shot_damage() * new.prestige_damage_mult;
+                            if new.crit_chance > 0.0 && random() < new.crit_chance {
+                                dmg *= new.crit_damage_mult;
+                            }
+                            if dmg < 1.0 {
+                                dmg = 1.0;
+                            }
+                            new.projectiles.push(Projectile {
+                                id: new.entity_ids.alloc(),
+                                x: cx,
+                                y: cy,
+                                vx: dx / dist * speed,
+                                vy: dy / dist * speed,
+                                remaining: travel,
+                                damage: dmg.round() as u32,
+                                splash_radius: new.projectile_splash_radius,
+                                apply_debuff: tw.apply_debuff.clone(),
+                                source: Some(tw.id),
+                                age: 0.0,
+                                bounces_remaining: new.projectile_bounces,
+                                slow: tw.kind_debuff().map(|d| Debuff {
+                                    remaining: d.remaining + new.cold_slow_duration,
+                                    strength: d.strength * new.cold_slow_multiplier,
+                                    ..d
+                                }),
+                            });
+                            tw.cooldown_remaining =
+                                1.0 / (tw.fire_rate * new.tower_fire_rate_global.max(0.01));
+                        }
+                    }
+                }
+                if !new.projectiles.is_empty() {
+                    advance_projectiles(&mut new, dt);
+                    if !new.enemies.is_empty() {
+                        reap_and_spread(&mut new);
+                    }
+                }
+                for dn in &mut new.damage_numbers {
+                    dn.ttl -= dt;
+                }
+                new.damage_numbers.retain(|d| d.ttl > 0.0);
+                for ft in &mut new.floating_texts {
+                    ft.ttl -= dt;
+                }
+                new.floating_texts.retain(|t| t.ttl > 0.0);
+                for e in &mut new.enemies {
+                    e.hit_flash_ttl = (e.hit_flash_ttl - dt).max(0.0);
+                    e.spawn_anim_ttl = (e.spawn_anim_ttl - dt).max(0.0);
+                }
+                for se in &mut new.splash_explosions {
+                    se.ttl -= dt;
+                }
+                new.splash_explosions.retain(|s| s.ttl > 0.0);
+                for fr in &mut new.fresh_rock {
+                    fr.ttl -= dt;
+                }
+                new.fresh_rock.retain(|f| f.ttl > 0.0);
+                age_pickups(&mut new, dt);
+                if new.loop_total_length > 0.0
+                    && new.path_loop.len() >= 2
+                    && !new.enemies.is_empty()
+                {
+                    let total = new.loop_total_length;
+                    let open = route_is_open(&new);
+                    let lap = new.lap_frame();
+                    let sample_pos = |nodes: &Vec<Position>, cum: &Vec<f64>, total: f64, d: f64| {
+                        if nodes.len() < 2 || total <= 0.0 {
+                            return (0.0, 0.0, 0.0, 0.0, 0usize);
+                        }
+                        let dist = if open { d.min(total) } else { d % total };
+                        let mut seg_i = 0usize;
+                        while seg_i + 1 < cum.len() && cum[seg_i + 1] <= dist {
+                            seg_i += 1;
+                        }
+                        let (ax, ay, bx, by, seg_len) = if seg_i + 1 < nodes.len() {
+                            let a = nodes[seg_i];
+                            let b = nodes[seg_i + 1];
+                            let seg_len = (((b.x as f64 - a.x as f64).powi(2)
+                                + (b.y as f64 - a.y as f64).powi(2))
+                            .sqrt())
+                            .max(1e-6);
+                            (
+                                a.x as f64 + 0.5,
+                                a.y as f64 + 0.5,
+                                b.x as f64 + 0.5,
+                                b.y as f64 + 0.5,
+                                seg_len,
+                            )
+                        } else {
+                            let a = nodes.last().unwrap();
+                            let b = nodes[0];
+                            let seg_len = (((b.x as f64 - a.x as f64).powi(2)
+                                + (b.y as f64 - a.y as f64).powi(2))
+                            .sqrt())
+                            .max(1e-6);
+                            (
+                                a.x as f64 + 0.5,
+                                a.y as f64 + 0.5,
+                                b.x as f64 + 0.5,
+                                b.y as f64 + 0.5,
+                                seg_len,
+                            )
+                        };
+                        let base = cum.get(seg_i).copied().unwrap_or(0.0);
+                        let t = ((dist - base) / seg_len).clamp(0.0, 1.0);
+                        let dx = bx - ax;
+                        let dy = by - ay;
+                        let nx = ax + dx * t;
+                        let ny = ay + dy * t;
+                        (
+                            nx,
+                            ny,
+                            dx / seg_len,
+                            dy / seg_len,
+                            (seg_i + 1) % nodes.len(),
+                        )
+                    };
+                    let mut mason_left: Vec<((u32, u32), (u32, u32))> = Vec::new();
+                    let mut leaks = 0u32;
+                    let mut leaked: Vec<u32> = Vec::new();
+                    let mut breached: Vec<usize> = Vec::new();
+                    let gs = new.grid_size;
+                    let lava_pulse = (new.sim_time / LAVA_PULSE_SECS).floor()
+                        > ((new.sim_time - dt) / LAVA_PULSE_SECS).floor();
+                    let mut lava_hit = false;
+                    for (i, e) in new.enemies.iter_mut().enumerate() {
+                        // Process debuffs
+                        let mut poison_damage = 0u32;
+                        let mut burn_damage = 0u32;
+
+                        // Update debuffs and calculate effects
+                        for debuff in &mut e.debuffs {
+                            let before = debuff.remaining;
+                            debuff.remaining -= dt;
+                            let dot = dot_damage(debuff.strength, before, debuff.remaining);
+                            match debuff.kind {
+                                // Poison and burn deal damage per second
+                                DebuffKind::Poison => {
+                                    poison_damage = poison_damage.saturating_add(dot)
                                 }
+                                DebuffKind::Burn => burn_damage = burn_damage.saturating_add(dot),
+                                // Movement effects: see debuff_speed_mult below
+                                DebuffKind::Slow | DebuffKind::Freeze => {}
+                            }
+                        }
+
+                        // Remove expired debuffs
+                        e.debuffs.retain(debuff_lingers);
+                        let speed_mult = debuff_speed_mult(&e.debuffs);
+
+                        // Apply poison damage
+                        if poison_damage > 0 && e.hp > 0 {
+                            apply_damage(e, &mut new.heat, poison_damage);
+                            // Show damage number for poison
+                            new.damage_numbers.push(DamageNumber {
+                                x: e.x,
+                                y: e.y,
+                                amount: poison_damage,
+                                ttl: 0.6,
+                                is_crit: false,
+                                is_gold: false,
+                                is_heal: false,
+                                is_clutch: false,
+                            });
+                        }
+
+                        // Apply burn damage
+                        if burn_damage > 0 && e.hp > 0 {
+                            apply_damage(e, &mut new.heat, burn_damage);
+                            // Show damage number for burn
+                            new.damage_numbers.push(DamageNumber {
+                                x: e.x,
+                                y: e.y,
+                                amount: burn_damage,
+                                ttl: 0.6,
+                                is_crit: false,
+                                is_gold: false,
+                                is_heal: false,
+                                is_clutch: false,
+                            });
+                        }
+                        // Killed by its DoT: reaped below rather than moved on
+                        if e.hp == 0 {
+                            continue;
+                        }
+
+                        // Apply movement with slow multiplier
+                        let prev_tile = (e.x.floor(), e.y.floor());
+                        let step = e.speed_tps * dt * speed_mult;
+                        let lapped = lap.progress(e.loop_dist) + step >= total;
+                        e.loop_dist += step;
+                        if !open {
+                            e.loop_dist %= total;
+                        }
+                        if lapped {
+                            if open {
+                                e.loop_dist = total;
+                                breached.push(i);
+                            }
+                            leaked.push(e.kind.life_damage());
+                            if open {
+                                new.stats.breaches = new.stats.breaches.saturating_add(1);
+                            } else {
+                                new.stats.loops_completed =
+                                    new.stats.loops_completed.saturating_add(1);
+                            }
+                        }
+                        let (nx, ny, dx, dy, next_i) =
+                            sample_pos(&new.path_loop, &new.loop_cum_lengths, total, e.loop_dist);
+                        e.x = nx;
+                        e.y = ny;
+                        e.dir_dx = dx;
+                        e.dir_dy = dy;
+                        e.path_index = next_i;
+                        if lava_pulse && e.hp > 0 && lava_under(&new.tiles, gs, e.x, e.y) {
+                            let applied = apply_damage(e, &mut new.heat, LAVA_PULSE_DAMAGE);
+                            new.damage_numbers.push(DamageNumber {
+                                x: e.x,
+                                y: e.y,
+                                amount: applied,
+                                ttl: 0.6,
+                                is_crit: false,
+                                is_gold: false,
+                                is_heal: false,
+                                is_clutch: false,
+                            });
+                            lava_hit = true;
+                        }
+                        if e.kind == EnemyKind::Mason
+                            && e.hp > 0
+                            && prev_tile != (e.x.floor(), e.y.floor())
+                            && prev_tile.0 >= 0.0
+                            && prev_tile.1 >= 0.0
+                        {
+                            mason_left.push((
+                                (prev_tile.0 as u32, prev_tile.1 as u32),
+                                (e.x.floor() as u32, e.y.floor() as u32),
+                            ));
+                        }
+                    }
+                    // Stepping on the latest wall's tile, even in passing, keeps it for good
+                    if let Some(w) = new.last_wall {
+                        let entered = w.entered || enemy_on(&new, w.idx);
+                        new.last_wall = Some(LastWall { entered, ..w });
+                    }
+                    for damage in leaked {
+                        if leak_life(&mut new, damage) {
+                            leaks += 1;
+                        }
+                    }
+                    // Breaching enemies are spent, not killed
+                    for i in breached.into_iter().rev() {
+                        new.enemies.remove(i);
+                    }
+                    if lava_hit || new.enemies.iter().any(|e| e.hp == 0) {
+                        reap_and_spread(&mut new);
+                    }
+                    // Masons rebuild behind themselves after everyone has moved
+                    if !mason_left.is_empty() {
+                        let mut rng = SeededRng::new(new.mason_rng);
+                        for ((tx, ty), at) in mason_left {
+                            if rng.next_f64() < MASON_REBUILD_CHANCE {
+                                mason_rebuild_rock(&mut new, tx, ty, Some(at));
+                            }
+                        }
+                        new.mason_rng = rng.state;
+                    }
+                    if leaks > 0 {
+                        break_clean_streak(&mut new);
+                    }
+                    if new.siege {
+                        siege_contact(&mut new, dt);
+                    }
+                }
+            }
+            PlaceWall { x, y } => {
+                let gs = new.grid_size;
+                if x < gs.width && y < gs.height && feature_gate(&mut new, Feature::Walls) {
+                    lay_walls(&mut new, &[Position { x, y }]);
+                }
+            }
+            PlaceWalls { tiles } => {
+                if feature_gate(&mut new, Feature::Walls) {
+                    lay_walls(&mut new, &tiles);
+                }
+            }
+            UndoLastWall => {
+                if let Some(idx) = undoable_wall(&new).map(|w| w.idx) {
+                    new.tiles[idx].kind = TileKind::Empty;
+                    new.stats.walls_built = new.stats.walls_built.saturating_sub(1);
+                    let path = compute_path(&new);
+                    install_path(&mut new, path);
+                }
+                new.last_wall = None;
+            }
+            ApplyStamp {
+                origin,
+                stamp_id,
+                rotation,
+            } => {
+                let applied = (!new.game_over)
+                    .then(|| simulate_stamp(&new, origin, stamp_id, rotation).ok())
+                    .flatten();
+                if let Some((stamped, _)) = applied {
+                    new = stamped;
+                }
+            }
+            SetProgression { run } => {
+                new.progression_run = run;
+                new.locked_notice = None;
+            }
+            SetSiege { on } => {
+                if !new.started && new.scenario.is_none() {
+                    new.siege = on;
+                }
+            }
+            SetRouteMode { mode } => {
+                if new.started || new.scenario.is_some() || new.route_mode == mode {
+                    return shared.unwrap_or_else(|| Rc::new(new));
+                }
+                set_route_mode(&mut new, mode);
+            }
+            SetHints { on } => {
+                new.hints = on;
+                if !on {
+                    new.hint = None;
+                }
+            }
+            DismissHint => new.hint = None,
+            DismissNotices => {
+                new.locked_notice = None;
+                new.streak_notice = None;
+                new.siege_notice = None;
+            }
+            SetManualCollection { on } => {
+                new.manual_collection = on;
+                if !on {
+                    for p in std::mem::take(&mut new.pickups) {
+                        credit_pickup(&mut new, &p, 1.0);
+                    }
+                }
+            }
+            CollectPickups { x, y } => {
+                if new.game_over || !pickup_in_reach(&new, x, y) {
+                    return shared.unwrap_or_else(|| Rc::new(new));
+                }
+                let (got, left): (Vec<_>, Vec<_>) = std::mem::take(&mut new.pickups)
+                    .into_iter()
+                    .partition(|p| p.in_reach(x, y));
+                new.pickups = left;
+                for p in &got {
+                    credit_pickup(&mut new, p, 1.0);
+                }
+            }
+            RepairTower { x, y } => {
+                if new.siege && !new.game_over {
+                    let gold = new.currencies.gold;
+                    let repair = new.tower_at_mut(x, y).and_then(|tw| {
+                        let cost = tower_repair_cost(tw);
+                        if cost == 0 || gold < cost {
+                            return None;
+                        }
+                        tw.hp = tw.max_hp;
+                        Some(cost)
+                    });
+                    if let Some(cost) = repair {
+                        spend_gold(&mut new, cost);
+                    }
+                }
+            }
+            SetNoTarget { x, y, on } => {
+                // Older saves deserialize an empty, zero-sized bitset
+                let gs = new.grid_size;
+                if !new.no_target.fits(gs) {
+                    new.no_target = TileBitset::new(gs.width, gs.height);
+                }
+                new.no_target.set(x, y, on);
+            }
+            ToggleTowerIgnoreExclusion { x, y } => {
+                if let Some(tw) = new.tower_at_mut(x, y) {
+                    tw.ignore_exclusion = !tw.ignore_exclusion;
+                }
+            }
+            CycleTargetMode { x, y } => {
+                if let Some(tw) = new.tower_at_mut(x, y) {
+                    tw.target_mode = tw.target_mode.next();
+                }
+            }
+            PlaceTower { x, y, kind } => {
+                if feature_gate(&mut new, Feature::Towers) {
+                    place_tower(&mut new, x, y, kind);
+                }
+            }
+            SellTower { x, y } => {
+                if let Some(p) = new.tower_index_at(x, y) {
+                    let tw = new.towers.remove(p);
+                    retire_tower(&mut new, &tw);
+                    let refund = sell_refund(
+                        new.tower_cost_of(&tw.kind),
+                        new.tower_refund_percent,
+                        tw.paid,
+                    );
+                    new.currencies.gold = new.currencies.gold.saturating_add(refund);
+                    new.stats.towers_sold += 1;
+                }
+            }
+            UpgradeTower { x, y } => {
+                let upgrade = new
+                    .tower_index_at(x, y)
+                    .filter(|_| !new.game_over)
+                    .and_then(|p| tower_upgrade_cost(&new, &new.towers[p]).map(|cost| (p, cost)))
+                    .filter(|&(_, cost)| new.currencies.gold >= cost);
+                if let Some((p, cost)) = upgrade {
+                    spend_gold(&mut new, cost);
+                    if let Some(leveled) = leveled_tower(&new.towers[p]) {
+                        new.towers[p] = leveled;
+                    }
+                }
+            }
+            ReplaceTowerKind { x, y, kind } => {
+                if let Some(p) = new
+                    .tower_index_at(x, y)
+                    .filter(|&p| new.towers[p].kind != kind && !new.game_over)
+                {
+                    let cost = swap_cost(&new, &new.towers[p], &kind);
+                    if new.currencies.gold >= cost {
+                        spend_gold(&mut new, cost);
+                        new.towers[p] = swapped_tower(&new, &new.towers[p], kind);
+                    }
+                }
+            }
+            MoveTower {
+                from: (from_x, from_y),
+                to: (to_x, to_y),
+            } => {
+                let boost = move_target_boost(&new, to_x, to_y);
+                if let (Some(boost), Some(p)) = (boost, new.tower_index_at(from_x, from_y)) {
+                    let old = new.towers.remove(p);
+                    // What it did from the old tile stays credited there
+                    retire_tower(&mut new, &old);
+                    // Stats follow the new tile; identity, veterancy, level, targeting and
+                    // cooldown travel with the tower, so shots in flight still credit it
+                    let mut tower = Tower::new(
+                        to_x,
+                        to_y,
+                        old.kind,
+                        new.tower_base_range,
+                        new.tower_base_damage,
+                        boost,
+                    );
+                    tower.apply_debuff = match boost {
+                        Some(BoostKind::Slow) => new.cold_debuff_template.clone(),
+                        Some(BoostKind::Damage) => new.poison_debuff_template.clone(),
+                        Some(BoostKind::Fire) => new.fire_debuff_template.clone(),
+                        _ => None,
+                    };
+                    tower.id = old.id;
+                    tower.damage_dealt = old.damage_dealt;
+                    tower.kills = old.kills;
+                    tower.cooldown_remaining = old.cooldown_remaining;
+                    tower.target_mode = old.target_mode;
+                    set_tower_level(&mut tower, old.level);
+                    new.towers.push(tower);
+                }
+            }
+            ChoosePerk { perk } => {
+                if !new.started
+                    && new.perk.is_none()
+                    && new.scenario.is_none()
+                    && offered_perks(new.map_seed).contains(&perk)
+                {
+                    new.perk = Some(perk);
+                    log(Level::Debug, &format!("perk chosen: {}", perk.name()));
+                    match perk {
+                        Perk::FreeTower => new.free_towers += 1,
+                        Perk::BonusGold => earn_gold(&mut new, PERK_BONUS_GOLD),
+                        Perk::QuickPicks => new.perk_mining_secs = PERK_MINING_SECS,
+                        Perk::LeakForgiven => new.leak_shield = true,
+                    }
+                }
+            }
+            SpendResearchOnStructure { x, y, kind } => {
+                if !new.game_over && structure_placement_error(&new, x, y, kind).is_none() {
+                    new.currencies.research -= kind.research_cost();
+                    log(
+                        Level::Debug,
+                        &format!("{} raised at ({x}, {y})", kind.name()),
+                    );
+                    new.structures.push(Structure {
+                        x,
+                        y,
+                        kind,
+                        hp: kind.durability(),
+                        max_hp: kind.durability(),
+                    });
+                }
+            }
+            SpendResearch { amount } => {
+                if new.currencies.research >= amount {
+                    new.currencies.research -= amount;
+                }
+            }
+            // Scenarios run on base stats
+            ApplyUpgrades { ups } => {
+                if new.scenario.is_none() {
+                    apply_upgrades_to_run(&mut new, &ups);
+                }
+            }
+            SetResearch { amount } => {
+                new.currencies.research = amount;
+            }
+            ResetRun
+            | ResetRunWithUpgrades { .. }
+            | ResetRunWithSeed { .. }
+            | StartScenario { .. }
+            | StartPregenerated { .. }
+            | LoadSaved { .. }
+            | RestoreSnapshot { .. }
+            | PlaceTowerAndStart { .. }
+            | PurchaseUpgrades { .. }
+            | StepSim { .. }
+            | CatchUp { .. }
+            | Batch(_) => unreachable!(),
+        }
+        new.low_life = low_life_next(new.low_life, new.life, new.life_max);
+        new.version = new.version.wrapping_add(1);
+        Rc::new(new)
+    }
+}
+
+// --- Tests ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{HashMap, HashSet};
+    use yew::Reducible;
+    fn make_run() -> RunState {
+        RunState::new_basic(GridSize {
+            width: 25,
+            height: 25,
+        })
+    }
+
+    #[test]
+    fn start_and_indestructibles_exist() {
+        let rs = make_run();
+        let mut start_idx = None;
+        for (i, t) in rs.tiles.iter().enumerate() {
+            if matches!(t.kind, TileKind::Start) {
+                start_idx = Some(i);
+                break;
+            }
+        }
+        assert!(start_idx.is_some(), "Start tile missing");
+        let idx = start_idx.unwrap();
+        let sx = (idx as u32) % rs.grid_size.width;
+        let sy = (idx as u32) / rs.grid_size.width;
+        // At least one orthogonal indestructible
+        let mut found = false;
+        for (dx, dy) in [(1i32, 0), (-1, 0), (0, 1), (0, -1)] {
+            let x = sx as i32 + dx;
+            let y = sy as i32 + dy;
+            if x >= 0
+                && y >= 0
+                && (x as u32) < rs.grid_size.width
+                && (y as u32) < rs.grid_size.height
+            {
+                let i2 = (y as u32 * rs.grid_size.width + x as u32) as usize;
+                if matches!(rs.tiles[i2].kind, TileKind::Indestructible) {
+                    found = true;
+                    break;
+                }
+            }
+        }
+        assert!(found, "No indestructible neighbor to start");
+        assert!(!rs.path.is_empty(), "Initial path empty");
+        assert!(rs.path_loop.len() >= 2, "Loop path too short");
+    }
+
+    #[test]
+    fn enemy_spawns_after_time() {
+        let mut rs = make_run();
+        rs.started = true; // simulate StartRun
+        rs.stats.time_survived_secs = 10; // large enough to exceed spawn interval
+        let rc = Rc::new(rs);
+        let after = rc.reduce(super::RunAction::SimTick { dt: 0.016 });
+        assert!(after.enemies.len() >= 1, "Enemy did not spawn");
+    }
+
+    #[test]
+    fn starting_gold_applied_only_once() {
+        // Prepare upgrades with StartingGold level 3
+        let mut ups = UpgradeState::default();
+        ups.levels.insert(UpgradeId::StartingGold.key().into(), 3);
+        let rs = RunState::new_with_upgrades(
+            GridSize {
+                width: 10,
+                height: 10,
+            },
+            &ups,
+            RouteMode::Loop,
+        );
+        // Base gold is 2, each level +2 => +6 => 8 total
+        assert_eq!(
+            rs.currencies.gold, 8,
+            "Starting gold not applied correctly on new run"
+        );
+        assert_eq!(
+            rs.starting_gold_applied_level, 3,
+            "Applied level tracker incorrect"
+        );
+        // Simulate mid-run (started) and re-apply upgrades -- should not change gold
+        let mut rs2 = rs.clone();
+        let before = rs2.currencies.gold;
+        rs2.started = true;
+        rs2.stats.time_survived_secs = 5;
+        apply_upgrades_to_run(&mut rs2, &ups);
+        assert_eq!(
+            rs2.currencies.gold, before,
+            "Gold changed mid-run when it should not"
+        );
+    }
+
+    #[test]
+    fn starting_gold_applies_difference_incrementally_pre_run() {
+        let mut ups = UpgradeState::default();
+        let mut rs = RunState::new_basic(GridSize {
+            width: 10,
+            height: 10,
+        });
+        // level 0 -> 1
+        ups.levels.insert(UpgradeId::StartingGold.key().into(), 1);
+        apply_upgrades_to_run(&mut rs, &ups);
+        assert_eq!(
+            rs.currencies.gold,
+            2 + 2 * 1,
+            "Level 1 starting gold incorrect"
+        );
+        assert_eq!(rs.starting_gold_applied_level, 1);
+        // level 1 -> 3 adds only +4 more (delta levels =2)
+        ups.levels.insert(UpgradeId::StartingGold.key().into(), 3);
+        apply_upgrades_to_run(&mut rs, &ups);
+        assert_eq!(
+            rs.currencies.gold,
+            2 + 2 * 3,
+            "Incremental starting gold difference not applied correctly"
+        );
+        assert_eq!(rs.starting_gold_applied_level, 3);
+    }
+
+    #[test]
+    fn same_seed_generates_same_map() {
+        let gs = GridSize {
+            width: 18,
+            height: 18,
+        };
+        let ups = UpgradeState::default();
+        let a = RunState::new_with_upgrades_seeded(gs, &ups, 1234);
+        let b = RunState::new_with_upgrades_seeded(gs, &ups, 1234);
+        assert_eq!(a.tiles, b.tiles);
+        assert_eq!(a.map_seed, 1234);
+        let c = RunState::new_with_upgrades_seeded(gs, &ups, 1235);
+        assert_ne!(a.tiles, c.tiles, "consecutive seeds should differ");
+    }
+
+    #[test]
+    fn seeded_runs_are_byte_identical() {
+        let mut ups = UpgradeState::default();
+        for id in [
+            UpgradeId::GoldTileChance,
+            UpgradeId::BoostColdUnlock,
+            UpgradeId::BoostFireUnlock,
+        ] {
+            ups.levels.insert(id.key().into(), 2);
+        }
+        let gs = GridSize {
+            width: 32,
+            height: 32,
+        };
+        let bytes = |ups: &UpgradeState, mode| {
+            let rs = RunState::new_with_route(gs, ups, 987_654_321, mode);
+            serde_json::to_vec(&*rs.tiles).unwrap()
+        };
+        for mode in [RouteMode::Loop, RouteMode::End] {
+            assert_eq!(bytes(&ups, mode), bytes(&ups, mode));
+        }
+        // Upgrade levels are part of the recipe
+        let loop_map = bytes(&ups, RouteMode::Loop);
+        ups.levels.insert(UpgradeId::GoldTileChance.key().into(), 3);
+        assert_ne!(bytes(&ups, RouteMode::Loop), loop_map);
+    }
+
+    #[test]
+    fn map_candidates_match_created_runs() {
+        let mut ups = UpgradeState::default();
+        ups.levels
+            .insert(UpgradeId::BoostColdUnlock.key().into(), 1);
+        for size in [14u32, 52] {
+            let gs = GridSize {
+                width: size,
+                height: size,
+            };
+            let cands = generate_map_candidates(gs, &ups, 77, 4);
+            assert_eq!(cands.len(), 4);
+            for (i, c) in cands.iter().enumerate() {
+                assert_eq!(c.seed, 77 + i as u64, "seeds are consecutive");
+                let rs = RunState::new_with_upgrades_seeded(gs, &ups, c.seed);
+                assert_eq!(c.summary, summarize_map(&rs.tiles));
+                assert!(c.summary.orientation.is_some());
+                assert_eq!(c.thumb.len(), (c.thumb_width * c.thumb_height) as usize);
+                assert!(
+                    c.thumb.contains(&ThumbCell::Start),
+                    "start corridor visible"
+                );
+            }
+            let stride = if size > 40 { 2 } else { 1 };
+            assert!(cands.iter().all(|c| c.thumb_stride == stride));
+        }
+    }
+
+    #[test]
+    fn reset_with_seed_uses_chosen_seed() {
+        let ups = UpgradeState::default();
+        let mut rs = make_run();
+        rs.currencies.research = 9;
+        let after = Rc::new(rs).reduce(RunAction::ResetRunWithSeed {
+            ups: ups.clone(),
+            seed: 42,
+        });
+        assert_eq!(after.map_seed, 42);
+        assert_eq!(after.currencies.research, 9);
+        let gs = after.grid_size;
+        let expected = RunState::new_with_upgrades_seeded(gs, &ups, 42);
+        assert_eq!(after.tiles, expected.tiles);
+    }
+
+    #[test]
+    fn pregenerated_start_matches_a_seeded_reset() {
+        let ups = UpgradeState::default();
+        let mut rs = make_run();
+        rs.currencies.research = 9;
+        rs.game_over = true;
+        let rc = Rc::new(rs);
+        let seeded = rc.clone().reduce(RunAction::ResetRunWithSeed {
+            ups: ups.clone(),
+            seed: 42,
+        });
+        // Built before the research below was earned; the swap keeps the live balance
+        let mut early = RunState::new_with_upgrades_seeded(seeded.grid_size, &ups, 42);
+        early.currencies.research = 0;
+        let swapped = rc.reduce(RunAction::StartPregenerated {
+            state: Box::new(early),
+        });
+        assert_eq!(swapped.tiles, seeded.tiles);
+        assert_eq!(swapped.run_id, seeded.run_id);
+        assert_eq!(swapped.currencies.research, 9);
+        assert!(!swapped.game_over);
+    }
+
+    // Build a run from an ASCII map: '#' rock, '.' empty, 'W' wall, 'I' indestructible,
+    // 'S' start, 'E' entrance (Right), 'X' exit (Right)
+    fn run_from_rows(rows: &[&str]) -> RunState {
+        let mut rs = make_run();
+        let (gs, tiles) = tiles_from_rows(rows);
+        rs.grid_size = gs;
+        rs.no_target = TileBitset::new(gs.width, gs.height);
+        rs.tiles = tiles.into();
+        rs.path.clear();
+        rs.path = compute_path(&rs).into();
+        rs.path_loop = build_loop_path(&rs).into();
+        update_loop_geometry(&mut rs);
+        rs
+    }
+
+    // Short route over the top (8 steps), long route underneath (10 steps)
+    const RING: &[&str] = &[
+        "#########",
+        "#########",
+        "##.....##",
+        "##.#I#.##",
+        "##.XSE.##",
+        "##.#I#.##",
+        "##.###.##",
+        "##.....##",
+        "#########",
+    ];
+
+    // Every node one orthogonal step from the next, and from Start again unless the route ends
+    fn assert_walkable_loop(rs: &RunState, nodes: &[Position]) {
+        assert!(nodes.windows(2).all(|w| one_step(w[0], w[1])), "{nodes:?}");
+        let last = *nodes.last().unwrap();
+        let ends = rs.tiles[(last.y * rs.grid_size.width + last.x) as usize].kind == TileKind::End;
+        assert!(ends || one_step(last, nodes[0]));
+    }
+
+    #[test]
+    fn loop_gaps_are_bridged_over_open_tiles() {
+        let mut rs = run_from_rows(RING);
+        let whole = rs.path_loop.to_vec();
+        assert_walkable_loop(&rs, &whole);
+        // The path no longer starts beside the Entrance: its first two steps are walked again
+        let cut: Vec<Position> = rs.path[2..].to_vec();
+        rs.path = cut.into();
+        assert_eq!(build_loop_path(&rs), whole);
+
+        // With the corridor off the Entrance rocked in, and the way back round through Start and
+        // the other marker too, the gap can't be bridged: no loop at all rather than one that
+        // walks through rock
+        let cut: Vec<Position> = rs.path[1..].to_vec();
+        rs.path = cut.into();
+        for idx in [3 * 9 + 6, 5 * 9 + 6, 4 * 9 + 2] {
+            rs.tiles[idx].kind = TileKind::Rock {
+                has_gold: false,
+                boost: None,
+            };
+        }
+        assert!(build_loop_path(&rs).is_empty());
+
+        // Start and the markers are walked like open ground
+        let rs = run_from_rows(RING);
+        let across = [(5, 4), (4, 4), (3, 4)].map(|(x, y)| Position { x, y });
+        assert_eq!(
+            bridge(&rs, Position { x: 6, y: 4 }, Position { x: 2, y: 4 }, &[]),
+            Some(across.to_vec())
+        );
+
+        for seed in 0..8 {
+            let gs = GridSize {
+                width: 14,
+                height: 14,
+            };
+            let rs = RunState::new_with_upgrades_seeded(gs, &UpgradeState::default(), seed);
+            assert_walkable_loop(&rs, &rs.path_loop);
+        }
+    }
+
+    #[test]
+    fn wall_effect_lengthens_loop() {
+        let rs = run_from_rows(RING);
+        assert!(!rs.path.is_empty());
+        let eff = wall_placement_effect(&rs, 4, 2).expect("wall on top route keeps a path");
+        assert!(
+            (eff.delta() - 2.0).abs() < 1e-9,
+            "delta was {}",
+            eff.delta()
+        );
+        assert!(eff.new_loop.iter().any(|p| p.y == 7));
+        // State itself is untouched
+        assert!(matches!(
+            rs.tiles[(2 * 9 + 4) as usize].kind,
+            TileKind::Empty
+        ));
+    }
+
+    #[test]
+    fn wall_effect_matches_the_placed_wall() {
+        let rc = Rc::new(run_from_rows(RING));
+        let w = rc.grid_size.width;
+        let mut compared = 0;
+        for idx in 0..rc.tiles.len() {
+            let (x, y) = (idx as u32 % w, idx as u32 / w);
+            let Some(eff) = wall_placement_effect(&rc, x, y) else {
+                continue;
+            };
+            let placed = rc.clone().reduce(RunAction::PlaceWall { x, y });
+            if placed.tiles[idx].kind != TileKind::Wall {
+                continue;
+            }
+            assert_eq!(eff.new_loop, *placed.path_loop, "wall at ({x}, {y})");
+            assert!((eff.new_length - placed.loop_total_length).abs() < 1e-9);
+            compared += 1;
+        }
+        assert!(compared > 1);
+    }
+
+    #[test]
+    fn wall_effect_reports_shortening_from_stale_path() {
+        let mut rs = run_from_rows(RING);
+        // Force the long route, then reopen the short one without recomputing
+        let top = (2 * 9 + 4) as usize;
+        rs.tiles[top].kind = TileKind::Wall;
+        rs.path = compute_path(&rs).into();
+        rs.path_loop = build_loop_path(&rs).into();
+        update_loop_geometry(&mut rs);
+        rs.tiles[top].kind = TileKind::Empty;
+        let eff = wall_placement_effect(&rs, 4, 7).expect("top route still open");
+        assert!(
+            eff.delta() < 0.0,
+            "expected negative delta, got {}",
+            eff.delta()
+        );
+    }
+
+    #[test]
+    fn wall_effect_none_when_path_breaks() {
+        let rs = run_from_rows(RING);
+        // Only empty neighbour of the entrance
+        assert!(wall_placement_effect(&rs, 6, 4).is_none());
+        // Non-empty tiles can't take a wall
+        assert!(wall_placement_effect(&rs, 0, 0).is_none());
+        assert!(wall_placement_effect(&rs, 99, 0).is_none());
+    }
+
+    #[test]
+    fn wall_undo_restores_the_tile_and_path_within_the_window() {
+        let rc = Rc::new(run_from_rows(RING));
+        let walled = rc.clone().reduce(RunAction::PlaceWall { x: 4, y: 2 });
+        let top = 2 * 9 + 4;
+        assert_eq!(walled.last_wall.map(|w| w.idx), Some(top));
+        let mut later = (*walled).clone();
+        later.sim_time += WALL_UNDO_SECS - 0.5;
+        let undone = Rc::new(later).reduce(RunAction::UndoLastWall);
+        assert_eq!(undone.tiles[top].kind, TileKind::Empty);
+        assert_eq!(undone.path, rc.path);
+        assert_eq!(undone.path_loop, rc.path_loop);
+        assert_eq!(undone.loop_total_length, rc.loop_total_length);
+        assert!(undone.last_wall.is_none());
+        assert_eq!((walled.stats.walls_built, undone.stats.walls_built), (1, 0));
+        // Only once
+        let again = undone.clone().reduce(RunAction::UndoLastWall);
+        assert_eq!(again.tiles, undone.tiles);
+        assert_eq!(again.stats.walls_built, 0);
+    }
+
+    #[test]
+    fn wall_undo_expires_and_refuses_an_occupied_tile() {
+        let walled = Rc::new(run_from_rows(RING)).reduce(RunAction::PlaceWall { x: 4, y: 2 });
+        let top = 2 * 9 + 4;
+        let mut late = (*walled).clone();
+        late.sim_time += WALL_UNDO_SECS + 0.1;
+        assert!(undoable_wall(&late).is_none());
+        let kept = Rc::new(late).reduce(RunAction::UndoLastWall);
+        assert_eq!(kept.tiles[top].kind, TileKind::Wall);
+        assert_eq!(kept.path, walled.path);
+        assert!(kept.last_wall.is_none());
+
+        let mut crowded = (*walled).clone();
+        let mut e = veteran_run(0).enemies[0].clone();
+        e.x = 4.5;
+        e.y = 2.5;
+        crowded.enemies.push(e);
+        assert!(undoable_wall(&crowded).is_none());
+        let kept = Rc::new(crowded).reduce(RunAction::UndoLastWall);
+        assert_eq!(kept.tiles[top].kind, TileKind::Wall);
+        assert_eq!(kept.stats.walls_built, 1);
+    }
+
+    #[test]
+    fn wall_undo_refuses_a_tile_an_enemy_walked_through() {
+        let mut rs = run_from_rows(RING);
+        rs.started = true;
+        // Walled right under an enemy, which then walks off along the new route
+        let mut e = veteran_run(0).enemies[0].clone();
+        (e.x, e.y, e.speed_tps) = (4.5, 2.5, 1.0);
+        rs.enemies.push(e);
+        let top = 2 * 9 + 4;
+        let mut rc = Rc::new(rs).reduce(RunAction::PlaceWall { x: 4, y: 2 });
+        assert_eq!(rc.tiles[top].kind, TileKind::Wall);
+        for _ in 0..4 {
+            rc = rc.reduce(RunAction::SimTick { dt: 0.25 });
+        }
+        assert!(!enemy_on(&rc, top), "the enemy has moved on");
+        assert!(rc.sim_time - rc.last_wall.unwrap().placed_at < WALL_UNDO_SECS);
+        assert!(rc.last_wall.is_some_and(|w| w.entered));
+        assert!(undoable_wall(&rc).is_none());
+        let kept = rc.reduce(RunAction::UndoLastWall);
+        assert_eq!(kept.tiles[top].kind, TileKind::Wall);
+        assert_eq!(kept.stats.walls_built, 1);
+    }
+
+    fn walls(rs: &RunState) -> Vec<usize> {
+        (0..rs.tiles.len())
+            .filter(|&i| rs.tiles[i].kind == TileKind::Wall)
+            .collect()
+    }
+
+    #[test]
+    fn dragged_walls_land_in_one_step() {
+        let rc = Rc::new(run_from_rows(RING));
+        let one_by_one = rc
+            .clone()
+            .reduce(RunAction::PlaceWall { x: 4, y: 2 })
+            .reduce(RunAction::PlaceWall { x: 3, y: 2 });
+        let drag = vec![Position { x: 4, y: 2 }, Position { x: 3, y: 2 }];
+        assert!(walls_keep_path(&rc, &drag));
+        let dragged = rc.clone().reduce(RunAction::PlaceWalls { tiles: drag });
+        assert_eq!(dragged.version, rc.version + 1);
+        assert_eq!(walls(&dragged), vec![2 * 9 + 3, 2 * 9 + 4]);
+        assert_eq!(dragged.path, one_by_one.path);
+        assert_eq!(dragged.path_loop, one_by_one.path_loop);
+        assert_eq!(dragged.last_wall.map(|w| w.idx), Some(2 * 9 + 3));
+        // Non-Empty tiles in the drag are stepped over
+        let skipped = rc.reduce(RunAction::PlaceWalls {
+            tiles: vec![Position { x: 0, y: 0 }, Position { x: 4, y: 2 }],
+        });
+        assert_eq!(walls(&skipped), vec![2 * 9 + 4]);
+    }
+
+    #[test]
+    fn actions_timed_on_a_stale_board_are_checked_against_the_live_one() {
+        let rc = Rc::new(run_from_rows(RING));
+        // (4, 1) is only reachable through (4, 2); walling either side of that seals it off
+        let corner = 9 + 4;
+        assert!(crate::state::compute_interactable_mask(&rc)[corner]);
+        let sealed = rc
+            .clone()
+            .reduce(RunAction::PlaceWall { x: 3, y: 2 })
+            .reduce(RunAction::PlaceWall { x: 5, y: 2 });
+        assert_eq!(walls(&sealed), vec![2 * 9 + 3, 2 * 9 + 5]);
+        let mask = crate::state::compute_interactable_mask(&sealed);
+        assert!(!mask[corner] && !mask[2 * 9 + 4]);
+
+        // Mining that finished against the old board goes through only there
+        let mine = RunAction::MiningComplete { idx: corner };
+        let on_time = rc.clone().reduce(mine.clone());
+        assert_eq!(on_time.tiles[corner].kind, TileKind::Empty);
+        let late = sealed.clone().reduce(mine);
+        assert_eq!(late.tiles, sealed.tiles);
+        assert_eq!(late.stats.blocks_mined, 0);
+        // Nor can the pocket take a tower or a wall
+        let mut rich = (*sealed).clone();
+        rich.currencies.gold = 1000;
+        let rich = Rc::new(rich);
+        let tower = rich.clone().reduce(RunAction::PlaceTower {
+            x: 4,
+            y: 1,
+            kind: TowerKind::Basic,
+        });
+        assert!(tower.towers.is_empty());
+        assert_eq!(rich.currencies.gold, tower.currencies.gold);
+        let wall = rich.reduce(RunAction::PlaceWall { x: 4, y: 2 });
+        assert_eq!(walls(&wall), walls(&sealed));
+    }
+
+    #[test]
+    fn a_drag_that_blocks_the_loop_keeps_only_its_valid_prefix() {
+        let rc = Rc::new(run_from_rows(RING));
+        // Closing the top route leaves the bottom one; closing that too would seal the loop
+        let drag = vec![
+            Position { x: 4, y: 2 },
+            Position { x: 4, y: 7 },
+            Position { x: 3, y: 2 },
+        ];
+        assert!(!walls_keep_path(&rc, &drag[..2]));
+        let dragged = rc.clone().reduce(RunAction::PlaceWalls { tiles: drag });
+        assert_eq!(walls(&dragged), vec![2 * 9 + 4]);
+        assert!(!dragged.path.is_empty());
+        assert_eq!(
+            dragged.path,
+            rc.clone().reduce(RunAction::PlaceWall { x: 4, y: 2 }).path
+        );
+
+        // Blocking from the first tile places nothing at all
+        let sealed = rc.clone().reduce(RunAction::PlaceWalls {
+            tiles: vec![Position { x: 6, y: 4 }, Position { x: 4, y: 2 }],
+        });
+        assert!(walls(&sealed).is_empty());
+        assert_eq!(sealed.path, rc.path);
+        assert!(sealed.last_wall.is_none());
+    }
+
+    #[test]
+    fn only_the_latest_wall_is_undoable() {
+        let first = Rc::new(run_from_rows(RING)).reduce(RunAction::PlaceWall { x: 4, y: 2 });
+        let second = first.clone().reduce(RunAction::PlaceWall { x: 3, y: 2 });
+        assert_eq!(second.last_wall.map(|w| w.idx), Some(2 * 9 + 3));
+        assert_ne!(
+            second.last_wall.map(|w| w.version),
+            first.last_wall.map(|w| w.version)
+        );
+        let undone = second.reduce(RunAction::UndoLastWall);
+        assert_eq!(undone.tiles[2 * 9 + 3].kind, TileKind::Empty);
+        assert_eq!(undone.tiles[2 * 9 + 4].kind, TileKind::Wall);
+        assert_eq!(undone.path, first.path);
+        // The earlier wall stays put
+        let again = undone.reduce(RunAction::UndoLastWall);
+        assert_eq!(again.tiles[2 * 9 + 4].kind, TileKind::Wall);
+    }
+
+    // RING map with a started run, one Basic tower at (3,3) and a stationary
+    // tanky enemy parked at the start of the loop
+    fn veteran_run(damage_dealt: u64) -> RunState {
+        let mut rs = run_from_rows(RING);
+        rs.started = true;
+        let mut tw = Tower::new(3, 3, TowerKind::Basic, 3.5, 20, None);
+        tw.id = rs.entity_ids.alloc();
+        tw.damage_dealt = damage_dealt;
+        rs.towers.push(tw);
+        rs.enemies.push(Enemy {
+            id: EntityId::default(),
+            x: 4.5,
+            y: 4.5,
+            speed_tps: 0.0,
+            hp: 10_000,
+            max_hp: 10_000,
+            spawned_at: 0,
+            path_index: 0,
+            dir_dx: 0.0,
+            dir_dy: 0.0,
+            radius_scale: 1.0,
+            loop_dist: 0.0,
+            debuffs: Vec::new(),
+            kind: EnemyKind::Normal,
+            hit_flash_ttl: 0.0,
+            spawn_anim_ttl: 0.0,
+        });
+        rs.stats.enemies_spawned = 1;
+        rs
+    }
+
+    #[test]
+    fn magma_pockets_are_seeded_under_plain_rock() {
+        let gs = GridSize {
+            width: 40,
+            height: 40,
+        };
+        let pockets = |rs: &RunState| -> Vec<usize> {
+            (0..rs.tiles.len()).filter(|&i| rs.tiles[i].magma).collect()
+        };
+        let rs = RunState::new_with_upgrades_seeded(gs, &UpgradeState::default(), 9);
+        let magma = pockets(&rs);
+        assert_eq!(magma.len(), 4);
+        for &i in &magma {
+            assert_eq!(
+                rs.tiles[i].kind,
+                TileKind::Rock {
+                    has_gold: false,
+                    boost: None
+                }
+            );
+        }
+        let again = RunState::new_with_upgrades_seeded(gs, &UpgradeState::default(), 9);
+        assert_eq!(pockets(&again), magma);
+        // Mining one opens lava; the pockets sit deep in the rock, out of the player's reach
+        let mut mined = rs;
+        complete_mining(&mut mined, magma[0]);
+        assert!(mined.tiles[magma[0]].is_lava());
+    }
+
+    // RING without the tower: lava on the first open loop tile, a parked enemy on it with `hp`
+    // and a healthy one parked further round
+    fn lava_run(hp: u32) -> RunState {
+        let mut rs = veteran_run(0);
+        rs.towers.clear();
+        let template = rs.enemies.remove(0);
+        let w = rs.grid_size.width;
+        let on_loop = |i: usize| (rs.path_loop[i].y * w + rs.path_loop[i].x) as usize;
+        let lava = (0..rs.path_loop.len())
+            .find(|&i| rs.tiles[on_loop(i)].kind == TileKind::Empty)
+            .unwrap();
+        let (lava_idx, safe) = (on_loop(lava), lava + 4);
+        rs.tiles[lava_idx].magma = true;
+        rs.enemies.push(Enemy {
+            hp,
+            loop_dist: rs.loop_cum_lengths[lava],
+            ..template.clone()
+        });
+        rs.enemies.push(Enemy {
+            id: EntityId(1),
+            hp: 100,
+            loop_dist: rs.loop_cum_lengths[safe],
+            ..template
+        });
+        rs
+    }
+
+    #[test]
+    fn lava_burns_only_enemies_standing_on_it() {
+        let mut rc = Rc::new(lava_run(100));
+        for _ in 0..4 {
+            rc = rc.reduce(RunAction::SimTick { dt: 0.25 });
+        }
+        // One pulse each at 0.5s and 1.0s
+        assert_eq!(rc.enemies[0].hp, 100 - 2 * LAVA_PULSE_DAMAGE);
+        assert_eq!(rc.enemies[1].hp, 100);
+        assert_eq!(rc.damage_numbers.len(), 2);
+    }
+
+    #[test]
+    fn lava_kills_are_credited() {
+        let before = lava_run(LAVA_PULSE_DAMAGE + 1);
+        let research = before.currencies.research;
+        let mut rc = Rc::new(before);
+        for _ in 0..4 {
+            rc = rc.reduce(RunAction::SimTick { dt: 0.25 });
+        }
+        assert_eq!(rc.enemies.len(), 1);
+        assert_eq!(rc.stats.enemies_killed, 1);
+        assert!(rc.currencies.research > research);
+        assert!(rc.pickups.is_empty());
+    }
+
+    // RING without the tower: an enemy with `hp` parked on the first loop tile carrying 2 DPS
+    // of poison, a healthy one on the next tile and another four tiles on
+    fn poison_run(hp: u32) -> RunState {
+        let mut rs = veteran_run(0);
+        rs.towers.clear();
+        let template = rs.enemies.remove(0);
+        for (id, (i, hp)) in [(0, hp), (1, 100), (4, 100)].into_iter().enumerate() {
+            let p = rs.path_loop[i];
+            rs.enemies.push(Enemy {
+                id: EntityId(id as u64),
+                x: p.x as f64 + 0.5,
+                y: p.y as f64 + 0.5,
+                hp,
+                loop_dist: rs.loop_cum_lengths[i],
+                ..template.clone()
+            });
+        }
+        rs.enemies[0].debuffs.push(Debuff {
+            kind: DebuffKind::Poison,
+            remaining: 4.0,
+            strength: 2.0,
+        });
+        rs
+    }
+
+    fn poisoned(e: &Enemy) -> bool {
+        e.debuffs.iter().any(|d| d.kind == DebuffKind::Poison)
+    }
+
+    #[test]
+    fn poison_kills_are_credited() {
+        let before = poison_run(2);
+        let research = before.currencies.research;
+        let rc = tick(Rc::new(before), 4);
+        assert_eq!(rc.enemies.len(), 2);
+        assert_eq!(rc.stats.enemies_killed, 1);
+        assert!(rc.currencies.research > research);
+        // Without Poison Spread the poison dies with it
+        assert!(!rc.enemies.iter().any(poisoned));
+    }
+
+    #[test]
+    fn poison_spread_passes_to_the_nearest_enemy_only() {
+        let mut rs = poison_run(2);
+        rs.poison_spread = true;
+        let rc = tick(Rc::new(rs), 4);
+        assert_eq!(rc.stats.enemies_killed, 1);
+        let near = rc.enemies.iter().find(|e| e.id == EntityId(1)).unwrap();
+        let far = rc.enemies.iter().find(|e| e.id == EntityId(2)).unwrap();
+        assert!(poisoned(near));
+        assert!(!poisoned(far));
+    }
+
+    #[test]
+    fn poison_stacks_to_a_cap_and_ticks_at_frame_rate() {
+        let mut e = poison_run(100).enemies.remove(1);
+        let hit = Debuff {
+            kind: DebuffKind::Poison,
+            remaining: 3.0,
+            strength: 1.0,
+        };
+        for _ in 0..5 {
+            inflict(&mut e, hit.clone());
+        }
+        assert_eq!(e.debuffs.len(), 1);
+        assert_eq!(e.debuffs[0].strength, POISON_MAX_STACKS);
+        // 1.5s of 60 fps frames at 1 DPS still lands one point
+        let mut remaining = 4.0;
+        let mut dealt = 0;
+        for _ in 0..90 {
+            let before = remaining;
+            remaining -= 1.0 / 60.0;
+            dealt += dot_damage(1.0, before, remaining);
+        }
+        assert_eq!(dealt, 1);
+    }
+
+    fn tick(mut rc: Rc<RunState>, n: usize) -> Rc<RunState> {
+        for _ in 0..n {
+            rc = rc.reduce(RunAction::SimTick { dt: 0.25 });
+        }
+        rc
+    }
+
+    #[test]
+    fn manual_collection_drops_kill_rewards_as_pickups() {
+        let mut rs = lava_run(LAVA_PULSE_DAMAGE + 1);
+        rs.manual_collection = true;
+        rs.gold_bounty_per_kill = 3;
+        let (research, gold) = (rs.currencies.research, rs.currencies.gold);
+        let rc = tick(Rc::new(rs), 4);
+        assert_eq!(rc.stats.enemies_killed, 1);
+        assert_eq!(rc.pickups.len(), 1);
+        let p = &rc.pickups[0];
+        // Dropped where the enemy died, on the lava
+        let at = (p.y.floor() as u32 * rc.grid_size.width + p.x.floor() as u32) as usize;
+        assert!(rc.tiles[at].magma);
+        assert_eq!(p.gold, 3);
+        assert_eq!(p.research, EnemyKind::Normal.research_reward());
+        assert_eq!(
+            (rc.currencies.research, rc.currencies.gold),
+            (research, gold)
+        );
+    }
+
+    fn pickup_run() -> RunState {
+        let mut rs = lava_run(100);
+        rs.enemies.clear();
+        rs.manual_collection = true;
+        rs.clean_streak = 0;
+        rs.research_carry = 0.0;
+        rs.pickups = vec![Pickup {
+            x: 4.5,
+            y: 4.5,
+            research: 8.0,
+            gold: 6,
+            ttl: 0.5,
+        }];
+        rs
+    }
+
+    #[test]
+    fn expired_pickups_pay_half_exactly_once() {
+        let rs = pickup_run();
+        let (research, gold) = (rs.currencies.research, rs.currencies.gold);
+        let rc = tick(Rc::new(rs), 2);
+        assert!(rc.pickups.is_empty());
+        assert_eq!(rc.currencies.research, research + 4);
+        assert_eq!(rc.currencies.gold, gold + 3);
+        let rc = tick(rc, 8);
+        assert_eq!(rc.currencies.research, research + 4);
+        assert_eq!(rc.currencies.gold, gold + 3);
+    }
+
+    #[test]
+    fn sweeping_the_cursor_collects_full_value() {
+        let rs = Rc::new(pickup_run());
+        let (research, gold) = (rs.currencies.research, rs.currencies.gold);
+        // Out of reach: nothing changes
+        let missed = rs
+            .clone()
+            .reduce(RunAction::CollectPickups { x: 9.0, y: 4.5 });
+        assert!(Rc::ptr_eq(&missed, &rs));
+        let got = rs.reduce(RunAction::CollectPickups { x: 5.5, y: 5.0 });
+        assert!(got.pickups.is_empty());
+        assert_eq!(got.currencies.research, research + 8);
+        assert_eq!(got.currencies.gold, gold + 6);
+        // Nothing left to expire
+        let later = tick(got, 4);
+        assert_eq!(later.currencies.research, research + 8);
+    }
+
+    #[test]
+    fn towers_keep_off_tiles_beside_lava() {
+        let mut rs = run_from_rows(RING);
+        rs.currencies.gold = 100;
+        // (2, 2) is the top-left corner of the loop
+        rs.tiles[2 * 9 + 2].magma = true;
+        assert!(too_hot_for_tower(&rs, 1, 2));
+        assert!(too_hot_for_tower(&rs, 2, 1));
+        // Diagonal neighbours are fine
+        assert!(!too_hot_for_tower(&rs, 1, 1));
+        assert!(!too_hot_for_tower(&rs, 3, 3));
+        assert!(!too_hot_for_tower(&rs, 1, 3));
+        let rc = Rc::new(rs);
+        let hot = rc.clone().reduce(RunAction::PlaceTower {
+            x: 1,
+            y: 2,
+            kind: TowerKind::Basic,
+        });
+        assert!(hot.towers.is_empty());
+        let cool = rc.reduce(RunAction::PlaceTower {
+            x: 1,
+            y: 3,
+            kind: TowerKind::Basic,
+        });
+        assert_eq!(cool.towers.len(), 1);
+    }
+
+    #[test]
+    fn crossing_rank_threshold_raises_next_shot_damage() {
+        let mut rc = Rc::new(veteran_run(90));
+        let mut fired = Vec::new();
+        for _ in 0..200 {
+            let before = rc.projectiles.len();
+            rc = rc.reduce(RunAction::SimTick { dt: 0.05 });
+            if rc.projectiles.len() > before {
+                fired.extend(rc.projectiles.last().map(|p| p.damage));
+            }
+            if fired.len() >= 2 {
+                break;
+            }
+        }
+        assert_eq!(
+            fired,
+            vec![20, 21],
+            "second shot should carry the rank 1 bonus"
+        );
+        assert_eq!(rc.towers[0].rank(), 1);
+        assert!(rc.towers[0].damage_dealt >= 110);
+    }
+
+    #[test]
+    fn move_preserves_veterancy() {
+        let rs = Rc::new(veteran_run(600));
+        let after = rs.reduce(RunAction::MoveTower {
+            from: (3, 3),
+            to: (5, 3),
+        });
+        assert_eq!(after.towers.len(), 1);
+        let tw = &after.towers[0];
+        assert_eq!((tw.x, tw.y), (5, 3));
+        assert_eq!(tw.damage_dealt, 600);
+        assert_eq!(tw.rank(), 2);
+    }
+
+    #[test]
+    fn remove_and_replace_resets_veterancy() {
+        let mut rs = veteran_run(2500);
+        rs.currencies.gold = 100;
+        let rc = Rc::new(rs);
+        let removed = rc.reduce(RunAction::SellTower { x: 3, y: 3 });
+        let placed = removed.reduce(RunAction::PlaceTower {
+            x: 3,
+            y: 3,
+            kind: TowerKind::Basic,
+        });
+        assert_eq!(placed.towers.len(), 1);
+        assert_eq!(placed.towers[0].damage_dealt, 0);
+        assert_eq!(placed.towers[0].rank(), 0);
+    }
+
+    #[test]
+    fn refund_percent_adds_recovery_levels_up_to_full() {
+        let mut ups = UpgradeState {
+            tower_refund_rate_percent: 50,
+            ..Default::default()
+        };
+        assert_eq!(tower_refund_percent(&ups), 50);
+        ups.levels
+            .insert(UpgradeId::ResourceRecovery.key().into(), 2);
+        assert_eq!(tower_refund_percent(&ups), 90);
+        ups.levels
+            .insert(UpgradeId::ResourceRecovery.key().into(), 5);
+        assert_eq!(tower_refund_percent(&ups), 100);
+        // Partial refunds round down; perk towers were free
+        assert_eq!(sell_refund(7, 50, 7), 3);
+        assert_eq!(sell_refund(7, 100, 0), 0);
+    }
+
+    #[test]
+    fn prestige_wipes_levels_for_points_above_the_threshold() {
+        assert_eq!(prestige_points_for(99), 0);
+        assert_eq!(prestige_points_for(10_000), 10);
+        assert_eq!(prestige_points_for(16_384), 12);
+
+        let mut ups = UpgradeState {
+            tower_refund_rate_percent: 50,
+            ..Default::default()
+        };
+        ups.levels.insert(UpgradeId::TowerDamage1.key().into(), 3);
+        ups.levels.insert(UpgradeId::MiningSpeed.key().into(), 2);
+        ups.meta.prestige_points = 4;
+        let spent = ups.total_spent();
+        assert_eq!(prestige(&ups, PRESTIGE_MIN_RESEARCH - spent - 1), None);
+        let after = prestige(&ups, PRESTIGE_MIN_RESEARCH - spent).unwrap();
+        assert!(after.levels.values().all(|l| *l == 0));
+        assert_eq!(after.tower_refund_rate_percent, 50);
+        assert_eq!(
+            after.meta,
+            MetaState {
+                prestige_points: 4 + prestige_points_for(spent),
+                prestige_count: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn prestige_points_scale_damage_and_mining_speed() {
+        let gs = GridSize {
+            width: 12,
+            height: 12,
+        };
+        let mut rs = RunState::new_with_upgrades_seeded(gs, &UpgradeState::default(), 7);
+        assert_eq!(rs.tower_base_damage, 2);
+        assert!((rs.mining_speed - 2.0).abs() < 1e-9);
+        let mut ups = UpgradeState::default();
+        ups.meta.prestige_points = 25;
+        assert!((ups.meta.multiplier() - 1.5).abs() < 1e-9);
+        apply_upgrades_to_run(&mut rs, &ups);
+        let tw = Tower::new(0, 0, TowerKind::Basic, 3.5, rs.tower_base_damage, None);
+        assert!((rs.shot_damage(&tw) - 3.0).abs() < 1e-9);
+        assert!((rs.mining_speed - 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn one_prestige_point_raises_tower_damage() {
+        let gs = GridSize {
+            width: 12,
+            height: 12,
+        };
+        let mut ups = UpgradeState::default();
+        let base = RunState::new_with_upgrades_seeded(gs, &ups, 7);
+        ups.meta.prestige_points = 1;
+        let mut rs = base.clone();
+        apply_upgrades_to_run(&mut rs, &ups);
+        let tw = Tower::new(0, 0, TowerKind::Basic, 3.5, rs.tower_base_damage, None);
+        let before = base.shot_damage(&tw);
+        let after = rs.shot_damage(&tw);
+        assert!((after - before * (1.0 + PRESTIGE_BONUS_PER_POINT)).abs() < 1e-9);
+        assert!(tower_stats(&rs, &tw).damage > tower_stats(&base, &tw).damage);
+    }
+
+    fn gold_after_selling(recovery: u8) -> u64 {
+        let mut ups = UpgradeState {
+            tower_refund_rate_percent: 50,
+            ..Default::default()
+        };
+        ups.levels
+            .insert(UpgradeId::ResourceRecovery.key().into(), recovery);
+        let mut rs = run_from_rows(RING);
+        apply_upgrades_to_run(&mut rs, &ups);
+        rs.tower_cost = 9;
+        rs.currencies.gold = 9;
+        let placed = Rc::new(rs).reduce(RunAction::PlaceTower {
+            x: 3,
+            y: 3,
+            kind: TowerKind::Basic,
+        });
+        assert_eq!(placed.towers[0].paid, 9);
+        let sold = placed.reduce(RunAction::SellTower { x: 3, y: 3 });
+        assert!(sold.towers.is_empty());
+        sold.currencies.gold
+    }
+
+    #[test]
+    fn selling_refunds_by_recovery_level() {
+        assert_eq!(gold_after_selling(0), 4);
+        assert_eq!(gold_after_selling(5), 9);
+    }
+
+    #[test]
+    fn placing_each_kind_charges_its_own_price() {
+        for kind in TowerKind::ALL {
+            let mut rs = run_from_rows(RING);
+            rs.currencies.gold = 10;
+            let cost = rs.tower_cost_of(&kind);
+            let placed = Rc::new(rs).reduce(RunAction::PlaceTower {
+                x: 3,
+                y: 3,
+                kind: kind.clone(),
+            });
+            assert_eq!(placed.currencies.gold, 10 - cost, "{kind:?}");
+            let tw = &placed.towers[0];
+            assert_eq!(tw.paid, cost);
+            let expected = Tower::new(
+                3,
+                3,
+                kind.clone(),
+                placed.tower_base_range,
+                placed.tower_base_damage,
+                None,
+            );
+            assert_eq!(tw.kind, kind);
+            assert_eq!(tw.damage, expected.damage);
+            assert!((tw.range - expected.range).abs() < 1e-9);
+            assert!((tw.fire_rate - expected.fire_rate).abs() < 1e-9);
+        }
+        let rs = run_from_rows(RING);
+        assert!(rs.tower_cost < rs.tower_cost_slow && rs.tower_cost_slow < rs.tower_cost_damage);
+    }
+
+    // A tower placed on (3, 3) of the RING map, on a rock carrying `boost`
+    fn placed_on_boost(boost: Option<BoostKind>) -> Tower {
+        let mut rs = run_from_rows(RING);
+        rs.tiles[(3 * 9 + 3) as usize].kind = TileKind::Rock {
+            has_gold: false,
+            boost,
+        };
+        rs.currencies.gold = rs.tower_cost;
+        let rc = Rc::new(rs)
+            .reduce(RunAction::PlaceTower {
+                x: 3,
+                y: 3,
+                kind: TowerKind::Basic,
+            })
+            .reduce(RunAction::ApplyUpgrades {
+                ups: UpgradeState::default(),
+            });
+        assert_eq!(rc.towers.len(), 1);
+        rc.towers[0].clone()
+    }
+
+    #[test]
+    fn boost_tiles_improve_the_tower_built_on_them() {
+        let plain = placed_on_boost(None);
+        let poison = placed_on_boost(Some(BoostKind::Damage));
+        assert_eq!(poison.boost, Some(BoostKind::Damage));
+        assert_eq!(
+            poison.damage,
+            (plain.damage as f64 * BOOST_DAMAGE_MULT).round() as u32
+        );
+        assert!(poison.apply_debuff.is_some());
+        let ranged = placed_on_boost(Some(BoostKind::Range));
+        assert!((ranged.range - (plain.range + BOOST_RANGE_BONUS)).abs() < 1e-9);
+        assert_eq!(ranged.damage, plain.damage);
+        let healing = placed_on_boost(Some(BoostKind::Healing));
+        assert_eq!((healing.range, healing.damage), (plain.range, plain.damage));
+        let rapid = placed_on_boost(Some(BoostKind::FireRate));
+        assert!((rapid.fire_rate - plain.fire_rate * BOOST_FIRE_RATE_MULT).abs() < 1e-9);
+        let cold = placed_on_boost(Some(BoostKind::Slow));
+        assert!(matches!(
+            cold.apply_debuff,
+            Some(Debuff {
+                kind: DebuffKind::Slow,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn upgrades_keep_the_boost_bonus_baked_in() {
+        let mut rs = run_from_rows(RING);
+        let boosted = Tower::new(
+            3,
+            3,
+            TowerKind::Basic,
+            rs.tower_base_range,
+            rs.tower_base_damage,
+            Some(BoostKind::Damage),
+        );
+        let before = boosted.damage;
+        rs.towers.push(boosted);
+        let mut ups = UpgradeState::default();
+        apply_upgrades_to_run(&mut rs, &ups);
+        assert_eq!(rs.towers[0].damage, before);
+        ups.levels.insert(UpgradeId::TowerDamage1.key().into(), 5);
+        apply_upgrades_to_run(&mut rs, &ups);
+        let base = rs.tower_base_damage as f64;
+        assert_eq!(rs.towers[0].boost, Some(BoostKind::Damage));
+        assert_eq!(
+            rs.towers[0].damage,
+            (base * BOOST_DAMAGE_MULT).round() as u32
+        );
+    }
+
+    #[test]
+    fn a_kind_out_of_budget_is_not_placed() {
+        let mut rs = run_from_rows(RING);
+        rs.currencies.gold = rs.tower_cost_slow;
+        let rc = Rc::new(rs);
+        let damage = rc.clone().reduce(RunAction::PlaceTower {
+            x: 3,
+            y: 3,
+            kind: TowerKind::Damage,
+        });
+        assert!(damage.towers.is_empty());
+        assert_eq!(damage.currencies.gold, rc.tower_cost_slow);
+        let slow = rc.reduce(RunAction::PlaceTower {
+            x: 3,
+            y: 3,
+            kind: TowerKind::Slow,
+        });
+        assert_eq!(slow.towers.len(), 1);
+        assert_eq!(slow.currencies.gold, 0);
+        // The refund follows the kind's own price
+        let sold = slow.reduce(RunAction::SellTower { x: 3, y: 3 });
+        assert_eq!(
+            sold.currencies.gold,
+            sell_refund(
+                sold.tower_cost_slow,
+                sold.tower_refund_percent,
+                sold.tower_cost_slow
+            )
+        );
+    }
+
+    #[test]
+    fn selling_a_missing_tower_is_a_no_op() {
+        let rc = Rc::new(veteran_run(0));
+        let after = rc.clone().reduce(RunAction::SellTower { x: 5, y: 3 });
+        assert_eq!(after.towers, rc.towers);
+        assert_eq!(after.currencies, rc.currencies);
+    }
+
+    #[test]
+    fn swapped_tower_stats_follow_the_kind_and_keep_veterancy() {
+        let mut rs = veteran_run(2500);
+        rs.crit_chance = 0.5;
+        rs.crit_damage_mult = 3.0;
+        // Rebuilt through the same pipeline so both use the run's base stats
+        let basic = swapped_tower(&rs, &rs.towers[0], TowerKind::Basic);
+        let heavy = swapped_tower(&rs, &basic, TowerKind::Damage);
+        assert_eq!(heavy.rank(), basic.rank());
+        let (before, after) = (tower_stats(&rs, &basic), tower_stats(&rs, &heavy));
+        assert!((after.damage - 2.0 * before.damage).abs() < 1e-9);
+        assert!((after.range - 0.7 * before.range).abs() < 1e-9);
+        assert!((after.fire_rate - 1.5 * before.fire_rate).abs() < 1e-9);
+        // Half the shots crit for 3x: 2x on average
+        assert!((after.dps - after.damage * 2.0 * after.fire_rate).abs() < 1e-9);
+        assert_eq!(after.coverage, loop_tiles_in_range(&rs, 3, 3, after.range));
+        assert!(after.coverage < before.coverage);
+    }
+
+    #[test]
+    fn tower_upgrade_costs_grow_and_stop_at_the_cap() {
+        let mut rs = veteran_run(0);
+        rs.tower_cost = 10;
+        rs.currencies.gold = 1_000;
+        let mut rc = Rc::new(rs);
+        for (level, cost) in [10, 15, 23, 34, 51].into_iter().enumerate() {
+            assert_eq!(rc.towers[0].level as usize, level);
+            assert_eq!(tower_upgrade_cost(&rc, &rc.towers[0]), Some(cost));
+            let gold = rc.currencies.gold;
+            rc = rc.reduce(RunAction::UpgradeTower { x: 3, y: 3 });
+            assert_eq!(gold - rc.currencies.gold, cost);
+        }
+        assert_eq!(rc.towers[0].level, TOWER_MAX_LEVEL);
+        assert_eq!(tower_upgrade_cost(&rc, &rc.towers[0]), None);
+        let capped = rc.clone().reduce(RunAction::UpgradeTower { x: 3, y: 3 });
+        assert_eq!(
+            (capped.towers[0].level, capped.currencies.gold),
+            (rc.towers[0].level, rc.currencies.gold)
+        );
+    }
+
+    #[test]
+    fn level_preview_matches_the_upgrade() {
+        let mut rs = veteran_run(0);
+        rs.tower_cost = 10;
+        rs.currencies.gold = 1_000;
+        let rc = Rc::new(rs);
+        let preview = leveled_tower(&rc.towers[0]).unwrap();
+        let (before, after) = (tower_stats(&rc, &rc.towers[0]), tower_stats(&rc, &preview));
+        assert!((after.range - before.range * tower_level_mults(1).0).abs() < 1e-9);
+        assert!(after.damage > before.damage);
+        let upgraded = rc.clone().reduce(RunAction::UpgradeTower { x: 3, y: 3 });
+        assert_eq!(upgraded.towers[0], preview);
+        let mut capped = preview;
+        capped.level = TOWER_MAX_LEVEL;
+        assert_eq!(leveled_tower(&capped), None);
+    }
+
+    #[test]
+    fn upgrading_a_tower_needs_the_gold() {
+        let mut rs = veteran_run(0);
+        rs.tower_cost = 10;
+        rs.currencies.gold = 9;
+        let rc = Rc::new(rs).reduce(RunAction::UpgradeTower { x: 3, y: 3 });
+        assert_eq!((rc.towers[0].level, rc.currencies.gold), (0, 9));
+    }
+
+    #[test]
+    fn tower_levels_compose_with_meta_upgrades() {
+        let mut ups = UpgradeState::default();
+        ups.levels.insert(UpgradeId::TowerDamage1.key().into(), 2);
+        let mut rs = veteran_run(0);
+        apply_upgrades_to_run(&mut rs, &ups);
+        let (range, damage) = (rs.towers[0].range, rs.towers[0].shot_damage());
+        rs.tower_cost = 10;
+        rs.currencies.gold = 1_000;
+        let mut rc = Rc::new(rs);
+        for _ in 0..2 {
+            rc = rc.reduce(RunAction::UpgradeTower { x: 3, y: 3 });
+        }
+        let mut rs = (*rc).clone();
+        let bought = (rs.towers[0].range, rs.towers[0].shot_damage());
+        apply_upgrades_to_run(&mut rs, &ups);
+        // +10% range and +50% damage on top of the upgraded stats, however they were reached
+        let recomputed = (rs.towers[0].range, rs.towers[0].shot_damage());
+        assert!((recomputed.0 - range * 1.1).abs() < 1e-9);
+        assert!((recomputed.1 - damage * 1.5).abs() < 1e-9);
+        assert_eq!(bought, recomputed);
+    }
+
+    // Tower at (3, 3) with three enemies in range: (x, y, loop_dist, hp)
+    fn targeting_board() -> RunState {
+        let mut rs = veteran_run(0);
+        let template = rs.enemies.remove(0);
+        for (x, y, loop_dist, hp) in [
+            (4.5, 3.5, 6.0, 50),
+            (2.5, 4.5, 9.0, 20),
+            (3.5, 2.8, 2.0, 90),
+        ] {
+            rs.enemies.push(Enemy {
+                x,
+                y,
+                loop_dist,
+                hp,
+                ..template.clone()
+            });
+        }
+        // Out of range, yet furthest along and strongest
+        rs.enemies.push(Enemy {
+            x: 9.5,
+            y: 9.5,
+            loop_dist: 30.0,
+            hp: 500,
+            ..template
+        });
+        rs.stats.enemies_spawned = 4;
+        rs
+    }
+
+    #[test]
+    fn each_target_mode_picks_its_enemy() {
+        let mut rs = targeting_board();
+        let lap = rs.lap_frame();
+        let tw = &mut rs.towers[0];
+        tw.range = 2.0;
+        let cases = [
+            (TargetMode::First, 1),
+            (TargetMode::Last, 2),
+            (TargetMode::Closest, 2),
+            (TargetMode::Strongest, 2),
+        ];
+        for (mode, expected) in cases {
+            tw.target_mode = mode;
+            assert_eq!(
+                tw.pick_target(&rs.enemies, &rs.no_target, lap),
+                Some(expected),
+                "{}",
+                mode.name()
+            );
+        }
+        // Progress counts from the Entrance: just short of it is nearly a full lap along
+        let from_seven = LapFrame {
+            total: 20.0,
+            entry: 7.0,
+            open: false,
+        };
+        tw.target_mode = TargetMode::First;
+        let first = tw.pick_target(&rs.enemies, &rs.no_target, from_seven);
+        assert_eq!(first, Some(0));
+        // Closest isn't just Last in disguise
+        rs.enemies[2].y = 1.6;
+        let tw = &mut rs.towers[0];
+        tw.target_mode = TargetMode::Closest;
+        assert_eq!(tw.pick_target(&rs.enemies, &rs.no_target, lap), Some(0));
+        tw.range = 0.1;
+        assert_eq!(tw.pick_target(&rs.enemies, &rs.no_target, lap), None);
+    }
+
+    #[test]
+    fn target_mode_cycles_and_steers_the_shot() {
+        let mut rs = targeting_board();
+        rs.towers[0].range = 2.0;
+        let mut rc = Rc::new(rs);
+        for mode in [TargetMode::Last, TargetMode::Closest, TargetMode::Strongest] {
+            rc = rc.reduce(RunAction::CycleTargetMode { x: 3, y: 3 });
+            assert_eq!(rc.towers[0].target_mode, mode);
+        }
+        // Strongest: the shot heads up toward the 90 hp enemy
+        let fired = rc.clone().reduce(RunAction::SimTick { dt: 0.016 });
+        let p = fired.projectiles.last().unwrap();
+        assert!(p.vy < 0.0 && p.vx.abs() < p.vy.abs());
+        let rc = rc.reduce(RunAction::CycleTargetMode { x: 3, y: 3 });
+        assert_eq!(rc.towers[0].target_mode, TargetMode::First);
+    }
+
+    #[test]
+    fn low_life_warning_latches_between_the_thresholds() {
+        // life_max 10: on below 2.5, off above 4
+        assert!(!low_life_next(false, 3, 10));
+        assert!(low_life_next(false, 2, 10));
+        assert!(low_life_next(true, 3, 10));
+        assert!(low_life_next(true, 4, 10));
+        assert!(!low_life_next(true, 5, 10));
+        // Climbing back through the gap doesn't re-enter
+        assert!(!low_life_next(false, 4, 10));
+        assert!(!low_life_next(false, 0, 0));
+    }
+
+    #[test]
+    fn low_life_follows_leaks_through_the_reducer() {
+        let mut rs = run_from_rows(RING);
+        rs.life = 3;
+        let rc = Rc::new(rs).reduce(RunAction::TickSecond);
+        assert!(!rc.low_life);
+        let mut rs = (*rc).clone();
+        rs.life = 2;
+        let rc = Rc::new(rs).reduce(RunAction::TickSecond);
+        assert!(rc.low_life);
+        let mut rs = (*rc).clone();
+        rs.life = 4;
+        let rc = Rc::new(rs).reduce(RunAction::TickSecond);
+        assert!(rc.low_life);
+    }
+
+    fn stretch_tiles(rs: &RunState, s: &UncoveredStretch) -> Vec<Position> {
+        let n = rs.path_loop.len();
+        (0..s.len)
+            .map(|k| rs.path_loop[(s.start + k) % n])
+            .collect()
+    }
+
+    #[test]
+    fn uncovered_stretch_is_the_whole_loop_without_towers() {
+        let rs = run_from_rows(RING);
+        let s = largest_uncovered_stretch(&rs).unwrap();
+        assert_eq!(s.len, rs.path_loop.len());
+        let mut covered = rs.clone();
+        covered
+            .towers
+            .push(Tower::new(1, 1, TowerKind::Basic, 20.0, 1, None));
+        assert_eq!(largest_uncovered_stretch(&covered), None);
+    }
+
+    #[test]
+    fn uncovered_stretch_wraps_and_picks_the_longest_gap() {
+        let mut rs = run_from_rows(RING);
+        let n = rs.path_loop.len();
+        // Reaches only the loop corner at (2, 2)
+        let mut tw = Tower::new(1, 1, TowerKind::Basic, 3.5, 1, None);
+        tw.range = 1.5;
+        rs.towers.push(tw.clone());
+        let s = largest_uncovered_stretch(&rs).unwrap();
+        assert_eq!(s.len, n - 1);
+        let corner = Position { x: 2, y: 2 };
+        assert!(!stretch_tiles(&rs, &s).contains(&corner));
+        // A second tower at (6, 3) splits the gap: four tiles along the top, six round the
+        // bottom across the loop's seam
+        tw.x = 7;
+        tw.y = 3;
+        tw.range = 1.0;
+        rs.towers.push(tw);
+        let s = largest_uncovered_stretch(&rs).unwrap();
+        let tiles = stretch_tiles(&rs, &s);
+        let expected: Vec<Position> = [(2, 3), (2, 4), (3, 4), (4, 4), (5, 4), (6, 4)]
+            .into_iter()
+            .map(|(x, y)| Position { x, y })
+            .collect();
+        assert_eq!(tiles, expected);
+        assert_eq!(s.mid, tiles[s.len / 2]);
+    }
+
+    fn banked_run(level: u8, gold: u64) -> Rc<RunState> {
+        let mut ups = UpgradeState::default();
+        ups.levels
+            .insert(UpgradeId::BankInterest.key().into(), level);
+        let mut rs = run_from_rows(RING);
+        apply_upgrades_to_run(&mut rs, &ups);
+        rs.started = true;
+        rs.currencies.gold = gold;
+        Rc::new(rs)
+    }
+
+    #[test]
+    fn bank_pays_interest_every_ten_seconds() {
+        for (level, expected) in [(0, 0), (1, 6), (2, 12), (3, 18), (5, 30)] {
+            let mut rc = banked_run(level, 200);
+            for s in 1..=BANK_INTEREST_PERIOD_SECS {
+                rc = rc.reduce(RunAction::TickSecond);
+                if s < BANK_INTEREST_PERIOD_SECS {
+                    assert_eq!(rc.currencies.gold, 200, "level {level} paid early");
+                }
+            }
+            assert_eq!(rc.currencies.gold, 200 + expected, "level {level}");
+            assert_eq!(rc.last_interest, expected);
+            // Floated up off Start, unless nothing was paid
+            let texts: Vec<String> = rc.floating_texts.iter().map(|t| t.text.clone()).collect();
+            let want = (expected > 0).then(|| format!("+{expected}g"));
+            assert_eq!(texts, want.into_iter().collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn kills_float_their_research_and_the_texts_are_capped() {
+        let mut rs = run_from_rows(RING);
+        let dead = |kind: EnemyKind| Enemy {
+            hp: 0,
+            kind,
+            ..walker(0.0)
+        };
+        rs.enemies = vec![
+            dead(EnemyKind::Normal),
+            dead(EnemyKind::Tank),
+            dead(EnemyKind::Swarm),
+        ];
+        rs.stats.enemies_spawned = 3;
+        let mut manual = rs.clone();
+        reap_kills(&mut rs);
+        let texts: Vec<&str> = rs.floating_texts.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(texts, vec!["+1 RP", "+3 RP", "+0.3 RP"]);
+        assert!(rs.floating_texts.iter().all(|t| t.color == RESEARCH_TEXT_COLOR));
+        // Collected by hand, the research shows up as pickups instead
+        manual.manual_collection = true;
+        reap_kills(&mut manual);
+        assert!(manual.floating_texts.is_empty());
+        assert_eq!(manual.pickups.len(), 3);
+
+        for i in 0..FLOATING_TEXT_CAP + 20 {
+            float_text(&mut rs, 0.0, 0.0, i.to_string(), GOLD_TEXT_COLOR, false);
+        }
+        assert_eq!(rs.floating_texts.len(), FLOATING_TEXT_CAP);
+        assert_eq!(rs.floating_texts[0].text, "20");
+        // And they fade like damage numbers
+        rs.started = true;
+        let rc = Rc::new(rs).reduce(RunAction::SimTick {
+            dt: FLOATING_TEXT_SECS,
+        });
+        assert!(rc.floating_texts.is_empty());
+    }
+
+    #[test]
+    fn bank_interest_is_capped_per_payment() {
+        for level in [1, 4] {
+            let rc = tick_seconds(banked_run(level, 100_000), BANK_INTEREST_PERIOD_SECS as u32);
+            let cap = BANK_INTEREST_CAP_PER_LEVEL * level as u64;
+            assert_eq!(rc.currencies.gold, 100_000 + cap);
+        }
+    }
+
+    #[test]
+    fn no_interest_while_paused_or_after_game_over() {
+        let mut paused = (*banked_run(2, 200)).clone();
+        paused.is_paused = true;
+        let rc = tick_seconds(Rc::new(paused), BANK_INTEREST_PERIOD_SECS as u32);
+        assert_eq!(rc.currencies.gold, 200);
+        let mut over = (*banked_run(2, 200)).clone();
+        over.game_over = true;
+        over.life = 0;
+        let rc = tick_seconds(Rc::new(over), BANK_INTEREST_PERIOD_SECS as u32);
+        assert_eq!(rc.currencies.gold, 200);
+    }
+
+    fn auto_mined_run(level: u8) -> Rc<RunState> {
+        let mut ups = UpgradeState::default();
+        ups.levels.insert(UpgradeId::AutoMiner.key().into(), level);
+        let mut rs = run_from_rows(RING);
+        apply_upgrades_to_run(&mut rs, &ups);
+        rs.started = true;
+        Rc::new(rs)
+    }
+
+    fn rocks(rs: &RunState) -> usize {
+        rs.tiles
+            .iter()
+            .filter(|t| matches!(t.kind, TileKind::Rock { .. }))
+            .count()
+    }
+
+    #[test]
+    fn auto_miner_clears_a_path_side_rock_each_period() {
+        let start = auto_mined_run(1);
+        let period = AUTO_MINE_SECS[0] as u32;
+        let before = rocks(&start);
+        let early = tick_seconds(start.clone(), period - 1);
+        assert_eq!(rocks(&early), before);
+        let rc = tick_seconds(start.clone(), 3 * period);
+        assert_eq!(rocks(&rc), before - 3);
+        assert_eq!(rc.stats.blocks_mined, 3);
+        assert_eq!(rc.currencies.tile_credits, start.currencies.tile_credits + 3);
+        assert_eq!(
+            rc.tiles.iter().filter(|t| t.kind == TileKind::Empty).count(),
+            start.tiles.iter().filter(|t| t.kind == TileKind::Empty).count() + 3
+        );
+        assert_eq!(rocks(&tick_seconds(auto_mined_run(0), 3 * period)), before);
+    }
+
+    #[test]
+    fn auto_miner_skips_towers_and_stops_when_paused() {
+        let mut rs = (*auto_mined_run(3)).clone();
+        let first = auto_mine_target(&rs, &crate::state::compute_interactable_mask(&rs)).unwrap();
+        let (x, y) = (first as u32 % 9, first as u32 / 9);
+        rs.towers.push(Tower::new(x, y, TowerKind::Basic, 3.5, 1, None));
+        let period = AUTO_MINE_SECS[2] as u32;
+        let rc = tick_seconds(Rc::new(rs.clone()), period);
+        assert!(matches!(rc.tiles[first].kind, TileKind::Rock { .. }));
+        assert_eq!(rocks(&rc), rocks(&rs) - 1);
+        rs.is_paused = true;
+        let rc = tick_seconds(Rc::new(rs.clone()), 3 * period);
+        assert_eq!(rocks(&rc), rocks(&rs));
+    }
+
+    #[test]
+    fn crit_expectation_averages_crits_into_every_shot() {
+        let mut rs = run_from_rows(RING);
+        assert_eq!(crit_expectation(&rs), 1.0);
+        rs.crit_chance = 0.2;
+        rs.crit_damage_mult = 2.0;
+        assert!((crit_expectation(&rs) - 1.2).abs() < 1e-12);
+        // Past certainty every shot crits, no more
+        rs.crit_chance = 1.5;
+        assert!((crit_expectation(&rs) - 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn total_dps_sums_towers_with_global_rate_and_crits() {
+        let mut rs = run_from_rows(RING);
+        assert_eq!(total_tower_dps(&rs), 0.0);
+        for (x, damage, fire_rate) in [(1, 10, 1.0), (3, 4, 2.0)] {
+            let mut tw = Tower::new(x, 1, TowerKind::Basic, 3.5, damage, None);
+            tw.damage = damage;
+            tw.fire_rate = fire_rate;
+            rs.towers.push(tw);
+        }
+        rs.tower_fire_rate_global = 1.5;
+        rs.crit_chance = 0.2;
+        rs.crit_damage_mult = 2.0;
+        // (10 x 1 + 4 x 2) x 1.5 x 1.2
+        assert!((total_tower_dps(&rs) - 32.4).abs() < 1e-9);
+        let per_tower: f64 = rs.towers.iter().map(|tw| tower_stats(&rs, tw).dps).sum();
+        assert!((total_tower_dps(&rs) - per_tower).abs() < 1e-9);
+    }
+
+    #[test]
+    fn coverage_counts_overlapping_towers_once() {
+        let mut rs = run_from_rows(RING);
+        let n = rs.path_loop.len() as f64;
+        assert_eq!(loop_coverage(&rs), 0.0);
+        // (1, 1) reaches the corner (2, 2); (1, 3) reaches (2, 2), (2, 3) and (2, 4)
+        for y in [1, 3] {
+            let mut tw = Tower::new(1, y, TowerKind::Basic, 3.5, 1, None);
+            tw.range = 1.5;
+            rs.towers.push(tw);
+        }
+        let summed: u32 = rs
+            .towers
+            .iter()
+            .map(|tw| tower_stats(&rs, tw).coverage)
+            .sum();
+        assert_eq!(summed, 4);
+        assert!((loop_coverage(&rs) - 3.0 / n).abs() < 1e-12);
+        rs.towers[0].range = 20.0;
+        assert_eq!(loop_coverage(&rs), 1.0);
+    }
+
+    #[test]
+    fn swap_costs_the_price_less_the_sale_refund() {
+        let mut rs = veteran_run(0);
+        rs.tower_cost = 9;
+        rs.tower_cost_slow = 9;
+        rs.tower_refund_percent = 50;
+        rs.towers[0].paid = 9;
+        assert_eq!(swap_cost(&rs, &rs.towers[0], &TowerKind::Slow), 5);
+        rs.tower_refund_percent = 100;
+        assert_eq!(swap_cost(&rs, &rs.towers[0], &TowerKind::Slow), 0);
+        // Perk towers refund nothing, so the swap is full price
+        rs.towers[0].paid = 0;
+        assert_eq!(swap_cost(&rs, &rs.towers[0], &TowerKind::Slow), 9);
+    }
+
+    #[test]
+    fn replace_tower_kind_charges_and_rebuilds() {
+        let mut rs = veteran_run(600);
+        rs.tower_cost = 9;
+        rs.tower_cost_slow = 9;
+        rs.tower_refund_percent = 50;
+        rs.towers[0].paid = 9;
+        rs.currencies.gold = 4;
+        let rc = Rc::new(rs);
+        let swap = RunAction::ReplaceTowerKind {
+            x: 3,
+            y: 3,
+            kind: TowerKind::Slow,
+        };
+        let poor = rc.clone().reduce(swap.clone());
+        assert_eq!(poor.towers, rc.towers);
+
+        let mut rich = (*rc).clone();
+        rich.currencies.gold = 7;
+        let swapped = Rc::new(rich).reduce(swap);
+        assert_eq!(swapped.currencies.gold, 2);
+        assert_eq!(swapped.towers[0].kind, TowerKind::Slow);
+        assert_eq!(swapped.towers[0].damage_dealt, 600);
+        assert_eq!(swapped.towers[0].paid, 9);
+        let same = swapped.clone().reduce(RunAction::ReplaceTowerKind {
+            x: 3,
+            y: 3,
+            kind: TowerKind::Slow,
+        });
+        assert_eq!(same.currencies.gold, 2);
+    }
+
+    #[test]
+    fn veterancy_stacks_multiplicatively_after_upgrades() {
+        let mut ups = UpgradeState::default();
+        ups.levels.insert(UpgradeId::TowerDamage1.key().into(), 5);
+        let rc = Rc::new(veteran_run(2000));
+        let after = rc.reduce(RunAction::ApplyUpgrades { ups });
+        let tw = &after.towers[0];
+        // Upgrades re-derive base damage but keep experience
+        assert_eq!(tw.damage, after.tower_base_damage);
+        assert!(tw.damage > 2);
+        assert_eq!(tw.damage_dealt, 2000);
+        assert_eq!(tw.rank(), 3);
+        let expected = tw.damage as f64 * 1.05f64.powi(3);
+        assert!((tw.shot_damage() - expected).abs() < 1e-9);
+    }
+
+    // Invariants that multi-dispatch sequences used to break for a frame
+    fn invariant_violations(rs: &RunState, ups: &UpgradeState) -> Vec<&'static str> {
+        let mut v = Vec::new();
+        if !rs.towers.is_empty() && !rs.started {
+            v.push("tower exists before run started");
+        }
+        let mut applied = rs.clone();
+        apply_upgrades_to_run(&mut applied, ups);
+        if applied.tower_base_damage != rs.tower_base_damage
+            || applied.tower_base_range != rs.tower_base_range
+            || applied.life_max != rs.life_max
+            || applied.mining_speed != rs.mining_speed
+        {
+            v.push("upgrades not applied");
+        }
+        v
+    }
+
+    fn strong_ups() -> UpgradeState {
+        let mut ups = UpgradeState::default();
+        ups.levels.insert(UpgradeId::TowerDamage1.key().into(), 5);
+        ups.levels.insert(UpgradeId::HealthStart.key().into(), 2);
+        ups
+    }
+
+    #[test]
+    fn atomic_reset_matches_reset_then_apply() {
+        let ups = strong_ups();
+        let mut rs = make_run();
+        rs.grid_size = GridSize {
+            width: 10,
+            height: 10,
+        };
+        rs.currencies.research = 42;
+        let rc = Rc::new(rs);
+
+        // Old sequence: the intermediate state is a run without upgrades
+        let mid = rc.clone().reduce(RunAction::ResetRun);
+        assert!(!invariant_violations(&mid, &ups).is_empty());
+        let old = mid.reduce(RunAction::ApplyUpgrades { ups: ups.clone() });
+
+        let atomic = rc.clone().reduce(RunAction::Batch(vec![
+            RunAction::ResetRun,
+            RunAction::ApplyUpgrades { ups: ups.clone() },
+        ]));
+        assert!(invariant_violations(&atomic, &ups).is_empty());
+        assert_eq!(atomic.version, rc.version + 1);
+        assert_eq!(atomic.grid_size, old.grid_size);
+        assert_eq!(atomic.run_id, old.run_id);
+        assert_eq!(atomic.currencies.research, 42);
+        assert_eq!(atomic.tower_base_damage, old.tower_base_damage);
+        assert_eq!(atomic.life_max, old.life_max);
+        assert_eq!(atomic.started, old.started);
+        assert!(atomic.towers.is_empty() && atomic.enemies.is_empty());
+    }
+
+    #[test]
+    fn atomic_place_matches_start_place_unpause() {
+        let ups = UpgradeState::default();
+        let mut rs = run_from_rows(RING);
+        apply_upgrades_to_run(&mut rs, &ups);
+        rs.currencies.gold = 10;
+        rs.is_paused = true;
+        let rc = Rc::new(rs);
+
+        let old = rc
+            .clone()
+            .reduce(RunAction::StartRun)
+            .reduce(RunAction::PlaceTower {
+                x: 3,
+                y: 3,
+                kind: TowerKind::Basic,
+            })
+            .reduce(RunAction::TogglePause);
+
+        let atomic = rc.clone().reduce(RunAction::PlaceTowerAndStart {
+            x: 3,
+            y: 3,
+            kind: TowerKind::Basic,
+            unpause: true,
+        });
+        assert!(invariant_violations(&atomic, &ups).is_empty());
+        assert_eq!(atomic.version, rc.version + 1);
+        assert_eq!(atomic.towers, old.towers);
+        assert_eq!(atomic.currencies.gold, old.currencies.gold);
+        assert_eq!(
+            (atomic.started, atomic.is_paused),
+            (old.started, old.is_paused)
+        );
+
+        // A failed placement must not start the run
+        let blocked = rc.reduce(RunAction::PlaceTowerAndStart {
+            x: 2,
+            y: 2,
+            kind: TowerKind::Basic,
+            unpause: true,
+        });
+        assert!(blocked.towers.is_empty());
+        assert!(!blocked.started && blocked.is_paused);
+    }
+
+    #[test]
+    fn upgrade_purchases_need_the_research() {
+        let mut ups = UpgradeState::default();
+        ups.levels.insert(UpgradeId::TowerDamage1.key().into(), 1);
+        let mut rs = veteran_run(0);
+        rs.currencies.research = 4;
+        let rc = Rc::new(rs);
+        let buy = |cost| RunAction::PurchaseUpgrades {
+            cost,
+            ups: ups.clone(),
+            reset: false,
+        };
+        let short = rc.clone().reduce(buy(5));
+        assert!(Rc::ptr_eq(&short, &rc));
+        let bought = rc.clone().reduce(buy(4));
+        let batched = rc.clone().reduce(RunAction::Batch(vec![
+            RunAction::SpendResearch { amount: 4 },
+            RunAction::ApplyUpgrades { ups: ups.clone() },
+        ]));
+        assert_eq!(bought.currencies.research, 0);
+        assert_eq!(*bought, *batched);
+    }
+
+    #[test]
+    fn batch_matches_sequential_dispatch() {
+        let ups = UpgradeState::default();
+        let mut rs = run_from_rows(RING);
+        apply_upgrades_to_run(&mut rs, &ups);
+        rs.currencies.gold = 10;
+        rs.is_paused = true;
+        let rc = Rc::new(rs);
+        let actions = vec![
+            RunAction::StartRun,
+            RunAction::PlaceTower {
+                x: 3,
+                y: 3,
+                kind: TowerKind::Basic,
+            },
+            RunAction::TogglePause,
+            // The rock above the top route
+            RunAction::MiningComplete { idx: 9 + 4 },
+            RunAction::SetResearch { amount: 7 },
+        ];
+        let sequential = actions
+            .iter()
+            .cloned()
+            .fold(rc.clone(), |acc, a| acc.reduce(a));
+        let batched = rc.clone().reduce(RunAction::Batch(actions));
+        assert_eq!(batched.version, rc.version + 1);
+        assert_eq!(sequential.version, rc.version + 5);
+        let mut expected = (*sequential).clone();
+        expected.version = batched.version;
+        assert_eq!(*batched, expected);
+        assert_eq!(batched.stats.blocks_mined, 1);
+
+        // Nothing to do: the same state back, so nothing re-renders
+        let far = RunAction::CollectPickups { x: 0.5, y: 0.5 };
+        let idle = rc.clone().reduce(RunAction::Batch(vec![far]));
+        assert!(Rc::ptr_eq(&idle, &rc));
+        let empty = rc.clone().reduce(RunAction::Batch(Vec::new()));
+        assert!(Rc::ptr_eq(&empty, &rc));
+    }
+
+    // Random mining and walls on random small boards: wherever an edit skips the path search,
+    // a full search would have found the path already in place
+    #[test]
+    fn skipped_path_searches_never_miss_a_change() {
+        let (mut dead_ends, mut off_route) = (0, 0);
+        for seed in 0..60 {
+            let mut rng = SeededRng::new(seed);
+            // Open ground, rock and walls at random around RING's markers
+            let rows: Vec<String> = (0..11)
+                .map(|y| {
+                    (0..11)
+                        .map(|x| match (x, y) {
+                            (5, 5) => 'S',
+                            (4, 5) => 'X',
+                            (6, 5) => 'E',
+                            (5, 4) | (5, 6) => 'I',
+                            _ => match rng.next_f64() {
+                                r if r < 0.55 => '.',
+                                r if r < 0.85 => '#',
+                                _ => 'W',
+                            },
+                        })
+                        .collect()
+                })
+                .collect();
+            let rows: Vec<&str> = rows.iter().map(String::as_str).collect();
+            let mut rs = run_from_rows(&rows);
+            let gs = rs.grid_size;
+            for _ in 0..40 {
+                let idx = (rng.next_u64() % rs.tiles.len() as u64) as usize;
+                let pos = Position {
+                    x: idx as u32 % gs.width,
+                    y: idx as u32 / gs.width,
+                };
+                let mut edited = rs.clone();
+                match rs.tiles[idx].kind {
+                    TileKind::Rock { .. } | TileKind::Wall => {
+                        edited.tiles[idx].kind = TileKind::Empty;
+                        if opens_dead_end(&edited, idx) {
+                            dead_ends += 1;
+                            assert_eq!(compute_path(&edited), *edited.path, "seed {seed}");
+                            assert_eq!(build_loop_path(&edited), *edited.path_loop);
+                        }
+                        complete_mining(&mut rs, idx);
+                    }
+                    TileKind::Empty => {
+                        edited.tiles[idx].kind = TileKind::Wall;
+                        if off_settled_route(&edited, idx) {
+                            off_route += 1;
+                            assert_eq!(compute_path(&edited), *edited.path, "seed {seed}");
+                            assert_eq!(build_loop_path(&edited), *edited.path_loop);
+                        }
+                        lay_walls(&mut rs, &[pos]);
+                    }
+                    _ => {}
+                }
+                // Whichever way the edit went, the board is left settled
+                assert_eq!(compute_path(&rs), *rs.path, "seed {seed}");
+                assert_eq!(build_loop_path(&rs), *rs.path_loop, "seed {seed}");
+            }
+        }
+        assert!(dead_ends > 50 && off_route > 50, "{dead_ends} {off_route}");
+    }
+
+    fn walkable(rs: &RunState, x: f64, y: f64) -> bool {
+        let idx = (y.floor() as u32 * rs.grid_size.width + x.floor() as u32) as usize;
+        matches!(
+            rs.tiles[idx].kind,
+            TileKind::Empty | TileKind::Start | TileKind::Direction { .. }
+        )
+    }
+
+    #[test]
+    fn mason_rebuild_never_severs_loop() {
+        let mut rs = run_from_rows(RING);
+        let before = rs.loop_total_length;
+        // Closing the top route reroutes through the bottom
+        assert!(mason_rebuild_rock(&mut rs, 4, 2, None));
+        assert!(matches!(rs.tiles[2 * 9 + 4].kind, TileKind::Rock { .. }));
+        assert_eq!(rs.tiles[2 * 9 + 4].hardness, 2);
+        assert!(rs.loop_total_length > before);
+        assert_eq!(rs.fresh_rock.len(), 1);
+        // Closing the bottom route too would sever the loop, so it is skipped
+        assert!(!mason_rebuild_rock(&mut rs, 4, 7, None));
+        assert!(matches!(rs.tiles[7 * 9 + 4].kind, TileKind::Empty));
+        assert!(!rs.path.is_empty());
+        assert!(!rs.path_loop.is_empty());
+    }
+
+    #[test]
+    fn mason_rebuild_updates_path_and_remaps_enemies() {
+        let mut rs = veteran_run(0);
+        rs.towers.clear();
+        // Enemy on the bottom route, which survives closing the top route
+        rs.enemies[0].x = 4.5;
+        rs.enemies[0].y = 7.5;
+        let old_path = rs.path.clone();
+        assert!(mason_rebuild_rock(&mut rs, 4, 2, None));
+        assert_ne!(rs.path, old_path);
+        let e = &rs.enemies[0];
+        let d = e.loop_dist;
+        assert!(d >= 0.0 && d < rs.loop_total_length);
+        // The remapped distance samples back to where the enemy stands
+        let i = rs
+            .path_loop
+            .iter()
+            .position(|p| p.x == 4 && p.y == 7)
+            .expect("bottom route is on the new loop");
+        assert!((rs.loop_cum_lengths[i] - d).abs() < 1e-9);
+    }
+
+    #[test]
+    fn mason_rebuild_skips_protected_tiles() {
+        let mut rs = run_from_rows(RING);
+        // Start, entrance, exit, rock and indestructible tiles are never converted
+        for (x, y) in [(4, 4), (5, 4), (3, 4), (0, 0), (4, 3)] {
+            let kind = rs.tiles[y * 9 + x].kind.clone();
+            assert!(!mason_rebuild_rock(&mut rs, x as u32, y as u32, None));
+            assert_eq!(rs.tiles[y * 9 + x].kind, kind);
+        }
+        // Nor is an Empty tile with a tower on it
+        rs.towers
+            .push(Tower::new(6, 3, TowerKind::Basic, 3.5, 2, None));
+        assert!(!mason_rebuild_rock(&mut rs, 6, 3, None));
+        assert!(matches!(rs.tiles[3 * 9 + 6].kind, TileKind::Empty));
+        assert!(rs.fresh_rock.is_empty());
+    }
+
+    // Two-wide ring so rebuilt tiles can usually be routed around
+    const WIDE_RING: &[&str] = &[
+        "###########",
+        "#.........#",
+        "#.........#",
+        "#..IIIII..#",
+        "#.XSE.....#",
+        "#..IIIII..#",
+        "#.........#",
+        "#.........#",
+        "###########",
+    ];
+
+    #[test]
+    fn mason_keeps_walking_after_rebuilding_behind_itself() {
+        let mut rs = run_from_rows(WIDE_RING);
+        rs.started = true;
+        rs.enemies.push(Enemy {
+            id: EntityId::default(),
+            x: 4.5,
+            y: 4.5,
+            speed_tps: 1.5,
+            hp: 10_000,
+            max_hp: 10_000,
+            spawned_at: 0,
+            path_index: 0,
+            dir_dx: 0.0,
+            dir_dy: 0.0,
+            radius_scale: 1.0,
+            loop_dist: 0.0,
+            debuffs: Vec::new(),
+            kind: EnemyKind::Mason,
+            hit_flash_ttl: 0.0,
+            spawn_anim_ttl: 0.0,
+        });
+        let start = Rc::new(rs);
+        let mut rc = start.clone();
+        let mut prev = (4.5, 4.5);
+        let mut rebuilt = 0;
+        for _ in 0..600 {
+            let rocks_before = rc.fresh_rock.len();
+            rc = rc.reduce(RunAction::SimTick { dt: 0.05 });
+            if rc.fresh_rock.len() > rocks_before {
+                rebuilt += 1;
+            }
+            assert!(!rc.path_loop.is_empty());
+            let e = &rc.enemies[0];
+            assert!(e.loop_dist < rc.loop_total_length);
+            assert!(walkable(&rc, e.x, e.y), "mason walked into rock");
+            // Re-anchoring stays within the Mason's own tile, never a jump
+            let step = ((e.x - prev.0).powi(2) + (e.y - prev.1).powi(2)).sqrt();
+            assert!(step < 1.0, "mason jumped {step} tiles");
+            prev = (e.x, e.y);
+        }
+        assert!(rebuilt > 0, "mason never rebuilt a tile");
+        // Rebuild rolls come from the run's own stream, so the same start replays exactly
+        let replay = (0..600).fold(start, |rc, _| rc.reduce(RunAction::SimTick { dt: 0.05 }));
+        assert_eq!(replay.fresh_rock, rc.fresh_rock);
+        assert_eq!(replay.tiles, rc.tiles);
+    }
+
+    #[test]
+    fn feature_gating_by_run_number() {
+        use Feature::*;
+        let allowed = |run: u64| {
+            [Towers, Upgrades, Walls, Legend, FullSettings].map(|f| feature_allowed(Some(run), f))
+        };
+        assert_eq!(allowed(1), [false, false, false, false, false]);
+        assert_eq!(allowed(2), [true, true, false, false, false]);
+        assert_eq!(allowed(3), [true, true, true, true, true]);
+        assert_eq!(allowed(10), [true, true, true, true, true]);
+        // Skipped progression unlocks everything from the start
+        assert!(feature_allowed(None, Walls));
+        assert_eq!(Towers.locked_message(1), "Towers unlock next run");
+        assert_eq!(Walls.locked_message(1), "Walls unlock on run 3");
+    }
+
+    #[test]
+    fn reducer_rejects_locked_placements() {
+        let mut rs = run_from_rows(RING);
+        rs.currencies.gold = 10;
+        let rc = Rc::new(rs).reduce(RunAction::SetProgression { run: Some(1) });
+
+        let t = rc.clone().reduce(RunAction::PlaceTower {
+            x: 3,
+            y: 3,
+            kind: TowerKind::Basic,
+        });
+        assert!(t.towers.is_empty());
+        assert_eq!(t.currencies.gold, 10);
+        assert_eq!(t.locked_notice.as_deref(), Some("Towers unlock next run"));
+        let shown = t.reduce(RunAction::DismissNotices);
+        assert!(shown.locked_notice.is_none());
+
+        let hk = rc.clone().reduce(RunAction::PlaceTowerAndStart {
+            x: 3,
+            y: 3,
+            kind: TowerKind::Basic,
+            unpause: false,
+        });
+        assert!(hk.towers.is_empty() && !hk.started);
+
+        let w = rc.clone().reduce(RunAction::PlaceWall { x: 4, y: 2 });
+        assert!(matches!(w.tiles[2 * 9 + 4].kind, TileKind::Empty));
+        assert_eq!(w.locked_notice.as_deref(), Some("Walls unlock on run 3"));
+
+        // Run 2: towers allowed, walls still locked
+        let r2 = rc.reduce(RunAction::SetProgression { run: Some(2) });
+        let t2 = r2.clone().reduce(RunAction::PlaceTower {
+            x: 3,
+            y: 3,
+            kind: TowerKind::Basic,
+        });
+        assert_eq!(t2.towers.len(), 1);
+        let w2 = r2.clone().reduce(RunAction::PlaceWall { x: 4, y: 2 });
+        assert!(matches!(w2.tiles[2 * 9 + 4].kind, TileKind::Empty));
+        assert_eq!(w2.locked_notice.as_deref(), Some("Walls unlock next run"));
+
+        // Progression carries over resets until the app updates it
+        let reset = r2.reduce(RunAction::ResetRun);
+        assert_eq!(reset.progression_run, Some(2));
+        let w3 = reset
+            .reduce(RunAction::SetProgression { run: Some(3) })
+            .reduce(RunAction::PlaceWall { x: 4, y: 2 });
+        assert!(w3.locked_notice.is_none());
+    }
+
+    fn ups_with(levels: &[(UpgradeId, u8)]) -> UpgradeState {
+        let mut ups = UpgradeState::default();
+        for &(id, lvl) in levels {
+            ups.levels.insert(id.key().into(), lvl);
+        }
+        ups
+    }
+
+    #[test]
+    fn upgrade_queue_skips_blocked_entries_without_dropping_them() {
+        use UpgradeId::*;
+        let ups = UpgradeState::default();
+        let td = ups.next_cost(TowerDamage1).unwrap();
+        // StartingGold needs Mining Speed 2: skipped, kept, and the damage level still buys
+        let out = process_upgrade_queue(&[StartingGold, TowerDamage1], &ups, td, false);
+        assert_eq!(out.bought, vec![TowerDamage1]);
+        assert_eq!(out.remaining, vec![StartingGold]);
+        assert_eq!(out.spent, td);
+        // A purchase unblocks an entry skipped earlier in the same pass
+        let hs = ups_with(&[(TowerDamage1, 1)])
+            .next_cost(HealthStart)
+            .unwrap();
+        let out = process_upgrade_queue(&[HealthStart, TowerDamage1], &ups, td + hs, false);
+        assert_eq!(out.bought, vec![TowerDamage1, HealthStart]);
+        assert!(out.remaining.is_empty());
+        // Maxed entries fall out; the board-resizing upgrade waits out a live run
+        let maxed = ups_with(&[(TowerDamage1, 5)]);
+        let out = process_upgrade_queue(&[TowerDamage1], &maxed, 10_000, false);
+        assert!(out.bought.is_empty() && out.remaining.is_empty());
+        let out = process_upgrade_queue(&[PlayAreaSize], &ups, 10_000, true);
+        assert_eq!(out.remaining, vec![PlayAreaSize]);
+    }
+
+    #[test]
+    fn upgrade_queue_saves_for_its_head() {
+        use UpgradeId::*;
+        let ups = ups_with(&[(TowerDamage1, 1)]);
+        let fr = ups.next_cost(FireRate).unwrap();
+        let hs = ups.next_cost(HealthStart).unwrap();
+        assert!(fr > hs);
+        // The head isn't affordable: nothing behind it jumps the line
+        let out = process_upgrade_queue(&[FireRate, HealthStart], &ups, fr - 1, false);
+        assert!(out.bought.is_empty());
+        assert_eq!(out.remaining, vec![FireRate, HealthStart]);
+        let out = process_upgrade_queue(&[FireRate, HealthStart], &ups, fr + hs, false);
+        assert_eq!(out.bought, vec![FireRate, HealthStart]);
+        assert_eq!(out.spent, fr + hs);
+    }
+
+    #[test]
+    fn upgrade_queue_edits_respect_the_cap_and_max_levels() {
+        use UpgradeId::*;
+        let ups = UpgradeState::default();
+        let mut queue = Vec::new();
+        for _ in 0..6 {
+            edit_upgrade_queue(&mut queue, &ups, QueueEdit::Add(TowerDamage1));
+        }
+        // Tower Damage has five levels to buy
+        assert_eq!(queue.len(), 5);
+        for id in [
+            FireRate,
+            HealthStart,
+            CritChance,
+            MiningSpeed,
+            KillBounty,
+            Bounce,
+        ] {
+            edit_upgrade_queue(&mut queue, &ups, QueueEdit::Add(id));
+        }
+        assert_eq!(queue.len(), UPGRADE_QUEUE_CAP);
+        assert_eq!(queue[9], KillBounty);
+        edit_upgrade_queue(&mut queue, &ups, QueueEdit::MoveUp(9));
+        assert_eq!(queue[8..], [KillBounty, MiningSpeed]);
+        edit_upgrade_queue(&mut queue, &ups, QueueEdit::MoveDown(9));
+        edit_upgrade_queue(&mut queue, &ups, QueueEdit::MoveUp(0));
+        assert_eq!(queue[8..], [KillBounty, MiningSpeed]);
+        edit_upgrade_queue(&mut queue, &ups, QueueEdit::Remove(0));
+        edit_upgrade_queue(&mut queue, &ups, QueueEdit::Remove(42));
+        assert_eq!(queue.len(), UPGRADE_QUEUE_CAP - 1);
+        assert_eq!(queue[0], TowerDamage1);
+    }
+
+    #[test]
+    fn catch_up_plan_caps_and_spreads_steps() {
+        // Short blips are ignored
+        let blip = plan_catch_up(0.2);
+        assert!(blip.batches.is_empty());
+        assert_eq!(blip.discarded_secs, 0.2);
+
+        // 2s fits under the cap: 40 steps over 4 frames
+        let short = plan_catch_up(2.0);
+        assert_eq!(short.batches, vec![10, 10, 10, 10]);
+        assert!((short.simulated_secs - 2.0).abs() < 1e-9);
+        assert!(short.discarded_secs < 1e-9);
+
+        // Ten minutes away still simulates only the capped 5s
+        let long = plan_catch_up(600.0);
+        let steps: u32 = long.batches.iter().sum();
+        assert_eq!(steps as f64 * long.step_secs, CATCH_UP_MAX_SECS);
+        assert!(long.batches.iter().all(|&n| n <= CATCH_UP_STEPS_PER_FRAME));
+        assert!((long.discarded_secs - 595.0).abs() < 1e-9);
+
+        // Uneven remainder lands in the last frame
+        assert_eq!(plan_catch_up(1.2).batches, vec![10, 10, 4]);
+        assert!(plan_catch_up(f64::NAN).batches.is_empty());
+        assert!(plan_catch_up(-3.0).batches.is_empty());
+    }
+
+    #[test]
+    fn long_catch_up_plan_covers_minutes_in_bigger_batches() {
+        let blip = plan_long_catch_up(0.2);
+        assert!(blip.batches.is_empty());
+        assert_eq!(blip.discarded_secs, 0.2);
+
+        // 12s fits under the cap: 120 steps over 3 frames
+        let short = plan_long_catch_up(12.0);
+        assert_eq!(short.batches, vec![50, 50, 20]);
+        assert!((short.simulated_secs - 12.0).abs() < 1e-9);
+        assert!(short.discarded_secs < 1e-9);
+
+        // Ten minutes away simulates the first five
+        let long = plan_long_catch_up(600.0);
+        let steps: u32 = long.batches.iter().sum();
+        assert!((steps as f64 * long.step_secs - LONG_CATCH_UP_MAX_SECS).abs() < 1e-9);
+        assert!(long
+            .batches
+            .iter()
+            .all(|&n| n <= LONG_CATCH_UP_STEPS_PER_FRAME));
+        assert!((long.discarded_secs - 300.0).abs() < 1e-6);
+
+        assert_eq!(plan_long_catch_up(7.3).batches, vec![50, 23]);
+        assert!(plan_long_catch_up(f64::NAN).batches.is_empty());
+    }
+
+    // The 1000-tick run on a large board that the shared-board change was measured with. No
+    // wall-clock threshold, only that the sim actually ran: time passed, enemies came and the
+    // renderer saw every tick.
+    #[test]
+    fn a_128_grid_runs_1000_sim_ticks() {
+        let gs = GridSize {
+            width: 128,
+            height: 128,
+        };
+        let mut rs = RunState::new_with_upgrades_seeded(gs, &UpgradeState::default(), 7);
+        rs.started = true;
+        rs.life_max = 1_000_000;
+        rs.life = rs.life_max;
+        let mut rc = Rc::new(rs);
+        let version = rc.version;
+        for i in 1..=1000 {
+            rc = rc.reduce(RunAction::SimTick { dt: 0.016 });
+            // The seconds timer drives spawning; without it the board stays empty
+            if i % 60 == 0 {
+                rc = rc.reduce(RunAction::TickSecond);
+            }
+        }
+        assert!((rc.sim_time - 16.0).abs() < 1e-6, "{}", rc.sim_time);
+        assert_eq!(rc.stats.time_survived_secs, 16);
+        assert!(rc.stats.enemies_spawned > 0);
+        assert!(!rc.game_over);
+        // Every tick still bumps the version the renderer watches
+        assert_eq!(rc.version, version + 1016);
+    }
+
+    #[test]
+    fn sim_tick_shares_the_board_with_the_previous_state() {
+        let mut rs = run_from_rows(RING);
+        rs.started = true;
+        let before = Rc::new(rs);
+        let after = before.clone().reduce(RunAction::SimTick { dt: 0.1 });
+        assert!(Shared::ptr_eq(&before.tiles, &after.tiles));
+        assert!(Shared::ptr_eq(&before.path_loop, &after.path_loop));
+        // A write copies the board for the new state only
+        let mut edited = (*after).clone();
+        edited.tiles[0].hardness += 1;
+        assert!(!Shared::ptr_eq(&after.tiles, &edited.tiles));
+        assert_ne!(after.tiles, edited.tiles);
+    }
+
+    fn catch_up_run(life: u32) -> Rc<RunState> {
+        let mut rs = run_from_rows(RING);
+        rs.started = true;
+        rs.life_max = life;
+        rs.life = life;
+        Rc::new(rs)
+    }
+
+    #[test]
+    fn catch_up_matches_the_live_tick_cadence() {
+        let start = catch_up_run(1_000);
+        // What the page does while visible: 0.1s ticks, with the seconds timer alongside
+        let mut live = start.clone();
+        for i in 1..=600 {
+            live = live.reduce(RunAction::SimTick { dt: 0.1 });
+            if i % 10 == 0 {
+                live = live.reduce(RunAction::TickSecond);
+            }
+        }
+        let caught = start.reduce(RunAction::CatchUp {
+            elapsed_secs: 60.0,
+            mode: CatchUpMode::Long,
+        });
+        // Spawn archetypes are random, so the counts agree only roughly
+        let (got, want) = (caught.stats.loops_completed, live.stats.loops_completed);
+        assert!(want > 0);
+        assert!(got.abs_diff(want) <= want / 10 + 1, "{got} vs {want} loops");
+        assert_eq!(caught.stats.time_survived_secs, 60);
+        assert!(caught.damage_numbers.len() <= CATCH_UP_MAX_DAMAGE_NUMBERS);
+    }
+
+    #[test]
+    fn capped_catch_up_replays_only_the_first_seconds() {
+        let caught = catch_up_run(1_000).reduce(RunAction::CatchUp {
+            elapsed_secs: 60.0,
+            mode: CatchUpMode::Capped,
+        });
+        assert_eq!(caught.stats.time_survived_secs, CATCH_UP_MAX_SECS as u64);
+        assert!((caught.sim_time - CATCH_UP_MAX_SECS).abs() < 1e-6);
+    }
+
+    #[test]
+    fn catch_up_stops_at_game_over_and_skips_idle_runs() {
+        let catch_up = |elapsed_secs| RunAction::CatchUp {
+            elapsed_secs,
+            mode: CatchUpMode::Long,
+        };
+        let doomed = catch_up_run(1).reduce(catch_up(1_000.0));
+        assert!(doomed.game_over);
+        assert!(doomed.stats.time_survived_secs < LONG_CATCH_UP_MAX_SECS as u64);
+        let after = doomed.clone().reduce(catch_up(30.0));
+        assert!(Rc::ptr_eq(&doomed, &after));
+
+        let mut paused = (*catch_up_run(10)).clone();
+        paused.is_paused = true;
+        let paused = Rc::new(paused);
+        let after = paused.clone().reduce(catch_up(30.0));
+        assert!(Rc::ptr_eq(&paused, &after));
+    }
+
+    #[test]
+    fn digest_summarizes_changes_between_snapshots() {
+        let before = make_run();
+        let mut after = before.clone();
+        after.stats.enemies_spawned += 12;
+        after.stats.enemies_killed += 9;
+        after.life = before.life - 1;
+        after.currencies.gold += 30;
+        let d = RunDigest::between(&before, &after);
+        assert_eq!(d.gold_gained, 30);
+        assert_eq!(d.summary(), "12 enemies spawned, 9 killed, life -1");
+
+        // Regen or a reset never reports negative losses
+        let quiet = RunDigest::between(&after, &before);
+        assert_eq!(quiet.summary(), "0 enemies spawned, 0 killed");
+    }
+
+    // Two 8-step routes, over the top and underneath, and a pocket off the top-left corner
+    const TWIN: &[&str] = &[
+        "##.######",
+        "##.....##",
+        "##.#I#.##",
+        "##.XSE.##",
+        "##.#I#.##",
+        "##.....##",
+        "#########",
+    ];
+
+    // Route taken when `blocked` is walled off
+    fn route_avoiding(rs: &RunState, blocked: (u32, u32)) -> Vec<Position> {
+        let mut alt = rs.clone();
+        let i = (blocked.1 * alt.grid_size.width + blocked.0) as usize;
+        alt.tiles[i].kind = TileKind::Wall;
+        alt.path.clear();
+        compute_path(&alt)
+    }
+
+    #[test]
+    fn path_ties_keep_previous_route() {
+        let rs = run_from_rows(TWIN);
+        let top = route_avoiding(&rs, (4, 5));
+        let bottom = route_avoiding(&rs, (4, 1));
+        assert_eq!(top.len(), bottom.len());
+        assert_ne!(top, bottom);
+        for chosen in [top, bottom] {
+            let mut start = rs.clone();
+            start.path = chosen.clone().into();
+            // Opening the pocket into a square adds no shorter route, but it does rerun the
+            // search, which has both routes to pick from
+            assert!(!opens_dead_end(&start, 3));
+            let after = Rc::new(start).reduce(RunAction::MiningComplete { idx: 3 });
+            assert!(matches!(after.tiles[3].kind, TileKind::Empty));
+            assert_eq!(*after.path, chosen);
+        }
+    }
+
+    #[test]
+    fn shorter_route_beats_previous_path_bias() {
+        let rs = run_from_rows(RING);
+        let top = rs.path.clone();
+        let bottom = route_avoiding(&rs, (4, 2));
+        assert!(bottom.len() > top.len());
+        let mut start = rs.clone();
+        start.path = bottom.into();
+        // Squares off the bottom-left corner: no shortcut, but not a dead end either
+        let idx = 6 * 9 + 3;
+        assert!(!opens_dead_end(&start, idx));
+        let after = Rc::new(start).reduce(RunAction::MiningComplete { idx });
+        assert_eq!(after.path, top);
+    }
+
+    #[test]
+    fn dead_end_mine_leaves_the_path_alone() {
+        let rs = Rc::new(run_from_rows(RING));
+        // Only touches the top corridor
+        let idx = 9 + 2;
+        assert!(opens_dead_end(&rs, idx));
+        let after = rs.clone().reduce(RunAction::MiningComplete { idx });
+        assert!(matches!(after.tiles[idx].kind, TileKind::Empty));
+        assert!(Shared::ptr_eq(&rs.path, &after.path));
+        assert!(Shared::ptr_eq(&rs.path_loop, &after.path_loop));
+        // The search it skipped would have found the same route
+        assert_eq!(*after.path, compute_path(&after));
+    }
+
+    #[test]
+    fn projection_picks_the_nearest_segment() {
+        // 2x2 square of tiles (0,0) (2,0) (2,2) (0,2): sides of 2, closing side included
+        let nodes: Vec<Position> = [(0, 0), (2, 0), (2, 2), (0, 2)]
+            .iter()
+            .map(|&(x, y)| Position { x, y })
+            .collect();
+        let cum = [0.0, 2.0, 4.0, 6.0];
+        let at = |open: bool, x: f64, y: f64| {
+            let total = if open { 6.0 } else { 8.0 };
+            project_onto_loop(&nodes, &cum, total, open, x, y)
+        };
+        // Halfway along the top, from just off it
+        assert!((at(false, 1.5, 0.2) - 1.0).abs() < 1e-9);
+        // Beyond a corner clamps to the corner
+        assert!((at(false, 5.0, 5.0) - 4.0).abs() < 1e-9);
+        // Beside the closing side: a quarter of the way back up it, or the End of an open route
+        assert!((at(false, 0.3, 2.0) - 6.5).abs() < 1e-9);
+        assert!((at(true, 0.3, 2.0) - 6.0).abs() < 1e-9);
+        // On Start itself wraps to 0, and nothing to project onto gives 0
+        assert_eq!(at(false, 0.5, 0.5), 0.0);
+        let lone = project_onto_loop(&nodes[..1], &cum[..1], 0.0, false, 3.0, 3.0);
+        assert_eq!(lone, 0.0);
+    }
+
+    // An enemy standing on `node`, with the given (possibly stale) distance
+    fn standing_at(node: Position, loop_dist: f64) -> Enemy {
+        let mut e = walker(loop_dist);
+        e.x = node.x as f64 + 0.5;
+        e.y = node.y as f64 + 0.5;
+        e
+    }
+
+    fn dist_of(rs: &RunState, node: Position) -> f64 {
+        let i = rs.path_loop.iter().position(|p| *p == node).unwrap();
+        rs.loop_cum_lengths[i]
+    }
+
+    #[test]
+    fn path_changes_keep_enemies_where_they_stand() {
+        let mut rs = run_from_rows(RING);
+        let detour = wall_placement_effect(&rs, 4, 2).unwrap().new_loop;
+        // A tile both routes share, past the detour so it sits further along the new loop
+        let shared = *rs
+            .path_loop
+            .iter()
+            .rev()
+            .find(|&&p| detour.contains(&p))
+            .unwrap();
+        let old_d = dist_of(&rs, shared);
+        rs.enemies = vec![standing_at(shared, old_d), standing_at(shared, 999.0)];
+        let walled = Rc::new(rs).reduce(RunAction::PlaceWall { x: 4, y: 2 });
+        let new_d = dist_of(&walled, shared);
+        assert!((new_d - old_d).abs() > 0.5);
+        for e in &walled.enemies {
+            assert!((e.loop_dist - new_d).abs() < 1e-9, "{}", e.loop_dist);
+        }
+        // Mining the wall back out returns to the short route, and the enemies follow
+        let mined = walled.reduce(RunAction::MiningComplete { idx: 2 * 9 + 4 });
+        let back = dist_of(&mined, shared);
+        assert!((back - old_d).abs() < 1e-9);
+        for e in &mined.enemies {
+            assert!((e.loop_dist - back).abs() < 1e-9, "{}", e.loop_dist);
+        }
+    }
+
+    #[test]
+    fn tile_bitset_set_get_and_bounds() {
+        let mut b = TileBitset::new(10, 7);
+        assert_eq!(b.words.len(), 2);
+        b.set(0, 0, true);
+        b.set(9, 6, true);
+        b.set(3, 4, true);
+        b.set(3, 4, false);
+        b.set(10, 0, true);
+        assert!(b.get(0, 0) && b.get(9, 6));
+        assert!(!b.get(3, 4) && !b.get(10, 0) && !b.get(0, 7));
+        assert_eq!(b.count(), 2);
+        assert!(b.contains_point(9.99, 6.01));
+        assert!(!b.contains_point(-0.5, 0.5));
+    }
+
+    #[test]
+    fn tile_bitset_round_trips_through_json() {
+        let mut b = TileBitset::new(12, 12);
+        b.set(5, 11, true);
+        b.set(11, 0, true);
+        let json = serde_json::to_string(&b).unwrap();
+        let back: TileBitset = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, b);
+    }
+
+    #[test]
+    fn towers_skip_enemies_on_excluded_tiles() {
+        let rs = veteran_run(0);
+        let tw = rs.towers[0].clone();
+        let mut e = rs.enemies[0].clone();
+        let mut zones = TileBitset::new(9, 9);
+        zones.set(4, 4, true);
+        // Excluded exactly while floor(x), floor(y) is the flagged tile
+        for (x, y, excluded) in [
+            (4.5, 4.5, true),
+            (4.0, 4.999, true),
+            (3.999, 4.5, false),
+            (4.5, 5.0, false),
+        ] {
+            e.x = x;
+            e.y = y;
+            assert_eq!(!tw.may_target(&e, &zones), excluded, "at ({x}, {y})");
+        }
+
+        let fire = |rc: Rc<RunState>| {
+            let mut rc = rc;
+            for _ in 0..20 {
+                rc = rc.reduce(RunAction::SimTick { dt: 0.05 });
+            }
+            rc
+        };
+        let zoned = Rc::new(rs).reduce(RunAction::SetNoTarget {
+            x: 4,
+            y: 4,
+            on: true,
+        });
+        assert!(zoned.no_target.get(4, 4));
+        let held = fire(zoned.clone());
+        assert!(held.projectiles.is_empty() && held.towers[0].damage_dealt == 0);
+
+        // Per-tower opt-out fires anyway
+        let opted = fire(zoned.reduce(RunAction::ToggleTowerIgnoreExclusion { x: 3, y: 3 }));
+        assert!(opted.towers[0].damage_dealt > 0);
+    }
+
+    #[test]
+    fn exclusion_zones_survive_path_changes_and_warn_when_heavy() {
+        let mut rc = Rc::new(run_from_rows(RING));
+        let loop_tiles = rc.path_loop.clone();
+        assert!(exclusion_warning(&rc).is_none());
+        for p in loop_tiles.iter().take(loop_tiles.len() * 4 / 5) {
+            rc = rc.reduce(RunAction::SetNoTarget {
+                x: p.x,
+                y: p.y,
+                on: true,
+            });
+        }
+        assert!(excluded_path_fraction(&rc) <= EXCLUSION_WARN_FRACTION);
+        assert!(exclusion_warning(&rc).is_none());
+        let last = loop_tiles[loop_tiles.len() - 1];
+        rc = rc.reduce(RunAction::SetNoTarget {
+            x: last.x,
+            y: last.y,
+            on: true,
+        });
+        assert!(exclusion_warning(&rc).is_some());
+
+        // Walling the top route reroutes underneath but leaves the painted tiles alone
+        let painted = rc.no_target.clone();
+        let walled = rc.clone().reduce(RunAction::PlaceWall { x: 4, y: 2 });
+        assert_ne!(walled.path, rc.path);
+        assert_eq!(walled.no_target, painted);
+    }
+
+    fn tick_seconds(mut rc: Rc<RunState>, n: u32) -> Rc<RunState> {
+        for _ in 0..n {
+            rc = rc.reduce(RunAction::TickSecond);
+        }
+        rc
+    }
+
+    #[test]
+    fn clean_minutes_build_a_capped_streak() {
+        let mut rs = run_from_rows(RING);
+        rs.started = true;
+        let rc = tick_seconds(Rc::new(rs), 59);
+        assert_eq!(rc.clean_streak, 0);
+        let rc = tick_seconds(rc, 1);
+        assert_eq!(rc.clean_streak, 1);
+        assert!(rc.streak_notice.as_deref().unwrap().contains("x1.1"));
+        let rc = tick_seconds(rc, 120);
+        assert_eq!(rc.clean_streak, 3);
+        assert!((rc.streak_research_mult() - 1.3).abs() < 1e-9);
+
+        // Capped at 2x no matter how long the run stays clean
+        let rc = tick_seconds(rc, 60 * 20);
+        assert_eq!(rc.clean_streak, STREAK_MAX);
+        assert!((rc.streak_research_mult() - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn leak_resets_streak_mid_minute() {
+        let mut rs = veteran_run(0);
+        rs.clean_streak = 2;
+        let rc = tick_seconds(Rc::new(rs), 30);
+        assert_eq!((rc.clean_streak, rc.clean_streak_secs), (2, 30));
+
+        let mut rs = (*rc).clone();
+        let life = rs.life;
+        rs.enemies[0].speed_tps = 1.0;
+        rs.enemies[0].loop_dist = short_of_lap(&rs, 0.01);
+        let leaked = Rc::new(rs).reduce(RunAction::SimTick { dt: 0.05 });
+        assert_eq!(leaked.life, life - 1);
+        assert_eq!((leaked.clean_streak, leaked.clean_streak_secs), (0, 0));
+        assert_eq!(
+            leaked.streak_notice.as_deref(),
+            Some("Streak lost: an enemy leaked")
+        );
+        let shown = leaked.reduce(RunAction::DismissNotices);
+        assert!(shown.streak_notice.is_none());
+        // The next full minute starts from scratch
+        assert_eq!(tick_seconds(shown, 60).clean_streak, 1);
+    }
+
+    #[test]
+    fn streak_multiplies_kill_research_only() {
+        let mut rs = veteran_run(0);
+        rs.clean_streak = STREAK_MAX;
+        rs.enemies[0].hp = 1;
+        let mut rc = Rc::new(rs);
+        for _ in 0..40 {
+            rc = rc.reduce(RunAction::SimTick { dt: 0.05 });
+        }
+        assert!(rc.stats.enemies_killed > 0);
+        assert_eq!(rc.currencies.research, rc.stats.enemies_killed * 2);
+
+        // Restoring saved research is never multiplied
+        let loaded = rc.reduce(RunAction::SetResearch { amount: 50 });
+        assert_eq!(loaded.currencies.research, 50);
+    }
+
+    #[test]
+    fn fractional_streak_research_carries_over() {
+        let mut rs = make_run();
+        rs.clean_streak = 5;
+        for _ in 0..4 {
+            add_research(&mut rs, 1.0);
+        }
+        // 4 kills at 1.5x
+        assert_eq!(rs.currencies.research, 6);
+        assert!(rs.research_carry.abs() < 1e-9);
+    }
+
+    #[test]
+    fn tile_labels_by_kind() {
+        let rock = |has_gold| Tile {
+            kind: TileKind::Rock {
+                has_gold,
+                boost: None,
+            },
+            hardness: 3,
+            magma: false,
+        };
+        assert_eq!(
+            tile_label(&rock(false), None, None, 1.0, 1.0).as_deref(),
+            Some("3")
+        );
+        assert_eq!(
+            tile_label(&rock(true), None, None, 2.5, 1.0).as_deref(),
+            Some("3g")
+        );
+        let wall = Tile {
+            kind: TileKind::Wall,
+            hardness: 1,
+            magma: false,
+        };
+        assert_eq!(
+            tile_label(&wall, None, None, 1.0, 1.0).as_deref(),
+            Some("1")
+        );
+
+        // Towers show per-shot damage including veterancy, over whatever tile they sit on
+        let mut tw = Tower::new(0, 0, TowerKind::Basic, 3.0, 20, None);
+        assert_eq!(
+            tile_label(&rock(true), Some(&tw), None, 1.0, 1.0).as_deref(),
+            Some("20")
+        );
+        tw.damage_dealt = VETERANCY_THRESHOLDS[1];
+        assert_eq!(
+            tile_label(&wall, Some(&tw), None, 1.0, 1.0).as_deref(),
+            Some("22")
+        );
+
+        // Path tiles show loop distance only when on the loop
+        let empty = Tile {
+            kind: TileKind::Empty,
+            hardness: 1,
+            magma: false,
+        };
+        assert_eq!(
+            tile_label(&empty, None, Some(7.4), 1.0, 1.0).as_deref(),
+            Some("7")
+        );
+        assert!(tile_label(&empty, None, None, 1.0, 1.0).is_none());
+        let start = Tile {
+            kind: TileKind::Start,
+            hardness: 1,
+            magma: false,
+        };
+        assert!(tile_label(&start, None, Some(0.0), 1.0, 1.0).is_none());
+    }
+
+    fn scarecrow_at(rs: &RunState, x: u32, y: u32) -> Rc<RunState> {
+        Rc::new(rs.clone()).reduce(RunAction::SpendResearchOnStructure {
+            x,
+            y,
+            kind: StructureKind::Scarecrow,
+        })
+    }
+
+    #[test]
+    fn difficulty_curve_runs_on_time() {
+        let curve = DifficultyCurve::default();
+        let hp: Vec<f64> = [0.0, 60.0, 300.0, 600.0]
+            .iter()
+            .map(|&t| (curve.hp_at(t) * 100.0).round() / 100.0)
+            .collect();
+        assert_eq!(hp, [5.0, 5.97, 12.14, 29.46]);
+        assert_eq!(curve.speed_at(0.0), ENEMY_BASE_SPEED);
+        assert!((curve.speed_at(600.0) - 2.1).abs() < 1e-9);
+        assert_eq!(curve.speed_at(6000.0), curve.max_speed);
+        // Gaps keep closing without ever reaching the floor; the cap grows a little each minute
+        let gaps: Vec<f64> = [0.0, 90.0, 600.0, 6000.0]
+            .iter()
+            .map(|&t| curve.interval_at(t))
+            .collect();
+        assert_eq!(gaps[..2], [2.0, 1.1]);
+        assert!(gaps.windows(2).all(|w| w[1] < w[0]));
+        assert!(gaps[3] > curve.min_interval);
+        assert_eq!(curve.enemy_cap_at(0.0), 40);
+        assert_eq!(curve.enemy_cap_at(600.0), 80);
+    }
+
+    #[test]
+    fn held_loops_no_longer_hold_enemies_weak() {
+        let mut rs = run_from_rows(RING);
+        assert_eq!(spawn_hp(&rs), 5);
+        rs.stats.loops_completed = 50;
+        assert_eq!(spawn_hp(&rs), 5);
+        rs.stats.time_survived_secs = 300;
+        assert_eq!(spawn_hp(&rs), 12);
+        rs.player_power_level = 10.0;
+        assert_eq!(spawn_hp(&rs), 16);
+
+        // A full field makes the spawner wait
+        rs.started = true;
+        rs.last_enemy_spawn_time_secs = 0.0;
+        for i in 0..rs.difficulty.enemy_cap_at(300.0) {
+            rs.enemies.push(walker(i as f64 * 0.01));
+        }
+        rs.stats.enemies_spawned = rs.enemies.len() as u64;
+        let spawned = rs.stats.enemies_spawned;
+        let rc = tick(Rc::new(rs), 1);
+        assert_eq!(rc.stats.enemies_spawned, spawned);
+    }
+
+    #[test]
+    fn scarecrow_stretches_spawn_interval() {
+        let mut rs = run_from_rows(RING);
+        rs.stats.time_survived_secs = 40;
+        let base = spawn_interval(&rs);
+        assert!((base - rs.difficulty.interval_at(40.0)).abs() < 1e-9);
+        rs.currencies.research = SCARECROW_RESEARCH_COST;
+        let with = scarecrow_at(&rs, 1, 2);
+        assert_eq!(with.structures.len(), 1);
+        assert!((spawn_interval(&with) - base * SCARECROW_SPAWN_INTERVAL_MULT).abs() < 1e-9);
+
+        // Late game too, on top of the tightest gaps
+        let mut late = (*with).clone();
+        late.stats.time_survived_secs = 1000;
+        let tight = late.difficulty.interval_at(1000.0);
+        assert!((spawn_interval(&late) - tight * SCARECROW_SPAWN_INTERVAL_MULT).abs() < 1e-9);
+    }
+
+    #[test]
+    fn scarecrow_wears_once_per_spawn_and_crumbles() {
+        let mut rs = run_from_rows(RING);
+        rs.started = true;
+        rs.currencies.research = SCARECROW_RESEARCH_COST;
+        let mut rc = scarecrow_at(&rs, 1, 2);
+        // Jumps the clock to `at` with exactly one spawn due
+        let spawn = |rc: Rc<RunState>, at: u64| {
+            let mut rs = (*rc).clone();
+            rs.stats.time_survived_secs = at;
+            rs.last_enemy_spawn_time_secs = (at as f64 - spawn_interval(&rs)).floor();
+            Rc::new(rs).reduce(RunAction::SimTick { dt: 0.01 })
+        };
+        rc = spawn(rc, 10);
+        assert_eq!(rc.stats.enemies_spawned, 1);
+        assert_eq!(rc.structures[0].hp, SCARECROW_DURABILITY - 1);
+        // No spawn due yet: no wear
+        rc = rc.reduce(RunAction::SimTick { dt: 0.01 });
+        assert_eq!(rc.stats.enemies_spawned, 1);
+        assert_eq!(rc.structures[0].hp, SCARECROW_DURABILITY - 1);
+        rc = spawn(rc, 20);
+        assert_eq!(rc.structures[0].hp, SCARECROW_DURABILITY - 2);
+
+        let mut worn = (*rc).clone();
+        worn.structures[0].hp = 1;
+        let gone = spawn(Rc::new(worn), 30);
+        assert!(gone.structures.is_empty());
+        assert_eq!(gone.stats.enemies_spawned, 3);
+    }
+
+    #[test]
+    fn scarecrow_placement_is_validated() {
+        let mut rs = run_from_rows(RING);
+        rs.currencies.research = SCARECROW_RESEARCH_COST * 2;
+        // Not rock, and rock nowhere near the path
+        assert!(scarecrow_at(&rs, 2, 2).structures.is_empty());
+        assert!(scarecrow_at(&rs, 0, 0).structures.is_empty());
+        assert_eq!(
+            structure_placement_error(&rs, 0, 0, StructureKind::Scarecrow).as_deref(),
+            Some("Out of reach")
+        );
+
+        let one = scarecrow_at(&rs, 1, 2);
+        assert_eq!(one.structures.len(), 1);
+        let two = scarecrow_at(&one, 1, 3);
+        assert_eq!(two.structures.len(), 1);
+        assert_eq!(two.currencies.research, SCARECROW_RESEARCH_COST);
+
+        // Towers and structures don't share a tile
+        let mut gold = (*one).clone();
+        gold.currencies.gold = 100;
+        let tw = Rc::new(gold).reduce(RunAction::PlaceTower {
+            x: 1,
+            y: 2,
+            kind: TowerKind::Basic,
+        });
+        assert!(tw.towers.is_empty());
+
+        // Mining the rock takes the scarecrow with it
+        let idx = (2 * one.grid_size.width + 1) as usize;
+        let mined = one.reduce(RunAction::MiningComplete { idx });
+        assert!(mined.structures.is_empty());
+    }
+
+    #[test]
+    fn scarecrow_costs_research() {
+        let mut rs = run_from_rows(RING);
+        rs.currencies.research = SCARECROW_RESEARCH_COST - 1;
+        let poor = scarecrow_at(&rs, 1, 2);
+        assert!(poor.structures.is_empty());
+        assert_eq!(poor.currencies.research, SCARECROW_RESEARCH_COST - 1);
+
+        rs.currencies.research = SCARECROW_RESEARCH_COST + 3;
+        let bought = scarecrow_at(&rs, 1, 2);
+        assert_eq!(bought.currencies.research, 3);
+        assert_eq!(
+            bought.structures[0],
+            Structure {
+                x: 1,
+                y: 2,
+                kind: StructureKind::Scarecrow,
+                hp: SCARECROW_DURABILITY,
+                max_hp: SCARECROW_DURABILITY,
+            }
+        );
+    }
+
+    fn stray(x: f64, y: f64, vx: f64, remaining: f64) -> Projectile {
+        Projectile {
+            id: EntityId::default(),
+            x,
+            y,
+            vx,
+            vy: 0.0,
+            remaining,
+            damage: 5,
+            splash_radius: 0.0,
+            apply_debuff: None,
+            source: None,
+            age: 0.0,
+            bounces_remaining: 0,
+            slow: None,
+        }
+    }
+
+    // Two still enemies a tile apart, a 10-damage shot landing on the first
+    fn splash_shot(splash_level: u8) -> RunState {
+        let mut ups = UpgradeState::default();
+        ups.levels
+            .insert(UpgradeId::SplashRadius.key().into(), splash_level);
+        let mut rs = veteran_run(0);
+        apply_upgrades_to_run(&mut rs, &ups);
+        let mut second = rs.enemies[0].clone();
+        second.x += 1.0;
+        rs.enemies.push(second);
+        rs.stats.enemies_spawned = 2;
+        let mut shot = stray(4.5, 4.5, 0.0, 0.01);
+        shot.damage = 10;
+        shot.splash_radius = rs.projectile_splash_radius;
+        rs.projectiles.push(shot);
+        advance_projectiles(&mut rs, 0.05);
+        rs
+    }
+
+    // Same two enemies, an unsplashed 10-damage shot, run until it's spent
+    fn bounce_shot(bounce_level: u8) -> RunState {
+        let mut ups = UpgradeState::default();
+        ups.levels
+            .insert(UpgradeId::Bounce.key().into(), bounce_level);
+        let mut rs = veteran_run(0);
+        apply_upgrades_to_run(&mut rs, &ups);
+        let mut second = rs.enemies[0].clone();
+        second.x += 1.0;
+        rs.enemies.push(second);
+        rs.stats.enemies_spawned = 2;
+        // Slow enough that no step overshoots the 0.3-tile hit radius
+        let mut shot = stray(4.4, 4.5, 2.0, 0.05);
+        shot.damage = 10;
+        shot.bounces_remaining = rs.projectile_bounces;
+        rs.projectiles.push(shot);
+        for _ in 0..60 {
+            advance_projectiles(&mut rs, 0.05);
+        }
+        assert!(rs.projectiles.is_empty());
+        rs
+    }
+
+    fn damage_taken(rs: &RunState) -> Vec<u32> {
+        rs.enemies.iter().map(|e| e.max_hp - e.hp).collect()
+    }
+
+    #[test]
+    fn archetype_multipliers_apply_at_spawn() {
+        for kind in [
+            EnemyKind::Normal,
+            EnemyKind::Fast,
+            EnemyKind::Tank,
+            EnemyKind::Mason,
+        ] {
+            let mut rs = run_from_rows(RING);
+            let s = kind.scale();
+            let hp = (100.0 * s.hp).round() as u32;
+            spawn_archetype(&mut rs, kind, hp, 2.0, 1.0);
+            assert_eq!(rs.enemies.len(), 1, "{kind:?}");
+            let e = &rs.enemies[0];
+            assert_eq!((e.kind, e.hp, e.max_hp), (kind, hp, hp));
+            assert!((e.speed_tps - 2.0 * s.speed).abs() < 1e-9, "{kind:?}");
+            assert!((e.radius_scale - s.size).abs() < 1e-9, "{kind:?}");
+        }
+        let (fast, tank) = (EnemyKind::Fast.scale(), EnemyKind::Tank.scale());
+        assert!(fast.speed > 1.0 && fast.hp < 1.0);
+        assert!(tank.speed < 1.0 && tank.hp > 1.0);
+    }
+
+    #[test]
+    fn a_swarm_spawns_several_enemies_in_one_event() {
+        let mut rs = run_from_rows(RING);
+        spawn_archetype(&mut rs, EnemyKind::Swarm, 7, 2.0, 1.0);
+        assert_eq!(rs.enemies.len(), SWARM_SIZE);
+        assert_eq!(rs.stats.enemies_spawned, SWARM_SIZE as u64);
+        assert!(rs
+            .enemies
+            .iter()
+            .all(|e| e.kind == EnemyKind::Swarm && e.hp == 7 && e.radius_scale < 1.0));
+        let ids: HashSet<_> = rs.enemies.iter().map(|e| e.id).collect();
+        assert_eq!(ids.len(), SWARM_SIZE);
+    }
+
+    #[test]
+    fn later_archetypes_only_appear_as_the_run_goes_on() {
+        let kinds_at = |secs| -> HashSet<EnemyKind> {
+            (0..100)
+                .map(|i| pick_archetype(secs, i as f64 / 100.0))
+                .collect()
+        };
+        assert_eq!(kinds_at(0), HashSet::from([EnemyKind::Normal]));
+        let early = kinds_at(60);
+        assert!(early.contains(&EnemyKind::Fast));
+        assert!(!early.contains(&EnemyKind::Tank) && !early.contains(&EnemyKind::Swarm));
+        let late = kinds_at(600);
+        assert!(late.contains(&EnemyKind::Tank) && late.contains(&EnemyKind::Swarm));
+        assert!(!late.contains(&EnemyKind::Mason));
+    }
+
+    #[test]
+    fn kill_research_follows_the_kind() {
+        let research_for = |kinds: &[EnemyKind]| {
+            let mut rs = veteran_run(0);
+            rs.towers.clear();
+            rs.enemies[0].hp = 1;
+            let template = rs.enemies.remove(0);
+            // A tile apart so each shot finds its own target
+            for (i, &kind) in kinds.iter().enumerate() {
+                let x = template.x + i as f64;
+                rs.enemies.push(Enemy {
+                    kind,
+                    x,
+                    ..template.clone()
+                });
+                rs.projectiles.push(stray(x, template.y, 0.0, 0.01));
+            }
+            rs.stats.enemies_spawned = kinds.len() as u64;
+            let before = rs.currencies.research;
+            let rc = Rc::new(rs).reduce(RunAction::SimTick { dt: 0.05 });
+            assert!(rc.enemies.is_empty());
+            rc.currencies.research - before
+        };
+        assert_eq!(research_for(&[EnemyKind::Tank]), 3);
+        assert_eq!(research_for(&[EnemyKind::Fast]), 1);
+        assert_eq!(research_for(&[EnemyKind::Swarm]), 0);
+        assert_eq!(research_for(&[EnemyKind::Swarm; SWARM_SIZE]), 1);
+    }
+
+    #[test]
+    fn enemies_enter_at_the_entrance_and_lap_back_to_it() {
+        let mut rs = run_from_rows(RING);
+        rs.started = true;
+        spawn_enemy(&mut rs, EnemyKind::Normal, 50, 1.0, 1.0);
+        let e = &rs.enemies[0];
+        // The Entrance is (5, 4), a step on from Start
+        assert_eq!((e.x, e.y), (5.5, 4.5));
+        assert_eq!((e.loop_dist, rs.loop_entry_dist), (1.0, 1.0));
+        assert_eq!(e.spawn_anim_ttl, SPAWN_ANIM_SECS);
+        // One lap at a tile a second costs exactly one life, on the tick it reaches the Entrance
+        let life = rs.life;
+        let ticks = (rs.loop_total_length / 0.25).round() as usize;
+        let rc = tick(Rc::new(rs), ticks - 1);
+        assert_eq!((rc.life, rc.stats.loops_completed), (life, 0));
+        assert_eq!(rc.enemies[0].spawn_anim_ttl, 0.0);
+        let rc = tick(rc, 1);
+        assert_eq!((rc.life, rc.stats.loops_completed), (life - 1, 1));
+        let e = &rc.enemies[0];
+        assert!((e.x - 5.5).abs() < 1e-9 && (e.y - 4.5).abs() < 1e-9);
+    }
+
+    // Loop distance `left` tiles before a lap ends: short of the Entrance, or of an open route's End
+    fn short_of_lap(rs: &RunState, left: f64) -> f64 {
+        let lap = rs.lap_frame();
+        if lap.open {
+            lap.total - left
+        } else {
+            (lap.entry - left).rem_euclid(lap.total)
+        }
+    }
+
+    // A one-hp enemy `left` tiles short of the lap's end, shot dead in one tick
+    fn clutch_kill(seed: u64, lives_missing: u32, left: f64) -> Rc<RunState> {
+        let mut rs = veteran_run(0);
+        rs.towers.clear();
+        rs.enemies[0].hp = 1;
+        rs.enemies[0].loop_dist = short_of_lap(&rs, left);
+        let (x, y) = (rs.enemies[0].x, rs.enemies[0].y);
+        rs.projectiles.push(stray(x, y, 0.0, 0.01));
+        rs.clutch_rng = seed;
+        rs.life = rs.life_max - lives_missing;
+        let rc = Rc::new(rs).reduce(RunAction::SimTick { dt: 0.05 });
+        assert!(rc.enemies.is_empty());
+        rc
+    }
+
+    // First seeds whose opening roll wins / loses at the early-run odds
+    fn clutch_seeds() -> (u64, u64) {
+        let chance = clutch_chance(0.0);
+        let roll = |s: &u64| SeededRng::new(*s).next_f64();
+        (
+            (0..).find(|s| roll(s) < chance).unwrap(),
+            (0..).find(|s| roll(s) >= chance).unwrap(),
+        )
+    }
+
+    #[test]
+    fn clutch_kills_refund_a_life_on_a_lucky_roll() {
+        assert_eq!(clutch_chance(0.0), 0.5);
+        assert_eq!(clutch_chance(5.0), 0.25);
+        assert_eq!(clutch_chance(20.0), CLUTCH_CHANCE_HARD);
+        let (lucky, unlucky) = clutch_seeds();
+        let won = clutch_kill(lucky, 2, 0.5);
+        assert_eq!(won.life, won.life_max - 1);
+        assert_eq!(won.stats.clutch_saves, 1);
+        assert!(won.damage_numbers.iter().any(|d| d.is_clutch));
+        assert_ne!(won.clutch_rng, lucky);
+        let lost = clutch_kill(unlucky, 2, 0.5);
+        assert_eq!(lost.life, lost.life_max - 2);
+        assert_eq!(lost.stats.clutch_saves, 0);
+        assert!(!lost.damage_numbers.iter().any(|d| d.is_clutch));
+    }
+
+    #[test]
+    fn clutch_needs_under_a_tile_of_loop_left() {
+        assert!(!is_clutch_kill(20.0, 19.0));
+        assert!(is_clutch_kill(20.0, 19.001));
+        assert!(!is_clutch_kill(0.0, 0.0));
+        let (lucky, _) = clutch_seeds();
+        let far = clutch_kill(lucky, 2, CLUTCH_DISTANCE);
+        assert_eq!((far.life, far.stats.clutch_saves), (far.life_max - 2, 0));
+    }
+
+    #[test]
+    fn full_life_gets_no_clutch_refund() {
+        let (lucky, _) = clutch_seeds();
+        let rc = clutch_kill(lucky, 0, 0.5);
+        assert_eq!((rc.life, rc.stats.clutch_saves), (rc.life_max, 0));
+        assert!(!rc.damage_numbers.iter().any(|d| d.is_clutch));
+    }
+
+    #[test]
+    fn splash_upgrade_hits_a_neighbour_with_falloff() {
+        let single = splash_shot(0);
+        assert_eq!(damage_taken(&single), vec![10, 0]);
+        assert_eq!(single.damage_numbers.len(), 1);
+        // Radius 1.5: the neighbour sits two thirds of the way out, taking 2/3 damage
+        let splashed = splash_shot(3);
+        assert_eq!(damage_taken(&splashed), vec![10, 7]);
+        assert_eq!(splashed.damage_numbers.len(), 2);
+    }
+
+    #[test]
+    fn bounce_carries_a_shot_on_to_the_next_enemy() {
+        assert_eq!(damage_taken(&bounce_shot(0)), vec![10, 0]);
+        // One hop at 75%, rounded
+        assert_eq!(damage_taken(&bounce_shot(1)), vec![10, 8]);
+        // The second hop comes back to the first enemy, the only other one in range
+        assert_eq!(damage_taken(&bounce_shot(2)), vec![16, 8]);
+    }
+
+    #[test]
+    fn bounce_needs_another_enemy_in_range() {
+        let mut rs = veteran_run(0);
+        let mut far = rs.enemies[0].clone();
+        far.x += BOUNCE_RANGE_TILES + 0.5;
+        rs.enemies.push(far);
+        rs.stats.enemies_spawned = 2;
+        let mut shot = stray(4.4, 4.5, 2.0, 0.05);
+        shot.bounces_remaining = 3;
+        rs.projectiles.push(shot);
+        advance_projectiles(&mut rs, 0.05);
+        assert!(rs.projectiles.is_empty());
+        assert_eq!(damage_taken(&rs), vec![5, 0]);
+    }
+
+    // Loop distance covered by each enemy in one tick
+    fn strides(rc: &mut Rc<RunState>, dt: f64) -> Vec<f64> {
+        let before: Vec<f64> = rc.enemies.iter().map(|e| e.loop_dist).collect();
+        *rc = rc.clone().reduce(RunAction::SimTick { dt });
+        let total = rc.loop_total_length;
+        rc.enemies
+            .iter()
+            .zip(before)
+            .map(|(e, b)| (e.loop_dist - b).rem_euclid(total))
+            .collect()
+    }
+
+    #[test]
+    fn slowed_enemies_cover_less_ground_until_the_slow_expires() {
+        let mut rs = veteran_run(0);
+        rs.towers.clear();
+        rs.enemies[0].speed_tps = 1.0;
+        let mut chilled = rs.enemies[0].clone();
+        chilled.debuffs = Tower::new(3, 3, TowerKind::Slow, 3.5, 20, None)
+            .kind_debuff()
+            .into_iter()
+            .collect();
+        rs.enemies.push(chilled);
+        rs.stats.enemies_spawned = 2;
+        let mut rc = Rc::new(rs);
+        let s = strides(&mut rc, 0.1);
+        assert!(
+            (s[1] / s[0] - (1.0 - SLOW_TOWER_STRENGTH)).abs() < 1e-9,
+            "{s:?}"
+        );
+        for _ in 0..20 {
+            strides(&mut rc, 0.1);
+        }
+        assert!(rc.enemies[1].debuffs.is_empty());
+        let s = strides(&mut rc, 0.1);
+        assert!((s[1] - s[0]).abs() < 1e-9, "{s:?}");
+    }
+
+    #[test]
+    fn stacked_slows_refresh_but_stop_at_the_floor() {
+        let slow = |strength, remaining| Debuff {
+            kind: DebuffKind::Slow,
+            remaining,
+            strength,
+        };
+        let mut e = veteran_run(0).enemies[0].clone();
+        inflict(&mut e, slow(0.5, 1.0));
+        inflict(&mut e, slow(0.35, 3.0));
+        assert_eq!(e.debuffs, vec![slow(0.5, 3.0)]);
+        assert_eq!(debuff_speed_mult(&e.debuffs), 0.5);
+        inflict(&mut e, slow(0.9, 0.5));
+        assert_eq!(debuff_speed_mult(&e.debuffs), SLOW_SPEED_FLOOR);
+        // Freeze is a stop, not a slow
+        e.debuffs.push(Debuff {
+            kind: DebuffKind::Freeze,
+            remaining: 1.0,
+            strength: 1.0,
+        });
+        assert_eq!(debuff_speed_mult(&e.debuffs), 0.0);
+    }
+
+    #[test]
+    fn slow_tower_hits_apply_the_chill() {
+        let mut rs = veteran_run(0);
+        rs.towers[0].kind = TowerKind::Slow;
+        let mut rc = Rc::new(rs);
+        for _ in 0..40 {
+            rc = rc.reduce(RunAction::SimTick { dt: 0.05 });
+        }
+        let e = &rc.enemies[0];
+        assert!(e.hp < e.max_hp);
+        assert!(e.debuffs.iter().any(|d| d.kind == DebuffKind::Slow));
+    }
+
+    #[test]
+    fn cold_upgrades_scale_slow_tower_hits() {
+        let mut ups = UpgradeState::default();
+        ups.levels
+            .insert(UpgradeId::BoostColdSlowAmount.key().into(), 5);
+        ups.levels
+            .insert(UpgradeId::BoostColdSlowDuration.key().into(), 2);
+        let mut rs = veteran_run(0);
+        apply_upgrades_to_run(&mut rs, &ups);
+        assert_eq!((rs.cold_slow_multiplier, rs.cold_slow_duration), (1.5, 2.0));
+        let tile = rs.cold_debuff_template.clone().unwrap();
+        assert_eq!((tile.strength, tile.remaining), (0.75, 3.0));
+        assert_eq!(rs.freeze_chance, 0.0);
+
+        rs.towers[0].kind = TowerKind::Slow;
+        let mut rc = Rc::new(rs);
+        while rc.enemies[0].debuffs.is_empty() {
+            rc = rc.reduce(RunAction::SimTick { dt: 0.05 });
+        }
+        let slow = &rc.enemies[0].debuffs[0];
+        assert_eq!(slow.kind, DebuffKind::Slow);
+        assert!((slow.strength - SLOW_TOWER_STRENGTH * 1.5).abs() < 1e-9);
+        assert!(slow.remaining > SLOW_TOWER_SECS + 1.5, "{}", slow.remaining);
+    }
+
+    #[test]
+    fn a_thawed_enemy_is_immune_to_freezing_for_a_while() {
+        let mut rs = veteran_run(0);
+        rs.towers.clear();
+        rs.enemies[0].speed_tps = 1.0;
+        let slow = Debuff {
+            kind: DebuffKind::Slow,
+            remaining: 0.1,
+            strength: 0.2,
+        };
+        let freeze_left = |rc: &RunState| {
+            rc.enemies[0]
+                .debuffs
+                .iter()
+                .find(|d| d.kind == DebuffKind::Freeze)
+                .map(|d| d.remaining)
+        };
+        // Every hit rolls a freeze
+        let hit =
+            |rc: &mut Rc<RunState>| chill(&mut Rc::make_mut(rc).enemies[0], slow.clone(), 1.0, 0.0);
+        let mut rc = Rc::new(rs);
+        hit(&mut rc);
+        rc = tick(rc, 2);
+        // Frozen: no ground covered, and a second freeze doesn't extend it
+        assert_eq!(rc.enemies[0].loop_dist, 0.0);
+        hit(&mut rc);
+        assert_eq!(freeze_left(&rc), Some(FREEZE_SECS - 0.5));
+
+        // Thawed and moving, but still inside the immunity window
+        rc = tick(rc, 4);
+        assert!(rc.enemies[0].loop_dist > 0.0);
+        hit(&mut rc);
+        assert!(freeze_left(&rc).is_some_and(|t| t < 0.0));
+        assert!(debuff_speed_mult(&rc.enemies[0].debuffs) > 0.0);
+
+        // Window over: the next lucky hit freezes again
+        rc = tick(rc, 8);
+        assert_eq!(freeze_left(&rc), None);
+        hit(&mut rc);
+        assert_eq!(freeze_left(&rc), Some(FREEZE_SECS));
+    }
+
+    // A started run with towers, enemies in flight and some history
+    fn busy_run() -> RunState {
+        let mut rs = veteran_run(300);
+        rs.currencies.gold = 17;
+        rs.life = 7;
+        rs.stats.time_survived_secs = 95;
+        rs.stats.enemies_spawned = 40;
+        rs.stats.enemies_killed = 33;
+        tick_seconds(Rc::new(rs), 3).as_ref().clone()
+    }
+
+    #[test]
+    fn saved_run_comes_back_paused_with_a_clear_field() {
+        let rs = busy_run();
+        assert!(!rs.enemies.is_empty());
+        let back = RunState::from_save(&rs.to_save()).unwrap();
+        let mut expected = rs.clone();
+        expected.enemies.clear();
+        expected.projectiles.clear();
+        expected.damage_numbers.clear();
+        expected.splash_explosions.clear();
+        expected.heat = HeatGrid::new(rs.grid_size);
+        expected.is_paused = true;
+        assert_eq!(back, expected);
+    }
+
+    #[test]
+    fn enemy_ids_are_unique_and_survive_serialization() {
+        let rs = play_ring_road(scenario_run(ScenarioId::RingRoad), 8, |_| {});
+        assert!(rs.enemies.len() > 1);
+        let ids: HashSet<_> = rs.enemies.iter().map(|e| e.id).collect();
+        assert_eq!(ids.len(), rs.enemies.len());
+        let back: RunState = serde_json::from_str(&serde_json::to_string(&rs).unwrap()).unwrap();
+        for e in &rs.enemies {
+            assert_eq!(back.enemies.iter().find(|b| b.id == e.id), Some(e));
+        }
+        // The counter comes along too, so the next arrival can't take a used id
+        let mut back = RunState::from_save(&rs.to_save()).unwrap();
+        let next = back.entity_ids.alloc();
+        assert!(ids.iter().all(|id| *id < next));
+    }
+
+    #[test]
+    fn save_run_length_encodes_the_board() {
+        let rs = RunState::new_basic(GridSize {
+            width: 255,
+            height: 255,
+        });
+        let raw = rs.to_save();
+        let plain = serde_json::to_string(&rs.tiles).unwrap();
+        assert!(
+            raw.len() * 3 < plain.len(),
+            "{} vs {}",
+            raw.len(),
+            plain.len()
+        );
+        let back = RunState::from_save(&raw).unwrap();
+        assert_eq!(back.tiles, rs.tiles);
+        assert_eq!(back.path_loop, rs.path_loop);
+    }
+
+    #[test]
+    fn unreadable_saves_are_discarded() {
+        let raw = busy_run().to_save();
+        assert!(RunState::from_save("").is_none());
+        assert!(RunState::from_save(&raw[..raw.len() / 2]).is_none());
+        // A board that doesn't fill its grid
+        let mut rs = busy_run();
+        rs.grid_size.width += 1;
+        assert!(RunState::from_save(&rs.to_save()).is_none());
+    }
+
+    #[test]
+    fn load_saved_replaces_the_run() {
+        let saved = RunState::from_save(&busy_run().to_save()).unwrap();
+        let mut current = make_run();
+        current.currencies.research = 123;
+        let rc = Rc::new(current).reduce(RunAction::LoadSaved {
+            state: Box::new(saved.clone()),
+        });
+        let mut expected = saved;
+        expected.currencies.research = 123;
+        assert_eq!(*rc, expected);
+    }
+
+    #[test]
+    fn restored_snapshot_serializes_like_the_original() {
+        let snap = busy_run();
+        assert!(!snap.enemies.is_empty() && !snap.towers.is_empty());
+        let later = tick_seconds(Rc::new(snap.clone()), 2);
+        let rc = later.clone().reduce(RunAction::RestoreSnapshot {
+            state: Box::new(snap.clone()),
+        });
+        assert!(rc.is_paused);
+        assert_eq!(rc.version, later.version + 1);
+        let mut expected = snap;
+        expected.is_paused = true;
+        expected.version = rc.version;
+        assert_eq!(
+            serde_json::to_string(&*rc).unwrap(),
+            serde_json::to_string(&expected).unwrap()
+        );
+    }
+
+    #[test]
+    fn single_step_advances_one_tick_and_stays_paused() {
+        let mut rs = busy_run();
+        rs.is_paused = true;
+        // No spawn due: archetype rolls would differ between the two ticks compared
+        rs.last_enemy_spawn_time_secs = rs.stats.time_survived_secs as f64;
+        let paused = Rc::new(rs);
+        let dt = 0.05;
+        let stepped = paused.clone().reduce(RunAction::StepSim { dt });
+        assert!(stepped.is_paused);
+        assert!((stepped.sim_time - paused.sim_time - dt).abs() < 1e-12);
+        // Same as one tick of the unpaused run
+        let mut running = (*paused).clone();
+        running.is_paused = false;
+        let mut ticked = (*Rc::new(running).reduce(RunAction::SimTick { dt })).clone();
+        ticked.is_paused = true;
+        assert_eq!(*stepped, ticked);
+        // Running runs are left to the normal loop
+        let live = Rc::new(busy_run());
+        assert!(Rc::ptr_eq(
+            &live.clone().reduce(RunAction::StepSim { dt }),
+            &live
+        ));
+    }
+
+    // Enemies a scenario's schedule releases in total
+    fn scheduled(def: &ScenarioDef) -> u64 {
+        def.waves.iter().map(|w| w.count as u64).sum()
+    }
+
+    fn scenario_run(id: ScenarioId) -> Rc<RunState> {
+        Rc::new(RunState::new_basic(GridSize {
+            width: 10,
+            height: 10,
+        }))
+        .reduce(RunAction::StartScenario { id })
+        .reduce(RunAction::StartRun)
+    }
+
+    #[test]
+    fn sim_speed_takes_only_offered_speeds_and_steps_stay_exact() {
+        let rc = scenario_run(ScenarioId::RingRoad).reduce(RunAction::SetSimSpeed { speed: 4.0 });
+        assert_eq!(rc.sim_speed, 4.0);
+        let odd = rc.clone().reduce(RunAction::SetSimSpeed { speed: 3.0 });
+        assert_eq!(odd.sim_speed, 4.0);
+        assert_eq!(step_sim_speed(4.0, true), 4.0);
+        assert_eq!(step_sim_speed(4.0, false), 2.0);
+        assert_eq!(step_sim_speed(1.0, false), 1.0);
+        // A paused step is the given dt whatever the speed
+        let paused = rc.reduce(RunAction::TogglePause);
+        let stepped = paused.clone().reduce(RunAction::StepSim { dt: 0.1 });
+        assert!((stepped.sim_time - paused.sim_time - 0.1).abs() < 1e-12);
+        assert_eq!(stepped.sim_speed, 4.0);
+    }
+
+    #[test]
+    fn double_speed_matches_twice_the_ticks() {
+        // Five real seconds of ticks at 2x against ten at 1x, a TickSecond per real second
+        let run = |speed: f64, secs: usize| {
+            let mut rc =
+                scenario_run(ScenarioId::RingRoad).reduce(RunAction::SetSimSpeed { speed });
+            for _ in 0..secs {
+                rc = tick(rc, 4);
+                rc = rc.reduce(RunAction::TickSecond);
+            }
+            rc
+        };
+        let (fast, slow) = (run(2.0, 5), run(1.0, 10));
+        assert_eq!(fast.stats.time_survived_secs, 10);
+        assert_eq!(slow.stats.time_survived_secs, 10);
+        assert!((fast.sim_time - slow.sim_time).abs() < 1e-9);
+        assert!(fast.stats.enemies_spawned > 0);
+        assert_eq!(fast.stats.enemies_spawned, slow.stats.enemies_spawned);
+        assert_eq!(fast.enemies.len(), slow.enemies.len());
+        // Spawns land at the end of a real second, so a 2x enemy may trail by one world second
+        let total = slow.loop_total_length;
+        for (f, s) in fast.enemies.iter().zip(&slow.enemies) {
+            let gap = (f.loop_dist - s.loop_dist).rem_euclid(total);
+            let gap = gap.min(total - gap);
+            assert!(gap <= f.speed_tps + 1e-9, "enemy {:?} is {gap} apart", f.id);
+        }
+    }
+
+    #[test]
+    fn fast_forward_keeps_the_late_spawn_rate() {
+        // Late enough that spawns come faster than a TickSecond
+        let mut rs = run_from_rows(RING);
+        rs.started = true;
+        rs.life_max = 10_000;
+        rs.life = 10_000;
+        rs.stats.time_survived_secs = 900;
+        rs.last_enemy_spawn_time_secs = 900.0;
+        rs.difficulty.enemy_cap = 10_000.0;
+        assert!(spawn_interval(&rs) < 1.0);
+        let start = Rc::new(rs);
+        let run = |speed: f64, secs: usize| {
+            let mut rc = start.clone().reduce(RunAction::SetSimSpeed { speed });
+            for _ in 0..secs {
+                rc = tick(rc, 4);
+                rc = rc.reduce(RunAction::TickSecond);
+            }
+            rc
+        };
+        // A last tick lets both spawn what their final TickSecond made due
+        let (fast, slow) = (tick(run(4.0, 2), 1), tick(run(1.0, 8), 1));
+        assert_eq!(fast.stats.time_survived_secs, slow.stats.time_survived_secs);
+        let (got, want) = (fast.stats.enemies_spawned, slow.stats.enemies_spawned);
+        // Swarms are random and count several each, so only roughly the same
+        assert!(want > 8, "{want} spawns in 8s");
+        assert!(got * 10 >= want * 8, "{got} spawns at 4x vs {want} at 1x");
+    }
+
+    #[test]
+    fn catch_up_replays_at_normal_speed() {
+        let fast = catch_up_run(1_000).reduce(RunAction::SetSimSpeed { speed: 4.0 });
+        let caught = fast.reduce(RunAction::CatchUp {
+            elapsed_secs: 3.0,
+            mode: CatchUpMode::Long,
+        });
+        assert_eq!(caught.stats.time_survived_secs, 3);
+        assert!((caught.sim_time - 3.0).abs() < 1e-6);
+        assert_eq!(caught.sim_speed, 4.0);
+    }
+
+    #[test]
+    fn scenarios_load_valid_runs_with_their_purse() {
+        for id in ScenarioId::ALL {
+            let def = id.def();
+            let rs = RunState::from_scenario(id);
+            assert_eq!(crate::state::invariants::check(&rs), vec![], "{}", def.name);
+            assert!(!rs.path_loop.is_empty(), "{} has no loop", def.name);
+            assert_eq!(
+                (rs.currencies.gold, rs.life, rs.life_max),
+                (def.gold, def.life, def.life)
+            );
+            // Second 0 never ticks, so nothing may be scheduled there
+            assert!(def.waves.iter().all(|w| w.at_secs >= 1 && w.count > 0));
+            if let ScenarioGoal::Kills(n) = def.goal {
+                assert!(scheduled(def) >= n, "{} can't be won", def.name);
+            }
+        }
+        // Research carries over; meta upgrades don't apply
+        let mut ups = UpgradeState::default();
+        ups.levels.insert(UpgradeId::TowerDamage1.key().into(), 5);
+        let mut rs = RunState::new_with_upgrades(
+            GridSize {
+                width: 10,
+                height: 10,
+            },
+            &ups,
+            RouteMode::Loop,
+        );
+        rs.currencies.research = 77;
+        let rc = Rc::new(rs)
+            .reduce(RunAction::StartScenario {
+                id: ScenarioId::RingRoad,
+            })
+            .reduce(RunAction::ApplyUpgrades { ups });
+        assert_eq!(rc.currencies.research, 77);
+        assert_eq!(rc.tower_base_damage, 2);
+    }
+
+    #[test]
+    fn scenario_schedules_spawn_exactly_as_written() {
+        for id in ScenarioId::ALL {
+            let def = id.def();
+            let mut rc = scenario_run(id);
+            let end = def.last_spawn_secs();
+            for secs in 1..=end + 5 {
+                let before = rc.stats.enemies_spawned;
+                // SimTick moves nothing here: the dynamic spawner is off
+                rc = rc.reduce(RunAction::SimTick { dt: 0.0 });
+                rc = rc.reduce(RunAction::TickSecond);
+                if rc.game_over {
+                    break;
+                }
+                let due = def.spawns_at(secs).count() as u64;
+                assert_eq!(
+                    rc.stats.enemies_spawned - before,
+                    due,
+                    "{} at {secs}s",
+                    def.name
+                );
+                let kinds: Vec<EnemyKind> = def.spawns_at(secs).map(|w| w.kind).collect();
+                let fresh = rc.enemies.iter().filter(|e| e.spawned_at == secs);
+                assert_eq!(fresh.map(|e| e.kind).collect::<Vec<_>>(), kinds);
+            }
+            assert!(rc.game_over || rc.stats.enemies_spawned == scheduled(def));
+        }
+    }
+
+    #[test]
+    fn scenario_constraints_reject_violating_actions() {
+        let mut rc = scenario_run(ScenarioId::FourTowers);
+        let mut rs = (*rc).clone();
+        rs.currencies.gold = 100;
+        rs.currencies.tile_credits = 10;
+        rc = Rc::new(rs);
+        for x in 2..7 {
+            rc = rc.reduce(RunAction::PlaceTower {
+                x,
+                y: 2,
+                kind: TowerKind::Basic,
+            });
+        }
+        assert_eq!(rc.towers.len(), 4);
+        assert!(at_tower_limit(&rc));
+        // Selling frees a slot
+        rc = rc.reduce(RunAction::SellTower { x: 2, y: 2 });
+        assert!(!at_tower_limit(&rc));
+
+        let fresh = Rc::new(RunState::from_scenario(ScenarioId::RingRoad));
+        let perk = offered_perks(fresh.map_seed)[0];
+        let after = fresh.clone().reduce(RunAction::ChoosePerk { perk });
+        assert_eq!(after.perk, None);
+        let after = fresh.reduce(RunAction::SetSiege { on: true });
+        assert!(!after.siege);
+    }
+
+    #[test]
+    fn scenario_win_fires_once_and_scores_stars() {
+        let mut rs = (*scenario_run(ScenarioId::RingRoad)).clone();
+        rs.stats.time_survived_secs = 119;
+        rs.life = 6;
+        let won = Rc::new(rs).reduce(RunAction::TickSecond);
+        assert!(won.game_over);
+        assert_eq!(won.scenario.unwrap().outcome, Some(ScenarioOutcome::Won));
+        assert_eq!(scenario_stars(&won), 2);
+        let again = won.clone().reduce(RunAction::TickSecond);
+        assert_eq!(again.stats.time_survived_secs, 120);
+        assert_eq!(again.scenario, won.scenario);
+
+        let mut records = MetaRecords::default();
+        assert!(records.record_scenario(ScenarioId::RingRoad, 2));
+        assert!(!records.record_scenario(ScenarioId::RingRoad, 1));
+        assert_eq!(records.stars(ScenarioId::RingRoad), 2);
+        assert_eq!(records.stars(ScenarioId::GoldRush), 0);
+    }
+
+    #[test]
+    fn kill_goal_is_lost_once_the_schedule_runs_dry() {
+        let def = ScenarioId::FourTowers.def();
+        let mut rs = (*scenario_run(ScenarioId::FourTowers)).clone();
+        rs.stats.time_survived_secs = def.last_spawn_secs();
+        rs.stats.enemies_spawned = scheduled(def);
+        rs.stats.enemies_killed = 39;
+        let lost = Rc::new(rs.clone()).reduce(RunAction::TickSecond);
+        assert_eq!(lost.scenario.unwrap().outcome, Some(ScenarioOutcome::Lost));
+        assert_eq!(scenario_stars(&lost), 0);
+        rs.stats.enemies_killed = 40;
+        let won = Rc::new(rs).reduce(RunAction::TickSecond);
+        assert_eq!(won.scenario.unwrap().outcome, Some(ScenarioOutcome::Won));
+        assert_eq!(scenario_stars(&won), 3);
+    }
+
+    #[test]
+    fn heat_cells_cover_two_tiles_with_a_partial_last_row() {
+        let heat = HeatGrid::new(GridSize {
+            width: 9,
+            height: 5,
+        });
+        assert_eq!(heat.width(), 5);
+        assert_eq!(heat.cells().len(), 15);
+        assert_eq!(heat.cell_index(0.0, 0.0), Some(0));
+        assert_eq!(heat.cell_index(1.99, 1.99), Some(0));
+        assert_eq!(heat.cell_index(2.0, 0.5), Some(1));
+        // Column 8 is the lone tile in the last cell; row 4 the lone row
+        assert_eq!(heat.cell_index(8.5, 4.5), Some(14));
+        assert_eq!(heat.cell_index(9.0, 0.0), None);
+        assert_eq!(heat.cell_index(0.0, 5.0), None);
+        assert_eq!(heat.cell_index(-0.1, 0.0), None);
+    }
+
+    #[test]
+    fn heat_decays_and_clears_faint_cells() {
+        let mut heat = HeatGrid::new(GridSize {
+            width: 4,
+            height: 4,
+        });
+        heat.add(0.5, 0.5, 10.0);
+        heat.add(3.5, 3.5, 0.0101);
+        heat.decay(0.5);
+        assert_eq!(heat.cells()[0], 5.0);
+        assert_eq!(heat.cells()[3], 0.0);
+        assert_eq!(heat.max(), 5.0);
+    }
+
+    #[test]
+    fn heat_intensity_is_logarithmic_and_capped() {
+        assert_eq!(heat_intensity(0.0, 100.0), 0.0);
+        assert!((heat_intensity(100.0, 100.0) - 1.0).abs() < 1e-9);
+        // A tenth of the peak still shows at about half strength
+        let tenth = heat_intensity(10.0, 100.0);
+        assert!(tenth > 0.5 && tenth < 0.55, "{tenth}");
+        // Small totals are measured against the minimum scale, not against themselves
+        assert!(heat_intensity(2.0, 2.0) < 0.4);
+    }
+
+    #[test]
+    fn hits_heat_the_cells_they_land_in() {
+        let rs = splash_shot(3);
+        // Both enemies stand in the cell covering tiles 4..6
+        let i = rs.heat.cell_index(4.5, 4.5).unwrap();
+        assert_eq!(rs.heat.cell_index(5.5, 4.5), Some(i));
+        assert_eq!(rs.heat.cells()[i], 17.0);
+    }
+
+    #[test]
+    fn splash_falloff_runs_from_full_to_half() {
+        assert!((splash_falloff(0.0, 1.5) - 1.0).abs() < 1e-9);
+        assert!((splash_falloff(1.5, 1.5) - 0.5).abs() < 1e-9);
+        assert_eq!(splash_falloff(0.2, 0.0), 0.0);
+    }
+
+    #[test]
+    fn stuck_projectile_expires_at_max_lifetime() {
+        let mut rs = run_from_rows(RING);
+        // Never arrives and never moves: only the lifetime cap can remove it
+        rs.projectiles.push(stray(4.5, 4.5, 0.0, f64::INFINITY));
+        let steps = (PROJECTILE_MAX_LIFETIME_SECS / 0.1).round() as u32;
+        for _ in 0..steps - 1 {
+            advance_projectiles(&mut rs, 0.1);
+        }
+        assert_eq!(rs.projectiles.len(), 1);
+        advance_projectiles(&mut rs, 0.1);
+        assert!(rs.projectiles.is_empty());
+        assert_eq!(rs.projectile_culls.expired, 1);
+    }
+
+    #[test]
+    fn projectile_cap_culls_oldest_first() {
+        let mut rs = run_from_rows(RING);
+        for i in 0..PROJECTILE_CAP + 3 {
+            let mut p = stray(4.5, 4.5, 0.0, 10.0);
+            p.damage = i as u32;
+            rs.projectiles.push(p);
+        }
+        advance_projectiles(&mut rs, 0.01);
+        assert_eq!(rs.projectiles.len(), PROJECTILE_CAP);
+        assert_eq!(rs.projectiles[0].damage, 3);
+        assert_eq!(rs.projectile_culls.capped, 3);
+    }
+
+    #[test]
+    fn projectile_leaving_the_grid_is_removed() {
+        let mut rs = run_from_rows(RING);
+        let edge = rs.grid_size.width as f64 + PROJECTILE_OOB_MARGIN_TILES;
+        rs.projectiles.push(stray(edge - 0.05, 4.5, 1.0, 10.0));
+        rs.projectiles.push(stray(4.5, 4.5, 1.0, 10.0));
+        advance_projectiles(&mut rs, 0.1);
+        assert_eq!(rs.projectiles.len(), 1);
+        assert_eq!(rs.projectile_culls.out_of_bounds, 1);
+        // A projectile arriving out there still resolves as a normal impact
+        rs.projectiles[0].x = edge + 1.0;
+        rs.projectiles[0].remaining = 0.05;
+        advance_projectiles(&mut rs, 0.1);
+        assert!(rs.projectiles.is_empty());
+        assert_eq!(rs.projectile_culls.out_of_bounds, 1);
+    }
+
+    // A RING run whose map seed offers `perk`, with the perk taken
+    fn run_with_perk(perk: Perk) -> Rc<RunState> {
+        let mut rs = run_from_rows(RING);
+        rs.map_seed = (0..)
+            .find(|&seed| offered_perks(seed).contains(&perk))
+            .unwrap();
+        Rc::new(rs).reduce(RunAction::ChoosePerk { perk })
+    }
+
+    #[test]
+    fn perk_offer_is_seeded() {
+        for seed in 0..20 {
+            let offer = offered_perks(seed);
+            assert_eq!(offer, offered_perks(seed));
+            assert_eq!(offer.len(), PERK_OFFER_COUNT);
+            assert!(offer
+                .iter()
+                .all(|p| offer.iter().filter(|q| *q == p).count() == 1));
+        }
+        assert!((0..20).any(|seed| offered_perks(seed) != offered_perks(0)));
+    }
+
+    #[test]
+    fn bonus_gold_perk_adds_starting_gold() {
+        let gold = run_from_rows(RING).currencies.gold;
+        let rc = run_with_perk(Perk::BonusGold);
+        assert_eq!(rc.perk, Some(Perk::BonusGold));
+        assert_eq!(rc.currencies.gold, gold + PERK_BONUS_GOLD);
+    }
+
+    #[test]
+    fn free_tower_perk_waives_one_tower() {
+        let mut rs = (*run_with_perk(Perk::FreeTower)).clone();
+        rs.currencies.gold = 0;
+        let place = |rc: Rc<RunState>, x| {
+            rc.reduce(RunAction::PlaceTower {
+                x,
+                y: 2,
+                kind: TowerKind::Basic,
+            })
+        };
+        let one = place(Rc::new(rs), 1);
+        assert_eq!(one.towers.len(), 1);
+        assert_eq!((one.currencies.gold, one.free_towers), (0, 0));
+        assert_eq!(place(one, 7).towers.len(), 1);
+    }
+
+    #[test]
+    fn each_tower_standing_raises_the_price_of_the_next() {
+        let costs: Vec<u64> = (0..6).map(|n| scaled_tower_cost(2, n)).collect();
+        assert_eq!(costs, [2, 2, 3, 3, 3, 4]);
+        let mut rs = run_from_rows(RING);
+        rs.currencies.gold = 100;
+        rs.currencies.tile_credits = 3;
+        let mut rc = Rc::new(rs);
+        for (x, y) in [(1, 2), (7, 2), (1, 4)] {
+            let cost = rc.current_tower_cost(&TowerKind::Damage);
+            let gold = rc.currencies.gold;
+            rc = rc.reduce(RunAction::PlaceTower {
+                x,
+                y,
+                kind: TowerKind::Damage,
+            });
+            assert_eq!(rc.currencies.gold, gold - cost);
+            assert_eq!(rc.towers.last().unwrap().paid, cost);
+        }
+        assert_eq!(rc.towers.len(), 3);
+        assert_eq!(rc.current_tower_cost(&TowerKind::Damage), 6);
+        // Upgrades, swaps and refunds still go by the list price
+        assert_eq!(rc.tower_cost_of(&TowerKind::Damage), TOWER_COST_DAMAGE);
+    }
+
+    #[test]
+    fn towers_take_a_tile_credit_that_selling_keeps() {
+        let mut rs = run_from_rows(RING);
+        rs.currencies.gold = 100;
+        rs.currencies.tile_credits = 1;
+        let place = |rc: Rc<RunState>, x| {
+            rc.reduce(RunAction::PlaceTower {
+                x,
+                y: 2,
+                kind: TowerKind::Basic,
+            })
+        };
+        let one = place(Rc::new(rs), 1);
+        assert_eq!((one.towers.len(), one.currencies.tile_credits), (1, 0));
+        assert_eq!(
+            one.tower_purchase_error(&TowerKind::Basic).as_deref(),
+            Some("Need a tile credit: mine some rock")
+        );
+        assert_eq!(place(one.clone(), 7).towers.len(), 1);
+        let sold = one.reduce(RunAction::SellTower { x: 1, y: 2 });
+        assert_eq!(sold.currencies.tile_credits, 0);
+        assert!(!sold.can_afford_tower(&TowerKind::Basic));
+        // Gold is reported first when both are short
+        let mut broke = (*sold).clone();
+        broke.currencies.gold = 0;
+        assert_eq!(
+            broke.tower_purchase_error(&TowerKind::Basic).as_deref(),
+            Some("Need 2 gold")
+        );
+    }
+
+    #[test]
+    fn placement_check_names_each_reason() {
+        use PlacementResult as P;
+        let mut rs = run_from_rows(RING);
+        rs.currencies.gold = 100;
+        rs.currencies.tile_credits = 5;
+        let basic = TowerKind::Basic;
+        assert_eq!(placement_check(&rs, 1, 2, &basic), P::Valid);
+        // Buried rock, and off the board
+        assert_eq!(placement_check(&rs, 0, 0, &basic), P::OutOfReach);
+        assert_eq!(placement_check(&rs, 9, 2, &basic), P::OutOfReach);
+        // Open floor and Start
+        assert_eq!(placement_check(&rs, 2, 2, &basic), P::WrongTile);
+        assert_eq!(placement_check(&rs, 4, 4, &basic), P::WrongTile);
+
+        let placed = Rc::new(rs.clone()).reduce(RunAction::PlaceTower {
+            x: 1,
+            y: 2,
+            kind: basic.clone(),
+        });
+        assert_eq!(placement_check(&placed, 1, 2, &basic), P::Occupied);
+
+        let mut hot = rs.clone();
+        hot.tiles[2 * 9 + 2].magma = true;
+        assert_eq!(placement_check(&hot, 1, 2, &basic), P::TooHot);
+
+        let mut early = rs.clone();
+        early.progression_run = Some(1);
+        assert_eq!(
+            placement_check(&early, 1, 2, &basic),
+            P::Locked("Towers unlock next run".to_string())
+        );
 
-                                if splash_radius > 0.0 {
-                                    new.splash_explosions.push(SplashExplosion {
-                                        x: ix,
-                                        y: iy,
-                                        radius: splash_radius,
-                                        ttl: 0.25,
-                                    });
-
-                                    let splash_radius_sq = splash_radius * splash_radius;
-                                    let splash_damage = (p_damage as f64 * 0.5).round() as u32;
-                                    for (ei, e) in new.enemies.iter_mut().enumerate() {
-                                        if Some(ei) == hit {
-                                            continue;
-                                        }
-                                        let dx = e.x - ix;
-                                        let dy = e.y - iy;
-                                        let d2 = dx * dx + dy * dy;
-                                        if d2 <= splash_radius_sq && splash_damage > 0 {
-                                            let applied = splash_damage.min(e.hp);
-                                            if splash_damage >= e.hp {
-                                                e.hp = 0;
-                                            } else {
-                                                e.hp -= splash_damage;
-                                            }
-                                            new.damage_numbers.push(DamageNumber {
-                                                x: e.x,
-                                                y: e.y,
-                                                amount: applied,
-                                                ttl: 0.8,
-                                                is_crit: false,
-                                                is_gold: false,
-                                                is_heal: false,
-                                            });
-                                        }
-                                    }
-                                }
-                                remove = true;
-                            }
-                        }
-                        if remove {
-                            new.projectiles.remove(i);
-                        } else {
-                            i += 1;
-                        }
-                    }
-                    if !new.enemies.is_empty() {
-                        // Collect burning enemies that died for spread processing
-                        let mut burn_spread_sources: Vec<(f64, f64, Debuff)> = Vec::new();
-                        if new.fire_spread_radius > 0.0 {
-                            for e in &new.enemies {
-                                if e.hp == 0 {
-                                    // Check if enemy has burn debuff
-                                    if let Some(burn) = e.debuffs.iter().find(|d| {
-                                        matches!(d.kind, DebuffKind::Burn) && d.remaining > 0.0
-                                    }) {
-                                        burn_spread_sources.push((e.x, e.y, burn.clone()));
-                                    }
-                                }
-                            }
-                        }
+        let mut broke = rs.clone();
+        broke.currencies.gold = 0;
+        let cost = broke.current_tower_cost(&basic);
+        assert_eq!(placement_check(&broke, 1, 2, &basic), P::NeedGold(cost));
+        let mut no_credit = rs.clone();
+        no_credit.currencies.tile_credits = 0;
+        assert_eq!(placement_check(&no_credit, 1, 2, &basic), P::NeedTileCredit);
 
-                        // Remove dead enemies before spreading burn (to avoid spreading to already-dead enemies)
-                        new.enemies.retain(|e| {
-                            if e.hp == 0 {
-                                kills = kills.saturating_add(1);
-                                false
-                            } else {
-                                true
-                            }
-                        });
-
-                        // Process burn spread to nearby living enemies
-                        if !burn_spread_sources.is_empty() && new.fire_spread_radius > 0.0 {
-                            for (sx, sy, burn) in burn_spread_sources {
-                                let spread_radius_sq =
-                                    new.fire_spread_radius * new.fire_spread_radius;
-                                for e in &mut new.enemies {
-                                    let dx = e.x - sx;
-                                    let dy = e.y - sy;
-                                    let dist_sq = dx * dx + dy * dy;
-                                    if dist_sq <= spread_radius_sq {
-                                        // Apply weakened burn (50% strength, 50% duration)
-                                        let spread_burn = Debuff {
-                                            kind: DebuffKind::Burn,
-                                            remaining: burn.remaining * 0.5,
-                                            strength: burn.strength * 0.5,
-                                        };
-                                        // Check if enemy already has burn
-                                        if let Some(existing) = e
-                                            .debuffs
-                                            .iter_mut()
-                                            .find(|d| matches!(d.kind, DebuffKind::Burn))
-                                        {
-                                            // Refresh duration and update strength (take max)
-                                            existing.remaining =
-                                                existing.remaining.max(spread_burn.remaining);
-                                            existing.strength =
-                                                existing.strength.max(spread_burn.strength);
-                                        } else {
-                                            // Add new burn
-                                            e.debuffs.push(spread_burn);
-                                        }
-                                    }
-                                }
-                            }
-                        }
+        let mut over = rs;
+        over.game_over = true;
+        assert_eq!(placement_check(&over, 1, 2, &basic), P::GameOver);
+        let refused = Rc::new(over).reduce(RunAction::PlaceTower {
+            x: 1,
+            y: 2,
+            kind: basic.clone(),
+        });
+        assert!(refused.towers.is_empty());
 
-                        if kills > 0 {
-                            new.currencies.research = new.currencies.research.saturating_add(kills);
-                            if new.gold_bounty_per_kill > 0 {
-                                new.currencies.gold = new
-                                    .currencies
-                                    .gold
-                                    .saturating_add(kills * new.gold_bounty_per_kill);
-                            }
-                        }
-                    }
-                }
-                for dn in &mut new.damage_numbers {
-                    dn.ttl -= dt;
-                }
-                new.damage_numbers.retain(|d| d.ttl > 0.0);
-                for se in &mut new.splash_explosions {
-                    se.ttl -= dt;
-                }
-                new.splash_explosions.retain(|s| s.ttl > 0.0);
-                if new.loop_total_length > 0.0
-                    && new.path_loop.len() >= 2
-                    && !new.enemies.is_empty()
-                {
-                    let total = new.loop_total_length;
-                    let sample_pos = |nodes: &Vec<Position>, cum: &Vec<f64>, total: f64, d: f64| {
-                        if nodes.len() < 2 || total <= 0.0 {
-                            return (0.0, 0.0, 0.0, 0.0, 0usize);
-                        }
-                        let dist = d % total;
-                        let mut seg_i = 0usize;
-                        while seg_i + 1 < cum.len() && cum[seg_i + 1] <= dist {
-                            seg_i += 1;
-                        }
-                        let (ax, ay, bx, by, seg_len) = if seg_i + 1 < nodes.len() {
-                            let a = nodes[seg_i];
-                            let b = nodes[seg_i + 1];
-                            let seg_len = (((b.x as f64 - a.x as f64).powi(2)
-                                + (b.y as f64 - a.y as f64).powi(2))
-                            .sqrt())
-                            .max(1e-6);
-                            (
-                                a.x as f64 + 0.5,
-                                a.y as f64 + 0.5,
-                                b.x as f64 + 0.5,
-                                b.y as f64 + 0.5,
-                                seg_len,
-                            )
-                        } else {
-                            let a = nodes.last().unwrap();
-                            let b = nodes[0];
-                            let seg_len = (((b.x as f64 - a.x as f64).powi(2)
-                                + (b.y as f64 - a.y as f64).powi(2))
-                            .sqrt())
-                            .max(1e-6);
-                            (
-                                a.x as f64 + 0.5,
-                                a.y as f64 + 0.5,
-                                b.x as f64 + 0.5,
-                                b.y as f64 + 0.5,
-                                seg_len,
-                            )
-                        };
-                        let base = cum.get(seg_i).copied().unwrap_or(0.0);
-                        let t = ((dist - base) / seg_len).clamp(0.0, 1.0);
-                        let dx = bx - ax;
-                        let dy = by - ay;
-                        let nx = ax + dx * t;
-                        let ny = ay + dy * t;
-                        (
-                            nx,
-                            ny,
-                            dx / seg_len,
-                            dy / seg_len,
-                            (seg_i + 1) % nodes.len(),
-                        )
-                    };
-                    for e in &mut new.enemies {
-                        // Process debuffs
-                        let mut speed_mult: f64 = 1.0;
-                        let mut poison_damage = 0u32;
-                        let mut burn_damage = 0u32;
+        // FourTowers stops at its fourth
+        let mut four = (*scenario_run(ScenarioId::FourTowers)).clone();
+        four.currencies.gold = 100;
+        four.currencies.tile_credits = 10;
+        let mut rc = Rc::new(four);
+        for x in 2..6 {
+            rc = rc.reduce(RunAction::PlaceTower {
+                x,
+                y: 2,
+                kind: basic.clone(),
+            });
+        }
+        assert_eq!(rc.towers.len(), 4);
+        assert_eq!(placement_check(&rc, 6, 2, &basic), P::TowerLimit);
+    }
 
-                        // Update debuffs and calculate effects
-                        for debuff in &mut e.debuffs {
-                            debuff.remaining -= dt;
-                            if debuff.remaining > 0.0 {
-                                if matches!(debuff.kind, DebuffKind::Freeze) {
-                                    speed_mult = 0.0;
-                                }
-                                match debuff.kind {
-                                    DebuffKind::Slow => {
-                                        // Slow reduces speed (strength is the multiplier, e.g., 0.5 = 50% slow)
-                                        speed_mult = speed_mult.min(1.0 - debuff.strength);
-                                    }
-                                    DebuffKind::Poison => {
-                                        // Poison deals damage per second
-                                        poison_damage = poison_damage
-                                            .saturating_add((debuff.strength * dt).round() as u32);
-                                    }
-                                    DebuffKind::Burn => {
-                                        // Burn deals damage per second
-                                        burn_damage = burn_damage
-                                            .saturating_add((debuff.strength * dt).round() as u32);
-                                    }
-                                    DebuffKind::Freeze => {}
-                                }
-                            }
-                        }
+    #[test]
+    fn ghost_range_follows_the_kind() {
+        let rs = run_from_rows(RING);
+        let range = |kind| preview_tower(&rs, 1, 2, kind).range;
+        assert_eq!(range(TowerKind::Basic), rs.tower_base_range);
+        assert!(range(TowerKind::Slow) > range(TowerKind::Basic));
+        assert!(range(TowerKind::Damage) < range(TowerKind::Basic));
+    }
 
-                        // Remove expired debuffs
-                        e.debuffs.retain(|d| d.remaining > 0.0);
+    #[test]
+    fn quick_picks_perk_expires() {
+        let mut rs = (*run_with_perk(Perk::QuickPicks)).clone();
+        let base = rs.mining_speed;
+        assert!((rs.effective_mining_speed() - base * (1.0 + PERK_MINING_BONUS)).abs() < 1e-9);
+        // The clock only runs once the run does
+        assert_eq!(
+            tick_seconds(Rc::new(rs.clone()), 5).perk_mining_secs,
+            PERK_MINING_SECS
+        );
+        rs.started = true;
+        let rc = tick_seconds(Rc::new(rs), PERK_MINING_SECS - 1);
+        assert!(rc.effective_mining_speed() > base);
+        let rc = tick_seconds(rc, 1);
+        assert_eq!(rc.perk_mining_secs, 0);
+        assert!((rc.effective_mining_speed() - base).abs() < 1e-9);
+    }
 
-                        // Apply poison damage
-                        if poison_damage > 0 && e.hp > 0 {
-                            e.hp = e.hp.saturating_sub(poison_damage);
-                            // Show damage number for poison
-                            new.damage_numbers.push(DamageNumber {
-                                x: e.x,
-                                y: e.y,
-                                amount: poison_damage,
-                                ttl: 0.6,
-                                is_crit: false,
-                                is_gold: false,
-                                is_heal: false,
-                            });
-                        }
+    #[test]
+    fn leak_forgiven_perk_absorbs_first_leak_only() {
+        let mut rs = veteran_run(0);
+        rs.leak_shield = true;
+        rs.clean_streak = 1;
+        let life = rs.life;
+        let leak = |mut rs: RunState| {
+            rs.enemies[0].speed_tps = 1.0;
+            rs.enemies[0].loop_dist = short_of_lap(&rs, 0.01);
+            Rc::new(rs).reduce(RunAction::SimTick { dt: 0.05 })
+        };
+        let first = leak(rs);
+        assert_eq!(first.life, life);
+        assert!(!first.leak_shield);
+        assert_eq!(first.clean_streak, 1);
+        let second = leak((*first).clone());
+        assert_eq!(second.life, life - 1);
+    }
+
+    fn healing_run(power: u8, shield: u8, healers: u32) -> RunState {
+        use UpgradeId::*;
+        let mut ups = UpgradeState::default();
+        for (id, level) in [
+            (BoostHealingUnlock, 1),
+            (BoostHealingPower, power),
+            (BoostHealingShield, shield),
+        ] {
+            ups.levels.insert(id.key().into(), level);
+        }
+        let mut rs = run_from_rows(RING);
+        apply_upgrades_to_run(&mut rs, &ups);
+        rs.started = true;
+        for x in 0..healers {
+            let tw = Tower::new(x, 0, TowerKind::Basic, 3.5, 1, Some(BoostKind::Healing));
+            rs.towers.push(tw);
+        }
+        rs
+    }
+
+    #[test]
+    fn healing_towers_pulse_life_back() {
+        let pulse = HEAL_PULSE_SECS as u32;
+        // 1.5 life a pulse: the half carries over to the next one
+        let mut rs = healing_run(1, 0, 1);
+        rs.life = 2;
+        let rc = tick_seconds(Rc::new(rs.clone()), pulse - 1);
+        assert_eq!(rc.life, 2);
+        let rc = tick_seconds(rc, 1);
+        assert_eq!(rc.life, 3);
+        assert!(rc.damage_numbers.iter().any(|d| d.is_heal && d.amount == 1));
+        assert_eq!(rc.last_heal_pulse_at, Some(rc.sim_time));
+        assert_eq!(tick_seconds(rc, pulse).life, 5);
+        // No tower on a Healing tile, no healing
+        let mut bare = healing_run(1, 0, 0);
+        bare.life = 2;
+        assert_eq!(tick_seconds(Rc::new(bare), 2 * pulse).life, 2);
+    }
+
+    #[test]
+    fn healing_stops_at_max_life_and_overheals_into_shield() {
+        let pulse = HEAL_PULSE_SECS as u32;
+        // Two towers at 2 life each: 4 life against 1 missing
+        let mut rs = healing_run(2, 0, 2);
+        rs.life = rs.life_max - 1;
+        let rc = tick_seconds(Rc::new(rs), pulse);
+        assert_eq!((rc.life, rc.shield), (rc.life_max, 0));
+        let mut rs = healing_run(2, 1, 2);
+        rs.life = rs.life_max - 1;
+        let rc = tick_seconds(Rc::new(rs), pulse);
+        assert_eq!(rc.shield_max, HEAL_SHIELD_PER_LEVEL);
+        assert_eq!((rc.life, rc.shield), (rc.life_max, HEAL_SHIELD_PER_LEVEL));
+        // Shield wears off over time
+        let mut rs = healing_run(0, 1, 0);
+        rs.shield = 2;
+        let rc = tick_seconds(Rc::new(rs), SHIELD_DECAY_SECS as u32);
+        assert_eq!(rc.shield, 1);
+    }
+
+    #[test]
+    fn shield_is_spent_before_life_on_a_loop() {
+        let mut rs = veteran_run(0);
+        rs.shield_max = 2;
+        rs.shield = 1;
+        rs.leak_shield = true;
+        let life = rs.life;
+        let leak = |mut rs: RunState| {
+            rs.enemies[0].speed_tps = 1.0;
+            rs.enemies[0].loop_dist = short_of_lap(&rs, 0.01);
+            Rc::new(rs).reduce(RunAction::SimTick { dt: 0.05 })
+        };
+        // The perk's one-off pardon goes first, then shield, then life
+        let first = leak(rs);
+        assert_eq!((first.life, first.shield), (life, 1));
+        let second = leak((*first).clone());
+        assert_eq!((second.life, second.shield), (life, 0));
+        let third = leak((*second).clone());
+        assert_eq!((third.life, third.shield), (life - 1, 0));
+    }
+
+    #[test]
+    fn tanks_cost_more_life_and_overkill_ends_the_run() {
+        let mut rs = veteran_run(0);
+        let life = rs.life;
+        let leak = |mut rs: RunState, kind| {
+            rs.enemies[0].kind = kind;
+            rs.enemies[0].speed_tps = 1.0;
+            rs.enemies[0].loop_dist = short_of_lap(&rs, 0.01);
+            Rc::new(rs).reduce(RunAction::SimTick { dt: 0.05 })
+        };
+        let normal = leak(rs.clone(), EnemyKind::Normal);
+        assert_eq!(normal.life, life - 1);
+        assert_eq!(normal.last_life_loss_at, normal.sim_time);
+        assert!(normal.life_loss_flash());
+        let tank = leak(rs.clone(), EnemyKind::Tank);
+        assert_eq!(tank.life, life - 3);
+        assert!(!tank.game_over);
+
+        // Two life left against a tank's three: the run ends rather than waiting for exactly 0
+        rs.life = 2;
+        let over = leak(rs, EnemyKind::Tank);
+        assert_eq!(over.life, 0);
+        assert!(over.game_over);
+    }
+
+    #[test]
+    fn only_one_offered_perk_before_start() {
+        let rc = run_with_perk(Perk::BonusGold);
+        let gold = rc.currencies.gold;
+        let other = *offered_perks(rc.map_seed)
+            .iter()
+            .find(|p| **p != Perk::BonusGold)
+            .unwrap();
+        let again = rc.reduce(RunAction::ChoosePerk { perk: other });
+        assert_eq!(again.perk, Some(Perk::BonusGold));
+        assert_eq!(again.currencies.gold, gold);
+
+        // Perks not on offer, or taken after the run started, are refused
+        let mut rs = run_from_rows(RING);
+        let missing = *PERK_POOL
+            .iter()
+            .find(|p| !offered_perks(rs.map_seed).contains(p))
+            .unwrap();
+        assert!(Rc::new(rs.clone())
+            .reduce(RunAction::ChoosePerk { perk: missing })
+            .perk
+            .is_none());
+        rs.started = true;
+        let offered = offered_perks(rs.map_seed)[0];
+        assert!(Rc::new(rs)
+            .reduce(RunAction::ChoosePerk { perk: offered })
+            .perk
+            .is_none());
+    }
+
+    // veteran_run's parked enemy sits diagonally next to the tower
+    fn siege_run() -> Rc<RunState> {
+        let mut rs = veteran_run(0);
+        rs.siege = true;
+        Rc::new(rs)
+    }
+
+    fn sim_ticks(mut rc: Rc<RunState>, n: u32) -> Rc<RunState> {
+        for _ in 0..n {
+            rc = rc.reduce(RunAction::SimTick { dt: 0.1 });
+        }
+        rc
+    }
+
+    #[test]
+    fn contact_damage_only_under_siege() {
+        let calm = sim_ticks(Rc::new(veteran_run(0)), 10);
+        assert_eq!(calm.towers[0].hp, SIEGE_TOWER_HP);
+
+        let sieged = sim_ticks(siege_run(), 10);
+        let expected = SIEGE_TOWER_HP - SIEGE_CONTACT_DPS;
+        assert!((sieged.towers[0].hp - expected).abs() < 1e-9);
+
+        // Nobody adjacent, nothing lost
+        let mut far = (*siege_run()).clone();
+        far.towers[0] = Tower::new(6, 8, TowerKind::Basic, 3.5, 20, None);
+        assert_eq!(sim_ticks(Rc::new(far), 10).towers[0].hp, SIEGE_TOWER_HP);
+    }
+
+    #[test]
+    fn crumbled_tower_is_removed_without_refund() {
+        let mut rs = (*siege_run()).clone();
+        rs.towers[0].hp = 0.1;
+        let gold = rs.currencies.gold;
+        let rc = Rc::new(rs).reduce(RunAction::SimTick { dt: 0.1 });
+        assert!(rc.towers.is_empty());
+        assert_eq!(rc.currencies.gold, gold);
+        assert!(matches!(rc.tiles[3 * 9 + 3].kind, TileKind::Rock { .. }));
+        assert_eq!(rc.siege_notice.as_deref(), Some("Tower at (3, 3) crumbled"));
+        assert!(rc.reduce(RunAction::DismissNotices).siege_notice.is_none());
+    }
+
+    #[test]
+    fn repair_cost_scales_with_missing_hp() {
+        let mut tw = Tower::new(0, 0, TowerKind::Basic, 3.5, 20, None);
+        assert_eq!(tower_repair_cost(&tw), 0);
+        tw.hp = SIEGE_TOWER_HP - 0.1;
+        assert_eq!(tower_repair_cost(&tw), 1);
+        tw.hp = 0.5;
+        assert_eq!(tower_repair_cost(&tw), 5);
+
+        let mut rs = (*siege_run()).clone();
+        rs.towers[0].hp = 10.0;
+        rs.currencies.gold = 2;
+        let repair = RunAction::RepairTower { x: 3, y: 3 };
+        let broke = Rc::new(rs.clone()).reduce(repair.clone());
+        assert_eq!(broke.towers[0].hp, 10.0);
+        rs.currencies.gold = 4;
+        let fixed = Rc::new(rs.clone()).reduce(repair.clone());
+        assert_eq!(fixed.towers[0].hp, SIEGE_TOWER_HP);
+        assert_eq!(fixed.currencies.gold, 1);
+        // Without the modifier there is nothing to repair
+        rs.siege = false;
+        assert_eq!(Rc::new(rs).reduce(repair).currencies.gold, 4);
+    }
+
+    #[test]
+    fn siege_is_chosen_before_the_run_starts() {
+        let fresh = Rc::new(run_from_rows(RING));
+        assert!(fresh.reduce(RunAction::SetSiege { on: true }).siege);
+        assert!(
+            !Rc::new(veteran_run(0))
+                .reduce(RunAction::SetSiege { on: true })
+                .siege
+        );
+    }
+
+    #[test]
+    fn tower_durability_round_trips_through_json() {
+        let mut tw = Tower::new(2, 3, TowerKind::Slow, 3.5, 20, None);
+        tw.hp = 7.5;
+        let back: Tower = serde_json::from_str(&serde_json::to_string(&tw).unwrap()).unwrap();
+        assert_eq!(back, tw);
+        // Saves from before durability existed load at full health
+        let mut v = serde_json::to_value(&tw).unwrap();
+        let obj = v.as_object_mut().unwrap();
+        obj.remove("hp");
+        obj.remove("max_hp");
+        let old: Tower = serde_json::from_value(v).unwrap();
+        assert_eq!((old.hp, old.max_hp), (SIEGE_TOWER_HP, SIEGE_TOWER_HP));
+    }
+
+    #[test]
+    fn enemy_health_round_trips_through_json() {
+        let mut e = veteran_run(0).enemies.remove(0);
+        e.hp = 2_500;
+        e.hit_flash_ttl = 0.05;
+        let back: Enemy = serde_json::from_str(&serde_json::to_string(&e).unwrap()).unwrap();
+        assert_eq!((back.hp, back.max_hp), (2_500, 10_000));
+        assert_eq!(back, e);
+        // Saves from before hit flashes existed load without one
+        let mut v = serde_json::to_value(&e).unwrap();
+        v.as_object_mut().unwrap().remove("hit_flash_ttl");
+        let old: Enemy = serde_json::from_value(v).unwrap();
+        assert_eq!(old.hit_flash_ttl, 0.0);
+    }
+
+    #[test]
+    fn hit_flash_is_set_on_impact_and_decays_in_sim_tick() {
+        let mut rs = splash_shot(0);
+        assert_eq!(rs.enemies[0].hit_flash_ttl, HIT_FLASH_SECS);
+        assert_eq!(rs.enemies[1].hit_flash_ttl, 0.0);
+        rs.towers.clear();
+        let mut rc = Rc::new(rs).reduce(RunAction::SimTick { dt: 0.05 });
+        assert!((rc.enemies[0].hit_flash_ttl - (HIT_FLASH_SECS - 0.05)).abs() < 1e-9);
+        for _ in 0..2 {
+            rc = rc.reduce(RunAction::SimTick { dt: 0.05 });
+        }
+        assert_eq!(rc.enemies[0].hit_flash_ttl, 0.0);
+    }
+
+    #[test]
+    fn run_stats_track_tower_damage_and_live_dps() {
+        let mut rc = Rc::new(veteran_run(0));
+        for _ in 0..200 {
+            rc = rc.reduce(RunAction::SimTick { dt: 0.05 });
+        }
+        let dealt = rc.towers[0].damage_dealt;
+        assert!(dealt > 0);
+        assert_eq!(rc.stats.total_damage_dealt, dealt);
+        assert!(rc.stats.highest_single_hit >= 20);
+        let dps = rc.damage_window.dps(rc.sim_time);
+        assert!(dps > 0.0 && dps <= dealt as f64 / DPS_WINDOW_SECS as f64 + 1e-9);
+    }
+
+    #[test]
+    fn damage_window_forgets_seconds_past_the_window() {
+        let mut w = DamageWindow::default();
+        w.add(0.5, 30);
+        w.add(0.9, 30);
+        // Young runs average over their own length
+        assert_eq!(w.dps(2.0), 30.0);
+        w.add(9.2, 100);
+        assert_eq!(w.dps(9.5), 160.0 / 9.5);
+        // Second 0 drops out once second 10 starts
+        assert_eq!(w.dps(10.5), 10.0);
+        w.add(25.0, 50);
+        assert_eq!(w.buckets.len(), 1);
+        assert_eq!(w.dps(25.0), 5.0);
+    }
+
+    #[test]
+    fn run_stats_count_building_selling_and_gold() {
+        let mut rs = run_from_rows(RING);
+        rs.currencies.gold = 100;
+        let cost = rs.tower_cost_of(&TowerKind::Basic);
+        let built = Rc::new(rs).reduce(RunAction::PlaceTower {
+            x: 3,
+            y: 3,
+            kind: TowerKind::Basic,
+        });
+        assert_eq!(built.stats.towers_built, 1);
+        assert_eq!(built.stats.gold_spent, cost);
+        let sold = built.reduce(RunAction::SellTower { x: 3, y: 3 });
+        assert_eq!(sold.stats.towers_sold, 1);
+        // A refund isn't income
+        assert!(sold.currencies.gold > 100 - cost);
+        assert_eq!(sold.stats.gold_earned, 0);
+        let walled =
+            sold.reduce(RunAction::PlaceWall { x: 4, y: 2 })
+                .reduce(RunAction::PlaceWalls {
+                    tiles: vec![Position { x: 3, y: 2 }, Position { x: 4, y: 7 }],
+                });
+        assert_eq!(walled.stats.walls_built, 2);
+
+        let mut rs = (*walled).clone();
+        rs.pickups.push(Pickup {
+            x: 2.5,
+            y: 2.5,
+            research: 0.0,
+            gold: 7,
+            ttl: PICKUP_TTL_SECS,
+        });
+        let picked = Rc::new(rs).reduce(RunAction::CollectPickups { x: 2.5, y: 2.5 });
+        assert_eq!(picked.stats.gold_earned, 7);
+    }
+
+    #[test]
+    fn run_stats_load_from_saves_without_the_new_counters() {
+        let stats = RunStats {
+            time_survived_secs: 90,
+            enemies_killed: 12,
+            total_damage_dealt: 4_000,
+            walls_built: 3,
+            ..Default::default()
+        };
+        let mut v = serde_json::to_value(stats).unwrap();
+        let obj = v.as_object_mut().unwrap();
+        for key in [
+            "total_damage_dealt",
+            "highest_single_hit",
+            "gold_earned",
+            "gold_spent",
+            "towers_built",
+            "towers_sold",
+            "walls_built",
+        ] {
+            obj.remove(key);
+        }
+        let old: RunStats = serde_json::from_value(v).unwrap();
+        assert_eq!(
+            old,
+            RunStats {
+                time_survived_secs: 90,
+                enemies_killed: 12,
+                ..Default::default()
+            }
+        );
+    }
+
+    // RING run on an early progression run where no hint rule fires yet
+    fn quiet_hint_run() -> RunState {
+        let mut rs = run_from_rows(RING);
+        rs.started = true;
+        rs.hints = true;
+        rs.progression_run = Some(3);
+        rs.currencies.gold = 0;
+        rs.currencies.research = 0;
+        rs.currencies.tile_credits = 0;
+        rs.cheapest_upgrade_cost = None;
+        rs.life = rs.life_max;
+        rs
+    }
+
+    #[test]
+    fn no_hint_fires_on_a_quiet_run() {
+        let rs = quiet_hint_run();
+        assert!(HintId::ALL.iter().all(|h| !h.applies(&rs)));
+        assert_eq!(next_hint(&rs), None);
+    }
+
+    #[test]
+    fn place_tower_hint_needs_gold_and_no_towers() {
+        let mut rs = quiet_hint_run();
+        rs.currencies.gold = rs.tower_cost;
+        assert!(HintId::PlaceTower.applies(&rs));
+        rs.progression_run = Some(1);
+        assert!(!HintId::PlaceTower.applies(&rs));
+        let built = veteran_run(0);
+        assert!(!HintId::PlaceTower.applies(&built));
+    }
+
+    #[test]
+    fn lengthen_maze_hint_needs_a_short_loop_and_credits() {
+        let mut rs = quiet_hint_run();
+        rs.currencies.tile_credits = 1;
+        assert!(rs.path_loop.len() >= HINT_SHORT_LOOP);
+        assert!(!HintId::LengthenMaze.applies(&rs));
+        rs.path_loop.truncate(HINT_SHORT_LOOP - 1);
+        assert!(HintId::LengthenMaze.applies(&rs));
+        rs.currencies.tile_credits = 0;
+        assert!(!HintId::LengthenMaze.applies(&rs));
+    }
+
+    #[test]
+    fn defend_gate_hint_needs_low_life_and_spare_gold() {
+        let mut rs = quiet_hint_run();
+        rs.currencies.gold = rs.tower_cost * 2;
+        rs.life_max = 10;
+        rs.life = 3;
+        assert!(!HintId::DefendGate.applies(&rs));
+        rs.life = 2;
+        assert!(HintId::DefendGate.applies(&rs));
+        rs.currencies.gold -= 1;
+        assert!(!HintId::DefendGate.applies(&rs));
+    }
+
+    #[test]
+    fn upgrade_hint_tracks_the_cheapest_upgrade() {
+        let mut rs = quiet_hint_run();
+        apply_upgrades_to_run(&mut rs, &UpgradeState::default());
+        let cheapest = rs.cheapest_upgrade_cost.unwrap();
+        rs.currencies.research = cheapest - 1;
+        assert!(!HintId::AffordUpgrade.applies(&rs));
+        rs.currencies.research = cheapest;
+        assert!(HintId::AffordUpgrade.applies(&rs));
+        rs.progression_run = Some(1);
+        assert!(!HintId::AffordUpgrade.applies(&rs));
+    }
+
+    #[test]
+    fn hints_follow_priority_and_never_repeat() {
+        let mut rs = quiet_hint_run();
+        rs.currencies.gold = rs.tower_cost;
+        rs.cheapest_upgrade_cost = Some(1);
+        rs.currencies.research = 1;
+        assert_eq!(next_hint(&rs), Some(HintId::PlaceTower));
+        rs.hints_shown.push(HintId::PlaceTower);
+        assert_eq!(next_hint(&rs), Some(HintId::AffordUpgrade));
+        rs.hints_shown.push(HintId::AffordUpgrade);
+        assert_eq!(next_hint(&rs), None);
+    }
+
+    #[test]
+    fn hints_surface_on_the_ten_second_cadence() {
+        let mut rs = quiet_hint_run();
+        rs.currencies.gold = rs.tower_cost;
+        let rc = tick_seconds(Rc::new(rs), HINT_INTERVAL_SECS as u32 - 1);
+        assert_eq!(rc.hint, None);
+        let rc = tick_seconds(rc, 1);
+        assert_eq!(rc.hint, Some(HintId::PlaceTower));
+        // Dismissed hints stay gone for the rest of the run
+        let rc = rc.reduce(RunAction::DismissHint);
+        let rc = tick_seconds(rc, HINT_INTERVAL_SECS as u32);
+        assert_eq!(rc.hint, None);
+        assert_eq!(rc.hints_shown, vec![HintId::PlaceTower]);
+    }
+
+    #[test]
+    fn stale_hints_clear_and_late_runs_get_none() {
+        let mut rs = quiet_hint_run();
+        rs.currencies.gold = rs.tower_cost;
+        let rc = tick_seconds(Rc::new(rs.clone()), HINT_INTERVAL_SECS as u32);
+        let mut spent = (*rc).clone();
+        spent.currencies.gold = 0;
+        let rc = tick_seconds(Rc::new(spent), HINT_INTERVAL_SECS as u32);
+        assert_eq!(rc.hint, None);
+
+        rs.progression_run = Some(HINT_LAST_RUN + 1);
+        assert!(!hints_active(&rs));
+        rs.progression_run = None;
+        assert!(!hints_active(&rs));
+        rs.progression_run = Some(HINT_LAST_RUN);
+        assert!(hints_active(&rs));
+        let off = Rc::new(rs).reduce(RunAction::SetHints { on: false });
+        assert!(!hints_active(&off));
+    }
+
+    fn stamp_run(credits: u64) -> Rc<RunState> {
+        let mut rs = run_from_rows(RING);
+        rs.currencies.tile_credits = credits;
+        Rc::new(rs)
+    }
+
+    #[test]
+    fn stamp_rotations_turn_clockwise_and_cycle() {
+        assert_eq!(rotate_offset(1, 0, 1), (0, 1));
+        assert_eq!(rotate_offset(1, 0, 2), (-1, 0));
+        assert_eq!(rotate_offset(1, 0, 3), (0, -1));
+        assert_eq!(rotate_offset(2, -1, 4), (2, -1));
+        let flat = stamp_cells(StampId::Zigzag, (4, 4), 0);
+        let flipped = stamp_cells(StampId::Zigzag, (4, 4), 2);
+        for (((ax, ay), _), ((bx, by), _)) in flat.iter().zip(&flipped) {
+            assert_eq!((ax - 4, ay - 4), (4 - bx, 4 - by));
+        }
+    }
+
+    #[test]
+    fn stamp_costs_one_credit_per_converted_tile() {
+        // Zigzag at (1,0): four rocks to mine, (3,2) and (4,2) are already open
+        let rc = stamp_run(10);
+        assert_eq!(stamp_check(&rc, (1, 0), StampId::Zigzag, 0), Ok(4));
+        let after = rc.clone().reduce(RunAction::ApplyStamp {
+            origin: (1, 0),
+            stamp_id: StampId::Zigzag,
+            rotation: 0,
+        });
+        // Each rock is mined as by hand, so it pays its credit back
+        assert_eq!(after.currencies.tile_credits, 10 - 4 + 4);
+        assert_eq!(after.stats.blocks_mined, rc.stats.blocks_mined + 4);
+        for (x, y) in [(1, 0), (2, 0), (2, 1), (3, 1)] {
+            assert_eq!(after.tiles[y * 9 + x].kind, TileKind::Empty);
+        }
+        assert_eq!(
+            stamp_check(&stamp_run(3), (1, 0), StampId::Zigzag, 0)
+                .unwrap_err()
+                .reason,
+            "Need 4 tile credits"
+        );
+    }
+
+    #[test]
+    fn stamp_is_rejected_whole_when_one_tile_fails() {
+        // Zigzag at (1,1) ends on the indestructible tile at (4,3)
+        let rc = stamp_run(10);
+        let err = stamp_check(&rc, (1, 1), StampId::Zigzag, 0).unwrap_err();
+        assert_eq!(err.tile, Some((4, 3)));
+        let after = rc.clone().reduce(RunAction::ApplyStamp {
+            origin: (1, 1),
+            stamp_id: StampId::Zigzag,
+            rotation: 0,
+        });
+        assert_eq!(after.tiles, rc.tiles);
+        assert_eq!(after.currencies.tile_credits, 10);
+        // Off the map counts as a failing tile too
+        let err = stamp_check(&rc, (0, 0), StampId::Pocket, 0).unwrap_err();
+        assert_eq!(err.tile, Some((-1, -1)));
+    }
+
+    #[test]
+    fn stamp_never_cuts_the_path() {
+        // Platform at (6,3): (6,4) is the only way out of the entrance
+        let rc = stamp_run(10);
+        let err = stamp_check(&rc, (6, 3), StampId::Platform, 0).unwrap_err();
+        assert_eq!(err.tile, Some((6, 4)));
+        assert_eq!(err.reason, "Would block the path");
+        // The same platform one row up leaves the loop intact
+        let rc = rc.reduce(RunAction::ApplyStamp {
+            origin: (6, 2),
+            stamp_id: StampId::Platform,
+            rotation: 0,
+        });
+        assert!(matches!(rc.tiles[2 * 9 + 6].kind, TileKind::Rock { .. }));
+        assert!(!rc.path.is_empty());
+        assert_eq!(rc.currencies.tile_credits, 8);
+    }
+
+    #[test]
+    fn trickle_rate_comes_from_the_best_long_run() {
+        let mut r = MetaRecords::default();
+        let run = |secs, research| RunStats {
+            time_survived_secs: secs,
+            research_earned: research,
+            ..Default::default()
+        };
+        r.update_from_stats(&run(120, 8));
+        assert!((trickle_rate_per_min(&r) - 0.4).abs() < 1e-9);
+        // A worse run keeps the best rate; a short burst doesn't count at all
+        r.update_from_stats(&run(600, 10));
+        r.update_from_stats(&run(TRICKLE_MIN_RUN_SECS - 1, 500));
+        assert!((r.best_research_per_min - 4.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn research_earned_tracks_kill_income() {
+        let mut rs = run_from_rows(RING);
+        add_research(&mut rs, 3.0);
+        add_research(&mut rs, 2.0);
+        assert_eq!(rs.stats.research_earned, 5);
+    }
 
-                        // Apply burn damage
-                        if burn_damage > 0 && e.hp > 0 {
-                            e.hp = e.hp.saturating_sub(burn_damage);
-                            // Show damage number for burn
-                            new.damage_numbers.push(DamageNumber {
-                                x: e.x,
-                                y: e.y,
-                                amount: burn_damage,
-                                ttl: 0.6,
-                                is_crit: false,
-                                is_gold: false,
-                                is_heal: false,
-                            });
-                        }
+    #[test]
+    fn trickle_pays_whole_points_and_keeps_the_fraction() {
+        let mut t = ResearchTrickle::default();
+        // 0.4 per minute: nothing for two minutes, then the first point
+        assert_eq!(t.advance(0.4, 120.0, false), 0);
+        assert!((t.carry - 0.8).abs() < 1e-9);
+        assert_eq!(t.advance(0.4, 30.0, false), 1);
+        assert!(t.carry.abs() < 1e-9);
+        assert_eq!(t.advance(0.0, 60.0, false), 0);
+    }
 
-                        // Apply movement with slow multiplier
-                        e.loop_dist += e.speed_tps * dt * speed_mult;
-                        if e.loop_dist >= total {
-                            e.loop_dist %= total;
-                            if new.life > 0 {
-                                new.life = new.life.saturating_sub(1);
-                                if new.life == 0 {
-                                    new.game_over = true;
-                                }
-                            }
-                            if new.stats.loops_completed < u32::MAX {
-                                new.stats.loops_completed += 1;
-                            }
-                        }
-                        let (nx, ny, dx, dy, next_i) =
-                            sample_pos(&new.path_loop, &new.loop_cum_lengths, total, e.loop_dist);
-                        e.x = nx;
-                        e.y = ny;
-                        e.dir_dx = dx;
-                        e.dir_dy = dy;
-                        e.path_index = next_i;
-                    }
-                }
-            }
-            PlaceWall { x, y } => {
-                let gs = new.grid_size;
-                if x < gs.width && y < gs.height {
-                    let idx = (y * gs.width + x) as usize;
-                    if matches!(new.tiles[idx].kind, TileKind::Empty) {
-                        let old = new.tiles[idx].kind.clone();
-                        new.tiles[idx].kind = TileKind::Wall;
-                        if compute_path(&new).is_empty() {
-                            new.tiles[idx].kind = old;
-                        } else {
-                            new.path = compute_path(&new);
-                            new.path_loop = build_loop_path(&new);
-                            update_loop_geometry(&mut new);
-                        }
-                    }
-                }
-            }
-            PlaceTower { x, y, kind } => {
-                let gs = new.grid_size;
-                if x < gs.width && y < gs.height && new.currencies.gold >= new.tower_cost {
-                    let idx = (y * gs.width + x) as usize;
-                    if matches!(new.tiles[idx].kind, TileKind::Rock { .. } | TileKind::Wall)
-                        && !new.towers.iter().any(|t| t.x == x && t.y == y)
-                    {
-                        new.currencies.gold -= new.tower_cost;
-                        // Extract boost from tile if present
-                        let boost = match &new.tiles[idx].kind {
-                            TileKind::Rock { boost, .. } => *boost,
-                            _ => None,
-                        };
-                        let mut tower = Tower::new(
-                            x,
-                            y,
-                            kind,
-                            new.tower_base_range,
-                            new.tower_base_damage,
-                            boost,
-                        );
-                        // Set debuff based on boost type using pre-calculated templates
-                        tower.apply_debuff = match boost {
-                            Some(BoostKind::Slow) => new.cold_debuff_template.clone(),
-                            Some(BoostKind::Damage) => new.poison_debuff_template.clone(),
-                            Some(BoostKind::Fire) => new.fire_debuff_template.clone(),
-                            _ => None,
-                        };
-                        new.towers.push(tower);
-                    }
-                }
-            }
-            RemoveTower { x, y } => {
-                if let Some(p) = new.towers.iter().position(|t| t.x == x && t.y == y) {
-                    new.towers.remove(p);
-                    let refund = (new.tower_cost as f64 * new.tower_refund_mult).round() as u64;
-                    new.currencies.gold = new.currencies.gold.saturating_add(refund);
-                }
-            }
-            SpendResearch { amount } => {
-                if new.currencies.research >= amount {
-                    new.currencies.research -= amount;
-                }
+    #[test]
+    fn trickle_stops_at_the_session_cap() {
+        let mut t = ResearchTrickle::default();
+        let paid: u64 = (0..40).map(|_| t.advance(1.0, 60.0, false)).sum();
+        assert_eq!(paid, 30);
+        assert!(t.capped());
+        assert_eq!(t.advance(1.0, 600.0, false), 0);
+    }
+
+    #[test]
+    fn trickle_pauses_while_a_run_is_live() {
+        let mut t = ResearchTrickle::default();
+        let mut rs = run_from_rows(RING);
+        assert!(!run_is_live(&rs));
+        rs.started = true;
+        assert!(run_is_live(&rs));
+        assert_eq!(t.advance(60.0, 60.0, run_is_live(&rs)), 0);
+        assert_eq!(t.session_secs, 0.0);
+        rs.game_over = true;
+        assert_eq!(t.advance(60.0, 60.0, run_is_live(&rs)), 60);
+    }
+
+    // Ring Road with six towers along the top road, stepped a tenth of a second at a time.
+    // Crits and freezes are off, so the run is fully deterministic.
+    fn play_ring_road(
+        mut rc: Rc<RunState>,
+        secs: u32,
+        mut each: impl FnMut(&RunState),
+    ) -> Rc<RunState> {
+        for _ in 0..secs {
+            for _ in 0..10 {
+                rc = rc.reduce(RunAction::SimTick { dt: 0.1 });
+                each(&rc);
             }
-            ApplyUpgrades { ups } => {
-                apply_upgrades_to_run(&mut new, &ups);
+            rc = rc.reduce(RunAction::TickSecond);
+        }
+        rc
+    }
+
+    fn towered_ring_road() -> Rc<RunState> {
+        let mut rc = scenario_run(ScenarioId::RingRoad);
+        for (x, kind) in [
+            (2, TowerKind::Basic),
+            (3, TowerKind::Basic),
+            (4, TowerKind::Basic),
+            (8, TowerKind::Damage),
+            (9, TowerKind::Damage),
+            (10, TowerKind::Slow),
+        ] {
+            // Each tower paid for exactly, so the run keeps no gold of its own
+            let mut rs = (*rc).clone();
+            rs.currencies.gold = rs.current_tower_cost(&kind);
+            rs.currencies.tile_credits = TOWER_TILE_CREDITS;
+            rc = Rc::new(rs).reduce(RunAction::PlaceTower { x, y: 2, kind });
+        }
+        rc
+    }
+
+    #[test]
+    fn scripted_ring_road_plays_out_as_recorded() {
+        let rc = play_ring_road(towered_ring_road(), 60, |_| {});
+        let dealt: Vec<u64> = rc.towers.iter().map(|t| t.damage_dealt).collect();
+        let hp: u32 = rc.enemies.iter().map(|e| e.hp).sum();
+        assert_eq!(rc.stats.time_survived_secs, 60);
+        assert_eq!(
+            (rc.stats.enemies_spawned, rc.stats.enemies_killed),
+            (23, 18)
+        );
+        assert_eq!((rc.life, rc.currencies.gold), (10, 0));
+        assert_eq!(dealt, [10, 8, 11, 68, 62, 8]);
+        assert_eq!((rc.enemies.len(), rc.projectiles.len(), hp), (5, 0, 47));
+    }
+
+    #[test]
+    fn entity_ids_are_never_reused_within_a_run() {
+        // (run_id, id) -> which list it was first seen in
+        let mut seen: HashMap<(u64, EntityId), &str> = HashMap::new();
+        let mut record = |rs: &RunState| {
+            let ids = rs
+                .enemies
+                .iter()
+                .map(|e| (e.id, "enemy"))
+                .chain(rs.towers.iter().map(|t| (t.id, "tower")))
+                .chain(rs.projectiles.iter().map(|p| (p.id, "projectile")));
+            let mut now = HashSet::new();
+            for (id, list) in ids {
+                assert_ne!(id, EntityId::default(), "unassigned {list}");
+                assert!(now.insert(id), "{id:?} held twice");
+                assert_eq!(*seen.entry((rs.run_id, id)).or_insert(list), list);
             }
-            SetResearch { amount } => {
-                new.currencies.research = amount;
+        };
+        let first = play_ring_road(towered_ring_road(), 40, &mut record);
+        // A new run restarts the counter; run_id keeps the two apart
+        let rs = first.reduce(RunAction::StartScenario {
+            id: ScenarioId::RingRoad,
+        });
+        assert_eq!(rs.entity_ids, EntityIds::default());
+        let mut rs = (*rs.reduce(RunAction::StartRun)).clone();
+        rs.currencies.gold = 17;
+        let rc = Rc::new(rs).reduce(RunAction::PlaceTower {
+            x: 2,
+            y: 2,
+            kind: TowerKind::Basic,
+        });
+        assert_eq!(rc.towers[0].id, EntityId(1));
+        play_ring_road(rc, 40, &mut record);
+        assert!(seen.len() > 100);
+        assert_eq!(seen.keys().map(|k| k.0).collect::<HashSet<_>>().len(), 2);
+    }
+
+    #[test]
+    fn towers_keep_their_id_when_moved_or_swapped() {
+        let rc = towered_ring_road();
+        let id = rc.tower_at(2, 2).unwrap().id;
+        let moved = rc.reduce(RunAction::MoveTower {
+            from: (2, 2),
+            to: (5, 2),
+        });
+        assert!(moved.tower_at(2, 2).is_none());
+        assert_eq!(moved.tower_at(5, 2).unwrap().id, id);
+        let mut rs = (*moved).clone();
+        rs.currencies.gold = 10;
+        let swapped = Rc::new(rs).reduce(RunAction::ReplaceTowerKind {
+            x: 5,
+            y: 2,
+            kind: TowerKind::Damage,
+        });
+        let tw = swapped.towers.iter().find(|t| t.id == id).unwrap();
+        assert_eq!((tw.x, tw.y, tw.kind.clone()), (5, 2, TowerKind::Damage));
+    }
+
+    #[test]
+    fn can_move_tower_agrees_with_the_reducer() {
+        let rc = towered_ring_road();
+        let gs = rc.grid_size;
+        let mut outcomes = HashSet::new();
+        for y in 0..gs.height {
+            for x in 0..gs.width {
+                let moved = rc.clone().reduce(RunAction::MoveTower {
+                    from: (2, 2),
+                    to: (x, y),
+                });
+                let ok = can_move_tower(&rc, (2, 2), (x, y));
+                assert_eq!(moved.tower_at(2, 2).is_none(), ok, "({x}, {y})");
+                outcomes.insert(ok);
             }
-            ResetRun | ResetRunWithUpgrades { .. } => unreachable!(),
         }
-        new.version = new.version.wrapping_add(1);
-        Rc::new(new)
+        assert_eq!(outcomes.len(), 2);
+        // Nothing to move
+        assert!(!can_move_tower(&rc, (5, 2), (6, 2)));
     }
-}
 
-// --- Tests ---
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use yew::Reducible;
-    fn make_run() -> RunState {
-        RunState::new_basic(GridSize {
-            width: 25,
-            height: 25,
-        })
+    #[test]
+    fn moves_follow_the_placement_rules() {
+        let mut rs = run_from_rows(RING);
+        rs.currencies.gold = 100;
+        rs.currencies.tile_credits = 5;
+        rs.currencies.research = SCARECROW_RESEARCH_COST;
+        let placed = Rc::new(rs).reduce(RunAction::PlaceTower {
+            x: 1,
+            y: 3,
+            kind: TowerKind::Basic,
+        });
+        // Checked against the reducer so the panel never offers a move that won't happen
+        let moves = |rs: &RunState, to: (u32, u32)| {
+            let ok = can_move_tower(rs, (1, 3), to);
+            let moved = Rc::new(rs.clone()).reduce(RunAction::MoveTower { from: (1, 3), to });
+            assert_eq!(moved.tower_at(1, 3).is_none(), ok, "{to:?}");
+            ok
+        };
+        assert!(moves(&placed, (1, 2)));
+        // Buried rock with no open neighbour
+        assert!(!moves(&placed, (0, 0)));
+        assert!(!moves(&scarecrow_at(&placed, 1, 2), (1, 2)));
+        let mut hot = (*placed).clone();
+        hot.tiles[2 * 9 + 2].magma = true;
+        assert!(!moves(&hot, (1, 2)));
+        let mut over = (*placed).clone();
+        over.game_over = true;
+        assert!(!moves(&over, (1, 2)));
+        // Moving costs nothing, so an empty purse doesn't stop it
+        let mut broke = (*placed).clone();
+        broke.currencies.gold = 0;
+        broke.currencies.tile_credits = 0;
+        assert!(moves(&broke, (1, 2)));
     }
 
     #[test]
-    fn start_and_indestructibles_exist() {
-        let rs = make_run();
-        let mut start_idx = None;
-        for (i, t) in rs.tiles.iter().enumerate() {
-            if matches!(t.kind, TileKind::Start) {
-                start_idx = Some(i);
-                break;
+    fn shots_credit_their_tower_after_it_moves() {
+        let mut rs = veteran_run(0);
+        rs.towers[0].id = EntityId(7);
+        rs.towers[0].cooldown_remaining = 10.0;
+        rs.projectiles.push(Projectile {
+            source: Some(EntityId(7)),
+            ..stray(4.5, 4.5, 0.0, 0.05)
+        });
+        let rc = Rc::new(rs)
+            .reduce(RunAction::MoveTower {
+                from: (3, 3),
+                to: (3, 5),
+            })
+            .reduce(RunAction::SimTick { dt: 0.1 });
+        assert!(rc.projectiles.is_empty());
+        let tw = rc.towers.iter().find(|t| t.id == EntityId(7)).unwrap();
+        assert_eq!((tw.x, tw.y, tw.damage_dealt), (3, 5, 5));
+    }
+
+    // veteran_run's tower (id 7, cooling down) with a 5-damage shot about to land on the enemy
+    fn history_run(enemy_hp: u32) -> RunState {
+        let mut rs = veteran_run(0);
+        rs.towers[0].id = EntityId(7);
+        rs.towers[0].cooldown_remaining = 10.0;
+        rs.enemies[0].hp = enemy_hp;
+        rs.projectiles.push(Projectile {
+            source: Some(EntityId(7)),
+            ..stray(4.5, 4.5, 0.0, 0.05)
+        });
+        rs
+    }
+
+    #[test]
+    fn landed_damage_trickles_research() {
+        // Ten 5-damage hits make exactly one point
+        let mut rs = history_run(10_000);
+        rs.projectiles.clear();
+        for _ in 0..10 {
+            assert_eq!(rs.currencies.research, 0);
+            rs.projectiles.push(stray(4.5, 4.5, 0.0, 0.05));
+            advance_projectiles(&mut rs, 0.1);
+        }
+        assert_eq!(rs.currencies.research, 1);
+        assert_eq!(rs.research_progress, 0.0);
+
+        // Overkill on a 1-hp enemy counts the 1 damage it took
+        let mut rs = history_run(1);
+        advance_projectiles(&mut rs, 0.1);
+        assert_eq!(rs.research_progress, 1.0 / DAMAGE_PER_RESEARCH);
+        let back: RunState = serde_json::from_str(&serde_json::to_string(&rs).unwrap()).unwrap();
+        assert_eq!(back.research_progress, rs.research_progress);
+    }
+
+    #[test]
+    fn moved_tower_leaves_its_tally_on_the_old_tile() {
+        let rc = Rc::new(history_run(10_000)).reduce(RunAction::SimTick { dt: 0.1 });
+        let w = rc.grid_size.width;
+        let hit = TowerTally {
+            kills: 0,
+            damage: 5,
+        };
+        assert_eq!(rc.towers[0].here, hit);
+        assert!(rc.tower_history.is_empty());
+        assert_eq!(placement_history(&rc), BTreeMap::from([(3 * w + 3, hit)]));
+
+        let mut rs = (*rc.reduce(RunAction::MoveTower {
+            from: (3, 3),
+            to: (3, 5),
+        }))
+        .clone();
+        assert_eq!(rs.tower_history, BTreeMap::from([(3 * w + 3, hit)]));
+        assert_eq!(rs.towers[0].here, TowerTally::default());
+        assert_eq!(rs.towers[0].damage_dealt, 5);
+
+        // Shots after the move count for the new tile only
+        rs.projectiles.push(Projectile {
+            source: Some(EntityId(7)),
+            ..stray(4.5, 4.5, 0.0, 0.05)
+        });
+        let rc = Rc::new(rs).reduce(RunAction::SimTick { dt: 0.1 });
+        assert_eq!(
+            placement_history(&rc),
+            BTreeMap::from([(3 * w + 3, hit), (5 * w + 3, hit)])
+        );
+    }
+
+    #[test]
+    fn sold_tower_folds_kills_and_damage_into_its_tile() {
+        let rc = Rc::new(history_run(5)).reduce(RunAction::SimTick { dt: 0.1 });
+        let w = rc.grid_size.width;
+        let tally = TowerTally {
+            kills: 1,
+            damage: 5,
+        };
+        assert_eq!(rc.towers[0].here, tally);
+        let mut rs = (*rc.reduce(RunAction::SellTower { x: 3, y: 3 })).clone();
+        assert!(rs.towers.is_empty());
+        assert_eq!(rs.tower_history, BTreeMap::from([(3 * w + 3, tally)]));
+
+        // A new tower on the same tile adds to the history rather than replacing it
+        rs.currencies.gold = 100;
+        let placed = Rc::new(rs).reduce(RunAction::PlaceTower {
+            x: 3,
+            y: 3,
+            kind: TowerKind::Basic,
+        });
+        let mut rs = (*placed).clone();
+        rs.towers[0].here = tally;
+        let sold = Rc::new(rs).reduce(RunAction::SellTower { x: 3, y: 3 });
+        assert_eq!(
+            sold.tower_history[&(3 * w + 3)],
+            TowerTally {
+                kills: 2,
+                damage: 10
             }
+        );
+    }
+
+    #[test]
+    fn tower_kill_count_follows_it_across_moves_and_swaps() {
+        let rc = Rc::new(history_run(5)).reduce(RunAction::SimTick { dt: 0.1 });
+        assert_eq!(rc.towers[0].kills, 1);
+        let moved = rc.reduce(RunAction::MoveTower {
+            from: (3, 3),
+            to: (3, 5),
+        });
+        let tw = moved.towers.iter().find(|t| t.id == EntityId(7)).unwrap();
+        assert_eq!((tw.kills, tw.here), (1, TowerTally::default()));
+        assert_eq!(swapped_tower(&moved, tw, TowerKind::Slow).kills, 1);
+    }
+
+    // A rock beside the current path that isn't beside any earlier pick
+    // Mining it has the path searched again: not a dead end, or a search is already running
+    fn rock_by_path(rs: &RunState, skip: &[usize]) -> usize {
+        let w = rs.grid_size.width;
+        rs.path
+            .iter()
+            .flat_map(|p| [(p.x + 1, p.y), (p.x, p.y + 1)])
+            .filter(|&(x, y)| x < w && y < rs.grid_size.height)
+            .map(|(x, y)| (y * w + x) as usize)
+            .find(|i| {
+                matches!(rs.tiles[*i].kind, TileKind::Rock { .. })
+                    && !skip.contains(i)
+                    && !opens_dead_end(rs, *i)
+            })
+            .expect("rock next to the path")
+    }
+
+    #[test]
+    fn large_board_searches_a_mined_path_over_ticks() {
+        let gs = GridSize {
+            width: 200,
+            height: 200,
+        };
+        let rs = Rc::new(RunState::new_with_upgrades_seeded(
+            gs,
+            &UpgradeState::default(),
+            5,
+        ));
+        assert!(!rs.path.is_empty() && !rs.path_recomputing());
+        let first = rock_by_path(&rs, &[]);
+        let mined = rs.clone().reduce(RunAction::MiningComplete { idx: first });
+        assert!(mined.path_recomputing());
+        assert!(
+            Shared::ptr_eq(&mined.path, &rs.path),
+            "old path stays in use"
+        );
+
+        // A second tile mid-search restarts it on the newer board
+        let second = rock_by_path(&mined, &[first]);
+        let again = mined
+            .clone()
+            .reduce(RunAction::MiningComplete { idx: second });
+        assert!(again.path_job.is_some() && again.path_job != mined.path_job);
+        let expected = compute_path(&again);
+
+        let mut rc = again;
+        let mut ticks = 0;
+        while rc.path_recomputing() {
+            rc = rc.reduce(RunAction::SimTick { dt: 0.016 });
+            ticks += 1;
+            assert!(ticks < 1000);
         }
-        assert!(start_idx.is_some(), "Start tile missing");
-        let idx = start_idx.unwrap();
-        let sx = (idx as u32) % rs.grid_size.width;
-        let sy = (idx as u32) / rs.grid_size.width;
-        // At least one orthogonal indestructible
-        let mut found = false;
-        for (dx, dy) in [(1i32, 0), (-1, 0), (0, 1), (0, -1)] {
-            let x = sx as i32 + dx;
-            let y = sy as i32 + dy;
-            if x >= 0
-                && y >= 0
-                && (x as u32) < rs.grid_size.width
-                && (y as u32) < rs.grid_size.height
-            {
-                let i2 = (y as u32 * rs.grid_size.width + x as u32) as usize;
-                if matches!(rs.tiles[i2].kind, TileKind::Indestructible) {
-                    found = true;
-                    break;
-                }
+        assert_eq!(*rc.path, expected);
+        assert!(!rc.path_loop.is_empty());
+
+        // A synchronous path change drops the pending search
+        let pending = rs.clone().reduce(RunAction::MiningComplete { idx: first });
+        let w = gs.width;
+        let empty = (0..pending.tiles.len())
+            .find(|&i| {
+                pending.tiles[i].kind == TileKind::Empty
+                    && !pending.path.iter().any(|p| (p.y * w + p.x) as usize == i)
+            })
+            .unwrap() as u32;
+        let walled = pending.reduce(RunAction::PlaceWall {
+            x: empty % w,
+            y: empty / w,
+        });
+        assert_eq!(walled.tiles[empty as usize].kind, TileKind::Wall);
+        assert!(!walled.path_recomputing());
+    }
+
+    // Opens every rock tile so the route can reach whatever the map buried
+    fn dug_out(mut rs: RunState) -> RunState {
+        for t in rs.tiles.iter_mut() {
+            if matches!(t.kind, TileKind::Rock { .. }) {
+                *t = Tile {
+                    kind: TileKind::Empty,
+                    hardness: EMPTY_HARDNESS,
+                    magma: false,
+                };
             }
         }
-        assert!(found, "No indestructible neighbor to start");
-        assert!(!rs.path.is_empty(), "Initial path empty");
-        assert!(rs.path_loop.len() >= 2, "Loop path too short");
+        let path = compute_path(&rs);
+        install_path(&mut rs, path);
+        rs
+    }
+
+    fn route_run(mode: RouteMode, seed: u64) -> RunState {
+        let gs = GridSize {
+            width: 24,
+            height: 24,
+        };
+        RunState::new_with_route(gs, &UpgradeState::default(), seed, mode)
     }
 
     #[test]
-    fn enemy_spawns_after_time() {
-        let mut rs = make_run();
-        rs.started = true; // simulate StartRun
-        rs.stats.time_survived_secs = 10; // large enough to exceed spawn interval
+    fn mid_run_restart_keeps_the_route_and_upgrades() {
+        let ups = strong_ups();
+        let mut rs = route_run(RouteMode::End, 3);
+        apply_upgrades_to_run(&mut rs, &ups);
+        rs.started = true;
         let rc = Rc::new(rs);
-        let after = rc.reduce(super::RunAction::SimTick { dt: 0.016 });
-        assert!(after.enemies.len() >= 1, "Enemy did not spawn");
+        let after = rc.clone().reduce(RunAction::ResetRunWithSeed {
+            ups: ups.clone(),
+            seed: 5,
+        });
+        assert_eq!(after.run_id, rc.run_id + 1);
+        assert_eq!(after.route_mode, RouteMode::End);
+        assert!(find_end(&after).is_some());
+        assert!(!after.started);
+        assert!(invariant_violations(&after, &ups).is_empty());
+        let size = play_area_size_for_level(ups.level(UpgradeId::PlayAreaSize));
+        assert_eq!(after.grid_size.width, size);
     }
 
     #[test]
-    fn starting_gold_applied_only_once() {
-        // Prepare upgrades with StartingGold level 3
-        let mut ups = UpgradeState::default();
-        ups.levels.insert(UpgradeId::StartingGold.key().into(), 3);
-        let rs = RunState::new_with_upgrades(
-            GridSize {
-                width: 10,
-                height: 10,
-            },
-            &ups,
-        );
-        // Base gold is 2, each level +2 => +6 => 8 total
-        assert_eq!(
-            rs.currencies.gold, 8,
-            "Starting gold not applied correctly on new run"
-        );
+    fn end_route_buries_an_end_away_from_start() {
+        for seed in 0..20 {
+            let end = route_run(RouteMode::End, seed);
+            let looped = route_run(RouteMode::Loop, seed);
+            assert_eq!(end.route_mode, RouteMode::End);
+            assert!(find_end(&looped).is_none());
+            let at = find_end(&end).unwrap();
+            assert!(at.x.abs_diff(12) + at.y.abs_diff(12) >= END_MIN_DISTANCE as u32);
+            // The same map around it, and nothing open beside it yet, so the run loops until
+            // it is mined through to
+            let i = (at.y * 24 + at.x) as usize;
+            let differ: Vec<_> = (0..end.tiles.len())
+                .filter(|&j| end.tiles[j] != looped.tiles[j])
+                .collect();
+            assert_eq!(differ, vec![i]);
+            assert!(!route_is_open(&end));
+            assert_eq!(end.path_loop, looped.path_loop);
+            assert!(crate::state::invariants::check(&end).is_empty());
+        }
+    }
+
+    #[test]
+    fn end_route_is_open_and_loop_route_closes() {
+        let open = dug_out(route_run(RouteMode::End, 3));
+        let end = find_end(&open).unwrap();
+        assert!(route_is_open(&open));
+        let first = open.path_loop[0];
         assert_eq!(
-            rs.starting_gold_applied_level, 3,
-            "Applied level tracker incorrect"
+            open.tiles[(first.y * 24 + first.x) as usize].kind,
+            TileKind::Start
         );
-        // Simulate mid-run (started) and re-apply upgrades -- should not change gold
-        let mut rs2 = rs.clone();
-        let before = rs2.currencies.gold;
-        rs2.started = true;
-        rs2.stats.time_survived_secs = 5;
-        apply_upgrades_to_run(&mut rs2, &ups);
+        assert_eq!(open.path_loop.last(), Some(&end));
+        // No closing segment, and projection stops at the End instead of wrapping to Start
         assert_eq!(
-            rs2.currencies.gold, before,
-            "Gold changed mid-run when it should not"
+            open.loop_total_length,
+            *open.loop_cum_lengths.last().unwrap()
         );
+        let (ex, ey) = (end.x as f64 + 0.5, end.y as f64 + 0.5);
+        let total = open.loop_total_length;
+        let d = project_onto_loop(&open.path_loop, &open.loop_cum_lengths, total, true, ex, ey);
+        assert!((d - total).abs() < 1e-9);
+
+        let closed = dug_out(route_run(RouteMode::Loop, 3));
+        assert!(!route_is_open(&closed));
+        assert!(closed.loop_total_length > *closed.loop_cum_lengths.last().unwrap());
+    }
+
+    fn walker(loop_dist: f64) -> Enemy {
+        Enemy {
+            id: EntityId::default(),
+            x: 0.0,
+            y: 0.0,
+            speed_tps: 1.0,
+            hp: 50,
+            max_hp: 50,
+            spawned_at: 0,
+            path_index: 0,
+            dir_dx: 0.0,
+            dir_dy: 0.0,
+            radius_scale: 1.0,
+            loop_dist,
+            debuffs: Vec::new(),
+            kind: EnemyKind::Normal,
+            hit_flash_ttl: 0.0,
+            spawn_anim_ttl: 0.0,
+        }
     }
 
     #[test]
-    fn starting_gold_applies_difference_incrementally_pre_run() {
-        let mut ups = UpgradeState::default();
-        let mut rs = RunState::new_basic(GridSize {
-            width: 10,
-            height: 10,
-        });
-        // level 0 -> 1
-        ups.levels.insert(UpgradeId::StartingGold.key().into(), 1);
-        apply_upgrades_to_run(&mut rs, &ups);
+    fn end_route_enemies_breach_and_loop_route_enemies_lap() {
+        let step = |mode: RouteMode| {
+            let mut rs = dug_out(route_run(mode, 5));
+            rs.started = true;
+            rs.enemies = vec![walker(short_of_lap(&rs, 0.01)), walker(0.0)];
+            rs.stats.enemies_spawned = 2;
+            let life = rs.life;
+            let rc = Rc::new(rs).reduce(RunAction::SimTick { dt: 0.05 });
+            assert_eq!(rc.life, life - 1);
+            assert_eq!(rc.stats.enemies_killed, 0);
+            rc
+        };
+        let breached = step(RouteMode::End);
         assert_eq!(
-            rs.currencies.gold,
-            2 + 2 * 1,
-            "Level 1 starting gold incorrect"
+            (breached.stats.breaches, breached.stats.loops_completed),
+            (1, 0)
         );
-        assert_eq!(rs.starting_gold_applied_level, 1);
-        // level 1 -> 3 adds only +4 more (delta levels =2)
-        ups.levels.insert(UpgradeId::StartingGold.key().into(), 3);
-        apply_upgrades_to_run(&mut rs, &ups);
+        assert_eq!(breached.enemies.len(), 1);
+        let lapped = step(RouteMode::Loop);
         assert_eq!(
-            rs.currencies.gold,
-            2 + 2 * 3,
-            "Incremental starting gold difference not applied correctly"
+            (lapped.stats.breaches, lapped.stats.loops_completed),
+            (0, 1)
         );
-        assert_eq!(rs.starting_gold_applied_level, 3);
+        assert_eq!(lapped.enemies.len(), 2);
+        assert!(lapped.lap_frame().progress(lapped.enemies[0].loop_dist) < 0.05);
+    }
+
+    #[test]
+    fn route_mode_is_chosen_before_the_run_starts() {
+        let rc = Rc::new(route_run(RouteMode::Loop, 9));
+        let end = rc.clone().reduce(RunAction::SetRouteMode {
+            mode: RouteMode::End,
+        });
+        assert_eq!(end.route_mode, RouteMode::End);
+        assert_eq!(find_end(&end), find_end(&route_run(RouteMode::End, 9)));
+        let back = end.reduce(RunAction::SetRouteMode {
+            mode: RouteMode::Loop,
+        });
+        assert!(find_end(&back).is_none());
+        assert_eq!(back.path_loop, rc.path_loop);
+
+        let mut started = (*rc).clone();
+        started.started = true;
+        let kept = Rc::new(started).reduce(RunAction::SetRouteMode {
+            mode: RouteMode::End,
+        });
+        assert_eq!(kept.route_mode, RouteMode::Loop);
     }
 }
@@ -0,0 +1,442 @@
+// Enemy routing: A* over the open tiles as a search that can be stopped after any number of node
+// expansions and picked up again later. Small boards run it to completion inside the reducer; on
+// large boards a mined tile starts a PathJob that SimTick advances a budget at a time, and the old
+// path stays in use until the job finishes.
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::rc::Rc;
+
+use crate::model::{GridSize, Position};
+
+// Tile coordinates
+type Cell = (u32, u32);
+
+// The board as the search sees it: which tiles are walkable, and which lay on the previous path
+#[derive(Clone, Debug)]
+pub struct SearchGrid {
+    width: u32,
+    height: u32,
+    open: Vec<bool>,
+    on_prev: Option<Vec<bool>>,
+}
+
+impl SearchGrid {
+    // `prev` is the current path. A step off it costs a tiny bit more, so among equally short
+    // routes the one that overlaps it most wins; an empty one means no tie-breaking.
+    pub fn new(gs: GridSize, open: Vec<bool>, prev: &[Position]) -> Self {
+        let on_prev = (!prev.is_empty()).then(|| {
+            let mut on = vec![false; open.len()];
+            for p in prev {
+                if p.x < gs.width && p.y < gs.height {
+                    on[(p.y * gs.width + p.x) as usize] = true;
+                }
+            }
+            on
+        });
+        Self {
+            width: gs.width,
+            height: gs.height,
+            open,
+            on_prev,
+        }
+    }
+
+    fn idx(&self, x: u32, y: u32) -> Option<usize> {
+        (x < self.width && y < self.height).then(|| (y * self.width + x) as usize)
+    }
+
+    fn pos(&self, i: usize) -> Position {
+        Position {
+            x: i as u32 % self.width,
+            y: i as u32 / self.width,
+        }
+    }
+
+    // Tiles of `p` off the previous path
+    fn misses(&self, p: &[usize]) -> usize {
+        self.on_prev
+            .as_ref()
+            .map_or(0, |on| p.iter().filter(|&&i| !on[i]).count())
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+struct Node {
+    f: u64,
+    idx: usize,
+}
+
+impl Ord for Node {
+    fn cmp(&self, o: &Self) -> Ordering {
+        o.f.cmp(&self.f).then_with(|| self.idx.cmp(&o.idx))
+    }
+}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, o: &Self) -> Option<Ordering> {
+        Some(self.cmp(o))
+    }
+}
+
+// One start/goal search. Everything it needs between calls lives here, so `advance` can stop at
+// any expansion and resume with the same result as an uninterrupted run.
+#[derive(Clone, Debug)]
+pub struct AStar {
+    start: usize,
+    goal: usize,
+    // Unit steps scaled so every off-path penalty combined stays below one step
+    step: u64,
+    open: BinaryHeap<Node>,
+    closed: Vec<bool>,
+    g: Vec<u64>,
+    parent: Vec<Option<usize>>,
+    done: bool,
+}
+
+impl AStar {
+    pub fn new(grid: &SearchGrid, start: Cell, goal: Cell) -> Self {
+        let n = grid.open.len();
+        let start = grid.idx(start.0, start.1);
+        let goal = grid.idx(goal.0, goal.1);
+        let mut search = Self {
+            start: start.unwrap_or(0),
+            goal: goal.unwrap_or(0),
+            step: grid.width as u64 * grid.height as u64 + 1,
+            open: BinaryHeap::new(),
+            closed: vec![false; n],
+            g: vec![u64::MAX; n],
+            parent: vec![None; n],
+            done: true,
+        };
+        let ends = start
+            .zip(goal)
+            .filter(|&(s, g)| grid.open[s] && grid.open[g]);
+        if let Some((s, _)) = ends {
+            search.g[s] = 0;
+            search.open.push(Node {
+                f: search.h(grid, s),
+                idx: s,
+            });
+            search.done = false;
+        }
+        search
+    }
+
+    fn h(&self, grid: &SearchGrid, i: usize) -> u64 {
+        let (p, goal) = (grid.pos(i), grid.pos(self.goal));
+        (p.x.abs_diff(goal.x) + p.y.abs_diff(goal.y)) as u64 * self.step
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    // Expands at most `budget` nodes and returns how many it did
+    pub fn advance(&mut self, grid: &SearchGrid, budget: usize) -> usize {
+        let mut expanded = 0;
+        while !self.done && expanded < budget {
+            let Some(Node { idx: ci, .. }) = self.open.pop() else {
+                self.done = true;
+                break;
+            };
+            if ci == self.goal {
+                self.done = true;
+                break;
+            }
+            // Stale entry: a cheaper one was expanded already
+            if std::mem::replace(&mut self.closed[ci], true) {
+                continue;
+            }
+            expanded += 1;
+            let c = grid.pos(ci);
+            let g_here = self.g[ci];
+            for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+                let Some(ni) = grid.idx(c.x.wrapping_add_signed(dx), c.y.wrapping_add_signed(dy))
+                else {
+                    continue;
+                };
+                if !grid.open[ni] {
+                    continue;
+                }
+                let off_prev = grid.on_prev.as_ref().is_some_and(|on| !on[ni]);
+                let tentative = g_here + self.step + u64::from(off_prev);
+                if tentative < self.g[ni] {
+                    self.g[ni] = tentative;
+                    self.parent[ni] = Some(ci);
+                    let f = tentative + self.h(grid, ni);
+                    self.open.push(Node { f, idx: ni });
+                }
+            }
+        }
+        expanded
+    }
+
+    // Tile indices from start to goal once done; empty when the goal can't be reached
+    pub fn path(&self) -> Vec<usize> {
+        if self.g[self.goal] == u64::MAX {
+            return Vec::new();
+        }
+        let mut rev = vec![self.goal];
+        let mut cur = self.goal;
+        while cur != self.start {
+            let Some(p) = self.parent[cur] else {
+                return Vec::new();
+            };
+            rev.push(p);
+            cur = p;
+        }
+        rev.reverse();
+        rev
+    }
+}
+
+// Every start/goal pair searched in turn; the shortest route wins, then the one that strays least
+// from the previous path, then the earlier pair
+#[derive(Clone, Debug)]
+pub struct PathJob {
+    grid: SearchGrid,
+    pairs: Vec<(Cell, Cell)>,
+    next: usize,
+    search: Option<AStar>,
+    best: Option<(Vec<usize>, usize)>,
+    pub expanded: u64,
+}
+
+impl PathJob {
+    pub fn new(grid: SearchGrid, starts: &[Cell], goals: &[Cell]) -> Self {
+        let pairs = starts
+            .iter()
+            .flat_map(|&s| goals.iter().map(move |&g| (s, g)))
+            .collect();
+        Self {
+            grid,
+            pairs,
+            next: 0,
+            search: None,
+            best: None,
+            expanded: 0,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.search.is_none() && self.next == self.pairs.len()
+    }
+
+    // Spends at most `budget` node expansions; true once every pair has been searched
+    pub fn advance(&mut self, budget: usize) -> bool {
+        let mut left = budget;
+        loop {
+            let search = match &mut self.search {
+                Some(s) => s,
+                None if self.next == self.pairs.len() => return true,
+                None => {
+                    let (s, g) = self.pairs[self.next];
+                    self.next += 1;
+                    self.search.insert(AStar::new(&self.grid, s, g))
+                }
+            };
+            let spent = search.advance(&self.grid, left);
+            left -= spent;
+            self.expanded += spent as u64;
+            if !search.is_done() {
+                return false;
+            }
+            let p = search.path();
+            self.search = None;
+            // A start next to its own goal isn't a route
+            if p.len() > 1 {
+                let m = self.grid.misses(&p);
+                if self
+                    .best
+                    .as_ref()
+                    .is_none_or(|(b, bm)| (p.len(), m) < (b.len(), *bm))
+                {
+                    self.best = Some((p, m));
+                }
+            }
+        }
+    }
+
+    // The winning route so far; the final one once `is_done`
+    pub fn result(&self) -> Vec<Position> {
+        self.best
+            .as_ref()
+            .map(|(p, _)| p.iter().map(|&i| self.grid.pos(i)).collect())
+            .unwrap_or_default()
+    }
+
+    // Synchronous: search everything now
+    pub fn run(mut self) -> Vec<Position> {
+        self.advance(usize::MAX);
+        self.result()
+    }
+}
+
+// RunState's handle on a running job. State clones share it, so advancing it each tick doesn't
+// copy the open set; the job reads only the board it started from, so every state holding it
+// agrees on the result.
+#[derive(Clone, Debug)]
+pub struct PendingPath(Rc<RefCell<PathJob>>);
+
+impl PendingPath {
+    pub fn new(job: PathJob) -> Self {
+        Self(Rc::new(RefCell::new(job)))
+    }
+
+    pub fn advance(&self, budget: usize) -> bool {
+        self.0.borrow_mut().advance(budget)
+    }
+
+    pub fn result(&self) -> Vec<Position> {
+        self.0.borrow().result()
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.0.borrow().is_done()
+    }
+}
+
+impl PartialEq for PendingPath {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Tiny xorshift so the grids are the same on every run
+    fn random_grid(seed: u64, w: u32, h: u32, rock_percent: u64) -> SearchGrid {
+        let mut s = seed.max(1);
+        let open = (0..w * h)
+            .map(|_| {
+                s ^= s << 13;
+                s ^= s >> 7;
+                s ^= s << 17;
+                s % 100 >= rock_percent
+            })
+            .collect();
+        SearchGrid::new(
+            GridSize {
+                width: w,
+                height: h,
+            },
+            open,
+            &[],
+        )
+    }
+
+    fn ends(grid: &SearchGrid) -> (Vec<Cell>, Vec<Cell>) {
+        let open = |x: u32, y: u32| grid.open[(y * grid.width + x) as usize];
+        let col = |x: u32| -> Vec<Cell> {
+            (0..grid.height)
+                .map(|y| (x, y))
+                .filter(|&(x, y)| open(x, y))
+                .take(3)
+                .collect()
+        };
+        (col(0), col(grid.width - 1))
+    }
+
+    // Breadth-first distance in steps, as an independent check on optimality
+    fn bfs_len(grid: &SearchGrid, s: (u32, u32), g: (u32, u32)) -> Option<usize> {
+        let (s, g) = (grid.idx(s.0, s.1)?, grid.idx(g.0, g.1)?);
+        let mut dist = vec![usize::MAX; grid.open.len()];
+        let mut queue = std::collections::VecDeque::from([s]);
+        dist[s] = 0;
+        while let Some(i) = queue.pop_front() {
+            let c = grid.pos(i);
+            for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+                let Some(n) = grid.idx(c.x.wrapping_add_signed(dx), c.y.wrapping_add_signed(dy))
+                else {
+                    continue;
+                };
+                if grid.open[n] && dist[n] == usize::MAX {
+                    dist[n] = dist[i] + 1;
+                    queue.push_back(n);
+                }
+            }
+        }
+        (dist[g] != usize::MAX).then_some(dist[g])
+    }
+
+    #[test]
+    fn chunked_search_matches_the_synchronous_one() {
+        for seed in 1..40 {
+            let grid = random_grid(seed, 30, 24, 30);
+            let (starts, goals) = ends(&grid);
+            let sync = PathJob::new(grid.clone(), &starts, &goals).run();
+            for budget in [1, 7, 64] {
+                let mut job = PathJob::new(grid.clone(), &starts, &goals);
+                while !job.advance(budget) {}
+                assert_eq!(job.result(), sync, "seed {seed}, budget {budget}");
+            }
+            let grid = &grid;
+            let shortest = starts
+                .iter()
+                .flat_map(|&s| goals.iter().filter_map(move |&g| bfs_len(grid, s, g)))
+                .min();
+            assert_eq!(
+                sync.len().checked_sub(1).filter(|_| !sync.is_empty()),
+                shortest.filter(|&d| d > 0),
+                "seed {seed}"
+            );
+        }
+    }
+
+    #[test]
+    fn advance_respects_the_expansion_budget() {
+        let grid = random_grid(9, 120, 120, 20);
+        let (starts, goals) = ends(&grid);
+        let mut job = PathJob::new(grid, &starts, &goals);
+        let mut calls = 0;
+        loop {
+            let before = job.expanded;
+            let done = job.advance(100);
+            assert!(job.expanded - before <= 100);
+            calls += 1;
+            if done {
+                break;
+            }
+            // Not done means the whole budget went into the search
+            assert_eq!(job.expanded - before, 100);
+        }
+        assert!(calls > 1, "a 120x120 board takes more than one budget");
+        assert!(job.is_done());
+    }
+
+    #[test]
+    fn previous_path_breaks_ties() {
+        // Open 5x5: every monotone route is as short as any other
+        let gs = GridSize {
+            width: 5,
+            height: 5,
+        };
+        let prev: Vec<Position> = (0..5)
+            .map(|y| Position { x: 0, y })
+            .chain((1..5).map(|x| Position { x, y: 4 }))
+            .collect();
+        let grid = SearchGrid::new(gs, vec![true; 25], &prev);
+        assert_eq!(PathJob::new(grid, &[(0, 0)], &[(4, 4)]).run(), prev);
+    }
+
+    #[test]
+    fn blocked_or_closed_ends_give_no_route() {
+        let gs = GridSize {
+            width: 3,
+            height: 3,
+        };
+        let mut open = vec![true; 9];
+        // Wall down the middle column
+        for y in 0..3 {
+            open[(y * 3 + 1) as usize] = false;
+        }
+        let grid = SearchGrid::new(gs, open, &[]);
+        assert!(PathJob::new(grid.clone(), &[(0, 0)], &[(2, 2)])
+            .run()
+            .is_empty());
+        assert!(PathJob::new(grid, &[(1, 0)], &[(0, 2)]).run().is_empty());
+    }
+}
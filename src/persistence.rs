@@ -0,0 +1,667 @@
+// Versioned localStorage. Every persisted key registers a Schema below; migrate_all() runs
+// at startup before anything reads storage, so readers only ever see the current format.
+// Changing a blob's format means bumping its version, appending a migration, and adding
+// fixtures/storage/<key>.v<N>.json (the tests fail without it).
+use serde_json::Value;
+use std::collections::HashMap;
+
+// Per-key schema versions, stored as a JSON object
+pub const VERSIONS_KEY: &str = "md_schema_versions";
+
+pub trait Storage {
+    fn get(&self, key: &str) -> Option<String>;
+    fn set(&mut self, key: &str, value: &str);
+    fn remove(&mut self, key: &str);
+}
+
+pub struct LocalStorage(web_sys::Storage);
+impl LocalStorage {
+    pub fn open() -> Option<Self> {
+        web_sys::window()?.local_storage().ok().flatten().map(Self)
+    }
+}
+impl Storage for LocalStorage {
+    fn get(&self, key: &str) -> Option<String> {
+        self.0.get_item(key).ok().flatten()
+    }
+    fn set(&mut self, key: &str, value: &str) {
+        // Quota errors leave the old blob in place; nothing better to do at startup
+        let _ = self.0.set_item(key, value);
+    }
+    fn remove(&mut self, key: &str) {
+        let _ = self.0.remove_item(key);
+    }
+}
+
+// In-memory backend for native tests
+#[cfg(test)]
+#[derive(Default, Debug, Clone)]
+pub struct MemoryStorage {
+    pub items: HashMap<String, String>,
+}
+#[cfg(test)]
+impl Storage for MemoryStorage {
+    fn get(&self, key: &str) -> Option<String> {
+        self.items.get(key).cloned()
+    }
+    fn set(&mut self, key: &str, value: &str) {
+        self.items.insert(key.to_string(), value.to_string());
+    }
+    fn remove(&mut self, key: &str) {
+        self.items.remove(key);
+    }
+}
+
+// Upgrades a blob by exactly one version
+pub type Migration = fn(Value) -> Value;
+
+pub struct Schema {
+    pub key: &'static str,
+    // Current version; blobs written before versioning existed count as version 1
+    pub version: u32,
+    // migrations[i] takes version i + 1 to version i + 2
+    pub migrations: &'static [Migration],
+}
+
+pub const SCHEMAS: &[Schema] = &[
+    Schema {
+        key: "md_upgrade_state",
+        version: 1,
+        migrations: &[],
+    },
+    Schema {
+        key: "md_meta_state",
+        version: 1,
+        migrations: &[],
+    },
+    Schema {
+        key: "md_research",
+        version: 1,
+        migrations: &[],
+    },
+    Schema {
+        key: "md_records",
+        version: 1,
+        migrations: &[],
+    },
+    Schema {
+        key: "md_run_history",
+        version: 1,
+        migrations: &[],
+    },
+    Schema {
+        key: "md_run_best",
+        version: 1,
+        migrations: &[],
+    },
+    Schema {
+        key: "md_research_carry",
+        version: 1,
+        migrations: &[],
+    },
+    Schema {
+        key: "md_intro_seen",
+        version: 1,
+        migrations: &[],
+    },
+    Schema {
+        key: "md_setting_show_path",
+        version: 1,
+        migrations: &[],
+    },
+    Schema {
+        key: "md_setting_show_damage_numbers",
+        version: 1,
+        migrations: &[],
+    },
+    Schema {
+        key: "md_setting_show_health_bars",
+        version: 1,
+        migrations: &[],
+    },
+    Schema {
+        key: "md_setting_show_secondary_stats",
+        version: 1,
+        migrations: &[],
+    },
+    Schema {
+        key: "md_setting_map_picker",
+        version: 1,
+        migrations: &[],
+    },
+    Schema {
+        key: "md_setting_skip_progression",
+        version: 1,
+        migrations: &[],
+    },
+    Schema {
+        key: "md_setting_tile_labels",
+        version: 1,
+        migrations: &[],
+    },
+    Schema {
+        key: "md_setting_log_level",
+        version: 1,
+        migrations: &[],
+    },
+    Schema {
+        key: "md_setting_siege",
+        version: 1,
+        migrations: &[],
+    },
+    Schema {
+        key: "md_setting_end_route",
+        version: 1,
+        migrations: &[],
+    },
+    Schema {
+        key: "md_setting_hints",
+        version: 1,
+        migrations: &[],
+    },
+    Schema {
+        key: "md_setting_prospector",
+        version: 1,
+        migrations: &[],
+    },
+    Schema {
+        key: "md_setting_reduced_effects",
+        version: 1,
+        migrations: &[],
+    },
+    Schema {
+        key: "md_setting_edge_scroll",
+        version: 1,
+        migrations: &[],
+    },
+    Schema {
+        key: "md_setting_minimap",
+        version: 1,
+        migrations: &[],
+    },
+    Schema {
+        key: "md_setting_manual_collection",
+        version: 1,
+        migrations: &[],
+    },
+    Schema {
+        key: "md_setting_hud_layout",
+        version: 1,
+        migrations: &[],
+    },
+    Schema {
+        key: "md_setting_theme",
+        version: 1,
+        migrations: &[],
+    },
+    Schema {
+        key: "md_hotkeys",
+        version: 1,
+        migrations: &[],
+    },
+    Schema {
+        key: "md_setting_heartbeat",
+        version: 1,
+        migrations: &[],
+    },
+    Schema {
+        key: "md_upgrade_queue",
+        version: 1,
+        migrations: &[],
+    },
+    Schema {
+        key: "md_tour_upgrades",
+        version: 1,
+        migrations: &[],
+    },
+    Schema {
+        key: "md_tutorial",
+        version: 1,
+        migrations: &[],
+    },
+    Schema {
+        key: "md_achievements",
+        version: 1,
+        migrations: &[],
+    },
+    Schema {
+        key: "md_setting_pause_hidden",
+        version: 1,
+        migrations: &[],
+    },
+    Schema {
+        key: "md_setting_long_catch_up",
+        version: 1,
+        migrations: &[],
+    },
+    Schema {
+        key: "md_setting_audio",
+        version: 1,
+        migrations: &[],
+    },
+    Schema {
+        key: "md_run_state",
+        version: 3,
+        migrations: &[palette_run_tiles, healing_boosts],
+    },
+];
+
+// md_run_state v1 -> v2: (count, tile) runs become (count, index) runs over a tile palette.
+// Debug bundles from before DUMP_VERSION 2 carry the same v1 run. A blob that already has its
+// palette is v2 and comes back unchanged.
+pub fn palette_run_tiles(mut v: Value) -> Value {
+    if v.get("palette").is_some() {
+        return v;
+    }
+    let Some(runs) = v.get_mut("tiles").and_then(Value::as_array_mut) else {
+        return v;
+    };
+    let mut palette: Vec<Value> = Vec::new();
+    for run in runs.iter_mut() {
+        let Some(tile) = run.get_mut(1) else {
+            continue;
+        };
+        let i = match palette.iter().position(|p| p == tile) {
+            Some(i) => i,
+            None => {
+                palette.push(tile.clone());
+                palette.len() - 1
+            }
+        };
+        *tile = Value::from(i as u64);
+    }
+    if let Some(obj) = v.as_object_mut() {
+        obj.insert("palette".to_string(), Value::Array(palette));
+    }
+    v
+}
+
+// md_run_state v2 -> v3: healing tiles were saved as the Range boost before Healing had its own
+// variant. Rocks in the palette and the towers built on them both carry the old name.
+pub fn healing_boosts(mut v: Value) -> Value {
+    fn rename(boost: Option<&mut Value>) {
+        if let Some(b) = boost.filter(|b| b.as_str() == Some("Range")) {
+            *b = Value::from("Healing");
+        }
+    }
+    if let Some(palette) = v.get_mut("palette").and_then(Value::as_array_mut) {
+        for tile in palette {
+            rename(tile.pointer_mut("/kind/Rock/boost"));
+        }
+    }
+    if let Some(towers) = v.pointer_mut("/run/towers").and_then(Value::as_array_mut) {
+        for tower in towers {
+            rename(tower.get_mut("boost"));
+        }
+    }
+    v
+}
+
+// Writes a registered key and records it at the current version, so the next startup reads it
+// as current instead of migrating it again
+pub fn write(store: &mut dyn Storage, key: &str, value: &str) {
+    store.set(key, value);
+    let Some(schema) = SCHEMAS.iter().find(|s| s.key == key) else {
+        return;
+    };
+    let mut versions = load_versions(store);
+    if versions.get(key) != Some(&schema.version) {
+        versions.insert(key.to_string(), schema.version);
+        save_versions(store, &versions);
+    }
+}
+
+fn load_versions(store: &dyn Storage) -> HashMap<String, u32> {
+    store
+        .get(VERSIONS_KEY)
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn save_versions(store: &mut dyn Storage, versions: &HashMap<String, u32>) {
+    if let Ok(out) = serde_json::to_string(versions) {
+        store.set(VERSIONS_KEY, &out);
+    }
+}
+
+// Returns the keys whose blobs were rewritten
+pub fn migrate_all(store: &mut dyn Storage) -> Vec<&'static str> {
+    migrate_schemas(store, SCHEMAS)
+}
+
+fn migrate_schemas(store: &mut dyn Storage, schemas: &[Schema]) -> Vec<&'static str> {
+    let mut versions = load_versions(store);
+    let mut migrated = Vec::new();
+    for schema in schemas {
+        // A blob from a newer build keeps its version rather than being marked older
+        let recorded = versions.get(schema.key).copied();
+        let Some(raw) = store.get(schema.key) else {
+            // Nothing saved: whatever this build writes next is already current
+            let current = recorded.unwrap_or(0).max(schema.version);
+            versions.insert(schema.key.to_string(), current);
+            continue;
+        };
+        let from = recorded.unwrap_or(1).max(1);
+        if from >= schema.version {
+            versions.insert(schema.key.to_string(), from);
+            continue;
+        }
+        // Unparseable blobs are left alone at their old version; every reader already has a
+        // fallback
+        let Ok(mut v) = serde_json::from_str::<Value>(&raw) else {
+            versions.insert(schema.key.to_string(), from);
+            continue;
+        };
+        for m in schema.migrations.iter().skip((from - 1) as usize) {
+            v = m(v);
+        }
+        let done = match serde_json::to_string(&v) {
+            Ok(out) => {
+                store.set(schema.key, &out);
+                migrated.push(schema.key);
+                schema.version
+            }
+            Err(_) => from,
+        };
+        versions.insert(schema.key.to_string(), done);
+    }
+    save_versions(store, &versions);
+    migrated
+}
+
+// Hard reset: wipes every registered key
+pub fn clear_all(store: &mut dyn Storage) {
+    for schema in SCHEMAS {
+        store.remove(schema.key);
+    }
+    store.remove(VERSIONS_KEY);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::achievements::{AchievementId, AchievementState};
+    use crate::model::{
+        BoostKind, EntityId, MetaRecords, MetaState, RunState, TileKind, UpgradeId, UpgradeState,
+    };
+    use crate::run_history::RunRecord;
+    use crate::state::{
+        AudioSettings, HotkeyAction, Hotkeys, HudLayoutSettings, PaletteId, ThemeSettings,
+        TutorialState,
+    };
+    use std::collections::HashSet;
+
+    fn fixture(key: &str, version: u32) -> Option<String> {
+        let path = format!(
+            "{}/fixtures/storage/{}.v{}.json",
+            env!("CARGO_MANIFEST_DIR"),
+            key,
+            version
+        );
+        std::fs::read_to_string(path)
+            .ok()
+            .map(|s| s.trim().to_string())
+    }
+
+    // Every fixture encodes the same values; these must survive any migration chain
+    fn assert_survives(key: &str, raw: &str) {
+        match key {
+            "md_upgrade_state" => {
+                let us: UpgradeState = serde_json::from_str(raw).unwrap();
+                assert_eq!(us.level(UpgradeId::TowerDamage1), 3);
+                assert_eq!(us.level(UpgradeId::FireRate), 2);
+                assert_eq!(us.level(UpgradeId::PlayAreaSize), 1);
+                assert_eq!(us.tower_refund_rate_percent, 80);
+            }
+            "md_upgrade_queue" => {
+                let q: Vec<UpgradeId> = serde_json::from_str(raw).unwrap();
+                assert_eq!(q, vec![UpgradeId::FireRate, UpgradeId::TowerDamage1]);
+            }
+            "md_meta_state" => {
+                let m: MetaState = serde_json::from_str(raw).unwrap();
+                assert_eq!((m.prestige_points, m.prestige_count), (7, 2));
+            }
+            "md_research" => assert_eq!(raw.parse::<u64>().unwrap(), 1234),
+            "md_research_carry" => assert_eq!(raw.parse::<f64>().unwrap(), 0.25),
+            "md_setting_hud_layout" => {
+                let s: HudLayoutSettings = serde_json::from_str(raw).unwrap();
+                assert_eq!(s.narrow_below_px, 900);
+                assert_eq!(s.ultrawide_ratio, 2.4);
+                assert_eq!(s.ultrawide_margin_pct, 10);
+            }
+            "md_setting_theme" => {
+                let t: ThemeSettings = serde_json::from_str(raw).unwrap();
+                assert_eq!((t.palette, t.ui_scale), (PaletteId::HighContrast, 1.25));
+            }
+            "md_tutorial" => {
+                let t: TutorialState = serde_json::from_str(raw).unwrap();
+                assert_eq!(t.completed, 2);
+            }
+            "md_achievements" => {
+                let a: AchievementState = serde_json::from_str(raw).unwrap();
+                assert_eq!(a.unlocked.len(), 2);
+                assert_eq!(a.unlocked_at(AchievementId::Survivor), Some(1.7e12));
+            }
+            "md_hotkeys" => {
+                let hk = Hotkeys::from_json(raw);
+                assert_eq!(hk.code(HotkeyAction::PlaceTower), Some("KeyY"));
+                assert_eq!(hk.code(HotkeyAction::TogglePause), Some("Space"));
+            }
+            "md_setting_audio" => {
+                let s: AudioSettings = serde_json::from_str(raw).unwrap();
+                assert_eq!((s.music_volume, s.sfx_volume, s.muted), (40, 70, true));
+                // Saved before the master volume existed
+                assert_eq!(s.master_volume, 100);
+            }
+            "md_records" => {
+                let r: MetaRecords = serde_json::from_str(raw).unwrap();
+                assert_eq!(r.best_time_secs, 754);
+                assert_eq!(r.best_loops, 12);
+                assert_eq!(r.best_blocks_mined, 87);
+                assert_eq!(r.total_runs, 9);
+            }
+            "md_run_best" => {
+                let r: RunRecord = serde_json::from_str(raw).unwrap();
+                assert_eq!((r.run_id, r.time_survived_secs), (7, 412));
+            }
+            "md_run_history" => {
+                let h: Vec<RunRecord> = serde_json::from_str(raw).unwrap();
+                assert_eq!(h.len(), 2);
+                assert_eq!(h[1].run_id, 7);
+                assert_eq!(h[1].time_survived_secs, 412);
+                assert_eq!(h[1].grid_size.width, 32);
+            }
+            "md_run_state" => {
+                let rs = RunState::from_save(raw).unwrap();
+                assert_eq!(rs.tiles.len(), 15);
+                assert_eq!(rs.currencies.gold, 23);
+                assert_eq!((rs.life, rs.life_max), (8, 10));
+                assert_eq!(rs.stats.time_survived_secs, 61);
+                assert_eq!(rs.run_id, 4);
+                assert!(rs.is_paused);
+                let healing = Some(BoostKind::Healing);
+                assert!(matches!(
+                    rs.tiles[rs.grid_size.width as usize * 2 + 4].kind,
+                    TileKind::Rock { boost, .. } if boost == healing
+                ));
+                let boosts: Vec<_> = rs.towers.iter().map(|t| t.boost).collect();
+                assert_eq!(boosts, vec![None, healing]);
+            }
+            _ => assert_eq!(raw, "1", "{key}"),
+        }
+    }
+
+    #[test]
+    fn every_schema_version_has_a_fixture() {
+        for s in SCHEMAS {
+            assert_eq!(
+                s.migrations.len() as u32 + 1,
+                s.version,
+                "{} needs one migration per version step",
+                s.key
+            );
+            for v in 1..=s.version {
+                assert!(
+                    fixture(s.key, v).is_some(),
+                    "missing fixtures/storage/{}.v{}.json",
+                    s.key,
+                    v
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn fixtures_survive_migration_to_current() {
+        for s in SCHEMAS {
+            for v in 1..=s.version {
+                let mut store = MemoryStorage::default();
+                store.set(s.key, &fixture(s.key, v).unwrap());
+                // Version 1 blobs predate the versions map entirely
+                if v > 1 {
+                    store.set(VERSIONS_KEY, &format!("{{\"{}\":{}}}", s.key, v));
+                }
+                migrate_all(&mut store);
+                assert_survives(s.key, &store.get(s.key).unwrap());
+                let versions: HashMap<String, u32> =
+                    serde_json::from_str(&store.get(VERSIONS_KEY).unwrap()).unwrap();
+                assert_eq!(versions[s.key], s.version);
+            }
+        }
+    }
+
+    fn rename_best(mut v: Value) -> Value {
+        let best = v.as_object_mut().and_then(|obj| obj.remove("best"));
+        if let (Some(obj), Some(best)) = (v.as_object_mut(), best) {
+            obj.insert("best_time_secs".to_string(), best);
+        }
+        v
+    }
+    fn add_runs(mut v: Value) -> Value {
+        if let Some(obj) = v.as_object_mut() {
+            obj.entry("total_runs".to_string())
+                .or_insert(Value::from(1u64));
+        }
+        v
+    }
+    const CHAIN: &[Schema] = &[Schema {
+        key: "md_test_chain",
+        version: 3,
+        migrations: &[rename_best, add_runs],
+    }];
+
+    #[test]
+    fn palette_migration_runs_once() {
+        let v1: Value = serde_json::from_str(&fixture("md_run_state", 1).unwrap()).unwrap();
+        let once = palette_run_tiles(v1);
+        assert_eq!(palette_run_tiles(once.clone()), once);
+        assert_survives("md_run_state", &healing_boosts(once).to_string());
+    }
+
+    #[test]
+    fn towers_saved_without_ids_get_fresh_ones() {
+        let v1: Value = serde_json::from_str(&fixture("md_run_state", 1).unwrap()).unwrap();
+        assert!(v1["run"]["towers"][0].get("id").is_none());
+        let current = healing_boosts(palette_run_tiles(v1)).to_string();
+        let mut back = RunState::from_save(&current).unwrap();
+        let ids: HashSet<_> = back.towers.iter().map(|t| t.id).collect();
+        assert_eq!(ids.len(), 2);
+        assert!(!ids.contains(&EntityId::default()));
+        let next = back.entity_ids.alloc();
+        assert!(ids.iter().all(|id| *id < next));
+
+        // A repeated id is replaced on the second tower, not the first
+        let mut twin = back.clone();
+        twin.towers[1].id = twin.towers[0].id;
+        let back = RunState::from_save(&twin.to_save()).unwrap();
+        assert_eq!(back.towers[0].id, twin.towers[0].id);
+        assert_ne!(back.towers[1].id, back.towers[0].id);
+    }
+
+    #[test]
+    fn chain_resumes_from_stored_version() {
+        let mut v1 = MemoryStorage::default();
+        v1.set("md_test_chain", r#"{"best":40}"#);
+        assert_eq!(migrate_schemas(&mut v1, CHAIN), vec!["md_test_chain"]);
+        let out: Value = serde_json::from_str(&v1.get("md_test_chain").unwrap()).unwrap();
+        assert_eq!(out["best_time_secs"], 40);
+        assert_eq!(out["total_runs"], 1);
+
+        // Already at v2: only the second step runs
+        let mut v2 = MemoryStorage::default();
+        v2.set("md_test_chain", r#"{"best":7,"total_runs":5}"#);
+        v2.set(VERSIONS_KEY, r#"{"md_test_chain":2}"#);
+        migrate_schemas(&mut v2, CHAIN);
+        let out: Value = serde_json::from_str(&v2.get("md_test_chain").unwrap()).unwrap();
+        assert_eq!(out["best"], 7);
+        assert_eq!(out["total_runs"], 5);
+
+        // Current blobs are untouched, and a second pass is a no-op
+        assert!(migrate_schemas(&mut v2, CHAIN).is_empty());
+    }
+
+    fn stored_versions(store: &MemoryStorage) -> HashMap<String, u32> {
+        serde_json::from_str(&store.get(VERSIONS_KEY).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn migrate_skips_absent_and_unparseable_blobs() {
+        let mut store = MemoryStorage::default();
+        store.set("md_test_chain", "not json");
+        assert!(migrate_schemas(&mut store, CHAIN).is_empty());
+        assert_eq!(store.get("md_test_chain").as_deref(), Some("not json"));
+        // Still owed its migrations once it parses
+        assert_eq!(stored_versions(&store)["md_test_chain"], 1);
+
+        // Absent keys are recorded as current: the next write is in this build's format
+        let mut empty = MemoryStorage::default();
+        assert!(migrate_all(&mut empty).is_empty());
+        let versions = stored_versions(&empty);
+        for s in SCHEMAS {
+            assert_eq!(versions[s.key], s.version, "{}", s.key);
+        }
+    }
+
+    #[test]
+    fn current_blobs_survive_repeated_reloads() {
+        for s in SCHEMAS {
+            let mut store = MemoryStorage::default();
+            migrate_all(&mut store);
+            let current = fixture(s.key, s.version).unwrap();
+            write(&mut store, s.key, &current);
+            for _ in 0..2 {
+                assert!(
+                    migrate_all(&mut store).is_empty(),
+                    "{} migrated again",
+                    s.key
+                );
+                assert_eq!(store.get(s.key).as_deref(), Some(current.as_str()));
+            }
+        }
+    }
+
+    #[test]
+    fn write_stamps_the_current_version() {
+        // Written after a hard reset wiped the versions map
+        let schema = SCHEMAS.iter().find(|s| s.key == "md_run_state").unwrap();
+        let mut store = MemoryStorage::default();
+        let current = fixture(schema.key, schema.version).unwrap();
+        write(&mut store, schema.key, &current);
+        assert_eq!(stored_versions(&store)[schema.key], schema.version);
+        assert!(migrate_all(&mut store).is_empty());
+        assert_eq!(store.get("md_run_state").as_deref(), Some(current.as_str()));
+    }
+
+    #[test]
+    fn clear_all_removes_registered_keys() {
+        let mut store = MemoryStorage::default();
+        for s in SCHEMAS {
+            store.set(s.key, "1");
+        }
+        migrate_all(&mut store);
+        store.set("other_app_key", "keep");
+        clear_all(&mut store);
+        assert_eq!(store.items.len(), 1);
+        assert!(store.items.contains_key("other_app_key"));
+    }
+}
@@ -0,0 +1,99 @@
+// Prestige in storage. MetaState sits under md_meta_state next to md_upgrade_state; a prestige
+// rewrites both, plus research, in one go so a reload can never pair the new points with the
+// old levels. Run history and records are left alone.
+use crate::model::{MetaState, UpgradeState};
+use crate::persistence::{self, Storage};
+
+pub const META_KEY: &str = "md_meta_state";
+
+pub fn load(store: &dyn Storage) -> MetaState {
+    store
+        .get(META_KEY)
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+pub fn save(store: &mut dyn Storage, meta: MetaState) {
+    if let Ok(raw) = serde_json::to_string(&meta) {
+        persistence::write(store, META_KEY, &raw);
+    }
+}
+
+// Stores the upgrades model::prestige returned, with research spent down to nothing and the
+// queue emptied
+pub fn commit(store: &mut dyn Storage, ups: &UpgradeState) {
+    save(store, ups.meta);
+    if let Ok(raw) = serde_json::to_string(ups) {
+        persistence::write(store, "md_upgrade_state", &raw);
+    }
+    persistence::write(store, "md_research", "0");
+    store.remove("md_upgrade_queue");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{prestige, GridSize, UpgradeId, PRESTIGE_MIN_RESEARCH};
+    use crate::persistence::MemoryStorage;
+    use crate::run_history::{self, RunRecord};
+
+    #[test]
+    fn prestige_keeps_history_and_records() {
+        let mut store = MemoryStorage::default();
+        run_history::record(
+            &mut store,
+            RunRecord {
+                run_id: 3,
+                time_survived_secs: 640,
+                loops_completed: 9,
+                enemies_killed: 210,
+                research_earned: 900,
+                grid_size: GridSize {
+                    width: 32,
+                    height: 32,
+                },
+                timestamp_ms: 1.7e12,
+            },
+        );
+        store.set("md_records", r#"{"best_time_secs":640}"#);
+        store.set("md_research", "9000");
+        store.set("md_upgrade_queue", r#"["FireRate"]"#);
+        let history = run_history::load(&store);
+
+        let mut ups = UpgradeState::default();
+        ups.levels.insert(UpgradeId::FireRate.key().into(), 3);
+        let after = prestige(&ups, PRESTIGE_MIN_RESEARCH).unwrap();
+        commit(&mut store, &after);
+
+        assert_eq!(run_history::load(&store), history);
+        assert_eq!(
+            store.get("md_records").as_deref(),
+            Some(r#"{"best_time_secs":640}"#)
+        );
+        assert_eq!(load(&store), after.meta);
+        assert_eq!(load(&store).prestige_count, 1);
+        assert_eq!(store.get("md_research").as_deref(), Some("0"));
+        assert_eq!(store.get("md_upgrade_queue"), None);
+        // Levels come back wiped, and the points don't ride along inside them
+        let stored: UpgradeState =
+            serde_json::from_str(&store.get("md_upgrade_state").unwrap()).unwrap();
+        assert!(stored.levels.values().all(|l| *l == 0));
+        assert_eq!(stored.meta, MetaState::default());
+    }
+
+    #[test]
+    fn missing_or_unreadable_meta_loads_default() {
+        let mut store = MemoryStorage::default();
+        assert_eq!(load(&store), MetaState::default());
+        store.set(META_KEY, "{oops");
+        assert_eq!(load(&store), MetaState::default());
+        save(
+            &mut store,
+            MetaState {
+                prestige_points: 3,
+                prestige_count: 1,
+            },
+        );
+        assert_eq!(load(&store).prestige_points, 3);
+    }
+}
@@ -0,0 +1,183 @@
+// Past runs, kept under md_run_history as a JSON list, newest last and capped at HISTORY_CAP.
+// A run is recorded once: when it ends, or when it's restarted mid-run after it started. The
+// all-time best is kept apart under md_run_best, so it outlives the cap and a cleared history.
+use serde::{Deserialize, Serialize};
+
+use crate::model::{GridSize, RunState};
+use crate::persistence::{self, Storage};
+
+pub const HISTORY_KEY: &str = "md_run_history";
+pub const HISTORY_CAP: usize = 20;
+pub const BEST_KEY: &str = "md_run_best";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub run_id: u64,
+    pub time_survived_secs: u64,
+    pub loops_completed: u32,
+    pub enemies_killed: u64,
+    pub research_earned: u64,
+    pub grid_size: GridSize,
+    // Date.now() when recorded
+    pub timestamp_ms: f64,
+}
+
+impl RunRecord {
+    pub fn from_run(rs: &RunState, timestamp_ms: f64) -> Self {
+        Self {
+            run_id: rs.run_id,
+            time_survived_secs: rs.stats.time_survived_secs,
+            loops_completed: rs.stats.loops_completed,
+            enemies_killed: rs.stats.enemies_killed,
+            research_earned: rs.stats.research_earned,
+            grid_size: rs.grid_size,
+            timestamp_ms,
+        }
+    }
+}
+
+// Whether a run belongs in the history: started, and neither a scenario nor an imported
+// debug run
+pub fn counts(rs: &RunState) -> bool {
+    rs.started && rs.scenario.is_none() && !rs.debug_allowed
+}
+
+pub fn load(store: &dyn Storage) -> Vec<RunRecord> {
+    store
+        .get(HISTORY_KEY)
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+// The stored all-time best; stores from before it was kept fall back to their history's
+pub fn load_best(store: &dyn Storage) -> Option<RunRecord> {
+    match store.get(BEST_KEY) {
+        Some(raw) => serde_json::from_str(&raw).ok(),
+        None => best(&load(store)).cloned(),
+    }
+}
+
+pub fn save_best(store: &mut dyn Storage, best: Option<&RunRecord>) {
+    match best.map(serde_json::to_string) {
+        Some(Ok(raw)) => persistence::write(store, BEST_KEY, &raw),
+        _ => store.remove(BEST_KEY),
+    }
+}
+
+// Adds `rec` and saves; true when it beats the all-time best time (a first run has nothing to
+// beat)
+pub fn record(store: &mut dyn Storage, rec: RunRecord) -> bool {
+    let best = load_best(store);
+    let new_best = best
+        .as_ref()
+        .is_some_and(|b| rec.time_survived_secs > b.time_survived_secs);
+    if new_best || best.is_none() {
+        save_best(store, Some(&rec));
+    }
+    let mut history = load(store);
+    history.push(rec);
+    if history.len() > HISTORY_CAP {
+        history.drain(..history.len() - HISTORY_CAP);
+    }
+    if let Ok(raw) = serde_json::to_string(&history) {
+        persistence::write(store, HISTORY_KEY, &raw);
+    }
+    new_best
+}
+
+pub fn clear(store: &mut dyn Storage) {
+    store.remove(HISTORY_KEY);
+}
+
+// Longest survival; the earlier run wins a tie
+pub fn best(history: &[RunRecord]) -> Option<&RunRecord> {
+    history.iter().rev().max_by_key(|r| r.time_survived_secs)
+}
+
+// Longest survival first, as the History view lists them
+pub fn by_time_survived(history: &[RunRecord]) -> Vec<RunRecord> {
+    let mut sorted = history.to_vec();
+    sorted.sort_by_key(|r| std::cmp::Reverse(r.time_survived_secs));
+    sorted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::MemoryStorage;
+
+    fn rec(run_id: u64, secs: u64) -> RunRecord {
+        RunRecord {
+            run_id,
+            time_survived_secs: secs,
+            loops_completed: 2,
+            enemies_killed: 30,
+            research_earned: 12,
+            grid_size: GridSize {
+                width: 24,
+                height: 24,
+            },
+            timestamp_ms: 1.7e12 + run_id as f64,
+        }
+    }
+
+    #[test]
+    fn history_keeps_the_latest_runs_and_flags_new_bests() {
+        let mut store = MemoryStorage::default();
+        assert!(load(&store).is_empty());
+        // Nothing to beat yet
+        assert!(!record(&mut store, rec(1, 120)));
+        assert!(!record(&mut store, rec(2, 90)));
+        assert!(record(&mut store, rec(3, 300)));
+        assert!(!record(&mut store, rec(4, 300)));
+        assert_eq!(best(&load(&store)).map(|r| r.run_id), Some(3));
+        let order: Vec<u64> = by_time_survived(&load(&store))
+            .iter()
+            .map(|r| r.run_id)
+            .collect();
+        assert_eq!(order, vec![3, 4, 1, 2]);
+
+        for id in 10..40 {
+            record(&mut store, rec(id, id));
+        }
+        let history = load(&store);
+        assert_eq!(history.len(), HISTORY_CAP);
+        assert_eq!(history[0].run_id, 20);
+
+        clear(&mut store);
+        assert!(load(&store).is_empty());
+    }
+
+    #[test]
+    fn the_best_outlives_the_history() {
+        let mut store = MemoryStorage::default();
+        record(&mut store, rec(1, 600));
+        // Pushed out of the capped list, and then the list cleared
+        for id in 2..30 {
+            assert!(!record(&mut store, rec(id, 100 + id)));
+        }
+        assert!(load(&store).iter().all(|r| r.run_id != 1));
+        clear(&mut store);
+        assert_eq!(load_best(&store).map(|r| r.run_id), Some(1));
+        assert!(!record(&mut store, rec(30, 599)));
+        assert!(record(&mut store, rec(31, 601)));
+        assert_eq!(load_best(&store).map(|r| r.run_id), Some(31));
+    }
+
+    #[test]
+    fn best_falls_back_to_the_stored_history() {
+        let mut store = MemoryStorage::default();
+        let history = vec![rec(1, 300), rec(2, 200)];
+        store.set(HISTORY_KEY, &serde_json::to_string(&history).unwrap());
+        assert_eq!(load_best(&store), Some(rec(1, 300)));
+        assert!(!record(&mut store, rec(3, 250)));
+        assert!(record(&mut store, rec(4, 301)));
+    }
+
+    #[test]
+    fn unreadable_history_loads_empty() {
+        let mut store = MemoryStorage::default();
+        store.set(HISTORY_KEY, "{not json");
+        assert!(load(&store).is_empty());
+    }
+}
@@ -0,0 +1,265 @@
+// Whole-save export for moving progress between browsers: upgrades, research, run history and
+// settings as one pasteable string. The payload JSON travels inside a versioned envelope with
+// an FNV-1a checksum, base64'd behind a prefix, so a clipped or edited paste is refused with a
+// reason instead of quietly resetting progress. Upgrades and research are applied by the
+// caller through the app's handles; history and settings go straight back into storage.
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::{MetaState, UpgradeState};
+use crate::persistence::{self, LocalStorage, Storage, SCHEMAS};
+use crate::prestige;
+use crate::run_history::{self, RunRecord, HISTORY_KEY};
+use crate::util::{base64_decode, base64_encode};
+
+pub const SAVE_FORMAT: u32 = 1;
+const SAVE_PREFIX: &str = "md-save:";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SaveData {
+    pub upgrades: UpgradeState,
+    // UpgradeState leaves its meta out when serialized; it travels here and is put back on decode
+    #[serde(default)]
+    pub meta: MetaState,
+    pub research: u64,
+    pub history: Vec<RunRecord>,
+    // Raw md_setting_* and md_hotkeys blobs, written back as they are
+    pub settings: BTreeMap<String, String>,
+}
+
+// The payload is kept as the exact string the checksum was taken over
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    format: u32,
+    checksum: u32,
+    payload: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SaveError {
+    // No prefix: not a save string, or only its tail was pasted
+    NotASave,
+    // Bad base64 or JSON, usually a truncated paste
+    Corrupt,
+    // Decodes, but the payload was changed after export
+    Checksum,
+    // Written by a newer build
+    Format(u32),
+}
+
+impl SaveError {
+    pub fn message(self) -> String {
+        match self {
+            SaveError::NotASave => "Not a save (it should start with md-save:)".into(),
+            SaveError::Corrupt => "Save is damaged or cut short".into(),
+            SaveError::Checksum => {
+                "Save doesn't match its checksum; it was edited or damaged".into()
+            }
+            SaveError::Format(v) => {
+                format!("Save is format v{v}; this build reads up to v{SAVE_FORMAT}")
+            }
+        }
+    }
+}
+
+// What an import would bring in, for the confirmation prompt
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SavePreview {
+    pub research: u64,
+    pub upgrade_levels: u32,
+    pub runs: usize,
+}
+
+impl SaveData {
+    pub fn preview(&self) -> SavePreview {
+        SavePreview {
+            research: self.research,
+            upgrade_levels: self.upgrades.total_levels(),
+            runs: self.history.len(),
+        }
+    }
+}
+
+fn is_setting(key: &str) -> bool {
+    key.starts_with("md_setting_") || key == "md_hotkeys"
+}
+
+pub fn collect(upgrades: &UpgradeState, research: u64, store: Option<&dyn Storage>) -> SaveData {
+    let settings = SCHEMAS
+        .iter()
+        .filter(|s| is_setting(s.key))
+        .filter_map(|s| Some((s.key.to_string(), store?.get(s.key)?)))
+        .collect();
+    SaveData {
+        upgrades: upgrades.clone(),
+        meta: upgrades.meta,
+        research,
+        history: store.map(run_history::load).unwrap_or_default(),
+        settings,
+    }
+}
+
+// The encoded save for the live session, history and settings read from localStorage
+pub fn session_save(upgrades: &UpgradeState, research: u64) -> String {
+    let store = LocalStorage::open();
+    let store = store.as_ref().map(|s| s as &dyn Storage);
+    encode(&collect(upgrades, research, store))
+}
+
+// Replaces the stored prestige, history (best included) and settings with the save's;
+// settings it lacks go back to their defaults
+pub fn apply_stored(store: &mut dyn Storage, data: &SaveData) {
+    prestige::save(store, data.meta);
+    match serde_json::to_string(&data.history) {
+        Ok(raw) if !data.history.is_empty() => persistence::write(store, HISTORY_KEY, &raw),
+        _ => run_history::clear(store),
+    }
+    run_history::save_best(store, run_history::best(&data.history));
+    for s in SCHEMAS.iter().filter(|s| is_setting(s.key)) {
+        match data.settings.get(s.key) {
+            Some(v) => persistence::write(store, s.key, v),
+            None => store.remove(s.key),
+        }
+    }
+}
+
+// FNV-1a, 32-bit
+fn checksum(text: &str) -> u32 {
+    text.bytes().fold(0x811c_9dc5, |h, b| {
+        (h ^ u32::from(b)).wrapping_mul(0x0100_0193)
+    })
+}
+
+pub fn encode(data: &SaveData) -> String {
+    let payload = serde_json::to_string(data).unwrap_or_default();
+    let env = Envelope {
+        format: SAVE_FORMAT,
+        checksum: checksum(&payload),
+        payload,
+    };
+    let json = serde_json::to_string(&env).unwrap_or_default();
+    format!("{SAVE_PREFIX}{}", base64_encode(json.as_bytes()))
+}
+
+pub fn decode(raw: &str) -> Result<SaveData, SaveError> {
+    let body = raw
+        .trim()
+        .strip_prefix(SAVE_PREFIX)
+        .ok_or(SaveError::NotASave)?;
+    let bytes = base64_decode(body).ok_or(SaveError::Corrupt)?;
+    // Peek at the format first so a newer one isn't reported as damage
+    let head: serde_json::Value = serde_json::from_slice(&bytes).map_err(|_| SaveError::Corrupt)?;
+    match head.get("format").and_then(|v| v.as_u64()) {
+        Some(v) if v == u64::from(SAVE_FORMAT) => {}
+        Some(v) => return Err(SaveError::Format(v as u32)),
+        None => return Err(SaveError::Corrupt),
+    }
+    let env: Envelope = serde_json::from_value(head).map_err(|_| SaveError::Corrupt)?;
+    if checksum(&env.payload) != env.checksum {
+        return Err(SaveError::Checksum);
+    }
+    let mut data: SaveData = serde_json::from_str(&env.payload).map_err(|_| SaveError::Corrupt)?;
+    data.upgrades.meta = data.meta;
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{GridSize, UpgradeId};
+    use crate::persistence::MemoryStorage;
+
+    fn record(run_id: u64) -> RunRecord {
+        RunRecord {
+            run_id,
+            time_survived_secs: 300 + run_id,
+            loops_completed: 4,
+            enemies_killed: 55,
+            research_earned: 20,
+            grid_size: GridSize {
+                width: 24,
+                height: 24,
+            },
+            timestamp_ms: 1.7e12,
+        }
+    }
+
+    fn store() -> MemoryStorage {
+        let mut store = MemoryStorage::default();
+        store.set("md_setting_minimap", "0");
+        store.set("md_hotkeys", r#"{"PlaceTower":"KeyY"}"#);
+        store.set("md_research", "1234");
+        run_history::record(&mut store, record(1));
+        run_history::record(&mut store, record(2));
+        store
+    }
+
+    fn data() -> SaveData {
+        let mut ups = UpgradeState::default();
+        ups.levels.insert(UpgradeId::FireRate.key().into(), 2);
+        ups.levels.insert(UpgradeId::TowerDamage1.key().into(), 3);
+        ups.meta.prestige_points = 6;
+        collect(&ups, 1234, Some(&store()))
+    }
+
+    // Re-encodes a save with its envelope edited by `f`
+    fn reseal(save: &str, f: impl FnOnce(&mut Envelope)) -> String {
+        let bytes = base64_decode(&save[SAVE_PREFIX.len()..]).unwrap();
+        let mut env: Envelope = serde_json::from_slice(&bytes).unwrap();
+        f(&mut env);
+        let json = serde_json::to_string(&env).unwrap();
+        format!("{SAVE_PREFIX}{}", base64_encode(json.as_bytes()))
+    }
+
+    #[test]
+    fn save_round_trips_and_previews() {
+        let d = data();
+        assert_eq!(
+            d.settings.keys().collect::<Vec<_>>(),
+            vec!["md_hotkeys", "md_setting_minimap"]
+        );
+        let save = encode(&d);
+        assert!(save.starts_with("md-save:"));
+        assert_eq!(decode(&format!("  {save}\n")), Ok(d.clone()));
+        assert_eq!(
+            d.preview(),
+            SavePreview {
+                research: 1234,
+                upgrade_levels: 5,
+                runs: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn applying_a_save_replaces_history_and_settings() {
+        let d = data();
+        let mut target = MemoryStorage::default();
+        target.set("md_setting_show_path", "0");
+        target.set("md_research", "9");
+        apply_stored(&mut target, &d);
+        assert_eq!(run_history::load(&target), d.history);
+        assert_eq!(prestige::load(&target).prestige_points, 6);
+        assert_eq!(target.get("md_setting_minimap").as_deref(), Some("0"));
+        // Absent from the save, so back to its default
+        assert_eq!(target.get("md_setting_show_path"), None);
+        // Research goes through the reducer, not storage
+        assert_eq!(target.get("md_research").as_deref(), Some("9"));
+    }
+
+    #[test]
+    fn damaged_edited_or_newer_saves_are_rejected() {
+        let save = encode(&data());
+        assert_eq!(decode(&save[..save.len() - 5]), Err(SaveError::Corrupt));
+        assert_eq!(decode(&save[8..]), Err(SaveError::NotASave));
+        assert_eq!(decode("md-save:bm90IGpzb24="), Err(SaveError::Corrupt));
+        let edited = reseal(&save, |env| {
+            env.payload = env.payload.replace("1234", "9999999")
+        });
+        assert_eq!(decode(&edited), Err(SaveError::Checksum));
+        let newer = reseal(&save, |env| env.format = SAVE_FORMAT + 1);
+        assert_eq!(decode(&newer), Err(SaveError::Format(SAVE_FORMAT + 1)));
+        assert!(SaveError::Format(2).message().contains("v2"));
+    }
+}
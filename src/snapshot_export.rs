@@ -0,0 +1,280 @@
+// Interactive snapshot: the final board, towers and run stats as one self-contained HTML file
+// with a small pan/zoom canvas viewer, for sharing a run without a server. Huge maps are
+// downsampled in square blocks until the file fits EXPORT_MAX_BYTES.
+use serde::Serialize;
+
+use crate::model::{DirRole, RunState, TileKind};
+
+// Cells per side before downsampling kicks in
+pub const EXPORT_MAX_SIDE: u32 = 96;
+pub const EXPORT_MAX_BYTES: usize = 256 * 1024;
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SnapshotStats {
+    pub run_id: u64,
+    pub map_seed: u64,
+    pub time_survived_secs: u64,
+    pub loops_completed: u32,
+    pub blocks_mined: u32,
+    pub enemies_killed: u64,
+    pub research_earned: u64,
+    pub towers: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SnapshotPayload {
+    pub title: String,
+    // Cell grid after downsampling; each cell covers `scale` x `scale` tiles
+    pub width: u32,
+    pub height: u32,
+    pub scale: u32,
+    // Row-major (count, code) runs of tile_code values
+    pub tiles: Vec<(u32, char)>,
+    // Cell x, cell y, kind name
+    pub towers: Vec<(u32, u32, &'static str)>,
+    pub stats: SnapshotStats,
+}
+
+// One letter per tile kind, plus how strongly it claims a downsampled cell: the loop and its
+// markers win over the rock around them so the maze stays readable
+fn tile_code(kind: &TileKind) -> (char, u8) {
+    match kind {
+        TileKind::Start => ('S', 9),
+        TileKind::End => ('E', 9),
+        TileKind::Direction {
+            role: DirRole::Entrance,
+            ..
+        } => ('N', 8),
+        TileKind::Direction {
+            role: DirRole::Exit,
+            ..
+        } => ('X', 8),
+        TileKind::Empty => ('.', 6),
+        TileKind::Wall => ('W', 5),
+        TileKind::Indestructible => ('I', 4),
+        TileKind::Rock { has_gold: true, .. } => ('g', 3),
+        TileKind::Rock { boost: Some(_), .. } => ('b', 2),
+        TileKind::Rock { .. } => ('#', 1),
+    }
+}
+
+// Smallest block size that brings the longer side within `max_side`
+fn block_scale(rs: &RunState, max_side: u32) -> u32 {
+    let side = rs.grid_size.width.max(rs.grid_size.height);
+    side.div_ceil(max_side.max(1)).max(1)
+}
+
+// Cell codes at `scale`, row-major, and the cell grid's size
+fn downsample(rs: &RunState, scale: u32) -> (u32, u32, Vec<char>) {
+    let gs = rs.grid_size;
+    let (w, h) = (gs.width.div_ceil(scale), gs.height.div_ceil(scale));
+    let mut cells = vec![('#', 0u8); (w * h) as usize];
+    for (i, t) in rs.tiles.iter().enumerate() {
+        let (x, y) = (i as u32 % gs.width, i as u32 / gs.width);
+        let cell = &mut cells[((y / scale) * w + x / scale) as usize];
+        let code = tile_code(&t.kind);
+        if code.1 > cell.1 {
+            *cell = code;
+        }
+    }
+    (w, h, cells.into_iter().map(|c| c.0).collect())
+}
+
+fn run_lengths(cells: &[char]) -> Vec<(u32, char)> {
+    let mut runs: Vec<(u32, char)> = Vec::new();
+    for &c in cells {
+        match runs.last_mut() {
+            Some((n, last)) if *last == c => *n += 1,
+            _ => runs.push((1, c)),
+        }
+    }
+    runs
+}
+
+pub fn snapshot_payload(rs: &RunState, title: &str, scale: u32) -> SnapshotPayload {
+    let scale = scale.max(1);
+    let (width, height, cells) = downsample(rs, scale);
+    let mut towers: Vec<(u32, u32, &'static str)> = rs
+        .towers
+        .iter()
+        .map(|tw| (tw.x / scale, tw.y / scale, tw.kind.name()))
+        .collect();
+    // Several towers in one cell draw as one
+    towers.sort_by_key(|t| (t.1, t.0));
+    towers.dedup_by_key(|t| (t.0, t.1));
+    SnapshotPayload {
+        title: title.to_string(),
+        width,
+        height,
+        scale,
+        tiles: run_lengths(&cells),
+        towers,
+        stats: SnapshotStats {
+            run_id: rs.run_id,
+            map_seed: rs.map_seed,
+            time_survived_secs: rs.stats.time_survived_secs,
+            loops_completed: rs.stats.loops_completed,
+            blocks_mined: rs.stats.blocks_mined,
+            enemies_killed: rs.stats.enemies_killed,
+            research_earned: rs.stats.research_earned,
+            towers: rs.towers.len(),
+        },
+    }
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// Fills the viewer template. `json` goes inside a <script> element, where no entity decoding
+// happens, so `<` is written as its JSON escape: nothing in the data can close the tag.
+pub fn snapshot_html(title: &str, json: &str) -> String {
+    VIEWER_TEMPLATE
+        .replace("{{TITLE}}", &escape_html(title))
+        .replace("{{DATA}}", &json.replace('<', "\\u003c"))
+}
+
+// The whole file, downsampling further until it fits; None if the payload won't serialize
+pub fn export_html(rs: &RunState, title: &str) -> Option<String> {
+    let mut scale = block_scale(rs, EXPORT_MAX_SIDE);
+    loop {
+        let payload = snapshot_payload(rs, title, scale);
+        let html = snapshot_html(title, &serde_json::to_string(&payload).ok()?);
+        if html.len() <= EXPORT_MAX_BYTES || (payload.width <= 1 && payload.height <= 1) {
+            return Some(html);
+        }
+        scale *= 2;
+    }
+}
+
+const VIEWER_TEMPLATE: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>{{TITLE}}</title>
+<style>
+html, body { margin: 0; height: 100%; background: #0e1116; color: #c9d1d9; font: 13px sans-serif; overflow: hidden; }
+#stats { position: absolute; top: 12px; left: 12px; background: rgba(22,27,34,0.9); border: 1px solid #30363d; border-radius: 8px; padding: 8px 12px; line-height: 1.6; }
+canvas { display: block; cursor: grab; }
+</style>
+</head>
+<body>
+<canvas id="view"></canvas>
+<div id="stats"></div>
+<script id="snapshot" type="application/json">{{DATA}}</script>
+<script>
+const d = JSON.parse(document.getElementById('snapshot').textContent);
+const tileColors = { '.': '#082235', '#': '#1d2430', g: '#4d3b1f', b: '#2d3f5a', W: '#2a2f38', I: '#3c4454', S: '#58a6ff', N: '#2ea043', X: '#f0883e', E: '#a371f7' };
+const towerColors = { Basic: '#ffd700', Slow: '#58a6ff', Damage: '#f85149' };
+const cells = [];
+for (const [n, c] of d.tiles) for (let i = 0; i < n; i++) cells.push(c);
+const s = d.stats;
+const mins = Math.floor(s.time_survived_secs / 60), secs = String(s.time_survived_secs % 60).padStart(2, '0');
+const lines = [d.title, 'Survived ' + mins + ':' + secs, 'Loops ' + s.loops_completed + ' · Kills ' + s.enemies_killed,
+  'Blocks mined ' + s.blocks_mined + ' · Towers ' + s.towers, 'Research earned ' + s.research_earned, 'Seed ' + s.map_seed];
+if (d.scale > 1) lines.push('1 cell = ' + d.scale + 'x' + d.scale + ' tiles');
+for (const l of lines) { const div = document.createElement('div'); div.textContent = l; document.getElementById('stats').appendChild(div); }
+const cv = document.getElementById('view'), ctx = cv.getContext('2d');
+let zoom = 1, ox = 0, oy = 0, drag = null;
+function fit() { zoom = 0.9 * Math.min(innerWidth / d.width, innerHeight / d.height); ox = 0; oy = 0; }
+function draw() {
+  cv.width = innerWidth; cv.height = innerHeight;
+  ctx.setTransform(zoom, 0, 0, zoom, cv.width / 2 + ox - d.width * zoom / 2, cv.height / 2 + oy - d.height * zoom / 2);
+  cells.forEach((c, i) => { ctx.fillStyle = tileColors[c] || '#000'; ctx.fillRect(i % d.width, Math.floor(i / d.width), 1.02, 1.02); });
+  for (const [x, y, kind] of d.towers) {
+    ctx.fillStyle = towerColors[kind] || '#ffd700';
+    ctx.beginPath(); ctx.arc(x + 0.5, y + 0.5, 0.35, 0, 2 * Math.PI); ctx.fill();
+  }
+}
+cv.addEventListener('mousedown', e => { drag = [e.clientX, e.clientY]; });
+addEventListener('mouseup', () => { drag = null; });
+addEventListener('mousemove', e => {
+  if (!drag) return;
+  ox += e.clientX - drag[0]; oy += e.clientY - drag[1]; drag = [e.clientX, e.clientY]; draw();
+});
+cv.addEventListener('wheel', e => { e.preventDefault(); zoom *= e.deltaY < 0 ? 1.15 : 1 / 1.15; draw(); }, { passive: false });
+cv.addEventListener('dblclick', () => { fit(); draw(); });
+addEventListener('resize', draw);
+fit(); draw();
+</script>
+</body>
+</html>
+"##;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{GridSize, Tower, TowerKind};
+
+    fn board(size: u32) -> RunState {
+        RunState::new_basic(GridSize {
+            width: size,
+            height: size,
+        })
+    }
+
+    fn expand(runs: &[(u32, char)]) -> Vec<char> {
+        runs.iter()
+            .flat_map(|&(n, c)| std::iter::repeat_n(c, n as usize))
+            .collect()
+    }
+
+    #[test]
+    fn small_boards_export_tile_for_tile() {
+        let mut rs = board(10);
+        rs.towers
+            .push(Tower::new(2, 3, TowerKind::Slow, 3.5, 1, None));
+        let p = snapshot_payload(&rs, "Run", block_scale(&rs, EXPORT_MAX_SIDE));
+        assert_eq!((p.width, p.height, p.scale), (10, 10, 1));
+        let cells = expand(&p.tiles);
+        let expected: Vec<char> = rs.tiles.iter().map(|t| tile_code(&t.kind).0).collect();
+        assert_eq!(cells, expected);
+        assert_eq!(p.towers, vec![(2, 3, "Slow")]);
+    }
+
+    #[test]
+    fn downsampled_cells_keep_the_loop_and_its_markers() {
+        let rs = board(40);
+        let scale = block_scale(&rs, 16);
+        assert_eq!(scale, 3);
+        let p = snapshot_payload(&rs, "Run", scale);
+        assert_eq!((p.width, p.height), (14, 14));
+        let cells = expand(&p.tiles);
+        assert_eq!(cells.len(), 14 * 14);
+        assert!(cells.contains(&'S'));
+        // Any block touching the loop shows the loop or a marker, never the rock around it
+        for (i, t) in rs.tiles.iter().enumerate() {
+            if tile_code(&t.kind).1 >= tile_code(&TileKind::Empty).1 {
+                let (x, y) = (i as u32 % 40 / scale, i as u32 / 40 / scale);
+                let cell = cells[(y * p.width + x) as usize];
+                assert!(
+                    matches!(cell, '.' | 'S' | 'E' | 'N' | 'X'),
+                    "{cell} at {x},{y}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn data_cannot_break_out_of_its_script_tag() {
+        let title = "</title><script>alert(1)</script>";
+        let json = r#"{"title":"</script><script>alert(1)</script>"}"#;
+        let html = snapshot_html(title, json);
+        // Only the template's own two script elements close
+        assert_eq!(html.matches("</script>").count(), 2);
+        assert_eq!(html.matches("</title>").count(), 1);
+        assert!(html.contains("&lt;/title&gt;"));
+        assert!(html.contains(r#""\u003c/script>\u003cscript>alert(1)\u003c/script>""#));
+    }
+
+    #[test]
+    fn huge_boards_fit_the_size_cap() {
+        let rs = board(112);
+        let html = export_html(&rs, "Run").unwrap();
+        assert!(html.len() <= EXPORT_MAX_BYTES, "{} bytes", html.len());
+        assert!(html.contains("\"scale\":2"));
+    }
+}
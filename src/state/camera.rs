@@ -8,6 +8,82 @@ pub struct Camera {
     pub last_x: f64,
     pub last_y: f64,
     pub initialized: bool,
+    // In-flight eased move of the offsets, stepped by the RAF loop
+    pub glide: Option<CameraGlide>,
+    // Held pan keys, as left/right/up/down
+    pub pan_keys: [bool; 4],
+    // Edge-scroll direction from the cursor position, each axis -1, 0 or 1
+    pub pan_edge: (f64, f64),
+    // Multiplier on PAN_PX_PER_SEC, one of PAN_SPEEDS
+    pub pan_speed: f64,
+}
+
+pub const GLIDE_MS: f64 = 600.0;
+// Keyboard and edge panning speed in screen pixels, so zoomed in the board scrolls fewer tiles
+pub const PAN_PX_PER_SEC: f64 = 720.0;
+// Pan speed hotkey steps through these
+pub const PAN_SPEEDS: [f64; 3] = [1.0, 2.0, 0.5];
+// Cursor within this many pixels of the canvas edge scrolls toward it
+pub const EDGE_SCROLL_PX: f64 = 20.0;
+// Longest frame gap a held key pans across; a stalled tab shouldn't fling the view
+const PAN_MAX_DT: f64 = 0.1;
+// Share of the grid, per axis, that panning and zooming must leave on screen
+pub const CLAMP_VISIBLE: f64 = 0.25;
+
+// Clamped offset along one axis for a grid `extent` px long on a `view` px canvas
+fn clamp_axis(offset: f64, extent: f64, view: f64) -> f64 {
+    if extent <= view {
+        return (view - extent) / 2.0;
+    }
+    let keep = (extent * CLAMP_VISIBLE).min(view);
+    offset.clamp(keep - extent, view - keep)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PanDir {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+impl PanDir {
+    // WASD by physical key, so other layouts pan from the same spot; arrows only with Shift,
+    // since plain arrows move the keyboard cursor
+    pub fn from_code(code: &str, shift: bool) -> Option<Self> {
+        match code {
+            "KeyA" => Some(Self::Left),
+            "KeyD" => Some(Self::Right),
+            "KeyW" => Some(Self::Up),
+            "KeyS" => Some(Self::Down),
+            "ArrowLeft" if shift => Some(Self::Left),
+            "ArrowRight" if shift => Some(Self::Right),
+            "ArrowUp" if shift => Some(Self::Up),
+            "ArrowDown" if shift => Some(Self::Down),
+            _ => None,
+        }
+    }
+}
+
+// Direction to edge-scroll for a cursor at (x, y) on a w x h canvas
+pub fn edge_scroll_dir(x: f64, y: f64, w: f64, h: f64) -> (f64, f64) {
+    let axis = |p: f64, len: f64| {
+        if p < EDGE_SCROLL_PX {
+            -1.0
+        } else if p > len - EDGE_SCROLL_PX {
+            1.0
+        } else {
+            0.0
+        }
+    };
+    (axis(x, w), axis(y, h))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraGlide {
+    from: (f64, f64),
+    to: (f64, f64),
+    zoom: (f64, f64),
+    start_ms: f64,
 }
 impl Default for Camera {
     fn default() -> Self {
@@ -19,6 +95,246 @@ impl Default for Camera {
             last_x: 0.0,
             last_y: 0.0,
             initialized: false,
+            glide: None,
+            pan_keys: [false; 4],
+            pan_edge: (0.0, 0.0),
+            pan_speed: 1.0,
         }
     }
 }
+
+impl Camera {
+    // Ease the offsets to (x, y) over GLIDE_MS; a new glide replaces one in flight
+    pub fn glide_to(&mut self, x: f64, y: f64, now_ms: f64) {
+        self.glide_to_view(x, y, self.zoom, now_ms);
+    }
+
+    // As glide_to, easing the zoom along with the offsets
+    pub fn glide_to_view(&mut self, x: f64, y: f64, zoom: f64, now_ms: f64) {
+        self.glide = Some(CameraGlide {
+            from: (self.offset_x, self.offset_y),
+            to: (x, y),
+            zoom: (self.zoom, zoom),
+            start_ms: now_ms,
+        });
+    }
+
+    // Moves the offsets along the glide; true while one is still in flight
+    pub fn step_glide(&mut self, now_ms: f64) -> bool {
+        let Some(g) = self.glide else {
+            return false;
+        };
+        let t = ((now_ms - g.start_ms) / GLIDE_MS).clamp(0.0, 1.0);
+        // Ease out cubic: quick start, gentle landing
+        let k = 1.0 - (1.0 - t).powi(3);
+        self.offset_x = g.from.0 + (g.to.0 - g.from.0) * k;
+        self.offset_y = g.from.1 + (g.to.1 - g.from.1) * k;
+        self.zoom = g.zoom.0 + (g.zoom.1 - g.zoom.0) * k;
+        if t >= 1.0 {
+            self.glide = None;
+        }
+        self.glide.is_some()
+    }
+
+    // Puts world position (x, y), in tiles, at the centre of a canvas_w x canvas_h canvas,
+    // cancelling any glide
+    pub fn center_on(&mut self, x: f64, y: f64, canvas_w: f64, canvas_h: f64, tile_px: f64) {
+        let scale = self.zoom * tile_px;
+        self.offset_x = canvas_w * 0.5 - scale * x;
+        self.offset_y = canvas_h * 0.5 - scale * y;
+        self.glide = None;
+    }
+
+    // Keeps CLAMP_VISIBLE of the grid on screen along each axis at the current zoom; an axis
+    // the whole grid fits on is centred instead, so a small board can't drift into a corner
+    pub fn clamp_to_grid(
+        &mut self,
+        grid_w: u32,
+        grid_h: u32,
+        canvas_w: f64,
+        canvas_h: f64,
+        tile_px: f64,
+    ) {
+        let scale = self.zoom * tile_px;
+        self.offset_x = clamp_axis(self.offset_x, grid_w as f64 * scale, canvas_w);
+        self.offset_y = clamp_axis(self.offset_y, grid_h as f64 * scale, canvas_h);
+    }
+
+    pub fn hold_pan(&mut self, dir: PanDir, down: bool) {
+        self.pan_keys[dir as usize] = down;
+    }
+
+    // Window blur: keyup never arrives for keys released elsewhere
+    pub fn release_pan(&mut self) {
+        self.pan_keys = [false; 4];
+        self.pan_edge = (0.0, 0.0);
+    }
+
+    // Direction the view is moving in screen pixels per second; diagonals aren't faster
+    pub fn pan_velocity(&self) -> (f64, f64) {
+        let [l, r, u, d] = self.pan_keys.map(f64::from);
+        let x = (r - l + self.pan_edge.0).clamp(-1.0, 1.0);
+        let y = (d - u + self.pan_edge.1).clamp(-1.0, 1.0);
+        let len = x.hypot(y);
+        if len == 0.0 {
+            return (0.0, 0.0);
+        }
+        let speed = PAN_PX_PER_SEC * self.pan_speed;
+        (x / len * speed, y / len * speed)
+    }
+
+    // Next of PAN_SPEEDS, returning it for the toast
+    pub fn cycle_pan_speed(&mut self) -> f64 {
+        let i = PAN_SPEEDS.iter().position(|s| *s == self.pan_speed);
+        self.pan_speed = PAN_SPEEDS[i.map_or(0, |i| (i + 1) % PAN_SPEEDS.len())];
+        self.pan_speed
+    }
+
+    // Pans by `dt` seconds of held keys and edge scrolling; true if the view moved. Panning
+    // takes over from a glide in flight.
+    pub fn apply_pan(&mut self, dt: f64) -> bool {
+        let (vx, vy) = self.pan_velocity();
+        if (vx, vy) == (0.0, 0.0) || self.panning {
+            return false;
+        }
+        let dt = dt.clamp(0.0, PAN_MAX_DT);
+        self.glide = None;
+        self.offset_x -= vx * dt;
+        self.offset_y -= vy * dt;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn held_keys_pan_at_a_fixed_screen_speed() {
+        let mut cam = Camera::default();
+        assert!(!cam.apply_pan(0.016));
+        cam.hold_pan(PanDir::Right, true);
+        assert!(cam.apply_pan(0.5));
+        // The view moves right, so the board slides left
+        assert_eq!((cam.offset_x, cam.offset_y), (-PAN_PX_PER_SEC * 0.1, 0.0));
+        cam.zoom = 6.0;
+        cam.offset_x = 0.0;
+        cam.apply_pan(0.05);
+        assert_eq!(cam.offset_x, -PAN_PX_PER_SEC * 0.05);
+        // Opposite keys cancel; releasing stops
+        cam.hold_pan(PanDir::Left, true);
+        assert!(!cam.apply_pan(0.05));
+        cam.hold_pan(PanDir::Left, false);
+        cam.hold_pan(PanDir::Right, false);
+        assert_eq!(cam.pan_velocity(), (0.0, 0.0));
+    }
+
+    #[test]
+    fn diagonals_and_edges_share_the_speed_cap() {
+        let mut cam = Camera::default();
+        cam.hold_pan(PanDir::Up, true);
+        cam.hold_pan(PanDir::Left, true);
+        let (vx, vy) = cam.pan_velocity();
+        assert!((vx.hypot(vy) - PAN_PX_PER_SEC).abs() < 1e-9);
+        assert!(vx < 0.0 && vy < 0.0);
+        // The edge adds to keys on the same axis without going faster
+        cam.pan_edge = (-1.0, 0.0);
+        assert!((cam.pan_velocity().0 - vx).abs() < 1e-9);
+        cam.release_pan();
+        assert_eq!(cam.pan_velocity(), (0.0, 0.0));
+    }
+
+    #[test]
+    fn pan_speed_cycles_and_scales_the_velocity() {
+        let mut cam = Camera::default();
+        cam.hold_pan(PanDir::Right, true);
+        assert_eq!(cam.cycle_pan_speed(), 2.0);
+        assert_eq!(cam.pan_velocity(), (PAN_PX_PER_SEC * 2.0, 0.0));
+        assert_eq!(cam.cycle_pan_speed(), 0.5);
+        assert_eq!(cam.cycle_pan_speed(), 1.0);
+    }
+
+    #[test]
+    fn edge_scroll_only_near_the_border() {
+        assert_eq!(edge_scroll_dir(400.0, 300.0, 800.0, 600.0), (0.0, 0.0));
+        assert_eq!(edge_scroll_dir(5.0, 300.0, 800.0, 600.0), (-1.0, 0.0));
+        assert_eq!(edge_scroll_dir(795.0, 590.0, 800.0, 600.0), (1.0, 1.0));
+    }
+
+    #[test]
+    fn panning_cancels_a_glide_and_yields_to_drag() {
+        let mut cam = Camera::default();
+        cam.glide_to(100.0, 100.0, 0.0);
+        cam.hold_pan(PanDir::Down, true);
+        assert!(cam.apply_pan(0.016));
+        assert!(cam.glide.is_none());
+        cam.panning = true;
+        assert!(!cam.apply_pan(0.016));
+    }
+
+    #[test]
+    fn small_grid_stays_centred() {
+        // 10x8 tiles at zoom 1 is 320x256 px on an 800x600 canvas
+        let mut cam = Camera {
+            zoom: 1.0,
+            offset_x: -5000.0,
+            offset_y: 900.0,
+            ..Camera::default()
+        };
+        cam.clamp_to_grid(10, 8, 800.0, 600.0, 32.0);
+        assert_eq!((cam.offset_x, cam.offset_y), (240.0, 172.0));
+        // Clamping again, or after a nudge, lands on the same spot
+        cam.offset_x += 3.0;
+        cam.clamp_to_grid(10, 8, 800.0, 600.0, 32.0);
+        assert_eq!((cam.offset_x, cam.offset_y), (240.0, 172.0));
+    }
+
+    #[test]
+    fn large_grid_keeps_a_quarter_on_screen() {
+        // 100x100 tiles at zoom 1 is 3200 px a side; a quarter is 800 px
+        let mut cam = Camera {
+            zoom: 1.0,
+            ..Camera::default()
+        };
+        cam.offset_x = -10_000.0;
+        cam.offset_y = 10_000.0;
+        cam.clamp_to_grid(100, 100, 1000.0, 900.0, 32.0);
+        assert_eq!((cam.offset_x, cam.offset_y), (-2400.0, 100.0));
+        // Inside the bounds nothing moves
+        cam.offset_x = -1000.0;
+        cam.clamp_to_grid(100, 100, 1000.0, 900.0, 32.0);
+        assert_eq!(cam.offset_x, -1000.0);
+        // A quarter wider than the canvas: the grid must cover it
+        cam.offset_x = 500.0;
+        cam.clamp_to_grid(100, 100, 600.0, 900.0, 32.0);
+        assert_eq!(cam.offset_x, 0.0);
+        cam.offset_x = -3000.0;
+        cam.clamp_to_grid(100, 100, 600.0, 900.0, 32.0);
+        assert_eq!(cam.offset_x, -2600.0);
+    }
+
+    #[test]
+    fn clamp_follows_the_zoom() {
+        // 20 tiles fit an 800 px canvas at zoom 1 but not at zoom 2
+        let mut cam = Camera {
+            zoom: 1.0,
+            offset_x: -600.0,
+            ..Camera::default()
+        };
+        cam.clamp_to_grid(20, 20, 800.0, 800.0, 32.0);
+        assert_eq!(cam.offset_x, 80.0);
+        cam.zoom = 2.0;
+        cam.offset_x = -1000.0;
+        cam.clamp_to_grid(20, 20, 800.0, 800.0, 32.0);
+        assert_eq!(cam.offset_x, -960.0);
+    }
+
+    #[test]
+    fn wasd_always_pans_and_arrows_need_shift() {
+        assert_eq!(PanDir::from_code("KeyW", false), Some(PanDir::Up));
+        assert_eq!(PanDir::from_code("ArrowLeft", false), None);
+        assert_eq!(PanDir::from_code("ArrowLeft", true), Some(PanDir::Left));
+        assert_eq!(PanDir::from_code("Space", false), None);
+        assert_eq!(PanDir::from_code("KeyT", false), None);
+    }
+}
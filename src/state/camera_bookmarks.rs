@@ -0,0 +1,206 @@
+// Camera bookmarks: four saved views (offsets + zoom) for the current run, kept in memory only.
+// Ctrl+1..4 saves the view into a slot and Alt+1..4 glides back to it; bare digits stay with
+// the tower-kind selector.
+use super::camera::Camera;
+
+pub const BOOKMARK_SLOTS: usize = 4;
+const LABEL_MAX_CHARS: usize = 16;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bookmark {
+    pub label: String,
+    pub offset_x: f64,
+    pub offset_y: f64,
+    pub zoom: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BookmarkKey {
+    Save(usize),
+    Recall(usize),
+}
+
+// Digit row by physical key, so Alt/Ctrl layouts that change `key` still match. Shift and Meta
+// combos are left to the browser.
+pub fn bookmark_key(
+    code: &str,
+    ctrl: bool,
+    alt: bool,
+    shift: bool,
+    meta: bool,
+) -> Option<BookmarkKey> {
+    let slot = match code {
+        "Digit1" => 0,
+        "Digit2" => 1,
+        "Digit3" => 2,
+        "Digit4" => 3,
+        _ => return None,
+    };
+    match (ctrl, alt, shift || meta) {
+        (true, false, false) => Some(BookmarkKey::Save(slot)),
+        (false, true, false) => Some(BookmarkKey::Recall(slot)),
+        _ => None,
+    }
+}
+
+// Input types that take typed text; hotkeys stand down while one has focus
+pub fn is_text_entry(input_type: &str) -> bool {
+    matches!(
+        input_type,
+        "text" | "search" | "number" | "email" | "password" | "url" | "tel"
+    )
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Bookmarks {
+    run_id: u64,
+    slots: [Option<Bookmark>; BOOKMARK_SLOTS],
+}
+
+impl Bookmarks {
+    // Slots from an earlier run point at a different map; they read as empty
+    pub fn get(&self, run_id: u64, slot: usize) -> Option<&Bookmark> {
+        self.slots
+            .get(slot)
+            .and_then(Option::as_ref)
+            .filter(|_| self.run_id == run_id)
+    }
+
+    // Overwriting a slot keeps its name
+    pub fn save(&mut self, run_id: u64, slot: usize, cam: &Camera) {
+        if slot >= BOOKMARK_SLOTS {
+            return;
+        }
+        if self.run_id != run_id {
+            *self = Self {
+                run_id,
+                ..Self::default()
+            };
+        }
+        let label = self.slots[slot]
+            .take()
+            .map_or_else(|| default_label(slot), |b| b.label);
+        self.slots[slot] = Some(Bookmark {
+            label,
+            offset_x: cam.offset_x,
+            offset_y: cam.offset_y,
+            zoom: cam.zoom,
+        });
+    }
+
+    // Blank names fall back to the default; long ones are cut
+    pub fn rename(&mut self, run_id: u64, slot: usize, label: &str) {
+        if self.run_id != run_id {
+            return;
+        }
+        if let Some(b) = self.slots.get_mut(slot).and_then(Option::as_mut) {
+            let label = label.trim();
+            b.label = if label.is_empty() {
+                default_label(slot)
+            } else {
+                label.chars().take(LABEL_MAX_CHARS).collect()
+            };
+        }
+    }
+
+    // Starts a glide to the slot's view; false for an empty slot
+    pub fn recall(&self, run_id: u64, slot: usize, cam: &mut Camera, now_ms: f64) -> bool {
+        let Some(b) = self.get(run_id, slot) else {
+            return false;
+        };
+        cam.glide_to_view(b.offset_x, b.offset_y, b.zoom, now_ms);
+        true
+    }
+}
+
+fn default_label(slot: usize) -> String {
+    format!("View {}", slot + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::camera::GLIDE_MS;
+
+    fn cam_at(x: f64, y: f64, zoom: f64) -> Camera {
+        Camera {
+            offset_x: x,
+            offset_y: y,
+            zoom,
+            ..Camera::default()
+        }
+    }
+
+    #[test]
+    fn modifiers_pick_save_or_recall_and_bare_digits_stay_tower_keys() {
+        use BookmarkKey::*;
+        assert_eq!(
+            bookmark_key("Digit1", true, false, false, false),
+            Some(Save(0))
+        );
+        assert_eq!(
+            bookmark_key("Digit4", false, true, false, false),
+            Some(Recall(3))
+        );
+        assert_eq!(bookmark_key("Digit2", false, false, false, false), None);
+        assert_eq!(bookmark_key("Digit5", true, false, false, false), None);
+        assert_eq!(bookmark_key("Digit1", true, true, false, false), None);
+        assert_eq!(bookmark_key("Digit1", true, false, true, false), None);
+        assert_eq!(bookmark_key("Digit1", false, true, false, true), None);
+        assert_eq!(bookmark_key("Numpad1", true, false, false, false), None);
+    }
+
+    #[test]
+    fn only_typing_fields_swallow_hotkeys() {
+        assert!(is_text_entry("text"));
+        assert!(is_text_entry("number"));
+        assert!(!is_text_entry("checkbox"));
+        assert!(!is_text_entry("range"));
+    }
+
+    #[test]
+    fn recall_glides_back_to_the_saved_view() {
+        let mut marks = Bookmarks::default();
+        let mut cam = cam_at(-120.0, 40.0, 1.5);
+        marks.save(3, 1, &cam);
+        cam = cam_at(300.0, -80.0, 4.0);
+        assert!(!marks.recall(3, 0, &mut cam, 0.0));
+        assert!(marks.recall(3, 1, &mut cam, 1000.0));
+        assert!(cam.step_glide(1000.0 + GLIDE_MS / 2.0));
+        assert!(!cam.step_glide(1000.0 + GLIDE_MS));
+        assert_eq!((cam.offset_x, cam.offset_y, cam.zoom), (-120.0, 40.0, 1.5));
+    }
+
+    #[test]
+    fn slots_belong_to_one_run() {
+        let mut marks = Bookmarks::default();
+        marks.save(3, 0, &cam_at(1.0, 2.0, 2.0));
+        marks.save(3, 2, &cam_at(5.0, 6.0, 3.0));
+        assert!(marks.get(3, 2).is_some());
+        // Nothing carries over to the next run, and renaming across runs is ignored
+        assert!(marks.get(4, 0).is_none());
+        marks.rename(4, 0, "Gate");
+        assert_eq!(marks.get(3, 0).unwrap().label, "View 1");
+        marks.save(4, 1, &cam_at(0.0, 0.0, 1.0));
+        assert!(marks.get(4, 0).is_none() && marks.get(4, 1).is_some());
+    }
+
+    #[test]
+    fn renamed_slots_keep_their_name_when_saved_over() {
+        let mut marks = Bookmarks::default();
+        marks.rename(1, 0, "Kill box");
+        assert!(marks.get(1, 0).is_none());
+        marks.save(1, 0, &cam_at(1.0, 1.0, 2.0));
+        marks.rename(1, 0, "  Kill box  ");
+        marks.save(1, 0, &cam_at(9.0, 9.0, 2.0));
+        let b = marks.get(1, 0).unwrap();
+        assert_eq!((b.label.as_str(), b.offset_x), ("Kill box", 9.0));
+        marks.rename(1, 0, "   ");
+        assert_eq!(marks.get(1, 0).unwrap().label, "View 1");
+        marks.rename(1, 0, "A very long bookmark name");
+        assert_eq!(
+            marks.get(1, 0).unwrap().label.chars().count(),
+            LABEL_MAX_CHARS
+        );
+    }
+}
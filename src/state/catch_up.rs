@@ -0,0 +1,66 @@
+// Bounded catch-up after the tab was hidden (see model::plan_catch_up)
+use crate::model::{
+    plan_catch_up, plan_long_catch_up, CatchUpMode, RunAction, RunDigest, RunState,
+};
+use std::collections::VecDeque;
+
+#[derive(Default, Debug, Clone)]
+pub struct CatchUp {
+    hidden_at_ms: Option<f64>,
+    batches: VecDeque<u32>,
+    step_secs: f64,
+    mode: CatchUpMode,
+    discarded_secs: f64,
+    before: Option<RunState>,
+}
+
+impl CatchUp {
+    pub fn on_hidden(&mut self, now_ms: f64) {
+        self.hidden_at_ms = Some(now_ms);
+    }
+
+    // Queues the catch-up for `mode`; only a live, running sim is caught up
+    pub fn on_visible(&mut self, now_ms: f64, rs: &RunState, mode: CatchUpMode) {
+        let Some(hidden_at) = self.hidden_at_ms.take() else {
+            return;
+        };
+        if !rs.started || rs.is_paused || rs.game_over || self.before.is_some() {
+            return;
+        }
+        let elapsed_secs = (now_ms - hidden_at) / 1000.0;
+        let plan = match mode {
+            CatchUpMode::Capped => plan_catch_up(elapsed_secs),
+            CatchUpMode::Long => plan_long_catch_up(elapsed_secs),
+        };
+        if plan.batches.is_empty() {
+            return;
+        }
+        self.batches = plan.batches.into();
+        self.step_secs = plan.step_secs;
+        self.mode = mode;
+        self.discarded_secs = plan.discarded_secs;
+        self.before = Some(rs.clone());
+    }
+
+    // This frame's share of the missed time, as one RunAction::CatchUp
+    pub fn next_batch(&mut self) -> Option<RunAction> {
+        self.batches.pop_front().map(|n| RunAction::CatchUp {
+            elapsed_secs: n as f64 * self.step_secs,
+            mode: self.mode,
+        })
+    }
+
+    // Once the queue drains, the toast text describing what happened
+    pub fn finish(&mut self, rs: &RunState) -> Option<String> {
+        if !self.batches.is_empty() {
+            return None;
+        }
+        let before = self.before.take()?;
+        let digest = RunDigest::between(&before, rs);
+        let mut msg = format!("While you were away: {}", digest.summary());
+        if self.discarded_secs >= 1.0 {
+            msg.push_str(&format!(" ({:.0}s skipped)", self.discarded_secs));
+        }
+        Some(msg)
+    }
+}
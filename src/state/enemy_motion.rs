@@ -0,0 +1,161 @@
+// Enemy motion between sim steps. The sim moves enemies on its own interval and board frames
+// land in between, so run_view keeps where each enemy stood in the state before the one being
+// drawn, keyed by enemy id, and draws it partway along by how much of the interval has gone by.
+// Pure, so the bookkeeping can be tested natively.
+use std::collections::HashMap;
+
+use crate::model::{Enemy, EntityId, RunState};
+
+// A step longer than this (tiles) is a jump rather than a walk, drawn where it landed
+pub const MAX_LERP_STEP: f64 = 1.0;
+
+// `from` at t=0, `to` at t=1; t outside that is held to the nearer end
+pub fn lerp_pos(from: (f64, f64), to: (f64, f64), t: f64) -> (f64, f64) {
+    let t = t.clamp(0.0, 1.0);
+    (from.0 + (to.0 - from.0) * t, from.1 + (to.1 - from.1) * t)
+}
+
+#[derive(Debug, Clone)]
+pub struct EnemyMotion {
+    // Wall-clock gap between sim steps, ms
+    interval_ms: f64,
+    // (run_id, sim_time) of the newest state observed
+    seen: Option<(u64, f64)>,
+    // Positions in the newest state, and in the one before it
+    cur: HashMap<EntityId, (f64, f64)>,
+    prev: HashMap<EntityId, (f64, f64)>,
+    // When the newest state was first observed, and the frame being drawn
+    stepped_at_ms: f64,
+    frame_ms: f64,
+}
+
+impl EnemyMotion {
+    pub fn new(interval_ms: f64) -> Self {
+        Self {
+            interval_ms: interval_ms.max(1.0),
+            seen: None,
+            cur: HashMap::new(),
+            prev: HashMap::new(),
+            stepped_at_ms: 0.0,
+            frame_ms: 0.0,
+        }
+    }
+
+    // Takes in the state about to be drawn, once per frame. A sim step moves the positions it
+    // replaced into `prev`; another run or a rewind has nothing to come from, and a board that
+    // stopped (paused, not started, game over) is drawn where everyone actually is.
+    pub fn observe(&mut self, rs: &RunState, now_ms: f64) {
+        self.frame_ms = now_ms;
+        let key = (rs.run_id, rs.sim_time);
+        if self.seen == Some(key) {
+            return;
+        }
+        let stepped = matches!(self.seen, Some((run, t)) if run == rs.run_id && t < rs.sim_time);
+        let positions = rs.enemies.iter().map(|e| (e.id, (e.x, e.y))).collect();
+        let last = std::mem::replace(&mut self.cur, positions);
+        self.prev = if stepped && rs.started && !rs.is_paused && !rs.game_over {
+            last
+        } else {
+            HashMap::new()
+        };
+        self.seen = Some(key);
+        self.stepped_at_ms = now_ms;
+    }
+
+    // Where to draw `e` this frame: on its way from the previous state to the current one.
+    // New arrivals and jumps are drawn where they are.
+    pub fn position(&self, e: &Enemy) -> (f64, f64) {
+        let to = (e.x, e.y);
+        match self.prev.get(&e.id) {
+            Some(&from) if (to.0 - from.0).hypot(to.1 - from.1) <= MAX_LERP_STEP => {
+                let t = (self.frame_ms - self.stepped_at_ms) / self.interval_ms;
+                lerp_pos(from, to, t)
+            }
+            _ => to,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{GridSize, UpgradeState};
+
+    fn run() -> RunState {
+        let gs = GridSize {
+            width: 12,
+            height: 12,
+        };
+        let mut rs = RunState::new_with_upgrades_seeded(gs, &UpgradeState::default(), 5);
+        rs.started = true;
+        rs
+    }
+
+    fn enemy(id: u64, x: f64) -> Enemy {
+        Enemy {
+            id: EntityId(id),
+            x,
+            y: 2.0,
+            speed_tps: 1.0,
+            hp: 5,
+            max_hp: 5,
+            spawned_at: 0,
+            path_index: 0,
+            dir_dx: 1.0,
+            dir_dy: 0.0,
+            radius_scale: 1.0,
+            loop_dist: 0.0,
+            debuffs: Vec::new(),
+            kind: Default::default(),
+            hit_flash_ttl: 0.0,
+            spawn_anim_ttl: 0.0,
+        }
+    }
+
+    #[test]
+    fn lerp_returns_the_endpoints_at_zero_and_one() {
+        let (a, b) = ((1.0, 2.0), (3.0, -2.0));
+        assert_eq!(lerp_pos(a, b, 0.0), a);
+        assert_eq!(lerp_pos(a, b, 1.0), b);
+        assert_eq!(lerp_pos(a, b, 0.5), (2.0, 0.0));
+        assert_eq!(lerp_pos(a, b, -1.0), a);
+        assert_eq!(lerp_pos(a, b, 4.0), b);
+    }
+
+    #[test]
+    fn enemies_are_drawn_between_the_last_two_steps() {
+        let mut rs = run();
+        rs.enemies = vec![enemy(1, 2.0), enemy(2, 5.0)];
+        let mut m = EnemyMotion::new(16.0);
+        m.observe(&rs, 100.0);
+        // Nothing to come from yet
+        assert_eq!(m.position(&rs.enemies[0]), (2.0, 2.0));
+
+        rs.sim_time += 0.016;
+        rs.enemies[0].x = 2.2;
+        // The second one jumped (catch-up, a teleport): no streak across the board
+        rs.enemies[1].x = 9.0;
+        rs.enemies.push(enemy(3, 1.0));
+        m.observe(&rs, 200.0);
+        assert_eq!(m.position(&rs.enemies[0]), (2.0, 2.0));
+        m.observe(&rs, 208.0);
+        let (x, _) = m.position(&rs.enemies[0]);
+        assert!((x - 2.1).abs() < 1e-9, "{x}");
+        assert_eq!(m.position(&rs.enemies[1]), (9.0, 2.0));
+        assert_eq!(m.position(&rs.enemies[2]), (1.0, 2.0));
+        // Frames past the interval hold at the current state
+        m.observe(&rs, 240.0);
+        assert_eq!(m.position(&rs.enemies[0]), (2.2, 2.0));
+
+        // Paused, or rewound, the board shows where everyone is
+        let mut paused = rs.clone();
+        paused.sim_time += 0.016;
+        paused.enemies[0].x = 2.4;
+        paused.is_paused = true;
+        m.observe(&paused, 300.0);
+        assert_eq!(m.position(&paused.enemies[0]), (2.4, 2.0));
+        rs.sim_time -= 1.0;
+        m.observe(&rs, 400.0);
+        assert_eq!(m.position(&rs.enemies[0]), (2.2, 2.0));
+    }
+}
@@ -0,0 +1,8 @@
+// Targeting exclusion brush ('X'): drag paints no-target tiles without any mining time
+#[derive(Default, Debug, Clone)]
+pub struct ExclusionBrush {
+    pub active: bool,
+    // Value the current stroke paints, taken from the first tile it touched
+    pub stroke: Option<bool>,
+    pub last_tile: (i32, i32),
+}
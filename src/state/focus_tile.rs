@@ -0,0 +1,165 @@
+// The tile every hover-driven system reads (feedback, legend, tile info, hotkeys).
+// Whichever input moved it last owns it: the mouse, the arrow-key cursor, or a gamepad D-pad.
+use crate::model::GridSize;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FocusSource {
+    #[default]
+    None,
+    Mouse,
+    Keyboard,
+    Gamepad,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FocusTile {
+    pub source: FocusSource,
+    tile: (i32, i32),
+    // Last tile the pointer reported, so a mousemove that stays on it can't steal focus back
+    mouse_tile: (i32, i32),
+}
+
+impl Default for FocusTile {
+    fn default() -> Self {
+        Self {
+            source: FocusSource::None,
+            tile: (-1, -1),
+            mouse_tile: (-1, -1),
+        }
+    }
+}
+
+impl FocusTile {
+    // Current tile, (-1, -1) when nothing has focus
+    pub fn tile(&self) -> (i32, i32) {
+        self.tile
+    }
+
+    // The focused tile if it lies on the grid
+    pub fn in_grid(&self, gs: GridSize) -> Option<(u32, u32)> {
+        let (x, y) = self.tile;
+        (x >= 0 && y >= 0 && (x as u32) < gs.width && (y as u32) < gs.height)
+            .then_some((x as u32, y as u32))
+    }
+
+    pub fn set(&mut self, source: FocusSource, tile: (i32, i32)) {
+        self.source = source;
+        self.tile = tile;
+    }
+
+    // Pointer moved; only a move onto a different tile takes focus from another input
+    pub fn on_mouse(&mut self, tile: (i32, i32)) {
+        if self.source == FocusSource::Mouse || tile != self.mouse_tile {
+            self.set(FocusSource::Mouse, tile);
+        }
+        self.mouse_tile = tile;
+    }
+
+    // Arrow-key cursor: steps from the current focus (or the grid centre) and stays on the grid
+    pub fn step(&mut self, source: FocusSource, dx: i32, dy: i32, gs: GridSize) {
+        let (x, y) = match self.in_grid(gs) {
+            Some((x, y)) => (x as i32, y as i32),
+            None => (gs.width as i32 / 2 - dx, gs.height as i32 / 2 - dy),
+        };
+        let nx = (x + dx).clamp(0, gs.width as i32 - 1);
+        let ny = (y + dy).clamp(0, gs.height as i32 - 1);
+        self.set(source, (nx, ny));
+    }
+}
+
+// Standard-mapping D-pad buttons, and the step each one takes
+pub const GAMEPAD_DPAD: [(usize, (i32, i32)); 4] =
+    [(12, (0, -1)), (13, (0, 1)), (14, (-1, 0)), (15, (1, 0))];
+
+// D-pad state between polls: a press steps the focus once, holding it doesn't repeat
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GamepadDpad {
+    held: [bool; 4],
+}
+
+impl GamepadDpad {
+    // `pressed` in GAMEPAD_DPAD order; the step for the buttons newly down since the last poll
+    pub fn poll(&mut self, pressed: [bool; 4]) -> Option<(i32, i32)> {
+        let mut step = (0, 0);
+        for (i, &(_, (dx, dy))) in GAMEPAD_DPAD.iter().enumerate() {
+            if pressed[i] && !self.held[i] {
+                step = (step.0 + dx, step.1 + dy);
+            }
+        }
+        self.held = pressed;
+        (step != (0, 0)).then_some(step)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GS: GridSize = GridSize {
+        width: 9,
+        height: 9,
+    };
+
+    #[test]
+    fn last_input_wins() {
+        let mut f = FocusTile::default();
+        assert_eq!(f.in_grid(GS), None);
+        f.on_mouse((2, 3));
+        assert_eq!((f.source, f.tile()), (FocusSource::Mouse, (2, 3)));
+        f.step(FocusSource::Keyboard, 1, 0, GS);
+        assert_eq!((f.source, f.tile()), (FocusSource::Keyboard, (3, 3)));
+        f.set(FocusSource::Gamepad, (5, 5));
+        assert_eq!(f.source, FocusSource::Gamepad);
+        f.on_mouse((6, 3));
+        assert_eq!((f.source, f.tile()), (FocusSource::Mouse, (6, 3)));
+    }
+
+    #[test]
+    fn hotkeys_see_keyboard_focus_while_mouse_is_still() {
+        let mut f = FocusTile::default();
+        f.on_mouse((4, 4));
+        f.step(FocusSource::Keyboard, 0, -1, GS);
+        f.step(FocusSource::Keyboard, 0, -1, GS);
+        // Stray mousemoves on the same tile (scroll, relayout) keep the keyboard's tile
+        f.on_mouse((4, 4));
+        assert_eq!(f.in_grid(GS), Some((4, 2)));
+        assert_eq!(f.source, FocusSource::Keyboard);
+        // Within mouse focus every move counts, including back onto the old tile
+        f.on_mouse((4, 5));
+        f.on_mouse((4, 4));
+        assert_eq!(f.in_grid(GS), Some((4, 4)));
+    }
+
+    #[test]
+    fn dpad_steps_once_per_press() {
+        let mut pad = GamepadDpad::default();
+        let right = [false, false, false, true];
+        assert_eq!(pad.poll(right), Some((1, 0)));
+        // Held across frames: no repeat until it's let go
+        assert_eq!(pad.poll(right), None);
+        assert_eq!(pad.poll([false; 4]), None);
+        assert_eq!(pad.poll(right), Some((1, 0)));
+        // Up joins the held right: only the new press counts
+        assert_eq!(pad.poll([true, false, false, true]), Some((0, -1)));
+        let mut f = FocusTile::default();
+        f.on_mouse((2, 2));
+        f.step(FocusSource::Gamepad, 1, 0, GS);
+        assert_eq!((f.source, f.tile()), (FocusSource::Gamepad, (3, 2)));
+    }
+
+    #[test]
+    fn keyboard_cursor_starts_centred_and_clamps() {
+        let mut f = FocusTile::default();
+        f.step(FocusSource::Keyboard, 1, 0, GS);
+        assert_eq!(f.tile(), (4, 4));
+        for _ in 0..20 {
+            f.step(FocusSource::Keyboard, -1, 1, GS);
+        }
+        assert_eq!(f.tile(), (0, 8));
+        // Off-grid mouse focus restarts the cursor in the middle
+        f.on_mouse((-3, 12));
+        assert_eq!(f.in_grid(GS), None);
+        f.step(FocusSource::Keyboard, 0, 1, GS);
+        assert_eq!(f.tile(), (4, 4));
+    }
+}
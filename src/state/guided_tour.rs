@@ -0,0 +1,151 @@
+// First-visit walkthroughs: an ordered list of steps shown one at a time, advanced by Next or
+// by clicking the spotlighted element, and ended by finishing, skipping, or acting for real
+// (a purchase during the upgrades tour). Whether a tour was seen lives under its own
+// localStorage key, written by the view once the tour ends.
+use crate::model::{UpgradeId, UpgradeState, UPGRADE_DEFS};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TourEvent {
+    Next,
+    TargetClicked,
+    Skip,
+    Purchased,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GuidedTour<S: Copy + 'static> {
+    steps: &'static [S],
+    at: Option<usize>,
+}
+
+impl<S: Copy + 'static> GuidedTour<S> {
+    // A tour already seen starts finished
+    pub fn new(steps: &'static [S], seen: bool) -> Self {
+        Self {
+            steps,
+            at: (!seen && !steps.is_empty()).then_some(0),
+        }
+    }
+
+    pub fn current(&self) -> Option<S> {
+        self.at.map(|i| self.steps[i])
+    }
+
+    // (step, total), 1-based for display
+    pub fn progress(&self) -> Option<(usize, usize)> {
+        self.at.map(|i| (i + 1, self.steps.len()))
+    }
+
+    // Returns true when this event ended the tour, the cue to persist it as seen
+    pub fn handle(&mut self, event: TourEvent) -> bool {
+        let Some(i) = self.at else {
+            return false;
+        };
+        self.at = match event {
+            TourEvent::Next | TourEvent::TargetClicked => {
+                Some(i + 1).filter(|&n| n < self.steps.len())
+            }
+            TourEvent::Skip | TourEvent::Purchased => None,
+        };
+        self.at.is_none()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UpgradesTourStep {
+    Research,
+    RootNode,
+    LockedNode,
+    Back,
+}
+
+pub const UPGRADES_TOUR: &[UpgradesTourStep] = &[
+    UpgradesTourStep::Research,
+    UpgradesTourStep::RootNode,
+    UpgradesTourStep::LockedNode,
+    UpgradesTourStep::Back,
+];
+
+impl UpgradesTourStep {
+    pub fn title(self) -> &'static str {
+        match self {
+            Self::Research => "Research points",
+            Self::RootNode => "Your first upgrade",
+            Self::LockedNode => "Locked upgrades",
+            Self::Back => "Back to the maze",
+        }
+    }
+
+    pub fn text(self) -> &'static str {
+        match self {
+            Self::Research => {
+                "Runs earn research (RP). It carries over between runs and is spent here."
+            }
+            Self::RootNode => {
+                "Every upgrade grows from Tower Damage. The badge shows level/max; each level costs more RP than the last. Click a node to buy its next level."
+            }
+            Self::LockedNode => {
+                "Upgrades unlock once the node their line leads from reaches the level they need. Hover any node to see its prerequisites."
+            }
+            Self::Back => "Start another run from here; upgrades apply to every new run.",
+        }
+    }
+}
+
+// The locked upgrade the tour reveals: the first one whose prerequisites are all unlocked
+// nodes, so its edge leads back into the visible tree
+pub fn tour_locked_node(ups: &UpgradeState) -> Option<UpgradeId> {
+    UPGRADE_DEFS
+        .iter()
+        .find(|d| !ups.is_unlocked(d.id) && d.prerequisites.iter().all(|p| ups.is_unlocked(p.id)))
+        .map(|d| d.id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_advance_in_order_and_finish_once() {
+        let mut tour = GuidedTour::new(UPGRADES_TOUR, false);
+        assert_eq!(tour.current(), Some(UpgradesTourStep::Research));
+        assert!(!tour.handle(TourEvent::Next));
+        assert_eq!(tour.progress(), Some((2, 4)));
+        // Clicking the spotlighted element counts as Next
+        assert!(!tour.handle(TourEvent::TargetClicked));
+        assert_eq!(tour.current(), Some(UpgradesTourStep::LockedNode));
+        assert!(!tour.handle(TourEvent::Next));
+        assert!(tour.handle(TourEvent::TargetClicked));
+        assert_eq!(tour.current(), None);
+        // Finished tours ignore further events
+        assert!(!tour.handle(TourEvent::Next));
+    }
+
+    #[test]
+    fn skip_and_purchase_end_the_tour() {
+        let mut tour = GuidedTour::new(UPGRADES_TOUR, false);
+        tour.handle(TourEvent::Next);
+        assert!(tour.handle(TourEvent::Purchased));
+        assert_eq!(tour.progress(), None);
+
+        let mut tour = GuidedTour::new(UPGRADES_TOUR, false);
+        assert!(tour.handle(TourEvent::Skip));
+        assert_eq!(tour.current(), None);
+
+        assert_eq!(GuidedTour::new(UPGRADES_TOUR, true).current(), None);
+    }
+
+    #[test]
+    fn locked_node_hangs_off_the_unlocked_tree() {
+        let mut ups = UpgradeState::default();
+        let id = tour_locked_node(&ups).expect("a fresh tree has locked nodes");
+        let def = UPGRADE_DEFS.iter().find(|d| d.id == id).unwrap();
+        assert!(!ups.is_unlocked(id));
+        assert!(def.prerequisites.iter().all(|p| ups.is_unlocked(p.id)));
+        // Meeting its prerequisites moves the tour on to another node
+        for p in def.prerequisites {
+            ups.levels.insert(p.id.key().into(), p.level);
+        }
+        assert_ne!(tour_locked_node(&ups), Some(id));
+    }
+}
@@ -0,0 +1,280 @@
+// Rebindable single-key hotkeys, saved under md_hotkeys. Bindings are KeyboardEvent.code values
+// ("KeyT", "Space") like the pan keys, so they stay on the same physical key on any layout. The
+// movement keys (WASD, arrows), tower digits and bookmark chords are fixed and can't be taken.
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::camera::PanDir;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum HotkeyAction {
+    PlaceTower,
+    UpgradeTower,
+    TargetMode,
+    Scarecrow,
+    TogglePause,
+    CycleTowerKind,
+    ExclusionBrush,
+    SuggestSpots,
+    CycleStamp,
+    Prospector,
+    Heatmap,
+    ShowPath,
+    CenterCamera,
+    CyclePanSpeed,
+    UndoWall,
+    DebugPanel,
+}
+
+impl HotkeyAction {
+    pub const ALL: [HotkeyAction; 16] = [
+        HotkeyAction::PlaceTower,
+        HotkeyAction::UpgradeTower,
+        HotkeyAction::TargetMode,
+        HotkeyAction::Scarecrow,
+        HotkeyAction::TogglePause,
+        HotkeyAction::CycleTowerKind,
+        HotkeyAction::ExclusionBrush,
+        HotkeyAction::SuggestSpots,
+        HotkeyAction::CycleStamp,
+        HotkeyAction::Prospector,
+        HotkeyAction::Heatmap,
+        HotkeyAction::ShowPath,
+        HotkeyAction::CenterCamera,
+        HotkeyAction::CyclePanSpeed,
+        HotkeyAction::UndoWall,
+        HotkeyAction::DebugPanel,
+    ];
+
+    pub fn default_code(self) -> &'static str {
+        match self {
+            HotkeyAction::PlaceTower => "KeyT",
+            HotkeyAction::UpgradeTower => "KeyU",
+            HotkeyAction::TargetMode => "KeyR",
+            HotkeyAction::Scarecrow => "KeyC",
+            HotkeyAction::TogglePause => "Space",
+            HotkeyAction::CycleTowerKind => "KeyQ",
+            HotkeyAction::ExclusionBrush => "KeyX",
+            HotkeyAction::SuggestSpots => "KeyG",
+            HotkeyAction::CycleStamp => "KeyB",
+            HotkeyAction::Prospector => "KeyP",
+            HotkeyAction::Heatmap => "KeyH",
+            HotkeyAction::ShowPath => "KeyL",
+            HotkeyAction::CenterCamera => "KeyF",
+            HotkeyAction::CyclePanSpeed => "KeyV",
+            HotkeyAction::UndoWall => "KeyZ",
+            HotkeyAction::DebugPanel => "Backquote",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            HotkeyAction::PlaceTower => "Place / remove tower",
+            HotkeyAction::UpgradeTower => "Upgrade tower",
+            HotkeyAction::TargetMode => "Target mode / rotate stamp",
+            HotkeyAction::Scarecrow => "Raise scarecrow",
+            HotkeyAction::TogglePause => "Pause / resume",
+            HotkeyAction::CycleTowerKind => "Next tower kind",
+            HotkeyAction::ExclusionBrush => "Exclusion brush",
+            HotkeyAction::SuggestSpots => "Suggest tower spots",
+            HotkeyAction::CycleStamp => "Next stamp",
+            HotkeyAction::Prospector => "Prospector overlay",
+            HotkeyAction::Heatmap => "Heatmap",
+            HotkeyAction::ShowPath => "Show path",
+            HotkeyAction::CenterCamera => "Center on start",
+            HotkeyAction::CyclePanSpeed => "Pan speed",
+            HotkeyAction::UndoWall => "Undo last wall",
+            HotkeyAction::DebugPanel => "Developer panel",
+        }
+    }
+}
+
+// Keys no binding can take: pan keys, keyboard cursor arrows, the tower digits, the game
+// speed keys, and the keys that close dialogs and move focus
+pub fn is_reserved(code: &str) -> bool {
+    PanDir::from_code(code, true).is_some()
+        || speed_key(code).is_some()
+        || matches!(code, "Digit1" | "Digit2" | "Digit3" | "Escape" | "Tab")
+}
+
+// +/- step the game speed: Some(true) for faster
+pub fn speed_key(code: &str) -> Option<bool> {
+    match code {
+        "Equal" | "NumpadAdd" => Some(true),
+        "Minus" | "NumpadSubtract" => Some(false),
+        _ => None,
+    }
+}
+
+// "KeyT" -> "T", "Digit5" -> "5", "Backquote" -> "`"; anything else shows its code
+pub fn key_label(code: &str) -> String {
+    if let Some(c) = code.strip_prefix("Key").or(code.strip_prefix("Digit")) {
+        return c.to_string();
+    }
+    match code {
+        "Backquote" => "`".into(),
+        "Minus" => "-".into(),
+        "Equal" => "=".into(),
+        "BracketLeft" => "[".into(),
+        "BracketRight" => "]".into(),
+        "Semicolon" => ";".into(),
+        "Quote" => "'".into(),
+        "Comma" => ",".into(),
+        "Period" => ".".into(),
+        "Slash" => "/".into(),
+        "Backslash" => "\\".into(),
+        _ => code.to_string(),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RebindError {
+    Reserved,
+    Taken(HotkeyAction),
+}
+
+impl RebindError {
+    pub fn message(self, code: &str) -> String {
+        match self {
+            RebindError::Reserved => format!("{} is reserved for movement", key_label(code)),
+            RebindError::Taken(other) => {
+                format!("{} is already bound to {}", key_label(code), other.label())
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Hotkeys {
+    bindings: BTreeMap<HotkeyAction, String>,
+}
+
+impl Default for Hotkeys {
+    fn default() -> Self {
+        Self {
+            bindings: HotkeyAction::ALL
+                .iter()
+                .map(|a| (*a, a.default_code().to_string()))
+                .collect(),
+        }
+    }
+}
+
+impl Hotkeys {
+    // Saved bindings; actions added since (or lost to a clash) get their default if it's free
+    pub fn from_json(raw: &str) -> Self {
+        serde_json::from_str::<Hotkeys>(raw).map_or_else(|_| Self::default(), Self::repaired)
+    }
+
+    fn repaired(mut self) -> Self {
+        self.bindings.retain(|_, code| !is_reserved(code));
+        let mut seen = Vec::new();
+        self.bindings.retain(|_, code| {
+            let fresh = !seen.contains(code);
+            seen.push(code.clone());
+            fresh
+        });
+        for a in HotkeyAction::ALL {
+            let code = a.default_code();
+            if !self.bindings.contains_key(&a) && self.action(code).is_none() {
+                self.bindings.insert(a, code.to_string());
+            }
+        }
+        self
+    }
+
+    // Physical key bound to `action`, or None if a clash left it unbound
+    pub fn code(&self, action: HotkeyAction) -> Option<&str> {
+        self.bindings.get(&action).map(String::as_str)
+    }
+
+    // The label to show for `action`'s key
+    pub fn label(&self, action: HotkeyAction) -> String {
+        self.code(action).map_or_else(|| "—".into(), key_label)
+    }
+
+    pub fn action(&self, code: &str) -> Option<HotkeyAction> {
+        self.bindings
+            .iter()
+            .find_map(|(a, c)| (c == code).then_some(*a))
+    }
+
+    pub fn rebind(&mut self, action: HotkeyAction, code: &str) -> Result<(), RebindError> {
+        if is_reserved(code) {
+            return Err(RebindError::Reserved);
+        }
+        match self.action(code) {
+            Some(other) if other != action => Err(RebindError::Taken(other)),
+            _ => {
+                self.bindings.insert(action, code.to_string());
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_match_the_old_hardcoded_keys() {
+        let hk = Hotkeys::default();
+        assert_eq!(hk.action("KeyT"), Some(HotkeyAction::PlaceTower));
+        assert_eq!(hk.action("Space"), Some(HotkeyAction::TogglePause));
+        assert_eq!(hk.action("Backquote"), Some(HotkeyAction::DebugPanel));
+        assert_eq!(hk.action("KeyW"), None);
+        // No two defaults share a key, and none sits on a fixed one
+        for a in HotkeyAction::ALL {
+            assert_eq!(hk.action(a.default_code()), Some(a));
+            assert!(!is_reserved(a.default_code()), "{a:?}");
+        }
+    }
+
+    #[test]
+    fn rebinding_refuses_clashes_and_reserved_keys() {
+        let mut hk = Hotkeys::default();
+        assert_eq!(
+            hk.rebind(HotkeyAction::PlaceTower, "KeyU"),
+            Err(RebindError::Taken(HotkeyAction::UpgradeTower))
+        );
+        assert_eq!(
+            hk.rebind(HotkeyAction::PlaceTower, "KeyW"),
+            Err(RebindError::Reserved)
+        );
+        assert_eq!(
+            hk.rebind(HotkeyAction::PlaceTower, "Minus"),
+            Err(RebindError::Reserved)
+        );
+        assert_eq!(hk.code(HotkeyAction::PlaceTower), Some("KeyT"));
+        // Rebinding to its own key is a no-op, and a free key moves it
+        assert_eq!(hk.rebind(HotkeyAction::PlaceTower, "KeyT"), Ok(()));
+        assert_eq!(hk.rebind(HotkeyAction::PlaceTower, "KeyY"), Ok(()));
+        assert_eq!(hk.action("KeyY"), Some(HotkeyAction::PlaceTower));
+        assert_eq!(hk.action("KeyT"), None);
+        assert_eq!(hk.label(HotkeyAction::PlaceTower), "Y");
+    }
+
+    #[test]
+    fn saved_bindings_are_repaired_on_load() {
+        // Two actions on one key, a reserved key, and most actions missing entirely
+        let saved = [
+            (HotkeyAction::PlaceTower, "KeyH"),
+            (HotkeyAction::Heatmap, "KeyH"),
+            (HotkeyAction::UpgradeTower, "KeyA"),
+        ];
+        let hk = Hotkeys {
+            bindings: saved.iter().map(|(a, c)| (*a, c.to_string())).collect(),
+        }
+        .repaired();
+        assert_eq!(hk.action("KeyH"), Some(HotkeyAction::PlaceTower));
+        assert_eq!(hk.code(HotkeyAction::Heatmap), None);
+        assert_eq!(hk.label(HotkeyAction::Heatmap), "—");
+        // Defaults fill in where free; KeyT went unused, so nothing else takes it
+        assert_eq!(hk.code(HotkeyAction::UpgradeTower), Some("KeyU"));
+        assert_eq!(hk.code(HotkeyAction::TogglePause), Some("Space"));
+        assert_eq!(hk.action("KeyT"), None);
+    }
+}
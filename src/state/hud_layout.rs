@@ -0,0 +1,169 @@
+// HUD layout mode from the observed container size. Narrow (and any portrait) containers stack
+// the panels into a top drawer and a bottom action area; ultra-wide ones pull the panels inward
+// so they stay near the action. Only the panels move: the canvas always fills the container,
+// so the mouse-to-tile math never sees the layout.
+use serde::{Deserialize, Serialize};
+
+// Ultra-wide insets never squeeze the HUD below this aspect ratio
+const MIN_SPAN_RATIO: f64 = 16.0 / 9.0;
+
+pub const NARROW_PRESETS: [u32; 4] = [600, 720, 900, 1024];
+pub const ULTRAWIDE_PRESETS: [f64; 3] = [2.0, 2.2, 2.4];
+pub const MARGIN_PRESETS: [u32; 4] = [0, 10, 15, 20];
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HudLayoutSettings {
+    // Containers narrower than this stack their panels
+    pub narrow_below_px: u32,
+    // Width / height at or above which the panels pull inward
+    pub ultrawide_ratio: f64,
+    // Inset per side on ultra-wide containers, as a percentage of the width
+    pub ultrawide_margin_pct: u32,
+}
+
+impl Default for HudLayoutSettings {
+    fn default() -> Self {
+        Self {
+            narrow_below_px: 720,
+            ultrawide_ratio: 2.2,
+            ultrawide_margin_pct: 15,
+        }
+    }
+}
+
+// Next preset after `cur`, wrapping; values off the list restart at the first preset
+fn next_preset<T: Copy + PartialEq>(presets: &[T], cur: T) -> T {
+    let i = presets.iter().position(|p| *p == cur);
+    presets[i.map_or(0, |i| (i + 1) % presets.len())]
+}
+
+impl HudLayoutSettings {
+    pub fn cycle_narrow(self) -> Self {
+        Self {
+            narrow_below_px: next_preset(&NARROW_PRESETS, self.narrow_below_px),
+            ..self
+        }
+    }
+    pub fn cycle_ultrawide(self) -> Self {
+        Self {
+            ultrawide_ratio: next_preset(&ULTRAWIDE_PRESETS, self.ultrawide_ratio),
+            ..self
+        }
+    }
+    pub fn cycle_margin(self) -> Self {
+        Self {
+            ultrawide_margin_pct: next_preset(&MARGIN_PRESETS, self.ultrawide_margin_pct),
+            ..self
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HudMode {
+    // Narrow or portrait: top drawer plus bottom action area
+    Stacked,
+    #[default]
+    Standard,
+    // Corner panels inset from the container edges
+    UltraWide,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct HudLayout {
+    pub mode: HudMode,
+    // Horizontal inset per side, in CSS pixels; zero outside UltraWide
+    pub inset_px: f64,
+}
+
+pub fn hud_layout(width: f64, height: f64, s: &HudLayoutSettings) -> HudLayout {
+    if width <= 0.0 || height <= 0.0 {
+        return HudLayout::default();
+    }
+    if width < s.narrow_below_px as f64 || height > width {
+        return HudLayout {
+            mode: HudMode::Stacked,
+            inset_px: 0.0,
+        };
+    }
+    if width / height < s.ultrawide_ratio {
+        return HudLayout::default();
+    }
+    let slack = ((width - height * MIN_SPAN_RATIO) / 2.0).max(0.0);
+    let inset_px = (width * s.ultrawide_margin_pct as f64 / 100.0).min(slack);
+    HudLayout {
+        mode: HudMode::UltraWide,
+        inset_px,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn portrait_and_narrow_containers_stack() {
+        let s = HudLayoutSettings::default();
+        assert_eq!(hud_layout(390.0, 844.0, &s).mode, HudMode::Stacked);
+        // Landscape but under the width threshold
+        assert_eq!(hud_layout(700.0, 400.0, &s).mode, HudMode::Stacked);
+        // Portrait wins even on a wide monitor turned on its side
+        assert_eq!(hud_layout(1440.0, 2560.0, &s).mode, HudMode::Stacked);
+        assert_eq!(hud_layout(1920.0, 1080.0, &s), HudLayout::default());
+        // Unmeasured container: leave the panels where they are
+        assert_eq!(hud_layout(0.0, 0.0, &s), HudLayout::default());
+    }
+
+    #[test]
+    fn ultra_wide_insets_by_margin_but_never_below_sixteen_nine() {
+        let s = HudLayoutSettings::default();
+        // 21:9 at 1440p: 15% of 3440 = 516, slack (3440 - 2560) / 2 = 440
+        let l = hud_layout(3440.0, 1440.0, &s);
+        assert_eq!(l.mode, HudMode::UltraWide);
+        assert_eq!(l.inset_px, 440.0);
+        // 32:9: the margin binds first
+        let l = hud_layout(5120.0, 1440.0, &s);
+        assert_eq!(l.inset_px, 768.0);
+        let zero = HudLayoutSettings {
+            ultrawide_margin_pct: 0,
+            ..s
+        };
+        assert_eq!(hud_layout(5120.0, 1440.0, &zero).inset_px, 0.0);
+    }
+
+    #[test]
+    fn thresholds_follow_the_settings() {
+        let s = HudLayoutSettings {
+            narrow_below_px: 1024,
+            ultrawide_ratio: 2.0,
+            ultrawide_margin_pct: 10,
+        };
+        assert_eq!(hud_layout(1000.0, 600.0, &s).mode, HudMode::Stacked);
+        assert_eq!(hud_layout(2400.0, 1200.0, &s).mode, HudMode::UltraWide);
+        assert_eq!(
+            hud_layout(2400.0, 1200.0, &HudLayoutSettings::default()).mode,
+            HudMode::Standard
+        );
+    }
+
+    #[test]
+    fn presets_cycle_and_recover_from_custom_values() {
+        let s = HudLayoutSettings::default();
+        assert_eq!(s.cycle_narrow().narrow_below_px, 900);
+        assert_eq!(s.cycle_margin().cycle_margin().ultrawide_margin_pct, 0);
+        let odd = HudLayoutSettings {
+            ultrawide_ratio: 3.0,
+            ..s
+        };
+        assert_eq!(odd.cycle_ultrawide().ultrawide_ratio, 2.0);
+        assert_eq!(
+            HudLayoutSettings {
+                narrow_below_px: 1024,
+                ..s
+            }
+            .cycle_narrow()
+            .narrow_below_px,
+            600
+        );
+    }
+}
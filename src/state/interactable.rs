@@ -18,9 +18,9 @@ pub fn compute_interactable_mask(rs: &RunState) -> Vec<bool> {
         }
     };
     let seeds: Vec<crate::model::Position> = if !rs.path_loop.is_empty() {
-        rs.path_loop.clone()
+        rs.path_loop.to_vec()
     } else {
-        rs.path.clone()
+        rs.path.to_vec()
     };
     for p in &seeds {
         if p.x < gs.width && p.y < gs.height {
@@ -98,3 +98,78 @@ pub fn compute_interactable_mask(rs: &RunState) -> Vec<bool> {
     }
     mask
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{compute_path, tiles_from_rows, GridSize, UpgradeState};
+
+    fn board(rows: &[&str]) -> RunState {
+        let gs = GridSize {
+            width: 4,
+            height: 4,
+        };
+        let mut rs = RunState::new_with_upgrades_seeded(gs, &UpgradeState::default(), 1);
+        let (gs, tiles) = tiles_from_rows(rows);
+        rs.grid_size = gs;
+        rs.tiles = tiles.into();
+        rs.path.clear();
+        rs.path_loop.clear();
+        rs
+    }
+
+    #[test]
+    fn reach_spreads_from_start_and_stops_at_rock() {
+        // The pocket at (1, 4) is sealed off, and so is the rock around it
+        let rs = board(&[
+            "#########",
+            "#.S..####",
+            "#.##.####",
+            "####..###",
+            "#.#######",
+            "#########",
+        ]);
+        // The open tiles Start reaches, and the rock bordering them
+        let expected = [
+            ".oooo....",
+            "oooooo...",
+            "oooooo...",
+            ".o.oooo..",
+            "....oo...",
+            ".........",
+        ];
+        let mask = compute_interactable_mask(&rs);
+        let drawn: Vec<String> = mask
+            .chunks(9)
+            .map(|row| row.iter().map(|&m| if m { 'o' } else { '.' }).collect())
+            .collect();
+        assert_eq!(drawn, expected);
+    }
+
+    #[test]
+    fn reach_follows_the_path_and_never_takes_indestructible() {
+        let mut rs = board(&[
+            "#########",
+            "#.....###",
+            "#.#I#.###",
+            "#.XSE.###",
+            "#.#I#.###",
+            "#.....###",
+            "#########",
+        ]);
+        rs.path = compute_path(&rs).into();
+        assert!(!rs.path.is_empty());
+        let mask = compute_interactable_mask(&rs);
+        let at = |x: usize, y: usize| mask[y * 9 + x];
+        // Start and its arrows are part of the route, and so is the rock along it
+        for (x, y) in [(3, 3), (2, 3), (4, 3), (1, 1), (5, 5)] {
+            assert!(at(x, y), "({x}, {y})");
+        }
+        for (x, y) in [(2, 2), (0, 3), (6, 1)] {
+            assert!(at(x, y), "({x}, {y})");
+        }
+        // The blocks beside Start never are
+        assert!(!at(3, 2) && !at(3, 4));
+        assert!(!at(0, 0) && !at(7, 3));
+    }
+}
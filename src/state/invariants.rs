@@ -0,0 +1,400 @@
+// Run-state invariants. `check` lists every rule a state breaks; reduce runs it after each action
+// in debug builds, and tests can call `assert_holds` on any state they build. One `Rule` per
+// invariant, documented on the variant.
+use crate::model::{DirRole, RunState, TileBitset, TileKind, EMPTY_HARDNESS, FIXED_HARDNESS};
+use std::collections::HashSet;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rule {
+    // tiles.len() is width * height
+    TileCount,
+    // life never exceeds life_max
+    LifeOverMax,
+    // Empty tiles have EMPTY_HARDNESS, however they were opened (carving, mining, stamps)
+    EmptyHardness,
+    // Start, Direction, Indestructible and End tiles have FIXED_HARDNESS
+    FixedHardness,
+    // Rock and Wall take at least one hardness point to mine
+    SoftHardness,
+    // Exactly one Start, one Entrance and one Exit, and at most one End
+    Landmarks,
+    // Towers sit inside the grid
+    TowerOutOfBounds,
+    // Towers sit on Rock or Wall, never on open or fixed tiles
+    TowerOnOpenTile,
+    // At most one tower per tile
+    TowersStacked,
+    // Tower durability lies in 0..=max_hp
+    TowerDurability,
+    // Structures sit on Rock
+    StructureOffRock,
+    // Every path tile is Empty
+    PathOffEmpty,
+    // Consecutive path tiles are orthogonal neighbours
+    PathBroken,
+    // loop_cum_lengths matches path_loop: same length, starts at 0, never decreases,
+    // and loop_total_length covers it
+    LoopGeometry,
+    // Enemy hp never exceeds max_hp
+    EnemyHp,
+    // No more kills than spawns
+    KillsOverSpawns,
+    // research_carry and research_progress are fractions in [0, 1)
+    ResearchCarry,
+    // The no-target mask is either unset or sized to the grid
+    NoTargetSize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Violation {
+    pub rule: Rule,
+    pub detail: String,
+}
+
+pub fn check(rs: &RunState) -> Vec<Violation> {
+    let mut out = Vec::new();
+    let mut fail = |rule: Rule, detail: String| out.push(Violation { rule, detail });
+    let gs = rs.grid_size;
+    let (w, h) = (gs.width, gs.height);
+    let tile_count = w as usize * h as usize;
+    if rs.tiles.len() != tile_count {
+        fail(
+            Rule::TileCount,
+            format!("{} tiles for a {}x{} grid", rs.tiles.len(), w, h),
+        );
+        // Every index-based rule below would be meaningless
+        return out;
+    }
+    let kind_at = |x: u32, y: u32| &rs.tiles[(y * w + x) as usize].kind;
+
+    if rs.life > rs.life_max {
+        fail(
+            Rule::LifeOverMax,
+            format!("life {} over max {}", rs.life, rs.life_max),
+        );
+    }
+
+    let (mut starts, mut entrances, mut exits, mut ends) = (0, 0, 0, 0);
+    for (idx, t) in rs.tiles.iter().enumerate() {
+        let at = (idx as u32 % w, idx as u32 / w);
+        match t.kind {
+            TileKind::Empty if t.hardness != EMPTY_HARDNESS => fail(
+                Rule::EmptyHardness,
+                format!("Empty {:?} has hardness {}", at, t.hardness),
+            ),
+            TileKind::Rock { .. } | TileKind::Wall if t.hardness == 0 => fail(
+                Rule::SoftHardness,
+                format!("{:?} at {:?} has hardness 0", t.kind, at),
+            ),
+            TileKind::Start
+            | TileKind::Direction { .. }
+            | TileKind::Indestructible
+            | TileKind::End
+                if t.hardness != FIXED_HARDNESS =>
+            {
+                fail(
+                    Rule::FixedHardness,
+                    format!("{:?} at {:?} has hardness {}", t.kind, at, t.hardness),
+                )
+            }
+            _ => {}
+        }
+        match t.kind {
+            TileKind::Start => starts += 1,
+            TileKind::Direction {
+                role: DirRole::Entrance,
+                ..
+            } => entrances += 1,
+            TileKind::Direction {
+                role: DirRole::Exit,
+                ..
+            } => exits += 1,
+            TileKind::End => ends += 1,
+            _ => {}
+        }
+    }
+    if (starts, entrances, exits) != (1, 1, 1) || ends > 1 {
+        fail(
+            Rule::Landmarks,
+            format!("{starts} starts, {entrances} entrances, {exits} exits, {ends} ends"),
+        );
+    }
+
+    let mut occupied = HashSet::new();
+    for t in &rs.towers {
+        if t.x >= w || t.y >= h {
+            fail(
+                Rule::TowerOutOfBounds,
+                format!("tower at ({}, {})", t.x, t.y),
+            );
+            continue;
+        }
+        if !matches!(kind_at(t.x, t.y), TileKind::Rock { .. } | TileKind::Wall) {
+            fail(
+                Rule::TowerOnOpenTile,
+                format!("tower at ({}, {}) on {:?}", t.x, t.y, kind_at(t.x, t.y)),
+            );
+        }
+        if !occupied.insert((t.x, t.y)) {
+            fail(
+                Rule::TowersStacked,
+                format!("second tower at ({}, {})", t.x, t.y),
+            );
+        }
+        if !(0.0..=t.max_hp).contains(&t.hp) {
+            fail(
+                Rule::TowerDurability,
+                format!("tower at ({}, {}) has {}/{} hp", t.x, t.y, t.hp, t.max_hp),
+            );
+        }
+    }
+    for s in &rs.structures {
+        let on_rock = s.x < w && s.y < h && matches!(kind_at(s.x, s.y), TileKind::Rock { .. });
+        if !on_rock {
+            fail(
+                Rule::StructureOffRock,
+                format!("{} at ({}, {})", s.kind.name(), s.x, s.y),
+            );
+        }
+    }
+
+    for p in &rs.path {
+        if p.x >= w || p.y >= h || !matches!(kind_at(p.x, p.y), TileKind::Empty) {
+            fail(
+                Rule::PathOffEmpty,
+                format!("path crosses ({}, {})", p.x, p.y),
+            );
+        }
+    }
+    for pair in rs.path.windows(2) {
+        if pair[0].x.abs_diff(pair[1].x) + pair[0].y.abs_diff(pair[1].y) != 1 {
+            fail(
+                Rule::PathBroken,
+                format!("path jumps {:?} -> {:?}", pair[0], pair[1]),
+            );
+        }
+    }
+
+    let cum = &rs.loop_cum_lengths;
+    let geometry_ok = if rs.path_loop.len() < 2 {
+        cum.is_empty() && rs.loop_total_length == 0.0
+    } else {
+        cum.len() == rs.path_loop.len()
+            && cum[0] == 0.0
+            && cum.windows(2).all(|c| c[1] >= c[0])
+            && rs.loop_total_length >= cum[cum.len() - 1]
+    };
+    if !geometry_ok {
+        fail(
+            Rule::LoopGeometry,
+            format!(
+                "{} loop nodes, {} cumulative lengths, total {}",
+                rs.path_loop.len(),
+                cum.len(),
+                rs.loop_total_length
+            ),
+        );
+    }
+
+    for (i, e) in rs.enemies.iter().enumerate() {
+        if e.hp > e.max_hp {
+            fail(
+                Rule::EnemyHp,
+                format!("enemy {} has {}/{} hp", i, e.hp, e.max_hp),
+            );
+        }
+    }
+    if rs.stats.enemies_killed > rs.stats.enemies_spawned {
+        fail(
+            Rule::KillsOverSpawns,
+            format!(
+                "{} kills from {} spawns",
+                rs.stats.enemies_killed, rs.stats.enemies_spawned
+            ),
+        );
+    }
+    if !(0.0..1.0).contains(&rs.research_carry) {
+        fail(
+            Rule::ResearchCarry,
+            format!("research carry {}", rs.research_carry),
+        );
+    }
+    if !(0.0..1.0).contains(&rs.research_progress) {
+        fail(
+            Rule::ResearchCarry,
+            format!("research progress {}", rs.research_progress),
+        );
+    }
+    if !rs.no_target.fits(gs) && rs.no_target != TileBitset::default() {
+        fail(
+            Rule::NoTargetSize,
+            "no-target mask doesn't match the grid".to_string(),
+        );
+    }
+    out
+}
+
+// Test helper: panics listing every broken rule
+#[cfg(test)]
+pub fn assert_holds(rs: &RunState) {
+    let v = check(rs);
+    assert!(v.is_empty(), "invariants broken: {:#?}", v);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{
+        Enemy, EnemyKind, EntityId, GridSize, Position, Structure, StructureKind, Tower, TowerKind,
+        UpgradeState,
+    };
+
+    fn run() -> RunState {
+        RunState::new_basic(GridSize {
+            width: 15,
+            height: 15,
+        })
+    }
+
+    fn idx_of(rs: &RunState, pred: impl Fn(&TileKind) -> bool) -> usize {
+        rs.tiles.iter().position(|t| pred(&t.kind)).unwrap()
+    }
+
+    fn xy(rs: &RunState, idx: usize) -> (u32, u32) {
+        (
+            idx as u32 % rs.grid_size.width,
+            idx as u32 / rs.grid_size.width,
+        )
+    }
+
+    fn rules(rs: &RunState) -> Vec<Rule> {
+        check(rs).into_iter().map(|v| v.rule).collect()
+    }
+
+    fn rock_xy(rs: &RunState) -> (u32, u32) {
+        xy(rs, idx_of(rs, |k| matches!(k, TileKind::Rock { .. })))
+    }
+
+    #[test]
+    fn fresh_runs_hold_every_invariant() {
+        let gs = run().grid_size;
+        for seed in 0..20 {
+            assert_holds(&RunState::new_with_upgrades_seeded(
+                gs,
+                &UpgradeState::default(),
+                seed,
+            ));
+        }
+    }
+
+    #[test]
+    fn catches_tile_and_landmark_corruption() {
+        let mut rs = run();
+        rs.life = rs.life_max + 1;
+        let e = idx_of(&rs, |k| matches!(k, TileKind::Empty));
+        rs.tiles[e].hardness = 3;
+        let s = idx_of(&rs, |k| matches!(k, TileKind::Start));
+        rs.tiles[s].hardness = 1;
+        let r = idx_of(&rs, |k| matches!(k, TileKind::Rock { .. }));
+        rs.tiles[r].hardness = 0;
+        let got = rules(&rs);
+        for rule in [
+            Rule::LifeOverMax,
+            Rule::EmptyHardness,
+            Rule::FixedHardness,
+            Rule::SoftHardness,
+        ] {
+            assert!(got.contains(&rule), "{rule:?} missed");
+        }
+
+        rs.tiles[s].kind = TileKind::Indestructible;
+        rs.tiles[s].hardness = FIXED_HARDNESS;
+        assert!(rules(&rs).contains(&Rule::Landmarks));
+
+        rs.tiles.pop();
+        assert_eq!(rules(&rs), vec![Rule::TileCount]);
+    }
+
+    #[test]
+    fn catches_tower_and_structure_corruption() {
+        let mut rs = run();
+        let (rx, ry) = rock_xy(&rs);
+        let tower = |x, y| Tower::new(x, y, TowerKind::Basic, 3.0, 5, None);
+        rs.towers.push(tower(rx, ry));
+        assert_holds(&rs);
+        rs.towers.push(tower(rx, ry));
+        let (ex, ey) = xy(&rs, idx_of(&rs, |k| matches!(k, TileKind::Empty)));
+        rs.towers.push(tower(ex, ey));
+        rs.towers.push(tower(rs.grid_size.width, 0));
+        rs.towers[0].hp = rs.towers[0].max_hp + 1.0;
+        rs.structures.push(Structure {
+            x: ex,
+            y: ey,
+            kind: StructureKind::Scarecrow,
+            hp: 1,
+            max_hp: 1,
+        });
+        let got = rules(&rs);
+        for rule in [
+            Rule::TowersStacked,
+            Rule::TowerOnOpenTile,
+            Rule::TowerOutOfBounds,
+            Rule::TowerDurability,
+            Rule::StructureOffRock,
+        ] {
+            assert!(got.contains(&rule), "{rule:?} missed");
+        }
+    }
+
+    #[test]
+    fn catches_path_and_loop_corruption() {
+        let mut rs = run();
+        assert!(rs.path.len() > 2);
+        let (rx, ry) = rock_xy(&rs);
+        rs.path.push(Position { x: rx, y: ry });
+        rs.loop_cum_lengths.pop();
+        let got = rules(&rs);
+        for rule in [Rule::PathOffEmpty, Rule::PathBroken, Rule::LoopGeometry] {
+            assert!(got.contains(&rule), "{rule:?} missed");
+        }
+
+        let mut rs = run();
+        rs.loop_total_length = 0.5;
+        assert_eq!(rules(&rs), vec![Rule::LoopGeometry]);
+    }
+
+    #[test]
+    fn catches_counter_corruption() {
+        let mut rs = run();
+        rs.stats.enemies_killed = 1;
+        rs.research_carry = 1.0;
+        rs.no_target = TileBitset::new(3, 3);
+        rs.enemies.push(Enemy {
+            id: EntityId::default(),
+            x: 0.5,
+            y: 0.5,
+            speed_tps: 1.0,
+            hp: 11,
+            max_hp: 10,
+            spawned_at: 0,
+            path_index: 0,
+            dir_dx: 0.0,
+            dir_dy: 0.0,
+            radius_scale: 1.0,
+            loop_dist: 0.0,
+            debuffs: Vec::new(),
+            kind: EnemyKind::Normal,
+            hit_flash_ttl: 0.0,
+            spawn_anim_ttl: 0.0,
+        });
+        let got = rules(&rs);
+        for rule in [
+            Rule::EnemyHp,
+            Rule::KillsOverSpawns,
+            Rule::ResearchCarry,
+            Rule::NoTargetSize,
+        ] {
+            assert!(got.contains(&rule), "{rule:?} missed");
+        }
+    }
+}
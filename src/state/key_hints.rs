@@ -0,0 +1,231 @@
+// Key hint bar: the actions that would do something right now at the focused tile, with their
+// keys. Derived from the run state alone; run_view renders the list under the board and hides
+// it on touch screens and behind overlays.
+use crate::model::{self, Feature, RunState, StructureKind, TileKind, TowerKind};
+
+use super::hotkeys::{HotkeyAction, Hotkeys};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HintAction {
+    PlaceTower,
+    SellTower,
+    UpgradeTower,
+    TargetMode,
+    Mine,
+    Wall,
+    Scarecrow,
+    Pause,
+    Resume,
+    Pan,
+    Heatmap,
+}
+
+impl HintAction {
+    // The key the keydown handler in run_view answers to under `hotkeys`
+    pub fn key(self, hotkeys: &Hotkeys) -> String {
+        let bound = match self {
+            HintAction::PlaceTower | HintAction::SellTower => HotkeyAction::PlaceTower,
+            HintAction::UpgradeTower => HotkeyAction::UpgradeTower,
+            HintAction::TargetMode => HotkeyAction::TargetMode,
+            HintAction::Scarecrow => HotkeyAction::Scarecrow,
+            HintAction::Pause | HintAction::Resume => HotkeyAction::TogglePause,
+            HintAction::Heatmap => HotkeyAction::Heatmap,
+            HintAction::Mine => return "Hold LMB".into(),
+            HintAction::Wall => return "LMB".into(),
+            HintAction::Pan => return "WASD".into(),
+        };
+        hotkeys.label(bound)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            HintAction::PlaceTower => "Place tower",
+            HintAction::SellTower => "Remove",
+            HintAction::UpgradeTower => "Upgrade",
+            HintAction::TargetMode => "Target",
+            HintAction::Mine => "Mine",
+            HintAction::Wall => "Wall",
+            HintAction::Scarecrow => "Scarecrow",
+            HintAction::Pause => "Pause",
+            HintAction::Resume => "Resume",
+            HintAction::Pan => "Pan",
+            HintAction::Heatmap => "Heatmap",
+        }
+    }
+}
+
+// Tile actions first, then the global ones. `focus` is the tile under the pointer or keyboard
+// cursor and `reachable` whether it lies in the interactable area; `kind` is the selected tower.
+pub fn key_hints(
+    rs: &RunState,
+    focus: Option<(u32, u32)>,
+    reachable: bool,
+    kind: &TowerKind,
+) -> Vec<HintAction> {
+    if rs.game_over {
+        return Vec::new();
+    }
+    let gs = rs.grid_size;
+    let mut out = Vec::new();
+    let focus = focus.filter(|&(x, y)| reachable && x < gs.width && y < gs.height);
+    if let Some((x, y)) = focus {
+        let tile = &rs.tiles[(y * gs.width + x) as usize];
+        let towers = model::feature_allowed(rs.progression_run, Feature::Towers);
+        if let Some(tw) = rs.tower_at(x, y) {
+            out.push(HintAction::SellTower);
+            if model::tower_upgrade_cost(rs, tw).is_some_and(|c| rs.currencies.gold >= c) {
+                out.push(HintAction::UpgradeTower);
+            }
+            out.push(HintAction::TargetMode);
+        } else {
+            match tile.kind {
+                TileKind::Rock { .. } | TileKind::Wall => {
+                    if towers
+                        && !model::at_tower_limit(rs)
+                        && !model::too_hot_for_tower(rs, x, y)
+                        && rs.can_afford_tower(kind)
+                    {
+                        out.push(HintAction::PlaceTower);
+                    }
+                    if !rs.is_paused {
+                        out.push(HintAction::Mine);
+                    }
+                    let scarecrow = StructureKind::Scarecrow;
+                    if matches!(tile.kind, TileKind::Rock { .. })
+                        && !rs.structures.iter().any(|s| s.kind == scarecrow)
+                        && rs.currencies.research >= scarecrow.research_cost()
+                    {
+                        out.push(HintAction::Scarecrow);
+                    }
+                }
+                TileKind::Empty if model::feature_allowed(rs.progression_run, Feature::Walls) => {
+                    out.push(HintAction::Wall);
+                }
+                _ => {}
+            }
+        }
+    }
+    out.push(if rs.is_paused {
+        HintAction::Resume
+    } else {
+        HintAction::Pause
+    });
+    out.extend([HintAction::Pan, HintAction::Heatmap]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{GridSize, Tower, UpgradeState};
+    use HintAction::*;
+
+    // A running board with the first plain rock and first empty tile
+    fn board() -> (RunState, (u32, u32), (u32, u32)) {
+        let mut rs = RunState::new_with_upgrades_seeded(
+            GridSize {
+                width: 24,
+                height: 24,
+            },
+            &UpgradeState::default(),
+            3,
+        );
+        rs.is_paused = false;
+        rs.started = true;
+        let w = rs.grid_size.width;
+        let at = |i: usize| (i as u32 % w, i as u32 / w);
+        let rock = rs
+            .tiles
+            .iter()
+            .position(|t| matches!(t.kind, TileKind::Rock { .. }) && !t.magma)
+            .unwrap();
+        let empty = rs
+            .tiles
+            .iter()
+            .position(|t| t.kind == TileKind::Empty && !t.magma)
+            .unwrap();
+        (rs, at(rock), at(empty))
+    }
+
+    #[test]
+    fn rock_offers_a_tower_only_when_affordable() {
+        let (mut rs, rock, _) = board();
+        rs.currencies.gold = rs.tower_cost_of(&TowerKind::Basic);
+        let hints = key_hints(&rs, Some(rock), true, &TowerKind::Basic);
+        assert_eq!(hints, vec![PlaceTower, Mine, Pause, Pan, Heatmap]);
+        rs.currencies.gold = 0;
+        let hints = key_hints(&rs, Some(rock), true, &TowerKind::Basic);
+        assert_eq!(hints, vec![Mine, Pause, Pan, Heatmap]);
+        // Locked towers aren't offered either
+        rs.currencies.gold = 1000;
+        rs.progression_run = Some(1);
+        assert!(!key_hints(&rs, Some(rock), true, &TowerKind::Basic).contains(&PlaceTower));
+    }
+
+    #[test]
+    fn tower_offers_remove_upgrade_and_target() {
+        let (mut rs, (x, y), _) = board();
+        rs.towers
+            .push(Tower::new(x, y, TowerKind::Basic, 3.5, 20, None));
+        rs.currencies.gold = 0;
+        let hints = key_hints(&rs, Some((x, y)), true, &TowerKind::Basic);
+        assert_eq!(hints, vec![SellTower, TargetMode, Pause, Pan, Heatmap]);
+        rs.currencies.gold = 1000;
+        let hints = key_hints(&rs, Some((x, y)), true, &TowerKind::Basic);
+        assert_eq!(&hints[..3], &[SellTower, UpgradeTower, TargetMode]);
+    }
+
+    #[test]
+    fn empty_tile_offers_a_wall_once_walls_unlock() {
+        let (mut rs, _, empty) = board();
+        assert_eq!(
+            key_hints(&rs, Some(empty), true, &TowerKind::Basic)[0],
+            Wall
+        );
+        rs.progression_run = Some(2);
+        assert_eq!(
+            key_hints(&rs, Some(empty), true, &TowerKind::Basic),
+            vec![Pause, Pan, Heatmap]
+        );
+    }
+
+    #[test]
+    fn scarecrow_needs_enough_research() {
+        let (mut rs, rock, _) = board();
+        rs.currencies.research = StructureKind::Scarecrow.research_cost();
+        assert!(key_hints(&rs, Some(rock), true, &TowerKind::Basic).contains(&Scarecrow));
+        rs.currencies.research -= 1;
+        assert!(!key_hints(&rs, Some(rock), true, &TowerKind::Basic).contains(&Scarecrow));
+    }
+
+    #[test]
+    fn out_of_reach_paused_and_game_over() {
+        let (mut rs, rock, _) = board();
+        rs.is_paused = true;
+        assert_eq!(
+            key_hints(&rs, Some(rock), false, &TowerKind::Basic),
+            vec![Resume, Pan, Heatmap]
+        );
+        assert_eq!(
+            key_hints(&rs, None, true, &TowerKind::Basic),
+            vec![Resume, Pan, Heatmap]
+        );
+        // Paused boards can't be mined
+        assert!(!key_hints(&rs, Some(rock), true, &TowerKind::Basic).contains(&Mine));
+        rs.game_over = true;
+        assert!(key_hints(&rs, Some(rock), true, &TowerKind::Basic).is_empty());
+    }
+
+    #[test]
+    fn hint_keys_follow_rebinding() {
+        let mut hk = Hotkeys::default();
+        assert_eq!(SellTower.key(&hk), "T");
+        assert_eq!(Resume.key(&hk), "Space");
+        hk.rebind(HotkeyAction::PlaceTower, "KeyY").unwrap();
+        assert_eq!(
+            (PlaceTower.key(&hk), SellTower.key(&hk)),
+            ("Y".into(), "Y".into())
+        );
+        assert_eq!(Pan.key(&hk), "WASD");
+    }
+}
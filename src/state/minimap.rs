@@ -0,0 +1,145 @@
+// Minimap geometry and colours. The whole grid is fitted into a MINIMAP_PX square, centred on
+// its short axis; the terrain is one pixel per tile, scaled to fit when drawn. Mapping between
+// minimap pixels and tiles lives here so the click-to-jump and the viewport box agree.
+use crate::model::{DirRole, GridSize, RunState, Tile, TileKind};
+
+use super::theme::Palette;
+use super::Camera;
+
+pub const MINIMAP_PX: f64 = 160.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MinimapFit {
+    // Minimap pixels per tile
+    pub scale: f64,
+    pub left: f64,
+    pub top: f64,
+    grid: GridSize,
+}
+
+impl MinimapFit {
+    pub fn of(gs: GridSize) -> Self {
+        let scale = MINIMAP_PX / gs.width.max(gs.height).max(1) as f64;
+        Self {
+            scale,
+            left: (MINIMAP_PX - gs.width as f64 * scale) / 2.0,
+            top: (MINIMAP_PX - gs.height as f64 * scale) / 2.0,
+            grid: gs,
+        }
+    }
+
+    pub fn to_minimap(self, x: f64, y: f64) -> (f64, f64) {
+        (self.left + x * self.scale, self.top + y * self.scale)
+    }
+
+    // Tile position under a minimap pixel, kept on the grid so a click in the margin jumps to
+    // the nearest edge
+    pub fn to_world(self, mx: f64, my: f64) -> (f64, f64) {
+        (
+            ((mx - self.left) / self.scale).clamp(0.0, self.grid.width as f64),
+            ((my - self.top) / self.scale).clamp(0.0, self.grid.height as f64),
+        )
+    }
+
+    // The part of the world a w x h main canvas shows, as (x, y, w, h) in minimap pixels
+    pub fn viewport(&self, cam: &Camera, (w, h): (f64, f64)) -> (f64, f64, f64, f64) {
+        let scale_px = cam.zoom * 32.0;
+        let (x, y) = self.to_minimap(-cam.offset_x / scale_px, -cam.offset_y / scale_px);
+        (x, y, w / scale_px * self.scale, h / scale_px * self.scale)
+    }
+}
+
+// "#rrggbb" as opaque RGBA bytes
+fn hex_rgba(hex: &str) -> [u8; 4] {
+    let v = u32::from_str_radix(hex.trim_start_matches('#'), 16).unwrap_or(0);
+    [(v >> 16) as u8, (v >> 8) as u8, v as u8, 0xff]
+}
+
+// The board's fill for each tile kind in `pal`, flattened to one colour
+pub fn tile_rgba(t: &Tile, pal: &Palette) -> [u8; 4] {
+    hex_rgba(match t.kind {
+        TileKind::Empty if t.magma => pal.lava,
+        TileKind::Empty => pal.path,
+        TileKind::Rock { boost: Some(b), .. } => pal.boost(b),
+        TileKind::Rock { has_gold: true, .. } => pal.gold_rock,
+        TileKind::Rock { .. } if t.magma => pal.magma_rock,
+        TileKind::Rock { .. } => pal.rock,
+        TileKind::Wall => pal.wall,
+        TileKind::Start => pal.start,
+        TileKind::Direction {
+            role: DirRole::Entrance,
+            ..
+        } => pal.entrance,
+        TileKind::Direction {
+            role: DirRole::Exit,
+            ..
+        } => pal.exit,
+        TileKind::Indestructible => pal.indestructible,
+        // The End's rings are drawn in the exit colour
+        TileKind::End => pal.exit,
+    })
+}
+
+// RGBA bytes for the terrain image, one pixel per tile in row order
+pub fn terrain_pixels(rs: &RunState, pal: &Palette) -> Vec<u8> {
+    rs.tiles.iter().flat_map(|t| tile_rgba(t, pal)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::UpgradeState;
+    use crate::state::theme::{DEFAULT_PALETTE, HIGH_CONTRAST_PALETTE};
+
+    fn gs(width: u32, height: u32) -> GridSize {
+        GridSize { width, height }
+    }
+
+    #[test]
+    fn grid_is_fitted_and_centred_on_its_short_axis() {
+        let fit = MinimapFit::of(gs(80, 40));
+        assert_eq!(fit.scale, 2.0);
+        assert_eq!((fit.left, fit.top), (0.0, 40.0));
+        assert_eq!(fit.to_minimap(80.0, 40.0), (MINIMAP_PX, 120.0));
+        assert_eq!(fit.to_world(80.0, 80.0), (40.0, 20.0));
+        // The margin above the grid maps onto its top edge
+        assert_eq!(fit.to_world(10.0, 5.0), (5.0, 0.0));
+        // A 255-wide grid shrinks below a pixel per tile
+        assert!(MinimapFit::of(gs(255, 255)).scale < 1.0);
+    }
+
+    #[test]
+    fn viewport_box_surrounds_the_camera_centre() {
+        let fit = MinimapFit::of(gs(100, 100));
+        let mut cam = Camera {
+            zoom: 1.0,
+            ..Camera::default()
+        };
+        let view = (640.0, 320.0);
+        cam.center_on(50.0, 25.0, view.0, view.1, 32.0);
+        let (x, y, w, h) = fit.viewport(&cam, view);
+        // 640 / 32 = 20 tiles across at 1.6 minimap px per tile
+        assert!((w - 32.0).abs() < 1e-9 && (h - 16.0).abs() < 1e-9);
+        let centre = fit.to_world(x + w / 2.0, y + h / 2.0);
+        assert!((centre.0 - 50.0).abs() < 1e-9 && (centre.1 - 25.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn terrain_image_has_a_pixel_per_tile() {
+        let rs = RunState::new_with_upgrades_seeded(gs(24, 24), &UpgradeState::default(), 3);
+        let px = terrain_pixels(&rs, &DEFAULT_PALETTE);
+        assert_eq!(px.len(), 24 * 24 * 4);
+        let start = rs
+            .tiles
+            .iter()
+            .position(|t| t.kind == TileKind::Start)
+            .unwrap();
+        assert_eq!(&px[start * 4..start * 4 + 4], &[0x58, 0xa6, 0xff, 0xff]);
+        // The colours follow the palette the board is drawn in
+        let contrast = terrain_pixels(&rs, &HIGH_CONTRAST_PALETTE);
+        assert_eq!(
+            &contrast[start * 4..start * 4 + 4],
+            &[0x56, 0xb4, 0xe9, 0xff]
+        );
+    }
+}
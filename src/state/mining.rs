@@ -1,4 +1,12 @@
-// Mining progress state extracted from main.rs
+// Mining progress state extracted from main.rs, plus the Shift-click queue: rocks lined up to be
+// mined one after another once the current one is done, without holding the button.
+use std::collections::VecDeque;
+
+use crate::model::{RunState, TileKind};
+use crate::state::compute_interactable_mask;
+
+pub const MINING_QUEUE_MAX: usize = 5;
+
 #[derive(Default, Debug, Clone)]
 pub struct Mining {
     pub tile_x: i32,
@@ -8,4 +16,256 @@ pub struct Mining {
     pub progress: f64,
     pub active: bool,
     pub mouse_down: bool,
+    // The current tile came off the queue, so it keeps going with the button up
+    pub from_queue: bool,
+    // Queued tiles, next first
+    pub queue: VecDeque<(i32, i32)>,
+    // Last completed tile, until the run state shows it open
+    pub just_mined: Option<usize>,
+}
+
+// Rock or Wall the player could mine right now: reachable, and no tower or structure on it
+pub fn can_mine(rs: &RunState, mask: &[bool], x: i32, y: i32) -> bool {
+    let gs = rs.grid_size;
+    if x < 0 || y < 0 || x as u32 >= gs.width || y as u32 >= gs.height {
+        return false;
+    }
+    let (ux, uy) = (x as u32, y as u32);
+    let idx = (uy * gs.width + ux) as usize;
+    matches!(rs.tiles[idx].kind, TileKind::Rock { .. } | TileKind::Wall)
+        && mask.get(idx).copied().unwrap_or(false)
+        && rs.tower_at(ux, uy).is_none()
+        && !rs.structures.iter().any(|s| s.x == ux && s.y == uy)
+}
+
+impl Mining {
+    // Mining on tile (x, y) is under way
+    pub fn mining_now(&self) -> bool {
+        self.active && (self.mouse_down || self.from_queue)
+    }
+
+    // Begins mining (x, y) from scratch; `held` while the button stays down
+    pub fn begin(&mut self, rs: &RunState, x: i32, y: i32, held: bool) {
+        let gs = rs.grid_size;
+        let idx = (y as u32 * gs.width + x as u32) as usize;
+        let hardness = rs.tiles[idx].hardness.max(1) as f64;
+        self.tile_x = x;
+        self.tile_y = y;
+        self.required_secs = hardness / rs.effective_mining_speed().max(0.0001);
+        self.elapsed_secs = 0.0;
+        self.progress = 0.0;
+        self.active = true;
+        self.mouse_down = held;
+        self.from_queue = !held;
+    }
+
+    // Stops the current tile; the queue stays
+    pub fn stop(&mut self) {
+        self.active = false;
+        self.mouse_down = false;
+        self.from_queue = false;
+        self.progress = 0.0;
+        self.elapsed_secs = 0.0;
+    }
+
+    // Button up: a held mine stops, a queued one carries on
+    pub fn release(&mut self) {
+        if !self.from_queue {
+            self.stop();
+        }
+    }
+
+    // Shift-click: queues the tile, or takes it back off if it is already queued. False when
+    // the queue is full or the tile is already being mined.
+    pub fn toggle_queued(&mut self, x: i32, y: i32) -> bool {
+        if let Some(i) = self.queue.iter().position(|&t| t == (x, y)) {
+            self.queue.remove(i);
+            return true;
+        }
+        if self.queue.len() >= MINING_QUEUE_MAX || (self.mining_now() && self.tile() == (x, y)) {
+            return false;
+        }
+        self.queue.push_back((x, y));
+        true
+    }
+
+    pub fn clear_queue(&mut self) {
+        self.queue.clear();
+        if self.from_queue {
+            self.stop();
+        }
+    }
+
+    pub fn tile(&self) -> (i32, i32) {
+        (self.tile_x, self.tile_y)
+    }
+
+    // Records a finished tile: mining stops and the queue waits for the run to show it open
+    pub fn complete(&mut self, idx: usize) {
+        self.stop();
+        self.just_mined = Some(idx);
+    }
+
+    // Picks up the next queued tile once idle. Tiles that can no longer be mined are dropped on
+    // the way; nothing is started while the last completion hasn't reached `rs` yet, since the
+    // next tile may only become reachable through it. A completion the run turned down (the
+    // tile went out of reach meanwhile) leaves a tile that can't be mined, so that doesn't wait.
+    // True when a tile was started.
+    pub fn advance_queue(&mut self, rs: &RunState) -> bool {
+        if self.active || self.queue.is_empty() {
+            return false;
+        }
+        if rs.game_over {
+            self.queue.clear();
+            return false;
+        }
+        let mask = compute_interactable_mask(rs);
+        if let Some(idx) = self.just_mined {
+            let w = rs.grid_size.width as usize;
+            if can_mine(rs, &mask, (idx % w) as i32, (idx / w) as i32) {
+                return false;
+            }
+            self.just_mined = None;
+        }
+        while let Some((x, y)) = self.queue.pop_front() {
+            if can_mine(rs, &mask, x, y) {
+                self.begin(rs, x, y, false);
+                return true;
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{GridSize, UpgradeState, EMPTY_HARDNESS};
+
+    fn run() -> RunState {
+        let gs = GridSize {
+            width: 14,
+            height: 14,
+        };
+        RunState::new_with_upgrades_seeded(gs, &UpgradeState::default(), 11)
+    }
+
+    // Minable rocks, in reading order
+    fn frontier(rs: &RunState) -> Vec<(i32, i32)> {
+        let mask = compute_interactable_mask(rs);
+        let w = rs.grid_size.width as i32;
+        (0..rs.tiles.len() as i32)
+            .map(|i| (i % w, i / w))
+            .filter(|&(x, y)| can_mine(rs, &mask, x, y))
+            .collect()
+    }
+
+    fn open(rs: &mut RunState, (x, y): (i32, i32)) -> usize {
+        let idx = (y as u32 * rs.grid_size.width + x as u32) as usize;
+        rs.tiles[idx].kind = TileKind::Empty;
+        rs.tiles[idx].hardness = EMPTY_HARDNESS;
+        idx
+    }
+
+    #[test]
+    fn queue_is_fifo_capped_and_toggles() {
+        let mut m = Mining::default();
+        for i in 0..MINING_QUEUE_MAX as i32 {
+            assert!(m.toggle_queued(i, 0));
+        }
+        assert!(!m.toggle_queued(9, 9), "full");
+        // Clicking a queued tile again takes it off
+        assert!(m.toggle_queued(2, 0));
+        assert!(m.toggle_queued(9, 9));
+        let order: Vec<_> = m.queue.iter().copied().collect();
+        assert_eq!(order, vec![(0, 0), (1, 0), (3, 0), (4, 0), (9, 9)]);
+    }
+
+    #[test]
+    fn queued_tiles_are_mined_one_after_another() {
+        let mut rs = run();
+        let rocks = frontier(&rs);
+        let (a, b) = (rocks[0], rocks[1]);
+        let mut m = Mining::default();
+        m.toggle_queued(a.0, a.1);
+        m.toggle_queued(b.0, b.1);
+        assert!(m.advance_queue(&rs));
+        assert!(m.mining_now() && m.from_queue && m.tile() == a);
+        assert!(m.required_secs > 0.0);
+        // The button going up doesn't stop a queued tile, and nothing else starts meanwhile
+        m.release();
+        assert!(m.mining_now());
+        assert!(!m.advance_queue(&rs));
+
+        let idx = open(&mut rs, a);
+        let stale = rs.clone();
+        rs.tiles[idx].kind = TileKind::Rock {
+            has_gold: false,
+            boost: None,
+        };
+        m.complete(idx);
+        // The snapshot doesn't show the mined tile yet: wait for it
+        assert!(!m.advance_queue(&rs));
+        assert!(m.advance_queue(&stale));
+        assert_eq!(m.tile(), b);
+        assert!(m.queue.is_empty());
+    }
+
+    #[test]
+    fn a_completion_the_run_turned_down_doesnt_hold_up_the_queue() {
+        let mut rs = run();
+        let rocks = frontier(&rs);
+        let (a, b) = (rocks[0], rocks[rocks.len() - 1]);
+        let mut m = Mining::default();
+        m.toggle_queued(b.0, b.1);
+        let w = rs.grid_size.width as i32;
+        m.complete((a.1 * w + a.0) as usize);
+        assert!(!m.advance_queue(&rs));
+        // Walls went up around `a` before the completion landed, so the run kept it as rock
+        for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+            let i = ((a.1 + dy) * w + a.0 + dx) as usize;
+            if rs.tiles[i].kind == TileKind::Empty {
+                rs.tiles[i].kind = TileKind::Wall;
+            }
+        }
+        assert!(!can_mine(&rs, &compute_interactable_mask(&rs), a.0, a.1));
+        assert!(m.advance_queue(&rs));
+        assert_eq!(m.tile(), b);
+        assert!(m.just_mined.is_none());
+    }
+
+    #[test]
+    fn unminable_tiles_drop_out_and_game_over_clears() {
+        let mut rs = run();
+        let rocks = frontier(&rs);
+        let mut m = Mining::default();
+        // Off the grid, then a tile opened meanwhile, then a real one
+        m.toggle_queued(-1, 3);
+        m.toggle_queued(rocks[0].0, rocks[0].1);
+        m.toggle_queued(rocks[1].0, rocks[1].1);
+        open(&mut rs, rocks[0]);
+        assert!(m.advance_queue(&rs));
+        assert_eq!(m.tile(), rocks[1]);
+
+        m.stop();
+        m.toggle_queued(rocks[2].0, rocks[2].1);
+        rs.game_over = true;
+        assert!(!m.advance_queue(&rs));
+        assert!(m.queue.is_empty());
+    }
+
+    #[test]
+    fn a_held_mine_elsewhere_clears_the_queue() {
+        let rs = run();
+        let rocks = frontier(&rs);
+        let mut m = Mining::default();
+        m.toggle_queued(rocks[0].0, rocks[0].1);
+        m.toggle_queued(rocks[1].0, rocks[1].1);
+        assert!(m.advance_queue(&rs));
+        m.clear_queue();
+        m.begin(&rs, rocks[2].0, rocks[2].1, true);
+        assert!(m.queue.is_empty() && m.mouse_down && !m.from_queue);
+        m.release();
+        assert!(!m.mining_now());
+    }
 }
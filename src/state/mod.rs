@@ -1,9 +1,61 @@
 pub mod camera;
+pub mod camera_bookmarks;
+pub mod catch_up;
+pub mod enemy_motion;
+pub mod exclusion_brush;
+pub mod focus_tile;
+pub mod guided_tour;
+pub mod hotkeys;
+pub mod hud_layout;
 pub mod interactable;
+pub mod invariants;
+pub mod key_hints;
+pub mod minimap;
 pub mod mining;
+pub mod music;
+pub mod next_run;
+pub mod path_overlay;
+pub mod prospector;
+pub mod render_cache;
+pub mod render_scheduler;
+pub mod rewind;
+pub mod sound_cues;
+pub mod stamp_cursor;
+pub mod theme;
 pub mod touch;
+pub mod tower_totals;
+pub mod tutorial;
+pub mod wall_drag;
+pub mod wall_preview;
 
-pub use camera::Camera;
+pub use camera::{edge_scroll_dir, Camera, PanDir};
+pub use camera_bookmarks::{bookmark_key, is_text_entry, BookmarkKey, Bookmarks, BOOKMARK_SLOTS};
+pub use catch_up::CatchUp;
+pub use enemy_motion::EnemyMotion;
+pub use exclusion_brush::ExclusionBrush;
+pub use focus_tile::{FocusSource, FocusTile, GamepadDpad, GAMEPAD_DPAD};
+pub use guided_tour::{tour_locked_node, GuidedTour, TourEvent, UpgradesTourStep, UPGRADES_TOUR};
+pub use hotkeys::{speed_key, HotkeyAction, Hotkeys};
+pub use hud_layout::{HudLayout, HudLayoutSettings, HudMode};
 pub use interactable::compute_interactable_mask;
+pub use key_hints::{key_hints, HintAction};
+pub use minimap::{terrain_pixels, MinimapFit, MINIMAP_PX};
 pub use mining::Mining;
+pub use music::{music_tempo, AudioSettings, MusicDirector};
+pub use next_run::{levels_hash, NextRun};
+pub use path_overlay::{
+    arrow_marks, leg_lengths, loop_segments, path_nodes_text, SegmentKind, ARROW_SPACING_TILES,
+    PATH_NODES_SHOWN,
+};
+pub use prospector::{Prospect, ProspectReadout};
+pub use render_cache::{RenderCache, StaticLayer};
+pub use render_scheduler::{FrameInputs, RenderScheduler};
+pub use rewind::RewindBuffer;
+pub use sound_cues::{cues_between, Cue};
+pub use stamp_cursor::{StampCursor, StampPreview};
+pub use theme::{PaletteId, ThemeSettings, UI_SCALE_MAX, UI_SCALE_MIN};
 pub use touch::TouchState;
+pub use tower_totals::TowerTotals;
+pub use tutorial::{advance_tutorial, tutorial_target, TutorialState};
+pub use wall_drag::WallDrag;
+pub use wall_preview::WallPreview;
@@ -0,0 +1,193 @@
+// Background music direction: once per survived second the board is scored for intensity
+// (crowd size, recent life loss, a boss on the loop), and the music level eases toward that
+// score so the arpeggio speeds up and swells without hard cuts. The audio graph itself lives
+// in crate::audio; this side is pure so it can be tested natively.
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::{EnemyKind, RunState};
+
+// Enemies on the board at which the crowd term maxes out
+pub const MUSIC_FULL_CROWD: f64 = 40.0;
+// Seconds of life loss the danger term remembers
+pub const MUSIC_LOSS_WINDOW_SECS: usize = 10;
+// Time constant of the ease toward the target, in seconds
+pub const MUSIC_EASE_SECS: f64 = 4.0;
+
+// Volumes are percentages, the master one scaling the other two; mute silences music and
+// effects alike
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioSettings {
+    pub master_volume: u8,
+    pub music_volume: u8,
+    pub sfx_volume: u8,
+    pub muted: bool,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self {
+            master_volume: 100,
+            music_volume: 50,
+            sfx_volume: 80,
+            muted: false,
+        }
+    }
+}
+
+impl AudioSettings {
+    // Output gains for (music, effects), 0..=1
+    pub fn gains(&self) -> (f32, f32) {
+        if self.muted {
+            return (0.0, 0.0);
+        }
+        let g = |v: u8| v.min(100) as f32 / 100.0;
+        let master = g(self.master_volume);
+        (master * g(self.music_volume), master * g(self.sfx_volume))
+    }
+}
+
+// 0..=1: half from the crowd, the rest from life lost in the window (a quarter of life_max
+// maxes it out) and a boss being on the board
+pub fn intensity_score(enemies: usize, recent_life_loss: u32, life_max: u32, boss: bool) -> f64 {
+    let crowd = (enemies as f64 / MUSIC_FULL_CROWD).min(1.0);
+    let danger = if life_max == 0 {
+        0.0
+    } else {
+        (recent_life_loss as f64 * 4.0 / life_max as f64).min(1.0)
+    };
+    (0.5 * crowd + 0.3 * danger + 0.2 * f64::from(u8::from(boss))).clamp(0.0, 1.0)
+}
+
+// Exponential approach: after `dt` seconds the gap to `target` has shrunk by e^(-dt / tau)
+pub fn approach(current: f64, target: f64, dt: f64, tau: f64) -> f64 {
+    if tau <= 0.0 {
+        return target;
+    }
+    target + (current - target) * (-dt / tau).exp()
+}
+
+// Arpeggio notes per second at a music level
+pub fn music_tempo(level: f64) -> f64 {
+    2.0 + 4.0 * level.clamp(0.0, 1.0)
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct MusicDirector {
+    // Eased intensity, 0..=1
+    pub level: f64,
+    // (run_id, life) at the previous tick
+    last: Option<(u64, u32)>,
+    // Life lost in each of the last MUSIC_LOSS_WINDOW_SECS ticks
+    losses: VecDeque<u32>,
+}
+
+impl MusicDirector {
+    // Call once per survived second; returns the new level. A new run forgets old losses.
+    pub fn tick(&mut self, rs: &RunState) -> f64 {
+        let lost = match self.last {
+            Some((run_id, life)) if run_id == rs.run_id => life.saturating_sub(rs.life),
+            Some(_) => {
+                self.losses.clear();
+                0
+            }
+            None => 0,
+        };
+        self.last = Some((rs.run_id, rs.life));
+        self.losses.push_back(lost);
+        while self.losses.len() > MUSIC_LOSS_WINDOW_SECS {
+            self.losses.pop_front();
+        }
+        let boss = rs.enemies.iter().any(|e| e.kind == EnemyKind::Mason);
+        let target = intensity_score(
+            rs.enemies.len(),
+            self.losses.iter().sum(),
+            rs.life_max,
+            boss,
+        );
+        self.level = approach(self.level, target, 1.0, MUSIC_EASE_SECS);
+        self.level
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::GridSize;
+
+    #[test]
+    fn intensity_weighs_crowd_losses_and_bosses() {
+        assert_eq!(intensity_score(0, 0, 10, false), 0.0);
+        assert!((intensity_score(20, 0, 10, false) - 0.25).abs() < 1e-12);
+        // The crowd term saturates
+        assert!((intensity_score(400, 0, 10, false) - 0.5).abs() < 1e-12);
+        // Losing a quarter of life_max maxes the danger term
+        assert!((intensity_score(0, 1, 20, false) - 0.06).abs() < 1e-12);
+        assert!((intensity_score(0, 5, 20, false) - 0.3).abs() < 1e-12);
+        assert_eq!(intensity_score(400, 50, 20, true), 1.0);
+        assert_eq!(intensity_score(0, 3, 0, false), 0.0);
+    }
+
+    #[test]
+    fn approach_eases_without_overshoot() {
+        let mut level = 0.0;
+        let mut prev = level;
+        for _ in 0..20 {
+            level = approach(level, 1.0, 1.0, MUSIC_EASE_SECS);
+            assert!(level > prev && level < 1.0);
+            prev = level;
+        }
+        assert!(level > 0.99);
+        // One time constant closes 1 - 1/e of the gap, either direction
+        assert!((approach(1.0, 0.0, 4.0, 4.0) - (-1.0f64).exp()).abs() < 1e-12);
+        assert_eq!(approach(0.3, 0.8, 1.0, 0.0), 0.8);
+    }
+
+    #[test]
+    fn director_remembers_recent_losses_per_run() {
+        let mut rs = RunState::new_basic(GridSize {
+            width: 10,
+            height: 10,
+        });
+        let mut music = MusicDirector::default();
+        music.tick(&rs);
+        assert_eq!(music.level, 0.0);
+        rs.life -= 2;
+        let hurt = music.tick(&rs);
+        assert!(hurt > 0.0);
+        // The level keeps climbing while the loss is in the window, then eases back down
+        let mut peak = hurt;
+        for _ in 1..MUSIC_LOSS_WINDOW_SECS {
+            let level = music.tick(&rs);
+            assert!(level > peak);
+            peak = level;
+        }
+        assert!(music.tick(&rs) < peak);
+        assert_eq!(music.losses.iter().sum::<u32>(), 0);
+        // A fresh run with more life doesn't count as healing, nor its start as a loss
+        rs.run_id += 1;
+        rs.life = 5;
+        music.tick(&rs);
+        assert_eq!(music.losses.iter().sum::<u32>(), 0);
+    }
+
+    #[test]
+    fn mute_silences_both_buses() {
+        let s = AudioSettings {
+            master_volume: 100,
+            music_volume: 40,
+            sfx_volume: 150,
+            muted: false,
+        };
+        assert_eq!(s.gains(), (0.4, 1.0));
+        assert_eq!(AudioSettings { muted: true, ..s }.gains(), (0.0, 0.0));
+        let (music, sfx) = AudioSettings {
+            master_volume: 50,
+            ..s
+        }
+        .gains();
+        assert!((music - 0.2).abs() < 1e-6 && (sfx - 0.5).abs() < 1e-6);
+    }
+}
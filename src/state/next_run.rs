@@ -0,0 +1,151 @@
+// Warm restart: while the game-over screen is up, the next run is generated ahead of the Restart
+// click. The prepared state is keyed on everything that shaped it (upgrade levels, seed, grid
+// size), so buying upgrades in between throws it away and builds again; a Restart that finds
+// nothing ready for its key falls back to generating on the spot.
+use crate::model::{
+    play_area_size_for_level, random_map_seed, GridSize, RunState, UpgradeId, UpgradeState,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NextRunKey {
+    pub levels: u64,
+    pub seed: u64,
+    pub size: u32,
+}
+
+impl NextRunKey {
+    pub fn new(ups: &UpgradeState, seed: u64) -> Self {
+        Self {
+            levels: levels_hash(ups),
+            seed,
+            size: play_area_size_for_level(ups.level(UpgradeId::PlayAreaSize)),
+        }
+    }
+
+    pub fn generate(&self, ups: &UpgradeState) -> RunState {
+        let gs = GridSize {
+            width: self.size,
+            height: self.size,
+        };
+        RunState::new_with_upgrades_seeded(gs, ups, self.seed)
+    }
+}
+
+// FNV-1a over the bought levels in key order and the prestige points; unbought entries don't
+// count, so a state loaded without them hashes the same as the default one
+pub fn levels_hash(ups: &UpgradeState) -> u64 {
+    let mut bought: Vec<(&str, u8)> = ups
+        .levels
+        .iter()
+        .filter(|(_, lvl)| **lvl > 0)
+        .map(|(k, lvl)| (k.as_str(), *lvl))
+        .collect();
+    bought.sort_unstable();
+    let mut h: u64 = 0xcbf2_9ce4_8422_2325;
+    let mut eat = |b: u8| h = (h ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3);
+    for (key, lvl) in bought {
+        key.bytes().for_each(&mut eat);
+        eat(b'=');
+        eat(lvl);
+    }
+    eat(ups.tower_refund_rate_percent);
+    ups.meta.prestige_points.to_le_bytes().into_iter().for_each(eat);
+    h
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct NextRun {
+    // Drawn when preparation starts and kept until a restart uses it, so regenerating after an
+    // upgrade purchase keeps the same map layout
+    seed: Option<u64>,
+    ready: Option<(NextRunKey, RunState)>,
+}
+
+impl NextRun {
+    pub fn key(&mut self, ups: &UpgradeState) -> NextRunKey {
+        NextRunKey::new(ups, *self.seed.get_or_insert_with(random_map_seed))
+    }
+
+    // Nothing prepared for this key yet
+    pub fn wants(&self, key: &NextRunKey) -> bool {
+        self.ready.as_ref().is_none_or(|(k, _)| k != key)
+    }
+
+    pub fn store(&mut self, key: NextRunKey, state: RunState) {
+        self.ready = Some((key, state));
+    }
+
+    // The prepared run if it still matches `key`. Either way the slot and seed are spent.
+    pub fn take(&mut self, key: &NextRunKey) -> Option<RunState> {
+        self.seed = None;
+        self.ready
+            .take()
+            .filter(|(k, _)| k == key)
+            .map(|(_, state)| state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seeded(seed: u64) -> NextRun {
+        NextRun {
+            seed: Some(seed),
+            ready: None,
+        }
+    }
+
+    #[test]
+    fn key_follows_levels_seed_and_size() {
+        let ups = UpgradeState::default();
+        let base = NextRunKey::new(&ups, 7);
+        assert_eq!(base, NextRunKey::new(&ups, 7));
+        assert_ne!(base, NextRunKey::new(&ups, 8));
+
+        // Missing zero-level entries hash like present ones
+        let mut sparse = ups.clone();
+        sparse.levels.retain(|_, lvl| *lvl > 0);
+        assert_eq!(NextRunKey::new(&sparse, 7), base);
+
+        let mut bought = ups.clone();
+        bought.purchase(UpgradeId::TowerDamage1);
+        assert_ne!(NextRunKey::new(&bought, 7).levels, base.levels);
+
+        let mut bigger = ups.clone();
+        bigger
+            .levels
+            .insert(UpgradeId::PlayAreaSize.key().into(), 1);
+        assert_ne!(NextRunKey::new(&bigger, 7).size, base.size);
+    }
+
+    #[test]
+    fn prepared_run_is_swapped_in_only_for_its_key() {
+        let ups = UpgradeState::default();
+        let mut next = seeded(11);
+        let key = next.key(&ups);
+        assert!(next.wants(&key));
+        next.store(key, key.generate(&ups));
+        assert!(!next.wants(&key));
+        let run = next.take(&key).expect("prepared");
+        assert_eq!(run.map_seed, 11);
+        assert_eq!(run.tiles, key.generate(&ups).tiles);
+        // Spent: nothing left to take
+        assert!(next.take(&key).is_none());
+    }
+
+    #[test]
+    fn upgrades_bought_meanwhile_invalidate_but_keep_the_seed() {
+        let ups = UpgradeState::default();
+        let mut next = seeded(11);
+        let key = next.key(&ups);
+        next.store(key, key.generate(&ups));
+        let mut bought = ups.clone();
+        bought.purchase(UpgradeId::TowerDamage1);
+        let rekey = next.key(&bought);
+        assert_eq!(rekey.seed, key.seed);
+        assert!(next.wants(&rekey));
+        // A restart now finds a stale run and must generate
+        assert!(next.take(&rekey).is_none());
+    }
+}
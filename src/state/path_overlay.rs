@@ -0,0 +1,195 @@
+// Show-path overlay layout: which legs of the loop came from the path search and which were
+// stitched on by model::build_loop_path, where the direction arrows sit, and the node readout.
+// Pure, so run_view only draws what this lays out.
+use std::collections::HashSet;
+
+use crate::model::{route_is_open, Position, RunState};
+
+// One arrowhead every this many tiles of loop
+pub const ARROW_SPACING_TILES: f64 = 3.0;
+// Nodes the readout lists before cutting off, unless all of them are asked for
+pub const PATH_NODES_SHOWN: usize = 14;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SegmentKind {
+    // A step of the searched Entrance-to-Exit path
+    Searched,
+    // Start, Entrance and Exit joined on, and the leg closing the loop back to Start
+    Stitched,
+    // Left from a path that no longer exists: the loop can cut across unrelated tiles
+    Stale,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Segment {
+    pub from: Position,
+    pub to: Position,
+    pub kind: SegmentKind,
+}
+
+impl Segment {
+    pub fn length(&self) -> f64 {
+        let dx = self.to.x as f64 - self.from.x as f64;
+        let dy = self.to.y as f64 - self.from.y as f64;
+        (dx * dx + dy * dy).sqrt()
+    }
+}
+
+// The loop as enemies walk it, closing leg included unless the route ends at an End
+pub fn loop_segments(rs: &RunState) -> Vec<Segment> {
+    let nodes = &rs.path_loop;
+    if nodes.len() < 2 {
+        return Vec::new();
+    }
+    // Each step of the searched path once, rather than a scan of the path per leg
+    let step = |a: Position, b: Position| (a.x, a.y, b.x, b.y);
+    let searched: HashSet<_> = rs.path.windows(2).map(|w| step(w[0], w[1])).collect();
+    let kind_of = |a, b| {
+        if rs.path.is_empty() {
+            SegmentKind::Stale
+        } else if searched.contains(&step(a, b)) {
+            SegmentKind::Searched
+        } else {
+            SegmentKind::Stitched
+        }
+    };
+    let mut legs: Vec<(Position, Position)> = nodes.windows(2).map(|w| (w[0], w[1])).collect();
+    if !route_is_open(rs) {
+        legs.push((nodes[nodes.len() - 1], nodes[0]));
+    }
+    legs.into_iter()
+        .filter(|(a, b)| a != b)
+        .map(|(from, to)| Segment {
+            from,
+            to,
+            kind: kind_of(from, to),
+        })
+        .collect()
+}
+
+// Arrowheads along the segments: (tile x, tile y, heading in radians, kind under it), the
+// first half a spacing in so none sits on Start
+pub fn arrow_marks(segments: &[Segment], spacing: f64) -> Vec<(f64, f64, f64, SegmentKind)> {
+    let mut marks = Vec::new();
+    let mut next = spacing / 2.0;
+    let mut walked = 0.0;
+    for seg in segments {
+        let len = seg.length();
+        let (ax, ay) = (seg.from.x as f64 + 0.5, seg.from.y as f64 + 0.5);
+        let dx = seg.to.x as f64 - seg.from.x as f64;
+        let dy = seg.to.y as f64 - seg.from.y as f64;
+        while next <= walked + len {
+            let t = (next - walked) / len;
+            marks.push((ax + dx * t, ay + dy * t, dy.atan2(dx), seg.kind));
+            next += spacing;
+        }
+        walked += len;
+    }
+    marks
+}
+
+// Tiles walked on searched legs, and on stitched or stale ones
+pub fn leg_lengths(segments: &[Segment]) -> (f64, f64) {
+    segments.iter().fold((0.0, 0.0), |(searched, other), s| {
+        if s.kind == SegmentKind::Searched {
+            (searched + s.length(), other)
+        } else {
+            (searched, other + s.length())
+        }
+    })
+}
+
+// "(x,y) -> (x,y) ...", cut after PATH_NODES_SHOWN nodes unless `all`
+pub fn path_nodes_text(nodes: &[Position], all: bool) -> String {
+    if nodes.is_empty() {
+        return "(empty)".to_string();
+    }
+    let shown = if all { nodes.len() } else { PATH_NODES_SHOWN };
+    let mut s = nodes
+        .iter()
+        .take(shown)
+        .map(|p| format!("({},{})", p.x, p.y))
+        .collect::<Vec<_>>()
+        .join(" -> ");
+    if nodes.len() > shown {
+        s.push_str(" ...");
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{GridSize, UpgradeState};
+
+    fn run() -> RunState {
+        let gs = GridSize {
+            width: 14,
+            height: 14,
+        };
+        RunState::new_with_upgrades_seeded(gs, &UpgradeState::default(), 11)
+    }
+
+    fn pos(x: u32, y: u32) -> Position {
+        Position { x, y }
+    }
+
+    #[test]
+    fn searched_steps_are_told_apart_from_the_stitching() {
+        let rs = run();
+        let segs = loop_segments(&rs);
+        assert!(!segs.is_empty());
+        // Every searched leg is a single step, and the loop closes back onto Start
+        for s in segs.iter().filter(|s| s.kind == SegmentKind::Searched) {
+            assert_eq!(s.length(), 1.0);
+        }
+        assert!(segs.iter().any(|s| s.kind == SegmentKind::Stitched));
+        assert_eq!(segs.last().unwrap().to, rs.path_loop[0]);
+        let (searched, other) = leg_lengths(&segs);
+        assert!((searched + other - rs.loop_total_length).abs() < 1e-9);
+
+        // Without a path the old loop is all stale
+        let mut lost = rs.clone();
+        lost.path = Vec::new().into();
+        let stale = loop_segments(&lost);
+        assert_eq!(stale.len(), segs.len());
+        assert!(stale.iter().all(|s| s.kind == SegmentKind::Stale));
+    }
+
+    #[test]
+    fn arrows_are_spaced_along_the_walk() {
+        let a = Segment {
+            from: pos(0, 0),
+            to: pos(4, 0),
+            kind: SegmentKind::Searched,
+        };
+        let b = Segment {
+            from: pos(4, 0),
+            to: pos(4, 4),
+            kind: SegmentKind::Stitched,
+        };
+        let marks = arrow_marks(&[a, b], 3.0);
+        let at: Vec<_> = marks.iter().map(|m| (m.0, m.1, m.3)).collect();
+        assert_eq!(
+            at,
+            [
+                (2.0, 0.5, SegmentKind::Searched),
+                (4.5, 1.0, SegmentKind::Stitched),
+                (4.5, 4.0, SegmentKind::Stitched),
+            ]
+        );
+        assert_eq!(marks[0].2, 0.0);
+        assert!((marks[1].2 - std::f64::consts::FRAC_PI_2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn node_readout_cuts_off_unless_all_are_asked_for() {
+        assert_eq!(path_nodes_text(&[], false), "(empty)");
+        let two = [pos(1, 2), pos(1, 3)];
+        assert_eq!(path_nodes_text(&two, false), "(1,2) -> (1,3)");
+        let long: Vec<_> = (0..20).map(|x| pos(x, 0)).collect();
+        let short = path_nodes_text(&long, false);
+        assert!(short.ends_with("(13,0) ..."));
+        assert_eq!(path_nodes_text(&long, true).matches(" -> ").count(), 19);
+    }
+}
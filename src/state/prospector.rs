@@ -0,0 +1,240 @@
+// Prospector overlay: which gold rocks to mark, and how much marked gold lies in each
+// direction from Start. `shown` is the interactable mask; a fog-of-war mask can be and-ed into
+// it, and nothing outside it (or one step behind it) is ever marked.
+use crate::model::{GridSize, RunState, Tile, TileKind};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Prospect {
+    #[default]
+    Hidden,
+    // Gold rock on the frontier, minable now
+    Coin,
+    // Gold rock one step behind the frontier
+    Hint,
+}
+
+fn is_gold(t: &Tile) -> bool {
+    matches!(t.kind, TileKind::Rock { has_gold: true, .. })
+}
+
+pub fn prospect_tiles(tiles: &[Tile], gs: GridSize, shown: &[bool]) -> Vec<Prospect> {
+    let (w, h) = (gs.width as i32, gs.height as i32);
+    let mut out = vec![Prospect::Hidden; tiles.len()];
+    for y in 0..h {
+        for x in 0..w {
+            let idx = (y * w + x) as usize;
+            if !is_gold(&tiles[idx]) {
+                continue;
+            }
+            if shown[idx] {
+                out[idx] = Prospect::Coin;
+                continue;
+            }
+            let near_frontier = [(1, 0), (-1, 0), (0, 1), (0, -1)].iter().any(|(dx, dy)| {
+                let (nx, ny) = (x + dx, y + dy);
+                nx >= 0 && ny >= 0 && nx < w && ny < h && shown[(ny * w + nx) as usize]
+            });
+            if near_frontier {
+                out[idx] = Prospect::Hint;
+            }
+        }
+    }
+    out
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct QuadrantGold {
+    pub nw: u64,
+    pub ne: u64,
+    pub sw: u64,
+    pub se: u64,
+}
+
+impl QuadrantGold {
+    pub fn total(&self) -> u64 {
+        self.nw + self.ne + self.sw + self.se
+    }
+}
+
+pub fn start_tile(tiles: &[Tile], gs: GridSize) -> Option<(u32, u32)> {
+    let idx = tiles
+        .iter()
+        .position(|t| matches!(t.kind, TileKind::Start))?;
+    Some((idx as u32 % gs.width, idx as u32 / gs.width))
+}
+
+// Gold in marked rocks (coins and hints) per quadrant around Start. Tiles level with Start
+// count as south, tiles in its column as east, so every tile lands in exactly one quadrant.
+pub fn quadrant_gold(
+    prospects: &[Prospect],
+    gs: GridSize,
+    start: (u32, u32),
+    gold_per_rock: u64,
+) -> QuadrantGold {
+    let mut q = QuadrantGold::default();
+    for (idx, p) in prospects.iter().enumerate() {
+        if *p == Prospect::Hidden {
+            continue;
+        }
+        let (x, y) = (idx as u32 % gs.width, idx as u32 / gs.width);
+        let slot = match (y < start.1, x < start.0) {
+            (true, true) => &mut q.nw,
+            (true, false) => &mut q.ne,
+            (false, true) => &mut q.sw,
+            (false, false) => &mut q.se,
+        };
+        *slot += gold_per_rock;
+    }
+    q
+}
+
+// The HUD's quadrant readout, recomputed at most once per run version like TowerTotals
+#[derive(Default, Debug, Clone)]
+pub struct ProspectReadout {
+    key: Option<(u64, u64)>,
+    pub gold: Option<QuadrantGold>,
+    // Recomputations so far, to check nothing recomputes per frame
+    pub computed: u64,
+}
+
+impl ProspectReadout {
+    // Keyed on (run_id, version): a new run starts its versions over
+    pub fn refresh(&mut self, rs: &RunState) -> Option<QuadrantGold> {
+        let key = (rs.run_id, rs.version);
+        if self.key != Some(key) {
+            self.key = Some(key);
+            self.gold = start_tile(&rs.tiles, rs.grid_size).map(|start| {
+                let shown = crate::state::compute_interactable_mask(rs);
+                let prospects = prospect_tiles(&rs.tiles, rs.grid_size, &shown);
+                quadrant_gold(&prospects, rs.grid_size, start, rs.gold_per_rock())
+            });
+            self.computed += 1;
+        }
+        self.gold
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::default_hardness;
+
+    // S = Start, G = gold rock, R = plain rock, . = empty
+    fn board(rows: &[&str]) -> (Vec<Tile>, GridSize) {
+        let gs = GridSize {
+            width: rows[0].len() as u32,
+            height: rows.len() as u32,
+        };
+        let tiles = rows
+            .iter()
+            .flat_map(|r| r.chars())
+            .map(|c| {
+                let kind = match c {
+                    'S' => TileKind::Start,
+                    'G' => TileKind::Rock {
+                        has_gold: true,
+                        boost: None,
+                    },
+                    'R' => TileKind::Rock {
+                        has_gold: false,
+                        boost: None,
+                    },
+                    _ => TileKind::Empty,
+                };
+                Tile {
+                    hardness: default_hardness(&kind),
+                    kind,
+                    magma: false,
+                }
+            })
+            .collect();
+        (tiles, gs)
+    }
+
+    fn mask(rows: &[&str]) -> Vec<bool> {
+        rows.iter()
+            .flat_map(|r| r.chars())
+            .map(|c| c == '#')
+            .collect()
+    }
+
+    #[test]
+    fn frontier_gold_is_a_coin_and_the_rock_behind_it_a_hint() {
+        let (tiles, gs) = board(&["GGG", "GS.", "..."]);
+        let shown = mask(&["#..", "##.", "###"]);
+        let p = prospect_tiles(&tiles, gs, &shown);
+        assert_eq!(p[0], Prospect::Coin);
+        assert_eq!(p[1], Prospect::Hint);
+        // Diagonal to the frontier only: stays hidden
+        assert_eq!(p[2], Prospect::Hidden);
+        assert_eq!(p[3], Prospect::Coin);
+        assert!(p[4..].iter().all(|p| *p == Prospect::Hidden));
+    }
+
+    #[test]
+    fn quadrants_split_on_start_with_axis_tiles_south_and_east() {
+        let (tiles, gs) = board(&["G.G", "GSG", "G.G"]);
+        let p = prospect_tiles(&tiles, gs, &[true; 9]);
+        let q = quadrant_gold(&p, gs, start_tile(&tiles, gs).unwrap(), 2);
+        // (0,1) is level with Start: south-west; (2,1): south-east
+        assert_eq!(
+            q,
+            QuadrantGold {
+                nw: 2,
+                ne: 2,
+                sw: 4,
+                se: 4,
+            }
+        );
+        assert_eq!(q.total(), 12);
+    }
+
+    #[test]
+    fn start_on_the_edge_and_hidden_rocks_count_nothing() {
+        let (tiles, gs) = board(&["SGG", "GGG"]);
+        let shown = mask(&["##.", "..."]);
+        let p = prospect_tiles(&tiles, gs, &shown);
+        let q = quadrant_gold(&p, gs, (0, 0), 1);
+        // Nothing lies north or west of a corner Start
+        assert_eq!((q.nw, q.ne, q.sw), (0, 0, 0));
+        // Coin at (1,0), hints at (2,0), (0,1) and (1,1); (2,1) is two steps out
+        assert_eq!(q.se, 4);
+        assert_eq!(p[5], Prospect::Hidden);
+    }
+
+    #[test]
+    fn fresh_map_marks_gold_only_near_the_frontier() {
+        let rs = RunState::new_basic(GridSize {
+            width: 25,
+            height: 25,
+        });
+        let shown = crate::state::compute_interactable_mask(&rs);
+        let p = prospect_tiles(&rs.tiles, rs.grid_size, &shown);
+        for (idx, pr) in p.iter().enumerate() {
+            match pr {
+                Prospect::Coin => assert!(shown[idx] && is_gold(&rs.tiles[idx])),
+                Prospect::Hint => assert!(!shown[idx] && is_gold(&rs.tiles[idx])),
+                Prospect::Hidden => {}
+            }
+        }
+    }
+
+    #[test]
+    fn readout_recomputes_once_per_version() {
+        let mut rs = RunState::new_basic(GridSize {
+            width: 25,
+            height: 25,
+        });
+        let mut readout = ProspectReadout::default();
+        let first = readout.refresh(&rs);
+        assert!(first.is_some());
+        readout.refresh(&rs);
+        assert_eq!(readout.computed, 1);
+        rs.version += 1;
+        assert_eq!(readout.refresh(&rs), first);
+        assert_eq!(readout.computed, 2);
+        rs.run_id += 1;
+        readout.refresh(&rs);
+        assert_eq!(readout.computed, 3);
+    }
+}
@@ -0,0 +1,176 @@
+// Bookkeeping for the static board layer: background, grid lines, tiles and the fog over
+// unreachable tiles only change with the terrain, so they are drawn once into an offscreen canvas
+// and blitted under the moving parts each frame. The layer is drawn at a power-of-two tile size,
+// so zooming redraws it only when crossing a doubling; a layer that would not fit LAYER_MAX_PX
+// is skipped and the board draws directly instead.
+use crate::model::{GridSize, Position, RunState, Shared, Tile};
+
+use super::compute_interactable_mask;
+
+// Longest side of the offscreen canvas; 4096² RGBA is 64 MB
+pub const LAYER_MAX_PX: u32 = 4096;
+// Tiles below this many pixels lose their outlines anyway
+const LAYER_MIN_TILE_PX: u32 = 4;
+
+// Pixels per tile for the layer at this zoom: the on-screen tile size rounded up to a power of
+// two. None when the whole grid at that size would exceed LAYER_MAX_PX.
+pub fn layer_tile_px(zoom: f64, gs: GridSize) -> Option<u32> {
+    let on_screen = (zoom * 32.0).ceil().max(1.0) as u32;
+    let px = on_screen.next_power_of_two().max(LAYER_MIN_TILE_PX);
+    (gs.width.max(gs.height).saturating_mul(px) <= LAYER_MAX_PX).then_some(px)
+}
+
+// What the layer was drawn from. Shared compares by pointer first, so an unchanged board costs
+// nothing to check.
+#[derive(Clone, Debug)]
+struct Terrain {
+    run_id: u64,
+    tiles: Shared<Vec<Tile>>,
+    path: Shared<Vec<Position>>,
+    path_loop: Shared<Vec<Position>>,
+}
+
+impl Terrain {
+    fn of(rs: &RunState) -> Self {
+        Self {
+            run_id: rs.run_id,
+            tiles: rs.tiles.clone(),
+            path: rs.path.clone(),
+            path_loop: rs.path_loop.clone(),
+        }
+    }
+
+    fn matches(&self, rs: &RunState) -> bool {
+        self.run_id == rs.run_id
+            && self.tiles == rs.tiles
+            && self.path == rs.path
+            && self.path_loop == rs.path_loop
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct RenderCache {
+    terrain: Option<Terrain>,
+    mask: Vec<bool>,
+    // Tile size the layer holds, None when it needs drawing
+    layer_px: Option<u32>,
+    pub layers_drawn: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StaticLayer {
+    // Blit the cached layer, drawn at this many pixels per tile
+    Cached(u32),
+    // (Re)draw the layer at this tile size, then blit it
+    Redraw(u32),
+    // Too large to cache at this zoom: draw the visible tiles straight to the board
+    Direct,
+}
+
+impl RenderCache {
+    // Window resize, canvas re-created: the next frame redraws the layer
+    pub fn invalidate(&mut self) {
+        self.layer_px = None;
+    }
+
+    // Call once per frame before drawing. Refreshes the fog mask when the terrain changed.
+    pub fn update(&mut self, rs: &RunState, zoom: f64) -> StaticLayer {
+        if !self.terrain.as_ref().is_some_and(|t| t.matches(rs)) {
+            self.terrain = Some(Terrain::of(rs));
+            self.mask = compute_interactable_mask(rs);
+            self.layer_px = None;
+        }
+        let Some(px) = layer_tile_px(zoom, rs.grid_size) else {
+            self.layer_px = None;
+            return StaticLayer::Direct;
+        };
+        if self.layer_px == Some(px) {
+            return StaticLayer::Cached(px);
+        }
+        self.layer_px = Some(px);
+        self.layers_drawn += 1;
+        StaticLayer::Redraw(px)
+    }
+
+    // compute_interactable_mask for the state last passed to update
+    pub fn mask(&self) -> &[bool] {
+        &self.mask
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{TileKind, UpgradeState};
+
+    fn board() -> RunState {
+        RunState::new_with_upgrades_seeded(
+            GridSize {
+                width: 24,
+                height: 24,
+            },
+            &UpgradeState::default(),
+            3,
+        )
+    }
+
+    #[test]
+    fn layer_is_kept_until_the_terrain_changes() {
+        let rs = board();
+        let mut cache = RenderCache::default();
+        assert_eq!(cache.update(&rs, 1.0), StaticLayer::Redraw(32));
+        assert_eq!(cache.mask(), compute_interactable_mask(&rs).as_slice());
+        // A tick moves enemies but shares the board
+        let mut ticked = rs.clone();
+        ticked.version += 1;
+        ticked.enemies.clear();
+        assert_eq!(cache.update(&ticked, 1.0), StaticLayer::Cached(32));
+        // Mining a tile writes the board
+        let mut mined = ticked.clone();
+        let rock = mined
+            .tiles
+            .iter()
+            .position(|t| matches!(t.kind, TileKind::Rock { .. }))
+            .unwrap();
+        mined.tiles[rock].kind = TileKind::Empty;
+        assert_eq!(cache.update(&mined, 1.0), StaticLayer::Redraw(32));
+        assert_eq!(cache.update(&mined, 1.0), StaticLayer::Cached(32));
+        assert_eq!(cache.layers_drawn, 2);
+    }
+
+    #[test]
+    fn zoom_redraws_only_across_a_doubling() {
+        let rs = board();
+        let mut cache = RenderCache::default();
+        assert_eq!(cache.update(&rs, 1.0), StaticLayer::Redraw(32));
+        assert_eq!(cache.update(&rs, 0.8), StaticLayer::Cached(32));
+        assert_eq!(cache.update(&rs, 1.2), StaticLayer::Redraw(64));
+        assert_eq!(cache.update(&rs, 1.9), StaticLayer::Cached(64));
+        // Past the size cap the board is drawn directly, and zooming back out redraws
+        assert_eq!(cache.update(&rs, 6.0), StaticLayer::Direct);
+        assert_eq!(cache.update(&rs, 1.9), StaticLayer::Redraw(64));
+    }
+
+    #[test]
+    fn new_run_and_invalidate_rebuild_the_layer() {
+        let rs = board();
+        let mut cache = RenderCache::default();
+        cache.update(&rs, 1.0);
+        cache.invalidate();
+        assert_eq!(cache.update(&rs, 1.0), StaticLayer::Redraw(32));
+        let mut next = rs.clone();
+        next.run_id += 1;
+        assert_eq!(cache.update(&next, 1.0), StaticLayer::Redraw(32));
+    }
+
+    #[test]
+    fn large_grids_cache_only_when_zoomed_out() {
+        let gs = GridSize {
+            width: 255,
+            height: 255,
+        };
+        assert_eq!(layer_tile_px(0.5, gs), Some(16));
+        assert_eq!(layer_tile_px(0.1, gs), Some(4));
+        assert_eq!(layer_tile_px(1.0, gs), None);
+    }
+}
@@ -0,0 +1,153 @@
+// Dirty-flag gate for the RAF loop: an idle (paused, unstarted, game over) board with a still
+// camera skips drawing entirely. Direct draws from event handlers report through mark_drawn.
+
+// While the debug overlay is open, idle frames still refresh it this often
+pub const HUD_REFRESH_MS: f64 = 500.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FrameInputs {
+    pub sim_running: bool,
+    pub version: u64,
+    // (zoom, offset_x, offset_y)
+    pub camera: (f64, f64, f64),
+    // Something moves without a state change, e.g. the mining progress bar
+    pub animating: bool,
+    pub hud_visible: bool,
+    pub now_ms: f64,
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct RenderScheduler {
+    drawn: Option<(u64, (f64, f64, f64))>,
+    redraw_requested: bool,
+    last_draw_ms: f64,
+    pub frames_drawn: u64,
+    pub frames_skipped: u64,
+    // Draw cost per RAF frame, idle frames counting as zero
+    pub avg_frame_ms: f64,
+}
+
+impl RenderScheduler {
+    // One-shot: resize, settings changes, anything the other inputs can't see
+    pub fn request_redraw(&mut self) {
+        self.redraw_requested = true;
+    }
+
+    pub fn should_draw(&mut self, f: &FrameInputs) -> bool {
+        let dirty = f.sim_running
+            || f.animating
+            || self.redraw_requested
+            || self.drawn != Some((f.version, f.camera))
+            || (f.hud_visible && f.now_ms - self.last_draw_ms >= HUD_REFRESH_MS);
+        if !dirty {
+            self.frames_skipped += 1;
+            self.avg_frame_ms *= 0.9;
+        }
+        dirty
+    }
+
+    // Every completed draw, whether from the RAF loop or an event handler
+    pub fn mark_drawn(&mut self, version: u64, camera: (f64, f64, f64), now_ms: f64) {
+        self.drawn = Some((version, camera));
+        self.redraw_requested = false;
+        self.last_draw_ms = now_ms;
+    }
+
+    // RAF draws only, so the average reflects the loop's own cost
+    pub fn record_frame(&mut self, draw_ms: f64) {
+        self.frames_drawn += 1;
+        self.avg_frame_ms = self.avg_frame_ms * 0.9 + draw_ms * 0.1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn idle(version: u64, now_ms: f64) -> FrameInputs {
+        FrameInputs {
+            sim_running: false,
+            version,
+            camera: (1.0, 0.0, 0.0),
+            animating: false,
+            hud_visible: false,
+            now_ms,
+        }
+    }
+
+    #[test]
+    fn idle_board_stops_drawing_after_first_frame() {
+        let mut s = RenderScheduler::default();
+        let f = idle(3, 0.0);
+        assert!(s.should_draw(&f));
+        s.mark_drawn(3, f.camera, 0.0);
+        for i in 1..=10 {
+            assert!(!s.should_draw(&idle(3, i as f64 * 16.0)));
+        }
+        assert_eq!(s.frames_skipped, 10);
+    }
+
+    #[test]
+    fn each_invalidation_wakes_one_frame() {
+        let mut s = RenderScheduler::default();
+        s.mark_drawn(3, (1.0, 0.0, 0.0), 0.0);
+
+        // Version bump
+        assert!(s.should_draw(&idle(4, 16.0)));
+        s.mark_drawn(4, (1.0, 0.0, 0.0), 16.0);
+        assert!(!s.should_draw(&idle(4, 32.0)));
+
+        // Camera pan
+        let panned = FrameInputs {
+            camera: (1.0, 5.0, 0.0),
+            ..idle(4, 48.0)
+        };
+        assert!(s.should_draw(&panned));
+        s.mark_drawn(4, panned.camera, 48.0);
+        assert!(!s.should_draw(&panned));
+
+        // One-shot request clears once drawn
+        s.request_redraw();
+        assert!(s.should_draw(&panned));
+        s.mark_drawn(4, panned.camera, 64.0);
+        assert!(!s.should_draw(&panned));
+    }
+
+    #[test]
+    fn running_sim_and_animations_draw_every_frame() {
+        let mut s = RenderScheduler::default();
+        s.mark_drawn(1, (1.0, 0.0, 0.0), 0.0);
+        let running = FrameInputs {
+            sim_running: true,
+            ..idle(1, 16.0)
+        };
+        let mining = FrameInputs {
+            animating: true,
+            ..idle(1, 16.0)
+        };
+        for _ in 0..3 {
+            assert!(s.should_draw(&running));
+            assert!(s.should_draw(&mining));
+            s.mark_drawn(1, (1.0, 0.0, 0.0), 16.0);
+        }
+        assert_eq!(s.frames_skipped, 0);
+    }
+
+    #[test]
+    fn hud_refreshes_slowly_and_idle_cost_decays() {
+        let mut s = RenderScheduler::default();
+        s.mark_drawn(1, (1.0, 0.0, 0.0), 0.0);
+        s.record_frame(4.0);
+        let hud = |now_ms| FrameInputs {
+            hud_visible: true,
+            ..idle(1, now_ms)
+        };
+        assert!(!s.should_draw(&hud(100.0)));
+        assert!(s.should_draw(&hud(HUD_REFRESH_MS)));
+        s.mark_drawn(1, (1.0, 0.0, 0.0), HUD_REFRESH_MS);
+        for i in 1..60 {
+            s.should_draw(&idle(1, HUD_REFRESH_MS + i as f64));
+        }
+        assert!(s.avg_frame_ms < 0.01);
+    }
+}
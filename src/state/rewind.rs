@@ -0,0 +1,118 @@
+// Developer rewind: full snapshots of the last REWIND_SLOTS survived seconds of the run. Only
+// fed while the developer panel is open, and emptied when it closes.
+use std::collections::VecDeque;
+
+use crate::model::RunState;
+
+pub const REWIND_SLOTS: usize = 10;
+
+#[derive(Default)]
+pub struct RewindBuffer {
+    snaps: VecDeque<RunState>,
+}
+
+impl RewindBuffer {
+    // Takes one snapshot per survived second of a started run; false when none was added.
+    // A new run starts over, and after a rewind the snapshots from the abandoned future go.
+    pub fn record(&mut self, rs: &RunState) -> bool {
+        if !rs.started || rs.game_over {
+            return false;
+        }
+        let secs = rs.stats.time_survived_secs;
+        if self.snaps.back().is_some_and(|s| s.run_id != rs.run_id) {
+            self.snaps.clear();
+        }
+        while self
+            .snaps
+            .back()
+            .is_some_and(|s| s.stats.time_survived_secs > secs)
+        {
+            self.snaps.pop_back();
+        }
+        if self
+            .snaps
+            .back()
+            .is_some_and(|s| s.stats.time_survived_secs == secs)
+        {
+            return false;
+        }
+        self.snaps.push_back(rs.clone());
+        if self.snaps.len() > REWIND_SLOTS {
+            self.snaps.pop_front();
+        }
+        true
+    }
+
+    pub fn clear(&mut self) {
+        self.snaps.clear();
+    }
+
+    // Oldest first
+    pub fn get(&self, i: usize) -> Option<&RunState> {
+        self.snaps.get(i)
+    }
+
+    // Survival time of each snapshot, oldest first
+    pub fn times(&self) -> Vec<u64> {
+        self.snaps
+            .iter()
+            .map(|s| s.stats.time_survived_secs)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::GridSize;
+
+    fn run_at(run_id: u64, secs: u64) -> RunState {
+        let mut rs = RunState::new_basic(GridSize {
+            width: 10,
+            height: 10,
+        });
+        rs.started = true;
+        rs.run_id = run_id;
+        rs.stats.time_survived_secs = secs;
+        rs
+    }
+
+    #[test]
+    fn keeps_only_the_last_ten_seconds() {
+        let mut buf = RewindBuffer::default();
+        for secs in 0..25 {
+            assert!(buf.record(&run_at(1, secs)));
+            assert!(!buf.record(&run_at(1, secs)), "one snapshot per second");
+        }
+        assert_eq!(buf.snaps.len(), REWIND_SLOTS);
+        assert_eq!(buf.times(), (15..25).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn a_new_run_or_a_rewind_drops_stale_snapshots() {
+        let mut buf = RewindBuffer::default();
+        for secs in 0..6 {
+            buf.record(&run_at(1, secs));
+        }
+        // Rewound to 2s: 3..5 never happened now
+        buf.record(&run_at(1, 2));
+        assert_eq!(buf.times(), vec![0, 1, 2]);
+        buf.record(&run_at(1, 3));
+        assert_eq!(buf.times(), vec![0, 1, 2, 3]);
+        buf.record(&run_at(2, 0));
+        assert_eq!(buf.times(), vec![0]);
+        assert_eq!(buf.get(0).map(|s| s.run_id), Some(2));
+    }
+
+    #[test]
+    fn idle_boards_are_not_recorded() {
+        let mut buf = RewindBuffer::default();
+        let mut rs = run_at(1, 0);
+        rs.started = false;
+        assert!(!buf.record(&rs));
+        rs.started = true;
+        rs.game_over = true;
+        assert!(!buf.record(&rs));
+        assert!(buf.snaps.is_empty());
+    }
+}
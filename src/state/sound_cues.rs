@@ -0,0 +1,151 @@
+// Sound cues read off the run state: run_view keeps the last snapshot it heard and asks which
+// cues the next one calls for, so the reducer never has to know about audio. Pure, so the
+// mapping can be tested natively; crate::audio turns each cue into sound.
+use crate::model::RunState;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Cue {
+    Shot,
+    Kill,
+    Mined,
+    Gold,
+    LifeLost,
+    // A clutch kill refunded a life
+    ClutchSave,
+    GameOver,
+}
+
+impl Cue {
+    // Shortest gap between two plays of the cue, so a volley or a wave of kills in one frame
+    // after another reads as a rhythm rather than a buzz
+    pub fn min_gap_secs(self) -> f64 {
+        match self {
+            Cue::Shot => 0.08,
+            Cue::Kill | Cue::Gold => 0.1,
+            Cue::Mined | Cue::LifeLost | Cue::ClutchSave => 0.15,
+            Cue::GameOver => 0.0,
+        }
+    }
+}
+
+// What happened between `prev` and `next`, at most once per cue. Another run, or the same run
+// rewound, is a jump rather than something that happened, so it stays silent. Gold from a kill
+// is heard as the kill, and the life that ends a run as the game over.
+pub fn cues_between(prev: &RunState, next: &RunState) -> Vec<Cue> {
+    if next.run_id != prev.run_id || next.sim_time < prev.sim_time {
+        return Vec::new();
+    }
+    if next.game_over {
+        return if prev.game_over {
+            Vec::new()
+        } else {
+            vec![Cue::GameOver]
+        };
+    }
+    let newest_shot = |rs: &RunState| rs.projectiles.iter().map(|p| p.id).max();
+    let killed = next.stats.enemies_killed > prev.stats.enemies_killed;
+    let mut cues = Vec::new();
+    if newest_shot(next) > newest_shot(prev) {
+        cues.push(Cue::Shot);
+    }
+    if killed {
+        cues.push(Cue::Kill);
+    }
+    if next.stats.blocks_mined > prev.stats.blocks_mined {
+        cues.push(Cue::Mined);
+    }
+    if !killed && next.currencies.gold > prev.currencies.gold {
+        cues.push(Cue::Gold);
+    }
+    if next.life < prev.life {
+        cues.push(Cue::LifeLost);
+    }
+    if next.stats.clutch_saves > prev.stats.clutch_saves {
+        cues.push(Cue::ClutchSave);
+    }
+    cues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{EntityId, GridSize, Projectile, UpgradeState};
+
+    fn run() -> RunState {
+        let gs = GridSize {
+            width: 12,
+            height: 12,
+        };
+        let mut rs = RunState::new_with_upgrades_seeded(gs, &UpgradeState::default(), 5);
+        rs.started = true;
+        rs
+    }
+
+    fn shot(id: u64) -> Projectile {
+        Projectile {
+            id: EntityId(id),
+            x: 1.0,
+            y: 1.0,
+            vx: 0.0,
+            vy: 0.0,
+            remaining: 1.0,
+            damage: 1,
+            splash_radius: 0.0,
+            apply_debuff: None,
+            source: None,
+            age: 0.0,
+            bounces_remaining: 0,
+            slow: None,
+        }
+    }
+
+    #[test]
+    fn each_change_maps_to_its_cue() {
+        let prev = run();
+        assert!(cues_between(&prev, &prev).is_empty());
+        let mut next = prev.clone();
+        next.sim_time += 0.05;
+        next.projectiles.push(shot(7));
+        next.stats.blocks_mined += 2;
+        next.currencies.gold += 3;
+        next.life -= 1;
+        assert_eq!(
+            cues_between(&prev, &next),
+            vec![Cue::Shot, Cue::Mined, Cue::Gold, Cue::LifeLost]
+        );
+        // A shot landing isn't a shot fired, and a kill's bounty is part of the kill
+        let mut later = next.clone();
+        later.projectiles.clear();
+        later.stats.enemies_killed += 1;
+        later.currencies.gold += 5;
+        assert_eq!(cues_between(&next, &later), vec![Cue::Kill]);
+        // A clutch kill is heard as the kill and the life it gave back
+        let mut saved = later.clone();
+        saved.stats.enemies_killed += 1;
+        saved.stats.clutch_saves += 1;
+        saved.life += 1;
+        assert_eq!(
+            cues_between(&later, &saved),
+            vec![Cue::Kill, Cue::ClutchSave]
+        );
+    }
+
+    #[test]
+    fn jumps_are_silent_and_game_over_plays_once() {
+        let prev = run();
+        let mut rewound = prev.clone();
+        rewound.sim_time -= 1.0;
+        rewound.life -= 1;
+        assert!(cues_between(&prev, &rewound).is_empty());
+        let mut fresh = prev.clone();
+        fresh.run_id += 1;
+        fresh.stats.blocks_mined += 1;
+        assert!(cues_between(&prev, &fresh).is_empty());
+
+        let mut over = prev.clone();
+        over.life = 0;
+        over.game_over = true;
+        assert_eq!(cues_between(&prev, &over), vec![Cue::GameOver]);
+        assert!(cues_between(&over, &over).is_empty());
+    }
+}
@@ -0,0 +1,84 @@
+// Room stamp selection ('B' cycles the palette, 'R' rotates); a click stamps at the cursor
+use crate::model::{stamp_check, RunState, StampId, StampReject};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StampCursor {
+    pub selected: Option<StampId>,
+    pub rotation: u8,
+}
+
+impl StampCursor {
+    // Off -> each stamp in palette order -> off
+    pub fn cycle(&mut self) {
+        self.selected = match self.selected {
+            None => StampId::ALL.first().copied(),
+            Some(id) => StampId::ALL
+                .iter()
+                .skip_while(|s| **s != id)
+                .nth(1)
+                .copied(),
+        };
+    }
+
+    pub fn rotate(&mut self) {
+        self.rotation = (self.rotation + 1) % 4;
+    }
+}
+
+// (run_id, version, origin, stamp, rotation)
+type PreviewKey = (u64, u64, (u32, u32), StampId, u8);
+
+// The ghost's stamp_check, redone only when the run or the stamp under the cursor changes
+#[derive(Default, Debug, Clone)]
+pub struct StampPreview {
+    key: Option<PreviewKey>,
+    check: Option<Result<u64, StampReject>>,
+    // Recomputations so far, to check nothing recomputes per frame
+    pub computed: u64,
+}
+
+impl StampPreview {
+    // Keyed on (run_id, version) like TowerTotals, plus the origin tile and the stamp itself
+    pub fn refresh(
+        &mut self,
+        rs: &RunState,
+        origin: (u32, u32),
+        id: StampId,
+        rotation: u8,
+    ) -> &Result<u64, StampReject> {
+        let key = (rs.run_id, rs.version, origin, id, rotation);
+        if self.key != Some(key) {
+            self.key = Some(key);
+            self.computed += 1;
+            self.check = None;
+        }
+        self.check
+            .get_or_insert_with(|| stamp_check(rs, origin, id, rotation))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::GridSize;
+
+    #[test]
+    fn rechecks_once_per_version_and_tile() {
+        let mut rs = RunState::new_basic(GridSize {
+            width: 10,
+            height: 10,
+        });
+        let mut preview = StampPreview::default();
+        for _ in 0..5 {
+            preview.refresh(&rs, (2, 2), StampId::Platform, 0);
+        }
+        assert_eq!(preview.computed, 1);
+        preview.refresh(&rs, (3, 2), StampId::Platform, 0);
+        preview.refresh(&rs, (3, 2), StampId::Platform, 1);
+        assert_eq!(preview.computed, 3);
+        rs.version += 1;
+        let check = preview.refresh(&rs, (3, 2), StampId::Platform, 1).clone();
+        assert_eq!(check, stamp_check(&rs, (3, 2), StampId::Platform, 1));
+        assert_eq!(preview.computed, 4);
+    }
+}
@@ -0,0 +1,282 @@
+// Board palettes and the HUD scale. The terrain pass, the enemy and tower sprites and the legend
+// ask the palette for a role's color rather than spelling out hex, so the colorblind-friendly
+// set swaps in everywhere at once. The UI scale reaches the HUD panels as the --ui-scale CSS
+// variable on run_view's root (see .hud-frame in index.html).
+use serde::{Deserialize, Serialize};
+
+use crate::model::{BoostKind, EnemyKind, TowerKind};
+
+pub const UI_SCALE_MIN: f64 = 0.8;
+pub const UI_SCALE_MAX: f64 = 1.5;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PaletteId {
+    #[default]
+    Default,
+    // Okabe-Ito hues at higher lightness: boosts and roles told apart without red-green cues
+    HighContrast,
+}
+
+impl PaletteId {
+    pub const ALL: [PaletteId; 2] = [PaletteId::Default, PaletteId::HighContrast];
+    pub fn name(self) -> &'static str {
+        match self {
+            PaletteId::Default => "Default",
+            PaletteId::HighContrast => "High contrast",
+        }
+    }
+    pub fn palette(self) -> &'static Palette {
+        match self {
+            PaletteId::Default => &DEFAULT_PALETTE,
+            PaletteId::HighContrast => &HIGH_CONTRAST_PALETTE,
+        }
+    }
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|p| *p == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThemeSettings {
+    pub palette: PaletteId,
+    // HUD panel scale, UI_SCALE_MIN..=UI_SCALE_MAX
+    pub ui_scale: f64,
+}
+
+impl Default for ThemeSettings {
+    fn default() -> Self {
+        Self {
+            palette: PaletteId::Default,
+            ui_scale: 1.0,
+        }
+    }
+}
+
+impl ThemeSettings {
+    // The stored scale, pulled back into range (hand-edited storage included)
+    pub fn scale(&self) -> f64 {
+        if self.ui_scale.is_finite() {
+            self.ui_scale.clamp(UI_SCALE_MIN, UI_SCALE_MAX)
+        } else {
+            1.0
+        }
+    }
+    pub fn with_scale(self, ui_scale: f64) -> Self {
+        let next = Self { ui_scale, ..self };
+        Self {
+            ui_scale: next.scale(),
+            ..self
+        }
+    }
+}
+
+type Rgb = (u8, u8, u8);
+
+// Colors by what they mean on the board
+#[derive(Debug, PartialEq)]
+pub struct Palette {
+    pub path: &'static str,
+    pub rock: &'static str,
+    pub gold_rock: &'static str,
+    pub magma_rock: &'static str,
+    pub wall: &'static str,
+    pub indestructible: &'static str,
+    pub lava_floor: &'static str,
+    pub lava: &'static str,
+    pub start: &'static str,
+    pub entrance: &'static str,
+    pub exit: &'static str,
+    pub boost_slow: &'static str,
+    pub boost_damage: &'static str,
+    pub boost_fire: &'static str,
+    pub boost_range: &'static str,
+    pub boost_fire_rate: &'static str,
+    pub boost_healing: &'static str,
+    pub enemy_normal: Rgb,
+    pub enemy_mason: Rgb,
+    pub enemy_fast: Rgb,
+    pub enemy_tank: Rgb,
+    pub enemy_swarm: Rgb,
+    pub tower_basic: Rgb,
+    pub tower_slow: Rgb,
+    pub tower_damage: Rgb,
+}
+
+pub static DEFAULT_PALETTE: Palette = Palette {
+    path: "#082235",
+    rock: "#1d2430",
+    gold_rock: "#4d3b1f",
+    magma_rock: "#3d1c14",
+    wall: "#2a2f38",
+    indestructible: "#3c4454",
+    lava_floor: "#7a2a0e",
+    lava: "#d9581c",
+    start: "#58a6ff",
+    entrance: "#2ea043",
+    exit: "#f0883e",
+    boost_slow: "#203a5a",
+    boost_damage: "#5a2320",
+    boost_fire: "#5a3520",
+    boost_range: "#204a3a",
+    boost_fire_rate: "#4a3a20",
+    boost_healing: "#1f5130",
+    enemy_normal: (255, 80, 50),
+    enemy_mason: (196, 140, 72),
+    enemy_fast: (255, 200, 60),
+    enemy_tank: (150, 60, 170),
+    enemy_swarm: (120, 220, 90),
+    tower_basic: (255, 215, 0),
+    tower_slow: (88, 166, 255),
+    tower_damage: (248, 81, 73),
+};
+
+pub static HIGH_CONTRAST_PALETTE: Palette = Palette {
+    path: "#000000",
+    rock: "#4a4f59",
+    gold_rock: "#a07c10",
+    magma_rock: "#6e3a2a",
+    wall: "#7d848f",
+    indestructible: "#c9ced6",
+    lava_floor: "#5c2400",
+    lava: "#ff7b1c",
+    start: "#56b4e9",
+    entrance: "#009e73",
+    exit: "#f0e442",
+    boost_slow: "#0072b2",
+    boost_damage: "#d55e00",
+    boost_fire: "#e69f00",
+    boost_range: "#00a88a",
+    boost_fire_rate: "#cc79a7",
+    boost_healing: "#8fd3ff",
+    enemy_normal: (213, 94, 0),
+    enemy_mason: (230, 159, 0),
+    enemy_fast: (240, 228, 66),
+    enemy_tank: (204, 121, 167),
+    enemy_swarm: (86, 180, 233),
+    tower_basic: (255, 255, 255),
+    tower_slow: (0, 114, 178),
+    tower_damage: (213, 94, 0),
+};
+
+impl Palette {
+    pub fn boost(&self, boost: BoostKind) -> &'static str {
+        match boost {
+            BoostKind::Slow => self.boost_slow,
+            BoostKind::Damage => self.boost_damage,
+            BoostKind::Fire => self.boost_fire,
+            BoostKind::Range => self.boost_range,
+            BoostKind::FireRate => self.boost_fire_rate,
+            BoostKind::Healing => self.boost_healing,
+        }
+    }
+    pub fn enemy_rgb(&self, kind: EnemyKind) -> Rgb {
+        match kind {
+            EnemyKind::Normal => self.enemy_normal,
+            EnemyKind::Mason => self.enemy_mason,
+            EnemyKind::Fast => self.enemy_fast,
+            EnemyKind::Tank => self.enemy_tank,
+            EnemyKind::Swarm => self.enemy_swarm,
+        }
+    }
+    pub fn enemy_color(&self, kind: EnemyKind) -> String {
+        let (r, g, b) = self.enemy_rgb(kind);
+        format!("rgb({r},{g},{b})")
+    }
+    // Tower body on the canvas and its swatch in the tower picker
+    pub fn tower_color(&self, kind: &TowerKind) -> String {
+        let (r, g, b) = self.tower_rgb(kind);
+        format!("rgb({r},{g},{b})")
+    }
+    // Body of the placement ghost: the tower color at half strength
+    pub fn tower_tint(&self, kind: &TowerKind) -> String {
+        let (r, g, b) = self.tower_rgb(kind);
+        format!("rgba({r},{g},{b},0.5)")
+    }
+    fn tower_rgb(&self, kind: &TowerKind) -> Rgb {
+        match kind {
+            TowerKind::Basic => self.tower_basic,
+            TowerKind::Slow => self.tower_slow,
+            TowerKind::Damage => self.tower_damage,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Per-channel spread between two "#rrggbb" colors
+    fn distance(a: &str, b: &str) -> i32 {
+        let rgb = |s: &str| {
+            let v = u32::from_str_radix(&s[1..], 16).unwrap() as i32;
+            [v >> 16, (v >> 8) & 0xff, v & 0xff]
+        };
+        let (a, b) = (rgb(a), rgb(b));
+        (0..3).map(|i| (a[i] - b[i]).abs()).sum()
+    }
+
+    // Tile fills the player has to tell apart at a glance
+    fn tile_roles(p: &Palette) -> Vec<&'static str> {
+        let mut roles = vec![
+            p.path,
+            p.rock,
+            p.gold_rock,
+            p.magma_rock,
+            p.wall,
+            p.indestructible,
+            p.lava_floor,
+        ];
+        roles.extend(
+            [
+                BoostKind::Slow,
+                BoostKind::Damage,
+                BoostKind::Fire,
+                BoostKind::Range,
+                BoostKind::FireRate,
+                BoostKind::Healing,
+            ]
+            .map(|b| p.boost(b)),
+        );
+        roles
+    }
+
+    #[test]
+    fn high_contrast_spreads_the_tiles_further_apart() {
+        let closest = |p: &Palette| {
+            let roles = tile_roles(p);
+            let mut min = i32::MAX;
+            for (i, a) in roles.iter().enumerate() {
+                for b in &roles[i + 1..] {
+                    min = min.min(distance(a, b));
+                }
+            }
+            min
+        };
+        let default = closest(&DEFAULT_PALETTE);
+        let contrast = closest(&HIGH_CONTRAST_PALETTE);
+        assert!(default > 0);
+        assert!(contrast > default, "{contrast} vs {default}");
+        // Cold and poison boosts, the pair that started this, stand well apart
+        let p = &HIGH_CONTRAST_PALETTE;
+        assert!(distance(p.boost(BoostKind::Slow), p.boost(BoostKind::Damage)) > 200);
+    }
+
+    #[test]
+    fn settings_clamp_the_scale_and_fill_in_missing_fields() {
+        let s = ThemeSettings::default();
+        assert_eq!(s.with_scale(3.0).ui_scale, UI_SCALE_MAX);
+        assert_eq!(s.with_scale(0.1).ui_scale, UI_SCALE_MIN);
+        assert_eq!(s.with_scale(1.25).ui_scale, 1.25);
+        let odd = ThemeSettings {
+            ui_scale: f64::NAN,
+            ..s
+        };
+        assert_eq!(odd.scale(), 1.0);
+        let old: ThemeSettings = serde_json::from_str(r#"{"palette":"HighContrast"}"#).unwrap();
+        assert_eq!(old.palette, PaletteId::HighContrast);
+        assert_eq!(old.scale(), 1.0);
+        assert_eq!(PaletteId::HighContrast.next(), PaletteId::Default);
+    }
+}
@@ -0,0 +1,60 @@
+// Aggregate tower numbers for the stats panel (see model::total_tower_dps, model::loop_coverage),
+// recomputed at most once per run version rather than per frame
+use crate::model::{loop_coverage, total_tower_dps, RunState};
+
+#[derive(Default, Debug, Clone)]
+pub struct TowerTotals {
+    key: Option<(u64, u64)>,
+    pub dps: f64,
+    // Fraction of the loop in range of some tower, 0..=1
+    pub coverage: f64,
+    // Recomputations so far, to check nothing recomputes per frame
+    pub computed: u64,
+}
+
+impl TowerTotals {
+    // Keyed on (run_id, version): a new run starts its versions over
+    pub fn refresh(&mut self, rs: &RunState) {
+        let key = (rs.run_id, rs.version);
+        if self.key == Some(key) {
+            return;
+        }
+        self.key = Some(key);
+        self.dps = total_tower_dps(rs);
+        self.coverage = loop_coverage(rs);
+        self.computed += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{GridSize, Tower, TowerKind};
+
+    #[test]
+    fn recomputes_once_per_version() {
+        let mut rs = RunState::new_basic(GridSize {
+            width: 10,
+            height: 10,
+        });
+        let mut totals = TowerTotals::default();
+        for _ in 0..5 {
+            totals.refresh(&rs);
+        }
+        assert_eq!((totals.computed, totals.dps), (1, 0.0));
+        rs.towers
+            .push(Tower::new(1, 1, TowerKind::Basic, 3.5, 2, None));
+        // Same version: still the cached numbers
+        totals.refresh(&rs);
+        assert_eq!((totals.computed, totals.dps), (1, 0.0));
+        rs.version += 1;
+        totals.refresh(&rs);
+        totals.refresh(&rs);
+        assert_eq!(totals.computed, 2);
+        assert!(totals.dps > 0.0);
+        // Versions restart with each run
+        rs.run_id += 1;
+        totals.refresh(&rs);
+        assert_eq!(totals.computed, 3);
+    }
+}
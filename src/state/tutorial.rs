@@ -0,0 +1,270 @@
+// First-run tutorial: four steps shown one at a time, each done when the run shows the player
+// did it for real (a rock mined, a tower placed, a kill, a wall). Progress is the count of steps
+// done, kept under md_tutorial so a returning player picks up where they left off; Skip marks
+// them all done. run_view shares the state with the draw closure, which marks the step's tile.
+use serde::{Deserialize, Serialize};
+
+use crate::model::{auto_mine_target, feature_lock_message, Feature, RunState, TileKind};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TutorialStep {
+    Mine,
+    PlaceTower,
+    Kill,
+    PlaceWall,
+}
+
+pub const TUTORIAL_STEPS: [TutorialStep; 4] = [
+    TutorialStep::Mine,
+    TutorialStep::PlaceTower,
+    TutorialStep::Kill,
+    TutorialStep::PlaceWall,
+];
+
+impl TutorialStep {
+    pub fn title(self) -> &'static str {
+        match self {
+            Self::Mine => "Mine a rock",
+            Self::PlaceTower => "Place a tower",
+            Self::Kill => "Defeat an enemy",
+            Self::PlaceWall => "Build a wall",
+        }
+    }
+
+    pub fn text(self) -> &'static str {
+        match self {
+            Self::Mine => {
+                "Hold the left mouse button on the marked rock until its bar fills. Mining opens the ground and earns tile credits."
+            }
+            Self::PlaceTower => {
+                "Hover the marked rock beside the path and press T. Towers cost gold and a tile credit."
+            }
+            Self::Kill => {
+                "Enemies walk the loop from the Entrance. Let your towers finish one off; kills pay gold and research."
+            }
+            Self::PlaceWall => {
+                "Click an Empty tile to wall it. Walls on the path make the loop longer, as long as one way through is left."
+            }
+        }
+    }
+
+    // Locked on a player's first runs
+    pub fn feature(self) -> Option<Feature> {
+        match self {
+            Self::Mine => None,
+            Self::PlaceTower | Self::Kill => Some(Feature::Towers),
+            Self::PlaceWall => Some(Feature::Walls),
+        }
+    }
+
+    // Why the step has to wait, while its feature is still locked
+    pub fn lock_message(self, rs: &RunState) -> Option<String> {
+        self.feature()
+            .and_then(|f| feature_lock_message(rs.progression_run, f))
+    }
+
+    // The snapshot shows the step done
+    pub fn done_in(self, rs: &RunState) -> bool {
+        match self {
+            Self::Mine => rs.stats.blocks_mined >= 1,
+            Self::PlaceTower => rs.stats.towers_built >= 1,
+            Self::Kill => rs.stats.enemies_killed >= 1,
+            Self::PlaceWall => rs.stats.walls_built >= 1,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TutorialState {
+    // Steps done, in TUTORIAL_STEPS order
+    pub completed: usize,
+}
+
+impl TutorialState {
+    pub fn current(&self) -> Option<TutorialStep> {
+        TUTORIAL_STEPS.get(self.completed).copied()
+    }
+
+    // (step, total), 1-based for display
+    pub fn progress(&self) -> Option<(usize, usize)> {
+        self.current()
+            .map(|_| (self.completed + 1, TUTORIAL_STEPS.len()))
+    }
+
+    pub fn skipped(self) -> Self {
+        Self {
+            completed: TUTORIAL_STEPS.len(),
+        }
+    }
+}
+
+// Every step from the current one on that the snapshot shows done, in order
+pub fn advance_tutorial(state: TutorialState, rs: &RunState) -> TutorialState {
+    let mut next = state;
+    while next.current().is_some_and(|step| step.done_in(rs)) {
+        next.completed += 1;
+    }
+    next
+}
+
+// Tile the step points at: the rock the Auto Miner would take (beside the path, so a tower
+// there covers it too), the enemy furthest along its lap, or an Empty tile off the route. None while
+// the step is locked or there is nothing to point at.
+pub fn tutorial_target(step: TutorialStep, rs: &RunState, mask: &[bool]) -> Option<(u32, u32)> {
+    if step.lock_message(rs).is_some() {
+        return None;
+    }
+    let w = rs.grid_size.width;
+    let at = |idx: usize| (idx as u32 % w, idx as u32 / w);
+    match step {
+        TutorialStep::Mine | TutorialStep::PlaceTower => auto_mine_target(rs, mask).map(at),
+        TutorialStep::Kill => rs
+            .enemies
+            .iter()
+            .filter(|e| e.hp > 0)
+            .max_by(|a, b| {
+                // Measured from the Entrance, so one about to finish its lap beats one just in
+                let lap = rs.lap_frame();
+                lap.progress(a.loop_dist)
+                    .total_cmp(&lap.progress(b.loop_dist))
+            })
+            .map(|e| (e.x.max(0.0) as u32, e.y.max(0.0) as u32)),
+        TutorialStep::PlaceWall => {
+            let mut on_route = vec![false; rs.tiles.len()];
+            for p in rs.path.iter().chain(rs.path_loop.iter()) {
+                on_route[(p.y * w + p.x) as usize] = true;
+            }
+            (0..rs.tiles.len()).map(at).find(|&(x, y)| {
+                let idx = (y * w + x) as usize;
+                !on_route[idx]
+                    && rs.tiles[idx].kind == TileKind::Empty
+                    && rs.tower_at(x, y).is_none()
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Enemy, EntityId, GridSize, UpgradeState};
+    use crate::state::compute_interactable_mask;
+
+    fn run() -> RunState {
+        let gs = GridSize {
+            width: 14,
+            height: 14,
+        };
+        let mut rs = RunState::new_with_upgrades_seeded(gs, &UpgradeState::default(), 11);
+        rs.started = true;
+        rs
+    }
+
+    #[test]
+    fn each_step_waits_for_its_own_action() {
+        let mut rs = run();
+        let mut t = advance_tutorial(TutorialState::default(), &rs);
+        assert_eq!(t.current(), Some(TutorialStep::Mine));
+        assert_eq!(t.progress(), Some((1, 4)));
+        // A kill doesn't stand in for mining
+        rs.stats.enemies_killed = 1;
+        t = advance_tutorial(t, &rs);
+        assert_eq!(t.current(), Some(TutorialStep::Mine));
+
+        rs.stats.enemies_killed = 0;
+        rs.stats.blocks_mined = 1;
+        t = advance_tutorial(t, &rs);
+        assert_eq!(t.current(), Some(TutorialStep::PlaceTower));
+        rs.stats.towers_built = 1;
+        t = advance_tutorial(t, &rs);
+        assert_eq!(t.current(), Some(TutorialStep::Kill));
+        rs.stats.enemies_killed = 1;
+        t = advance_tutorial(t, &rs);
+        assert_eq!(t.current(), Some(TutorialStep::PlaceWall));
+        rs.stats.walls_built = 1;
+        t = advance_tutorial(t, &rs);
+        assert_eq!((t.current(), t.progress()), (None, None));
+    }
+
+    #[test]
+    fn saved_progress_resumes_and_skip_finishes() {
+        // A new run starts from zero stats, and the steps already done stay done
+        let rs = run();
+        let back: TutorialState = serde_json::from_str(r#"{"completed":2}"#).unwrap();
+        let resumed = advance_tutorial(back, &rs);
+        assert_eq!(resumed.current(), Some(TutorialStep::Kill));
+        // Several steps done in one go are all passed over
+        let mut busy = run();
+        busy.stats.blocks_mined = 3;
+        busy.stats.towers_built = 1;
+        busy.stats.enemies_killed = 4;
+        let t = advance_tutorial(TutorialState::default(), &busy);
+        assert_eq!(t.current(), Some(TutorialStep::PlaceWall));
+
+        assert_eq!(t.skipped().current(), None);
+        let old: TutorialState = serde_json::from_str("{}").unwrap();
+        assert_eq!(old, TutorialState::default());
+    }
+
+    #[test]
+    fn targets_point_at_the_step_and_wait_while_locked() {
+        let mut rs = run();
+        let mask = compute_interactable_mask(&rs);
+        let (x, y) = tutorial_target(TutorialStep::Mine, &rs, &mask).unwrap();
+        let idx = (y * rs.grid_size.width + x) as usize;
+        assert!(matches!(rs.tiles[idx].kind, TileKind::Rock { .. }) && mask[idx]);
+        assert_eq!(tutorial_target(TutorialStep::Kill, &rs, &mask), None);
+        // The wall goes on open ground off the route; the rock beside it, mined, is some
+        rs.tiles[idx].kind = TileKind::Empty;
+        let (wx, wy) = tutorial_target(TutorialStep::PlaceWall, &rs, &mask).unwrap();
+        let widx = (wy * rs.grid_size.width + wx) as usize;
+        assert_eq!(rs.tiles[widx].kind, TileKind::Empty);
+        assert!(!rs.path.iter().any(|p| (p.x, p.y) == (wx, wy)));
+
+        // First run: towers are locked, so the step says why rather than pointing
+        rs.progression_run = Some(1);
+        assert!(TutorialStep::PlaceTower.lock_message(&rs).is_some());
+        assert_eq!(tutorial_target(TutorialStep::PlaceTower, &rs, &mask), None);
+        assert!(TutorialStep::Mine.lock_message(&rs).is_none());
+    }
+
+    #[test]
+    fn kill_step_points_at_the_enemy_furthest_into_its_lap() {
+        let mut rs = run();
+        rs.loop_entry_dist = rs.loop_total_length / 2.0;
+        let at = |id: u64, d: f64| {
+            let p = rs.path_loop[id as usize];
+            Enemy {
+                id: EntityId(id),
+                x: p.x as f64,
+                y: p.y as f64,
+                speed_tps: 1.0,
+                hp: 5,
+                max_hp: 5,
+                spawned_at: 0,
+                path_index: 0,
+                dir_dx: 1.0,
+                dir_dy: 0.0,
+                radius_scale: 1.0,
+                loop_dist: d,
+                debuffs: Vec::new(),
+                kind: Default::default(),
+                hit_flash_ttl: 0.0,
+                spawn_anim_ttl: 0.0,
+            }
+        };
+        // Just past the Entrance, then half a tile short of coming back round to it
+        let (entry, total) = (rs.loop_entry_dist, rs.loop_total_length);
+        let fresh = at(1, entry + 1.0);
+        let lapping = at(2, (entry - 0.5).rem_euclid(total));
+        assert!(fresh.loop_dist > lapping.loop_dist);
+        let target = (lapping.x as u32, lapping.y as u32);
+        rs.enemies = vec![fresh, lapping];
+        let mask = compute_interactable_mask(&rs);
+        assert_eq!(
+            tutorial_target(TutorialStep::Kill, &rs, &mask),
+            Some(target)
+        );
+    }
+}
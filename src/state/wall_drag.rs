@@ -0,0 +1,125 @@
+// Shift-drag wall painting: the stroke queues Empty tiles under the cursor and the whole run is
+// placed on mouseup with one RunAction::PlaceWalls. `valid` tracks the prefix that keeps a path,
+// so the preview can show in red where the drag starts to block.
+use crate::model::{walls_keep_path, Position, RunState, TileKind};
+
+#[derive(Default, Debug, Clone)]
+pub struct WallDrag {
+    pub active: bool,
+    pub tiles: Vec<Position>,
+    // Leading tiles that still leave a path once walled
+    pub valid: usize,
+    last_tile: (i32, i32),
+}
+
+impl WallDrag {
+    pub fn start(&mut self, rs: &RunState, tx: i32, ty: i32) {
+        *self = WallDrag {
+            active: true,
+            last_tile: (tx, ty),
+            ..WallDrag::default()
+        };
+        self.queue(rs, tx, ty);
+        self.check(rs, 0);
+    }
+
+    // Queues every tile stepped through from the last one, so a quick flick leaves no gaps.
+    // False when nothing changed.
+    pub fn extend(&mut self, rs: &RunState, tx: i32, ty: i32) -> bool {
+        if !self.active || self.last_tile == (tx, ty) {
+            return false;
+        }
+        let queued = self.tiles.len();
+        let (mut x, mut y) = self.last_tile;
+        while (x, y) != (tx, ty) {
+            // Step along the axis with the larger gap left, one tile at a time
+            if (tx - x).abs() >= (ty - y).abs() {
+                x += (tx - x).signum();
+            } else {
+                y += (ty - y).signum();
+            }
+            self.queue(rs, x, y);
+        }
+        self.last_tile = (tx, ty);
+        self.check(rs, queued);
+        true
+    }
+
+    // Ends the stroke, handing back the queued tiles
+    pub fn finish(&mut self) -> (Vec<Position>, usize) {
+        let valid = self.valid;
+        let tiles = std::mem::take(&mut self.tiles);
+        *self = WallDrag::default();
+        (tiles, valid)
+    }
+
+    fn queue(&mut self, rs: &RunState, x: i32, y: i32) {
+        let gs = rs.grid_size;
+        if x < 0 || y < 0 || x as u32 >= gs.width || y as u32 >= gs.height {
+            return;
+        }
+        let p = Position {
+            x: x as u32,
+            y: y as u32,
+        };
+        let idx = (p.y * gs.width + p.x) as usize;
+        if !matches!(rs.tiles[idx].kind, TileKind::Empty) || self.tiles.contains(&p) {
+            return;
+        }
+        self.tiles.push(p);
+    }
+
+    // Extends `valid` over the tiles queued from `queued` on. One search covers the whole stroke
+    // while it keeps a path; only a stroke that blocks is searched tile by tile to find where.
+    fn check(&mut self, rs: &RunState, queued: usize) {
+        // Once a wall blocks, everything after it is rejected too
+        if self.valid < queued || self.valid == self.tiles.len() {
+            return;
+        }
+        if walls_keep_path(rs, &self.tiles) {
+            self.valid = self.tiles.len();
+            return;
+        }
+        while self.valid + 1 < self.tiles.len() && walls_keep_path(rs, &self.tiles[..=self.valid]) {
+            self.valid += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::GridSize;
+
+    #[test]
+    fn a_fast_stroke_fills_the_gap_and_skips_solid_tiles() {
+        let mut rs = RunState::new_basic(GridSize {
+            width: 8,
+            height: 8,
+        });
+        for t in rs.tiles.iter_mut() {
+            t.kind = TileKind::Empty;
+        }
+        rs.tiles[3 * 8 + 5].kind = TileKind::Wall;
+        let mut drag = WallDrag::default();
+        drag.start(&rs, 1, 1);
+        assert!(!drag.extend(&rs, 1, 1));
+        // Three across and two down in one mousemove: five steps, each next to the last
+        assert!(drag.extend(&rs, 4, 3));
+        assert_eq!(drag.tiles.len(), 6);
+        for w in drag.tiles.windows(2) {
+            assert_eq!(w[0].x.abs_diff(w[1].x) + w[0].y.abs_diff(w[1].y), 1);
+        }
+        // Walls underway are stepped over
+        drag.extend(&rs, 6, 3);
+        assert_eq!(drag.tiles.len(), 7);
+        assert!(!drag.tiles.contains(&Position { x: 5, y: 3 }));
+        assert!(drag.tiles.contains(&Position { x: 6, y: 3 }));
+        // Back along row 3 and off the board: only the on-board tiles not yet queued
+        drag.extend(&rs, -3, 3);
+        assert_eq!(drag.tiles.len(), 11);
+        let (tiles, _) = drag.finish();
+        assert_eq!(tiles.len(), 11);
+        assert!(!drag.active && drag.tiles.is_empty());
+    }
+}
@@ -0,0 +1,42 @@
+// Cached hover preview of a contemplated wall (see model::wall_placement_effect)
+use crate::model::{wall_placement_effect, PathDelta, RunState};
+
+// Grids larger than this skip the preview unless Shift is held
+pub const WALL_PREVIEW_MAX_TILES: u32 = 40 * 40;
+
+#[derive(Default, Debug, Clone)]
+pub struct WallPreview {
+    pub version: u64,
+    pub tile: (i32, i32),
+    pub valid: bool,
+    pub skipped: bool,
+    pub effect: Option<PathDelta>,
+    pub shift_held: bool,
+    computed_with_shift: bool,
+}
+
+impl WallPreview {
+    // Cache is keyed on (version, tile). While the sim is running the version bumps every
+    // tick, so only a hover change (or Shift toggle) triggers a recompute then.
+    pub fn refresh(&mut self, rs: &RunState, tx: i32, ty: i32) {
+        let sim_idle = !rs.started || rs.is_paused || rs.game_over;
+        let stale = !self.valid
+            || self.tile != (tx, ty)
+            || self.computed_with_shift != self.shift_held
+            || (sim_idle && self.version != rs.version);
+        if !stale {
+            return;
+        }
+        self.version = rs.version;
+        self.tile = (tx, ty);
+        self.valid = true;
+        self.computed_with_shift = self.shift_held;
+        let gs = rs.grid_size;
+        self.skipped = gs.width * gs.height > WALL_PREVIEW_MAX_TILES && !self.shift_held;
+        self.effect = if self.skipped || tx < 0 || ty < 0 {
+            None
+        } else {
+            wall_placement_effect(rs, tx as u32, ty as u32)
+        };
+    }
+}
@@ -1,4 +1,6 @@
 // Utility helpers extracted from main.rs
+use std::cell::RefCell;
+use std::collections::VecDeque;
 
 pub fn format_time(secs: u64) -> String {
     let h = secs / 3600;
@@ -13,7 +15,288 @@ pub fn format_time(secs: u64) -> String {
     }
 }
 
-pub fn clog(msg: &str) {
-    // Debug logging disabled to reduce console spam
-    let _ = msg; // keep param to avoid warnings
+// -------- Logging --------
+// Every entry lands in an in-memory ring buffer (copyable from the developer panel and the
+// crash overlay); only levels at or above the console threshold are echoed to devtools.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+}
+impl Level {
+    pub fn label(self) -> &'static str {
+        match self {
+            Level::Trace => "TRACE",
+            Level::Debug => "DEBUG",
+            Level::Info => "INFO",
+            Level::Warn => "WARN",
+        }
+    }
+}
+
+pub const LOG_CAPACITY: usize = 500;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogEntry {
+    pub at_ms: f64,
+    pub level: Level,
+    pub msg: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct LogBuffer {
+    entries: VecDeque<LogEntry>,
+    capacity: usize,
+    // Lowest level echoed to the console; None keeps the console quiet
+    pub console_min: Option<Level>,
+}
+impl LogBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+            console_min: None,
+        }
+    }
+    // Records the entry, dropping the oldest when full
+    pub fn push(&mut self, at_ms: f64, level: Level, msg: &str) {
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(LogEntry {
+            at_ms,
+            level,
+            msg: msg.to_string(),
+        });
+    }
+    pub fn echoes(&self, level: Level) -> bool {
+        self.console_min.is_some_and(|min| level >= min)
+    }
+    // Plain-text dump, oldest first, headed with the build version and run
+    pub fn dump(&self, run_id: Option<u64>) -> String {
+        let mut out = format!(
+            "Maze Defence logs\nversion: {}\nrun_id: {}\n\n",
+            env!("CARGO_PKG_VERSION"),
+            run_id.map_or_else(|| "-".to_string(), |id| id.to_string())
+        );
+        for e in &self.entries {
+            out.push_str(&format!(
+                "[{:>10.1}] {:<5} {}\n",
+                e.at_ms / 1000.0,
+                e.level.label(),
+                e.msg
+            ));
+        }
+        out
+    }
+}
+
+thread_local! {
+    static LOGS: RefCell<LogBuffer> = RefCell::new(LogBuffer::new(LOG_CAPACITY));
+}
+
+fn now_ms() -> f64 {
+    #[cfg(target_arch = "wasm32")]
+    {
+        js_sys::Date::now()
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0.0, |d| d.as_secs_f64() * 1000.0)
+    }
+}
+
+// Math.random in the browser. Natively (tests, tools) a splitmix64 stream from a fixed seed per
+// thread, so a test rolls the same numbers every time it runs.
+pub fn random() -> f64 {
+    #[cfg(target_arch = "wasm32")]
+    {
+        js_sys::Math::random()
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        thread_local! {
+            static RNG: RefCell<crate::model::SeededRng> =
+                RefCell::new(crate::model::SeededRng::new(0x5EED));
+        }
+        RNG.with(|r| r.borrow_mut().next_f64())
+    }
+}
+
+pub fn log(level: Level, msg: &str) {
+    let echo = LOGS.with(|l| {
+        // A panic while logging must not turn into a second panic here
+        let Ok(mut buf) = l.try_borrow_mut() else {
+            return false;
+        };
+        buf.push(now_ms(), level, msg);
+        buf.echoes(level)
+    });
+    #[cfg(target_arch = "wasm32")]
+    if echo {
+        let line = wasm_bindgen::JsValue::from_str(&format!("[{}] {}", level.label(), msg));
+        match level {
+            Level::Warn => web_sys::console::warn_1(&line),
+            _ => web_sys::console::log_1(&line),
+        }
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    let _ = echo;
+}
+
+pub fn set_console_level(min: Option<Level>) {
+    LOGS.with(|l| {
+        if let Ok(mut buf) = l.try_borrow_mut() {
+            buf.console_min = min;
+        }
+    });
+}
+
+pub fn recent_logs(run_id: Option<u64>) -> String {
+    LOGS.with(|l| {
+        l.try_borrow()
+            .map(|buf| buf.dump(run_id))
+            .unwrap_or_default()
+    })
+}
+
+// Best-effort copy through navigator.clipboard; ignored where the API is unavailable
+pub fn copy_to_clipboard(text: &str) {
+    use wasm_bindgen::{JsCast, JsValue};
+    let Some(win) = web_sys::window() else {
+        return;
+    };
+    let Ok(clipboard) = js_sys::Reflect::get(&win, &JsValue::from_str("navigator"))
+        .and_then(|nav| js_sys::Reflect::get(&nav, &JsValue::from_str("clipboard")))
+    else {
+        return;
+    };
+    let Ok(write) = js_sys::Reflect::get(&clipboard, &JsValue::from_str("writeText")) else {
+        return;
+    };
+    if let Some(f) = write.dyn_ref::<js_sys::Function>() {
+        let _ = f.call1(&clipboard, &JsValue::from_str(text));
+    }
+}
+
+// Saves `text` as a file through a temporary blob URL and a clicked anchor
+pub fn download_text(filename: &str, text: &str) {
+    use wasm_bindgen::{JsCast, JsValue};
+    let Some(doc) = web_sys::window().and_then(|w| w.document()) else {
+        return;
+    };
+    let parts = js_sys::Array::of1(&JsValue::from_str(text));
+    let Ok(blob) = web_sys::Blob::new_with_str_sequence(&parts) else {
+        return;
+    };
+    let Ok(url) = web_sys::Url::create_object_url_with_blob(&blob) else {
+        return;
+    };
+    if let Some(a) = doc
+        .create_element("a")
+        .ok()
+        .and_then(|el| el.dyn_into::<web_sys::HtmlAnchorElement>().ok())
+    {
+        a.set_href(&url);
+        a.set_download(filename);
+        a.click();
+    }
+    let _ = web_sys::Url::revoke_object_url(&url);
+}
+
+// -------- Base64 --------
+// Standard alphabet with padding, for the pasteable debug and save strings
+const B64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub fn base64_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, b)| n | u32::from(*b) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(B64[((n >> (18 - 6 * i)) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+// None on stray characters or a length that isn't whole quads, as a clipped paste leaves
+pub fn base64_decode(text: &str) -> Option<Vec<u8>> {
+    let text: Vec<u8> = text.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+    if !text.len().is_multiple_of(4) {
+        return None;
+    }
+    let mut out = Vec::with_capacity(text.len() / 4 * 3);
+    for quad in text.chunks(4) {
+        let pad = quad.iter().rev().take_while(|b| **b == b'=').count();
+        if pad > 2 {
+            return None;
+        }
+        let mut n = 0u32;
+        for (i, b) in quad[..4 - pad].iter().enumerate() {
+            let v = B64.iter().position(|c| c == b)? as u32;
+            n |= v << (18 - 6 * i);
+        }
+        out.extend(n.to_be_bytes()[1..4 - pad].iter());
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64_round_trips_every_tail_length() {
+        for text in ["", "a", "ab", "abc", "abcd", "md-debug: {\"x\":1}"] {
+            let enc = base64_encode(text.as_bytes());
+            assert_eq!(enc.len() % 4, 0);
+            assert_eq!(base64_decode(&enc).unwrap(), text.as_bytes());
+        }
+        assert_eq!(base64_encode(b"Man"), "TWFu");
+        assert_eq!(base64_encode(b"Ma"), "TWE=");
+        assert_eq!(base64_decode("TWE"), None);
+        assert_eq!(base64_decode("TW!u"), None);
+    }
+
+    #[test]
+    fn ring_buffer_keeps_the_newest_entries() {
+        let mut buf = LogBuffer::new(3);
+        for i in 0..5 {
+            buf.push(i as f64, Level::Info, &format!("m{i}"));
+        }
+        let msgs: Vec<_> = buf.entries.iter().map(|e| e.msg.as_str()).collect();
+        assert_eq!(msgs, ["m2", "m3", "m4"]);
+        let dump = buf.dump(Some(7));
+        assert!(dump.contains("run_id: 7"));
+        assert!(dump.find("m2").unwrap() < dump.find("m4").unwrap());
+        assert!(!dump.contains("m1"));
+    }
+
+    const LEVELS: [Level; 4] = [Level::Trace, Level::Debug, Level::Info, Level::Warn];
+
+    #[test]
+    fn console_echo_respects_level_threshold() {
+        let mut buf = LogBuffer::new(LOG_CAPACITY);
+        assert!(LEVELS.iter().all(|&l| !buf.echoes(l)));
+        buf.console_min = Some(Level::Info);
+        assert!(!buf.echoes(Level::Trace) && !buf.echoes(Level::Debug));
+        assert!(buf.echoes(Level::Info) && buf.echoes(Level::Warn));
+        buf.console_min = Some(Level::Trace);
+        assert!(LEVELS.iter().all(|&l| buf.echoes(l)));
+        // Filtering only affects the console: the buffer keeps everything
+        buf.console_min = None;
+        buf.push(0.0, Level::Trace, "quiet");
+        assert_eq!(buf.entries.len(), 1);
+    }
 }
@@ -0,0 +1,95 @@
+// Worker side of the analysis: the last synced board and the answers computed against it.
+// Built into the worker bundle (src/bin/analysis_worker.rs); the app only compiles it for tests.
+use super::protocol::{AnalysisKind, AnalysisResult, FromWorker, ToWorker};
+use crate::model::{loop_tiles_in_range, wall_placement_effect, RunState, Tile, TileKind};
+
+pub fn decode_tiles(runs: &[(Tile, u32)]) -> Vec<Tile> {
+    runs.iter()
+        .flat_map(|(t, n)| std::iter::repeat_n(t.clone(), *n as usize))
+        .collect()
+}
+
+// Loop tiles within range of every tile's centre
+pub fn coverage_map(rs: &RunState, range: f64) -> Vec<u32> {
+    let gs = rs.grid_size;
+    let mut out = Vec::with_capacity((gs.width * gs.height) as usize);
+    for y in 0..gs.height {
+        for x in 0..gs.width {
+            out.push(loop_tiles_in_range(rs, x, y, range));
+        }
+    }
+    out
+}
+
+pub fn analyze(rs: &RunState, towers: &[(u32, u32)], kind: &AnalysisKind) -> AnalysisResult {
+    match *kind {
+        AnalysisKind::SuggestSpots { range, count } => {
+            let w = rs.grid_size.width;
+            let mut spots: Vec<(u32, u32, u32)> = coverage_map(rs, range)
+                .into_iter()
+                .enumerate()
+                .map(|(i, c)| (i as u32 % w, i as u32 / w, c))
+                .filter(|&(x, y, c)| {
+                    c > 0
+                        && matches!(
+                            rs.tiles[(y * w + x) as usize].kind,
+                            TileKind::Rock { .. } | TileKind::Wall
+                        )
+                        && !towers.contains(&(x, y))
+                })
+                .collect();
+            // Ties go to the top-left so repeated requests agree
+            spots.sort_by(|a, b| b.2.cmp(&a.2).then((a.1, a.0).cmp(&(b.1, b.0))));
+            spots.truncate(count);
+            AnalysisResult::Spots(spots)
+        }
+        AnalysisKind::WallDiff { x, y } => AnalysisResult::WallDiff(
+            wall_placement_effect(rs, x, y).map(|d| (d.old_length, d.new_length)),
+        ),
+        AnalysisKind::Coverage { range } => AnalysisResult::Coverage(coverage_map(rs, range)),
+    }
+}
+
+// The last synced board and the version it was posted with
+#[derive(Default)]
+pub struct AnalysisHost {
+    board: Option<HostBoard>,
+}
+
+struct HostBoard {
+    version: u64,
+    rs: RunState,
+    towers: Vec<(u32, u32)>,
+}
+
+impl AnalysisHost {
+    pub fn handle(&mut self, msg: ToWorker) -> Option<FromWorker> {
+        match msg {
+            ToWorker::Sync {
+                version,
+                grid_size,
+                tiles,
+                towers,
+            } => {
+                let tiles = decode_tiles(&tiles);
+                self.board =
+                    (tiles.len() == (grid_size.width * grid_size.height) as usize).then(|| {
+                        HostBoard {
+                            version,
+                            rs: RunState::from_tiles(grid_size, tiles),
+                            towers,
+                        }
+                    });
+                None
+            }
+            ToWorker::Analyze { id, kind } => {
+                let board = self.board.as_ref()?;
+                Some(FromWorker::Done {
+                    id,
+                    version: board.version,
+                    result: analyze(&board.rs, &board.towers, &kind),
+                })
+            }
+        }
+    }
+}
@@ -0,0 +1,388 @@
+// Off-main-thread map analysis (tower spot suggestions, hypothetical walls, coverage maps).
+// The UI posts the terrain as run-length encoded tiles whenever it changes; the worker keeps a
+// read-only board rebuilt from them and answers requests tagged with the version it used.
+// Answers for a board the UI has since replaced are dropped. Messages travel as JSON strings.
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+use wasm_bindgen::JsValue;
+use web_sys::{MessageEvent, Worker};
+
+use crate::model::{RunState, Shared, Tile};
+
+#[cfg(test)]
+mod host;
+pub mod protocol;
+
+use protocol::{from_json, to_json, FromWorker, TileRuns, ToWorker};
+pub use protocol::{AnalysisKind, AnalysisResult};
+
+// Trunk emits the worker bundle (src/bin/analysis_worker.rs) next to the app
+pub const WORKER_SCRIPT: &str = "./analysis_worker.js";
+
+pub fn encode_tiles(tiles: &[Tile]) -> TileRuns {
+    let mut runs: TileRuns = Vec::new();
+    for t in tiles {
+        match runs.last_mut() {
+            Some((last, n)) if last == t => *n += 1,
+            _ => runs.push((t.clone(), 1)),
+        }
+    }
+    runs
+}
+
+// What the worker was last sent
+#[derive(Debug)]
+struct ClientBoard {
+    version: u64,
+    tiles: Shared<Vec<Tile>>,
+    towers: Vec<(u32, u32)>,
+}
+
+// UI side: decides when to resync and which answers are still worth showing
+#[derive(Default, Debug)]
+pub struct AnalysisClient {
+    ready: bool,
+    // RunState version last compared, so the tile diff runs once per reducer step
+    checked: Option<u64>,
+    synced: Option<ClientBoard>,
+    next_id: u32,
+    // Only the newest request is answered; older ones were superseded
+    pending: Option<u32>,
+}
+
+impl AnalysisClient {
+    pub fn on_ready(&mut self) {
+        self.ready = true;
+        self.checked = None;
+        self.synced = None;
+    }
+
+    // A Sync message when the terrain or towers differ from what the worker holds
+    pub fn sync(&mut self, rs: &RunState) -> Option<ToWorker> {
+        if !self.ready || self.checked == Some(rs.version) {
+            return None;
+        }
+        self.checked = Some(rs.version);
+        let towers: Vec<(u32, u32)> = rs.towers.iter().map(|t| (t.x, t.y)).collect();
+        let unchanged = self
+            .synced
+            .as_ref()
+            .is_some_and(|b| b.tiles == rs.tiles && b.towers == towers);
+        if unchanged {
+            return None;
+        }
+        self.synced = Some(ClientBoard {
+            version: rs.version,
+            tiles: rs.tiles.clone(),
+            towers: towers.clone(),
+        });
+        Some(ToWorker::Sync {
+            version: rs.version,
+            grid_size: rs.grid_size,
+            tiles: encode_tiles(&rs.tiles),
+            towers,
+        })
+    }
+
+    pub fn request(&mut self, kind: AnalysisKind) -> Option<ToWorker> {
+        self.synced.as_ref()?;
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        self.pending = Some(id);
+        Some(ToWorker::Analyze { id, kind })
+    }
+
+    // Results for the current board and newest request; anything else is stale
+    pub fn accept(&mut self, msg: FromWorker) -> Option<AnalysisResult> {
+        match msg {
+            FromWorker::Ready => {
+                self.on_ready();
+                None
+            }
+            FromWorker::Done {
+                id,
+                version,
+                result,
+            } => {
+                let synced = self.synced.as_ref().map(|b| b.version);
+                if self.pending != Some(id) || synced != Some(version) {
+                    return None;
+                }
+                self.pending = None;
+                Some(result)
+            }
+        }
+    }
+}
+
+// Main-thread handle; the worker is terminated when this is dropped
+pub struct AnalysisWorker {
+    worker: Worker,
+    _onmessage: Closure<dyn FnMut(MessageEvent)>,
+}
+
+impl AnalysisWorker {
+    pub fn spawn(on_message: impl Fn(FromWorker) + 'static) -> Option<Self> {
+        let worker = Worker::new(WORKER_SCRIPT).ok()?;
+        let onmessage = Closure::wrap(Box::new(move |e: MessageEvent| {
+            if let Some(msg) = e.data().as_string().and_then(|raw| from_json(&raw)) {
+                on_message(msg);
+            }
+        }) as Box<dyn FnMut(MessageEvent)>);
+        worker.set_onmessage(Some(onmessage.as_ref().unchecked_ref()));
+        Some(Self {
+            worker,
+            _onmessage: onmessage,
+        })
+    }
+
+    pub fn post(&self, msg: &ToWorker) {
+        let _ = self.worker.post_message(&JsValue::from_str(&to_json(msg)));
+    }
+}
+
+impl Drop for AnalysisWorker {
+    fn drop(&mut self) {
+        self.worker.terminate();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::host::{coverage_map, decode_tiles, AnalysisHost};
+    use super::*;
+    use crate::model::{default_hardness, ArrowDir, DirRole, GridSize, TileKind};
+
+    // Short route over the top (8 steps), long route underneath (10 steps)
+    pub(super) fn board() -> RunState {
+        let rows = [
+            "#########",
+            "#########",
+            "##.....##",
+            "##.#I#.##",
+            "##.XSE.##",
+            "##.#I#.##",
+            "##.###.##",
+            "##.....##",
+            "#########",
+        ];
+        let tiles = rows
+            .iter()
+            .flat_map(|r| r.chars())
+            .map(|c| {
+                let kind = match c {
+                    '.' => TileKind::Empty,
+                    'I' => TileKind::Indestructible,
+                    'S' => TileKind::Start,
+                    'E' => TileKind::Direction {
+                        dir: ArrowDir::Right,
+                        role: DirRole::Entrance,
+                    },
+                    'X' => TileKind::Direction {
+                        dir: ArrowDir::Right,
+                        role: DirRole::Exit,
+                    },
+                    _ => TileKind::Rock {
+                        has_gold: false,
+                        boost: None,
+                    },
+                };
+                Tile {
+                    hardness: default_hardness(&kind),
+                    kind,
+                    magma: false,
+                }
+            })
+            .collect();
+        RunState::from_tiles(
+            GridSize {
+                width: 9,
+                height: 9,
+            },
+            tiles,
+        )
+    }
+
+    #[test]
+    fn tiles_survive_run_length_encoding() {
+        let rs = board();
+        let runs = encode_tiles(&rs.tiles);
+        assert!(runs.len() < rs.tiles.len() / 2);
+        assert_eq!(runs.iter().map(|(_, n)| n).sum::<u32>(), 81);
+        assert_eq!(decode_tiles(&runs), *rs.tiles);
+        assert!(encode_tiles(&[]).is_empty());
+    }
+
+    #[test]
+    fn messages_round_trip_as_json() {
+        let rs = board();
+        let sync = ToWorker::Sync {
+            version: 9,
+            grid_size: rs.grid_size,
+            tiles: encode_tiles(&rs.tiles),
+            towers: vec![(0, 0)],
+        };
+        let ask = ToWorker::Analyze {
+            id: 4,
+            kind: AnalysisKind::WallDiff { x: 1, y: 1 },
+        };
+        let done = FromWorker::Done {
+            id: 4,
+            version: 9,
+            result: AnalysisResult::Spots(vec![(1, 0, 6)]),
+        };
+        assert_eq!(from_json::<ToWorker>(&to_json(&sync)), Some(sync));
+        assert_eq!(from_json::<ToWorker>(&to_json(&ask)), Some(ask));
+        assert_eq!(from_json::<FromWorker>(&to_json(&done)), Some(done));
+        assert_eq!(from_json::<FromWorker>("{\"Nope\":1}"), None);
+    }
+
+    #[test]
+    fn host_answers_against_the_synced_board() {
+        let rs = board();
+        let mut host = AnalysisHost::default();
+        let ask = |id, kind| ToWorker::Analyze { id, kind };
+        // Nothing to analyse before the first sync
+        let spots = AnalysisKind::SuggestSpots {
+            range: 1.5,
+            count: 2,
+        };
+        assert_eq!(host.handle(ask(0, spots.clone())), None);
+        let sync = ToWorker::Sync {
+            version: 3,
+            grid_size: rs.grid_size,
+            tiles: encode_tiles(&rs.tiles),
+            towers: vec![(2, 0)],
+        };
+        assert_eq!(host.handle(sync), None);
+
+        let Some(FromWorker::Done {
+            id: 1,
+            version: 3,
+            result: AnalysisResult::Spots(spots),
+        }) = host.handle(ask(1, spots))
+        else {
+            panic!("expected spots");
+        };
+        assert_eq!(spots.len(), 2);
+        assert!(spots.iter().all(|&(x, y, _)| (x, y) != (2, 0)));
+        assert!(spots[0].2 >= spots[1].2);
+
+        let Some(FromWorker::Done {
+            result: AnalysisResult::Coverage(cov),
+            ..
+        }) = host.handle(ask(2, AnalysisKind::Coverage { range: 1.0 }))
+        else {
+            panic!("expected coverage");
+        };
+        assert_eq!(cov, coverage_map(&rs, 1.0));
+
+        // Closing the top route sends the loop underneath; closing both is refused
+        let wall = |host: &mut AnalysisHost, x, y| match host
+            .handle(ask(3, AnalysisKind::WallDiff { x, y }))
+        {
+            Some(FromWorker::Done {
+                result: AnalysisResult::WallDiff(diff),
+                ..
+            }) => diff,
+            other => panic!("expected wall diff, got {other:?}"),
+        };
+        let (old, new) = wall(&mut host, 4, 2).expect("bottom route still open");
+        assert!((new - old - 2.0).abs() < 1e-9);
+        assert_eq!(wall(&mut host, 6, 4), None);
+    }
+
+    #[test]
+    fn client_resyncs_only_on_board_changes() {
+        let mut rs = board();
+        let mut client = AnalysisClient::default();
+        // Posts before Ready would be lost
+        assert_eq!(client.sync(&rs), None);
+        client.accept(FromWorker::Ready);
+        assert!(matches!(client.sync(&rs), Some(ToWorker::Sync { .. })));
+        // Sim ticks bump the version without touching the terrain
+        rs.version += 1;
+        assert_eq!(client.sync(&rs), None);
+        rs.version += 1;
+        rs.tiles[8].kind = TileKind::Wall;
+        assert!(matches!(
+            client.sync(&rs),
+            Some(ToWorker::Sync { version, .. }) if version == rs.version
+        ));
+    }
+
+    #[test]
+    fn stale_and_superseded_results_are_dropped() {
+        let mut rs = board();
+        let mut client = AnalysisClient::default();
+        let kind = AnalysisKind::Coverage { range: 1.0 };
+        assert_eq!(client.request(kind.clone()), None);
+        client.accept(FromWorker::Ready);
+        client.sync(&rs);
+        let done = |id, version| FromWorker::Done {
+            id,
+            version,
+            result: AnalysisResult::Coverage(vec![]),
+        };
+
+        let Some(ToWorker::Analyze { id: first, .. }) = client.request(kind.clone()) else {
+            panic!("expected a request");
+        };
+        let Some(ToWorker::Analyze { id: second, .. }) = client.request(kind.clone()) else {
+            panic!("expected a request");
+        };
+        assert_eq!(client.accept(done(first, rs.version)), None);
+        assert!(client.accept(done(second, rs.version)).is_some());
+        // Answered once
+        assert_eq!(client.accept(done(second, rs.version)), None);
+
+        // The board moved on while the worker was busy
+        let Some(ToWorker::Analyze { id, .. }) = client.request(kind) else {
+            panic!("expected a request");
+        };
+        let asked_at = rs.version;
+        rs.version += 1;
+        rs.tiles[8].kind = TileKind::Wall;
+        client.sync(&rs);
+        assert_eq!(client.accept(done(id, asked_at)), None);
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod wasm_tests {
+    use super::host::AnalysisHost;
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    // Same path as a real worker message: JSON through a JsValue in both directions
+    #[wasm_bindgen_test]
+    fn request_round_trips_through_js_values() {
+        let rs = tests::board();
+        let mut client = AnalysisClient::default();
+        let mut host = AnalysisHost::default();
+        let to_js = |s: String| JsValue::from_str(&s);
+
+        client.accept(FromWorker::Ready);
+        let mut outbox = vec![client.sync(&rs).unwrap()];
+        outbox.push(
+            client
+                .request(AnalysisKind::SuggestSpots {
+                    range: 1.5,
+                    count: 3,
+                })
+                .unwrap(),
+        );
+        let mut answer = None;
+        for msg in outbox {
+            let raw = to_js(to_json(&msg)).as_string().unwrap();
+            if let Some(out) = host.handle(from_json(&raw).unwrap()) {
+                let raw = to_js(to_json(&out)).as_string().unwrap();
+                answer = client.accept(from_json(&raw).unwrap());
+            }
+        }
+        let Some(AnalysisResult::Spots(spots)) = answer else {
+            panic!("expected spots");
+        };
+        assert_eq!(spots.len(), 3);
+    }
+}
@@ -0,0 +1,61 @@
+// Messages between the UI and the analysis worker, shared by both builds. They travel as JSON
+// strings; the worker side lives in host.rs and src/bin/analysis_worker.rs.
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::model::{GridSize, Tile};
+
+// Row-major (tile, repeat) runs; maps are mostly plain rock so this is a fraction of the grid
+pub type TileRuns = Vec<(Tile, u32)>;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum AnalysisKind {
+    // Best tower tiles for this range, ranked by loop tiles covered
+    SuggestSpots { range: f64, count: usize },
+    // Loop length before/after walling off (x, y)
+    WallDiff { x: u32, y: u32 },
+    // Loop tiles in range of every tile, row-major
+    Coverage { range: f64 },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum AnalysisResult {
+    // (x, y, loop tiles covered), best first
+    Spots(Vec<(u32, u32, u32)>),
+    // (old length, new length); None when the wall is illegal or would block the path
+    WallDiff(Option<(f64, f64)>),
+    Coverage(Vec<u32>),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ToWorker {
+    Sync {
+        version: u64,
+        grid_size: GridSize,
+        tiles: TileRuns,
+        towers: Vec<(u32, u32)>,
+    },
+    Analyze {
+        id: u32,
+        kind: AnalysisKind,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum FromWorker {
+    // Posted once the worker's message handler is installed; earlier posts would be lost
+    Ready,
+    Done {
+        id: u32,
+        version: u64,
+        result: AnalysisResult,
+    },
+}
+
+pub fn to_json<T: Serialize>(msg: &T) -> String {
+    serde_json::to_string(msg).unwrap_or_default()
+}
+
+pub fn from_json<T: DeserializeOwned>(raw: &str) -> Option<T> {
+    serde_json::from_str(raw).ok()
+}